@@ -48,4 +48,88 @@ describe("agent JSON-RPC commands", () => {
     const parsed = JSON.parse(response);
     expect(parsed.result.status).toBe("stopped");
   });
+
+  it("responds to agent:hello with protocol version", async () => {
+    const { createAgentServer, PROTOCOL_VERSION } = await import("../index.js");
+    const server = createAgentServer();
+
+    const response = await server.handleRequest(JSON.stringify({
+      jsonrpc: "2.0", id: 4, method: "agent:hello",
+      params: { protocolVersion: PROTOCOL_VERSION, appVersion: "0.1.0" },
+    }));
+
+    const parsed = JSON.parse(response);
+    expect(parsed.result.protocolVersion).toBe(PROTOCOL_VERSION);
+    expect(parsed.result.methods).toContain("agent:start");
+  });
+
+  it("ignores source edits while idle", async () => {
+    const { createAgentServer } = await import("../index.js");
+    const server = createAgentServer();
+
+    const response = await server.handleRequest(JSON.stringify({
+      jsonrpc: "2.0", id: 5, method: "sources:remove", params: { id: "yahoo-spy" },
+    }));
+
+    const parsed = JSON.parse(response);
+    expect(parsed.result.status).toBe("idle");
+  });
+
+  it("resolves $env: secret references from the environment", async () => {
+    const { resolveSecret } = await import("../index.js");
+    process.env.FINWATCH_TEST_SECRET = "from-env";
+    expect(resolveSecret("$env:FINWATCH_TEST_SECRET")).toBe("from-env");
+    expect(resolveSecret("inline")).toBe("inline");
+    expect(resolveSecret("$env:FINWATCH_MISSING")).toBe("");
+    expect(resolveSecret(undefined)).toBe("");
+    delete process.env.FINWATCH_TEST_SECRET;
+  });
+
+  it("builds keyed providers in the routed order", async () => {
+    const { buildProviders } = await import("../index.js");
+    expect(buildProviders(undefined, "sk-ant", "sk-or").map((p) => p.id)).toEqual(["anthropic", "openrouter"]);
+    expect(buildProviders(["openrouter", "anthropic"], "sk-ant", "sk-or").map((p) => p.id)).toEqual([
+      "openrouter",
+      "anthropic",
+    ]);
+    expect(buildProviders(["anthropic", "openrouter"], "", "sk-or").map((p) => p.id)).toEqual(["openrouter"]);
+  });
+
+  it("refuses to trade while the kill switch is engaged", async () => {
+    const { createAgentServer } = await import("../index.js");
+    const { TradingGate } = await import("../trading/trading-gate.js");
+    const { PaperExecutor } = await import("../trading/paper-executor.js");
+    const gate = new TradingGate();
+    const server = createAgentServer({ tradingGate: gate });
+    const fetchMock = vi.fn().mockResolvedValue({ ok: true, status: 200, json: async () => ({}) } as Response);
+    const originalFetch = globalThis.fetch;
+    globalThis.fetch = fetchMock;
+    const executor = new PaperExecutor({ keyId: "K", secretKey: "S", baseUrl: "https://paper-api.alpaca.markets" }, gate);
+    const action = {
+      symbol: "AAPL",
+      side: "buy" as const,
+      qty: 1,
+      type: "market" as const,
+      rationale: "test",
+      confidence: 0.9,
+      anomalyId: "a-1",
+    };
+
+    try {
+      // The host forwards the kill switch as notifications
+      await server.handleMessage(JSON.stringify({
+        jsonrpc: "2.0", method: "trading:halt", params: { reason: "Daily loss limit" },
+      }));
+      expect(gate.killed).toBe(true);
+      await expect(executor.execute(action)).rejects.toThrow("Trading is halted: Daily loss limit");
+      expect(fetchMock).not.toHaveBeenCalled();
+
+      await server.handleMessage(JSON.stringify({ jsonrpc: "2.0", method: "trading:resume", params: {} }));
+      expect(gate.killed).toBe(false);
+      await executor.execute(action);
+      expect(fetchMock).toHaveBeenCalledTimes(1);
+    } finally {
+      globalThis.fetch = originalFetch;
+    }
+  });
 });
@@ -142,6 +142,35 @@ describe("Orchestrator", () => {
     expect(callsAfterStop).toBe(callsAfterStart);
   });
 
+  it("skips quarantined sources until the quarantine ends", async () => {
+    const source = mockSource("mock-src", []);
+    const orch = new Orchestrator(orchConfig());
+    orch.sources.register(source);
+    await orch.start();
+
+    orch.quarantine("mock-src", Date.now() + 5000);
+    await vi.advanceTimersByTimeAsync(3100);
+    expect(source.fetch).not.toHaveBeenCalled();
+
+    await vi.advanceTimersByTimeAsync(3000);
+    expect(source.fetch).toHaveBeenCalled();
+
+    await orch.stop();
+  });
+
+  it("emits health-change when a source's health is first checked", async () => {
+    const orch = new Orchestrator(orchConfig());
+    orch.sources.register(mockSource("mock-src"));
+    const changes: SourceHealth[] = [];
+    orch.on("health-change", (h) => changes.push(h));
+
+    await orch.start();
+    await vi.advanceTimersByTimeAsync(30_100);
+    expect(changes.map((h) => h.sourceId)).toContain("mock-src");
+
+    await orch.stop();
+  });
+
   it("creates MemoryManager when memory config is provided", () => {
     const db = createMemoryDb(":memory:");
     const tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), "fw-orch-mem-"));
@@ -211,4 +240,15 @@ describe("Orchestrator", () => {
 
     await orch.stop();
   });
+
+  it("applyConfig() reports the settings it applied", () => {
+    const orch = new Orchestrator(orchConfig());
+    const applied = orch.applyConfig({
+      model: "other-model",
+      monitor: { analysisIntervalMs: 30000 },
+      feed: "sip",
+    });
+    expect(applied).toEqual(["model", "monitor.analysisIntervalMs"]);
+    expect(orch.applyConfig({})).toEqual([]);
+  });
 });
@@ -157,4 +157,17 @@ describe("buildAnalysisPrompt", () => {
     const result = buildAnalysisPrompt([makeScoredTick()], makeContext());
     expect(result.responseFormat).toEqual({ type: "json_object" });
   });
+
+  it("uses a stored template in place of the built-in prompt", () => {
+    const result = buildAnalysisPrompt(
+      [makeScoredTick()],
+      makeContext({
+        template: "Flag only crypto anomalies. Cycle {{cycleId}} of {{sessionId}}.",
+        patterns: [{ id: "p1", pattern: "Friday volume fade", confidence: 0.8, source: "test", createdAt: 0, updatedAt: 0 }],
+      }),
+    );
+    expect(result.system).toMatch(/^Flag only crypto anomalies\. Cycle cycle-456 of session-123\./);
+    expect(result.system).not.toContain("financial anomaly detection agent");
+    expect(result.system).toContain("Friday volume fade");
+  });
 });
@@ -27,6 +27,8 @@ export type CycleRunnerDeps = {
   thresholds: DomainThreshold[];
   toolRegistry?: ToolRegistry;
   memoryContext?: (tickSummary: string) => string;
+  /** Stored `analysis` prompt template, when one replaces the built-in one. */
+  analysisPrompt?: string;
 };
 
 export type CycleResult = {
@@ -79,6 +81,7 @@ export class CycleRunner {
       cycleId: this._state.cycleId,
       patterns: this.deps.patterns,
       thresholds: this.deps.thresholds,
+      template: this.deps.analysisPrompt,
     });
 
     // Inject memory context if available
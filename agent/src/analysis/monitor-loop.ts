@@ -20,11 +20,13 @@ export type MonitorLoopDeps = {
   patterns: DomainPattern[];
   thresholds: DomainThreshold[];
   memoryContext?: (tickSummary: string) => string;
+  /** Stored `analysis` prompt template, when one replaces the built-in one. */
+  analysisPrompt?: string;
 };
 
 export class MonitorLoop {
   private readonly buffer: DataBuffer;
-  private readonly deps: MonitorLoopDeps;
+  private deps: MonitorLoopDeps;
 
   private _state: AgentState = "idle";
   private _totalCycles = 0;
@@ -44,6 +46,11 @@ export class MonitorLoop {
     this.deps = deps;
   }
 
+  /** Replace some dependencies; the next cycle picks them up. */
+  updateDeps(patch: Partial<MonitorLoopDeps>): void {
+    this.deps = { ...this.deps, ...patch };
+  }
+
   get status(): AgentStatus {
     return {
       state: this._state,
@@ -116,6 +123,7 @@ export class MonitorLoop {
       patterns: this.deps.patterns,
       thresholds: this.deps.thresholds,
       memoryContext: this.deps.memoryContext,
+      analysisPrompt: this.deps.analysisPrompt,
     });
 
     this._currentCycleId = runner.state.cycleId;
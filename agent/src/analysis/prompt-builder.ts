@@ -11,6 +11,8 @@ export type AnalysisContext = {
   cycleId: string;
   patterns: DomainPattern[];
   thresholds: DomainThreshold[];
+  /** Stored `analysis` prompt replacing DEFAULT_ANALYSIS_PROMPT. */
+  template?: string;
 };
 
 export type AnalysisPrompt = {
@@ -19,28 +21,38 @@ export type AnalysisPrompt = {
   responseFormat: ResponseFormat;
 };
 
+/** Built-in `analysis` prompt; `{{sessionId}}` and `{{cycleId}}` are filled in per cycle. */
+export const DEFAULT_ANALYSIS_PROMPT = [
+  `You are a financial anomaly detection agent. Analyze the provided market data ticks and identify any anomalies.`,
+  ``,
+  `Session: {{sessionId}} | Cycle: {{cycleId}}`,
+  ``,
+  `For each anomaly you detect, output a JSON array of objects with these fields:`,
+  `- severity: "low" | "medium" | "high" | "critical"`,
+  `- source: the data source ID`,
+  `- symbol: the ticker symbol (if applicable)`,
+  `- description: a brief explanation of the anomaly`,
+  `- metrics: an object of relevant metric values`,
+  `- preScreenScore: a confidence score from 0.0 to 1.0 indicating how confident you are this is a real anomaly`,
+  ``,
+  `Focus on unusual price movements, volume spikes, and significant deviations from recent history.`,
+  `A z-score above 2.0 or below -2.0 indicates a statistically significant deviation.`,
+  `Look for: large daily moves (>3%), volume >2x average, gap opens, trend reversals.`,
+  ``,
+  `If no anomalies are detected, output an empty JSON array: []`,
+  ``,
+  `IMPORTANT: Your response must contain a valid JSON array. Wrap it in \`\`\`json code fences.`,
+].join("\n");
+
+/** Fill a prompt template's `{{sessionId}}` and `{{cycleId}}` placeholders. */
+export function renderPromptTemplate(template: string, ctx: AnalysisContext): string {
+  return template
+    .replaceAll("{{sessionId}}", ctx.sessionId)
+    .replaceAll("{{cycleId}}", ctx.cycleId);
+}
+
 function buildSystemPrompt(ctx: AnalysisContext): string {
-  const parts: string[] = [
-    `You are a financial anomaly detection agent. Analyze the provided market data ticks and identify any anomalies.`,
-    ``,
-    `Session: ${ctx.sessionId} | Cycle: ${ctx.cycleId}`,
-    ``,
-    `For each anomaly you detect, output a JSON array of objects with these fields:`,
-    `- severity: "low" | "medium" | "high" | "critical"`,
-    `- source: the data source ID`,
-    `- symbol: the ticker symbol (if applicable)`,
-    `- description: a brief explanation of the anomaly`,
-    `- metrics: an object of relevant metric values`,
-    `- preScreenScore: a confidence score from 0.0 to 1.0 indicating how confident you are this is a real anomaly`,
-    ``,
-    `Focus on unusual price movements, volume spikes, and significant deviations from recent history.`,
-    `A z-score above 2.0 or below -2.0 indicates a statistically significant deviation.`,
-    `Look for: large daily moves (>3%), volume >2x average, gap opens, trend reversals.`,
-    ``,
-    `If no anomalies are detected, output an empty JSON array: []`,
-    ``,
-    `IMPORTANT: Your response must contain a valid JSON array. Wrap it in \`\`\`json code fences.`,
-  ];
+  const parts: string[] = [renderPromptTemplate(ctx.template ?? DEFAULT_ANALYSIS_PROMPT, ctx)];
 
   if (ctx.patterns.length > 0) {
     parts.push(``);
@@ -1,13 +1,23 @@
 import "dotenv/config";
-import type { LLMProvider, SourceConfig, BacktestConfig } from "@finwatch/shared";
+import type {
+  LLMProvider,
+  MemoryEntry,
+  SourceConfig,
+  BacktestConfig,
+} from "@finwatch/shared";
 import WebSocket from "ws";
 import { JsonRpcServer } from "./ipc/json-rpc-server.js";
-import { AlpacaStreamSource, type WsLike } from "./ingestion/alpaca-stream-source.js";
+import { encodeFrame, FrameDecoder, negotiateFraming, type Framing } from "./ipc/framing.js";
+import { AlpacaStreamSource, isCryptoSymbol, type WsLike } from "./ingestion/alpaca-stream-source.js";
 import { AlpacaBackfill } from "./ingestion/alpaca-backfill.js";
+import { createConfiguredSource } from "./ingestion/configured-source.js";
 import { BacktestEngine } from "./backtesting/backtest-engine.js";
 import { CycleRunner } from "./analysis/cycle-runner.js";
 import { withFallback } from "./providers/fallback.js";
-import { Orchestrator } from "./orchestrator.js";
+import { withUsageReporting, type LlmUsage } from "./providers/usage.js";
+import { Orchestrator, type ConfigChanges } from "./orchestrator.js";
+import type { MemoryMergeStrategy, MemorySearchFilter } from "./memory/types.js";
+import { TradingGate } from "./trading/trading-gate.js";
 import { AnthropicProvider } from "./providers/anthropic-provider.js";
 import { OpenRouterProvider } from "./providers/openrouter-provider.js";
 import { createLogger } from "./utils/logger.js";
@@ -16,12 +26,22 @@ const log = createLogger("agent-main");
 
 export { JsonRpcServer } from "./ipc/json-rpc-server.js";
 
+/** Stdio protocol version; must match PROTOCOL_VERSION in src-tauri/src/jsonrpc.rs. */
+export const PROTOCOL_VERSION = 1;
+const AGENT_VERSION = "0.0.1";
+
+/** Framing used for stdout writes; switched after the hello response is sent. */
+let outputFraming: Framing = "newline";
+let negotiatedFraming: Framing | null = null;
+
 type AgentStartParams = {
   alpaca: {
     keyId: string;
     secretKey: string;
     symbols: string[];
     feed: "iex" | "sip";
+    /** Trading mode whose credentials these are; defaults to paper. */
+    mode?: "paper" | "live";
   };
   llm: {
     anthropicApiKey?: string;
@@ -29,24 +49,86 @@ type AgentStartParams = {
     model: string;
     maxTokens: number;
     temperature: number;
+    /** Provider ids in the order the app routed them; anthropic first when absent. */
+    providers?: string[];
+  };
+  /** Name of the active config profile, if one was activated. */
+  profile?: string | null;
+  /** Data sources configured in the app, besides the Alpaca streams. */
+  sources?: SourceConfig[];
+  /** Active versions of the prompt templates stored in the app, by name. */
+  prompts?: Record<string, { version: number; body: string }>;
+  /** Kill switch state and hard caps held by the app. */
+  trading?: {
+    halted: boolean;
+    reason?: string | null;
+    guardrails?: { dailyLossLimit: number; maxOrderValue: number };
   };
 };
 
 type BacktestRunParams = {
   config: BacktestConfig;
-  alpaca: { keyId: string; secretKey: string };
+  alpaca: { keyId: string; secretKey: string; mode?: "paper" | "live" };
   llm: {
     anthropicApiKey?: string;
     openrouterApiKey?: string;
     model: string;
     maxTokens: number;
     temperature: number;
+    /** Provider ids in the order the app routed them; anthropic first when absent. */
+    providers?: string[];
   };
+  profile?: string | null;
 };
 
-export function createAgentServer(): JsonRpcServer {
+/** LLM providers that have keys, in `order`. */
+export function buildProviders(
+  order: string[] | undefined,
+  anthropicKey: string,
+  openrouterKey: string,
+): LLMProvider[] {
+  const create: Record<string, () => LLMProvider | null> = {
+    anthropic: () => (anthropicKey ? new AnthropicProvider({ apiKey: anthropicKey }) : null),
+    openrouter: () => (openrouterKey ? new OpenRouterProvider({ apiKey: openrouterKey }) : null),
+  };
+  return (order ?? ["anthropic", "openrouter"]).flatMap((id) => create[id]?.() ?? []);
+}
+
+/** Resolve a secret param that may be an `$env:NAME` reference to a spawn-time env var. */
+export function resolveSecret(value: string | undefined): string {
+  if (value?.startsWith("$env:")) {
+    return process.env[value.slice("$env:".length)] ?? "";
+  }
+  return value ?? "";
+}
+
+function createAlpacaStream(
+  stream: { id: string; name: string; feed: string; symbols: string[] },
+  alpaca: AgentStartParams["alpaca"],
+): AlpacaStreamSource {
+  const config: SourceConfig = {
+    id: stream.id,
+    name: stream.name,
+    type: "streaming",
+    plugin: "alpaca",
+    config: {
+      feed: stream.feed,
+      symbols: stream.symbols,
+      channels: ["trades", "quotes", "bars"],
+      keyId: alpaca.keyId,
+      secretKey: alpaca.secretKey,
+    },
+    enabled: true,
+  };
+  return new AlpacaStreamSource(config, (url: string) => new WebSocket(url) as unknown as WsLike);
+}
+
+/** The kill switch defaults to a gate owned by the server. */
+export function createAgentServer(deps: { tradingGate?: TradingGate } = {}): JsonRpcServer {
   const server = new JsonRpcServer();
+  const tradingGate = deps.tradingGate ?? new TradingGate();
   let orchestrator: Orchestrator | null = null;
+  let alpacaParams: AgentStartParams["alpaca"] | null = null;
   const runningBacktests = new Map<string, BacktestEngine>();
 
   server.register("ping", async () => ({
@@ -54,60 +136,104 @@ export function createAgentServer(): JsonRpcServer {
     timestamp: Date.now(),
   }));
 
+  server.register("agent:hello", async (params) => {
+    const p = params as { protocolVersion?: number; appVersion?: string; framings?: unknown };
+    log.info("Handshake", { hostProtocol: p.protocolVersion ?? null, appVersion: p.appVersion ?? null });
+    const framing = negotiateFraming(p.framings);
+    negotiatedFraming = framing;
+    return {
+      protocolVersion: PROTOCOL_VERSION,
+      agentVersion: AGENT_VERSION,
+      methods: server.listMethods(),
+      framing,
+    };
+  });
+
   server.register("agent:start", async (params) => {
-    const p = params as unknown as AgentStartParams;
+    const raw = params as unknown as AgentStartParams;
+    const p: AgentStartParams = {
+      ...raw,
+      alpaca: {
+        ...raw.alpaca,
+        keyId: resolveSecret(raw.alpaca.keyId),
+        secretKey: resolveSecret(raw.alpaca.secretKey),
+      },
+    };
 
     if (orchestrator) {
       await orchestrator.stop();
     }
 
+    if (p.trading?.halted) {
+      tradingGate.killSwitch(p.trading.reason);
+    } else if (p.trading) {
+      tradingGate.reset();
+    }
+
     // Resolve API keys: params first, then env vars
-    const anthropicKey = p.llm.anthropicApiKey || process.env.ANTHROPIC_API_KEY || "";
-    const openrouterKey = p.llm.openrouterApiKey || process.env.OPENROUTER_API_KEY || "";
+    const anthropicKey = resolveSecret(p.llm.anthropicApiKey) || process.env.ANTHROPIC_API_KEY || "";
+    const openrouterKey = resolveSecret(p.llm.openrouterApiKey) || process.env.OPENROUTER_API_KEY || "";
 
-    const providers: LLMProvider[] = [];
-    if (anthropicKey) {
-      providers.push(new AnthropicProvider({ apiKey: anthropicKey }));
-    }
-    if (openrouterKey) {
-      providers.push(new OpenRouterProvider({ apiKey: openrouterKey }));
-    }
+    const providers = buildProviders(p.llm.providers, anthropicKey, openrouterKey);
     if (providers.length === 0) {
       throw new Error("At least one LLM API key is required (params or ANTHROPIC_API_KEY/OPENROUTER_API_KEY env vars)");
     }
 
+    const reportUsage = (usage: LlmUsage) => writeNotification("llm:usage", usage);
+    const usageContext = () => ({
+      sessionId: orchestrator?.status.currentSessionId,
+      cycleId: orchestrator?.status.currentCycleId,
+    });
     orchestrator = new Orchestrator({
       alpaca: p.alpaca,
       llm: {
-        providers,
+        providers: providers.map((provider) => withUsageReporting(provider, reportUsage, usageContext)),
         model: p.llm.model,
         maxTokens: p.llm.maxTokens,
         temperature: p.llm.temperature,
       },
       buffer: { flushIntervalMs: 5000, urgentThreshold: 0.8 },
+      prompts: { analysis: p.prompts?.analysis?.body },
     });
+    if (p.prompts && Object.keys(p.prompts).length > 0) {
+      log.info("Using stored prompts", {
+        versions: Object.fromEntries(Object.entries(p.prompts).map(([name, prompt]) => [name, prompt.version])),
+      });
+    }
 
-    // Register the Alpaca streaming data source
-    const alpacaConfig: SourceConfig = {
-      id: "alpaca-stream",
-      name: "Alpaca Market Stream",
-      type: "streaming",
-      plugin: "alpaca",
-      config: {
-        feed: p.alpaca.feed,
-        symbols: p.alpaca.symbols,
-        channels: ["trades", "quotes", "bars"],
-        keyId: p.alpaca.keyId,
-        secretKey: p.alpaca.secretKey,
-      },
-      enabled: true,
-    };
-    const alpacaSource = new AlpacaStreamSource(
-      alpacaConfig,
-      (url: string) => new WebSocket(url) as unknown as WsLike,
-    );
-    orchestrator.sources.register(alpacaSource);
-    log.info("Registered AlpacaStreamSource", { symbols: p.alpaca.symbols, feed: p.alpaca.feed });
+    // Register the Alpaca streaming data sources: crypto pairs use their own feed
+    const cryptoSymbols = p.alpaca.symbols.filter(isCryptoSymbol);
+    const stockSymbols = p.alpaca.symbols.filter((s) => !isCryptoSymbol(s));
+    const streams: { id: string; name: string; feed: string; symbols: string[] }[] = [];
+    if (stockSymbols.length > 0 || cryptoSymbols.length === 0) {
+      streams.push({ id: "alpaca-stream", name: "Alpaca Market Stream", feed: p.alpaca.feed, symbols: stockSymbols });
+    }
+    if (cryptoSymbols.length > 0) {
+      streams.push({ id: "alpaca-crypto-stream", name: "Alpaca Crypto Stream", feed: "crypto", symbols: cryptoSymbols });
+    }
+    alpacaParams = p.alpaca;
+    for (const stream of streams) {
+      orchestrator.sources.register(createAlpacaStream(stream, p.alpaca));
+      log.info("Registered AlpacaStreamSource", {
+        id: stream.id,
+        symbols: stream.symbols,
+        feed: stream.feed,
+        mode: p.alpaca.mode ?? "paper",
+        profile: p.profile ?? null,
+      });
+    }
+
+    for (const source of p.sources ?? []) {
+      try {
+        orchestrator.sources.register(createConfiguredSource(source));
+        log.info("Registered configured source", { id: source.id, plugin: source.plugin, enabled: source.enabled });
+      } catch (err) {
+        log.warn("Skipping configured source", {
+          id: source.id,
+          error: err instanceof Error ? err.message : String(err),
+        });
+      }
+    }
 
     // Forward events as JSON-RPC notifications to stdout
     orchestrator.on("tick", (tick) => {
@@ -120,12 +246,70 @@ export function createAgentServer(): JsonRpcServer {
     orchestrator.on("activity", (activity) => {
       writeNotification("agent:activity", activity);
     });
+    orchestrator.on("health-change", (health) => {
+      writeNotification("source:health-change", health);
+    });
 
     await orchestrator.start();
     return { status: "started" };
   });
 
+  // Edit the watch list of a running agent without restarting it
+  server.register("agent:watch", async (params) => {
+    const p = params as { symbol: string };
+    if (!orchestrator || !alpacaParams) throw new Error("Agent is not running");
+    const crypto = isCryptoSymbol(p.symbol);
+    const id = crypto ? "alpaca-crypto-stream" : "alpaca-stream";
+    let source = orchestrator.sources.get(id) as AlpacaStreamSource | undefined;
+    if (!source) {
+      // First symbol of its kind: open the stream for it
+      source = createAlpacaStream(
+        crypto
+          ? { id, name: "Alpaca Crypto Stream", feed: "crypto", symbols: [] }
+          : { id, name: "Alpaca Market Stream", feed: alpacaParams.feed, symbols: [] },
+        alpacaParams,
+      );
+      orchestrator.sources.register(source);
+      await orchestrator.sources.start(id);
+    }
+    source.addSymbols([p.symbol]);
+    log.info("Watching symbol", { id, symbol: p.symbol });
+    return { sourceId: id, symbols: source.watchedSymbols };
+  });
+
+  server.register("agent:unwatch", async (params) => {
+    const p = params as { symbol: string };
+    if (!orchestrator) throw new Error("Agent is not running");
+    const id = isCryptoSymbol(p.symbol) ? "alpaca-crypto-stream" : "alpaca-stream";
+    const source = orchestrator.sources.get(id) as AlpacaStreamSource | undefined;
+    source?.removeSymbols([p.symbol]);
+    log.info("Unwatched symbol", { id, symbol: p.symbol });
+    return { sourceId: id, symbols: source?.watchedSymbols ?? [] };
+  });
+
+  // Source edits made in the app while the agent runs
+  server.register("sources:upsert", async (params) => {
+    const p = params as { source: SourceConfig };
+    if (!orchestrator) return { status: "idle" };
+    await orchestrator.upsertSource(createConfiguredSource(p.source));
+    return { status: "applied", id: p.source.id };
+  });
+
+  server.register("sources:remove", async (params) => {
+    const p = params as { id: string };
+    if (!orchestrator) return { status: "idle" };
+    const removed = await orchestrator.removeSource(p.id);
+    return { status: removed ? "removed" : "not_found", id: p.id };
+  });
+
+  server.register("sources:quarantine", async (params) => {
+    const p = params as { sourceId: string; until: number };
+    orchestrator?.quarantine(p.sourceId, p.until);
+    return { status: orchestrator ? "quarantined" : "idle" };
+  });
+
   server.register("agent:stop", async () => {
+    alpacaParams = null;
     if (orchestrator) {
       await orchestrator.stop();
       orchestrator = null;
@@ -133,6 +317,50 @@ export function createAgentServer(): JsonRpcServer {
     return { status: "stopped" };
   });
 
+  // Backup and seeding of the running agent's long-term memory
+  server.register("memory:export", async () => {
+    const memory = orchestrator?.memory;
+    if (!memory) throw new Error("Agent memory is not enabled");
+    return { entries: memory.exportEntries() };
+  });
+
+  server.register("memory:import", async (params) => {
+    const p = params as { entries: MemoryEntry[]; strategy?: MemoryMergeStrategy };
+    const memory = orchestrator?.memory;
+    if (!memory) throw new Error("Agent memory is not enabled");
+    const result = memory.importEntries(p.entries ?? [], p.strategy ?? "skip");
+    log.info("Imported memory entries", { ...result, strategy: p.strategy ?? "skip" });
+    return result;
+  });
+
+  server.register("memory:search", async (params) => {
+    const p = params as { query: string; limit?: number; filter?: MemorySearchFilter; embedding?: number[] | null };
+    const memory = orchestrator?.memory;
+    if (!memory) throw new Error("Agent memory is not enabled");
+    return { results: await memory.search(p.query ?? "", p.limit ?? 10, p.filter, p.embedding ?? undefined) };
+  });
+
+  // Embeddings computed by the host's local model
+  server.register("memory:unembedded", async (params) => {
+    const p = params as { limit?: number };
+    const memory = orchestrator?.memory;
+    if (!memory) throw new Error("Agent memory is not enabled");
+    return { entries: memory.unembedded(p.limit ?? 32) };
+  });
+
+  server.register("memory:set_embeddings", async (params) => {
+    const p = params as { embeddings: { id: string; embedding: number[] }[] };
+    const memory = orchestrator?.memory;
+    if (!memory) throw new Error("Agent memory is not enabled");
+    return { updated: memory.setEmbeddings(p.embeddings ?? []) };
+  });
+
+  server.register("memory:compact", async (params) => {
+    const p = params as { threshold: number; summarize?: boolean };
+    if (!orchestrator) throw new Error("Agent memory is not enabled");
+    return orchestrator.compactMemory(p.threshold, p.summarize ?? false);
+  });
+
   server.register("agent:status", async () => {
     if (!orchestrator) {
       return { state: "idle", totalCycles: 0, totalAnomalies: 0, uptime: 0 };
@@ -145,27 +373,29 @@ export function createAgentServer(): JsonRpcServer {
     const backtestId = p.config.id;
 
     // Resolve LLM providers (same pattern as agent:start)
-    const anthropicKey = p.llm.anthropicApiKey || process.env.ANTHROPIC_API_KEY || "";
-    const openrouterKey = p.llm.openrouterApiKey || process.env.OPENROUTER_API_KEY || "";
+    const anthropicKey = resolveSecret(p.llm.anthropicApiKey) || process.env.ANTHROPIC_API_KEY || "";
+    const openrouterKey = resolveSecret(p.llm.openrouterApiKey) || process.env.OPENROUTER_API_KEY || "";
 
-    const providers: LLMProvider[] = [];
-    if (anthropicKey) {
-      providers.push(new AnthropicProvider({ apiKey: anthropicKey }));
-    }
-    if (openrouterKey) {
-      providers.push(new OpenRouterProvider({ apiKey: openrouterKey }));
-    }
+    const providers = buildProviders(p.llm.providers, anthropicKey, openrouterKey);
     if (providers.length === 0) {
       throw new Error("At least one LLM API key is required for backtest analysis");
     }
 
-    const provider = withFallback(providers);
+    const provider = withFallback(
+      providers.map((provider) =>
+        withUsageReporting(
+          provider,
+          (usage) => writeNotification("llm:usage", usage),
+          () => ({ sessionId: backtestId }),
+        ),
+      ),
+    );
 
     // Create fetchData dependency via AlpacaBackfill
     const backfill = new AlpacaBackfill({
       sourceId: `backtest-${backtestId}`,
-      keyId: p.alpaca.keyId,
-      secretKey: p.alpaca.secretKey,
+      keyId: resolveSecret(p.alpaca.keyId),
+      secretKey: resolveSecret(p.alpaca.secretKey),
       baseUrl: "https://data.alpaca.markets",
     });
 
@@ -228,6 +458,40 @@ export function createAgentServer(): JsonRpcServer {
     return { backtestId, status: "started" };
   });
 
+  // Sent by the host as a notification after the user changes settings
+  server.register("config:update", async (params) => {
+    const p = params as unknown as { changes: ConfigChanges };
+    const applied = orchestrator ? orchestrator.applyConfig(p.changes ?? {}) : [];
+    return { applied };
+  });
+
+  // Sent by the host after the user deletes the broker credentials the agent runs with
+  server.register("credentials:revoked", async (params) => {
+    const p = params as unknown as { mode: string };
+    log.warn("Credentials revoked, stopping monitoring and backtests", { mode: p.mode });
+    if (orchestrator) {
+      await orchestrator.stop();
+      orchestrator = null;
+    }
+    for (const engine of runningBacktests.values()) {
+      engine.cancel();
+    }
+    return { status: "stopped" };
+  });
+
+  // Sent by the host when the user engages or releases the kill switch
+  server.register("trading:halt", async (params) => {
+    const p = params as unknown as { reason?: string | null };
+    tradingGate.killSwitch(p.reason);
+    return { halted: true };
+  });
+
+  server.register("trading:resume", async () => {
+    tradingGate.reset();
+    log.info("Kill switch released");
+    return { halted: false };
+  });
+
   server.register("backtest:cancel", async (params) => {
     const p = params as unknown as { backtestId: string };
     const engine = runningBacktests.get(p.backtestId);
@@ -243,29 +507,31 @@ export function createAgentServer(): JsonRpcServer {
   return server;
 }
 
+/** Write one JSON message to stdout using the current framing. */
+function writeMessage(json: string): void {
+  process.stdout.write(encodeFrame(json, outputFraming));
+}
+
 /** Write a JSON-RPC notification (no id) to stdout. */
 function writeNotification(method: string, params: unknown): void {
-  const notification = JSON.stringify({ jsonrpc: "2.0", method, params });
-  process.stdout.write(notification + "\n");
+  writeMessage(JSON.stringify({ jsonrpc: "2.0", method, params }));
 }
 
 export function start(): void {
   const server = createAgentServer();
-
-  process.stdin.setEncoding("utf-8");
-  let buffer = "";
-
-  process.stdin.on("data", (chunk: string) => {
-    buffer += chunk;
-    const lines = buffer.split("\n");
-    buffer = lines.pop() ?? "";
-
-    for (const line of lines) {
-      if (line.trim()) {
-        server.handleRequest(line.trim()).then((response) => {
-          process.stdout.write(response + "\n");
-        });
-      }
+  const decoder = new FrameDecoder();
+
+  process.stdin.on("data", (chunk: Buffer) => {
+    for (const message of decoder.push(chunk)) {
+      server.handleMessage(message).then((response) => {
+        if (response === null) return;
+        writeMessage(response);
+        // The hello response goes out in the old framing; switch afterwards
+        if (negotiatedFraming) {
+          outputFraming = negotiatedFraming;
+          negotiatedFraming = null;
+        }
+      });
     }
   });
 }
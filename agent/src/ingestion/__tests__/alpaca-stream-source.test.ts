@@ -1,5 +1,5 @@
 import { describe, it, expect, vi, beforeEach, afterEach } from "vitest";
-import { AlpacaStreamSource } from "../alpaca-stream-source.js";
+import { AlpacaStreamSource, isCryptoSymbol } from "../alpaca-stream-source.js";
 import type { SourceConfig } from "@finwatch/shared";
 import { EventEmitter } from "events";
 
@@ -102,6 +102,23 @@ describe("AlpacaStreamSource", () => {
     await source.stop();
   });
 
+  it("uses crypto endpoint when feed is crypto", async () => {
+    const source = new AlpacaStreamSource(
+      createConfig({ feed: "crypto", symbols: ["BTC/USD"] }),
+      mockWsFactory,
+    );
+    await source.start();
+
+    expect(mockWsInstances[0]!.url).toBe("wss://stream.data.alpaca.markets/v1beta3/crypto/us");
+
+    await source.stop();
+  });
+
+  it("detects crypto symbols by their slash", () => {
+    expect(isCryptoSymbol("BTC/USD")).toBe(true);
+    expect(isCryptoSymbol("AAPL")).toBe(false);
+  });
+
   it("sends auth message on connect", async () => {
     const source = new AlpacaStreamSource(createConfig(), mockWsFactory);
     await source.start();
@@ -140,6 +157,45 @@ describe("AlpacaStreamSource", () => {
     await source.stop();
   });
 
+  it("subscribes and unsubscribes symbols incrementally once authenticated", async () => {
+    const source = new AlpacaStreamSource(createConfig(), mockWsFactory);
+    await source.start();
+
+    const ws = mockWsInstances[0]!;
+    ws.simulateMessage([{ T: "success", msg: "authenticated" }]);
+    await vi.advanceTimersByTimeAsync(0);
+    const sent = ws.sentMessages.length;
+
+    expect(source.addSymbols(["MSFT", "AAPL"])).toEqual(["MSFT"]);
+    const sub = JSON.parse(ws.sentMessages[sent]!);
+    expect(sub).toEqual({ action: "subscribe", trades: ["MSFT"], quotes: ["MSFT"], bars: ["MSFT"] });
+
+    expect(source.removeSymbols(["TSLA", "NVDA"])).toEqual(["TSLA"]);
+    const unsub = JSON.parse(ws.sentMessages[sent + 1]!);
+    expect(unsub).toEqual({ action: "unsubscribe", trades: ["TSLA"], quotes: ["TSLA"], bars: ["TSLA"] });
+
+    expect(source.watchedSymbols).toEqual(["AAPL", "MSFT"]);
+    expect(source.addSymbols(["AAPL"])).toEqual([]);
+    expect(ws.sentMessages.length).toBe(sent + 2);
+
+    await source.stop();
+  });
+
+  it("defers symbol changes until authenticated", async () => {
+    const source = new AlpacaStreamSource(createConfig(), mockWsFactory);
+    source.addSymbols(["MSFT"]);
+    await source.start();
+
+    const ws = mockWsInstances[0]!;
+    ws.simulateMessage([{ T: "success", msg: "authenticated" }]);
+    await vi.advanceTimersByTimeAsync(0);
+
+    const subMsg = JSON.parse(ws.sentMessages[ws.sentMessages.length - 1]!);
+    expect(subMsg.trades).toEqual(["AAPL", "TSLA", "MSFT"]);
+
+    await source.stop();
+  });
+
   it("accumulates bar ticks and returns them via fetch()", async () => {
     const source = new AlpacaStreamSource(createConfig(), mockWsFactory);
     await source.start();
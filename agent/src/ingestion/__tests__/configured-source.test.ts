@@ -0,0 +1,31 @@
+import { describe, it, expect } from "vitest";
+import type { SourceConfig } from "@finwatch/shared";
+import { createConfiguredSource } from "../configured-source.js";
+import { YahooFinanceSource } from "../yahoo-finance-source.js";
+import { CsvFileSource } from "../csv-file-source.js";
+
+function config(plugin: string, overrides: Partial<SourceConfig> = {}): SourceConfig {
+  return {
+    id: `${plugin}-source`,
+    name: "Configured",
+    type: "polling",
+    plugin,
+    config: { directory: "/tmp/finwatch-csv" },
+    pollIntervalMs: 60_000,
+    enabled: true,
+    ...overrides,
+  };
+}
+
+describe("createConfiguredSource", () => {
+  it("builds sources by plugin", () => {
+    const yahoo = createConfiguredSource(config("yahoo"));
+    expect(yahoo).toBeInstanceOf(YahooFinanceSource);
+    expect(yahoo.id).toBe("yahoo-source");
+    expect(createConfiguredSource(config("csv", { type: "file" }))).toBeInstanceOf(CsvFileSource);
+  });
+
+  it("rejects unknown plugins", () => {
+    expect(() => createConfiguredSource(config("ftp"))).toThrow("Unknown source plugin: ftp");
+  });
+});
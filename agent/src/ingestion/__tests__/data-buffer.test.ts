@@ -185,4 +185,23 @@ describe("DataBuffer", () => {
     await batchPromise;
     expect(buffer.size).toBe(0);
   });
+
+  it("setFlushInterval() applies to the next batch", async () => {
+    const buffer = new DataBuffer({ flushIntervalMs: 5000, urgentThreshold: 0.8 });
+    buffer.setFlushInterval(1000);
+    buffer.push(makeTick());
+
+    const batchPromise = buffer.nextBatch();
+    vi.advanceTimersByTime(1100);
+
+    const batch = await batchPromise;
+    expect(batch).toHaveLength(1);
+    buffer.destroy();
+  });
+
+  it("setFlushInterval() rejects non-positive intervals", () => {
+    const buffer = new DataBuffer({ flushIntervalMs: 5000, urgentThreshold: 0.8 });
+    expect(() => buffer.setFlushInterval(0)).toThrow("Invalid flush interval");
+    buffer.destroy();
+  });
 });
@@ -33,8 +33,14 @@ export type WsFactory = (url: string) => WsLike;
 const WS_ENDPOINTS: Record<string, string> = {
   iex: "wss://stream.data.alpaca.markets/v2/iex",
   sip: "wss://stream.data.alpaca.markets/v2/sip",
+  crypto: "wss://stream.data.alpaca.markets/v1beta3/crypto/us",
 };
 
+/** Alpaca crypto pairs are written with a slash, e.g. "BTC/USD". */
+export function isCryptoSymbol(symbol: string): boolean {
+  return symbol.includes("/");
+}
+
 export class AlpacaStreamSource implements DataSource {
   readonly id: string;
   readonly config: SourceConfig;
@@ -97,6 +103,29 @@ export class AlpacaStreamSource implements DataSource {
     }
   }
 
+  /** Symbols currently subscribed, or to subscribe once connected. */
+  get watchedSymbols(): string[] {
+    return [...this.symbols];
+  }
+
+  /** Subscribe to more symbols without reconnecting. Returns the symbols newly added. */
+  addSymbols(symbols: string[]): string[] {
+    const added = [...new Set(symbols)].filter((s) => !this.symbols.includes(s));
+    if (added.length === 0) return [];
+    this.symbols.push(...added);
+    if (this.authenticated) this.sendSubscription("subscribe", added);
+    return added;
+  }
+
+  /** Unsubscribe from symbols without reconnecting. Returns the symbols removed. */
+  removeSymbols(symbols: string[]): string[] {
+    const removed = this.symbols.filter((s) => symbols.includes(s));
+    if (removed.length === 0) return [];
+    this.symbols = this.symbols.filter((s) => !removed.includes(s));
+    if (this.authenticated) this.sendSubscription("unsubscribe", removed);
+    return removed;
+  }
+
   async fetch(): Promise<DataTick[]> {
     const ticks = this.tickBuffer;
     this.tickBuffer = [];
@@ -264,11 +293,15 @@ export class AlpacaStreamSource implements DataSource {
   }
 
   private sendSubscribe(): void {
+    this.sendSubscription("subscribe", this.symbols);
+  }
+
+  private sendSubscription(action: "subscribe" | "unsubscribe", symbols: string[]): void {
     if (!this.ws) return;
-    const sub: Record<string, unknown> = { action: "subscribe" };
-    if (this.channels.includes("trades")) sub.trades = this.symbols;
-    if (this.channels.includes("quotes")) sub.quotes = this.symbols;
-    if (this.channels.includes("bars")) sub.bars = this.symbols;
+    const sub: Record<string, unknown> = { action };
+    if (this.channels.includes("trades")) sub.trades = symbols;
+    if (this.channels.includes("quotes")) sub.quotes = symbols;
+    if (this.channels.includes("bars")) sub.bars = symbols;
     try {
       this.ws.send(JSON.stringify(sub));
     } catch {
@@ -0,0 +1,16 @@
+import type { SourceConfig } from "@finwatch/shared";
+import type { DataSource } from "./types.js";
+import { YahooFinanceSource } from "./yahoo-finance-source.js";
+import { CsvFileSource } from "./csv-file-source.js";
+
+/** Build a source from a host-configured definition, picking the class by `plugin`. */
+export function createConfiguredSource(config: SourceConfig): DataSource {
+  switch (config.plugin) {
+    case "yahoo":
+      return new YahooFinanceSource(config);
+    case "csv":
+      return new CsvFileSource(config);
+    default:
+      throw new Error(`Unknown source plugin: ${config.plugin}`);
+  }
+}
@@ -16,7 +16,7 @@ export class DataBuffer extends EventEmitter {
   private ticks: DataTick[] = [];
   private pending: PendingBatch | null = null;
   private destroyed = false;
-  private readonly flushIntervalMs: number;
+  private flushIntervalMs: number;
   private readonly urgentThreshold: number;
 
   constructor(options: DataBufferOptions) {
@@ -25,6 +25,14 @@ export class DataBuffer extends EventEmitter {
     this.urgentThreshold = options.urgentThreshold;
   }
 
+  /** Change the flush interval; takes effect from the next batch. */
+  setFlushInterval(ms: number): void {
+    if (!Number.isInteger(ms) || ms <= 0) {
+      throw new Error(`Invalid flush interval: ${ms}`);
+    }
+    this.flushIntervalMs = ms;
+  }
+
   get size(): number {
     return this.ticks.length;
   }
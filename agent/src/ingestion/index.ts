@@ -11,3 +11,4 @@ export type { HealthMonitorOptions } from "./health-monitor.js";
 export { PollingScheduler } from "./polling-scheduler.js";
 export type { PollingSchedulerOptions } from "./polling-scheduler.js";
 export { CustomSourceLoader } from "./custom-source-loader.js";
+export { createConfiguredSource } from "./configured-source.js";
@@ -0,0 +1,38 @@
+import { describe, it, expect } from "vitest";
+import { encodeFrame, FrameDecoder, negotiateFraming } from "../framing.js";
+
+describe("framing", () => {
+  it("encodes newline frames", () => {
+    expect(encodeFrame('{"a":1}', "newline")).toBe('{"a":1}\n');
+  });
+
+  it("encodes content-length frames using byte length", () => {
+    expect(encodeFrame('{"s":"é"}', "content-length")).toBe('Content-Length: 10\r\n\r\n{"s":"é"}');
+  });
+
+  it("decodes newline-delimited messages across chunks", () => {
+    const decoder = new FrameDecoder();
+    expect(decoder.push('{"id":1}\n{"id"')).toEqual(['{"id":1}']);
+    expect(decoder.push(':2}\n')).toEqual(['{"id":2}']);
+  });
+
+  it("decodes content-length frames containing raw newlines", () => {
+    const decoder = new FrameDecoder();
+    const body = '{"text":"line1\nline2"}';
+    const frame = encodeFrame(body, "content-length");
+    expect(decoder.push(frame.slice(0, 10))).toEqual([]);
+    expect(decoder.push(frame.slice(10))).toEqual([body]);
+  });
+
+  it("handles mixed framings in one stream", () => {
+    const decoder = new FrameDecoder();
+    const out = decoder.push('{"id":1}\n' + encodeFrame('{"id":2}', "content-length"));
+    expect(out).toEqual(['{"id":1}', '{"id":2}']);
+  });
+
+  it("negotiates the first supported framing", () => {
+    expect(negotiateFraming(["content-length", "newline"])).toBe("content-length");
+    expect(negotiateFraming(["carrier-pigeon", "newline"])).toBe("newline");
+    expect(negotiateFraming(undefined)).toBe("newline");
+  });
+});
@@ -74,6 +74,38 @@ describe("JsonRpcServer", () => {
     expect(parsed.error.code).toBe(-32600);
   });
 
+  it("runs notification handlers without producing a response", async () => {
+    const server = new JsonRpcServer();
+    const handler = vi.fn(async () => ({ status: "cancelled" }));
+    server.register("backtest:cancel", handler);
+
+    const response = await server.handleMessage(
+      JSON.stringify({ jsonrpc: "2.0", method: "backtest:cancel", params: { backtestId: "bt-1" } })
+    );
+    expect(response).toBeNull();
+    expect(handler).toHaveBeenCalledWith({ backtestId: "bt-1" });
+  });
+
+  it("swallows errors and unknown methods for notifications", async () => {
+    const server = new JsonRpcServer();
+    server.register("fail", async () => {
+      throw new Error("boom");
+    });
+
+    expect(await server.handleMessage(JSON.stringify({ jsonrpc: "2.0", method: "fail" }))).toBeNull();
+    expect(await server.handleMessage(JSON.stringify({ jsonrpc: "2.0", method: "nope" }))).toBeNull();
+  });
+
+  it("handleMessage still answers requests with an id", async () => {
+    const server = new JsonRpcServer();
+    server.register("ping", async () => ({ status: "ok" }));
+
+    const response = await server.handleMessage(
+      JSON.stringify({ jsonrpc: "2.0", id: 9, method: "ping" })
+    );
+    expect(JSON.parse(response!).result).toEqual({ status: "ok" });
+  });
+
   it("lists registered methods", () => {
     const server = new JsonRpcServer();
     server.register("a", async () => ({}));
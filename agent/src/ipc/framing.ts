@@ -0,0 +1,76 @@
+/** Wire framing for the stdio JSON-RPC channel. */
+export type Framing = "newline" | "content-length";
+
+/** Framings this agent can speak, in order of preference. */
+export const SUPPORTED_FRAMINGS: Framing[] = ["content-length", "newline"];
+
+const HEADER = "Content-Length:";
+const HEADER_END = "\r\n\r\n";
+
+/** Encode one JSON message with the given framing. */
+export function encodeFrame(json: string, framing: Framing): string {
+  if (framing === "content-length") {
+    return `${HEADER} ${Buffer.byteLength(json, "utf-8")}${HEADER_END}${json}`;
+  }
+  return json + "\n";
+}
+
+/** Pick the first framing offered by the host that this agent supports. */
+export function negotiateFraming(offered: unknown): Framing {
+  if (!Array.isArray(offered)) return "newline";
+  const match = offered.find((f): f is Framing => SUPPORTED_FRAMINGS.includes(f as Framing));
+  return match ?? "newline";
+}
+
+/**
+ * Incremental decoder for stdin. Framing is detected per message: a
+ * `Content-Length:` header starts a length-prefixed frame, anything else is
+ * read as a newline-delimited JSON line.
+ */
+export class FrameDecoder {
+  private buffer = Buffer.alloc(0);
+
+  push(chunk: Buffer | string): string[] {
+    const bytes = typeof chunk === "string" ? Buffer.from(chunk, "utf-8") : chunk;
+    this.buffer = Buffer.concat([this.buffer, bytes]);
+    const messages: string[] = [];
+
+    for (;;) {
+      let start = 0;
+      while (start < this.buffer.length && isWhitespace(this.buffer[start]!)) start++;
+      this.buffer = this.buffer.subarray(start);
+      if (this.buffer.length === 0) break;
+
+      const prefix = this.buffer.subarray(0, HEADER.length).toString("utf-8");
+      if (prefix.length < HEADER.length && HEADER.startsWith(prefix)) break; // partial header
+
+      if (prefix.toLowerCase() === HEADER.toLowerCase()) {
+        const headerEnd = this.buffer.indexOf(HEADER_END);
+        if (headerEnd === -1) break;
+        const header = this.buffer.subarray(0, headerEnd).toString("utf-8");
+        const match = /Content-Length:\s*(\d+)/i.exec(header);
+        const bodyStart = headerEnd + HEADER_END.length;
+        if (!match) {
+          this.buffer = this.buffer.subarray(bodyStart);
+          continue;
+        }
+        const length = Number(match[1]);
+        if (this.buffer.length < bodyStart + length) break;
+        messages.push(this.buffer.subarray(bodyStart, bodyStart + length).toString("utf-8"));
+        this.buffer = this.buffer.subarray(bodyStart + length);
+      } else {
+        const newline = this.buffer.indexOf(0x0a);
+        if (newline === -1) break;
+        const line = this.buffer.subarray(0, newline).toString("utf-8").trim();
+        this.buffer = this.buffer.subarray(newline + 1);
+        if (line) messages.push(line);
+      }
+    }
+
+    return messages;
+  }
+}
+
+function isWhitespace(byte: number): boolean {
+  return byte === 0x0a || byte === 0x0d || byte === 0x20 || byte === 0x09;
+}
@@ -22,6 +22,42 @@ export class JsonRpcServer {
     return [...this.handlers.keys()];
   }
 
+  /**
+   * Handle a request or a notification. Notifications (no `id`) run their
+   * handler but never produce a response, per JSON-RPC 2.0.
+   */
+  async handleMessage(raw: string): Promise<string | null> {
+    let parsed: unknown;
+    try {
+      parsed = JSON.parse(raw);
+    } catch {
+      return this.handleRequest(raw);
+    }
+
+    if (
+      typeof parsed === "object" &&
+      parsed !== null &&
+      !("id" in parsed) &&
+      typeof (parsed as { method?: unknown }).method === "string"
+    ) {
+      const { method, params } = parsed as {
+        method: string;
+        params?: Record<string, unknown>;
+      };
+      const handler = this.handlers.get(method);
+      if (handler) {
+        try {
+          await handler(params ?? {});
+        } catch {
+          // Notifications have no error channel
+        }
+      }
+      return null;
+    }
+
+    return this.handleRequest(raw);
+  }
+
   async handleRequest(raw: string): Promise<string> {
     let id: number | string = 0;
 
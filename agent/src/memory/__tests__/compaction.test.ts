@@ -0,0 +1,76 @@
+import { describe, it, expect, vi, afterEach } from "vitest";
+import type { MemoryEntry } from "@finwatch/shared";
+import { findDuplicateClusters } from "../compaction.js";
+import { MemoryManager } from "../memory-manager.js";
+import { createMemoryDb } from "../db.js";
+import { embeddingToBuffer } from "../vector-search.js";
+import Database from "better-sqlite3";
+import fs from "node:fs";
+import path from "node:path";
+import os from "node:os";
+
+function entry(id: string, embedding: number[], timestamp = 0, tags: string[] = []): MemoryEntry {
+  return { id, content: `memory ${id}`, embedding, source: "test", timestamp, tags };
+}
+
+describe("findDuplicateClusters", () => {
+  it("groups entries similar to a group's first entry", () => {
+    const clusters = findDuplicateClusters(
+      [
+        entry("a", [1, 0]),
+        entry("b", [0, 1]),
+        entry("a2", [0.99, 0.05]),
+        entry("none", []),
+        entry("b2", [0.02, 1]),
+        entry("c", [0.7, 0.7]),
+      ],
+      0.95,
+    );
+    expect(clusters.map((c) => c.map((e) => e.id))).toEqual([
+      ["a", "a2"],
+      ["b", "b2"],
+    ]);
+  });
+});
+
+describe("MemoryManager.compact", () => {
+  let db: Database.Database;
+  let tmpDir: string;
+  afterEach(() => {
+    db?.close();
+    if (tmpDir) fs.rmSync(tmpDir, { recursive: true, force: true });
+  });
+
+  function setup(entries: MemoryEntry[]): MemoryManager {
+    db = createMemoryDb(":memory:");
+    tmpDir = fs.mkdtempSync(path.join(os.tmpdir(), "fw-compact-"));
+    const insert = db.prepare("INSERT INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,?,?,?,?)");
+    for (const e of entries) {
+      insert.run(e.id, e.content, embeddingToBuffer(e.embedding), e.source, e.timestamp, JSON.stringify(e.tags));
+    }
+    return new MemoryManager(db, tmpDir);
+  }
+
+  it("keeps the newest entry of each group with the group's tags", async () => {
+    const mgr = setup([entry("old", [1, 0], 1, ["NVDA"]), entry("new", [1, 0.01], 2, ["earnings"]), entry("other", [0, 1], 3)]);
+    const result = await mgr.compact(0.95);
+    expect(result).toEqual({ entriesBefore: 3, entriesAfter: 2, clusters: 1, summarized: 0 });
+    const kept = mgr.listAll().find((e) => e.id === "new")!;
+    expect(kept.content).toBe("memory new");
+    expect(kept.tags).toEqual(["NVDA", "earnings"]);
+  });
+
+  it("writes the summary when summarizing and falls back when it fails", async () => {
+    const mgr = setup([entry("a", [1, 0], 1), entry("a2", [1, 0], 2), entry("b", [0, 1], 3), entry("b2", [0, 1], 4)]);
+    const summarize = vi
+      .fn()
+      .mockResolvedValueOnce("merged memory about a")
+      .mockRejectedValueOnce(new Error("provider down"));
+    const result = await mgr.compact(0.95, summarize);
+    expect(result).toMatchObject({ entriesAfter: 2, clusters: 2, summarized: 1 });
+    expect(summarize).toHaveBeenCalledWith(["memory a", "memory a2"]);
+    const contents = mgr.listAll().map((e) => e.content).sort();
+    expect(contents).toEqual(["memory b2", "merged memory about a"]);
+    expect(mgr.searchKeyword("merged", 5)).toHaveLength(1);
+  });
+});
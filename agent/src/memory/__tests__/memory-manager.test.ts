@@ -86,6 +86,40 @@ describe("MemoryManager", () => {
     expect(results.length).toBeGreaterThanOrEqual(1);
   });
 
+  it("filters searches by tag, source and time in the query", async () => {
+    const mgr = setup();
+    const insert = db.prepare("INSERT INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,?,?,?,?)");
+    insert.run("f1", "AAPL volume spike", null, "cycle", 1_000, '["AAPL","volume"]');
+    insert.run("f2", "AAPL volume spike again", null, "cycle", 5_000, '["AAPL"]');
+    insert.run("f3", "MSFT volume spike", null, "cycle", 5_000, '["MSFT"]');
+    insert.run("f4", "AAPL volume spike noted", null, "manual", 6_000, '["AAPL"]');
+    mgr.syncSearch();
+
+    const ids = (results: { entry: { id: string } }[]) => results.map((r) => r.entry.id).sort();
+    expect(ids(await mgr.search("volume", 10, { tags: ["AAPL"], sources: ["cycle"], since: 2_000 }))).toEqual(["f2"]);
+    expect(ids(await mgr.search("volume", 10, { tags: ["MSFT", "volume"] }))).toEqual(["f1", "f3"]);
+    expect(ids(mgr.searchVector([1], 10, { until: 0 }))).toEqual([]);
+    const recent = await mgr.search("", 2, { tags: ["AAPL"] });
+    expect(recent.map((r) => r.entry.id)).toEqual(["f4", "f2"]);
+  });
+
+  it("stores embeddings computed by the host and searches with a given query embedding", async () => {
+    const mgr = setup();
+    const insert = db.prepare("INSERT INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,?,?,?,?)");
+    insert.run("u1", "semis sold off on export rules", null, "cycle", 1, "[]");
+    insert.run("u2", "chip stocks slid after new export curbs", null, "cycle", 2, "[]");
+    insert.run("o1", "openai embedded memory", embeddingToBuffer([1, 0, 0, 0]), "cycle", 3, "[]");
+    mgr.syncSearch();
+
+    expect(mgr.unembedded(10).map((e) => e.id)).toEqual(["u2", "u1"]);
+    expect(mgr.setEmbeddings([{ id: "u1", embedding: [0, 1] }, { id: "u2", embedding: [0.1, 0.9] }, { id: "gone", embedding: [1, 0] }])).toBe(2);
+    expect(mgr.unembedded(10)).toEqual([]);
+
+    const results = await mgr.search("chip", 5, undefined, [0, 1]);
+    expect(results.map((r) => r.entry.id)).toContain("u1");
+    expect(results.map((r) => r.entry.id)).not.toContain("o1");
+  });
+
   it("builds context string for cycle runner", () => {
     const mgr = setup();
     mgr.store("Previous AAPL anomaly detected at high volume", ["AAPL"]);
@@ -120,4 +154,26 @@ describe("MemoryManager", () => {
     const count = await mgr.backfill();
     expect(count).toBe(0);
   });
+
+  it("exports entries with embeddings and imports them per merge strategy", () => {
+    const source = setup();
+    db.prepare("INSERT INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,?,?,?,?)")
+      .run("e1", "NVDA gaps up after earnings", embeddingToBuffer([0.25, -0.5]), "test", 1, '["NVDA"]');
+    source.store("TSLA volume fades on Fridays", ["TSLA"]);
+    const exported = source.exportEntries();
+    expect(exported).toHaveLength(2);
+    expect(exported[0]!.embedding).toEqual([0.25, -0.5]);
+    expect(exported[0]!.tags).toEqual(["NVDA"]);
+    db.close();
+
+    const target = setup();
+    db.prepare("INSERT INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,NULL,?,?,?)")
+      .run("e1", "local copy", "test", 1, "[]");
+    expect(target.importEntries(exported, "skip")).toEqual({ imported: 1, skipped: 1 });
+    expect(target.listAll().find((e) => e.id === "e1")!.content).toBe("local copy");
+
+    expect(target.importEntries(exported, "replace")).toEqual({ imported: 2, skipped: 0 });
+    expect(target.exportEntries()[0]!.embedding).toEqual([0.25, -0.5]);
+    expect(target.searchKeyword("earnings", 5)).toHaveLength(1);
+  });
 });
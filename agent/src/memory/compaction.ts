@@ -0,0 +1,43 @@
+import type { LLMProvider, MemoryEntry } from "@finwatch/shared";
+import { cosineSimilarity } from "./vector-search.js";
+
+/** Folds the contents of near-duplicate memories into one. */
+export type MemorySummarizer = (contents: string[]) => Promise<string>;
+
+/**
+ * Groups of near-duplicate entries, in input order. Each entry joins the first
+ * group whose first entry it matches at `threshold` cosine similarity or above.
+ * Entries without embeddings are never grouped; singletons are left out.
+ */
+export function findDuplicateClusters(entries: MemoryEntry[], threshold: number): MemoryEntry[][] {
+  const clusters: MemoryEntry[][] = [];
+  for (const entry of entries) {
+    if (entry.embedding.length === 0) continue;
+    const cluster = clusters.find((c) => {
+      const seed = c[0]!.embedding;
+      return seed.length === entry.embedding.length && cosineSimilarity(seed, entry.embedding) >= threshold;
+    });
+    if (cluster) cluster.push(entry);
+    else clusters.push([entry]);
+  }
+  return clusters.filter((c) => c.length > 1);
+}
+
+/** A summarizer that asks `provider` to merge memories into one entry. */
+export function llmSummarizer(provider: LLMProvider, model: string): MemorySummarizer {
+  return async (contents) => {
+    let summary = "";
+    const stream = provider.createMessage({
+      model,
+      system:
+        "You maintain a financial monitoring agent's long-term memory. The following memories say nearly the same thing. Merge them into one concise memory that keeps every distinct fact, symbol, and number. Reply with the merged memory only.",
+      messages: [{ role: "user", content: contents.map((c, i) => `${i + 1}. ${c}`).join("\n\n") }],
+      maxTokens: 1024,
+      temperature: 0.2,
+    });
+    for await (const event of stream) {
+      if (event.type === "text_delta") summary += event.text;
+    }
+    return summary.trim();
+  };
+}
@@ -1,5 +1,7 @@
 import type Database from "better-sqlite3";
 import type { SearchResult } from "@finwatch/shared";
+import type { MemorySearchFilter } from "./types.js";
+import { filterClause } from "./search-filter.js";
 
 export class KeywordStore {
   private db: Database.Database;
@@ -12,12 +14,13 @@ export class KeywordStore {
     this.db.transaction(() => { for (const row of rows) insert.run(row.rowid, row.content, row.source, row.tags); })();
   }
 
-  search(query: string, topK: number): SearchResult[] {
+  search(query: string, topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    const where = filterClause(filter);
     return (this.db.prepare(`
       SELECT e.id, e.content, e.source, e.timestamp, e.tags, rank * -1 as score
       FROM entries_fts fts JOIN entries e ON e.rowid = fts.rowid
-      WHERE entries_fts MATCH ? ORDER BY rank LIMIT ?
-    `).all(query, topK) as { id: string; content: string; source: string; timestamp: number; tags: string; score: number }[])
+      WHERE entries_fts MATCH ?${where.sql} ORDER BY rank LIMIT ?
+    `).all(query, ...where.params, topK) as { id: string; content: string; source: string; timestamp: number; tags: string; score: number }[])
       .map(row => ({ entry: { id: row.id, content: row.content, embedding: [], source: row.source, timestamp: row.timestamp, tags: JSON.parse(row.tags) as string[] }, score: row.score, matchType: "keyword" as const }));
   }
 }
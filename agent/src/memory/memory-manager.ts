@@ -1,10 +1,18 @@
 import type Database from "better-sqlite3";
 import type { MemoryEntry, SearchResult } from "@finwatch/shared";
+import type {
+  MemoryCompactionResult,
+  MemoryImportResult,
+  MemoryMergeStrategy,
+  MemorySearchFilter,
+} from "./types.js";
 import { SemanticStore, type EmbeddingProvider } from "./semantic-store.js";
-import { VectorStore } from "./vector-search.js";
+import { VectorStore, bufferToEmbedding, embeddingToBuffer } from "./vector-search.js";
 import { KeywordStore } from "./keyword-search.js";
 import { mergeHybridResults, type HybridSearchConfig } from "./hybrid-search.js";
 import { backfillEmbeddings } from "./backfill-embeddings.js";
+import { findDuplicateClusters, type MemorySummarizer } from "./compaction.js";
+import { filterClause } from "./search-filter.js";
 
 const DEFAULT_HYBRID_CONFIG: HybridSearchConfig = {
   vectorWeight: 0.7,
@@ -43,24 +51,152 @@ export class MemoryManager {
     return this.semanticStore.listAll();
   }
 
+  /** Every entry with its embedding, oldest first. */
+  exportEntries(): MemoryEntry[] {
+    const rows = this.db
+      .prepare("SELECT id,content,embedding,source,timestamp,tags FROM entries ORDER BY timestamp")
+      .all() as { id: string; content: string; embedding: Buffer | null; source: string; timestamp: number; tags: string }[];
+    return rows.map((r) => ({
+      id: r.id,
+      content: r.content,
+      embedding: r.embedding ? bufferToEmbedding(r.embedding) : [],
+      source: r.source,
+      timestamp: r.timestamp,
+      tags: JSON.parse(r.tags) as string[],
+    }));
+  }
+
+  /** Store exported entries; one whose id is already stored is kept or replaced per `strategy`. */
+  importEntries(entries: MemoryEntry[], strategy: MemoryMergeStrategy): MemoryImportResult {
+    const conflict = strategy === "replace" ? "OR REPLACE" : "OR IGNORE";
+    const insert = this.db.prepare(
+      `INSERT ${conflict} INTO entries (id,content,embedding,source,timestamp,tags) VALUES (?,?,?,?,?,?)`,
+    );
+    let imported = 0;
+    this.db.transaction(() => {
+      for (const e of entries) {
+        const embedding = e.embedding.length > 0 ? embeddingToBuffer(e.embedding) : null;
+        imported += insert.run(e.id, e.content, embedding, e.source, e.timestamp, JSON.stringify(e.tags)).changes;
+      }
+    })();
+    this.keywordStore.syncFts();
+    return { imported, skipped: entries.length - imported };
+  }
+
+  /**
+   * Merge near-duplicate entries into the newest of each group, unioning
+   * their tags. With `summarize`, the group's contents are folded into one
+   * text and re-embedded; if that fails the newest content is kept.
+   */
+  async compact(threshold: number, summarize?: MemorySummarizer): Promise<MemoryCompactionResult> {
+    const entries = this.exportEntries();
+    const clusters = findDuplicateClusters(entries, threshold);
+    const update = this.db.prepare("UPDATE entries SET content = ?, embedding = ?, timestamp = ?, tags = ? WHERE id = ?");
+    const remove = this.db.prepare("DELETE FROM entries WHERE id = ?");
+    let removed = 0;
+    let summarized = 0;
+    for (const cluster of clusters) {
+      const keep = cluster.reduce((a, b) => (b.timestamp > a.timestamp ? b : a));
+      let content = keep.content;
+      let embedding = keep.embedding;
+      if (summarize) {
+        try {
+          const summary = await summarize(cluster.map((e) => e.content));
+          if (summary) {
+            embedding = this.embeddingService ? await this.embeddingService.embed(summary).catch(() => []) : [];
+            content = summary;
+            summarized++;
+          }
+        } catch {
+          // Keep the newest content; the duplicates are still merged
+        }
+      }
+      const tags = [...new Set(cluster.flatMap((e) => e.tags))];
+      this.db.transaction(() => {
+        const buf = embedding.length > 0 ? embeddingToBuffer(embedding) : null;
+        update.run(content, buf, keep.timestamp, JSON.stringify(tags), keep.id);
+        for (const e of cluster) {
+          if (e.id !== keep.id) removed += remove.run(e.id).changes;
+        }
+      })();
+    }
+    if (clusters.length > 0) this.keywordStore.syncFts();
+    return { entriesBefore: entries.length, entriesAfter: entries.length - removed, clusters: clusters.length, summarized };
+  }
+
+  /** Entries still without an embedding, newest first. */
+  unembedded(limit: number): { id: string; content: string }[] {
+    return this.db
+      .prepare("SELECT id, content FROM entries WHERE embedding IS NULL ORDER BY timestamp DESC LIMIT ?")
+      .all(limit) as { id: string; content: string }[];
+  }
+
+  /** Store embeddings computed elsewhere. Returns how many entries were updated. */
+  setEmbeddings(embeddings: { id: string; embedding: number[] }[]): number {
+    const update = this.db.prepare("UPDATE entries SET embedding = ? WHERE id = ?");
+    let updated = 0;
+    this.db.transaction(() => {
+      for (const { id, embedding } of embeddings) {
+        if (embedding.length > 0) updated += update.run(embeddingToBuffer(embedding), id).changes;
+      }
+    })();
+    return updated;
+  }
+
   syncSearch(): void {
     this.keywordStore.syncFts();
   }
 
-  searchKeyword(query: string, topK: number): SearchResult[] {
-    return this.keywordStore.search(query, topK);
+  searchKeyword(query: string, topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    return this.keywordStore.search(query, topK, filter);
   }
 
-  searchVector(queryEmbedding: number[], topK: number): SearchResult[] {
-    return this.vectorStore.search(queryEmbedding, topK);
+  searchVector(queryEmbedding: number[], topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    return this.vectorStore.search(queryEmbedding, topK, filter);
   }
 
-  searchHybrid(query: string, queryEmbedding: number[], topK: number): SearchResult[] {
-    const vectorResults = this.vectorStore.search(queryEmbedding, topK);
-    const keywordResults = this.keywordStore.search(query, topK);
+  searchHybrid(query: string, queryEmbedding: number[], topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    const vectorResults = this.vectorStore.search(queryEmbedding, topK, filter);
+    const keywordResults = this.keywordStore.search(query, topK, filter);
     return mergeHybridResults(vectorResults, keywordResults, this.hybridConfig);
   }
 
+  /**
+   * Search for `query` among entries matching `filter`: hybrid when the query
+   * is embedded, by the caller or the embedding service, keyword-only
+   * otherwise. A blank query lists the matching entries newest first.
+   */
+  async search(
+    query: string,
+    topK: number,
+    filter?: MemorySearchFilter,
+    queryEmbedding?: number[],
+  ): Promise<SearchResult[]> {
+    if (!query.trim()) {
+      return this.listFiltered(topK, filter);
+    }
+    if (queryEmbedding?.length) {
+      return this.searchHybrid(query, queryEmbedding, topK, filter);
+    }
+    if (this.embeddingService) {
+      const embedding = await this.embeddingService.embed(query).catch(() => null);
+      if (embedding) return this.searchHybrid(query, embedding, topK, filter);
+    }
+    return this.keywordStore.search(query, topK, filter);
+  }
+
+  private listFiltered(topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    const where = filterClause(filter);
+    const rows = this.db
+      .prepare(`SELECT id,content,source,timestamp,tags FROM entries e WHERE 1=1${where.sql} ORDER BY timestamp DESC LIMIT ?`)
+      .all(...where.params, topK) as { id: string; content: string; source: string; timestamp: number; tags: string }[];
+    return rows.map((r) => ({
+      entry: { id: r.id, content: r.content, embedding: [], source: r.source, timestamp: r.timestamp, tags: JSON.parse(r.tags) as string[] },
+      score: 1,
+      matchType: "keyword" as const,
+    }));
+  }
+
   buildContext(query: string): string {
     const keywordResults = this.keywordStore.search(query, 5);
     const sections: string[] = [];
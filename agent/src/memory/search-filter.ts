@@ -0,0 +1,29 @@
+import type { MemorySearchFilter } from "./types.js";
+
+/**
+ * SQL conditions on the `e` alias of `entries` for `filter`, joined with AND
+ * and prefixed so they can follow an existing WHERE clause. Tags and sources
+ * match any of the given values; the time range is inclusive.
+ */
+export function filterClause(filter: MemorySearchFilter | undefined): { sql: string; params: (string | number)[] } {
+  const conditions: string[] = [];
+  const params: (string | number)[] = [];
+  const placeholders = (n: number) => Array(n).fill("?").join(",");
+  if (filter?.tags?.length) {
+    conditions.push(`EXISTS (SELECT 1 FROM json_each(e.tags) WHERE value IN (${placeholders(filter.tags.length)}))`);
+    params.push(...filter.tags);
+  }
+  if (filter?.sources?.length) {
+    conditions.push(`e.source IN (${placeholders(filter.sources.length)})`);
+    params.push(...filter.sources);
+  }
+  if (filter?.since !== undefined) {
+    conditions.push("e.timestamp >= ?");
+    params.push(filter.since);
+  }
+  if (filter?.until !== undefined) {
+    conditions.push("e.timestamp <= ?");
+    params.push(filter.until);
+  }
+  return { sql: conditions.map((c) => ` AND ${c}`).join(""), params };
+}
@@ -0,0 +1,28 @@
+/** Narrows a memory search; tags and sources match any listed value. */
+export type MemorySearchFilter = {
+  tags?: string[];
+  sources?: string[];
+  /** Epoch millis, inclusive. */
+  since?: number;
+  /** Epoch millis, inclusive. */
+  until?: number;
+};
+
+/** What a memory import does with an entry whose id is already stored. */
+export type MemoryMergeStrategy = "skip" | "replace";
+
+export type MemoryImportResult = {
+  imported: number;
+  /** Entries left out because their id was already stored. */
+  skipped: number;
+};
+
+/** Outcome of merging near-duplicate memory entries. */
+export type MemoryCompactionResult = {
+  entriesBefore: number;
+  entriesAfter: number;
+  /** Groups of near-duplicates merged into one entry each. */
+  clusters: number;
+  /** Groups whose merged content was written by the LLM. */
+  summarized: number;
+};
@@ -1,5 +1,7 @@
 import type Database from "better-sqlite3";
 import type { MemoryEntry, SearchResult } from "@finwatch/shared";
+import type { MemorySearchFilter } from "./types.js";
+import { filterClause } from "./search-filter.js";
 
 export function cosineSimilarity(a: number[], b: number[]): number {
   let dot = 0, magA = 0, magB = 0;
@@ -26,10 +28,12 @@ export class VectorStore {
       .run(entry.id, entry.content, embBuf, entry.source, entry.timestamp, JSON.stringify(entry.tags));
   }
 
-  search(queryEmbedding: number[], topK: number): SearchResult[] {
-    const rows = this.db.prepare("SELECT id,content,embedding,source,timestamp,tags FROM entries WHERE embedding IS NOT NULL")
-      .all() as { id: string; content: string; embedding: Buffer; source: string; timestamp: number; tags: string }[];
-    return rows.map(row => {
+  search(queryEmbedding: number[], topK: number, filter?: MemorySearchFilter): SearchResult[] {
+    const where = filterClause(filter);
+    const rows = this.db.prepare(`SELECT id,content,embedding,source,timestamp,tags FROM entries e WHERE embedding IS NOT NULL${where.sql}`)
+      .all(...where.params) as { id: string; content: string; embedding: Buffer; source: string; timestamp: number; tags: string }[];
+    // Embeddings from another model (e.g. OpenAI vs local) are not comparable
+    return rows.filter(row => row.embedding.byteLength === queryEmbedding.length * 4).map(row => {
       const embedding = bufferToEmbedding(row.embedding);
       return { entry: { id: row.id, content: row.content, embedding, source: row.source, timestamp: row.timestamp, tags: JSON.parse(row.tags) as string[] }, score: cosineSimilarity(queryEmbedding, embedding), matchType: "vector" as const };
     }).sort((a, b) => b.score - a.score).slice(0, topK);
@@ -1,11 +1,22 @@
 import { EventEmitter } from "node:events";
 import type Database from "better-sqlite3";
-import type { DataTick, Anomaly, AgentActivity, AgentStatus, LLMProvider } from "@finwatch/shared";
+import type {
+  DataTick,
+  Anomaly,
+  AgentActivity,
+  AgentStatus,
+  LLMProvider,
+  SourceHealth,
+} from "@finwatch/shared";
 import { DataBuffer } from "./ingestion/data-buffer.js";
 import { SourceRegistry } from "./ingestion/source-registry.js";
+import { HealthMonitor } from "./ingestion/health-monitor.js";
+import type { DataSource } from "./ingestion/types.js";
 import { MonitorLoop } from "./analysis/monitor-loop.js";
 import { withFallback } from "./providers/fallback.js";
 import { MemoryManager } from "./memory/memory-manager.js";
+import type { MemoryCompactionResult } from "./memory/types.js";
+import { llmSummarizer } from "./memory/compaction.js";
 import { OpenAIEmbeddingProvider } from "./memory/openai-embeddings.js";
 import { createLogger } from "./utils/logger.js";
 
@@ -31,22 +42,43 @@ export type OrchestratorConfig = {
     memoryDir: string;
     openaiApiKey?: string;
   };
+  /** Stored prompt templates by name, replacing the built-in ones. */
+  prompts?: { analysis?: string };
+};
+
+/** Changed settings pushed by the host via `config:update`; shaped like the app config. */
+export type ConfigChanges = {
+  model?: string;
+  monitor?: { analysisIntervalMs?: number };
+  [key: string]: unknown;
 };
 
 export class Orchestrator extends EventEmitter {
   private readonly registry: SourceRegistry;
   private readonly buffer: DataBuffer;
   private readonly monitor: MonitorLoop;
+  private readonly health: HealthMonitor;
   private readonly _memory?: MemoryManager;
+  private readonly provider: LLMProvider;
+  private model: string;
   private running = false;
   private polling = false;
   private pollTimer: ReturnType<typeof setInterval> | null = null;
+  /** When each source was last fetched, for sources with their own `pollIntervalMs`. */
+  private lastPolled = new Map<string, number>();
+  /** Sources the host quarantined for flapping, with the epoch millis they are released. */
+  private quarantined = new Map<string, number>();
+  private static readonly HEALTH_CHECK_INTERVAL_MS = 30_000;
   private static readonly SOURCE_POLL_INTERVAL_MS = 1000;
   private log = createLogger("orchestrator");
 
   constructor(config: OrchestratorConfig) {
     super();
     this.registry = new SourceRegistry();
+    this.health = new HealthMonitor(this.registry, {
+      checkIntervalMs: Orchestrator.HEALTH_CHECK_INTERVAL_MS,
+    });
+    this.health.on("health-change", (h: SourceHealth) => this.emit("health-change", h));
     this.buffer = new DataBuffer({
       flushIntervalMs: config.buffer.flushIntervalMs,
       urgentThreshold: config.buffer.urgentThreshold,
@@ -63,6 +95,8 @@ export class Orchestrator extends EventEmitter {
     const provider = config.llm.providers.length > 1
       ? withFallback(config.llm.providers)
       : config.llm.providers[0]!;
+    this.provider = provider;
+    this.model = config.llm.model;
 
     this.monitor = new MonitorLoop(this.buffer, {
       provider,
@@ -75,6 +109,7 @@ export class Orchestrator extends EventEmitter {
       memoryContext: this._memory
         ? (tickSummary: string) => this._memory!.buildContext(tickSummary)
         : undefined,
+      analysisPrompt: config.prompts?.analysis,
     });
 
     this.monitor.onActivity = (a: AgentActivity) => this.emit("activity", a);
@@ -85,10 +120,40 @@ export class Orchestrator extends EventEmitter {
     return this.monitor.status;
   }
 
+  /** Apply settings that can change while running. Returns the paths applied. */
+  applyConfig(changes: ConfigChanges): string[] {
+    const applied: string[] = [];
+    if (typeof changes.model === "string" && changes.model) {
+      this.monitor.updateDeps({ model: changes.model });
+      this.model = changes.model;
+      applied.push("model");
+    }
+    const intervalMs = changes.monitor?.analysisIntervalMs;
+    if (typeof intervalMs === "number") {
+      this.buffer.setFlushInterval(intervalMs);
+      applied.push("monitor.analysisIntervalMs");
+    }
+    if (applied.length > 0) {
+      this.log.info("Applied config changes", { applied });
+    }
+    return applied;
+  }
+
   get memory(): MemoryManager | undefined {
     return this._memory;
   }
 
+  /** Merge near-duplicate memories, folding each group with the LLM when `summarize` is set. */
+  async compactMemory(threshold: number, summarize: boolean): Promise<MemoryCompactionResult> {
+    if (!this._memory) throw new Error("Agent memory is not enabled");
+    const result = await this._memory.compact(
+      threshold,
+      summarize ? llmSummarizer(this.provider, this.model) : undefined,
+    );
+    this.log.info("Compacted memory", { threshold, ...result });
+    return result;
+  }
+
   /** Inject a tick directly (for testing or manual sources). */
   injectTick(tick: DataTick): void {
     this.buffer.push(tick);
@@ -112,6 +177,7 @@ export class Orchestrator extends EventEmitter {
     await this.registry.startAll();
     this.log.info("All sources started, beginning poll loop");
     this.startSourcePolling();
+    this.health.start();
     this.monitor.start();
     this.emit("activity", { type: "cycle_start", message: "Orchestrator started", timestamp: Date.now() });
   }
@@ -120,11 +186,38 @@ export class Orchestrator extends EventEmitter {
     if (!this.running) return;
     this.running = false;
     this.stopSourcePolling();
+    this.health.stop();
     this.monitor.stop();
     await this.registry.stopAll();
     this.buffer.destroy();
   }
 
+  /** Add or replace a source, starting it if the orchestrator is running and it is enabled. */
+  async upsertSource(source: DataSource): Promise<void> {
+    await this.removeSource(source.id);
+    this.registry.register(source);
+    if (this.running && source.config.enabled) {
+      await source.start();
+    }
+    this.log.info("Source configured", { sourceId: source.id, enabled: source.config.enabled });
+  }
+
+  /** Stop and unregister a source. Returns false if it was not registered. */
+  async removeSource(id: string): Promise<boolean> {
+    const existing = this.registry.get(id);
+    if (!existing) return false;
+    await existing.stop();
+    this.registry.unregister(id);
+    this.lastPolled.delete(id);
+    return true;
+  }
+
+  /** Skip polling `id` until `until` (epoch millis). */
+  quarantine(id: string, until: number): void {
+    this.quarantined.set(id, until);
+    this.log.warn("Source quarantined", { sourceId: id, until });
+  }
+
   /** Expose registry for adding sources externally. */
   get sources(): SourceRegistry {
     return this.registry;
@@ -148,7 +241,17 @@ export class Orchestrator extends EventEmitter {
     this.polling = true;
     try {
       const sources = this.registry.list();
+      const now = Date.now();
       for (const source of sources) {
+        if (!source.config.enabled) continue;
+        const releaseAt = this.quarantined.get(source.id);
+        if (releaseAt !== undefined) {
+          if (now < releaseAt) continue;
+          this.quarantined.delete(source.id);
+        }
+        const interval = source.config.pollIntervalMs;
+        if (interval !== undefined && now - (this.lastPolled.get(source.id) ?? 0) < interval) continue;
+        this.lastPolled.set(source.id, now);
         try {
           const ticks = await source.fetch();
           if (ticks.length > 0) {
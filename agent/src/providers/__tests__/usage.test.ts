@@ -0,0 +1,62 @@
+import { describe, it, expect, vi } from "vitest";
+import type { CreateMessageParams, LLMProvider, StreamEvent } from "@finwatch/shared";
+import { withUsageReporting, type LlmUsage } from "../usage.js";
+
+function createProvider(events: StreamEvent[]): LLMProvider {
+  return {
+    id: "anthropic",
+    name: "Anthropic",
+    async *createMessage(_params: CreateMessageParams): AsyncIterable<StreamEvent> {
+      yield* events;
+    },
+    healthCheck: vi.fn(),
+    listModels: vi.fn<[], string[]>().mockReturnValue(["model-1"]),
+  };
+}
+
+const params: CreateMessageParams = {
+  model: "claude-haiku-4-5-20251001",
+  messages: [{ role: "user", content: "hi" }],
+  maxTokens: 100,
+};
+
+describe("withUsageReporting", () => {
+  it("reports usage events with the call context and passes every event through", async () => {
+    const reports: LlmUsage[] = [];
+    const provider = withUsageReporting(
+      createProvider([
+        { type: "text_delta", text: "ok" },
+        { type: "usage", input: 10, output: 5, cacheRead: 90 },
+        { type: "stop", reason: "end_turn" },
+      ]),
+      (u) => reports.push(u),
+      () => ({ sessionId: "monitor-1", cycleId: "cycle-7" }),
+    );
+
+    const events: StreamEvent[] = [];
+    for await (const event of provider.createMessage(params)) {
+      events.push(event);
+    }
+
+    expect(events.map((e) => e.type)).toEqual(["text_delta", "usage", "stop"]);
+    expect(reports).toHaveLength(1);
+    expect(reports[0]).toMatchObject({
+      providerId: "anthropic",
+      model: "claude-haiku-4-5-20251001",
+      inputTokens: 100,
+      outputTokens: 5,
+      sessionId: "monitor-1",
+      cycleId: "cycle-7",
+    });
+    expect(provider.listModels()).toEqual(["model-1"]);
+  });
+
+  it("reports nothing when the provider sends no usage", async () => {
+    const report = vi.fn();
+    const provider = withUsageReporting(createProvider([{ type: "stop", reason: "end_turn" }]), report);
+    for await (const _event of provider.createMessage(params)) {
+      // drain
+    }
+    expect(report).not.toHaveBeenCalled();
+  });
+});
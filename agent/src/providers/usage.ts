@@ -0,0 +1,54 @@
+import type { CreateMessageParams, LLMProvider, StreamEvent } from "@finwatch/shared";
+
+/** Token usage of one LLM call, reported to the host as `llm:usage`. */
+export type LlmUsage = {
+  providerId: string;
+  model: string;
+  inputTokens: number;
+  outputTokens: number;
+  latencyMs: number;
+  timestamp: number;
+  sessionId?: string;
+  cycleId?: string;
+};
+
+/** Session and cycle the current LLM call belongs to, if any. */
+export type UsageContext = { sessionId?: string; cycleId?: string };
+
+/**
+ * Wrap `provider` so every call that reports token usage is passed to `report`
+ * with its latency and the context at the time of the call.
+ */
+export function withUsageReporting(
+  provider: LLMProvider,
+  report: (usage: LlmUsage) => void,
+  context: () => UsageContext = () => ({}),
+): LLMProvider {
+  return {
+    id: provider.id,
+    name: provider.name,
+
+    async *createMessage(params: CreateMessageParams): AsyncIterable<StreamEvent> {
+      const { sessionId, cycleId } = context();
+      const started = Date.now();
+      for await (const event of provider.createMessage(params)) {
+        if (event.type === "usage") {
+          report({
+            providerId: provider.id,
+            model: params.model,
+            inputTokens: event.input + (event.cacheCreation ?? 0) + (event.cacheRead ?? 0),
+            outputTokens: event.output,
+            latencyMs: Date.now() - started,
+            timestamp: Date.now(),
+            sessionId,
+            cycleId,
+          });
+        }
+        yield event;
+      }
+    },
+
+    healthCheck: () => provider.healthCheck(),
+    listModels: () => provider.listModels(),
+  };
+}
@@ -1,5 +1,6 @@
 import type { TradeAction, TradeAuditEntry, TradeOutcome, FeedbackVerdict } from "@finwatch/shared";
 import { createLogger } from "../utils/logger.js";
+import type { TradingGate } from "./trading-gate.js";
 
 export type PaperExecutorConfig = {
   keyId: string;
@@ -9,6 +10,7 @@ export type PaperExecutorConfig = {
 
 export class PaperExecutor {
   private config: PaperExecutorConfig;
+  private gate?: TradingGate;
   private log = createLogger("paper-executor");
   private history: TradeAuditEntry[] = [];
   private auditSeq = 0;
@@ -16,8 +18,10 @@ export class PaperExecutor {
   onAudit?: (entry: TradeAuditEntry) => void;
   onFeedback?: (anomalyId: string, verdict: FeedbackVerdict) => void;
 
-  constructor(config: PaperExecutorConfig) {
+  /** Orders are refused while `gate`'s kill switch is engaged. */
+  constructor(config: PaperExecutorConfig, gate?: TradingGate) {
     this.config = config;
+    this.gate = gate;
   }
 
   get tradeCount(): number {
@@ -25,6 +29,11 @@ export class PaperExecutor {
   }
 
   async execute(action: TradeAction): Promise<TradeAuditEntry> {
+    if (this.gate?.killed) {
+      this.log.warn("Order refused, trading is halted", { symbol: action.symbol, reason: this.gate.haltReason });
+      throw new Error(`Trading is halted${this.gate.haltReason ? `: ${this.gate.haltReason}` : ""}`);
+    }
+
     const url = `${this.config.baseUrl}/v2/orders`;
     const controller = new AbortController();
     const timeout = setTimeout(() => controller.abort(), 30000);
@@ -30,6 +30,7 @@ export class TradingGate {
   private log = createLogger("trading-gate");
   private _mode: TradingMode = "paper";
   private _killed = false;
+  private _haltReason: string | null = null;
   private thresholds: GateThresholds;
 
   onKill?: () => void;
@@ -46,6 +47,10 @@ export class TradingGate {
     return this._killed;
   }
 
+  get haltReason(): string | null {
+    return this._haltReason;
+  }
+
   canGoLive(history: PaperHistory): GateCheckResult {
     const reasons: string[] = [];
 
@@ -90,15 +95,17 @@ export class TradingGate {
     return { success: true, reasons: [] };
   }
 
-  killSwitch(): void {
-    this.log.warn("Kill switch activated");
+  killSwitch(reason?: string | null): void {
+    this.log.warn("Kill switch activated", { reason: reason ?? null });
     this._mode = "paper";
     this._killed = true;
+    this._haltReason = reason ?? null;
     this.onKill?.();
   }
 
   reset(): void {
     this._killed = false;
+    this._haltReason = null;
     this._mode = "paper";
   }
 }
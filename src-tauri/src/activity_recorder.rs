@@ -0,0 +1,107 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::commands::activity::{activity_insert_db, activity_prune_db, ActivityConfig};
+use crate::db::DbPool;
+use crate::types::agent::AgentActivity;
+
+/// Minimum time between retention sweeps.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Persists `agent:activity` notifications from the agent, pruning past the
+/// `activity` retention window as it goes.
+pub struct ActivityRecorder {
+    config: Mutex<ActivityConfig>,
+    last_prune: Mutex<Option<Instant>>,
+}
+
+impl ActivityRecorder {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(ActivityConfig::default()),
+            last_prune: Mutex::new(None),
+        }
+    }
+
+    pub fn set_config(&self, config: ActivityConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// Store an `agent:activity` payload.
+    pub fn record(&self, pool: &DbPool, payload: &Value) -> Result<(), String> {
+        let config = *self.config.lock().unwrap_or_else(|e| e.into_inner());
+        let activity: AgentActivity = serde_json::from_value(payload.clone())
+            .map_err(|e| format!("Invalid activity payload: {}", e))?;
+        activity_insert_db(pool, &activity)?;
+        self.prune_if_due(pool, &config, activity.timestamp)
+    }
+
+    fn prune_if_due(&self, pool: &DbPool, config: &ActivityConfig, now_ms: u64) -> Result<(), String> {
+        {
+            let mut last = self.last_prune.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PRUNE_INTERVAL) {
+                return Ok(());
+            }
+            *last = Some(Instant::now());
+        }
+        let cutoff = now_ms.saturating_sub(config.retention_days.saturating_mul(DAY_MS));
+        let pruned = activity_prune_db(pool, cutoff)?;
+        if pruned > 0 {
+            tracing::debug!(pruned, "Pruned agent activity past retention");
+        }
+        Ok(())
+    }
+}
+
+impl Default for ActivityRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::activity::{activity_list_db, ActivityFilter};
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn payload(timestamp: u64) -> Value {
+        serde_json::json!({
+            "type": "cycle_start",
+            "message": "Cycle started",
+            "timestamp": timestamp,
+            "data": null,
+        })
+    }
+
+    #[test]
+    fn first_record_prunes_past_retention() {
+        let (_dir, pool) = test_pool();
+        let recorder = ActivityRecorder::new();
+        recorder.set_config(ActivityConfig { retention_days: 1 });
+        // Seed an old entry without triggering the sweep
+        activity_insert_db(&pool, &serde_json::from_value(payload(1_000)).unwrap()).unwrap();
+
+        recorder.record(&pool, &payload(1_000 + 2 * DAY_MS)).unwrap();
+        let listed = activity_list_db(&pool, &ActivityFilter::default()).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].timestamp, 1_000 + 2 * DAY_MS);
+    }
+
+    #[test]
+    fn invalid_payload_is_an_error() {
+        let (_dir, pool) = test_pool();
+        let recorder = ActivityRecorder::new();
+        assert!(recorder.record(&pool, &serde_json::json!({"foo": 1})).is_err());
+    }
+}
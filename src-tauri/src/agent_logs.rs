@@ -0,0 +1,268 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::time::now_millis;
+
+/// Active log file name; rotated files get a numeric suffix (`agent.log.1`, ...).
+const LOG_FILE: &str = "agent.log";
+/// Rotate once the active file exceeds this size (5 MB).
+pub const DEFAULT_MAX_BYTES: u64 = 5 * 1024 * 1024;
+/// Number of rotated files kept in addition to the active one.
+pub const DEFAULT_MAX_FILES: usize = 5;
+
+/// Directory holding agent stderr logs: `~/.finwatch/logs/agent/`.
+pub fn agent_logs_dir() -> PathBuf {
+    crate::db::finwatch_root_dir().join("logs").join("agent")
+}
+
+/// Appends lines to a log file (`agent.log` unless named), rotating by size.
+pub struct RotatingLogWriter {
+    dir: PathBuf,
+    file_name: &'static str,
+    max_bytes: u64,
+    max_files: usize,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingLogWriter {
+    pub fn new(dir: PathBuf, max_bytes: u64, max_files: usize) -> Self {
+        Self::named(dir, LOG_FILE, max_bytes, max_files)
+    }
+
+    /// A writer for `file_name` in `dir`, rotated to `file_name.1`, ...
+    pub fn named(dir: PathBuf, file_name: &'static str, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            dir,
+            file_name,
+            max_bytes,
+            max_files,
+            file: None,
+            size: 0,
+        }
+    }
+
+    /// Write one line of agent output, prefixed with the current time in epoch millis.
+    pub fn write_line(&mut self, text: &str) -> Result<(), String> {
+        self.write_raw(&format!("{} {}", now_millis(), text))
+    }
+
+    /// Write one line as-is.
+    pub fn write_raw(&mut self, text: &str) -> Result<(), String> {
+        if self.file.is_none() {
+            self.open()?;
+        }
+        if self.size >= self.max_bytes {
+            self.rotate()?;
+        }
+        let line = format!("{}\n", text.trim_end_matches('\n'));
+        let file = self.file.as_mut().ok_or("Log file not open")?;
+        file.write_all(line.as_bytes())
+            .map_err(|e| format!("Failed to write log: {}", e))?;
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    fn open(&mut self) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Failed to create log dir: {}", e))?;
+        let path = self.dir.join(self.file_name);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open log: {}", e))?;
+        self.size = file.metadata().map(|m| m.len()).unwrap_or(0);
+        self.file = Some(file);
+        Ok(())
+    }
+
+    /// Shift `agent.log.N-1` → `agent.log.N`, dropping the oldest, then start a fresh file.
+    fn rotate(&mut self) -> Result<(), String> {
+        self.file = None;
+        let oldest = rotated_path(&self.dir, self.file_name, self.max_files);
+        let _ = fs::remove_file(oldest);
+        for n in (1..self.max_files).rev() {
+            let from = rotated_path(&self.dir, self.file_name, n);
+            if from.exists() {
+                fs::rename(&from, rotated_path(&self.dir, self.file_name, n + 1))
+                    .map_err(|e| format!("Failed to rotate log: {}", e))?;
+            }
+        }
+        let active = self.dir.join(self.file_name);
+        if self.max_files > 0 && active.exists() {
+            fs::rename(&active, rotated_path(&self.dir, self.file_name, 1))
+                .map_err(|e| format!("Failed to rotate log: {}", e))?;
+        } else {
+            let _ = fs::remove_file(&active);
+        }
+        self.open()
+    }
+}
+
+fn rotated_path(dir: &Path, file_name: &str, n: usize) -> PathBuf {
+    dir.join(format!("{}.{}", file_name, n))
+}
+
+/// The log files of `file_name` in `dir`, oldest rotated file first and the
+/// active file last.
+pub(crate) fn log_paths(dir: &Path, file_name: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = (1..)
+        .map(|n| rotated_path(dir, file_name, n))
+        .take_while(|p| p.exists())
+        .collect();
+    paths.reverse();
+    paths.push(dir.join(file_name));
+    paths
+}
+
+/// A parsed line from the agent log files.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentLogLine {
+    pub timestamp: i64,
+    /// Level parsed from the agent logger's `[LEVEL]` prefix, if present.
+    pub level: Option<String>,
+    pub message: String,
+}
+
+fn level_rank(level: &str) -> u8 {
+    match level {
+        "debug" => 0,
+        "info" => 1,
+        "warn" => 2,
+        "error" => 3,
+        _ => 1,
+    }
+}
+
+fn parse_line(raw: &str) -> AgentLogLine {
+    let (timestamp, rest) = match raw.split_once(' ') {
+        Some((ts, rest)) => match ts.parse::<i64>() {
+            Ok(ts) => (ts, rest),
+            Err(_) => (0, raw),
+        },
+        None => (0, raw),
+    };
+    let level = rest
+        .strip_prefix('[')
+        .and_then(|r| r.split_once(']'))
+        .map(|(lvl, _)| lvl.to_lowercase())
+        .filter(|lvl| matches!(lvl.as_str(), "debug" | "info" | "warn" | "error"));
+    AgentLogLine {
+        timestamp,
+        level,
+        message: rest.to_string(),
+    }
+}
+
+/// Read the last `lines` entries across the active and rotated log files.
+///
+/// `level_filter` is a minimum level (`debug`, `info`, `warn`, `error`); lines
+/// without a recognizable level are treated as `info`.
+pub fn read_recent(
+    dir: &Path,
+    lines: usize,
+    level_filter: Option<&str>,
+) -> Result<Vec<AgentLogLine>, String> {
+    let min_rank = level_filter.map(level_rank).unwrap_or(0);
+
+    let mut result = std::collections::VecDeque::with_capacity(lines);
+    for path in log_paths(dir, LOG_FILE) {
+        let file = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read agent log: {}", e)),
+        };
+        for raw in BufReader::new(file).lines() {
+            let raw = raw.map_err(|e| format!("Failed to read agent log: {}", e))?;
+            let parsed = parse_line(&raw);
+            if level_rank(parsed.level.as_deref().unwrap_or("info")) < min_rank {
+                continue;
+            }
+            if result.len() == lines {
+                result.pop_front();
+            }
+            if lines > 0 {
+                result.push_back(parsed);
+            }
+        }
+    }
+    Ok(result.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn write_and_read_back_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RotatingLogWriter::new(dir.path().to_path_buf(), 1024, 3);
+        writer.write_line("[INFO] [agent-main] started").unwrap();
+        writer.write_line("[ERROR] [orchestrator] boom").unwrap();
+
+        let lines = read_recent(dir.path(), 10, None).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level.as_deref(), Some("info"));
+        assert_eq!(lines[1].message, "[ERROR] [orchestrator] boom");
+        assert!(lines[1].timestamp > 0);
+    }
+
+    #[test]
+    fn level_filter_is_a_minimum() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RotatingLogWriter::new(dir.path().to_path_buf(), 1024, 3);
+        writer.write_line("[DEBUG] [x] noisy").unwrap();
+        writer.write_line("[WARN] [x] careful").unwrap();
+        writer.write_line("[ERROR] [x] broken").unwrap();
+        writer.write_line("    at stack frame").unwrap();
+
+        let lines = read_recent(dir.path(), 10, Some("warn")).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].level.as_deref(), Some("warn"));
+    }
+
+    #[test]
+    fn read_returns_only_last_n_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RotatingLogWriter::new(dir.path().to_path_buf(), 1024, 3);
+        for i in 0..20 {
+            writer.write_line(&format!("[INFO] [x] line {}", i)).unwrap();
+        }
+        let lines = read_recent(dir.path(), 5, None).unwrap();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[4].message.ends_with("line 19"));
+    }
+
+    #[test]
+    fn rotation_caps_file_count_and_preserves_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = RotatingLogWriter::new(dir.path().to_path_buf(), 64, 2);
+        for i in 0..50 {
+            writer.write_line(&format!("[INFO] [x] line {}", i)).unwrap();
+        }
+        assert!(dir.path().join("agent.log.1").exists());
+        assert!(dir.path().join("agent.log.2").exists());
+        assert!(!dir.path().join("agent.log.3").exists());
+
+        let lines = read_recent(dir.path(), 1000, None).unwrap();
+        assert!(lines.len() < 50);
+        assert!(lines.last().unwrap().message.ends_with("line 49"));
+        let nums: Vec<u32> = lines
+            .iter()
+            .map(|l| l.message.rsplit(' ').next().unwrap().parse().unwrap())
+            .collect();
+        assert!(nums.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn read_missing_dir_returns_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let lines = read_recent(&dir.path().join("nope"), 10, None).unwrap();
+        assert!(lines.is_empty());
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::alerts::{alert_rules_list_db, alert_trigger_record_db};
+use crate::db::DbPool;
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::data::DataTick;
+use crate::workspace::WorkspaceDb;
+
+/// Tick metrics read as the price, in order of preference.
+const PRICE_METRICS: &[&str] = &["price", "close", "last"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// Price is above the threshold.
+    Above,
+    /// Price is below the threshold.
+    Below,
+    /// Price moved by at least `threshold` percent, either way, from the
+    /// price the rule was armed at (first tick seen, or the last trigger).
+    PercentChange,
+    /// Price moved from one side of the threshold to the other.
+    Crosses,
+}
+
+/// A stored price alert rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRule {
+    pub id: i64,
+    pub symbol: String,
+    pub condition: AlertCondition,
+    pub threshold: f64,
+    /// Minimum time between two triggers of this rule.
+    pub cooldown_secs: u64,
+    pub enabled: bool,
+    /// Epoch millis of the last trigger.
+    pub last_triggered: Option<u64>,
+}
+
+/// The user-editable part of a rule, taken by `alerts_rule_add` and `alerts_rule_update`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertRuleSpec {
+    pub symbol: String,
+    pub condition: AlertCondition,
+    pub threshold: f64,
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A rule firing on a tick; persisted and the payload of `alert:triggered`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertTrigger {
+    pub rule_id: i64,
+    pub symbol: String,
+    pub condition: AlertCondition,
+    pub threshold: f64,
+    pub price: f64,
+    /// Epoch millis.
+    pub triggered_at: u64,
+    pub message: String,
+}
+
+/// The price carried by a tick, if any.
+pub fn tick_price(tick: &DataTick) -> Option<f64> {
+    PRICE_METRICS
+        .iter()
+        .find_map(|m| tick.metrics.get(*m).copied())
+        .filter(|p| p.is_finite())
+}
+
+/// Whether `condition` holds for `price`, given the symbol's previous price
+/// and the rule's reference price.
+pub fn condition_met(
+    condition: AlertCondition,
+    threshold: f64,
+    price: f64,
+    previous: Option<f64>,
+    reference: Option<f64>,
+) -> bool {
+    match condition {
+        AlertCondition::Above => price > threshold,
+        AlertCondition::Below => price < threshold,
+        AlertCondition::PercentChange => reference
+            .filter(|r| *r != 0.0)
+            .is_some_and(|r| ((price - r) / r * 100.0).abs() >= threshold),
+        AlertCondition::Crosses => {
+            previous.is_some_and(|p| (p < threshold && price >= threshold) || (p > threshold && price <= threshold))
+        }
+    }
+}
+
+fn describe(rule: &AlertRule, price: f64) -> String {
+    let what = match rule.condition {
+        AlertCondition::Above => format!("is above {:.2}", rule.threshold),
+        AlertCondition::Below => format!("is below {:.2}", rule.threshold),
+        AlertCondition::PercentChange => format!("moved {}%", rule.threshold),
+        AlertCondition::Crosses => format!("crossed {:.2}", rule.threshold),
+    };
+    format!("{} {} (now {:.2})", rule.symbol, what, price)
+}
+
+/// Evaluates cached alert rules against incoming ticks.
+pub struct AlertEngine {
+    /// Loaded lazily from the database; `None` after a rule change.
+    rules: Mutex<Option<Vec<AlertRule>>>,
+    /// Last price seen per symbol, for `crosses`.
+    prices: Mutex<HashMap<String, f64>>,
+    /// Price each rule was armed at, for `percent_change`.
+    references: Mutex<HashMap<i64, f64>>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        Self {
+            rules: Mutex::new(None),
+            prices: Mutex::new(HashMap::new()),
+            references: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Drop the cached rules so the next tick reloads them.
+    pub fn invalidate(&self) {
+        *self.rules.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.references.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn set_rules(&self, rules: Vec<AlertRule>) {
+        *self.rules.lock().unwrap_or_else(|e| e.into_inner()) = Some(rules);
+    }
+
+    fn load_if_needed(&self, pool: &DbPool) -> Result<(), String> {
+        if self.rules.lock().unwrap_or_else(|e| e.into_inner()).is_none() {
+            self.set_rules(alert_rules_list_db(pool)?);
+        }
+        Ok(())
+    }
+
+    /// Check `tick` against the cached rules at `now_ms`. Returns the rules
+    /// that fired; their cooldown starts immediately.
+    pub fn evaluate(&self, tick: &DataTick, now_ms: u64) -> Vec<AlertTrigger> {
+        let (Some(symbol), Some(price)) = (tick.symbol.as_deref(), tick_price(tick)) else {
+            return Vec::new();
+        };
+        let previous = self
+            .prices
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(symbol.to_uppercase(), price);
+        let mut rules = self.rules.lock().unwrap_or_else(|e| e.into_inner());
+        let mut references = self.references.lock().unwrap_or_else(|e| e.into_inner());
+        let mut triggers = Vec::new();
+        for rule in rules.iter_mut().flatten() {
+            if !rule.enabled || !rule.symbol.eq_ignore_ascii_case(symbol) {
+                continue;
+            }
+            let reference = *references.entry(rule.id).or_insert(price);
+            if !condition_met(rule.condition, rule.threshold, price, previous, Some(reference)) {
+                continue;
+            }
+            let cooling = rule
+                .last_triggered
+                .is_some_and(|t| now_ms < t.saturating_add(rule.cooldown_secs.saturating_mul(1000)));
+            if cooling {
+                continue;
+            }
+            rule.last_triggered = Some(now_ms);
+            references.insert(rule.id, price);
+            triggers.push(AlertTrigger {
+                rule_id: rule.id,
+                symbol: rule.symbol.clone(),
+                condition: rule.condition,
+                threshold: rule.threshold,
+                price,
+                triggered_at: now_ms,
+                message: describe(rule, price),
+            });
+        }
+        triggers
+    }
+}
+
+impl Default for AlertEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Evaluate alert rules against `ticks`; record each trigger, emit
+/// `alert:triggered`, and show a desktop notification for it.
+pub fn check_ticks<R: Runtime>(app: &AppHandle<R>, ticks: &[DataTick]) {
+    let (Some(engine), Some(workspace)) = (app.try_state::<AlertEngine>(), app.try_state::<WorkspaceDb>()) else {
+        return;
+    };
+    let pool = workspace.pool();
+    if let Err(e) = engine.load_if_needed(&pool) {
+        tracing::warn!(error = %e, "Failed to load alert rules");
+        return;
+    }
+    for tick in ticks {
+        for trigger in engine.evaluate(tick, now_millis()) {
+            if let Err(e) = alert_trigger_record_db(&pool, &trigger) {
+                tracing::warn!(error = %e, rule_id = trigger.rule_id, "Failed to record alert");
+            }
+            if let Err(e) = app
+                .notification()
+                .builder()
+                .title(format!("FinWatch alert: {}", trigger.symbol))
+                .body(&trigger.message)
+                .show()
+            {
+                tracing::warn!(error = %e, "Failed to show alert notification");
+            }
+            let _ = emit_event(app, event_names::ALERT_TRIGGERED, &trigger);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, condition: AlertCondition, threshold: f64) -> AlertRule {
+        AlertRule {
+            id,
+            symbol: "SPY".to_string(),
+            condition,
+            threshold,
+            cooldown_secs: 60,
+            enabled: true,
+            last_triggered: None,
+        }
+    }
+
+    fn tick(symbol: &str, price: f64) -> DataTick {
+        DataTick {
+            source_id: "yahoo".to_string(),
+            timestamp: 0,
+            symbol: Some(symbol.to_string()),
+            metrics: [("price".to_string(), price)].into(),
+            metadata: Default::default(),
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn conditions() {
+        assert!(condition_met(AlertCondition::Above, 100.0, 101.0, None, None));
+        assert!(!condition_met(AlertCondition::Above, 100.0, 100.0, None, None));
+        assert!(condition_met(AlertCondition::Below, 100.0, 99.0, None, None));
+        assert!(condition_met(AlertCondition::PercentChange, 5.0, 94.0, None, Some(100.0)));
+        assert!(!condition_met(AlertCondition::PercentChange, 5.0, 104.0, None, Some(100.0)));
+        assert!(condition_met(AlertCondition::Crosses, 100.0, 100.5, Some(99.0), None));
+        assert!(condition_met(AlertCondition::Crosses, 100.0, 99.0, Some(101.0), None));
+        assert!(!condition_met(AlertCondition::Crosses, 100.0, 101.0, Some(100.5), None));
+        assert!(!condition_met(AlertCondition::Crosses, 100.0, 101.0, None, None));
+    }
+
+    #[test]
+    fn cooldown_suppresses_repeat_triggers() {
+        let engine = AlertEngine::new();
+        engine.set_rules(vec![rule(1, AlertCondition::Above, 100.0)]);
+        assert_eq!(engine.evaluate(&tick("spy", 101.0), 0).len(), 1);
+        assert!(engine.evaluate(&tick("SPY", 102.0), 30_000).is_empty());
+        let fired = engine.evaluate(&tick("SPY", 103.0), 60_000);
+        assert_eq!(fired[0].price, 103.0);
+        assert_eq!(fired[0].message, "SPY is above 100.00 (now 103.00)");
+    }
+
+    #[test]
+    fn percent_change_rearms_at_trigger_price() {
+        let engine = AlertEngine::new();
+        let mut r = rule(1, AlertCondition::PercentChange, 10.0);
+        r.cooldown_secs = 0;
+        engine.set_rules(vec![r]);
+        assert!(engine.evaluate(&tick("SPY", 100.0), 0).is_empty());
+        assert_eq!(engine.evaluate(&tick("SPY", 111.0), 1).len(), 1);
+        assert!(engine.evaluate(&tick("SPY", 115.0), 2).is_empty());
+        assert_eq!(engine.evaluate(&tick("SPY", 99.0), 3).len(), 1);
+    }
+
+    #[test]
+    fn ignores_other_symbols_disabled_rules_and_priceless_ticks() {
+        let engine = AlertEngine::new();
+        let mut disabled = rule(2, AlertCondition::Above, 0.0);
+        disabled.enabled = false;
+        engine.set_rules(vec![rule(1, AlertCondition::Above, 0.0), disabled]);
+        assert!(engine.evaluate(&tick("QQQ", 10.0), 0).is_empty());
+        let mut no_price = tick("SPY", 10.0);
+        no_price.metrics.clear();
+        assert!(engine.evaluate(&no_price, 0).is_empty());
+        let fired = engine.evaluate(&tick("SPY", 10.0), 0);
+        assert_eq!(fired.iter().map(|t| t.rule_id).collect::<Vec<_>>(), vec![1]);
+    }
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+
+use crate::commands::bars::Bar;
+use crate::commands::offline::ensure_online;
+use crate::commands::credentials::{alpaca_trading_url, credentials_get_active, AlpacaCredentials};
+use crate::db::{self, DbPool};
+use crate::http::{Conditional, Validators};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// Market Data API base URL; the same for paper and live keys.
+const DATA_URL: &str = "https://data.alpaca.markets";
+/// Largest page the historical bars endpoint returns.
+const BARS_PAGE_LIMIT: &str = "10000";
+
+/// Authenticated client for one environment of the Alpaca Trading API.
+pub struct AlpacaClient {
+    http: reqwest::Client,
+    base_url: String,
+    creds: AlpacaCredentials,
+}
+
+impl AlpacaClient {
+    pub fn new(mode: &str, creds: AlpacaCredentials) -> Result<Self, String> {
+        let http = crate::http::client_builder()?
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            http,
+            base_url: alpaca_trading_url(mode).to_string(),
+            creds,
+        })
+    }
+
+    /// A client with the active profile's credentials for `mode`.
+    pub async fn for_mode(pool: &DbPool, mode: &str) -> Result<Self, String> {
+        let lookup_mode = mode.to_string();
+        let creds = db::run_blocking(pool, move |pool| {
+            ensure_online(pool)?;
+            credentials_get_active(pool, &lookup_mode)
+        })
+        .await?
+        .ok_or_else(|| format!("No {} Alpaca credentials configured. Set them in Settings.", mode))?;
+        Self::new(mode, creds)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.request_to(&self.base_url, method, path)
+    }
+
+    fn request_to(&self, base_url: &str, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}{}", base_url, path))
+            .header("APCA-API-KEY-ID", &self.creds.key_id)
+            .header("APCA-API-SECRET-KEY", &self.creds.secret_key)
+    }
+
+    async fn send_raw(&self, request: reqwest::RequestBuilder, path: &str) -> Result<reqwest::Response, String> {
+        let response = crate::http::send(request)
+            .await
+            .map_err(|e| format!("Failed to reach Alpaca: {}", e))?;
+        Self::check_status(response, path).await
+    }
+
+    async fn check_status(response: reqwest::Response, path: &str) -> Result<reqwest::Response, String> {
+        let status = response.status();
+        if !status.is_success() {
+            // Alpaca explains rejections in a `message` field
+            let message = response
+                .json::<serde_json::Value>()
+                .await
+                .ok()
+                .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from));
+            return Err(match message {
+                Some(m) => format!("Alpaca API error {} on {}: {}", status, path, m),
+                None => format!("Alpaca API error {} on {}", status, path),
+            });
+        }
+        Ok(response)
+    }
+
+    async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder, path: &str) -> Result<T, String> {
+        self.send_raw(request, path)
+            .await?
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Alpaca response from {}: {}", path, e))
+    }
+
+    pub async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, String> {
+        self.send(self.request(reqwest::Method::GET, path).query(query), path).await
+    }
+
+    /// `GET` that the server may answer with `304 Not Modified` when
+    /// `validators` still match.
+    pub async fn get_conditional<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+        validators: &Validators,
+    ) -> Result<Conditional<T>, String> {
+        let request = validators.apply(self.request(reqwest::Method::GET, path).query(query));
+        let response = crate::http::send(request)
+            .await
+            .map_err(|e| format!("Failed to reach Alpaca: {}", e))?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(Conditional::NotModified);
+        }
+        let response = Self::check_status(response, path).await?;
+        let validators = Validators::from_response(&response);
+        let body = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse Alpaca response from {}: {}", path, e))?;
+        Ok(Conditional::Modified(body, validators))
+    }
+
+    /// The URL `get` requests for `path` and `query`, for keying cached validators.
+    pub fn url(&self, path: &str, query: &[(&str, &str)]) -> String {
+        self.request(reqwest::Method::GET, path)
+            .query(query)
+            .build()
+            .map(|r| r.url().to_string())
+            .unwrap_or_else(|_| format!("{}{}", self.base_url, path))
+    }
+
+    pub async fn post<T: DeserializeOwned>(&self, path: &str, body: &serde_json::Value) -> Result<T, String> {
+        self.send(self.request(reqwest::Method::POST, path).json(body), path).await
+    }
+
+    /// `DELETE` a resource; Alpaca answers these without a body.
+    pub async fn delete(&self, path: &str) -> Result<(), String> {
+        self.send_raw(self.request(reqwest::Method::DELETE, path), path).await?;
+        Ok(())
+    }
+
+    /// Price of the latest trade in `symbol`, from the Market Data API.
+    pub async fn latest_price(&self, symbol: &str) -> Result<f64, String> {
+        #[derive(Deserialize)]
+        struct LatestTrade {
+            trade: Trade,
+        }
+        #[derive(Deserialize)]
+        struct Trade {
+            p: f64,
+        }
+        let path = format!("/v2/stocks/{}/trades/latest", symbol);
+        let latest: LatestTrade = self
+            .send(self.request_to(DATA_URL, reqwest::Method::GET, &path), &path)
+            .await?;
+        Ok(latest.trade.p)
+    }
+
+    /// Historical `timeframe` bars for `symbol` between `from` and `to`
+    /// (epoch millis), oldest first, following every page.
+    pub async fn bars(&self, symbol: &str, timeframe: &str, from: u64, to: u64) -> Result<Vec<Bar>, String> {
+        #[derive(Deserialize)]
+        struct Page {
+            #[serde(default)]
+            bars: Option<Vec<AlpacaBar>>,
+            next_page_token: Option<String>,
+        }
+        let start = rfc3339(from)?;
+        let end = rfc3339(to)?;
+        let path = format!("/v2/stocks/{}/bars", symbol);
+        let mut bars = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("timeframe", timeframe),
+                ("start", start.as_str()),
+                ("end", end.as_str()),
+                ("limit", BARS_PAGE_LIMIT),
+            ];
+            if let Some(token) = &page_token {
+                query.push(("page_token", token.as_str()));
+            }
+            let page: Page = self
+                .send(self.request_to(DATA_URL, reqwest::Method::GET, &path).query(&query), &path)
+                .await?;
+            for bar in page.bars.unwrap_or_default() {
+                bars.push(bar.into_bar()?);
+            }
+            match page.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => break,
+            }
+        }
+        Ok(bars)
+    }
+}
+
+/// One bar as the Market Data API sends it.
+#[derive(Deserialize)]
+struct AlpacaBar {
+    t: String,
+    o: f64,
+    h: f64,
+    l: f64,
+    c: f64,
+    v: f64,
+}
+
+impl AlpacaBar {
+    fn into_bar(self) -> Result<Bar, String> {
+        let timestamp = chrono::DateTime::parse_from_rfc3339(&self.t)
+            .map_err(|e| format!("Invalid bar timestamp '{}': {}", self.t, e))?
+            .timestamp_millis();
+        Ok(Bar {
+            timestamp: timestamp.max(0) as u64,
+            open: self.o,
+            high: self.h,
+            low: self.l,
+            close: self.c,
+            volume: self.v,
+        })
+    }
+}
+
+fn rfc3339(millis: u64) -> Result<String, String> {
+    chrono::DateTime::from_timestamp_millis(millis as i64)
+        .map(|dt| dt.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .ok_or_else(|| format!("Invalid timestamp {}", millis))
+}
+
+/// Alpaca sends most amounts as decimal strings; accept those or plain numbers.
+pub fn de_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    de_opt_decimal(deserializer)?.ok_or_else(|| serde::de::Error::custom("missing decimal"))
+}
+
+pub fn de_opt_decimal<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Decimal {
+        Text(String),
+        Number(f64),
+    }
+    match Option::<Decimal>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Decimal::Number(n)) => Ok(Some(n)),
+        Some(Decimal::Text(s)) => s
+            .parse()
+            .map(Some)
+            .map_err(|_| serde::de::Error::custom(format!("invalid decimal '{}'", s))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Amounts {
+        #[serde(deserialize_with = "de_decimal")]
+        equity: f64,
+        #[serde(default, deserialize_with = "de_opt_decimal")]
+        limit_price: Option<f64>,
+    }
+
+    #[test]
+    fn bars_parse_rfc3339_timestamps() {
+        let bar: AlpacaBar = serde_json::from_str(
+            r#"{"t": "2025-01-02T05:00:00Z", "o": 1.0, "h": 2.0, "l": 0.5, "c": 1.5, "v": 100, "n": 3, "vw": 1.2}"#,
+        )
+        .unwrap();
+        let bar = bar.into_bar().unwrap();
+        assert_eq!(bar.timestamp, 1_735_794_000_000);
+        assert_eq!(bar.close, 1.5);
+        assert_eq!(rfc3339(1_735_794_000_000).unwrap(), "2025-01-02T05:00:00Z");
+    }
+
+    #[test]
+    fn decimals_parse_from_strings_numbers_and_null() {
+        let a: Amounts = serde_json::from_str(r#"{"equity": "1024.5", "limit_price": null}"#).unwrap();
+        assert_eq!(a.equity, 1024.5);
+        assert_eq!(a.limit_price, None);
+        let a: Amounts = serde_json::from_str(r#"{"equity": 7, "limit_price": "1.25"}"#).unwrap();
+        assert_eq!(a.equity, 7.0);
+        assert_eq!(a.limit_price, Some(1.25));
+        let a: Amounts = serde_json::from_str(r#"{"equity": "7"}"#).unwrap();
+        assert_eq!(a.limit_price, None);
+        assert!(serde_json::from_str::<Amounts>(r#"{"equity": "lots"}"#).is_err());
+    }
+}
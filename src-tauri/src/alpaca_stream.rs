@@ -0,0 +1,611 @@
+use std::collections::HashMap;
+use std::net::TcpStream;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+use crate::commands::config::config_effective_db;
+use crate::commands::credentials::{credentials_get_active, AlpacaCredentials};
+use crate::commands::sources::{sources_health_db, sources_health_set_db};
+use crate::commands::ticks::{tick_recording_config, ticks_insert_db};
+use crate::db::DbPool;
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::data::{DataTick, SourceHealth, SourceHealthStatus};
+use crate::workspace::WorkspaceDb;
+
+/// Source id the ticks and health row are recorded under. Distinct from the
+/// agent's own `alpaca-stream` so both can be told apart.
+pub const ALPACA_NATIVE_SOURCE_ID: &str = "alpaca-native";
+/// Feeds the stream can connect to.
+pub const FEEDS: &[&str] = &["iex", "sip"];
+const STREAM_URL: &str = "wss://stream.data.alpaca.markets/v2";
+/// How long a read blocks before the stop flag is checked again.
+const READ_POLL: Duration = Duration::from_millis(500);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The `alpacaStream` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AlpacaStreamConfig {
+    pub enabled: bool,
+    /// `iex` (free) or `sip` (paid subscription).
+    pub feed: String,
+    pub symbols: Vec<String>,
+    pub trades: bool,
+    pub quotes: bool,
+    pub bars: bool,
+}
+
+impl Default for AlpacaStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            feed: "iex".to_string(),
+            symbols: Vec::new(),
+            trades: true,
+            quotes: false,
+            bars: true,
+        }
+    }
+}
+
+/// Parse the `alpacaStream` section of the app config.
+pub fn alpaca_stream_config(app_config: &serde_json::Value) -> AlpacaStreamConfig {
+    app_config
+        .get("alpacaStream")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The `subscribe` action for `config`'s symbols and channels.
+pub fn subscribe_message(config: &AlpacaStreamConfig) -> serde_json::Value {
+    let symbols: Vec<String> = config.symbols.iter().map(|s| s.trim().to_uppercase()).collect();
+    let channel = |on: bool| if on { symbols.clone() } else { Vec::new() };
+    serde_json::json!({
+        "action": "subscribe",
+        "trades": channel(config.trades),
+        "quotes": channel(config.quotes),
+        "bars": channel(config.bars),
+    })
+}
+
+/// One message of a stream frame, as far as the client cares.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StreamMessage {
+    Connected,
+    Authenticated,
+    Subscribed,
+    Error(String),
+    Tick(DataTick),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "T")]
+enum RawMessage {
+    #[serde(rename = "success")]
+    Success { msg: String },
+    #[serde(rename = "error")]
+    Error { code: Option<i64>, msg: String },
+    #[serde(rename = "subscription")]
+    Subscription,
+    #[serde(rename = "t")]
+    Trade {
+        #[serde(rename = "S")]
+        symbol: String,
+        p: f64,
+        s: f64,
+        t: String,
+    },
+    #[serde(rename = "q")]
+    Quote {
+        #[serde(rename = "S")]
+        symbol: String,
+        bp: f64,
+        ap: f64,
+        bs: f64,
+        #[serde(rename = "as")]
+        ask_size: f64,
+        t: String,
+    },
+    #[serde(rename = "b")]
+    Bar {
+        #[serde(rename = "S")]
+        symbol: String,
+        o: f64,
+        h: f64,
+        l: f64,
+        c: f64,
+        v: f64,
+        t: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+fn parse_timestamp(value: &str) -> Result<u64, String> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.timestamp_millis().max(0) as u64)
+        .map_err(|e| format!("Invalid timestamp '{}': {}", value, e))
+}
+
+fn tick(
+    symbol: &str,
+    kind: &str,
+    timestamp: &str,
+    metrics: &[(&str, f64)],
+    raw: serde_json::Value,
+) -> Result<DataTick, String> {
+    Ok(DataTick {
+        source_id: ALPACA_NATIVE_SOURCE_ID.to_string(),
+        timestamp: parse_timestamp(timestamp)?,
+        symbol: Some(symbol.to_uppercase()),
+        metrics: metrics.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+        metadata: HashMap::from([("alpacaType".to_string(), kind.into())]),
+        raw: Some(raw),
+    })
+}
+
+/// Turn one stream frame (a JSON array of messages) into the messages the
+/// client acts on. Ticks use the same metrics as the agent's normalizer.
+pub fn parse_frame(text: &str) -> Result<Vec<StreamMessage>, String> {
+    let values: Vec<serde_json::Value> =
+        serde_json::from_str(text).map_err(|e| format!("Invalid stream frame: {}", e))?;
+    let mut messages = Vec::new();
+    for value in values {
+        let raw: RawMessage = match serde_json::from_value(value.clone()) {
+            Ok(raw) => raw,
+            Err(e) => {
+                tracing::debug!(error = %e, "Skipping unparseable stream message");
+                continue;
+            }
+        };
+        let message = match raw {
+            RawMessage::Success { msg } if msg == "authenticated" => StreamMessage::Authenticated,
+            RawMessage::Success { .. } => StreamMessage::Connected,
+            RawMessage::Error { code, msg } => StreamMessage::Error(match code {
+                Some(code) => format!("Alpaca stream error {}: {}", code, msg),
+                None => format!("Alpaca stream error: {}", msg),
+            }),
+            RawMessage::Subscription => StreamMessage::Subscribed,
+            RawMessage::Trade { symbol, p, s, t } => {
+                StreamMessage::Tick(tick(&symbol, "trade", &t, &[("price", p), ("size", s)], value)?)
+            }
+            RawMessage::Quote {
+                symbol,
+                bp,
+                ap,
+                bs,
+                ask_size,
+                t,
+            } => StreamMessage::Tick(tick(
+                &symbol,
+                "quote",
+                &t,
+                &[
+                    ("bidPrice", bp),
+                    ("askPrice", ap),
+                    ("bidSize", bs),
+                    ("askSize", ask_size),
+                    ("spread", ap - bp),
+                ],
+                value,
+            )?),
+            RawMessage::Bar {
+                symbol,
+                o,
+                h,
+                l,
+                c,
+                v,
+                t,
+            } => StreamMessage::Tick(tick(
+                &symbol,
+                "bar",
+                &t,
+                &[("open", o), ("high", h), ("low", l), ("close", c), ("volume", v)],
+                value,
+            )?),
+            RawMessage::Other => continue,
+        };
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+/// Health after a connection attempt; `error` is `None` once subscribed.
+pub fn connection_health(
+    previous: Option<&SourceHealth>,
+    error: Option<&str>,
+    latency_ms: u64,
+    now: u64,
+) -> SourceHealth {
+    let previous_success = previous.map_or(0, |p| p.last_success);
+    match error {
+        None => SourceHealth {
+            source_id: ALPACA_NATIVE_SOURCE_ID.to_string(),
+            status: SourceHealthStatus::Healthy,
+            last_success: now,
+            last_failure: previous.and_then(|p| p.last_failure),
+            fail_count: 0,
+            latency_ms,
+            message: None,
+        },
+        Some(error) => SourceHealth {
+            source_id: ALPACA_NATIVE_SOURCE_ID.to_string(),
+            status: SourceHealthStatus::Offline,
+            last_success: previous_success,
+            last_failure: Some(now),
+            fail_count: previous.map_or(0, |p| p.fail_count) + 1,
+            latency_ms,
+            message: Some(error.to_string()),
+        },
+    }
+}
+
+/// Market data is the same for both environments, so either mode's keys will do.
+fn credentials(pool: &DbPool) -> Result<AlpacaCredentials, String> {
+    match credentials_get_active(pool, "paper")? {
+        Some(creds) => Ok(creds),
+        None => credentials_get_active(pool, "live")?
+            .ok_or_else(|| "No Alpaca credentials configured. Set them in Settings.".to_string()),
+    }
+}
+
+fn record_health(pool: &DbPool, error: Option<&str>, latency_ms: u64) {
+    let result = sources_health_db(pool).and_then(|mut all| {
+        let previous = all.remove(ALPACA_NATIVE_SOURCE_ID);
+        sources_health_set_db(
+            pool,
+            &connection_health(previous.as_ref(), error, latency_ms, now_millis()),
+        )
+    });
+    if let Err(e) = result {
+        tracing::warn!(error = %e, "Failed to record Alpaca stream health");
+    }
+}
+
+/// Persist the ticks tick recording selects, emit `data:tick` for each, and
+/// check them against alert rules.
+fn publish_ticks<R: Runtime>(app: &AppHandle<R>, pool: &DbPool, ticks: &[DataTick]) {
+    match config_effective_db(pool).map(|c| tick_recording_config(&c)) {
+        Ok(recording) => {
+            for tick in ticks.iter().filter(|t| recording.should_record(t.symbol.as_deref())) {
+                if let Err(e) = ticks_insert_db(pool, tick) {
+                    tracing::warn!(error = %e, "Failed to record Alpaca stream tick");
+                }
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, "Alpaca stream could not read config"),
+    }
+    for tick in ticks {
+        let _ = emit_event(app, event_names::DATA_TICK, tick);
+    }
+    crate::alerts::check_ticks(app, ticks);
+}
+
+/// Runtime state of the native stream.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AlpacaStreamStatus {
+    pub running: bool,
+    /// Authenticated and subscribed.
+    pub connected: bool,
+    pub feed: Option<String>,
+    pub symbols: Vec<String>,
+    /// Ticks received since the stream was started.
+    pub ticks_received: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    connected: bool,
+    ticks_received: u64,
+    last_error: Option<String>,
+}
+
+struct ActiveStream {
+    config: AlpacaStreamConfig,
+    stop: Arc<AtomicBool>,
+    state: Arc<Mutex<StreamState>>,
+}
+
+fn set_read_timeout(socket: &WebSocket<MaybeTlsStream<TcpStream>>) -> Result<(), String> {
+    let result = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(READ_POLL)),
+        MaybeTlsStream::Rustls(stream) => stream.get_ref().set_read_timeout(Some(READ_POLL)),
+        _ => Ok(()),
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// One connection: authenticate, subscribe, and publish ticks until the
+/// server closes the socket, an error occurs, or `stop` is set.
+fn run_session<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &AlpacaStreamConfig,
+    stop: &AtomicBool,
+    state: &Mutex<StreamState>,
+) -> Result<(), String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let creds = credentials(&pool)?;
+    let started = Instant::now();
+    let url = format!("{}/{}", STREAM_URL, config.feed);
+    let (mut socket, _) =
+        tungstenite::connect(url.as_str()).map_err(|e| format!("Failed to connect to {}: {}", url, e))?;
+    set_read_timeout(&socket)?;
+    let send = |socket: &mut WebSocket<_>, value: serde_json::Value| {
+        socket
+            .send(Message::Text(value.to_string().into()))
+            .map_err(|e| format!("Failed to send to Alpaca stream: {}", e))
+    };
+    send(
+        &mut socket,
+        serde_json::json!({"action": "auth", "key": creds.key_id, "secret": creds.secret_key}),
+    )?;
+
+    while !stop.load(Ordering::SeqCst) {
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text.to_string(),
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => continue,
+            Err(tungstenite::Error::Io(e))
+                if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) =>
+            {
+                continue
+            }
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+            Err(e) => return Err(format!("Alpaca stream read failed: {}", e)),
+        };
+        let mut ticks = Vec::new();
+        for message in parse_frame(&text)? {
+            match message {
+                StreamMessage::Connected => {}
+                StreamMessage::Authenticated => send(&mut socket, subscribe_message(config))?,
+                StreamMessage::Subscribed => {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    state.connected = true;
+                    state.last_error = None;
+                    record_health(&pool, None, started.elapsed().as_millis() as u64);
+                    tracing::info!(
+                        feed = config.feed,
+                        symbols = config.symbols.len(),
+                        "Alpaca stream subscribed"
+                    );
+                }
+                StreamMessage::Error(e) => return Err(e),
+                StreamMessage::Tick(tick) => ticks.push(tick),
+            }
+        }
+        if !ticks.is_empty() {
+            state.lock().unwrap_or_else(|e| e.into_inner()).ticks_received += ticks.len() as u64;
+            publish_ticks(app, &pool, &ticks);
+        }
+    }
+    let _ = socket.close(None);
+    Ok(())
+}
+
+/// Live Alpaca market data straight from the websocket, independent of the
+/// agent sidecar.
+pub struct AlpacaStream {
+    active: Mutex<Option<ActiveStream>>,
+}
+
+impl AlpacaStream {
+    pub fn new() -> Self {
+        Self {
+            active: Mutex::new(None),
+        }
+    }
+
+    pub fn status(&self) -> AlpacaStreamStatus {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(active) = active.as_ref() else {
+            return AlpacaStreamStatus::default();
+        };
+        let state = active.state.lock().unwrap_or_else(|e| e.into_inner());
+        AlpacaStreamStatus {
+            running: true,
+            connected: state.connected,
+            feed: Some(active.config.feed.clone()),
+            symbols: active.config.symbols.clone(),
+            ticks_received: state.ticks_received,
+            last_error: state.last_error.clone(),
+        }
+    }
+
+    /// Connect with `config`, replacing any stream already running. The
+    /// stream reconnects with backoff until stopped.
+    pub fn start<R: Runtime>(
+        &self,
+        app: AppHandle<R>,
+        config: AlpacaStreamConfig,
+    ) -> Result<AlpacaStreamStatus, String> {
+        if config.symbols.is_empty() {
+            return Err("No Alpaca stream symbols are configured".to_string());
+        }
+        if !FEEDS.contains(&config.feed.as_str()) {
+            return Err(format!(
+                "Invalid feed '{}'; expected one of {}",
+                config.feed,
+                FEEDS.join(", ")
+            ));
+        }
+        self.stop();
+        let stop = Arc::new(AtomicBool::new(false));
+        let state = Arc::new(Mutex::new(StreamState::default()));
+        let (stop_flag, thread_state, thread_config) = (Arc::clone(&stop), Arc::clone(&state), config.clone());
+        std::thread::Builder::new()
+            .name("finwatch-alpaca-stream".to_string())
+            .spawn(move || {
+                let mut backoff = INITIAL_BACKOFF;
+                while !stop_flag.load(Ordering::SeqCst) {
+                    let started = Instant::now();
+                    let result = run_session(&app, &thread_config, &stop_flag, &thread_state);
+                    {
+                        let mut state = thread_state.lock().unwrap_or_else(|e| e.into_inner());
+                        state.connected = false;
+                        if let Err(e) = &result {
+                            state.last_error = Some(e.clone());
+                        }
+                    }
+                    if stop_flag.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Err(e) = &result {
+                        tracing::warn!(error = %e, "Alpaca stream disconnected");
+                        let pool = app.state::<WorkspaceDb>().pool();
+                        record_health(&pool, Some(e), started.elapsed().as_millis() as u64);
+                    }
+                    // A connection that stayed up for a while starts the backoff over
+                    if started.elapsed() > MAX_BACKOFF {
+                        backoff = INITIAL_BACKOFF;
+                    }
+                    let resume_at = Instant::now() + backoff;
+                    while Instant::now() < resume_at && !stop_flag.load(Ordering::SeqCst) {
+                        std::thread::sleep(READ_POLL);
+                    }
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            })
+            .map_err(|e| format!("Failed to spawn Alpaca stream thread: {}", e))?;
+        tracing::info!(
+            feed = config.feed,
+            symbols = config.symbols.len(),
+            "Alpaca stream started"
+        );
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = Some(ActiveStream { config, stop, state });
+        Ok(self.status())
+    }
+
+    /// Disconnect. Returns whether a stream was running.
+    pub fn stop(&self) -> bool {
+        let previous = self.active.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(previous) = &previous {
+            previous.stop.store(true, Ordering::SeqCst);
+            tracing::info!("Alpaca stream stopped");
+        }
+        previous.is_some()
+    }
+
+    /// Start or stop the stream to match `config`.
+    pub fn apply<R: Runtime>(
+        &self,
+        app: AppHandle<R>,
+        config: AlpacaStreamConfig,
+    ) -> Result<AlpacaStreamStatus, String> {
+        if !config.enabled || config.symbols.is_empty() {
+            self.stop();
+            return Ok(self.status());
+        }
+        self.start(app, config)
+    }
+}
+
+impl Default for AlpacaStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_control_and_market_messages() {
+        let frame = r#"[
+            {"T":"success","msg":"connected"},
+            {"T":"success","msg":"authenticated"},
+            {"T":"subscription","trades":["AAPL"],"quotes":[],"bars":["AAPL"]},
+            {"T":"t","S":"aapl","i":1,"x":"V","p":189.5,"s":100,"t":"2024-06-10T14:30:00.5Z","c":["@"],"z":"C"},
+            {"T":"q","S":"AAPL","bx":"V","bp":189.4,"bs":2,"ax":"V","ap":189.6,"as":3,"t":"2024-06-10T14:30:01Z"},
+            {"T":"b","S":"AAPL","o":189,"h":190,"l":188.5,"c":189.5,"v":12000,"t":"2024-06-10T14:30:00Z","n":40,"vw":189.3},
+            {"T":"d","S":"AAPL"}
+        ]"#;
+        let messages = parse_frame(frame).unwrap();
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages[0], StreamMessage::Connected);
+        assert_eq!(messages[1], StreamMessage::Authenticated);
+        assert_eq!(messages[2], StreamMessage::Subscribed);
+
+        let StreamMessage::Tick(trade) = &messages[3] else {
+            panic!("expected trade")
+        };
+        assert_eq!(trade.source_id, ALPACA_NATIVE_SOURCE_ID);
+        assert_eq!(trade.symbol.as_deref(), Some("AAPL"));
+        assert_eq!(trade.timestamp, 1_718_029_800_500);
+        assert_eq!(trade.metrics["price"], 189.5);
+        assert_eq!(trade.metadata["alpacaType"], "trade");
+
+        let StreamMessage::Tick(quote) = &messages[4] else {
+            panic!("expected quote")
+        };
+        assert!((quote.metrics["spread"] - 0.2).abs() < 1e-9);
+        assert_eq!(quote.metrics["askSize"], 3.0);
+
+        let StreamMessage::Tick(bar) = &messages[5] else {
+            panic!("expected bar")
+        };
+        assert_eq!(bar.metrics["volume"], 12000.0);
+    }
+
+    #[test]
+    fn reports_stream_errors() {
+        let messages = parse_frame(r#"[{"T":"error","code":406,"msg":"connection limit exceeded"}]"#).unwrap();
+        assert_eq!(
+            messages,
+            vec![StreamMessage::Error(
+                "Alpaca stream error 406: connection limit exceeded".to_string()
+            )]
+        );
+        assert!(parse_frame("not json").is_err());
+    }
+
+    #[test]
+    fn subscribes_only_enabled_channels() {
+        let config = AlpacaStreamConfig {
+            symbols: vec!["spy".to_string()],
+            ..Default::default()
+        };
+        let message = subscribe_message(&config);
+        assert_eq!(message["trades"], serde_json::json!(["SPY"]));
+        assert_eq!(message["quotes"], serde_json::json!([]));
+        assert_eq!(message["bars"], serde_json::json!(["SPY"]));
+    }
+
+    #[test]
+    fn health_tracks_connection_failures() {
+        let failed = connection_health(None, Some("refused"), 5, 1_000);
+        assert_eq!(failed.status, SourceHealthStatus::Offline);
+        assert_eq!(failed.fail_count, 1);
+        let again = connection_health(Some(&failed), Some("refused"), 5, 2_000);
+        assert_eq!(again.fail_count, 2);
+        let ok = connection_health(Some(&again), None, 80, 3_000);
+        assert_eq!(ok.status, SourceHealthStatus::Healthy);
+        assert_eq!(
+            (ok.fail_count, ok.last_success, ok.last_failure),
+            (0, 3_000, Some(2_000))
+        );
+    }
+
+    #[test]
+    fn config_defaults_to_disabled_iex() {
+        assert_eq!(
+            alpaca_stream_config(&serde_json::json!({})),
+            AlpacaStreamConfig::default()
+        );
+        let config = alpaca_stream_config(&serde_json::json!({"alpacaStream": {"enabled": true, "feed": "sip"}}));
+        assert!(config.enabled && config.trades && config.bars && !config.quotes);
+        assert_eq!(config.feed, "sip");
+    }
+}
@@ -0,0 +1,242 @@
+//! Return statistics over price and equity series, shared by the analytics
+//! commands. Returns are simple period returns as fractions.
+
+/// Trading days per year, for annualizing daily statistics.
+pub const TRADING_DAYS: f64 = 252.0;
+
+/// Period-over-period returns of `values`; one shorter than `values`.
+/// Periods starting from a non-positive value return 0.
+pub fn simple_returns(values: &[f64]) -> Vec<f64> {
+    values
+        .windows(2)
+        .map(|w| if w[0] > 0.0 { w[1] / w[0] - 1.0 } else { 0.0 })
+        .collect()
+}
+
+pub fn mean(xs: &[f64]) -> Option<f64> {
+    (!xs.is_empty()).then(|| xs.iter().sum::<f64>() / xs.len() as f64)
+}
+
+/// Sample standard deviation; `None` with fewer than two values.
+pub fn std_dev(xs: &[f64]) -> Option<f64> {
+    if xs.len() < 2 {
+        return None;
+    }
+    let m = mean(xs)?;
+    let var = xs.iter().map(|x| (x - m).powi(2)).sum::<f64>() / (xs.len() - 1) as f64;
+    Some(var.sqrt())
+}
+
+/// Sample covariance of two equally long series.
+pub fn covariance(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    if xs.len() != ys.len() || xs.len() < 2 {
+        return None;
+    }
+    let (mx, my) = (mean(xs)?, mean(ys)?);
+    let sum: f64 = xs.iter().zip(ys).map(|(x, y)| (x - mx) * (y - my)).sum();
+    Some(sum / (xs.len() - 1) as f64)
+}
+
+/// Standard deviation of `returns` scaled to a year of `periods_per_year`.
+pub fn annualized_volatility(returns: &[f64], periods_per_year: f64) -> Option<f64> {
+    std_dev(returns).map(|s| s * periods_per_year.sqrt())
+}
+
+/// Historical value at risk: the loss not exceeded in `confidence` of the
+/// periods in `returns`, as a non-negative fraction.
+pub fn historical_var(returns: &[f64], confidence: f64) -> Option<f64> {
+    if returns.is_empty() {
+        return None;
+    }
+    let mut sorted = returns.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let index = (((1.0 - confidence) * sorted.len() as f64).floor() as usize).min(sorted.len() - 1);
+    Some((-sorted[index]).max(0.0))
+}
+
+/// Annualized Sharpe ratio of `returns` over `risk_free`, the risk-free
+/// return per period; `None` when the returns do not vary.
+pub fn sharpe_ratio(returns: &[f64], risk_free: f64, periods_per_year: f64) -> Option<f64> {
+    let (m, sd) = (mean(returns)?, std_dev(returns)?);
+    (sd > 0.0).then(|| (m - risk_free) / sd * periods_per_year.sqrt())
+}
+
+/// Central moments two through four of `xs`.
+fn moments(xs: &[f64]) -> Option<(f64, f64, f64)> {
+    let m = mean(xs)?;
+    let n = xs.len() as f64;
+    let moment = |k: i32| xs.iter().map(|x| (x - m).powi(k)).sum::<f64>() / n;
+    Some((moment(2), moment(3), moment(4)))
+}
+
+/// Skewness of `xs`; `None` with fewer than three values or no variance.
+pub fn skewness(xs: &[f64]) -> Option<f64> {
+    if xs.len() < 3 {
+        return None;
+    }
+    let (m2, m3, _) = moments(xs)?;
+    (m2 > 0.0).then(|| m3 / m2.powf(1.5))
+}
+
+/// Excess kurtosis of `xs` (0 for a normal distribution); `None` with fewer
+/// than four values or no variance.
+pub fn excess_kurtosis(xs: &[f64]) -> Option<f64> {
+    if xs.len() < 4 {
+        return None;
+    }
+    let (m2, _, m4) = moments(xs)?;
+    (m2 > 0.0).then(|| m4 / (m2 * m2) - 3.0)
+}
+
+/// `stat` over each trailing `window` of `xs`, one value per element;
+/// `None` until a full window is available.
+pub fn rolling(xs: &[f64], window: usize, stat: impl Fn(&[f64]) -> Option<f64>) -> Vec<Option<f64>> {
+    (0..xs.len())
+        .map(|i| {
+            if window == 0 || i + 1 < window {
+                None
+            } else {
+                stat(&xs[i + 1 - window..=i])
+            }
+        })
+        .collect()
+}
+
+/// Pearson correlation of two equally long series; `None` when either is flat.
+pub fn correlation(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let (sx, sy) = (std_dev(xs)?, std_dev(ys)?);
+    if sx == 0.0 || sy == 0.0 {
+        return None;
+    }
+    Some((covariance(xs, ys)? / (sx * sy)).clamp(-1.0, 1.0))
+}
+
+/// Sensitivity of `asset` to `benchmark` returns over the same periods;
+/// `None` when the benchmark does not move.
+pub fn beta(asset: &[f64], benchmark: &[f64]) -> Option<f64> {
+    let var = covariance(benchmark, benchmark)?;
+    if var <= 0.0 {
+        return None;
+    }
+    Some(covariance(asset, benchmark)? / var)
+}
+
+/// One fall from a peak until the series regains it, by index.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DrawdownSpan {
+    pub peak: usize,
+    pub trough: usize,
+    /// First index back at or above the peak; `None` while still under water.
+    pub recovery: Option<usize>,
+    /// Fall from peak to trough, as a negative fraction.
+    pub depth: f64,
+}
+
+/// The fall from the running peak at each of `values`, as non-positive
+/// fractions (the underwater curve), and every drawdown in order.
+pub fn drawdowns(values: &[f64]) -> (Vec<f64>, Vec<DrawdownSpan>) {
+    let mut underwater = Vec::with_capacity(values.len());
+    let mut spans = Vec::new();
+    let mut current: Option<DrawdownSpan> = None;
+    let (mut peak, mut peak_index) = (f64::MIN, 0);
+    for (i, &value) in values.iter().enumerate() {
+        if value >= peak {
+            if let Some(mut span) = current.take() {
+                span.recovery = Some(i);
+                spans.push(span);
+            }
+            (peak, peak_index) = (value, i);
+            underwater.push(0.0);
+            continue;
+        }
+        let drawdown = if peak > 0.0 { value / peak - 1.0 } else { 0.0 };
+        underwater.push(drawdown);
+        if drawdown < 0.0 {
+            let span = current.get_or_insert(DrawdownSpan {
+                peak: peak_index,
+                trough: i,
+                recovery: None,
+                depth: drawdown,
+            });
+            if drawdown < span.depth {
+                span.depth = drawdown;
+                span.trough = i;
+            }
+        }
+    }
+    spans.extend(current);
+    (underwater, spans)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn basic_statistics() {
+        let returns = simple_returns(&[100.0, 110.0, 99.0, 0.0, 5.0]);
+        assert_eq!(returns.len(), 4);
+        assert!((returns[0] - 0.1).abs() < 1e-12);
+        assert!((returns[1] + 0.1).abs() < 1e-12);
+        assert_eq!(returns[3], 0.0);
+
+        assert_eq!(mean(&[]), None);
+        assert_eq!(std_dev(&[1.0]), None);
+        assert!((std_dev(&[1.0, 2.0, 3.0, 4.0]).unwrap() - 1.290_994_448_735_805_6).abs() < 1e-12);
+        let vol = annualized_volatility(&[0.01, -0.01], TRADING_DAYS).unwrap();
+        assert!((vol - 0.01 * 2f64.sqrt() * TRADING_DAYS.sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn shape_and_rolling_statistics() {
+        let symmetric = [-0.02, -0.01, 0.0, 0.01, 0.02];
+        assert!(skewness(&symmetric).unwrap().abs() < 1e-12);
+        // Uniform-like spread has thinner tails than a normal distribution
+        assert!(excess_kurtosis(&symmetric).unwrap() < 0.0);
+        let right_tail = [0.0, 0.0, 0.0, 0.0, 0.1];
+        assert!(skewness(&right_tail).unwrap() > 0.0);
+        assert!(excess_kurtosis(&right_tail).unwrap() > 0.0);
+        assert_eq!(skewness(&[0.01, 0.01, 0.01]), None);
+
+        assert_eq!(sharpe_ratio(&[0.01; 5], 0.0, TRADING_DAYS), None);
+        let sharpe = sharpe_ratio(&[0.01, 0.03], 0.0, TRADING_DAYS).unwrap();
+        assert!((sharpe - 0.02 / 0.0002f64.sqrt() * TRADING_DAYS.sqrt()).abs() < 1e-9);
+
+        let sums = rolling(&[1.0, 2.0, 3.0, 4.0], 2, |w| Some(w.iter().sum()));
+        assert_eq!(sums, vec![None, Some(3.0), Some(5.0), Some(7.0)]);
+        assert!(rolling(&[1.0], 0, mean).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn var_and_beta() {
+        let returns: Vec<f64> = (1..=100).map(|i| (i as f64 - 50.0) / 1000.0).collect();
+        // Five of the 100 returns are worse
+        assert!((historical_var(&returns, 0.95).unwrap() - 0.044).abs() < 1e-12);
+        assert_eq!(historical_var(&[0.01, 0.02], 0.95), Some(0.0));
+        assert_eq!(historical_var(&[], 0.95), None);
+
+        let benchmark = [0.01, -0.02, 0.015, 0.0];
+        let levered: Vec<f64> = benchmark.iter().map(|r| 2.0 * r).collect();
+        assert!((beta(&levered, &benchmark).unwrap() - 2.0).abs() < 1e-12);
+        assert_eq!(beta(&levered, &[0.0; 4]), None);
+
+        let inverse: Vec<f64> = benchmark.iter().map(|r| 0.5 - r).collect();
+        assert!((correlation(&levered, &benchmark).unwrap() - 1.0).abs() < 1e-12);
+        assert!((correlation(&inverse, &benchmark).unwrap() + 1.0).abs() < 1e-12);
+        assert_eq!(correlation(&benchmark, &[0.0; 4]), None);
+    }
+
+    #[test]
+    fn drawdowns_track_recovery() {
+        let (underwater, spans) = drawdowns(&[100.0, 110.0, 99.0, 105.0, 111.0, 100.0, 90.0]);
+        assert_eq!(underwater.len(), 7);
+        assert_eq!(underwater[1], 0.0);
+        assert!((underwater[2] + 0.1).abs() < 1e-12);
+        assert_eq!(underwater[4], 0.0);
+        assert_eq!(spans.len(), 2);
+        assert_eq!((spans[0].peak, spans[0].trough, spans[0].recovery), (1, 2, Some(4)));
+        assert!((spans[0].depth + 0.1).abs() < 1e-12);
+        assert_eq!((spans[1].peak, spans[1].trough, spans[1].recovery), (4, 6, None));
+        assert!(drawdowns(&[]).1.is_empty());
+    }
+}
@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::anomalies::anomaly_notification_claim_db;
+use crate::commands::config::config_effective_db;
+use crate::time::now_millis;
+use crate::types::anomaly::{Anomaly, Severity};
+use crate::workspace::WorkspaceDb;
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+
+/// A daily window, in local `HH:MM`, during which notifications are held back.
+/// The window may wrap past midnight (e.g. `22:00`–`07:00`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub start: String,
+    pub end: String,
+    /// Local time's offset from UTC, e.g. `-300` for New York in winter.
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Still notify for critical anomalies during quiet hours.
+    #[serde(default = "default_allow_critical")]
+    pub allow_critical: bool,
+}
+
+fn default_allow_critical() -> bool {
+    true
+}
+
+/// The `notifications` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    /// Anomalies below this severity are not notified.
+    pub min_severity: Severity,
+    /// Source ids whose anomalies are never notified.
+    pub muted_sources: Vec<String>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_severity: Severity::High,
+            muted_sources: Vec::new(),
+            quiet_hours: None,
+        }
+    }
+}
+
+/// Parse the `notifications` section of the app config.
+pub fn notification_config(app_config: &Value) -> NotificationConfig {
+    app_config
+        .get("notifications")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Minutes past midnight for an `HH:MM` time.
+pub fn parse_clock(value: &str) -> Option<i64> {
+    let (hour, minute) = value.split_once(':')?;
+    let (hour, minute): (i64, i64) = (hour.parse().ok()?, minute.parse().ok()?);
+    ((0..24).contains(&hour) && (0..60).contains(&minute)).then_some(hour * 60 + minute)
+}
+
+impl QuietHours {
+    /// Whether `now_ms` (epoch millis) falls inside the window. An unparseable
+    /// window is never active.
+    pub fn contains(&self, now_ms: u64) -> bool {
+        let (Some(start), Some(end)) = (parse_clock(&self.start), parse_clock(&self.end)) else {
+            return false;
+        };
+        let minute = ((now_ms / 60_000) as i64 + i64::from(self.utc_offset_minutes)).rem_euclid(MINUTES_PER_DAY);
+        if start <= end {
+            start <= minute && minute < end
+        } else {
+            minute >= start || minute < end
+        }
+    }
+}
+
+/// Why an anomaly was not notified, or `None` if the policy lets it through.
+pub fn suppression_reason(config: &NotificationConfig, anomaly: &Anomaly, now_ms: u64) -> Option<&'static str> {
+    if !config.enabled {
+        return Some("notifications disabled");
+    }
+    if anomaly.severity < config.min_severity {
+        return Some("below minimum severity");
+    }
+    if config.muted_sources.iter().any(|s| s == &anomaly.source) {
+        return Some("source muted");
+    }
+    let quiet = config
+        .quiet_hours
+        .as_ref()
+        .filter(|q| q.contains(now_ms))
+        .is_some_and(|q| !(q.allow_critical && anomaly.severity == Severity::Critical));
+    quiet.then_some("quiet hours")
+}
+
+fn title(anomaly: &Anomaly) -> String {
+    let severity = serde_json::to_value(anomaly.severity)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_uppercase))
+        .unwrap_or_default();
+    match &anomaly.symbol {
+        Some(symbol) => format!("[{}] {} anomaly", severity, symbol),
+        None => format!("[{}] Anomaly from {}", severity, anomaly.source),
+    }
+}
+
+/// Show an OS notification for an `anomaly:detected` payload if the
+/// notification policy allows it and it has not been delivered before.
+pub fn notify_anomaly<R: Runtime>(app: &AppHandle<R>, payload: &Value) {
+    let anomaly: Anomaly = match serde_json::from_value(payload.clone()) {
+        Ok(a) => a,
+        Err(e) => {
+            tracing::warn!(error = %e, "Invalid anomaly payload");
+            return;
+        }
+    };
+    let Some(workspace) = app.try_state::<WorkspaceDb>() else {
+        return;
+    };
+    let pool = workspace.pool();
+    let config = match config_effective_db(&pool) {
+        Ok(c) => notification_config(&c),
+        Err(e) => {
+            tracing::warn!(error = %e, "Could not read notification policy");
+            return;
+        }
+    };
+    let now_ms = now_millis();
+    if let Some(reason) = suppression_reason(&config, &anomaly, now_ms) {
+        tracing::debug!(anomaly_id = anomaly.id, reason, "Anomaly notification suppressed");
+        return;
+    }
+    match anomaly_notification_claim_db(&pool, &anomaly.id, now_ms) {
+        Ok(true) => {}
+        Ok(false) => return,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to record anomaly notification");
+            return;
+        }
+    }
+    if let Err(e) = app
+        .notification()
+        .builder()
+        .title(title(&anomaly))
+        .body(&anomaly.description)
+        .show()
+    {
+        tracing::warn!(error = %e, "Failed to show anomaly notification");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::anomaly::AnomalyStatus;
+
+    /// 2024-06-10 23:30 UTC.
+    const LATE_EVENING: u64 = 1_718_062_200_000;
+
+    fn anomaly(severity: Severity) -> Anomaly {
+        Anomaly {
+            id: "anom-1".to_string(),
+            severity,
+            source: "yahoo".to_string(),
+            symbol: Some("SPY".to_string()),
+            timestamp: 0,
+            description: "Volume spike".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.9,
+            session_id: "s1".to_string(),
+            status: AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        }
+    }
+
+    fn quiet(start: &str, end: &str, utc_offset_minutes: i32) -> QuietHours {
+        QuietHours {
+            start: start.to_string(),
+            end: end.to_string(),
+            utc_offset_minutes,
+            allow_critical: true,
+        }
+    }
+
+    #[test]
+    fn quiet_hours_wrap_midnight_and_apply_offset() {
+        assert!(quiet("22:00", "07:00", 0).contains(LATE_EVENING));
+        assert!(!quiet("22:00", "07:00", -300).contains(LATE_EVENING));
+        assert!(quiet("18:00", "19:00", -300).contains(LATE_EVENING));
+        assert!(!quiet("25:00", "07:00", 0).contains(LATE_EVENING));
+        assert_eq!(parse_clock("07:05"), Some(425));
+        assert_eq!(parse_clock("7"), None);
+    }
+
+    #[test]
+    fn policy_filters_severity_sources_and_quiet_hours() {
+        let mut config = NotificationConfig::default();
+        assert_eq!(suppression_reason(&config, &anomaly(Severity::High), LATE_EVENING), None);
+        assert_eq!(
+            suppression_reason(&config, &anomaly(Severity::Medium), LATE_EVENING),
+            Some("below minimum severity")
+        );
+
+        config.muted_sources = vec!["yahoo".to_string()];
+        assert_eq!(suppression_reason(&config, &anomaly(Severity::High), LATE_EVENING), Some("source muted"));
+
+        config.muted_sources.clear();
+        config.quiet_hours = Some(quiet("22:00", "07:00", 0));
+        assert_eq!(suppression_reason(&config, &anomaly(Severity::High), LATE_EVENING), Some("quiet hours"));
+        assert_eq!(suppression_reason(&config, &anomaly(Severity::Critical), LATE_EVENING), None);
+
+        config.enabled = false;
+        assert!(suppression_reason(&config, &anomaly(Severity::Critical), LATE_EVENING).is_some());
+    }
+
+    #[test]
+    fn config_defaults_to_high_severity() {
+        assert_eq!(notification_config(&serde_json::json!({})), NotificationConfig::default());
+        let config = notification_config(&serde_json::json!({
+            "notifications": {"minSeverity": "low", "quietHours": {"start": "22:00", "end": "06:30"}}
+        }));
+        assert_eq!(config.min_severity, Severity::Low);
+        assert!(config.quiet_hours.unwrap().allow_critical);
+    }
+}
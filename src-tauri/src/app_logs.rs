@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use serde::Serialize;
+use tracing_subscriber::reload;
+use tracing_subscriber::EnvFilter;
+
+use crate::agent_logs::{log_paths, RotatingLogWriter, DEFAULT_MAX_BYTES, DEFAULT_MAX_FILES};
+
+/// Active app log file; rotated files get a numeric suffix (`app.log.1`, ...).
+const LOG_FILE: &str = "app.log";
+/// Filter used when `RUST_LOG` is unset.
+pub const DEFAULT_FILTER: &str = "finwatch=info";
+
+/// Directory holding the app's own JSON logs: `~/.finwatch/logs/app/`.
+pub fn app_logs_dir() -> PathBuf {
+    crate::db::finwatch_root_dir().join("logs").join("app")
+}
+
+/// A `tracing` writer appending each formatted event to the rotating `app.log`.
+#[derive(Clone)]
+pub struct AppLogWriter {
+    inner: Arc<Mutex<RotatingLogWriter>>,
+}
+
+impl AppLogWriter {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(RotatingLogWriter::named(
+                dir,
+                LOG_FILE,
+                DEFAULT_MAX_BYTES,
+                DEFAULT_MAX_FILES,
+            ))),
+        }
+    }
+}
+
+impl std::io::Write for AppLogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        let mut writer = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        for line in text.lines().filter(|l| !l.is_empty()) {
+            writer.write_raw(line).map_err(std::io::Error::other)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for AppLogWriter {
+    type Writer = AppLogWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+struct LevelControl {
+    handle: reload::Handle<EnvFilter, tracing_subscriber::Registry>,
+    current: Mutex<String>,
+}
+
+static LEVEL_CONTROL: OnceLock<LevelControl> = OnceLock::new();
+
+/// Remember the reload handle of the installed filter so `set_filter` can
+/// change it at runtime.
+pub fn install_level_control(handle: reload::Handle<EnvFilter, tracing_subscriber::Registry>, current: String) {
+    let _ = LEVEL_CONTROL.set(LevelControl {
+        handle,
+        current: Mutex::new(current),
+    });
+}
+
+/// The filter directives in effect, e.g. `finwatch=debug`.
+pub fn current_filter() -> Option<String> {
+    LEVEL_CONTROL
+        .get()
+        .map(|c| c.current.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// Replace the log filter with `directives` (`RUST_LOG` syntax).
+pub fn set_filter(directives: &str) -> Result<String, String> {
+    let directives = directives.trim();
+    let filter = EnvFilter::try_new(directives).map_err(|e| format!("Invalid log filter '{}': {}", directives, e))?;
+    let control = LEVEL_CONTROL.get().ok_or("Logging is not initialized")?;
+    control
+        .handle
+        .reload(filter)
+        .map_err(|e| format!("Failed to change log filter: {}", e))?;
+    *control.current.lock().unwrap_or_else(|e| e.into_inner()) = directives.to_string();
+    tracing::info!(filter = directives, "Log filter changed");
+    Ok(directives.to_string())
+}
+
+/// A line from the app log: the JSON record when it parses, the raw text otherwise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum AppLogLine {
+    Record(serde_json::Value),
+    Text(String),
+}
+
+/// Read the last `lines` lines across the active and rotated app log files.
+pub fn tail(dir: &Path, lines: usize) -> Result<Vec<AppLogLine>, String> {
+    let mut result = std::collections::VecDeque::with_capacity(lines);
+    for path in log_paths(dir, LOG_FILE) {
+        let file = match File::open(&path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read app log: {}", e)),
+        };
+        for raw in BufReader::new(file).lines() {
+            let raw = raw.map_err(|e| format!("Failed to read app log: {}", e))?;
+            if lines == 0 {
+                continue;
+            }
+            if result.len() == lines {
+                result.pop_front();
+            }
+            result.push_back(raw);
+        }
+    }
+    Ok(result
+        .into_iter()
+        .map(|raw| match serde_json::from_str::<serde_json::Value>(&raw) {
+            Ok(record) if record.is_object() => AppLogLine::Record(record),
+            _ => AppLogLine::Text(raw),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn writer_splits_lines_and_tail_parses_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = AppLogWriter::new(dir.path().to_path_buf());
+        writer.write_all(b"{\"level\":\"INFO\",\"fields\":{\"message\":\"one\"}}\n").unwrap();
+        writer.write_all(b"plain text\n{\"level\":\"WARN\"}\n").unwrap();
+
+        let lines = tail(dir.path(), 10).unwrap();
+        assert_eq!(lines.len(), 3);
+        assert!(matches!(&lines[0], AppLogLine::Record(r) if r["fields"]["message"] == "one"));
+        assert_eq!(lines[1], AppLogLine::Text("plain text".to_string()));
+
+        let last = tail(dir.path(), 1).unwrap();
+        assert!(matches!(&last[0], AppLogLine::Record(r) if r["level"] == "WARN"));
+        assert!(tail(&dir.path().join("missing"), 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_filters_before_touching_the_subscriber() {
+        let err = set_filter("finwatch=loud").unwrap_err();
+        assert!(err.contains("Invalid log filter"), "{err}");
+    }
+}
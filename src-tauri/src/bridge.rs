@@ -1,17 +1,43 @@
 use std::io::{BufRead, BufReader, Write};
 use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use serde_json::Value;
-use tauri::{AppHandle, Runtime};
-use tracing::{debug, error, trace, warn};
-
+use tauri::{AppHandle, Manager, Runtime};
+use tracing::{debug, error, info, trace, warn};
+
+use crate::activity_recorder::ActivityRecorder;
+use crate::alerts;
+use crate::anomaly_notifier;
+use crate::agent_logs::{self, RotatingLogWriter};
+use crate::bridge_error::{self, BridgeError};
+use crate::bridge_journal::{JournalSnapshot, RequestJournal};
+use crate::bridge_metrics::{BridgeMetrics, BridgeMetricsSnapshot};
 use crate::bridge_pending::PendingRequestTracker;
+use crate::commands::activity::ActivityConfig;
+use crate::commands::backtest::backtest_record_complete_db;
+use crate::commands::config::config_effective_db;
+use crate::commands::providers::providers_health_set_db;
+use crate::commands::ticks::TickRecordingConfig;
+use crate::commands::usage::{usage_config, usage_record_db};
 use crate::events::{emit_event, event_names};
-use crate::jsonrpc::{JsonRpcRequest, JsonRpcResponse};
-use crate::sidecar::{SidecarState, SidecarSupervisor};
+use crate::jsonrpc::{
+    self, Framing, HelloResponse, JsonRpcNotification, JsonRpcRequest, JsonRpcResponse,
+};
+use crate::sidecar::{
+    RestartPolicy, SidecarCommand, SidecarLaunchConfig, SidecarState, SidecarSupervisor,
+};
+use crate::sidecar_resources::{self, ResourceLimits, ResourceMonitor, ResourceSample};
+use crate::source_quarantine::{QuarantineConfig, SourceQuarantine};
+use crate::tick_recorder::TickRecorder;
+use crate::time::now_millis;
+use crate::types::agent::{AgentHealth, AgentUnhealthy, WatchdogState};
+use crate::types::data::{DataTick, SourceHealth};
+use crate::types::provider::{LlmUsage, ProviderHealth};
+use crate::workspace::WorkspaceDb;
 
 /// Default timeout for JSON-RPC requests (31 seconds).
 const REQUEST_TIMEOUT: Duration = Duration::from_secs(31);
@@ -22,12 +48,18 @@ const WATCHDOG_POLL_INTERVAL: Duration = Duration::from_secs(10);
 /// Health check ping interval.
 const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
 /// Maximum silence before considering the agent unhealthy (3 missed pongs).
-const MAX_SILENCE: Duration = Duration::from_secs(90);
+pub const MAX_SILENCE: Duration = Duration::from_secs(90);
+/// How long to wait for the `agent:hello` handshake after spawning.
+const HELLO_TIMEOUT: Duration = Duration::from_secs(10);
+/// How long a journaled request waits for the watchdog to bring up a new agent.
+const RESTART_WAIT: Duration = Duration::from_secs(45);
+/// Failure reason given to pending requests when the child exits unexpectedly.
+const CRASH_REASON: &str = "Sidecar process crashed";
 
 /// Spawn the child OS process for the agent sidecar.
 /// Returns (child, stdin, stdout, stderr).
 fn spawn_child_process(
-    agent_script: &str,
+    command: &SidecarCommand,
 ) -> Result<
     (
         Child,
@@ -37,13 +69,14 @@ fn spawn_child_process(
     ),
     String,
 > {
-    let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
-    let project_root = manifest_dir.parent().unwrap_or(manifest_dir);
-    let tsx_bin = project_root.join("node_modules/.bin/tsx");
+    let mut cmd = Command::new(&command.program);
+    cmd.args(&command.args);
+    cmd.envs(command.env.iter().map(|(k, v)| (k, v)));
+    if let Some(ref cwd) = command.cwd {
+        cmd.current_dir(cwd);
+    }
 
-    let mut child = Command::new(tsx_bin)
-        .current_dir(project_root)
-        .arg(agent_script)
+    let mut child = cmd
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -64,29 +97,43 @@ fn spawn_reader_threads<R: Runtime + 'static>(
     stderr: std::process::ChildStderr,
     app: AppHandle<R>,
     pending: Arc<PendingRequestTracker>,
+    ticks: Arc<TickRecorder>,
+    activity: Arc<ActivityRecorder>,
+    quarantine: Arc<SourceQuarantine>,
 ) {
-    // Stderr reader
+    // Stderr reader: mirror to tracing and persist to rotating log files
     thread::spawn(move || {
         let reader = BufReader::new(stderr);
+        let mut log_writer = RotatingLogWriter::new(
+            agent_logs::agent_logs_dir(),
+            agent_logs::DEFAULT_MAX_BYTES,
+            agent_logs::DEFAULT_MAX_FILES,
+        );
+        let mut write_failed = false;
         for line in reader.lines() {
             match line {
-                Ok(text) => debug!(target: "agent_stderr", "{}", text),
+                Ok(text) => {
+                    debug!(target: "agent_stderr", "{}", text);
+                    if let Err(e) = log_writer.write_line(&text) {
+                        if !write_failed {
+                            warn!(error = %e, "Failed to persist agent stderr");
+                            write_failed = true;
+                        }
+                    }
+                }
                 Err(_) => break,
             }
         }
     });
 
-    // Stdout reader
+    // Stdout reader: framing is detected per message
     thread::spawn(move || {
-        let reader = BufReader::new(stdout);
+        let mut reader = BufReader::new(stdout);
         debug!("Stdout reader thread started");
-        for line in reader.lines() {
-            match line {
-                Ok(text) => {
-                    let text = text.trim().to_string();
-                    if text.is_empty() {
-                        continue;
-                    }
+        loop {
+            match jsonrpc::read_message(&mut reader) {
+                Ok(None) => break,
+                Ok(Some(text)) => {
                     trace!(raw = &text[..text.len().min(200)], "Agent stdout");
                     if let Ok(parsed) = serde_json::from_str::<Value>(&text) {
                         if let Some(id) = parsed.get("id").and_then(|v| v.as_u64()) {
@@ -100,12 +147,23 @@ fn spawn_reader_threads<R: Runtime + 'static>(
                                     warn!(id, error = %e, "Failed to parse JSON-RPC response");
                                 }
                             }
-                        } else if let Some(method) =
-                            parsed.get("method").and_then(|m| m.as_str())
-                        {
-                            debug!(method, "Routing notification");
-                            let params = parsed.get("params").cloned();
-                            route_notification(&app, method, params);
+                        } else if parsed.get("method").is_some() {
+                            match serde_json::from_value::<JsonRpcNotification>(parsed) {
+                                Ok(notification) => {
+                                    debug!(method = notification.method, "Routing notification");
+                                    route_notification(
+                                        &app,
+                                        &ticks,
+                                        &activity,
+                                        &quarantine,
+                                        &notification.method,
+                                        notification.params,
+                                    );
+                                }
+                                Err(e) => {
+                                    warn!(error = %e, "Failed to parse JSON-RPC notification");
+                                }
+                            }
                         }
                     } else {
                         warn!(raw = &text[..text.len().min(100)], "Non-JSON stdout from agent");
@@ -121,6 +179,152 @@ fn spawn_reader_threads<R: Runtime + 'static>(
     });
 }
 
+/// Map the supervisor's lifecycle state to what the watchdog is doing.
+fn watchdog_state(supervisor: &SidecarSupervisor) -> WatchdogState {
+    match supervisor.state() {
+        SidecarState::Stopped => WatchdogState::Stopped,
+        SidecarState::Starting => WatchdogState::Starting,
+        SidecarState::Running => WatchdogState::Watching,
+        SidecarState::Crashed { .. } if supervisor.should_restart() => WatchdogState::Restarting,
+        SidecarState::Crashed { .. } => WatchdogState::GaveUp,
+    }
+}
+
+/// `agent:unhealthy` reporting for the heartbeat thread.
+fn report_unhealthy<R: Runtime>(app: AppHandle<R>) -> impl Fn(AgentUnhealthy) + Send + 'static {
+    move |unhealthy| {
+        let _ = emit_event(&app, event_names::AGENT_UNHEALTHY, unhealthy);
+    }
+}
+
+/// What the heartbeat and request-timeout threads share with the bridge.
+/// `spawn` and the watchdog's respawn both start them through [`Monitors::start`].
+#[derive(Clone)]
+struct Monitors {
+    pending: Arc<PendingRequestTracker>,
+    stdin: Arc<Mutex<Option<std::process::ChildStdin>>>,
+    framing: Arc<Mutex<Framing>>,
+    state: Arc<Mutex<SidecarState>>,
+    last_pong: Arc<Mutex<Option<Instant>>>,
+    missed_pings: Arc<AtomicU64>,
+    epoch: Arc<AtomicU64>,
+}
+
+impl Monitors {
+    /// Reset the heartbeat for a freshly started process and start its
+    /// threads. Threads left from an earlier process exit at their next tick.
+    fn start(&self, on_unhealthy: impl Fn(AgentUnhealthy) + Send + 'static) {
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst) + 1;
+        // Give the new agent time to start before it counts as silent
+        *self.last_pong.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        self.missed_pings.store(0, Ordering::SeqCst);
+
+        let timeouts = self.clone();
+        thread::spawn(move || {
+            debug!("Timeout checker thread started");
+            loop {
+                thread::sleep(TIMEOUT_CHECK_INTERVAL);
+                if !timeouts.is_current(epoch) {
+                    debug!("Timeout checker exiting (sidecar not running)");
+                    break;
+                }
+                timeouts.pending.check_timeouts();
+            }
+        });
+
+        let health = self.clone();
+        thread::spawn(move || health.check_health(epoch, on_unhealthy));
+    }
+
+    /// Whether the threads of `epoch` still watch a running process.
+    fn is_current(&self, epoch: u64) -> bool {
+        self.epoch.load(Ordering::SeqCst) == epoch
+            && *self.state.lock().unwrap_or_else(|e| e.into_inner()) == SidecarState::Running
+    }
+
+    /// Ping the agent every `HEALTH_CHECK_INTERVAL` until the process of `epoch` is gone.
+    fn check_health(&self, epoch: u64, on_unhealthy: impl Fn(AgentUnhealthy)) {
+        debug!("Health checker thread started");
+        // `agent:unhealthy` is sent once per stretch of silence
+        let mut reported_unhealthy = false;
+        loop {
+            thread::sleep(HEALTH_CHECK_INTERVAL);
+            if !self.is_current(epoch) {
+                debug!("Health checker exiting (sidecar not running)");
+                break;
+            }
+
+            // Send a ping request
+            let ping_req = JsonRpcRequest::new("ping", None);
+            let ping_id = ping_req.id;
+            let rx = self.pending.register(ping_id, Duration::from_secs(10));
+
+            let send_ok = {
+                let mut guard = self.stdin.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(ref mut stdin) = *guard {
+                    let framing = *self.framing.lock().unwrap_or_else(|e| e.into_inner());
+                    if let Ok(line) = ping_req.to_frame(framing) {
+                        stdin.write_all(line.as_bytes()).is_ok() && stdin.flush().is_ok()
+                    } else {
+                        false
+                    }
+                } else {
+                    false
+                }
+            };
+
+            let ponged = send_ok
+                && match rx.recv_timeout(Duration::from_secs(10)) {
+                    Ok(Ok(_)) => {
+                        *self
+                            .last_pong
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+                        trace!("Pong received");
+                        true
+                    }
+                    Ok(Err(e)) => {
+                        warn!(error = %e, "Ping returned error");
+                        false
+                    }
+                    Err(_) => {
+                        warn!("Ping timed out");
+                        false
+                    }
+                };
+            if ponged {
+                self.missed_pings.store(0, Ordering::SeqCst);
+                reported_unhealthy = false;
+            } else {
+                self.missed_pings.fetch_add(1, Ordering::SeqCst);
+            }
+
+            // Check if we've exceeded max silence
+            let elapsed = self
+                .last_pong
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .map(|t| t.elapsed())
+                .unwrap_or(Duration::ZERO);
+            if elapsed > MAX_SILENCE {
+                error!(
+                    silence_secs = elapsed.as_secs(),
+                    "Agent unresponsive, marking unhealthy"
+                );
+                if !reported_unhealthy {
+                    reported_unhealthy = true;
+                    on_unhealthy(AgentUnhealthy {
+                        silence_ms: elapsed.as_millis() as u64,
+                        missed_pings: self.missed_pings.load(Ordering::SeqCst),
+                    });
+                }
+                // Don't break -- let the watchdog handle crash detection
+            }
+        }
+        debug!("Health checker thread exiting");
+    }
+}
+
 /// Manages the Node.js agent sidecar process and JSON-RPC communication.
 pub struct SidecarBridge {
     supervisor: SidecarSupervisor,
@@ -129,20 +333,152 @@ pub struct SidecarBridge {
     pending: Arc<PendingRequestTracker>,
     watchdog_shutdown: Mutex<Option<std::sync::mpsc::Sender<()>>>,
     last_pong: Arc<Mutex<Option<Instant>>>,
+    /// Consecutive pings without a pong; reset by the next pong.
+    missed_pings: Arc<AtomicU64>,
+    /// Bumped whenever the heartbeat and timeout threads are restarted.
+    monitor_epoch: Arc<AtomicU64>,
+    /// Watchdog respawns since the last `spawn`.
+    restarts: Arc<AtomicU64>,
+    metrics: Arc<BridgeMetrics>,
+    agent_info: Arc<Mutex<Option<HelloResponse>>>,
+    /// Framing for writes to the agent; newline until the handshake negotiates otherwise.
+    framing: Arc<Mutex<Framing>>,
+    /// Incremented every time a new child process is installed.
+    generation: Arc<AtomicU64>,
+    journal: RequestJournal,
+    resources: Arc<Mutex<Option<ResourceSample>>>,
+    resource_limits: Arc<Mutex<ResourceLimits>>,
+    ticks: Arc<TickRecorder>,
+    activity: Arc<ActivityRecorder>,
+    quarantine: Arc<SourceQuarantine>,
+    /// Command the watchdog respawns the agent with, including any secret env vars.
+    command: Arc<Mutex<Option<SidecarCommand>>>,
+    /// Trading mode whose broker credentials the running agent was given.
+    trading_mode: Mutex<Option<String>>,
 }
 
 impl SidecarBridge {
     pub fn new() -> Self {
         Self {
-            supervisor: SidecarSupervisor::new(3),
+            supervisor: SidecarSupervisor::with_policy(RestartPolicy::default()),
             child: Arc::new(Mutex::new(None)),
             stdin_writer: Arc::new(Mutex::new(None)),
             pending: Arc::new(PendingRequestTracker::new()),
             watchdog_shutdown: Mutex::new(None),
             last_pong: Arc::new(Mutex::new(None)),
+            missed_pings: Arc::new(AtomicU64::new(0)),
+            monitor_epoch: Arc::new(AtomicU64::new(0)),
+            restarts: Arc::new(AtomicU64::new(0)),
+            metrics: Arc::new(BridgeMetrics::new()),
+            agent_info: Arc::new(Mutex::new(None)),
+            framing: Arc::new(Mutex::new(Framing::Newline)),
+            generation: Arc::new(AtomicU64::new(0)),
+            journal: RequestJournal::new(),
+            resources: Arc::new(Mutex::new(None)),
+            resource_limits: Arc::new(Mutex::new(ResourceLimits::default())),
+            ticks: Arc::new(TickRecorder::new()),
+            activity: Arc::new(ActivityRecorder::new()),
+            quarantine: Arc::new(SourceQuarantine::new()),
+            command: Arc::new(Mutex::new(None)),
+            trading_mode: Mutex::new(None),
+        }
+    }
+
+    /// Drop `names` from the environment a crashed agent would be respawned
+    /// with, so revoked secrets are not handed to the next process.
+    pub fn forget_env(&self, names: &[&str]) {
+        if let Some(command) = self.command.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            command.env.retain(|(k, _)| !names.contains(&k.as_str()));
         }
     }
 
+    /// Record the trading mode whose credentials the agent was handed.
+    pub fn set_trading_mode(&self, mode: &str) {
+        *self.trading_mode.lock().unwrap_or_else(|e| e.into_inner()) = Some(mode.to_string());
+    }
+
+    /// Trading mode of the credentials the running agent holds, if any.
+    pub fn trading_mode(&self) -> Option<String> {
+        self.trading_mode
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Whether a running agent was started for a trading mode other than `mode`,
+    /// so the credentials in its spawn environment are not the ones `mode` needs.
+    pub fn running_for_other_mode(&self, mode: &str) -> bool {
+        self.is_running() && self.trading_mode().as_deref() != Some(mode)
+    }
+
+    /// Versions and methods reported by the agent during the last handshake.
+    pub fn agent_info(&self) -> Option<HelloResponse> {
+        self.agent_info
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Snapshot of per-method RPC counters and latency percentiles.
+    pub fn metrics_snapshot(&self) -> BridgeMetricsSnapshot {
+        self.metrics.snapshot(self.pending.len())
+    }
+
+    /// Clear all recorded RPC statistics.
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+
+    /// Apply the crash budget used by the watchdog.
+    pub fn set_restart_policy(&self, policy: RestartPolicy) {
+        self.supervisor.set_policy(policy);
+    }
+
+    /// Apply memory limits enforced by the watchdog.
+    pub fn set_resource_limits(&self, limits: ResourceLimits) {
+        *self.resource_limits.lock().unwrap_or_else(|e| e.into_inner()) = limits;
+    }
+
+    /// Apply the supervision settings from the `sidecar` config section.
+    pub fn apply_launch_config(&self, launch: &SidecarLaunchConfig) {
+        self.set_restart_policy(launch.restart_policy);
+        self.set_resource_limits(launch.resource_limits);
+        self.set_journaling(launch.journal_requests, launch.journal_methods.as_deref());
+    }
+
+    /// Configure which `data:tick` notifications are persisted.
+    pub fn set_tick_recording(&self, config: TickRecordingConfig) {
+        self.ticks.set_config(config);
+    }
+
+    /// Configure how long persisted `agent:activity` entries are kept.
+    pub fn set_activity_retention(&self, config: ActivityConfig) {
+        self.activity.set_config(config);
+    }
+
+    /// Configure when flapping sources are quarantined.
+    pub fn set_quarantine_config(&self, config: QuarantineConfig) {
+        self.quarantine.set_config(config);
+    }
+
+    /// Latest CPU/RSS sample taken by the watchdog, if the agent is running.
+    pub fn resource_usage(&self) -> Option<ResourceSample> {
+        *self.resources.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Enable or disable re-sending idempotent requests after a sidecar restart.
+    pub fn set_journaling(&self, enabled: bool, methods: Option<&[String]>) {
+        self.journal.set_enabled(enabled);
+        if let Some(methods) = methods {
+            self.journal.set_methods(methods);
+        }
+    }
+
+    /// Current journaling mode and replay counters.
+    pub fn journal_snapshot(&self) -> JournalSnapshot {
+        self.journal.snapshot()
+    }
+
     pub fn is_running(&self) -> bool {
         self.supervisor.state() == SidecarState::Running
     }
@@ -150,6 +486,38 @@ impl SidecarBridge {
     /// Record a successful pong response.
     pub fn record_pong(&self) {
         *self.last_pong.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        self.missed_pings.store(0, Ordering::SeqCst);
+    }
+
+    /// Heartbeat, restart, and watchdog details for `agent_status`.
+    pub fn health(&self) -> AgentHealth {
+        let last_pong_age_ms = if self.is_running() {
+            self.last_pong
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .map(|t| t.elapsed().as_millis() as u64)
+        } else {
+            None
+        };
+        AgentHealth {
+            last_pong_age_ms,
+            missed_pings: self.missed_pings.load(Ordering::SeqCst),
+            restart_count: self.restarts.load(Ordering::SeqCst),
+            watchdog: watchdog_state(&self.supervisor),
+        }
+    }
+
+    /// Handles for the heartbeat and timeout threads of the current process.
+    fn monitors(&self) -> Monitors {
+        Monitors {
+            pending: Arc::clone(&self.pending),
+            stdin: Arc::clone(&self.stdin_writer),
+            framing: Arc::clone(&self.framing),
+            state: self.supervisor.state_arc(),
+            last_pong: Arc::clone(&self.last_pong),
+            missed_pings: Arc::clone(&self.missed_pings),
+            epoch: Arc::clone(&self.monitor_epoch),
+        }
     }
 
     /// Check if the agent has responded within the given silence window.
@@ -163,11 +531,11 @@ impl SidecarBridge {
         }
     }
 
-    /// Spawn the Node.js agent sidecar and start reading its stdout.
+    /// Spawn the agent sidecar and start reading its stdout.
     pub fn spawn<R: Runtime + 'static>(
         &self,
         app: AppHandle<R>,
-        agent_script: &str,
+        command: &SidecarCommand,
     ) -> Result<(), String> {
         if self.is_running() {
             return Err("Sidecar already running".to_string());
@@ -175,7 +543,13 @@ impl SidecarBridge {
 
         self.supervisor.set_state(SidecarState::Starting);
 
-        let (child, stdin, stdout, stderr) = spawn_child_process(agent_script)?;
+        let (child, stdin, stdout, stderr) = match spawn_child_process(command) {
+            Ok(parts) => parts,
+            Err(e) => {
+                self.supervisor.record_stopped();
+                return Err(e);
+            }
+        };
 
         *self
             .stdin_writer
@@ -187,27 +561,20 @@ impl SidecarBridge {
             .map_err(|e| format!("Failed to acquire child lock: {}", e))? = Some(child);
 
         self.supervisor.record_started();
-
-        spawn_reader_threads(stdout, stderr, app.clone(), Arc::clone(&self.pending));
-
-        // Spawn timeout checker thread
-        let pending_for_timeout = Arc::clone(&self.pending);
-        let supervisor_for_timeout = self.supervisor.state_arc();
-        thread::spawn(move || {
-            debug!("Timeout checker thread started");
-            loop {
-                thread::sleep(TIMEOUT_CHECK_INTERVAL);
-                let state = supervisor_for_timeout
-                    .lock()
-                    .unwrap_or_else(|e| e.into_inner())
-                    .clone();
-                if state != SidecarState::Running {
-                    debug!("Timeout checker exiting (sidecar not running)");
-                    break;
-                }
-                pending_for_timeout.check_timeouts();
-            }
-        });
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        self.restarts.store(0, Ordering::SeqCst);
+        let monitors = self.monitors();
+        monitors.start(report_unhealthy(app.clone()));
+
+        spawn_reader_threads(
+            stdout,
+            stderr,
+            app.clone(),
+            Arc::clone(&self.pending),
+            Arc::clone(&self.ticks),
+            Arc::clone(&self.activity),
+            Arc::clone(&self.quarantine),
+        );
 
         // Spawn watchdog thread
         let (shutdown_tx, shutdown_rx) = std::sync::mpsc::channel::<()>();
@@ -219,12 +586,24 @@ impl SidecarBridge {
         let child_arc = Arc::clone(&self.child);
         let stdin_arc = Arc::clone(&self.stdin_writer);
         let pending_arc = Arc::clone(&self.pending);
-        let supervisor_arc = self.supervisor.state_arc();
-        let max_restarts = self.supervisor.max_restarts();
-        let script = agent_script.to_string();
+        let sup = self.supervisor.clone();
+        let framing_arc = Arc::clone(&self.framing);
+        let agent_info_arc = Arc::clone(&self.agent_info);
+        let generation_arc = Arc::clone(&self.generation);
+        let resources_arc = Arc::clone(&self.resources);
+        let limits_arc = Arc::clone(&self.resource_limits);
+        let ticks_arc = Arc::clone(&self.ticks);
+        let activity_arc = Arc::clone(&self.activity);
+        let quarantine_arc = Arc::clone(&self.quarantine);
+        *self.command.lock().unwrap_or_else(|e| e.into_inner()) = Some(command.clone());
+        let command_arc = Arc::clone(&self.command);
+        let restarts_arc = Arc::clone(&self.restarts);
+        let watchdog_app = app.clone();
+        let watchdog_monitors = monitors.clone();
 
         thread::spawn(move || {
             debug!("Watchdog thread started");
+            let mut monitor = ResourceMonitor::new();
             loop {
                 // Check for shutdown signal (non-blocking)
                 if shutdown_rx.try_recv().is_ok() {
@@ -242,32 +621,44 @@ impl SidecarBridge {
                             Ok(Some(status)) => {
                                 warn!(code = ?status.code(), "Sidecar process exited");
                                 *guard = None;
-                                true
+                                Some(status.code())
                             }
-                            Ok(None) => false, // Still running
+                            Ok(None) => None, // Still running
                             Err(e) => {
                                 error!(error = %e, "Failed to check child status");
-                                false
+                                None
                             }
                         }
                     } else {
                         // No child, but we may be in a restart cycle
-                        false
+                        None
                     }
                 };
 
-                if !exited {
+                let Some(exit_code) = exited else {
+                    sample_resources(
+                        &mut monitor,
+                        &child_arc,
+                        &resources_arc,
+                        &limits_arc,
+                        &watchdog_app,
+                    );
                     continue;
-                }
+                };
+                *resources_arc.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
                 // Child exited unexpectedly
-                pending_arc.fail_all("Sidecar process crashed");
+                pending_arc.fail_all(CRASH_REASON);
                 *stdin_arc.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                // A respawned agent starts un-negotiated
+                *framing_arc.lock().unwrap_or_else(|e| e.into_inner()) = Framing::Newline;
+                *agent_info_arc.lock().unwrap_or_else(|e| e.into_inner()) = None;
 
-                // Use a temporary supervisor to compute backoff/should_restart
-                let sup = SidecarSupervisor::from_arc(Arc::clone(&supervisor_arc), max_restarts);
                 sup.record_crash();
 
+                let crashed = sup.lifecycle_event(exit_code, Some("Sidecar process exited".to_string()));
+                let _ = emit_event(&watchdog_app, event_names::SIDECAR_CRASHED, crashed);
+
                 if !sup.should_restart() {
                     error!("Max restart attempts reached, watchdog exiting");
                     break;
@@ -279,6 +670,9 @@ impl SidecarBridge {
                     backoff_secs = backoff.as_secs(),
                     "Attempting restart after backoff"
                 );
+                let mut restarting = sup.lifecycle_event(exit_code, None);
+                restarting.backoff_ms = Some(backoff.as_millis() as u64);
+                let _ = emit_event(&watchdog_app, event_names::SIDECAR_RESTARTING, restarting);
                 thread::sleep(backoff);
 
                 // Check shutdown again after backoff
@@ -289,21 +683,48 @@ impl SidecarBridge {
 
                 // Attempt respawn
                 sup.set_state(SidecarState::Starting);
-                match spawn_child_process(&script) {
+                let Some(command) = command_arc.lock().unwrap_or_else(|e| e.into_inner()).clone() else {
+                    break;
+                };
+                match spawn_child_process(&command) {
                     Ok((new_child, new_stdin, new_stdout, new_stderr)) => {
                         *stdin_arc.lock().unwrap_or_else(|e| e.into_inner()) = Some(new_stdin);
                         *child_arc.lock().unwrap_or_else(|e| e.into_inner()) = Some(new_child);
+                        let restart_count = sup.restart_count();
                         sup.record_started();
+                        restarts_arc.fetch_add(1, Ordering::SeqCst);
+                        watchdog_monitors.start(report_unhealthy(watchdog_app.clone()));
+                        crate::metrics::global().counter_add(crate::metrics::SIDECAR_RESTARTS, &[], 1);
                         spawn_reader_threads(
                             new_stdout,
                             new_stderr,
-                            app.clone(),
+                            watchdog_app.clone(),
                             Arc::clone(&pending_arc),
+                            Arc::clone(&ticks_arc),
+                            Arc::clone(&activity_arc),
+                            Arc::clone(&quarantine_arc),
                         );
+                        // Negotiate like `spawn` does; an incompatible agent is
+                        // killed by `handshake`, which also stops this watchdog
+                        match watchdog_app.try_state::<SidecarBridge>() {
+                            Some(bridge) => {
+                                if bridge.handshake(&watchdog_app).is_err() {
+                                    break;
+                                }
+                            }
+                            None => warn!("Sidecar bridge not managed, skipping handshake after restart"),
+                        }
+                        // Journaled requests are replayed only once the new agent is negotiated
+                        generation_arc.fetch_add(1, Ordering::SeqCst);
                         debug!("Sidecar restarted successfully");
+                        let mut restarted = sup.lifecycle_event(None, None);
+                        restarted.restart_count = restart_count;
+                        let _ = emit_event(&watchdog_app, event_names::SIDECAR_RESTARTED, restarted);
                     }
                     Err(e) => {
                         error!(error = %e, "Failed to restart sidecar");
+                        let failed = sup.lifecycle_event(None, Some(e));
+                        let _ = emit_event(&watchdog_app, event_names::SIDECAR_CRASHED, failed);
                         // Will retry on next loop iteration if under max restarts
                     }
                 }
@@ -311,160 +732,207 @@ impl SidecarBridge {
             debug!("Watchdog thread exiting");
         });
 
-        // Spawn health checker thread
-        let pending_for_health = Arc::clone(&self.pending);
-        let stdin_for_health = Arc::clone(&self.stdin_writer);
-        let last_pong_for_health = Arc::clone(&self.last_pong);
-        let supervisor_for_health = self.supervisor.state_arc();
-        thread::spawn(move || {
-            debug!("Health checker thread started");
-            // Set initial pong timestamp so the agent has time to start
-            *last_pong_for_health
-                .lock()
-                .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
-            loop {
-                thread::sleep(HEALTH_CHECK_INTERVAL);
-                let state = supervisor_for_health
-                    .lock()
-                    .unwrap_or_else(|e| e.into_inner())
-                    .clone();
-                if state != SidecarState::Running {
-                    debug!("Health checker exiting (sidecar not running)");
-                    break;
-                }
+        self.handshake(&app)
+    }
 
-                // Send a ping request
-                let ping_req = JsonRpcRequest::new("ping", None);
-                let ping_id = ping_req.id;
-                let rx = pending_for_health.register(ping_id, Duration::from_secs(10));
-
-                let send_ok = {
-                    let mut guard = stdin_for_health
-                        .lock()
-                        .unwrap_or_else(|e| e.into_inner());
-                    if let Some(ref mut stdin) = *guard {
-                        if let Ok(line) = ping_req.to_line() {
-                            stdin.write_all(line.as_bytes()).is_ok()
-                                && stdin.flush().is_ok()
-                        } else {
-                            false
-                        }
-                    } else {
-                        false
-                    }
-                };
+    /// Exchange protocol versions with a freshly spawned or respawned agent. On
+    /// mismatch the child is killed and a `sidecar:incompatible` event is emitted.
+    fn handshake<R: Runtime>(&self, app: &AppHandle<R>) -> Result<(), String> {
+        let result = self
+            .send_once("agent:hello", Some(jsonrpc::hello_params()), HELLO_TIMEOUT)
+            .map_err(String::from)
+            .and_then(|response| jsonrpc::check_hello(&response));
+        match result {
+            Ok(hello) => {
+                info!(
+                    agent_version = hello.agent_version,
+                    protocol = hello.protocol_version,
+                    framing = ?hello.framing,
+                    "Agent handshake complete"
+                );
+                *self.framing.lock().unwrap_or_else(|e| e.into_inner()) = hello.framing;
+                *self.agent_info.lock().unwrap_or_else(|e| e.into_inner()) = Some(hello);
+                Ok(())
+            }
+            Err(e) => {
+                error!(error = %e, "Agent handshake failed");
+                let _ = emit_event(
+                    app,
+                    event_names::SIDECAR_INCOMPATIBLE,
+                    serde_json::json!({
+                        "expectedProtocolVersion": jsonrpc::PROTOCOL_VERSION,
+                        "message": e,
+                    }),
+                );
+                let _ = self.kill();
+                Err(e)
+            }
+        }
+    }
 
-                if send_ok {
-                    match rx.recv_timeout(Duration::from_secs(10)) {
-                        Ok(Ok(_)) => {
-                            *last_pong_for_health
-                                .lock()
-                                .unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
-                            trace!("Pong received");
-                        }
-                        Ok(Err(e)) => {
-                            warn!(error = %e, "Ping returned error");
-                        }
-                        Err(_) => {
-                            warn!("Ping timed out");
-                        }
-                    }
-                }
+    /// Send a JSON-RPC request to the agent and wait for the response.
+    pub fn send_request(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<JsonRpcResponse, BridgeError> {
+        self.send_request_with_timeout(method, params, REQUEST_TIMEOUT)
+    }
 
-                // Check if we've exceeded max silence
-                let elapsed = last_pong_for_health
-                    .lock()
-                    .unwrap_or_else(|e| e.into_inner())
-                    .map(|t| t.elapsed())
-                    .unwrap_or(Duration::ZERO);
-                if elapsed > MAX_SILENCE {
-                    error!(
-                        silence_secs = elapsed.as_secs(),
-                        "Agent unresponsive, marking unhealthy"
-                    );
-                    // Don't break -- let the watchdog handle crash detection
-                }
+    /// Send a JSON-RPC request and wait at most `timeout` for the response.
+    ///
+    /// With journaling enabled, idempotent methods that fail because the agent
+    /// crashed are re-sent once the watchdog has restarted it; the caller only
+    /// sees the final outcome.
+    pub fn send_request_with_timeout(
+        &self,
+        method: &str,
+        params: Option<Value>,
+        timeout: Duration,
+    ) -> Result<JsonRpcResponse, BridgeError> {
+        if !self.journal.should_journal(method) {
+            return self.send_once(method, params, timeout);
+        }
+
+        let key = self.journal.record(method, params.clone());
+        let mut generation = self.generation.load(Ordering::SeqCst);
+        let mut result = self.send_once(method, params, timeout);
+        while matches!(&result, Err(BridgeError::SidecarDown { message }) if message == CRASH_REASON) {
+            let Some(params) = self.journal.begin_replay(key) else {
+                break;
+            };
+            if !self.wait_for_restart(generation, RESTART_WAIT) {
+                warn!(method, "Agent did not restart in time, dropping journaled request");
+                break;
             }
-            debug!("Health checker thread exiting");
-        });
+            generation = self.generation.load(Ordering::SeqCst);
+            info!(method, "Re-sending journaled request to restarted agent");
+            result = self.send_once(method, params, timeout);
+        }
+        self.journal.complete(key, result.is_ok());
+        result
+    }
 
-        Ok(())
+    /// Block until a child newer than `generation` is running, or `timeout` elapses.
+    fn wait_for_restart(&self, generation: u64, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self.generation.load(Ordering::SeqCst) > generation && self.is_running() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        false
     }
 
-    /// Send a JSON-RPC request to the agent and wait for the response.
-    pub fn send_request(
+    fn send_once(
         &self,
         method: &str,
         params: Option<Value>,
-    ) -> Result<JsonRpcResponse, String> {
+        timeout: Duration,
+    ) -> Result<JsonRpcResponse, BridgeError> {
         if !self.is_running() {
-            return Err("Sidecar not running".to_string());
+            return Err(BridgeError::sidecar_down("Sidecar not running"));
         }
 
         let request = JsonRpcRequest::new(method, params);
-        let line = request.to_line().map_err(|e| e.to_string())?;
+        let line = request
+            .to_frame(self.framing())
+            .map_err(|e| BridgeError::transport(e.to_string()))?;
         let id = request.id;
 
         // Register pending request before writing to avoid race conditions
-        let rx = self.pending.register(id, REQUEST_TIMEOUT);
+        let rx = self.pending.register(id, timeout);
+        let started = Instant::now();
 
         // Write request to stdin
-        {
-            let mut guard = self
-                .stdin_writer
-                .lock()
-                .map_err(|e| format!("Failed to acquire stdin lock: {}", e))?;
-            if let Some(ref mut stdin) = *guard {
-                stdin
-                    .write_all(line.as_bytes())
-                    .map_err(|e| format!("Failed to write to stdin: {}", e))?;
-                stdin
-                    .flush()
-                    .map_err(|e| format!("Failed to flush stdin: {}", e))?;
-            } else {
-                return Err("Stdin not available".to_string());
-            }
-        } // Drop lock before waiting
+        let written = self.write_line(&line);
+        if let Err(e) = written {
+            self.metrics.record(method, started.elapsed(), false);
+            return Err(e);
+        }
 
         debug!(id, method = request.method, "Sent JSON-RPC request, waiting for response");
 
         // Wait for the response from the stdout reader thread
-        rx.recv_timeout(REQUEST_TIMEOUT)
-            .map_err(|e| format!("Request {} recv failed: {}", id, e))?
+        let result = match rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(_) => Err(BridgeError::Timeout {
+                id,
+                timeout_ms: timeout.as_millis() as u64,
+            }),
+        };
+        match &result {
+            Ok(response) => self.metrics.record(method, started.elapsed(), response.is_success()),
+            Err(BridgeError::Timeout { .. }) => self.metrics.record_timeout(method),
+            Err(_) => self.metrics.record(method, started.elapsed(), false),
+        }
+        result
     }
 
-    /// Send a JSON-RPC request without waiting for a response (fire-and-forget).
-    pub fn send_notification(
-        &self,
-        method: &str,
-        params: Option<Value>,
-    ) -> Result<(), String> {
-        if !self.is_running() {
-            return Err("Sidecar not running".to_string());
-        }
+    /// Send a request and return its result, mapping an error response to
+    /// `BridgeError::RpcError`.
+    pub fn call(&self, method: &str, params: Option<Value>) -> Result<Value, BridgeError> {
+        self.send_request(method, params)
+            .and_then(bridge_error::into_result)
+    }
 
-        let request = JsonRpcRequest::new(method, params);
-        let line = request.to_line().map_err(|e| e.to_string())?;
+    /// Framing currently used for writes to the agent.
+    pub fn framing(&self) -> Framing {
+        *self.framing.lock().unwrap_or_else(|e| e.into_inner())
+    }
 
+    /// Write a single encoded message to the sidecar's stdin.
+    fn write_line(&self, line: &str) -> Result<(), BridgeError> {
         let mut guard = self
             .stdin_writer
             .lock()
-            .map_err(|e| format!("Failed to acquire stdin lock: {}", e))?;
+            .map_err(|e| BridgeError::transport(format!("Failed to acquire stdin lock: {}", e)))?;
         if let Some(ref mut stdin) = *guard {
             stdin
                 .write_all(line.as_bytes())
-                .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+                .map_err(|e| BridgeError::transport(format!("Failed to write to stdin: {}", e)))?;
             stdin
                 .flush()
-                .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+                .map_err(|e| BridgeError::transport(format!("Failed to flush stdin: {}", e)))?;
+            Ok(())
         } else {
-            return Err("Stdin not available".to_string());
+            Err(BridgeError::transport("Stdin not available"))
         }
+    }
 
-        debug!(method = request.method, "Sent JSON-RPC notification (fire-and-forget)");
+    /// Send an id-less JSON-RPC notification; the agent does not reply.
+    pub fn send_notification(
+        &self,
+        method: &str,
+        params: Option<Value>,
+    ) -> Result<(), BridgeError> {
+        if !self.is_running() {
+            return Err(BridgeError::sidecar_down("Sidecar not running"));
+        }
+
+        let notification = JsonRpcNotification::new(method, params);
+        let line = notification
+            .to_frame(self.framing())
+            .map_err(|e| BridgeError::transport(e.to_string()))?;
+        self.write_line(&line)?;
+
+        debug!(method = notification.method, "Sent JSON-RPC notification");
         Ok(())
     }
 
+    /// Stop the agent gracefully: request `agent:stop`, wait up to `grace` for the
+    /// acknowledgment, then kill the child regardless of the outcome.
+    pub fn shutdown(&self, grace: Duration) -> Result<(), String> {
+        if self.is_running() {
+            match self.send_request_with_timeout("agent:stop", None, grace) {
+                Ok(_) => debug!("Agent acknowledged stop"),
+                Err(e) => warn!(error = %e, "Agent did not acknowledge stop within grace period"),
+            }
+        }
+        self.kill()
+    }
+
     /// Kill the sidecar process.
     pub fn kill(&self) -> Result<(), String> {
         // Signal watchdog to stop before killing the child
@@ -495,19 +963,199 @@ impl SidecarBridge {
             .stdin_writer
             .lock()
             .map_err(|e| format!("Failed to acquire stdin lock: {}", e))? = None;
+        *self.agent_info.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.command.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.trading_mode.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        *self.framing.lock().unwrap_or_else(|e| e.into_inner()) = Framing::Newline;
+        *self.resources.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.missed_pings.store(0, Ordering::SeqCst);
         self.supervisor.record_stopped();
         Ok(())
     }
 }
 
+impl Default for SidecarBridge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sample the running child's CPU/RSS and enforce the configured memory limit.
+/// A killed child is picked up as a crash on the next watchdog poll.
+fn sample_resources<R: Runtime>(
+    monitor: &mut ResourceMonitor,
+    child: &Mutex<Option<Child>>,
+    resources: &Mutex<Option<ResourceSample>>,
+    limits: &Mutex<ResourceLimits>,
+    app: &AppHandle<R>,
+) {
+    let Some(pid) = child
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(Child::id)
+    else {
+        return;
+    };
+    let sample = monitor.sample(pid);
+    *resources.lock().unwrap_or_else(|e| e.into_inner()) = sample;
+    let Some(sample) = sample else {
+        return;
+    };
+    trace!(pid, cpu = sample.cpu_percent, rss_mb = sample.rss_mb(), "Sidecar resources");
+
+    let limits = *limits.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(message) = sidecar_resources::check_limits(&sample, &limits) else {
+        return;
+    };
+    warn!(pid, rss_mb = sample.rss_mb(), "{}", message);
+    let mut killed = false;
+    if limits.kill_on_exceed {
+        if let Some(ref mut child) = *child.lock().unwrap_or_else(|e| e.into_inner()) {
+            killed = child.kill().is_ok();
+        }
+    }
+    let _ = emit_event(
+        app,
+        event_names::SIDECAR_RESOURCE_LIMIT,
+        serde_json::json!({
+            "sample": sample,
+            "limits": limits,
+            "killed": killed,
+            "message": message,
+        }),
+    );
+}
+
+/// Feed a `source:health-change` payload to the flap detector; on quarantine,
+/// tell the agent to skip the source and emit `source:quarantined`.
+fn check_quarantine<R: Runtime>(app: &AppHandle<R>, quarantine: &SourceQuarantine, payload: &Value) {
+    let health: SourceHealth = match serde_json::from_value(payload.clone()) {
+        Ok(h) => h,
+        Err(e) => {
+            warn!(error = %e, "Invalid source health payload");
+            return;
+        }
+    };
+    let now_ms = now_millis();
+    let Some(quarantined) = quarantine.observe(&health, now_ms) else {
+        return;
+    };
+    warn!(
+        source_id = quarantined.source_id,
+        transitions = quarantined.transitions,
+        "Source is flapping, quarantining"
+    );
+    if let Some(bridge) = app.try_state::<SidecarBridge>() {
+        let params = serde_json::json!({ "sourceId": quarantined.source_id, "until": quarantined.until });
+        if let Err(e) = bridge.send_notification("sources:quarantine", Some(params)) {
+            warn!(error = %e, "Failed to send quarantine to agent");
+        }
+    }
+    let _ = emit_event(app, event_names::SOURCE_QUARANTINED, quarantined);
+}
+
+/// Persist a `provider:health-change` payload so provider outages outlive the session.
+fn record_provider_health<R: Runtime>(app: &AppHandle<R>, payload: &Value) {
+    let health: ProviderHealth = match serde_json::from_value(payload.clone()) {
+        Ok(h) => h,
+        Err(e) => {
+            warn!(error = %e, "Invalid provider health payload");
+            return;
+        }
+    };
+    let Some(workspace) = app.try_state::<WorkspaceDb>() else {
+        return;
+    };
+    let now_ms = now_millis();
+    if let Err(e) = providers_health_set_db(&workspace.pool(), &health, now_ms) {
+        warn!(error = %e, "Failed to record provider health");
+    }
+}
+
+/// Persist a `llm:usage` payload, priced from the current config.
+fn record_llm_usage<R: Runtime>(app: &AppHandle<R>, payload: &Value) {
+    let usage: LlmUsage = match serde_json::from_value(payload.clone()) {
+        Ok(u) => u,
+        Err(e) => {
+            warn!(error = %e, "Invalid LLM usage payload");
+            return;
+        }
+    };
+    let Some(workspace) = app.try_state::<WorkspaceDb>() else {
+        return;
+    };
+    let pool = workspace.pool();
+    let recorded = config_effective_db(&pool).and_then(|c| usage_record_db(&pool, &usage, &usage_config(&c)));
+    if let Err(e) = recorded {
+        warn!(error = %e, "Failed to record LLM usage");
+    }
+}
+
+/// Persist the trades of a `backtest:complete` payload before the UI is told
+/// the run finished.
+fn record_backtest_trades<R: Runtime>(app: &AppHandle<R>, payload: &Value) {
+    let Some(workspace) = app.try_state::<WorkspaceDb>() else {
+        return;
+    };
+    match backtest_record_complete_db(&workspace.pool(), payload) {
+        Ok(count) => debug!(count, "Recorded backtest trades"),
+        Err(e) => warn!(error = %e, "Failed to record backtest trades"),
+    }
+}
+
 /// Route a JSON-RPC notification to the appropriate Tauri event.
-fn route_notification<R: Runtime>(app: &AppHandle<R>, method: &str, params: Option<Value>) {
+fn route_notification<R: Runtime>(
+    app: &AppHandle<R>,
+    ticks: &TickRecorder,
+    activity: &ActivityRecorder,
+    quarantine: &SourceQuarantine,
+    method: &str,
+    params: Option<Value>,
+) {
     let payload = params.unwrap_or(Value::Null);
+    if method == "data:tick" && ticks.is_enabled() {
+        if let Some(workspace) = app.try_state::<WorkspaceDb>() {
+            if let Err(e) = ticks.record(&workspace.pool(), &payload) {
+                warn!(error = %e, "Failed to record tick");
+            }
+        }
+    }
+    if method == "data:tick" {
+        match serde_json::from_value::<DataTick>(payload.clone()) {
+            Ok(tick) => alerts::check_ticks(app, std::slice::from_ref(&tick)),
+            Err(e) => warn!(error = %e, "Invalid tick payload"),
+        }
+    }
+    if method == "agent:activity" {
+        if let Some(workspace) = app.try_state::<WorkspaceDb>() {
+            if let Err(e) = activity.record(&workspace.pool(), &payload) {
+                warn!(error = %e, "Failed to record agent activity");
+            }
+        }
+    }
+    if method == "anomaly:detected" {
+        anomaly_notifier::notify_anomaly(app, &payload);
+    }
+    if method == "source:health-change" {
+        check_quarantine(app, quarantine, &payload);
+    }
+    if method == "provider:health-change" {
+        record_provider_health(app, &payload);
+    }
+    if method == "llm:usage" {
+        record_llm_usage(app, &payload);
+    }
+    if method == "backtest:complete" {
+        record_backtest_trades(app, &payload);
+    }
     let event = match method {
         "data:tick" => event_names::DATA_TICK,
         "anomaly:detected" => event_names::ANOMALY_DETECTED,
         "agent:activity" => event_names::AGENT_ACTIVITY,
         "source:health-change" => event_names::SOURCE_HEALTH_CHANGE,
+        "provider:health-change" => event_names::PROVIDER_HEALTH_CHANGE,
+        "llm:usage" => event_names::LLM_USAGE,
         "memory:updated" => event_names::MEMORY_UPDATED,
         "backtest:progress" => event_names::BACKTEST_PROGRESS,
         "backtest:complete" => event_names::BACKTEST_COMPLETE,
@@ -536,8 +1184,10 @@ mod tests {
     fn send_request_fails_when_not_running() {
         let bridge = SidecarBridge::new();
         let result = bridge.send_request("agent:status", None);
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Sidecar not running");
+        assert_eq!(
+            result.unwrap_err(),
+            BridgeError::sidecar_down("Sidecar not running")
+        );
     }
 
     #[test]
@@ -562,9 +1212,10 @@ mod tests {
         // but we can force the state to Running and test the poisoned path
         bridge.supervisor.record_started();
         let result = bridge.send_request("test:method", None);
-        assert!(result.is_err());
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), "transport");
         assert!(
-            result.unwrap_err().contains("lock"),
+            err.to_string().contains("lock"),
             "Error should mention lock poisoning"
         );
     }
@@ -587,6 +1238,145 @@ mod tests {
         );
     }
 
+    fn read_request<R: BufRead>(reader: &mut R) -> JsonRpcRequest {
+        let line = jsonrpc::read_message(reader).unwrap().unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn journaled_request_is_resent_after_restart() {
+        let bridge = Arc::new(SidecarBridge::new());
+        bridge.set_journaling(true, None);
+        bridge.supervisor.record_started();
+
+        // `cat` echoes our writes back, standing in for the agent's stdin
+        let mut cat = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        *bridge.stdin_writer.lock().unwrap() = cat.stdin.take();
+        let mut agent_rx = BufReader::new(cat.stdout.take().unwrap());
+
+        let caller = {
+            let bridge = Arc::clone(&bridge);
+            thread::spawn(move || bridge.send_request("agent:status", None))
+        };
+
+        // First attempt: simulate a crash followed by a watchdog restart
+        let first = read_request(&mut agent_rx);
+        assert_eq!(first.method, "agent:status");
+        bridge.pending.fail_all(CRASH_REASON);
+        bridge.generation.fetch_add(1, Ordering::SeqCst);
+
+        // Second attempt: answer it
+        let second = read_request(&mut agent_rx);
+        assert_ne!(first.id, second.id);
+        bridge.pending.resolve(
+            second.id,
+            JsonRpcResponse {
+                jsonrpc: "2.0".to_string(),
+                id: second.id,
+                result: Some(serde_json::json!({"state": "idle"})),
+                error: None,
+            },
+        );
+
+        let response = caller.join().unwrap().unwrap();
+        assert_eq!(response.result.unwrap()["state"], "idle");
+        let journal = bridge.journal_snapshot();
+        assert_eq!(journal.replayed, 1);
+        assert_eq!(journal.recovered, 1);
+        assert_eq!(journal.in_flight, 0);
+        let _ = cat.kill();
+        let _ = cat.wait();
+    }
+
+    #[test]
+    fn non_idempotent_request_is_not_resent() {
+        let bridge = SidecarBridge::new();
+        bridge.set_journaling(true, None);
+        bridge.supervisor.record_started();
+        assert!(bridge.send_request("agent:start", None).is_err());
+        assert_eq!(bridge.journal_snapshot().replayed, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn send_notification_writes_no_id_and_registers_nothing() {
+        let bridge = SidecarBridge::new();
+        bridge.supervisor.record_started();
+        let mut cat = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        *bridge.stdin_writer.lock().unwrap() = cat.stdin.take();
+        let mut agent_rx = BufReader::new(cat.stdout.take().unwrap());
+
+        bridge
+            .send_notification("backtest:cancel", Some(serde_json::json!({"backtestId": "bt-1"})))
+            .unwrap();
+        let line = jsonrpc::read_message(&mut agent_rx).unwrap().unwrap();
+        let value: Value = serde_json::from_str(&line).unwrap();
+        assert!(value.get("id").is_none());
+        assert_eq!(value["method"], "backtest:cancel");
+        assert!(bridge.pending.is_empty());
+        let _ = cat.kill();
+        let _ = cat.wait();
+    }
+
+    #[test]
+    fn kill_resets_framing_to_newline() {
+        let bridge = SidecarBridge::new();
+        *bridge.framing.lock().unwrap() = Framing::ContentLength;
+        bridge.kill().unwrap();
+        assert_eq!(bridge.framing(), Framing::Newline);
+    }
+
+    #[test]
+    fn shutdown_on_idle_bridge_succeeds() {
+        let bridge = SidecarBridge::new();
+        assert!(bridge.shutdown(Duration::from_millis(10)).is_ok());
+        assert!(!bridge.is_running());
+    }
+
+    #[test]
+    fn shutdown_kills_even_when_stop_is_not_acknowledged() {
+        let bridge = SidecarBridge::new();
+        bridge.supervisor.record_started();
+        // No stdin attached: agent:stop cannot be delivered
+        assert!(bridge.shutdown(Duration::from_millis(10)).is_ok());
+        assert!(!bridge.is_running());
+    }
+
+    #[test]
+    fn mode_switch_while_running_is_detected() {
+        let bridge = SidecarBridge::new();
+        bridge.set_trading_mode("paper");
+        assert!(!bridge.running_for_other_mode("live"));
+
+        bridge.supervisor.record_started();
+        assert!(!bridge.running_for_other_mode("paper"));
+        assert!(bridge.running_for_other_mode("live"));
+
+        bridge.shutdown(Duration::from_millis(10)).unwrap();
+        assert!(!bridge.running_for_other_mode("live"));
+    }
+
+    #[test]
+    fn failed_write_is_recorded_in_metrics() {
+        let bridge = SidecarBridge::new();
+        bridge.supervisor.record_started();
+        // No stdin attached, so the write fails
+        assert!(bridge.send_request("memory:search", None).is_err());
+        let snap = bridge.metrics_snapshot();
+        assert_eq!(snap.total_calls, 1);
+        assert_eq!(snap.total_errors, 1);
+        assert_eq!(snap.methods[0].method, "memory:search");
+    }
+
     #[test]
     fn is_healthy_false_when_not_running() {
         let bridge = SidecarBridge::new();
@@ -632,4 +1422,73 @@ mod tests {
         bridge.record_pong();
         assert!(bridge.is_healthy(Duration::from_secs(90)));
     }
+
+    #[test]
+    fn health_reports_pong_age_and_watchdog_state() {
+        let bridge = SidecarBridge::new();
+        assert_eq!(bridge.health(), AgentHealth::default());
+
+        bridge.supervisor.record_started();
+        *bridge.last_pong.lock().unwrap() = Some(Instant::now() - Duration::from_secs(40));
+        bridge.missed_pings.store(1, Ordering::SeqCst);
+        let health = bridge.health();
+        assert!(health.last_pong_age_ms.unwrap() >= 40_000);
+        assert_eq!(health.missed_pings, 1);
+        assert_eq!(health.watchdog, WatchdogState::Watching);
+
+        bridge.record_pong();
+        assert_eq!(bridge.health().missed_pings, 0);
+    }
+
+    #[test]
+    fn restart_resets_heartbeat_and_retires_old_monitors() {
+        let bridge = SidecarBridge::new();
+        bridge.supervisor.record_started();
+        let monitors = bridge.monitors();
+        monitors.start(|_| {});
+        let first = bridge.monitor_epoch.load(Ordering::SeqCst);
+
+        // A silent agent crashes and the watchdog brings it back
+        *bridge.last_pong.lock().unwrap() = Some(Instant::now() - Duration::from_secs(120));
+        bridge.missed_pings.store(4, Ordering::SeqCst);
+        bridge.supervisor.record_crash();
+        assert!(!monitors.is_current(first));
+        bridge.supervisor.record_started();
+        monitors.start(|_| {});
+
+        let health = bridge.health();
+        assert!(health.last_pong_age_ms.unwrap() < 1_000);
+        assert_eq!(health.missed_pings, 0);
+        assert!(bridge.is_healthy(MAX_SILENCE));
+        assert!(!monitors.is_current(first));
+        assert!(monitors.is_current(first + 1));
+    }
+
+    #[test]
+    fn watchdog_gives_up_after_crash_budget() {
+        let bridge = SidecarBridge::new();
+        bridge.set_restart_policy(RestartPolicy {
+            max_restarts: 1,
+            ..RestartPolicy::default()
+        });
+        bridge.supervisor.record_started();
+        bridge.supervisor.record_crash();
+        assert_eq!(bridge.health().watchdog, WatchdogState::GaveUp);
+        assert_eq!(bridge.health().last_pong_age_ms, None);
+    }
+
+    #[test]
+    fn forget_env_strips_respawn_secrets() {
+        let bridge = SidecarBridge::new();
+        bridge.forget_env(&["A"]);
+        *bridge.command.lock().unwrap() = Some(SidecarCommand {
+            program: "node".into(),
+            args: Vec::new(),
+            cwd: None,
+            env: vec![("A".to_string(), "1".to_string()), ("B".to_string(), "2".to_string())],
+        });
+        bridge.forget_env(&["A"]);
+        let command = bridge.command.lock().unwrap().clone().unwrap();
+        assert_eq!(command.env, vec![("B".to_string(), "2".to_string())]);
+    }
 }
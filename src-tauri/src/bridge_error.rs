@@ -0,0 +1,203 @@
+use std::fmt;
+
+use serde::ser::{Serialize, Serializer};
+use serde_json::Value;
+
+use crate::jsonrpc::JsonRpcResponse;
+
+/// JSON-RPC "server error" range reserved for agent-defined errors.
+const SERVER_ERROR_RANGE: std::ops::RangeInclusive<i32> = -32099..=-32000;
+
+/// Failure of a call through the sidecar bridge.
+///
+/// Serialized for the frontend as `{ kind, message, retryable, ... }` so the
+/// UI can tell a busy agent (retry) from a dead one (restart).
+#[derive(Debug, Clone, PartialEq)]
+pub enum BridgeError {
+    /// No response arrived before the deadline; the agent may just be busy.
+    Timeout { id: u64, timeout_ms: u64 },
+    /// Writing to or reading from the agent's stdio failed.
+    Transport { message: String },
+    /// The agent is not running, crashed, or was killed mid-request.
+    SidecarDown { message: String },
+    /// The agent answered with a JSON-RPC error object.
+    RpcError {
+        code: i32,
+        message: String,
+        data: Option<Value>,
+    },
+    /// A non-bridge failure in a command that also talks to the agent.
+    Internal { message: String },
+}
+
+impl BridgeError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BridgeError::Timeout { .. } => "timeout",
+            BridgeError::Transport { .. } => "transport",
+            BridgeError::SidecarDown { .. } => "sidecarDown",
+            BridgeError::RpcError { .. } => "rpcError",
+            BridgeError::Internal { .. } => "internal",
+        }
+    }
+
+    /// Whether retrying the same call later may succeed without user action.
+    pub fn retryable(&self) -> bool {
+        match self {
+            BridgeError::Timeout { .. } | BridgeError::Transport { .. } => true,
+            BridgeError::RpcError { code, .. } => SERVER_ERROR_RANGE.contains(code),
+            BridgeError::SidecarDown { .. } | BridgeError::Internal { .. } => false,
+        }
+    }
+
+    pub fn transport(message: impl Into<String>) -> Self {
+        BridgeError::Transport {
+            message: message.into(),
+        }
+    }
+
+    pub fn sidecar_down(message: impl Into<String>) -> Self {
+        BridgeError::SidecarDown {
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::Timeout { id, .. } => write!(f, "JSON-RPC request {} timed out", id),
+            BridgeError::Transport { message }
+            | BridgeError::SidecarDown { message }
+            | BridgeError::Internal { message } => f.write_str(message),
+            BridgeError::RpcError { code, message, .. } => {
+                write!(f, "Agent error {}: {}", code, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BridgeError {}
+
+impl Serialize for BridgeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::json!({
+            "kind": self.kind(),
+            "message": self.to_string(),
+            "retryable": self.retryable(),
+        });
+        match self {
+            BridgeError::Timeout { id, timeout_ms } => {
+                value["id"] = (*id).into();
+                value["timeoutMs"] = (*timeout_ms).into();
+            }
+            BridgeError::RpcError { code, data, .. } => {
+                value["code"] = (*code).into();
+                value["data"] = data.clone().unwrap_or(Value::Null);
+            }
+            _ => {}
+        }
+        value.serialize(serializer)
+    }
+}
+
+impl From<String> for BridgeError {
+    fn from(message: String) -> Self {
+        BridgeError::Internal { message }
+    }
+}
+
+impl From<BridgeError> for String {
+    fn from(err: BridgeError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Turn a response into its result, mapping a JSON-RPC error object to `RpcError`.
+pub fn into_result(response: JsonRpcResponse) -> Result<Value, BridgeError> {
+    match response.error {
+        Some(err) => Err(BridgeError::RpcError {
+            code: err.code,
+            message: err.message,
+            data: err.data,
+        }),
+        None => Ok(response.result.unwrap_or(Value::Null)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::JsonRpcError;
+
+    #[test]
+    fn timeout_serializes_with_retry_hint() {
+        let err = BridgeError::Timeout {
+            id: 7,
+            timeout_ms: 31_000,
+        };
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "timeout");
+        assert_eq!(json["retryable"], true);
+        assert_eq!(json["timeoutMs"], 31_000);
+        assert_eq!(json["message"], "JSON-RPC request 7 timed out");
+    }
+
+    #[test]
+    fn sidecar_down_is_not_retryable() {
+        let err = BridgeError::sidecar_down("Sidecar process crashed");
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "sidecarDown");
+        assert_eq!(json["retryable"], false);
+    }
+
+    #[test]
+    fn rpc_errors_retryable_only_in_server_range() {
+        let busy = BridgeError::RpcError {
+            code: -32001,
+            message: "busy".to_string(),
+            data: None,
+        };
+        let not_found = BridgeError::RpcError {
+            code: -32601,
+            message: "Method not found".to_string(),
+            data: None,
+        };
+        assert!(busy.retryable());
+        assert!(!not_found.retryable());
+        assert_eq!(serde_json::to_value(&not_found).unwrap()["code"], -32601);
+    }
+
+    #[test]
+    fn into_result_maps_error_responses() {
+        let ok = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: 1,
+            result: Some(serde_json::json!({"status": "started"})),
+            error: None,
+        };
+        assert_eq!(into_result(ok).unwrap()["status"], "started");
+
+        let failed = JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            id: 2,
+            result: None,
+            error: Some(JsonRpcError {
+                code: -32603,
+                message: "no keys".to_string(),
+                data: None,
+            }),
+        };
+        let err = into_result(failed).unwrap_err();
+        assert_eq!(err.kind(), "rpcError");
+        assert_eq!(err.to_string(), "Agent error -32603: no keys");
+    }
+
+    #[test]
+    fn converts_to_and_from_strings() {
+        let err: BridgeError = "Invalid config".to_string().into();
+        assert_eq!(err.kind(), "internal");
+        let message: String = BridgeError::transport("Stdin not available").into();
+        assert_eq!(message, "Stdin not available");
+    }
+}
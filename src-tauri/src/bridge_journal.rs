@@ -0,0 +1,202 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Methods that are safe to re-send to a restarted agent by default.
+pub const DEFAULT_REPLAYABLE_METHODS: &[&str] = &["ping", "agent:status", "memory:search"];
+/// Maximum number of times a journaled request is re-sent after crashes.
+pub const MAX_REPLAYS: u32 = 2;
+
+/// An idempotent request that is currently in flight.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub method: String,
+    pub params: Option<Value>,
+    /// Number of times the request has been re-sent.
+    pub replays: u32,
+}
+
+/// Journal counters returned alongside the bridge metrics.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalSnapshot {
+    pub enabled: bool,
+    pub in_flight: usize,
+    pub replayed: u64,
+    pub recovered: u64,
+    pub methods: Vec<String>,
+}
+
+/// Records idempotent requests so they can be re-sent when the watchdog
+/// replaces a crashed agent process. Disabled by default.
+pub struct RequestJournal {
+    enabled: AtomicBool,
+    methods: Mutex<HashSet<String>>,
+    entries: Mutex<HashMap<u64, JournalEntry>>,
+    next_key: AtomicU64,
+    replayed: AtomicU64,
+    recovered: AtomicU64,
+}
+
+impl RequestJournal {
+    pub fn new() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            methods: Mutex::new(DEFAULT_REPLAYABLE_METHODS.iter().map(|m| m.to_string()).collect()),
+            entries: Mutex::new(HashMap::new()),
+            next_key: AtomicU64::new(1),
+            replayed: AtomicU64::new(0),
+            recovered: AtomicU64::new(0),
+        }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// Replace the set of methods considered idempotent.
+    pub fn set_methods(&self, methods: &[String]) {
+        *self.methods.lock().unwrap_or_else(|e| e.into_inner()) =
+            methods.iter().cloned().collect();
+    }
+
+    /// True if journaling is on and `method` may be re-sent.
+    pub fn should_journal(&self, method: &str) -> bool {
+        self.is_enabled()
+            && self
+                .methods
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .contains(method)
+    }
+
+    /// Record an in-flight request. Returns the key used for later replay/completion.
+    pub fn record(&self, method: &str, params: Option<Value>) -> u64 {
+        let key = self.next_key.fetch_add(1, Ordering::Relaxed);
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                key,
+                JournalEntry {
+                    method: method.to_string(),
+                    params,
+                    replays: 0,
+                },
+            );
+        key
+    }
+
+    /// Mark a request for re-sending. Returns its params, or `None` once the
+    /// replay budget is spent (or the entry is unknown).
+    pub fn begin_replay(&self, key: u64) -> Option<Option<Value>> {
+        let mut map = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = map.get_mut(&key)?;
+        if entry.replays >= MAX_REPLAYS {
+            return None;
+        }
+        entry.replays += 1;
+        self.replayed.fetch_add(1, Ordering::Relaxed);
+        Some(entry.params.clone())
+    }
+
+    /// Remove a finished request, counting it as recovered if it succeeded after a replay.
+    pub fn complete(&self, key: u64, ok: bool) {
+        let entry = self
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&key);
+        if let Some(entry) = entry {
+            if ok && entry.replays > 0 {
+                self.recovered.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    pub fn snapshot(&self) -> JournalSnapshot {
+        let mut methods: Vec<String> = self
+            .methods
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect();
+        methods.sort();
+        JournalSnapshot {
+            enabled: self.is_enabled(),
+            in_flight: self.entries.lock().unwrap_or_else(|e| e.into_inner()).len(),
+            replayed: self.replayed.load(Ordering::Relaxed),
+            recovered: self.recovered.load(Ordering::Relaxed),
+            methods,
+        }
+    }
+}
+
+impl Default for RequestJournal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_by_default() {
+        let journal = RequestJournal::new();
+        assert!(!journal.should_journal("agent:status"));
+        journal.set_enabled(true);
+        assert!(journal.should_journal("agent:status"));
+        assert!(!journal.should_journal("agent:start"));
+    }
+
+    #[test]
+    fn custom_methods_replace_defaults() {
+        let journal = RequestJournal::new();
+        journal.set_enabled(true);
+        journal.set_methods(&["backtest:status".to_string()]);
+        assert!(journal.should_journal("backtest:status"));
+        assert!(!journal.should_journal("ping"));
+    }
+
+    #[test]
+    fn replay_budget_is_bounded() {
+        let journal = RequestJournal::new();
+        let key = journal.record("memory:search", Some(serde_json::json!({"q": "NET"})));
+        for _ in 0..MAX_REPLAYS {
+            let params = journal.begin_replay(key).unwrap();
+            assert_eq!(params.unwrap()["q"], "NET");
+        }
+        assert!(journal.begin_replay(key).is_none());
+        assert_eq!(journal.snapshot().replayed, MAX_REPLAYS as u64);
+    }
+
+    #[test]
+    fn complete_counts_recovered_requests() {
+        let journal = RequestJournal::new();
+        let first = journal.record("ping", None);
+        let second = journal.record("ping", None);
+        journal.begin_replay(first);
+        journal.complete(first, true);
+        journal.complete(second, true);
+        let snap = journal.snapshot();
+        assert_eq!(snap.recovered, 1);
+        assert_eq!(snap.in_flight, 0);
+    }
+
+    #[test]
+    fn begin_replay_unknown_entry_is_none() {
+        let journal = RequestJournal::new();
+        assert!(journal.begin_replay(99).is_none());
+    }
+}
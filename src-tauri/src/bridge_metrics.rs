@@ -0,0 +1,211 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Number of recent latency samples kept per method for percentile estimates.
+const LATENCY_WINDOW: usize = 256;
+
+#[derive(Default)]
+struct MethodStats {
+    calls: u64,
+    errors: u64,
+    timeouts: u64,
+    latencies_ms: VecDeque<u64>,
+}
+
+/// Snapshot of RPC statistics for a single JSON-RPC method.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodMetrics {
+    pub method: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub timeouts: u64,
+    pub p50_ms: Option<u64>,
+    pub p95_ms: Option<u64>,
+    pub p99_ms: Option<u64>,
+    pub max_ms: Option<u64>,
+}
+
+/// Snapshot of all bridge RPC statistics, returned by the `bridge_metrics` command.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeMetricsSnapshot {
+    pub total_calls: u64,
+    pub total_errors: u64,
+    pub pending: usize,
+    pub methods: Vec<MethodMetrics>,
+}
+
+/// Per-method counters and latency samples for JSON-RPC requests sent by the bridge.
+pub struct BridgeMetrics {
+    methods: Mutex<HashMap<String, MethodStats>>,
+}
+
+impl BridgeMetrics {
+    pub fn new() -> Self {
+        Self {
+            methods: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Record a completed request. `ok` is false for transport failures and error responses.
+    pub fn record(&self, method: &str, latency: Duration, ok: bool) {
+        let registry = crate::metrics::global();
+        registry.observe(crate::metrics::RPC_DURATION, &[("method", method)], latency);
+        if !ok {
+            registry.counter_add(crate::metrics::RPC_ERRORS, &[("method", method)], 1);
+        }
+        let mut map = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = map.entry(method.to_string()).or_default();
+        stats.calls += 1;
+        if !ok {
+            stats.errors += 1;
+        }
+        if stats.latencies_ms.len() == LATENCY_WINDOW {
+            stats.latencies_ms.pop_front();
+        }
+        stats.latencies_ms.push_back(latency.as_millis() as u64);
+    }
+
+    /// Record a request that never received a response before its deadline.
+    pub fn record_timeout(&self, method: &str) {
+        crate::metrics::global().counter_add(crate::metrics::RPC_ERRORS, &[("method", method)], 1);
+        let mut map = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = map.entry(method.to_string()).or_default();
+        stats.calls += 1;
+        stats.errors += 1;
+        stats.timeouts += 1;
+    }
+
+    /// Build a serializable snapshot, sorted by method name.
+    pub fn snapshot(&self, pending: usize) -> BridgeMetricsSnapshot {
+        let map = self.methods.lock().unwrap_or_else(|e| e.into_inner());
+        let mut methods: Vec<MethodMetrics> = map
+            .iter()
+            .map(|(method, stats)| {
+                let mut sorted: Vec<u64> = stats.latencies_ms.iter().copied().collect();
+                sorted.sort_unstable();
+                MethodMetrics {
+                    method: method.clone(),
+                    calls: stats.calls,
+                    errors: stats.errors,
+                    timeouts: stats.timeouts,
+                    p50_ms: percentile(&sorted, 0.50),
+                    p95_ms: percentile(&sorted, 0.95),
+                    p99_ms: percentile(&sorted, 0.99),
+                    max_ms: sorted.last().copied(),
+                }
+            })
+            .collect();
+        methods.sort_by(|a, b| a.method.cmp(&b.method));
+
+        BridgeMetricsSnapshot {
+            total_calls: methods.iter().map(|m| m.calls).sum(),
+            total_errors: methods.iter().map(|m| m.errors).sum(),
+            pending,
+            methods,
+        }
+    }
+
+    /// Clear all recorded statistics.
+    pub fn reset(&self) {
+        self.methods
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+impl Default for BridgeMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nearest-rank percentile over an already sorted slice.
+fn percentile(sorted: &[u64], q: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_metrics_snapshot() {
+        let metrics = BridgeMetrics::new();
+        let snap = metrics.snapshot(0);
+        assert_eq!(snap.total_calls, 0);
+        assert!(snap.methods.is_empty());
+    }
+
+    #[test]
+    fn record_counts_calls_and_errors_per_method() {
+        let metrics = BridgeMetrics::new();
+        metrics.record("ping", Duration::from_millis(5), true);
+        metrics.record("ping", Duration::from_millis(7), false);
+        metrics.record("agent:start", Duration::from_millis(120), true);
+
+        let snap = metrics.snapshot(2);
+        assert_eq!(snap.total_calls, 3);
+        assert_eq!(snap.total_errors, 1);
+        assert_eq!(snap.pending, 2);
+        assert_eq!(snap.methods[0].method, "agent:start");
+        assert_eq!(snap.methods[1].method, "ping");
+        assert_eq!(snap.methods[1].calls, 2);
+        assert_eq!(snap.methods[1].errors, 1);
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let metrics = BridgeMetrics::new();
+        for ms in 1..=100 {
+            metrics.record("memory:search", Duration::from_millis(ms), true);
+        }
+        let snap = metrics.snapshot(0);
+        let m = &snap.methods[0];
+        assert_eq!(m.p50_ms, Some(50));
+        assert_eq!(m.p95_ms, Some(95));
+        assert_eq!(m.p99_ms, Some(99));
+        assert_eq!(m.max_ms, Some(100));
+    }
+
+    #[test]
+    fn latency_window_is_bounded() {
+        let metrics = BridgeMetrics::new();
+        for _ in 0..LATENCY_WINDOW {
+            metrics.record("ping", Duration::from_millis(1000), true);
+        }
+        for _ in 0..LATENCY_WINDOW {
+            metrics.record("ping", Duration::from_millis(1), true);
+        }
+        let snap = metrics.snapshot(0);
+        assert_eq!(snap.methods[0].calls, 2 * LATENCY_WINDOW as u64);
+        assert_eq!(snap.methods[0].max_ms, Some(1));
+    }
+
+    #[test]
+    fn timeouts_count_as_errors_without_latency() {
+        let metrics = BridgeMetrics::new();
+        metrics.record_timeout("agent:start");
+        let snap = metrics.snapshot(0);
+        assert_eq!(snap.methods[0].timeouts, 1);
+        assert_eq!(snap.methods[0].errors, 1);
+        assert_eq!(snap.methods[0].p50_ms, None);
+    }
+
+    #[test]
+    fn reset_clears_all_methods() {
+        let metrics = BridgeMetrics::new();
+        metrics.record("ping", Duration::from_millis(1), true);
+        metrics.reset();
+        assert!(metrics.snapshot(0).methods.is_empty());
+    }
+}
@@ -4,14 +4,16 @@ use std::time::{Duration, Instant};
 
 use tracing::{debug, warn};
 
+use crate::bridge_error::BridgeError;
 use crate::jsonrpc::JsonRpcResponse;
 
-type ResponseSender = std::sync::mpsc::Sender<Result<JsonRpcResponse, String>>;
-type ResponseReceiver = std::sync::mpsc::Receiver<Result<JsonRpcResponse, String>>;
+type ResponseSender = std::sync::mpsc::Sender<Result<JsonRpcResponse, BridgeError>>;
+type ResponseReceiver = std::sync::mpsc::Receiver<Result<JsonRpcResponse, BridgeError>>;
 
 struct PendingRequest {
     sender: ResponseSender,
     deadline: Instant,
+    timeout: Duration,
 }
 
 /// Tracks in-flight JSON-RPC requests and matches them to responses by ID.
@@ -32,6 +34,7 @@ impl PendingRequestTracker {
         let entry = PendingRequest {
             sender: tx,
             deadline: Instant::now() + timeout,
+            timeout,
         };
         let mut map = self.pending.lock().unwrap_or_else(|e| e.into_inner());
         map.insert(id, entry);
@@ -63,22 +66,22 @@ impl PendingRequestTracker {
             .collect();
         for id in expired {
             if let Some(entry) = map.remove(&id) {
-                let _ = entry.sender.send(Err(format!(
-                    "JSON-RPC request {} timed out",
-                    id
-                )));
+                let _ = entry.sender.send(Err(BridgeError::Timeout {
+                    id,
+                    timeout_ms: entry.timeout.as_millis() as u64,
+                }));
                 warn!(id, "Request timed out");
             }
         }
     }
 
-    /// Fail all pending requests (used during shutdown).
+    /// Fail all pending requests with `SidecarDown` (used on crash and shutdown).
     pub fn fail_all(&self, reason: &str) {
         let mut map = self.pending.lock().unwrap_or_else(|e| e.into_inner());
         let ids: Vec<u64> = map.keys().copied().collect();
         for id in ids {
             if let Some(entry) = map.remove(&id) {
-                let _ = entry.sender.send(Err(reason.to_string()));
+                let _ = entry.sender.send(Err(BridgeError::sidecar_down(reason)));
             }
         }
         debug!(reason, "Failed all pending requests");
@@ -91,6 +94,17 @@ impl PendingRequestTracker {
             .unwrap_or_else(|e| e.into_inner())
             .len()
     }
+
+    /// Returns true if no requests are pending.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for PendingRequestTracker {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -145,7 +159,9 @@ mod tests {
 
         let received = rx.recv_timeout(Duration::from_millis(100)).unwrap();
         assert!(received.is_err());
-        assert!(received.unwrap_err().contains("timed out"));
+        let err = received.unwrap_err();
+        assert!(matches!(err, BridgeError::Timeout { id: 42, .. }));
+        assert!(err.to_string().contains("timed out"));
     }
 
     #[test]
@@ -171,8 +187,8 @@ mod tests {
         let r2 = rx2.recv_timeout(Duration::from_millis(100)).unwrap();
         assert!(r1.is_err());
         assert!(r2.is_err());
-        assert!(r1.unwrap_err().contains("sidecar killed"));
-        assert!(r2.unwrap_err().contains("sidecar killed"));
+        assert_eq!(r1.unwrap_err(), BridgeError::sidecar_down("sidecar killed"));
+        assert!(r2.unwrap_err().to_string().contains("sidecar killed"));
     }
 
     #[test]
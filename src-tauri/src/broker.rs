@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// A broker or market-data vendor whose API credentials can be stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrokerProvider {
+    Alpaca,
+    Tradier,
+    Polygon,
+    Finnhub,
+}
+
+/// Credential fields keyed by name, e.g. `key_id` and `secret_key` for Alpaca.
+pub type ProviderCredentials = BTreeMap<String, String>;
+
+impl BrokerProvider {
+    pub const ALL: [BrokerProvider; 4] = [
+        BrokerProvider::Alpaca,
+        BrokerProvider::Tradier,
+        BrokerProvider::Polygon,
+        BrokerProvider::Finnhub,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BrokerProvider::Alpaca => "alpaca",
+            BrokerProvider::Tradier => "tradier",
+            BrokerProvider::Polygon => "polygon",
+            BrokerProvider::Finnhub => "finnhub",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|p| p.as_str() == name)
+            .ok_or_else(|| {
+                format!(
+                    "Invalid broker provider: '{}'. Must be one of: alpaca, tradier, polygon, finnhub",
+                    name
+                )
+            })
+    }
+
+    /// Fields a credential set for this provider must have.
+    pub fn fields(&self) -> &'static [&'static str] {
+        match self {
+            BrokerProvider::Alpaca => &["key_id", "secret_key"],
+            BrokerProvider::Tradier => &["account_id", "access_token"],
+            BrokerProvider::Polygon | BrokerProvider::Finnhub => &["api_key"],
+        }
+    }
+
+    /// Fields that are never returned unmasked.
+    pub fn secret_fields(&self) -> &'static [&'static str] {
+        match self {
+            BrokerProvider::Alpaca => &["secret_key"],
+            BrokerProvider::Tradier => &["access_token"],
+            BrokerProvider::Polygon | BrokerProvider::Finnhub => &["api_key"],
+        }
+    }
+
+    /// Environments the provider has keys for. Data vendors only have live keys.
+    pub fn modes(&self) -> &'static [&'static str] {
+        match self {
+            BrokerProvider::Alpaca | BrokerProvider::Tradier => &["paper", "live"],
+            BrokerProvider::Polygon | BrokerProvider::Finnhub => &["live"],
+        }
+    }
+
+    pub fn validate_mode(&self, mode: &str) -> Result<(), String> {
+        if self.modes().contains(&mode) {
+            return Ok(());
+        }
+        Err(format!(
+            "Invalid trading mode for {}: '{}'. Must be {}",
+            self.as_str(),
+            mode,
+            self.modes()
+                .iter()
+                .map(|m| format!("'{}'", m))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        ))
+    }
+
+    /// Check that `creds` has exactly this provider's fields, all non-empty.
+    pub fn validate_credentials(&self, creds: &ProviderCredentials) -> Result<(), String> {
+        for field in self.fields() {
+            match creds.get(*field) {
+                Some(value) if !value.is_empty() => {}
+                _ => return Err(format!("Missing {} credential field '{}'", self.as_str(), field)),
+            }
+        }
+        if let Some(unknown) = creds.keys().find(|k| !self.fields().contains(&k.as_str())) {
+            return Err(format!(
+                "Unknown {} credential field '{}'",
+                self.as_str(),
+                unknown
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// What the UI needs to render a credentials form for a provider.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokerProviderInfo {
+    pub provider: BrokerProvider,
+    pub fields: Vec<&'static str>,
+    pub secret_fields: Vec<&'static str>,
+    pub modes: Vec<&'static str>,
+}
+
+impl From<BrokerProvider> for BrokerProviderInfo {
+    fn from(provider: BrokerProvider) -> Self {
+        Self {
+            provider,
+            fields: provider.fields().to_vec(),
+            secret_fields: provider.secret_fields().to_vec(),
+            modes: provider.modes().to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn creds(pairs: &[(&str, &str)]) -> ProviderCredentials {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_roundtrips_every_provider() {
+        for provider in BrokerProvider::ALL {
+            assert_eq!(BrokerProvider::parse(provider.as_str()).unwrap(), provider);
+        }
+        assert!(BrokerProvider::parse("ibkr").is_err());
+    }
+
+    #[test]
+    fn data_vendors_only_have_live_keys() {
+        assert!(BrokerProvider::Tradier.validate_mode("paper").is_ok());
+        assert!(BrokerProvider::Polygon.validate_mode("live").is_ok());
+        let err = BrokerProvider::Finnhub.validate_mode("paper").unwrap_err();
+        assert!(err.contains("'live'"));
+    }
+
+    #[test]
+    fn validate_credentials_checks_schema() {
+        let polygon = BrokerProvider::Polygon;
+        assert!(polygon.validate_credentials(&creds(&[("api_key", "k")])).is_ok());
+        assert!(polygon.validate_credentials(&creds(&[("api_key", "")])).is_err());
+        assert!(polygon.validate_credentials(&creds(&[])).is_err());
+        let err = polygon
+            .validate_credentials(&creds(&[("api_key", "k"), ("secret", "s")]))
+            .unwrap_err();
+        assert!(err.contains("Unknown"));
+    }
+
+    #[test]
+    fn provider_info_serializes_camel_case() {
+        let json = serde_json::to_value(BrokerProviderInfo::from(BrokerProvider::Alpaca)).unwrap();
+        assert_eq!(json["provider"], "alpaca");
+        assert_eq!(json["secretFields"], serde_json::json!(["secret_key"]));
+    }
+}
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, DbPool};
+use crate::types::agent::{AgentActivity, AgentActivityType};
+use crate::workspace::WorkspaceDb;
+
+/// Rows returned by `agent_activity_list` when the filter has no explicit limit.
+const DEFAULT_LIST_LIMIT: u32 = 200;
+/// Hard cap on rows returned by a single list.
+const MAX_LIST_LIMIT: u32 = 5_000;
+
+/// The `activity` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ActivityConfig {
+    /// Activity entries older than this are pruned.
+    pub retention_days: u64,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self { retention_days: 30 }
+    }
+}
+
+/// Parse the `activity` section of the app config.
+pub fn activity_config(app_config: &serde_json::Value) -> ActivityConfig {
+    app_config
+        .get("activity")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Which entries `agent_activity_list` returns. Times are epoch millis (inclusive).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityFilter {
+    /// Only these activity types; `None` returns every type.
+    pub types: Option<Vec<AgentActivityType>>,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    /// Case-insensitive substring of the message.
+    pub search: Option<String>,
+    pub limit: Option<u32>,
+}
+
+fn type_name(activity_type: AgentActivityType) -> String {
+    serde_json::to_value(activity_type)
+        .ok()
+        .and_then(|v| v.as_str().map(String::from))
+        .unwrap_or_default()
+}
+
+pub fn activity_insert_db(pool: &DbPool, activity: &AgentActivity) -> Result<(), String> {
+    let data = activity
+        .data
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO agent_activity (type, message, timestamp, data) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![
+            type_name(activity.activity_type),
+            activity.message,
+            activity.timestamp as i64,
+            data,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Persisted activity matching `filter`, newest first.
+pub fn activity_list_db(pool: &DbPool, filter: &ActivityFilter) -> Result<Vec<AgentActivity>, String> {
+    // Matched with instr() against ",type_a,type_b,"
+    let types = filter.types.as_ref().map(|types| {
+        let names: Vec<String> = types.iter().map(|t| type_name(*t)).collect();
+        format!(",{},", names.join(","))
+    });
+    let search = filter
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_lowercase());
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_LIST_LIMIT)
+        .min(MAX_LIST_LIMIT);
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT type, message, timestamp, data FROM agent_activity
+             WHERE (?1 IS NULL OR instr(?1, ',' || type || ',') > 0)
+               AND timestamp >= ?2 AND timestamp <= ?3
+               AND (?4 IS NULL OR instr(lower(message), ?4) > 0)
+             ORDER BY timestamp DESC, id DESC LIMIT ?5",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                types,
+                filter.since.unwrap_or(0) as i64,
+                filter.until.map(|t| t as i64).unwrap_or(i64::MAX),
+                search,
+                limit,
+            ],
+            |row| {
+                let activity_type: String = row.get(0)?;
+                let data: Option<String> = row.get(3)?;
+                Ok((activity_type, row.get(1)?, row.get::<_, i64>(2)?, data))
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        let (activity_type, message, timestamp, data) = row.map_err(|e| e.to_string())?;
+        let Ok(activity_type) = serde_json::from_value(serde_json::Value::String(activity_type)) else {
+            continue;
+        };
+        results.push(AgentActivity {
+            activity_type,
+            message,
+            timestamp: timestamp as u64,
+            data: data.and_then(|d| serde_json::from_str(&d).ok()),
+        });
+    }
+    Ok(results)
+}
+
+/// Delete activity recorded before `cutoff` (epoch millis). Returns rows removed.
+pub fn activity_prune_db(pool: &DbPool, cutoff: u64) -> Result<usize, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM agent_activity WHERE timestamp < ?1", [cutoff as i64])
+        .map_err(|e| e.to_string())
+}
+
+/// Search the persisted agent activity feed, newest first.
+#[tauri::command]
+pub async fn agent_activity_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    filter: Option<ActivityFilter>,
+) -> Result<Vec<AgentActivity>, String> {
+    let pool = workspace.pool();
+    let filter = filter.unwrap_or_default();
+    db::run_blocking(&pool, move |pool| activity_list_db(pool, &filter)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn activity(activity_type: AgentActivityType, message: &str, timestamp: u64) -> AgentActivity {
+        AgentActivity {
+            activity_type,
+            message: message.to_string(),
+            timestamp,
+            data: None,
+        }
+    }
+
+    fn seed(pool: &DbPool) {
+        activity_insert_db(pool, &activity(AgentActivityType::CycleStart, "Cycle 1 started", 1_000)).unwrap();
+        activity_insert_db(pool, &activity(AgentActivityType::AnomalyDetected, "Volume spike on NET", 2_000)).unwrap();
+        activity_insert_db(pool, &activity(AgentActivityType::Error, "Provider timeout", 3_000)).unwrap();
+    }
+
+    #[test]
+    fn list_returns_newest_first_with_data() {
+        let (_dir, pool) = test_pool();
+        let mut with_data = activity(AgentActivityType::CycleEnd, "Cycle 1 done", 4_000);
+        with_data.data = Some([("anomalies".to_string(), serde_json::json!(2))].into());
+        seed(&pool);
+        activity_insert_db(&pool, &with_data).unwrap();
+
+        let all = activity_list_db(&pool, &ActivityFilter::default()).unwrap();
+        assert_eq!(all.len(), 4);
+        assert_eq!(all[0].activity_type, AgentActivityType::CycleEnd);
+        assert_eq!(all[0].data.as_ref().unwrap()["anomalies"], 2);
+        assert_eq!(all[3].timestamp, 1_000);
+    }
+
+    #[test]
+    fn list_filters_by_type_time_and_search() {
+        let (_dir, pool) = test_pool();
+        seed(&pool);
+
+        let filter = ActivityFilter {
+            types: Some(vec![AgentActivityType::Error, AgentActivityType::CycleStart]),
+            ..Default::default()
+        };
+        assert_eq!(activity_list_db(&pool, &filter).unwrap().len(), 2);
+
+        let filter = ActivityFilter {
+            since: Some(1_500),
+            until: Some(2_500),
+            ..Default::default()
+        };
+        let listed = activity_list_db(&pool, &filter).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].message, "Volume spike on NET");
+
+        let filter = ActivityFilter {
+            search: Some("TIMEOUT".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(activity_list_db(&pool, &filter).unwrap()[0].activity_type, AgentActivityType::Error);
+    }
+
+    #[test]
+    fn prune_drops_old_entries() {
+        let (_dir, pool) = test_pool();
+        seed(&pool);
+        assert_eq!(activity_prune_db(&pool, 2_500).unwrap(), 2);
+        assert_eq!(activity_list_db(&pool, &ActivityFilter::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn config_defaults_to_thirty_days() {
+        assert_eq!(activity_config(&serde_json::json!({})).retention_days, 30);
+        let config = activity_config(&serde_json::json!({"activity": {"retentionDays": 3}}));
+        assert_eq!(config.retention_days, 3);
+    }
+}
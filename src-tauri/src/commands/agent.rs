@@ -1,62 +1,327 @@
+use serde::{Deserialize, Serialize};
 use tracing::{debug, info};
 
+use crate::agent_logs::AgentLogLine;
 use crate::bridge::SidecarBridge;
-use crate::db::DbPool;
-use crate::types::agent::{AgentState, AgentStatus};
+use crate::bridge_error::BridgeError;
+use crate::commands::activity::activity_config;
+use crate::commands::profiles::active_profile_db;
+use crate::commands::prompts::{prompts_agent_params, prompts_list_db};
+use crate::commands::providers::{providers_routing_db, ProviderRouting};
+use crate::commands::sources::sources_list_db;
+use crate::commands::ticks::tick_recording_config;
+use crate::commands::trading::{agent_trading_params, guardrails, trading_halt_get_db};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::sidecar::{SidecarCommand, SidecarLaunchConfig};
+use crate::source_quarantine::quarantine_config;
+use crate::time::now_millis;
+use crate::types::agent::{AgentRestartPhase, AgentRestartProgress, AgentState, AgentStatus};
+use crate::types::config::DEFAULT_MODEL;
+use crate::workspace::WorkspaceDb;
 
-/// Read a value from app config JSON, falling back to an environment variable.
-pub(crate) fn config_or_env(app_config: &serde_json::Value, config_key: &str, env_var: &str) -> String {
-    app_config
-        .get(config_key)
-        .and_then(|k| k.as_str())
-        .filter(|s| !s.is_empty())
-        .map(String::from)
-        .unwrap_or_else(|| std::env::var(env_var).unwrap_or_default())
+/// An LLM API key from the keychain or legacy config field, falling back to an environment variable.
+fn llm_key_or_env(app_config: &serde_json::Value, provider: &str, env_var: &str) -> Result<String, String> {
+    Ok(crate::commands::credentials::llm_key_get_any(app_config, provider)?
+        .unwrap_or_else(|| std::env::var(env_var).unwrap_or_default()))
+}
+
+/// Environment variable holding `provider`'s LLM key when none is stored.
+fn llm_key_env_var(provider: &str) -> Option<&'static str> {
+    match provider {
+        "anthropic" => Some("ANTHROPIC_API_KEY"),
+        "openrouter" => Some("OPENROUTER_API_KEY"),
+        _ => None,
+    }
+}
+
+/// `provider`'s LLM key from wherever [`AgentSecrets::resolve`] looks, empty when unset.
+pub(crate) fn llm_key(app_config: &serde_json::Value, provider: &str) -> Result<String, String> {
+    match llm_key_env_var(provider) {
+        Some(env_var) => llm_key_or_env(app_config, provider, env_var),
+        None => Ok(String::new()),
+    }
+}
+
+/// Whether an LLM key for `provider` is set anywhere [`AgentSecrets::resolve`] looks.
+pub(crate) fn llm_key_configured(app_config: &serde_json::Value, provider: &str) -> Result<bool, String> {
+    Ok(!llm_key(app_config, provider)?.is_empty())
+}
+
+/// Trading mode whose Alpaca credentials the agent runs with unless another is requested.
+pub(crate) const AGENT_TRADING_MODE: &str = "paper";
+
+/// The requested trading mode, validated, or [`AGENT_TRADING_MODE`].
+pub(crate) fn resolve_trading_mode(mode: Option<String>) -> Result<String, String> {
+    let mode = mode.unwrap_or_else(|| AGENT_TRADING_MODE.to_string());
+    crate::broker::BrokerProvider::Alpaca.validate_mode(&mode)?;
+    Ok(mode)
+}
+
+/// Sidecar environment variables that carry secrets when `sidecar.secretsViaEnv` is set.
+pub(crate) const ENV_ALPACA_KEY_ID: &str = "FINWATCH_ALPACA_KEY_ID";
+pub(crate) const ENV_ALPACA_SECRET_KEY: &str = "FINWATCH_ALPACA_SECRET_KEY";
+pub(crate) const ENV_ANTHROPIC_API_KEY: &str = "FINWATCH_ANTHROPIC_API_KEY";
+pub(crate) const ENV_OPENROUTER_API_KEY: &str = "FINWATCH_OPENROUTER_API_KEY";
+
+/// Prefix marking an RPC string param as a reference to a sidecar environment variable.
+const ENV_REF_PREFIX: &str = "$env:";
+
+/// Load the app config with defaults filled in for unset settings.
+pub(crate) fn load_app_config(pool: &DbPool) -> Result<serde_json::Value, String> {
+    crate::commands::config::config_effective_db(pool)
+}
+
+/// Parse the `sidecar` section of the app config.
+pub(crate) fn sidecar_launch_config(
+    app_config: &serde_json::Value,
+) -> Result<SidecarLaunchConfig, String> {
+    Ok(app_config
+        .get("sidecar")
+        .cloned()
+        .map(serde_json::from_value)
+        .transpose()
+        .map_err(|e| format!("Invalid sidecar config: {}", e))?
+        .unwrap_or_default())
+}
+
+/// Broker and LLM secrets the agent needs to run.
+pub(crate) struct AgentSecrets {
+    pub alpaca_key_id: String,
+    pub alpaca_secret_key: String,
+    pub anthropic_api_key: String,
+    pub openrouter_api_key: String,
+}
+
+impl AgentSecrets {
+    /// Resolve Alpaca credentials of the active credential profile for `mode`
+    /// (keychain, DB, then env vars) and LLM keys (keychain, config, then env vars).
+    pub fn resolve(pool: &DbPool, app_config: &serde_json::Value, mode: &str) -> Result<Self, String> {
+        let creds = crate::commands::credentials::credentials_get_active(pool, mode)?;
+        let (alpaca_key_id, alpaca_secret_key) = match creds {
+            Some(c) => (c.key_id, c.secret_key),
+            None => {
+                let key = std::env::var("ALPACA_KEY_ID")
+                    .map_err(|_| "Alpaca credentials not set. Configure in Settings or set ALPACA_KEY_ID/ALPACA_SECRET_KEY env vars.")?;
+                let secret = std::env::var("ALPACA_SECRET_KEY")
+                    .map_err(|_| "ALPACA_SECRET_KEY env var not set.")?;
+                (key, secret)
+            }
+        };
+        Ok(Self {
+            alpaca_key_id,
+            alpaca_secret_key,
+            anthropic_api_key: llm_key_or_env(app_config, "anthropic", "ANTHROPIC_API_KEY")?,
+            openrouter_api_key: llm_key_or_env(app_config, "openrouter", "OPENROUTER_API_KEY")?,
+        })
+    }
+
+    /// Whether these secrets include a key for LLM `provider`.
+    pub fn has_llm_key(&self, provider: &str) -> bool {
+        match provider {
+            "anthropic" => !self.anthropic_api_key.is_empty(),
+            "openrouter" => !self.openrouter_api_key.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// The configured provider fallback chain routed for these keys and the
+    /// persisted provider health.
+    pub fn provider_routing(
+        &self,
+        pool: &DbPool,
+        app_config: &serde_json::Value,
+    ) -> Result<ProviderRouting, String> {
+        providers_routing_db(pool, app_config, |id| self.has_llm_key(id), now_millis())
+    }
+
+    /// Environment variables injected into the sidecar at spawn time.
+    pub fn env(&self) -> Vec<(String, String)> {
+        [
+            (ENV_ALPACA_KEY_ID, &self.alpaca_key_id),
+            (ENV_ALPACA_SECRET_KEY, &self.alpaca_secret_key),
+            (ENV_ANTHROPIC_API_KEY, &self.anthropic_api_key),
+            (ENV_OPENROUTER_API_KEY, &self.openrouter_api_key),
+        ]
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| (k.to_string(), v.clone()))
+        .collect()
+    }
+
+    /// The `alpaca` key fields for an RPC payload: inline values or env references.
+    pub fn alpaca_params(&self, via_env: bool) -> (String, String) {
+        (
+            secret_param(&self.alpaca_key_id, ENV_ALPACA_KEY_ID, via_env),
+            secret_param(&self.alpaca_secret_key, ENV_ALPACA_SECRET_KEY, via_env),
+        )
+    }
+
+    /// The LLM key fields for an RPC payload: inline values or env references.
+    pub fn llm_params(&self, via_env: bool) -> (String, String) {
+        (
+            secret_param(&self.anthropic_api_key, ENV_ANTHROPIC_API_KEY, via_env),
+            secret_param(&self.openrouter_api_key, ENV_OPENROUTER_API_KEY, via_env),
+        )
+    }
+}
+
+fn secret_param(value: &str, env_var: &str, via_env: bool) -> String {
+    if via_env && !value.is_empty() {
+        format!("{}{}", ENV_REF_PREFIX, env_var)
+    } else {
+        value.to_string()
+    }
+}
+
+/// Resolve how to launch the agent sidecar. Secrets are only captured at spawn
+/// time, so changed keys take effect on the next sidecar start.
+pub(crate) fn sidecar_command(
+    launch: &SidecarLaunchConfig,
+    secrets: &AgentSecrets,
+) -> Result<SidecarCommand, String> {
+    let mut command = SidecarCommand::discover(launch)?;
+    if launch.secrets_via_env {
+        command.env = secrets.env();
+    }
+    Ok(command)
+}
+
+/// `config` table key holding the parameters of the last `agent_start`.
+const LAST_START_KEY: &str = "agent_last_start";
+
+/// What `agent_start` was called with, replayed by `agent_restart`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastAgentStart {
+    pub config: serde_json::Value,
+    #[serde(default = "default_trading_mode")]
+    pub mode: String,
+}
+
+fn default_trading_mode() -> String {
+    AGENT_TRADING_MODE.to_string()
+}
+
+fn record_last_start(pool: &DbPool, start: &LastAgentStart) -> Result<(), String> {
+    let json = serde_json::to_string(start).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![LAST_START_KEY, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The parameters the agent was last started with, if it was ever started.
+pub fn last_start_db(pool: &DbPool) -> Result<Option<LastAgentStart>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM config WHERE key = ?1", [LAST_START_KEY], |row| {
+            row.get(0)
+        })
+        .ok();
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+}
+
+/// The symbols in an `agent_start` config override, or the default watch list.
+pub(crate) fn session_symbols(config: &serde_json::Value) -> Vec<String> {
+    config
+        .get("symbols")
+        .and_then(|s| s.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_else(|| vec!["NET".to_string()])
+}
+
+/// Add (`watch`) or remove `symbol` from the symbols of the last `agent_start`,
+/// so restarts keep the edited watch list. Returns the updated list.
+pub fn update_session_symbols_db(pool: &DbPool, symbol: &str, watch: bool) -> Result<Vec<String>, String> {
+    let mut start = last_start_db(pool)?.unwrap_or_else(|| LastAgentStart {
+        config: serde_json::json!({}),
+        mode: default_trading_mode(),
+    });
+    let mut symbols = session_symbols(&start.config);
+    if watch {
+        if !symbols.iter().any(|s| s == symbol) {
+            symbols.push(symbol.to_string());
+        }
+    } else {
+        symbols.retain(|s| s != symbol);
+    }
+    if !start.config.is_object() {
+        start.config = serde_json::json!({});
+    }
+    start.config["symbols"] = serde_json::json!(symbols);
+    record_last_start(pool, &start)?;
+    Ok(symbols)
+}
+
+fn normalize_symbol(symbol: &str) -> Result<String, String> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol must not be empty".to_string());
+    }
+    Ok(symbol)
 }
 
 #[tauri::command]
 pub async fn agent_start(
     app: tauri::AppHandle,
-    pool: tauri::State<'_, DbPool>,
+    workspace: tauri::State<'_, WorkspaceDb>,
     bridge: tauri::State<'_, SidecarBridge>,
     config: serde_json::Value,
-) -> Result<serde_json::Value, String> {
-    // Get Alpaca credentials: keychain first, then DB, then env vars
-    let creds = crate::commands::credentials::credentials_get_any(&pool, "paper")?;
-    let (alpaca_key, alpaca_secret) = match creds {
-        Some(c) => (c.key_id, c.secret_key),
-        None => {
-            let key = std::env::var("ALPACA_KEY_ID")
-                .map_err(|_| "Alpaca credentials not set. Configure in Settings or set ALPACA_KEY_ID/ALPACA_SECRET_KEY env vars.")?;
-            let secret = std::env::var("ALPACA_SECRET_KEY")
-                .map_err(|_| "ALPACA_SECRET_KEY env var not set.")?;
-            (key, secret)
-        }
+    mode: Option<String>,
+) -> Result<serde_json::Value, BridgeError> {
+    let start = LastAgentStart {
+        config,
+        mode: resolve_trading_mode(mode)?,
     };
+    start_agent(app, &workspace.pool(), &bridge, start).await
+}
 
-    // Get LLM keys from config DB, falling back to env vars
-    let app_config = crate::commands::config::config_get_db(&pool)?;
-    let app_config: serde_json::Value =
-        serde_json::from_str(&app_config).unwrap_or(serde_json::json!({}));
-
-    let anthropic_key = config_or_env(&app_config, "anthropicApiKey", "ANTHROPIC_API_KEY");
-    let openrouter_key = config_or_env(&app_config, "openrouterApiKey", "OPENROUTER_API_KEY");
+/// Resolve secrets and config for `start.mode`, spawn the sidecar if needed,
+/// and send `agent:start` with `start.config` overriding the stored symbols and feed.
+pub(crate) async fn start_agent(
+    app: tauri::AppHandle,
+    pool: &DbPool,
+    bridge: &SidecarBridge,
+    start: LastAgentStart,
+) -> Result<serde_json::Value, BridgeError> {
+    let record = start.clone();
+    let (app_config, secrets, routing, profile, sources, halt, prompts) = db::run_blocking(pool, move |pool| {
+        let app_config = load_app_config(pool)?;
+        let secrets = AgentSecrets::resolve(pool, &app_config, &record.mode)?;
+        let routing = secrets.provider_routing(pool, &app_config)?;
+        record_last_start(pool, &record)?;
+        Ok((
+            app_config,
+            secrets,
+            routing,
+            active_profile_db(pool)?,
+            sources_list_db(pool)?,
+            trading_halt_get_db(pool)?,
+            prompts_list_db(pool, None)?,
+        ))
+    })
+    .await?;
+    let LastAgentStart { config, mode } = start;
+    let launch = sidecar_launch_config(&app_config)?;
+    let (alpaca_key, alpaca_secret) = secrets.alpaca_params(launch.secrets_via_env);
+    let (anthropic_key, openrouter_key) = secrets.llm_params(launch.secrets_via_env);
 
     let model = app_config
         .get("model")
         .and_then(|m| m.as_str())
-        .unwrap_or("claude-haiku-4-5-20251001");
+        .unwrap_or(DEFAULT_MODEL);
 
     // Build agent:start params merging stored config with provided overrides
-    let symbols = config
-        .get("symbols")
-        .and_then(|s| s.as_array())
-        .map(|arr| {
-            arr.iter()
-                .filter_map(|v| v.as_str().map(String::from))
-                .collect::<Vec<_>>()
-        })
-        .unwrap_or_else(|| vec!["NET".to_string()]);
+    let symbols = session_symbols(&config);
 
     let feed = config
         .get("feed")
@@ -69,6 +334,7 @@ pub async fn agent_start(
             "secretKey": alpaca_secret,
             "symbols": symbols,
             "feed": feed,
+            "mode": mode,
         },
         "llm": {
             "anthropicApiKey": anthropic_key,
@@ -76,25 +342,135 @@ pub async fn agent_start(
             "model": model,
             "maxTokens": 4096,
             "temperature": 0.3,
+            "providers": routing.order,
         },
+        "profile": profile,
+        "sources": sources,
+        "trading": agent_trading_params(&halt, &guardrails(&app_config)),
+        "prompts": prompts_agent_params(&prompts),
     });
 
-    info!(?symbols, feed, "Starting agent");
+    let prompt_versions: Vec<String> = prompts.iter().map(|p| format!("{}@{}", p.name, p.version)).collect();
+    info!(
+        ?symbols,
+        feed,
+        mode,
+        ?profile,
+        sources = sources.len(),
+        providers = ?routing.order,
+        prompts = ?prompt_versions,
+        "Starting agent"
+    );
+
+    bridge.apply_launch_config(&launch);
+    bridge.set_tick_recording(tick_recording_config(&app_config));
+    bridge.set_activity_retention(activity_config(&app_config));
+    bridge.set_quarantine_config(quarantine_config(&app_config));
+
+    // Secrets passed through the environment stay those of the mode the agent
+    // was spawned for, so a mode switch needs a fresh process
+    if launch.secrets_via_env && bridge.running_for_other_mode(&mode) {
+        info!(from = ?bridge.trading_mode(), to = mode, "Respawning agent for new trading mode");
+        bridge
+            .shutdown(crate::SHUTDOWN_GRACE)
+            .map_err(BridgeError::sidecar_down)?;
+    }
 
     // Spawn sidecar if not running
     if !bridge.is_running() {
         debug!("Spawning sidecar");
-        bridge.spawn(app, "agent/src/index.ts")?;
+        bridge
+            .spawn(app, &sidecar_command(&launch, &secrets)?)
+            .map_err(BridgeError::sidecar_down)?;
         debug!("Sidecar spawned");
     } else {
         debug!("Sidecar already running");
     }
+    bridge.set_trading_mode(&mode);
 
     // Send agent:start command
     debug!("Sending agent:start JSON-RPC request");
-    let response = bridge.send_request("agent:start", Some(agent_params))?;
-    debug!(result = ?response.result, "agent:start response received");
-    Ok(response.result.unwrap_or(serde_json::json!({"status": "started"})))
+    let result = bridge.call("agent:start", Some(agent_params))?;
+    debug!(?result, "agent:start response received");
+    if result.is_null() {
+        return Ok(serde_json::json!({"status": "started"}));
+    }
+    Ok(result)
+}
+
+fn emit_restart_progress(app: &tauri::AppHandle, phase: AgentRestartPhase, error: Option<String>) {
+    let _ = emit_event(app, event_names::AGENT_RESTART, AgentRestartProgress { phase, error });
+}
+
+/// Stop the sidecar, then start it again with the overrides and trading mode
+/// of the last `agent_start`. Credentials and config are re-resolved, so changed keys and
+/// settings take effect. Progress is reported through `agent:restart` events.
+#[tauri::command]
+pub async fn agent_restart(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+) -> Result<serde_json::Value, BridgeError> {
+    let pool = workspace.pool();
+    let start = db::run_blocking(&pool, last_start_db)
+        .await?
+        .ok_or_else(|| "The agent has not been started yet".to_string())?;
+
+    emit_restart_progress(&app, AgentRestartPhase::Stopping, None);
+    if let Err(e) = bridge.shutdown(crate::SHUTDOWN_GRACE) {
+        emit_restart_progress(&app, AgentRestartPhase::Failed, Some(e.clone()));
+        return Err(e.into());
+    }
+
+    emit_restart_progress(&app, AgentRestartPhase::Starting, None);
+    info!("Restarting agent");
+    match start_agent(app.clone(), &pool, &bridge, start).await {
+        Ok(result) => {
+            emit_restart_progress(&app, AgentRestartPhase::Started, None);
+            Ok(result)
+        }
+        Err(e) => {
+            emit_restart_progress(&app, AgentRestartPhase::Failed, Some(e.to_string()));
+            Err(e)
+        }
+    }
+}
+
+/// Send `agent:watch`/`agent:unwatch` to a running agent, then persist the change.
+async fn change_watch_list(
+    pool: &DbPool,
+    bridge: &SidecarBridge,
+    symbol: &str,
+    watch: bool,
+) -> Result<Vec<String>, BridgeError> {
+    let symbol = normalize_symbol(symbol)?;
+    if bridge.is_running() {
+        let method = if watch { "agent:watch" } else { "agent:unwatch" };
+        debug!(method, %symbol, "Updating agent watch list");
+        bridge.call(method, Some(serde_json::json!({ "symbol": symbol })))?;
+    }
+    Ok(db::run_blocking(pool, move |pool| update_session_symbols_db(pool, &symbol, watch)).await?)
+}
+
+/// Start monitoring `symbol` without restarting the agent. When the agent is
+/// stopped, the symbol is only added to the list used by the next restart.
+#[tauri::command]
+pub async fn agent_watch_symbol(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    symbol: String,
+) -> Result<Vec<String>, BridgeError> {
+    change_watch_list(&workspace.pool(), &bridge, &symbol, true).await
+}
+
+/// Stop monitoring `symbol` without restarting the agent.
+#[tauri::command]
+pub async fn agent_unwatch_symbol(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    symbol: String,
+) -> Result<Vec<String>, BridgeError> {
+    change_watch_list(&workspace.pool(), &bridge, &symbol, false).await
 }
 
 #[tauri::command]
@@ -113,7 +489,7 @@ pub fn agent_status(
     bridge: tauri::State<'_, SidecarBridge>,
 ) -> AgentStatus {
     let state = if bridge.is_running() {
-        if bridge.is_healthy(std::time::Duration::from_secs(90)) {
+        if bridge.is_healthy(crate::bridge::MAX_SILENCE) {
             AgentState::Running
         } else {
             AgentState::Unhealthy
@@ -129,5 +505,125 @@ pub fn agent_status(
         total_anomalies: 0,
         uptime: 0,
         last_error: None,
+        resources: bridge.resource_usage(),
+        health: bridge.health(),
+    }
+}
+
+/// Default number of log lines returned by `agent_logs_read`.
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Read the most recent agent stderr lines from `~/.finwatch/logs/agent/`.
+#[tauri::command]
+pub fn agent_logs_read(
+    lines: Option<usize>,
+    level_filter: Option<String>,
+) -> Result<Vec<AgentLogLine>, String> {
+    crate::agent_logs::read_recent(
+        &crate::agent_logs::agent_logs_dir(),
+        lines.unwrap_or(DEFAULT_LOG_LINES),
+        level_filter.as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secrets() -> AgentSecrets {
+        AgentSecrets {
+            alpaca_key_id: "PKTEST".to_string(),
+            alpaca_secret_key: "secret".to_string(),
+            anthropic_api_key: "sk-ant".to_string(),
+            openrouter_api_key: String::new(),
+        }
+    }
+
+    #[test]
+    fn params_are_inline_by_default() {
+        let (key, secret) = secrets().alpaca_params(false);
+        assert_eq!(key, "PKTEST");
+        assert_eq!(secret, "secret");
+    }
+
+    #[test]
+    fn params_become_env_references_when_enabled() {
+        let s = secrets();
+        let (key, secret) = s.alpaca_params(true);
+        assert_eq!(key, "$env:FINWATCH_ALPACA_KEY_ID");
+        assert_eq!(secret, "$env:FINWATCH_ALPACA_SECRET_KEY");
+        let (anthropic, openrouter) = s.llm_params(true);
+        assert_eq!(anthropic, "$env:FINWATCH_ANTHROPIC_API_KEY");
+        // Empty keys stay empty so the agent can fall back to its own env
+        assert_eq!(openrouter, "");
+    }
+
+    #[test]
+    fn env_skips_empty_secrets() {
+        let env = secrets().env();
+        assert_eq!(env.len(), 3);
+        assert!(env.contains(&(ENV_ALPACA_KEY_ID.to_string(), "PKTEST".to_string())));
+        assert!(!env.iter().any(|(k, _)| k == ENV_OPENROUTER_API_KEY));
+    }
+
+    #[test]
+    fn last_start_roundtrips_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        assert_eq!(last_start_db(&pool).unwrap(), None);
+
+        let start = LastAgentStart {
+            config: serde_json::json!({ "symbols": ["AAPL", "BTC/USD"], "feed": "sip" }),
+            mode: "live".to_string(),
+        };
+        record_last_start(&pool, &start).unwrap();
+        record_last_start(&pool, &start).unwrap();
+        assert_eq!(last_start_db(&pool).unwrap(), Some(start));
+        // Stored next to, not in place of, the main config
+        assert_eq!(crate::commands::config::config_get_db(&pool).unwrap(), "{}");
+    }
+
+    #[test]
+    fn watch_list_edits_persist_in_last_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+
+        // Without a recorded start, edits apply to the default list
+        assert_eq!(update_session_symbols_db(&pool, "AAPL", true).unwrap(), ["NET", "AAPL"]);
+        assert_eq!(update_session_symbols_db(&pool, "AAPL", true).unwrap(), ["NET", "AAPL"]);
+        assert_eq!(update_session_symbols_db(&pool, "NET", false).unwrap(), ["AAPL"]);
+
+        let start = LastAgentStart {
+            config: serde_json::json!({ "symbols": ["TSLA"], "feed": "sip" }),
+            mode: "live".to_string(),
+        };
+        record_last_start(&pool, &start).unwrap();
+        assert_eq!(update_session_symbols_db(&pool, "BTC/USD", true).unwrap(), ["TSLA", "BTC/USD"]);
+        let stored = last_start_db(&pool).unwrap().unwrap();
+        assert_eq!(stored.mode, "live");
+        assert_eq!(stored.config["feed"], "sip");
+        assert_eq!(session_symbols(&stored.config), ["TSLA", "BTC/USD"]);
+    }
+
+    #[test]
+    fn symbols_are_normalized() {
+        assert_eq!(normalize_symbol(" aapl ").unwrap(), "AAPL");
+        assert!(normalize_symbol("  ").is_err());
+    }
+
+    #[test]
+    fn trading_mode_defaults_to_paper_and_is_validated() {
+        assert_eq!(resolve_trading_mode(None).unwrap(), "paper");
+        assert_eq!(resolve_trading_mode(Some("live".to_string())).unwrap(), "live");
+        assert!(resolve_trading_mode(Some("sandbox".to_string())).is_err());
+    }
+
+    #[test]
+    fn sidecar_launch_config_reads_secrets_flag() {
+        let config = serde_json::json!({ "sidecar": { "secretsViaEnv": true } });
+        assert!(sidecar_launch_config(&config).unwrap().secrets_via_env);
+        assert!(!sidecar_launch_config(&serde_json::json!({})).unwrap().secrets_via_env);
     }
 }
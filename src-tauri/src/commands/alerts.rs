@@ -0,0 +1,312 @@
+use crate::alerts::{AlertCondition, AlertEngine, AlertRule, AlertRuleSpec, AlertTrigger};
+use crate::db::{self, DbPool};
+use crate::workspace::WorkspaceDb;
+
+/// Triggers returned by `alerts_history` when no limit is given.
+const DEFAULT_HISTORY_LIMIT: u32 = 200;
+
+/// Check a rule before it is stored.
+pub fn validate_rule(spec: &AlertRuleSpec) -> Result<(), String> {
+    if spec.symbol.trim().is_empty() {
+        return Err("Alert symbol must not be empty".to_string());
+    }
+    if !spec.threshold.is_finite() {
+        return Err("Alert threshold must be a number".to_string());
+    }
+    if spec.condition == AlertCondition::PercentChange && spec.threshold <= 0.0 {
+        return Err("Percent-change alerts need a positive threshold".to_string());
+    }
+    Ok(())
+}
+
+fn condition_str(condition: AlertCondition) -> Result<String, String> {
+    serde_json::to_value(condition)
+        .map_err(|e| e.to_string())
+        .map(|v| v.as_str().unwrap_or_default().to_string())
+}
+
+fn condition_from_str(value: &str) -> rusqlite::Result<AlertCondition> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(2, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+fn rule_from_row(row: &rusqlite::Row) -> rusqlite::Result<AlertRule> {
+    let condition: String = row.get(2)?;
+    Ok(AlertRule {
+        id: row.get(0)?,
+        symbol: row.get(1)?,
+        condition: condition_from_str(&condition)?,
+        threshold: row.get(3)?,
+        cooldown_secs: row.get::<_, i64>(4)?.max(0) as u64,
+        enabled: row.get(5)?,
+        last_triggered: row.get::<_, Option<i64>>(6)?.map(|t| t.max(0) as u64),
+    })
+}
+
+const RULE_COLUMNS: &str = "id, symbol, condition, threshold, cooldown_secs, enabled, last_triggered";
+
+/// All alert rules, oldest first.
+pub fn alert_rules_list_db(pool: &DbPool) -> Result<Vec<AlertRule>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {RULE_COLUMNS} FROM alert_rules ORDER BY id"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([], rule_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn alert_rule_get_db(pool: &DbPool, id: i64) -> Result<Option<AlertRule>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let result = conn.query_row(
+        &format!("SELECT {RULE_COLUMNS} FROM alert_rules WHERE id = ?1"),
+        [id],
+        rule_from_row,
+    );
+    match result {
+        Ok(rule) => Ok(Some(rule)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Store a new rule. Symbols are stored uppercase.
+pub fn alert_rule_add_db(pool: &DbPool, spec: &AlertRuleSpec) -> Result<AlertRule, String> {
+    validate_rule(spec)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO alert_rules (symbol, condition, threshold, cooldown_secs, enabled)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            spec.symbol.trim().to_uppercase(),
+            condition_str(spec.condition)?,
+            spec.threshold,
+            spec.cooldown_secs as i64,
+            spec.enabled,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    drop(conn);
+    alert_rule_get_db(pool, id)?.ok_or_else(|| format!("Alert rule not found: {id}"))
+}
+
+/// Replace the definition of an existing rule, keeping its trigger history.
+pub fn alert_rule_update_db(pool: &DbPool, id: i64, spec: &AlertRuleSpec) -> Result<AlertRule, String> {
+    validate_rule(spec)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let changed = conn
+        .execute(
+            "UPDATE alert_rules SET symbol = ?2, condition = ?3, threshold = ?4,
+                cooldown_secs = ?5, enabled = ?6, updated_at = datetime('now')
+             WHERE id = ?1",
+            rusqlite::params![
+                id,
+                spec.symbol.trim().to_uppercase(),
+                condition_str(spec.condition)?,
+                spec.threshold,
+                spec.cooldown_secs as i64,
+                spec.enabled,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Alert rule not found: {id}"));
+    }
+    drop(conn);
+    alert_rule_get_db(pool, id)?.ok_or_else(|| format!("Alert rule not found: {id}"))
+}
+
+/// Delete a rule and its triggers. Returns whether it existed.
+pub fn alert_rule_remove_db(pool: &DbPool, id: i64) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM alert_triggers WHERE rule_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    let removed = conn
+        .execute("DELETE FROM alert_rules WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+/// Store a trigger and start the rule's cooldown.
+pub fn alert_trigger_record_db(pool: &DbPool, trigger: &AlertTrigger) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO alert_triggers (rule_id, symbol, condition, threshold, price, triggered_at, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            trigger.rule_id,
+            trigger.symbol,
+            condition_str(trigger.condition)?,
+            trigger.threshold,
+            trigger.price,
+            trigger.triggered_at as i64,
+            trigger.message,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "UPDATE alert_rules SET last_triggered = ?2 WHERE id = ?1",
+        rusqlite::params![trigger.rule_id, trigger.triggered_at as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Recorded triggers, newest first, optionally for one rule.
+pub fn alert_triggers_list_db(pool: &DbPool, rule_id: Option<i64>, limit: u32) -> Result<Vec<AlertTrigger>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT rule_id, symbol, condition, threshold, price, triggered_at, message
+             FROM alert_triggers WHERE (?1 IS NULL OR rule_id = ?1)
+             ORDER BY triggered_at DESC, id DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params![rule_id, limit], |row| {
+            let condition: String = row.get(2)?;
+            Ok(AlertTrigger {
+                rule_id: row.get(0)?,
+                symbol: row.get(1)?,
+                condition: condition_from_str(&condition)?,
+                threshold: row.get(3)?,
+                price: row.get(4)?,
+                triggered_at: row.get::<_, i64>(5)?.max(0) as u64,
+                message: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn alerts_rules_list(workspace: tauri::State<'_, WorkspaceDb>) -> Result<Vec<AlertRule>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, alert_rules_list_db).await
+}
+
+#[tauri::command]
+pub async fn alerts_rule_add(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    engine: tauri::State<'_, AlertEngine>,
+    rule: AlertRuleSpec,
+) -> Result<AlertRule, String> {
+    let pool = workspace.pool();
+    let added = db::run_blocking(&pool, move |pool| alert_rule_add_db(pool, &rule)).await?;
+    engine.invalidate();
+    Ok(added)
+}
+
+#[tauri::command]
+pub async fn alerts_rule_update(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    engine: tauri::State<'_, AlertEngine>,
+    id: i64,
+    rule: AlertRuleSpec,
+) -> Result<AlertRule, String> {
+    let pool = workspace.pool();
+    let updated = db::run_blocking(&pool, move |pool| alert_rule_update_db(pool, id, &rule)).await?;
+    engine.invalidate();
+    Ok(updated)
+}
+
+/// Delete a rule and its trigger history. Returns whether it existed.
+#[tauri::command]
+pub async fn alerts_rule_remove(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    engine: tauri::State<'_, AlertEngine>,
+    id: i64,
+) -> Result<bool, String> {
+    let pool = workspace.pool();
+    let removed = db::run_blocking(&pool, move |pool| alert_rule_remove_db(pool, id)).await?;
+    engine.invalidate();
+    Ok(removed)
+}
+
+/// Triggered alerts, newest first.
+#[tauri::command]
+pub async fn alerts_history(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    rule_id: Option<i64>,
+    limit: Option<u32>,
+) -> Result<Vec<AlertTrigger>, String> {
+    let pool = workspace.pool();
+    let limit = limit.unwrap_or(DEFAULT_HISTORY_LIMIT);
+    db::run_blocking(&pool, move |pool| alert_triggers_list_db(pool, rule_id, limit)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn spec() -> AlertRuleSpec {
+        AlertRuleSpec {
+            symbol: " spy ".to_string(),
+            condition: AlertCondition::Crosses,
+            threshold: 500.0,
+            cooldown_secs: 300,
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn add_update_remove_roundtrip() {
+        let (_dir, pool) = test_pool();
+        let added = alert_rule_add_db(&pool, &spec()).unwrap();
+        assert_eq!(added.symbol, "SPY");
+        assert_eq!(added.last_triggered, None);
+        assert_eq!(alert_rules_list_db(&pool).unwrap(), vec![added.clone()]);
+
+        let updated = alert_rule_update_db(
+            &pool,
+            added.id,
+            &AlertRuleSpec { condition: AlertCondition::Below, enabled: false, ..spec() },
+        )
+        .unwrap();
+        assert_eq!(updated.condition, AlertCondition::Below);
+        assert!(!updated.enabled);
+        assert!(alert_rule_update_db(&pool, 999, &spec()).is_err());
+
+        assert!(alert_rule_remove_db(&pool, added.id).unwrap());
+        assert!(!alert_rule_remove_db(&pool, added.id).unwrap());
+        assert!(alert_rules_list_db(&pool).unwrap().is_empty());
+    }
+
+    #[test]
+    fn recording_a_trigger_starts_the_cooldown() {
+        let (_dir, pool) = test_pool();
+        let rule = alert_rule_add_db(&pool, &spec()).unwrap();
+        let trigger = AlertTrigger {
+            rule_id: rule.id,
+            symbol: "SPY".to_string(),
+            condition: AlertCondition::Crosses,
+            threshold: 500.0,
+            price: 501.0,
+            triggered_at: 5_000,
+            message: "SPY crossed 500.00 (now 501.00)".to_string(),
+        };
+        alert_trigger_record_db(&pool, &trigger).unwrap();
+        assert_eq!(alert_rule_get_db(&pool, rule.id).unwrap().unwrap().last_triggered, Some(5_000));
+        assert_eq!(alert_triggers_list_db(&pool, Some(rule.id), 10).unwrap(), vec![trigger]);
+        assert!(alert_triggers_list_db(&pool, Some(999), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn validation_rejects_bad_rules() {
+        assert!(validate_rule(&spec()).is_ok());
+        assert!(validate_rule(&AlertRuleSpec { symbol: " ".to_string(), ..spec() }).is_err());
+        assert!(validate_rule(&AlertRuleSpec { threshold: f64::NAN, ..spec() }).is_err());
+        let pct = AlertRuleSpec { condition: AlertCondition::PercentChange, threshold: 0.0, ..spec() };
+        assert!(validate_rule(&pct).is_err());
+    }
+}
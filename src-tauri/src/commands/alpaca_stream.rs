@@ -0,0 +1,26 @@
+use crate::alpaca_stream::{alpaca_stream_config, AlpacaStream, AlpacaStreamStatus};
+use crate::commands::config::config_effective_db;
+use crate::workspace::WorkspaceDb;
+
+#[tauri::command]
+pub fn alpaca_stream_status(stream: tauri::State<'_, AlpacaStream>) -> AlpacaStreamStatus {
+    stream.status()
+}
+
+/// Connect to the Alpaca market data stream with the configured feed and
+/// symbols, even if `alpacaStream.enabled` is off.
+#[tauri::command]
+pub fn alpaca_stream_start(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    stream: tauri::State<'_, AlpacaStream>,
+) -> Result<AlpacaStreamStatus, String> {
+    let config = alpaca_stream_config(&config_effective_db(&workspace.pool())?);
+    stream.start(app, config)
+}
+
+/// Disconnect from the stream. Returns whether it was running.
+#[tauri::command]
+pub fn alpaca_stream_stop(stream: tauri::State<'_, AlpacaStream>) -> bool {
+    stream.stop()
+}
@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::analytics::{
+    annualized_volatility, beta, correlation, drawdowns, excess_kurtosis, historical_var, mean, rolling, sharpe_ratio,
+    simple_returns, skewness, TRADING_DAYS,
+};
+use crate::commands::agent::resolve_trading_mode;
+use crate::commands::backtest::{backtest_get_db, backtest_get_trades_db};
+use crate::commands::bars::{bars_load, validate_timeframe, Bar};
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::is_offline;
+use crate::commands::performance::{performance_from_snapshots, snapshots_list_db, PerformanceRange};
+use crate::commands::portfolio::portfolio_config;
+use crate::db::{self, DbPool};
+use crate::report::{equity_curve, CurvePoint};
+use crate::time::now_millis;
+use crate::types::data::TickRange;
+use crate::workspace::WorkspaceDb;
+
+pub const DEFAULT_BENCHMARK: &str = "SPY";
+const DEFAULT_CONFIDENCE: f64 = 0.95;
+/// Most symbols `correlation_matrix` compares at once.
+const MAX_CORRELATION_SYMBOLS: usize = 50;
+/// Trading days in each window of `returns_stats`' rolling series.
+const DEFAULT_ROLLING_WINDOW: usize = 20;
+
+/// Daily returns keyed by trading date (`YYYY-MM-DD`), oldest first.
+type DatedReturns = Vec<(String, f64)>;
+
+fn bar_date(timestamp: u64) -> String {
+    DateTime::from_timestamp_millis(timestamp as i64)
+        .map(|t| t.date_naive().format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+/// Close-to-close returns of daily `bars`, keyed by the later bar's date.
+fn bar_returns(bars: &[Bar]) -> DatedReturns {
+    let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+    bars.iter()
+        .skip(1)
+        .map(|b| bar_date(b.timestamp))
+        .zip(simple_returns(&closes))
+        .collect()
+}
+
+/// Close-to-close returns of `bars`, keyed by the later bar's timestamp.
+fn timed_returns(bars: &[Bar]) -> Vec<(u64, f64)> {
+    let closes: Vec<f64> = bars.iter().map(|b| b.close).collect();
+    bars.iter()
+        .skip(1)
+        .map(|b| b.timestamp)
+        .zip(simple_returns(&closes))
+        .collect()
+}
+
+/// Bars of `symbol` and `timeframe` from `range`'s start, cached first.
+async fn range_bars(pool: &DbPool, symbol: &str, timeframe: &str, range: PerformanceRange) -> Result<Vec<Bar>, String> {
+    let range = TickRange {
+        from: range.start_millis(now_millis()),
+        to: None,
+        limit: None,
+    };
+    bars_load(pool, symbol, timeframe, &range).await
+}
+
+async fn daily_bars(pool: &DbPool, symbol: &str, range: PerformanceRange) -> Result<Vec<Bar>, String> {
+    range_bars(pool, symbol, "1Day", range).await
+}
+
+/// Pair up `returns` and `benchmark` on the dates both have.
+fn align(returns: &DatedReturns, benchmark: &DatedReturns) -> (Vec<f64>, Vec<f64>) {
+    let by_date: HashMap<&str, f64> = benchmark.iter().map(|(d, r)| (d.as_str(), *r)).collect();
+    returns
+        .iter()
+        .filter_map(|(d, r)| by_date.get(d.as_str()).map(|b| (*r, *b)))
+        .unzip()
+}
+
+/// What `risk_metrics` measures: `{"symbol": "AAPL"}` or `"portfolio"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskTarget {
+    Symbol(String),
+    Portfolio,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskMetrics {
+    pub target: RiskTarget,
+    pub range: PerformanceRange,
+    pub benchmark: String,
+    /// Daily returns the metrics are computed from.
+    pub observations: usize,
+    /// First and last trading date of those returns.
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub confidence: f64,
+    /// One-day historical value at risk, as a non-negative fraction.
+    pub var: Option<f64>,
+    /// `var` in account currency, for the portfolio.
+    pub var_amount: Option<f64>,
+    /// Annualized standard deviation of daily returns.
+    pub volatility: Option<f64>,
+    /// Beta against `benchmark` over the dates both have returns for.
+    pub beta: Option<f64>,
+    /// Computed from cached data in offline mode.
+    pub stale: bool,
+}
+
+/// Value at risk, annualized volatility, and beta over `returns`.
+pub fn risk_from_returns(
+    target: RiskTarget,
+    range: PerformanceRange,
+    benchmark: (&str, &DatedReturns),
+    confidence: f64,
+    returns: &DatedReturns,
+) -> RiskMetrics {
+    let values: Vec<f64> = returns.iter().map(|(_, r)| *r).collect();
+    let (asset, bench) = align(returns, benchmark.1);
+    RiskMetrics {
+        target,
+        range,
+        benchmark: benchmark.0.to_string(),
+        observations: values.len(),
+        from: returns.first().map(|(d, _)| d.clone()),
+        to: returns.last().map(|(d, _)| d.clone()),
+        confidence,
+        var: historical_var(&values, confidence),
+        var_amount: None,
+        volatility: annualized_volatility(&values, TRADING_DAYS),
+        beta: beta(&asset, &bench),
+        stale: false,
+    }
+}
+
+/// Historical VaR, annualized volatility, and beta against `benchmark`
+/// (SPY by default) for a symbol, from its daily bars, or for the portfolio
+/// in `mode`, from its daily snapshots.
+#[tauri::command]
+pub async fn risk_metrics(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    target: RiskTarget,
+    range: Option<PerformanceRange>,
+    benchmark: Option<String>,
+    confidence: Option<f64>,
+    mode: Option<String>,
+) -> Result<RiskMetrics, String> {
+    let range = range.unwrap_or_default();
+    let confidence = confidence.unwrap_or(DEFAULT_CONFIDENCE);
+    if confidence.is_nan() || confidence <= 0.0 || confidence >= 1.0 {
+        return Err("confidence must be between 0 and 1".to_string());
+    }
+    let benchmark = benchmark
+        .unwrap_or_else(|| DEFAULT_BENCHMARK.to_string())
+        .trim()
+        .to_uppercase();
+    let pool = workspace.pool();
+    let benchmark_returns = bar_returns(&daily_bars(&pool, &benchmark, range).await?);
+
+    let (target, returns, equity) = match target {
+        RiskTarget::Symbol(symbol) => {
+            let symbol = symbol.trim().to_uppercase();
+            let returns = bar_returns(&daily_bars(&pool, &symbol, range).await?);
+            (RiskTarget::Symbol(symbol), returns, None)
+        }
+        RiskTarget::Portfolio => {
+            let mode = mode.map(|m| resolve_trading_mode(Some(m))).transpose()?;
+            let since = DateTime::from_timestamp_millis(now_millis() as i64)
+                .and_then(|now| range.start(now.date_naive()))
+                .map(|d| d.format("%Y-%m-%d").to_string());
+            let history = db::run_blocking(&pool, move |pool| {
+                let mode = match mode {
+                    Some(m) => m,
+                    None => portfolio_config(&config_effective_db(pool)?).mode,
+                };
+                let snapshots = snapshots_list_db(pool, &mode, since.as_deref())?;
+                Ok(performance_from_snapshots(&mode, range, &snapshots))
+            })
+            .await?;
+            let equity = history.points.last().map(|p| p.equity);
+            let returns = history.points.into_iter().map(|p| (p.date, p.daily_return)).collect();
+            (RiskTarget::Portfolio, returns, equity)
+        }
+    };
+    if returns.len() < 2 {
+        return Err("Not enough daily history in range to measure risk".to_string());
+    }
+    let mut metrics = risk_from_returns(target, range, (&benchmark, &benchmark_returns), confidence, &returns);
+    metrics.var_amount = metrics.var.zip(equity).map(|(var, equity)| var * equity);
+    metrics.stale = db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await?;
+    Ok(metrics)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorrelationMatrix {
+    pub symbols: Vec<String>,
+    pub timeframe: String,
+    pub range: PerformanceRange,
+    /// `matrix[i][j]` correlates the returns of `symbols[i]` and `symbols[j]`;
+    /// `None` with fewer than two shared bars or when either did not move.
+    pub matrix: Vec<Vec<Option<f64>>>,
+    /// Returns each pair shares; the diagonal counts each symbol's returns.
+    pub observations: Vec<Vec<usize>>,
+    /// Computed from cached bars in offline mode.
+    pub stale: bool,
+}
+
+/// Pairwise correlations of `returns`, matched by timestamp, with the
+/// number of returns each pair shares. Pairs are computed in parallel.
+pub fn correlations(returns: &[Vec<(u64, f64)>]) -> (Vec<Vec<Option<f64>>>, Vec<Vec<usize>>) {
+    let n = returns.len();
+    let by_time: Vec<HashMap<u64, f64>> = returns.iter().map(|r| r.iter().copied().collect()).collect();
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+    let computed: Vec<(usize, usize, usize, Option<f64>)> = pairs
+        .into_par_iter()
+        .map(|(i, j)| {
+            let (xs, ys): (Vec<f64>, Vec<f64>) = returns[i]
+                .iter()
+                .filter_map(|(t, x)| by_time[j].get(t).map(|y| (*x, *y)))
+                .unzip();
+            (i, j, xs.len(), correlation(&xs, &ys))
+        })
+        .collect();
+
+    let mut matrix = vec![vec![None; n]; n];
+    let mut observations = vec![vec![0; n]; n];
+    for (i, series) in returns.iter().enumerate() {
+        let values: Vec<f64> = series.iter().map(|(_, r)| *r).collect();
+        matrix[i][i] = correlation(&values, &values);
+        observations[i][i] = values.len();
+    }
+    for (i, j, shared, value) in computed {
+        matrix[i][j] = value;
+        matrix[j][i] = value;
+        observations[i][j] = shared;
+        observations[j][i] = shared;
+    }
+    (matrix, observations)
+}
+
+/// Correlations between the `timeframe` bar returns (daily by default) of
+/// `symbols` over `range`, for rendering as a heatmap.
+#[tauri::command]
+pub async fn correlation_matrix(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbols: Vec<String>,
+    range: Option<PerformanceRange>,
+    timeframe: Option<String>,
+) -> Result<CorrelationMatrix, String> {
+    let range = range.unwrap_or_default();
+    let timeframe = timeframe.unwrap_or_else(|| "1Day".to_string());
+    validate_timeframe(&timeframe)?;
+    let mut unique: Vec<String> = Vec::new();
+    for symbol in symbols {
+        let symbol = symbol.trim().to_uppercase();
+        if !symbol.is_empty() && !unique.contains(&symbol) {
+            unique.push(symbol);
+        }
+    }
+    if unique.len() < 2 {
+        return Err("At least two symbols are required".to_string());
+    }
+    if unique.len() > MAX_CORRELATION_SYMBOLS {
+        return Err(format!(
+            "At most {} symbols can be correlated at once",
+            MAX_CORRELATION_SYMBOLS
+        ));
+    }
+    let pool = workspace.pool();
+    let mut returns = Vec::with_capacity(unique.len());
+    for symbol in &unique {
+        returns.push(timed_returns(&range_bars(&pool, symbol, &timeframe, range).await?));
+    }
+    let (matrix, observations) = tauri::async_runtime::spawn_blocking(move || correlations(&returns))
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(CorrelationMatrix {
+        symbols: unique,
+        timeframe,
+        range,
+        matrix,
+        observations,
+        stale: db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await?,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReturnStats {
+    pub symbol: String,
+    pub range: PerformanceRange,
+    /// Trading days in each rolling window.
+    pub window: usize,
+    /// Trading date of each return.
+    pub dates: Vec<String>,
+    /// Close-to-close daily returns, as fractions.
+    pub returns: Vec<f64>,
+    /// Rolling series, one value per return; `None` until a window fills.
+    pub rolling_volatility: Vec<Option<f64>>,
+    pub rolling_sharpe: Vec<Option<f64>>,
+    pub rolling_skew: Vec<Option<f64>>,
+    pub rolling_kurtosis: Vec<Option<f64>>,
+    /// Over the whole range; volatility and Sharpe are annualized.
+    pub volatility: Option<f64>,
+    pub sharpe: Option<f64>,
+    pub skew: Option<f64>,
+    /// Excess kurtosis, 0 for normally distributed returns.
+    pub kurtosis: Option<f64>,
+    /// Computed from cached bars in offline mode.
+    pub stale: bool,
+}
+
+/// Return statistics of `returns` with rolling series over `window` days.
+/// `risk_free_rate` is annual.
+pub fn return_stats(
+    symbol: &str,
+    range: PerformanceRange,
+    window: usize,
+    risk_free_rate: f64,
+    returns: DatedReturns,
+) -> ReturnStats {
+    let risk_free = risk_free_rate / TRADING_DAYS;
+    let (dates, values): (Vec<String>, Vec<f64>) = returns.into_iter().unzip();
+    ReturnStats {
+        symbol: symbol.to_string(),
+        range,
+        window,
+        rolling_volatility: rolling(&values, window, |w| annualized_volatility(w, TRADING_DAYS)),
+        rolling_sharpe: rolling(&values, window, |w| sharpe_ratio(w, risk_free, TRADING_DAYS)),
+        rolling_skew: rolling(&values, window, skewness),
+        rolling_kurtosis: rolling(&values, window, excess_kurtosis),
+        volatility: annualized_volatility(&values, TRADING_DAYS),
+        sharpe: sharpe_ratio(&values, risk_free, TRADING_DAYS),
+        skew: skewness(&values),
+        kurtosis: excess_kurtosis(&values),
+        dates,
+        returns: values,
+        stale: false,
+    }
+}
+
+/// Daily returns of `symbol` over `range` with rolling volatility, Sharpe
+/// ratio, skew, and kurtosis over `window` trading days (20 by default).
+/// `riskFreeRate` is the annual rate the Sharpe ratio is measured against.
+#[tauri::command]
+pub async fn returns_stats(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbol: String,
+    range: Option<PerformanceRange>,
+    window: Option<usize>,
+    risk_free_rate: Option<f64>,
+) -> Result<ReturnStats, String> {
+    let range = range.unwrap_or_default();
+    let window = window.unwrap_or(DEFAULT_ROLLING_WINDOW);
+    if window < 2 {
+        return Err("window must be at least 2".to_string());
+    }
+    let risk_free_rate = risk_free_rate.unwrap_or(0.0);
+    if !risk_free_rate.is_finite() {
+        return Err("riskFreeRate must be a number".to_string());
+    }
+    let symbol = symbol.trim().to_uppercase();
+    let pool = workspace.pool();
+    let returns = bar_returns(&daily_bars(&pool, &symbol, range).await?);
+    if returns.is_empty() {
+        return Err(format!("Not enough daily bars for {} in range", symbol));
+    }
+    let mut stats = return_stats(&symbol, range, window, risk_free_rate, returns);
+    stats.stale = db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await?;
+    Ok(stats)
+}
+
+/// Whose equity `drawdown_analysis` examines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesSource {
+    /// A backtest's equity curve; the id is the backtest's.
+    Backtest,
+    /// The recorded daily portfolio snapshots; the id is `paper` or `live`.
+    Portfolio,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnderwaterPoint {
+    pub date: String,
+    pub equity: f64,
+    /// Fall from the running peak, as a non-positive fraction.
+    pub drawdown: f64,
+}
+
+/// One fall from a peak until equity regained it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawdownPeriod {
+    pub peak_date: String,
+    pub trough_date: String,
+    /// When equity got back to the peak; `None` while still under water.
+    pub recovery_date: Option<String>,
+    /// Peak to trough, as a negative fraction.
+    pub depth: f64,
+    /// Calendar days from the peak to recovery, or to the last point.
+    pub duration_days: Option<i64>,
+    /// Calendar days from the trough to recovery.
+    pub recovery_days: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DrawdownAnalysis {
+    pub source: SeriesSource,
+    pub id: String,
+    /// Deepest drawdown, as a non-positive fraction.
+    pub max_drawdown: f64,
+    /// Drawdown at the last point.
+    pub current_drawdown: f64,
+    pub longest_duration_days: Option<i64>,
+    /// Mean days from trough to recovery over recovered drawdowns.
+    pub avg_recovery_days: Option<f64>,
+    /// Share of points below the running peak.
+    pub time_underwater: f64,
+    /// Drawdowns, deepest first.
+    pub drawdowns: Vec<DrawdownPeriod>,
+    pub underwater: Vec<UnderwaterPoint>,
+}
+
+/// Calendar days between two `YYYY-MM-DD` dates; `None` if either is not one.
+fn days_between(from: &str, to: &str) -> Option<i64> {
+    let parse = |d: &str| NaiveDate::parse_from_str(d.get(..10)?, "%Y-%m-%d").ok();
+    Some((parse(to)? - parse(from)?).num_days())
+}
+
+/// Drawdowns, recovery times, and the underwater curve of `curve`.
+pub fn drawdown_from_curve(source: SeriesSource, id: &str, curve: &[CurvePoint]) -> DrawdownAnalysis {
+    let values: Vec<f64> = curve.iter().map(|p| p.value).collect();
+    let (underwater, spans) = drawdowns(&values);
+    let last_date = curve.last().map_or("", |p| p.date.as_str());
+    let mut periods: Vec<DrawdownPeriod> = spans
+        .iter()
+        .map(|span| {
+            let peak_date = &curve[span.peak].date;
+            let recovery_date = span.recovery.map(|i| curve[i].date.clone());
+            DrawdownPeriod {
+                peak_date: peak_date.clone(),
+                trough_date: curve[span.trough].date.clone(),
+                duration_days: days_between(peak_date, recovery_date.as_deref().unwrap_or(last_date)),
+                recovery_days: recovery_date
+                    .as_deref()
+                    .and_then(|d| days_between(&curve[span.trough].date, d)),
+                recovery_date,
+                depth: span.depth,
+            }
+        })
+        .collect();
+    periods.sort_by(|a, b| a.depth.total_cmp(&b.depth));
+    let recoveries: Vec<f64> = periods
+        .iter()
+        .filter_map(|p| p.recovery_days)
+        .map(|d| d as f64)
+        .collect();
+    DrawdownAnalysis {
+        source,
+        id: id.to_string(),
+        max_drawdown: underwater.iter().copied().fold(0.0, f64::min),
+        current_drawdown: underwater.last().copied().unwrap_or(0.0),
+        longest_duration_days: periods.iter().filter_map(|p| p.duration_days).max(),
+        avg_recovery_days: mean(&recoveries),
+        time_underwater: if underwater.is_empty() {
+            0.0
+        } else {
+            underwater.iter().filter(|d| **d < 0.0).count() as f64 / underwater.len() as f64
+        },
+        drawdowns: periods,
+        underwater: curve
+            .iter()
+            .zip(underwater)
+            .map(|(p, drawdown)| UnderwaterPoint {
+                date: p.date.clone(),
+                equity: p.value,
+                drawdown,
+            })
+            .collect(),
+    }
+}
+
+/// Max drawdown, drawdown durations, recovery times, and the underwater
+/// curve of a backtest's equity curve (`id` is the backtest) or of the
+/// portfolio's daily snapshot history (`id` is `paper` or `live`).
+#[tauri::command]
+pub async fn drawdown_analysis(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    series_source: SeriesSource,
+    id: String,
+) -> Result<DrawdownAnalysis, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        let curve = match series_source {
+            SeriesSource::Backtest => {
+                let summary = backtest_get_db(pool, &id)?;
+                equity_curve(&summary, &backtest_get_trades_db(pool, &id)?)
+            }
+            SeriesSource::Portfolio => {
+                let mode = resolve_trading_mode(Some(id.clone()))?;
+                snapshots_list_db(pool, &mode, None)?
+                    .into_iter()
+                    .map(|s| CurvePoint {
+                        date: s.date,
+                        value: s.equity,
+                    })
+                    .collect()
+            }
+        };
+        if curve.is_empty() {
+            return Err(format!("No equity history for {}", id));
+        }
+        Ok(drawdown_from_curve(series_source, &id, &curve))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: u64, close: f64) -> Bar {
+        Bar {
+            timestamp: 1_750_046_400_000 + day * 86_400_000,
+            open: close,
+            high: close,
+            low: close,
+            close,
+            volume: 1_000.0,
+        }
+    }
+
+    #[test]
+    fn risk_aligns_benchmark_by_date() {
+        let spy = [bar(0, 100.0), bar(1, 101.0), bar(2, 99.0), bar(3, 100.0), bar(4, 102.0)];
+        // No bar for day 2, so the stock's day 3 return spans two days
+        let stock = [bar(0, 50.0), bar(1, 51.0), bar(3, 50.0), bar(4, 52.0)];
+        let spy_returns = bar_returns(&spy);
+        assert_eq!(spy_returns[0].0, "2025-06-17");
+        let stock_returns = bar_returns(&stock);
+
+        let (asset, bench) = align(&stock_returns, &spy_returns);
+        assert_eq!(asset.len(), 3);
+        assert_eq!(bench[1], spy_returns[2].1);
+
+        let metrics = risk_from_returns(
+            RiskTarget::Symbol("AAPL".to_string()),
+            PerformanceRange::All,
+            ("SPY", &spy_returns),
+            0.95,
+            &stock_returns,
+        );
+        assert_eq!(metrics.observations, 3);
+        assert_eq!(metrics.from.as_deref(), Some("2025-06-17"));
+        assert!(metrics.var.unwrap() > 0.0);
+        assert!(metrics.volatility.unwrap() > 0.0);
+        assert!(metrics.beta.is_some());
+
+        let json = serde_json::to_value(&metrics).unwrap();
+        assert_eq!(json["target"], serde_json::json!({ "symbol": "AAPL" }));
+        let portfolio: RiskTarget = serde_json::from_str("\"portfolio\"").unwrap();
+        assert_eq!(portfolio, RiskTarget::Portfolio);
+    }
+
+    #[test]
+    fn correlations_match_returns_by_timestamp() {
+        let a = [bar(0, 100.0), bar(1, 102.0), bar(2, 101.0), bar(3, 104.0)];
+        let b = [bar(0, 50.0), bar(1, 51.0), bar(2, 50.5), bar(3, 52.0)];
+        let c = [bar(0, 10.0), bar(1, 9.8), bar(2, 9.9), bar(3, 9.6)];
+        let flat = [bar(2, 7.0), bar(3, 7.0)];
+        let returns: Vec<_> = [&a[..], &b, &c, &flat].iter().map(|bars| timed_returns(bars)).collect();
+        let (matrix, observations) = correlations(&returns);
+
+        assert!((matrix[0][0].unwrap() - 1.0).abs() < 1e-12);
+        assert!(matrix[0][1].unwrap() > 0.99);
+        assert!(matrix[0][2].unwrap() < 0.0);
+        assert_eq!(matrix[0][1], matrix[1][0]);
+        assert_eq!(matrix[3][3], None);
+        assert_eq!(matrix[0][3], None);
+        assert_eq!(observations[0][1], 3);
+        assert_eq!(observations[0][3], 1);
+        assert_eq!(observations[3][3], 1);
+    }
+
+    #[test]
+    fn return_stats_fill_rolling_windows() {
+        let closes = [100.0, 101.0, 99.5, 102.0, 101.0, 103.5, 104.0];
+        let bars: Vec<Bar> = closes.iter().enumerate().map(|(i, c)| bar(i as u64, *c)).collect();
+        let stats = return_stats("SPY", PerformanceRange::All, 3, 0.0, bar_returns(&bars));
+        assert_eq!(stats.returns.len(), 6);
+        assert_eq!(stats.dates.len(), 6);
+        assert_eq!(stats.rolling_volatility.len(), 6);
+        assert!(stats.rolling_volatility[1].is_none());
+        assert!(stats.rolling_volatility[2].is_some());
+        // Skew needs three returns, kurtosis four
+        assert!(stats.rolling_skew[2].is_some());
+        assert!(stats.rolling_kurtosis.iter().all(Option::is_none));
+        assert!(stats.sharpe.unwrap() > 0.0);
+        assert!(stats.kurtosis.is_some());
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert!(json["rollingSharpe"][0].is_null());
+    }
+
+    #[test]
+    fn drawdown_periods_measure_recovery_in_days() {
+        let curve: Vec<CurvePoint> = [
+            ("2025-06-02", 100.0),
+            ("2025-06-03", 95.0),
+            ("2025-06-05", 102.0),
+            ("2025-06-06", 90.0),
+            ("2025-06-09", 96.0),
+        ]
+        .iter()
+        .map(|(date, value)| CurvePoint {
+            date: date.to_string(),
+            value: *value,
+        })
+        .collect();
+        let analysis = drawdown_from_curve(SeriesSource::Portfolio, "paper", &curve);
+        assert!((analysis.max_drawdown - (90.0 / 102.0 - 1.0)).abs() < 1e-12);
+        assert_eq!(analysis.current_drawdown, analysis.underwater[4].drawdown);
+        assert_eq!(analysis.time_underwater, 0.6);
+
+        // Deepest first: the open drawdown from 102, then the recovered one from 100
+        let [open, recovered] = &analysis.drawdowns[..] else {
+            panic!("expected two drawdowns")
+        };
+        assert_eq!(open.peak_date, "2025-06-05");
+        assert_eq!(open.recovery_date, None);
+        assert_eq!(open.duration_days, Some(4));
+        assert_eq!(recovered.recovery_date.as_deref(), Some("2025-06-05"));
+        assert_eq!(recovered.duration_days, Some(3));
+        assert_eq!(recovered.recovery_days, Some(2));
+        assert_eq!(analysis.longest_duration_days, Some(4));
+        assert_eq!(analysis.avg_recovery_days, Some(2.0));
+
+        let json = serde_json::to_value(&analysis).unwrap();
+        assert_eq!(json["source"], "portfolio");
+        assert_eq!(days_between("start", "2025-06-05"), None);
+    }
+}
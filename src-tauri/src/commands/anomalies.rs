@@ -1,21 +1,66 @@
-use crate::db::DbPool;
-use crate::types::anomaly::{Anomaly, AnomalyFeedback, AnomalyFilter, Severity};
+use crate::commands::offline::{is_offline, queue_push_db, QueuedOp};
+use crate::commands::performance::PerformanceRange;
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::anomaly::{
+    Anomaly, AnomalyFeedback, AnomalyFilter, AnomalyNote, AnomalyStatus, HeatmapBucket, HeatmapCell, Severity,
+};
+use crate::workspace::WorkspaceDb;
 
-pub fn anomalies_insert_db(pool: &DbPool, anomaly: &Anomaly) -> Result<(), String> {
-    let conn = pool.get().map_err(|e| e.to_string())?;
-    let metrics_json = serde_json::to_string(&anomaly.metrics).map_err(|e| e.to_string())?;
-    let severity_str = serde_json::to_value(anomaly.severity)
+/// Severity reads as the override when there is one; the last column is the
+/// detected severity, only while overridden.
+const ANOMALY_COLUMNS: &str = "id, COALESCE(override_severity, severity), source, symbol, timestamp, description, \
+     metrics, pre_screen_score, session_id, status, snoozed_until, \
+     CASE WHEN override_severity IS NULL THEN NULL ELSE severity END";
+
+fn severity_str(severity: Severity) -> Result<String, String> {
+    Ok(serde_json::to_value(severity)
         .map_err(|e| e.to_string())?
         .as_str()
         .unwrap_or("low")
-        .to_string();
+        .to_string())
+}
+
+fn parse_severity(s: &str) -> Severity {
+    serde_json::from_str(&format!("\"{}\"", s)).unwrap_or(Severity::Low)
+}
+
+fn anomaly_from_row(row: &rusqlite::Row) -> rusqlite::Result<Anomaly> {
+    let severity: String = row.get(1)?;
+    let metrics_str: String = row.get(6)?;
+    let status_str: String = row.get(9)?;
+    Ok(Anomaly {
+        id: row.get(0)?,
+        severity: parse_severity(&severity),
+        source: row.get(2)?,
+        symbol: row.get(3)?,
+        timestamp: row.get(4)?,
+        description: row.get(5)?,
+        metrics: serde_json::from_str(&metrics_str).unwrap_or_default(),
+        pre_screen_score: row.get(7)?,
+        session_id: row.get(8)?,
+        status: serde_json::from_str(&format!("\"{}\"", status_str)).unwrap_or_default(),
+        snoozed_until: row.get::<_, Option<i64>>(10)?.map(|t| t.max(0) as u64),
+        original_severity: row.get::<_, Option<String>>(11)?.map(|s| parse_severity(&s)),
+    })
+}
+
+pub fn anomalies_insert_db(pool: &DbPool, anomaly: &Anomaly) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let metrics_json = serde_json::to_string(&anomaly.metrics).map_err(|e| e.to_string())?;
+    // An overridden anomaly stores its detected severity underneath the override
+    let (detected, override_severity) = match anomaly.original_severity {
+        Some(original) => (original, Some(severity_str(anomaly.severity)?)),
+        None => (anomaly.severity, None),
+    };
 
     conn.execute(
-        "INSERT INTO anomalies (id, severity, source, symbol, timestamp, description, metrics, pre_screen_score, session_id)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        "INSERT INTO anomalies (id, severity, source, symbol, timestamp, description, metrics, pre_screen_score, session_id, override_severity)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         rusqlite::params![
             anomaly.id,
-            severity_str,
+            severity_str(detected)?,
             anomaly.source,
             anomaly.symbol,
             anomaly.timestamp,
@@ -23,6 +68,7 @@ pub fn anomalies_insert_db(pool: &DbPool, anomaly: &Anomaly) -> Result<(), Strin
             metrics_json,
             anomaly.pre_screen_score,
             anomaly.session_id,
+            override_severity,
         ],
     )
     .map_err(|e| e.to_string())?;
@@ -34,7 +80,7 @@ pub fn anomalies_list_db(
     filter: &Option<AnomalyFilter>,
 ) -> Result<Vec<Anomaly>, String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
-    let mut sql = "SELECT id, severity, source, symbol, timestamp, description, metrics, pre_screen_score, session_id FROM anomalies WHERE 1=1".to_string();
+    let mut sql = format!("SELECT {} FROM anomalies WHERE 1=1", ANOMALY_COLUMNS);
     let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
     if let Some(f) = filter {
@@ -45,7 +91,10 @@ pub fn anomalies_list_db(
                     .enumerate()
                     .map(|(i, _)| format!("?{}", params.len() + i + 1))
                     .collect();
-                sql.push_str(&format!(" AND severity IN ({})", placeholders.join(",")));
+                sql.push_str(&format!(
+                    " AND COALESCE(override_severity, severity) IN ({})",
+                    placeholders.join(",")
+                ));
                 for s in sevs {
                     let s_str = serde_json::to_value(s).unwrap();
                     params.push(Box::new(s_str.as_str().unwrap().to_string()));
@@ -64,6 +113,31 @@ pub fn anomalies_list_db(
             params.push(Box::new(since as i64));
             sql.push_str(&format!(" AND timestamp >= ?{}", params.len()));
         }
+        if let Some(incident_id) = f.incident_id {
+            params.push(Box::new(incident_id));
+            sql.push_str(&format!(" AND incident_id = ?{}", params.len()));
+        }
+        if let Some(ref statuses) = f.status {
+            if !statuses.is_empty() {
+                let placeholders: Vec<String> = statuses
+                    .iter()
+                    .enumerate()
+                    .map(|(i, _)| format!("?{}", params.len() + i + 1))
+                    .collect();
+                sql.push_str(&format!(" AND status IN ({})", placeholders.join(",")));
+                for s in statuses {
+                    params.push(Box::new(s.as_str()));
+                }
+            }
+        }
+    }
+    if !filter.as_ref().is_some_and(|f| f.include_snoozed == Some(true)) {
+        // A snooze that has ended puts the anomaly back in the list
+        params.push(Box::new(now_millis() as i64));
+        sql.push_str(&format!(
+            " AND NOT (status = 'snoozed' AND snoozed_until > ?{})",
+            params.len()
+        ));
     }
 
     sql.push_str(" ORDER BY timestamp DESC");
@@ -78,22 +152,7 @@ pub fn anomalies_list_db(
 
     let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
     let rows = stmt
-        .query_map(param_refs.as_slice(), |row| {
-            let severity_str: String = row.get(1)?;
-            let metrics_str: String = row.get(6)?;
-            Ok(Anomaly {
-                id: row.get(0)?,
-                severity: serde_json::from_str(&format!("\"{}\"", severity_str))
-                    .unwrap_or(Severity::Low),
-                source: row.get(2)?,
-                symbol: row.get(3)?,
-                timestamp: row.get(4)?,
-                description: row.get(5)?,
-                metrics: serde_json::from_str(&metrics_str).unwrap_or_default(),
-                pre_screen_score: row.get(7)?,
-                session_id: row.get(8)?,
-            })
-        })
+        .query_map(param_refs.as_slice(), anomaly_from_row)
         .map_err(|e| e.to_string())?;
 
     let mut results = Vec::new();
@@ -103,6 +162,99 @@ pub fn anomalies_list_db(
     Ok(results)
 }
 
+/// Anomaly counts per `bucket` since `since` (epoch millis), aggregated in SQL.
+pub fn anomalies_heatmap_db(
+    pool: &DbPool,
+    since: Option<u64>,
+    bucket: HeatmapBucket,
+) -> Result<Vec<HeatmapCell>, String> {
+    let (columns, order) = match bucket {
+        HeatmapBucket::DayHour => (
+            "CAST(strftime('%H', timestamp / 1000, 'unixepoch') AS INTEGER), NULL",
+            "day, 2",
+        ),
+        HeatmapBucket::SymbolDay => ("NULL, symbol", "symbol, day"),
+    };
+    let sql = format!(
+        "SELECT strftime('%Y-%m-%d', timestamp / 1000, 'unixepoch') AS day, {}, COUNT(*)
+         FROM anomalies WHERE ?1 IS NULL OR timestamp >= ?1
+         GROUP BY 1, 2, 3 ORDER BY {}",
+        columns, order
+    );
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([since.map(|t| t as i64)], |row| {
+            Ok(HeatmapCell {
+                day: row.get(0)?,
+                hour: row.get(1)?,
+                symbol: row.get(2)?,
+                count: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn anomaly_get_db(pool: &DbPool, id: &str) -> Result<Option<Anomaly>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!("SELECT {} FROM anomalies WHERE id = ?1", ANOMALY_COLUMNS);
+    match conn.query_row(&sql, [id], anomaly_from_row) {
+        Ok(anomaly) => Ok(Some(anomaly)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Move anomaly `id` to `status`, with `snoozed_until` kept only for a
+/// snooze, and return it.
+pub fn anomaly_status_set_db(
+    pool: &DbPool,
+    id: &str,
+    status: AnomalyStatus,
+    snoozed_until: Option<u64>,
+) -> Result<Anomaly, String> {
+    let snoozed_until = snoozed_until.filter(|_| status == AnomalyStatus::Snoozed);
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE anomalies SET status = ?2, snoozed_until = ?3 WHERE id = ?1",
+            rusqlite::params![id, status.as_str(), snoozed_until.map(|t| t as i64)],
+        )
+        .map_err(|e| e.to_string())?;
+    drop(conn);
+    if updated == 0 {
+        return Err(format!("Unknown anomaly {}", id));
+    }
+    anomaly_get_db(pool, id)?.ok_or_else(|| format!("Unknown anomaly {}", id))
+}
+
+/// Snooze anomaly `id` until `until` (epoch millis), which must be in the future.
+pub fn anomaly_snooze_db(pool: &DbPool, id: &str, until: u64, now_ms: u64) -> Result<Anomaly, String> {
+    if until <= now_ms {
+        return Err("Snooze must end in the future".to_string());
+    }
+    anomaly_status_set_db(pool, id, AnomalyStatus::Snoozed, Some(until))
+}
+
+/// Override the severity of anomaly `id`, or clear the override with `None`.
+/// Overriding to the detected severity clears it too.
+pub fn anomaly_severity_set_db(pool: &DbPool, id: &str, severity: Option<Severity>) -> Result<Anomaly, String> {
+    let override_severity = severity.map(severity_str).transpose()?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE anomalies SET override_severity = NULLIF(?2, severity) WHERE id = ?1",
+            rusqlite::params![id, override_severity],
+        )
+        .map_err(|e| e.to_string())?;
+    drop(conn);
+    if updated == 0 {
+        return Err(format!("Unknown anomaly {}", id));
+    }
+    anomaly_get_db(pool, id)?.ok_or_else(|| format!("Unknown anomaly {}", id))
+}
+
 pub fn anomalies_feedback_db(pool: &DbPool, feedback: &AnomalyFeedback) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     let verdict_str = serde_json::to_value(feedback.verdict)
@@ -119,21 +271,241 @@ pub fn anomalies_feedback_db(pool: &DbPool, feedback: &AnomalyFeedback) -> Resul
     Ok(())
 }
 
+const NOTE_COLUMNS: &str = "id, anomaly_id, body, created_at, updated_at";
+
+fn note_from_row(row: &rusqlite::Row) -> rusqlite::Result<AnomalyNote> {
+    Ok(AnomalyNote {
+        id: row.get(0)?,
+        anomaly_id: row.get(1)?,
+        body: row.get(2)?,
+        created_at: row.get::<_, i64>(3)?.max(0) as u64,
+        updated_at: row.get::<_, Option<i64>>(4)?.map(|t| t.max(0) as u64),
+    })
+}
+
+fn note_body(body: &str) -> Result<String, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("Note cannot be empty".to_string());
+    }
+    Ok(body.to_string())
+}
+
+fn note_get_db(pool: &DbPool, id: i64) -> Result<AnomalyNote, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!("SELECT {} FROM anomaly_notes WHERE id = ?1", NOTE_COLUMNS);
+    match conn.query_row(&sql, [id], note_from_row) {
+        Ok(note) => Ok(note),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(format!("Unknown note {}", id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Notes on `anomaly_id`, oldest first.
+pub fn anomaly_notes_list_db(pool: &DbPool, anomaly_id: &str) -> Result<Vec<AnomalyNote>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!(
+        "SELECT {} FROM anomaly_notes WHERE anomaly_id = ?1 ORDER BY created_at, id",
+        NOTE_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([anomaly_id], note_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn anomaly_note_add_db(pool: &DbPool, anomaly_id: &str, body: &str, now_ms: u64) -> Result<AnomalyNote, String> {
+    let body = note_body(body)?;
+    if anomaly_get_db(pool, anomaly_id)?.is_none() {
+        return Err(format!("Unknown anomaly {}", anomaly_id));
+    }
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO anomaly_notes (anomaly_id, body, created_at) VALUES (?1, ?2, ?3)",
+        rusqlite::params![anomaly_id, body, now_ms as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    drop(conn);
+    note_get_db(pool, id)
+}
+
+pub fn anomaly_note_edit_db(pool: &DbPool, id: i64, body: &str, now_ms: u64) -> Result<AnomalyNote, String> {
+    let body = note_body(body)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE anomaly_notes SET body = ?2, updated_at = ?3 WHERE id = ?1",
+            rusqlite::params![id, body, now_ms as i64],
+        )
+        .map_err(|e| e.to_string())?;
+    drop(conn);
+    if updated == 0 {
+        return Err(format!("Unknown note {}", id));
+    }
+    note_get_db(pool, id)
+}
+
+pub fn anomaly_note_delete_db(pool: &DbPool, id: i64) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM anomaly_notes WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Unknown note {}", id));
+    }
+    Ok(())
+}
+
+/// Record that `anomaly_id` was notified at `now_ms`. Returns `false` if it
+/// already had been, so the caller skips the duplicate.
+pub fn anomaly_notification_claim_db(pool: &DbPool, anomaly_id: &str, now_ms: u64) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO anomaly_notifications (anomaly_id, delivered_at) VALUES (?1, ?2)",
+            rusqlite::params![anomaly_id, now_ms as i64],
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(inserted > 0)
+}
+
 // Tauri command wrappers
 #[tauri::command]
-pub fn anomalies_list(
-    pool: tauri::State<'_, DbPool>,
+pub async fn anomalies_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
     filter: Option<AnomalyFilter>,
 ) -> Result<Vec<Anomaly>, String> {
-    anomalies_list_db(&pool, &filter)
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| anomalies_list_db(pool, &filter)).await
 }
 
+/// Anomaly counts over `range` for a calendar or heatmap view.
 #[tauri::command]
-pub fn anomalies_feedback(
-    pool: tauri::State<'_, DbPool>,
+pub async fn anomalies_heatmap(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    range: Option<PerformanceRange>,
+    bucket: Option<HeatmapBucket>,
+) -> Result<Vec<HeatmapCell>, String> {
+    let since = range.unwrap_or_default().start_millis(now_millis());
+    let bucket = bucket.unwrap_or_default();
+    db::run_blocking(&workspace.pool(), move |pool| anomalies_heatmap_db(pool, since, bucket)).await
+}
+
+async fn update_anomaly(
+    app: &tauri::AppHandle,
+    workspace: &WorkspaceDb,
+    update: impl FnOnce(&DbPool) -> Result<Anomaly, String> + Send + 'static,
+) -> Result<Anomaly, String> {
+    let anomaly = db::run_blocking(&workspace.pool(), update).await?;
+    let _ = emit_event(app, event_names::ANOMALY_UPDATED, &anomaly);
+    Ok(anomaly)
+}
+
+/// Mark an anomaly as seen; it stays listed until resolved.
+#[tauri::command]
+pub async fn anomalies_acknowledge(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: String,
+) -> Result<Anomaly, String> {
+    update_anomaly(&app, &workspace, move |pool| {
+        anomaly_status_set_db(pool, &id, AnomalyStatus::Acknowledged, None)
+    })
+    .await
+}
+
+/// Hide an anomaly from the default list until `until` (epoch millis).
+#[tauri::command]
+pub async fn anomalies_snooze(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: String,
+    until: u64,
+) -> Result<Anomaly, String> {
+    update_anomaly(&app, &workspace, move |pool| {
+        anomaly_snooze_db(pool, &id, until, now_millis())
+    })
+    .await
+}
+
+/// Close an anomaly out of triage.
+#[tauri::command]
+pub async fn anomalies_resolve(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: String,
+) -> Result<Anomaly, String> {
+    update_anomaly(&app, &workspace, move |pool| {
+        anomaly_status_set_db(pool, &id, AnomalyStatus::Resolved, None)
+    })
+    .await
+}
+
+/// Correct an anomaly's severity; `None` restores the detected one.
+#[tauri::command]
+pub async fn anomalies_set_severity(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: String,
+    severity: Option<Severity>,
+) -> Result<Anomaly, String> {
+    update_anomaly(&app, &workspace, move |pool| {
+        anomaly_severity_set_db(pool, &id, severity)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn anomalies_feedback(
+    workspace: tauri::State<'_, WorkspaceDb>,
     id: String,
     feedback: AnomalyFeedback,
 ) -> Result<(), String> {
+    let pool = workspace.pool();
     let _ = id; // anomaly_id is in the feedback struct
-    anomalies_feedback_db(&pool, &feedback)
+    db::run_blocking(&pool, move |pool| {
+        if is_offline(pool) {
+            queue_push_db(pool, &QueuedOp::Feedback { feedback })?;
+            return Ok(());
+        }
+        anomalies_feedback_db(pool, &feedback)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn anomaly_notes_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    anomaly_id: String,
+) -> Result<Vec<AnomalyNote>, String> {
+    db::run_blocking(&workspace.pool(), move |pool| anomaly_notes_list_db(pool, &anomaly_id)).await
+}
+
+#[tauri::command]
+pub async fn anomaly_notes_add(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    anomaly_id: String,
+    body: String,
+) -> Result<AnomalyNote, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        anomaly_note_add_db(pool, &anomaly_id, &body, now_millis())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn anomaly_notes_edit(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: i64,
+    body: String,
+) -> Result<AnomalyNote, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        anomaly_note_edit_db(pool, id, &body, now_millis())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn anomaly_notes_delete(workspace: tauri::State<'_, WorkspaceDb>, id: i64) -> Result<(), String> {
+    db::run_blocking(&workspace.pool(), move |pool| anomaly_note_delete_db(pool, id)).await
 }
@@ -1,5 +1,14 @@
-use crate::db::DbPool;
+use std::collections::HashSet;
+
+use crate::commands::offline::{ensure_online, is_offline};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::http::{self, Validators};
+use crate::tasks::TaskOutcome;
+use crate::time::now_secs;
+use crate::workspace::WorkspaceDb;
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Asset {
@@ -8,28 +17,96 @@ pub struct Asset {
     pub exchange: String,
     pub asset_class: String,
     pub status: String,
+    /// When the cached row was last refreshed from Alpaca (UTC, SQLite datetime).
+    #[serde(default)]
+    pub fetched_at: Option<String>,
+}
+
+/// The `assets` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AssetsConfig {
+    /// How long the asset cache is served before it is refetched.
+    pub cache_ttl_hours: u64,
+    /// How long a symbol's `asset_get` details are served before they are refetched.
+    pub detail_ttl_minutes: u64,
+    /// Refresh the cache in the background outside market hours.
+    pub background_refresh: bool,
+    /// How long before the cache expires the background refresh may run.
+    pub refresh_lead_hours: u64,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            cache_ttl_hours: 24,
+            detail_ttl_minutes: 60,
+            background_refresh: true,
+            refresh_lead_hours: 6,
+        }
+    }
+}
+
+/// Parse the `assets` section of the app config.
+pub fn assets_config(app_config: &serde_json::Value) -> AssetsConfig {
+    app_config
+        .get("assets")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
 }
 
-/// Insert or replace a batch of assets into the cache.
+/// Replace the cache with `assets` in one transaction: upsert every asset and
+/// drop the ones no longer listed. On failure the previous cache is kept.
 pub fn assets_cache_set(pool: &DbPool, assets: &[Asset]) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut upsert = tx
+            .prepare(
+                "INSERT INTO assets (symbol, name, exchange, asset_class, status, fetched_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))
+                 ON CONFLICT(symbol) DO UPDATE SET
+                     name = ?2, exchange = ?3, asset_class = ?4, status = ?5,
+                     fetched_at = datetime('now')",
+            )
+            .map_err(|e| e.to_string())?;
+        for asset in assets {
+            upsert
+                .execute(rusqlite::params![
+                    asset.symbol,
+                    asset.name,
+                    asset.exchange,
+                    asset.asset_class,
+                    asset.status,
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+
+        let listed: HashSet<&str> = assets.iter().map(|a| a.symbol.as_str()).collect();
+        let cached: Vec<String> = tx
+            .prepare("SELECT symbol FROM assets")
+            .map_err(|e| e.to_string())?
+            .query_map([], |row| row.get(0))
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+        let mut delete = tx
+            .prepare("DELETE FROM assets WHERE symbol = ?1")
+            .map_err(|e| e.to_string())?;
+        for symbol in cached.iter().filter(|s| !listed.contains(s.as_str())) {
+            delete.execute([symbol]).map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Mark every cached asset as refreshed now, after Alpaca confirmed the
+/// lists are unchanged.
+pub fn assets_cache_touch(pool: &DbPool) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM assets", []).map_err(|e| e.to_string())?;
-    let mut stmt = conn
-        .prepare(
-            "INSERT INTO assets (symbol, name, exchange, asset_class, status, fetched_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))",
-        )
-        .map_err(|e| e.to_string())?;
-    for asset in assets {
-        stmt.execute(rusqlite::params![
-            asset.symbol,
-            asset.name,
-            asset.exchange,
-            asset.asset_class,
-            asset.status,
-        ])
+    conn.execute("UPDATE assets SET fetched_at = datetime('now')", [])
         .map_err(|e| e.to_string())?;
-    }
     Ok(())
 }
 
@@ -37,7 +114,7 @@ pub fn assets_cache_set(pool: &DbPool, assets: &[Asset]) -> Result<(), String> {
 pub fn assets_cache_get(pool: &DbPool) -> Result<Vec<Asset>, String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT symbol, name, exchange, asset_class, status FROM assets ORDER BY symbol")
+        .prepare("SELECT symbol, name, exchange, asset_class, status, fetched_at FROM assets ORDER BY symbol")
         .map_err(|e| e.to_string())?;
     let assets = stmt
         .query_map([], |row| {
@@ -47,6 +124,7 @@ pub fn assets_cache_get(pool: &DbPool) -> Result<Vec<Asset>, String> {
                 exchange: row.get(2)?,
                 asset_class: row.get(3)?,
                 status: row.get(4)?,
+                fetched_at: row.get(5)?,
             })
         })
         .map_err(|e| e.to_string())?
@@ -55,79 +133,577 @@ pub fn assets_cache_get(pool: &DbPool) -> Result<Vec<Asset>, String> {
     Ok(assets)
 }
 
-const ASSETS_TTL_SECS: i64 = 86400; // 24 hours
+/// Default and maximum page sizes for `assets_search`.
+const SEARCH_DEFAULT_LIMIT: u32 = 50;
+const SEARCH_MAX_LIMIT: u32 = 500;
+
+/// Filters for `assets_search_db`. Empty strings are treated as unset.
+#[derive(Debug, Default, Clone)]
+pub struct AssetQuery {
+    /// Matched as a symbol prefix or a name substring, case-insensitively.
+    pub query: Option<String>,
+    pub exchange: Option<String>,
+    pub asset_class: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+/// One page of `assets_search` results.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetPage {
+    pub assets: Vec<Asset>,
+    /// Matches across all pages.
+    pub total: u64,
+}
+
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Search the cached assets. Exact symbol matches sort first, then symbol
+/// prefix matches, then name matches.
+pub fn assets_search_db(pool: &DbPool, q: &AssetQuery) -> Result<AssetPage, String> {
+    let non_empty = |v: &Option<String>| v.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(String::from);
+    let query = non_empty(&q.query).map(|v| escape_like(&v));
+    let exchange = non_empty(&q.exchange);
+    let asset_class = non_empty(&q.asset_class);
+    let limit = q.limit.unwrap_or(SEARCH_DEFAULT_LIMIT).clamp(1, SEARCH_MAX_LIMIT);
+    let offset = q.offset.unwrap_or(0);
 
+    const FILTER: &str = "(?1 IS NULL
+                            OR symbol LIKE ?1 || '%' ESCAPE '\\'
+                            OR name LIKE '%' || ?1 || '%' ESCAPE '\\')
+                       AND (?2 IS NULL OR exchange = ?2)
+                       AND (?3 IS NULL OR asset_class = ?3)";
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let total: i64 = conn
+        .query_row(
+            &format!("SELECT COUNT(*) FROM assets WHERE {}", FILTER),
+            rusqlite::params![query, exchange, asset_class],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT symbol, name, exchange, asset_class, status, fetched_at FROM assets
+             WHERE {}
+             ORDER BY CASE
+                 WHEN ?1 IS NULL THEN 0
+                 WHEN symbol LIKE ?1 ESCAPE '\\' THEN 0
+                 WHEN symbol LIKE ?1 || '%' ESCAPE '\\' THEN 1
+                 ELSE 2
+             END, symbol
+             LIMIT ?4 OFFSET ?5",
+            FILTER
+        ))
+        .map_err(|e| e.to_string())?;
+    let assets = stmt
+        .query_map(
+            rusqlite::params![query, exchange, asset_class, limit, offset],
+            |row| {
+                Ok(Asset {
+                    symbol: row.get(0)?,
+                    name: row.get(1)?,
+                    exchange: row.get(2)?,
+                    asset_class: row.get(3)?,
+                    status: row.get(4)?,
+                    fetched_at: row.get(5)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(AssetPage {
+        assets,
+        total: total as u64,
+    })
+}
+
+/// Alpaca asset classes fetched into the cache.
+pub const ASSET_CLASSES: &[&str] = &["us_equity", "crypto"];
+const ASSETS_URL: &str = "https://paper-api.alpaca.markets/v2/assets";
+
+fn validate_asset_class(class: &str) -> Result<(), String> {
+    if ASSET_CLASSES.contains(&class) {
+        return Ok(());
+    }
+    Err(format!(
+        "Invalid asset class: '{}'. Must be one of: {}",
+        class,
+        ASSET_CLASSES.join(", ")
+    ))
+}
+
+/// All tradable assets, or only those of `class` (`us_equity` or `crypto`).
+/// Served from the cache unless it is older than `assets.cacheTtlHours` or
+/// `force` is set.
 #[tauri::command]
 pub async fn assets_fetch(
-    pool: tauri::State<'_, DbPool>,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    class: Option<String>,
+    force: Option<bool>,
 ) -> Result<Vec<Asset>, String> {
-    // Return cache if fresh
-    if !assets_cache_is_stale(&pool, ASSETS_TTL_SECS)? {
-        return assets_cache_get(&pool);
+    if let Some(class) = &class {
+        validate_asset_class(class)?;
     }
+    let assets = fetch_assets(&workspace.pool(), force.unwrap_or(false)).await?;
+    Ok(match class {
+        Some(class) => assets.into_iter().filter(|a| a.asset_class == class).collect(),
+        None => assets,
+    })
+}
 
-    // Get Alpaca credentials
-    let creds = crate::commands::credentials::credentials_get_db(&pool, "paper")?;
-    let (key_id, secret_key) = match creds {
-        Some(c) => (c.key_id, c.secret_key),
-        None => {
-            let key = std::env::var("ALPACA_KEY_ID")
-                .map_err(|_| "Alpaca credentials not configured. Set them in Settings.".to_string())?;
-            let secret = std::env::var("ALPACA_SECRET_KEY")
-                .map_err(|_| "ALPACA_SECRET_KEY not set.".to_string())?;
-            (key, secret)
+/// Search the asset cache one page at a time, refreshing it first when stale.
+#[tauri::command]
+pub async fn assets_search(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    query: Option<String>,
+    exchange: Option<String>,
+    class: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<AssetPage, String> {
+    let pool = workspace.pool();
+    refresh_if_stale(&pool).await?;
+    let q = AssetQuery {
+        query,
+        exchange,
+        asset_class: class,
+        limit,
+        offset,
+    };
+    db::run_blocking(&pool, move |pool| assets_search_db(pool, &q)).await
+}
+
+/// Refresh the cache when stale. A failed refresh is only an error when
+/// there is no stale cache to search instead.
+async fn refresh_if_stale(pool: &DbPool) -> Result<(), String> {
+    let stale = db::run_blocking(pool, |pool| assets_cache_is_stale(pool, cache_ttl_secs(pool)?)).await?;
+    if stale {
+        if let Err(e) = fetch_assets(pool, false).await {
+            let empty = db::run_blocking(pool, |pool| assets_cache_get(pool).map(|a| a.is_empty())).await?;
+            if empty {
+                return Err(e);
+            }
+            tracing::warn!(error = %e, "Asset refresh failed, searching stale cache");
         }
+    }
+    Ok(())
+}
+
+/// Default number of `assets_fuzzy_search` results.
+const FUZZY_DEFAULT_LIMIT: usize = 20;
+/// Scores below this are not returned by `assets_fuzzy_search`.
+const FUZZY_MIN_SCORE: f64 = 0.3;
+
+/// An asset with how well it matched a fuzzy query, from 0 to 1.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ScoredAsset {
+    pub asset: Asset,
+    pub score: f64,
+}
+
+/// Padded character trigrams of `word`, e.g. `"  a", " ap", "app", ...` for "apple".
+fn trigrams(word: &str) -> HashSet<[char; 3]> {
+    let chars: Vec<char> = format!("  {} ", word).chars().collect();
+    chars.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+}
+
+/// Dice coefficient of the trigram sets of `a` and `b`.
+fn trigram_similarity(a: &str, b: &str) -> f64 {
+    let (ta, tb) = (trigrams(a), trigrams(b));
+    if ta.is_empty() || tb.is_empty() {
+        return 0.0;
+    }
+    2.0 * ta.intersection(&tb).count() as f64 / (ta.len() + tb.len()) as f64
+}
+
+/// How well `query` (lowercase, trimmed) matches `asset`. Exact symbols score
+/// 1, symbol prefixes 0.8–0.95, name word prefixes 0.6–0.75, and anything else
+/// the best trigram similarity against the symbol or a name word, scaled to 0.7.
+/// Prefix scores grow with how much of the symbol or word the query covers.
+pub fn fuzzy_score(query: &str, asset: &Asset) -> f64 {
+    if query.is_empty() {
+        return 0.0;
+    }
+    let symbol = asset.symbol.to_lowercase();
+    if symbol == query {
+        return 1.0;
+    }
+    if symbol.starts_with(query) {
+        return 0.8 + 0.15 * query.len() as f64 / symbol.len() as f64;
+    }
+    let name = asset.name.to_lowercase();
+    let words: Vec<&str> = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if let Some(word) = words.iter().filter(|w| w.starts_with(query)).min_by_key(|w| w.len()) {
+        return 0.6 + 0.15 * query.len() as f64 / word.len() as f64;
+    }
+    words
+        .iter()
+        .map(|w| trigram_similarity(query, w))
+        .fold(trigram_similarity(query, &symbol), f64::max)
+        * 0.7
+}
+
+/// Rank `assets` against `query`, best first; ties go to the shorter symbol.
+pub fn fuzzy_rank(assets: Vec<Asset>, query: &str, limit: usize) -> Vec<ScoredAsset> {
+    let query = query.trim().to_lowercase();
+    let mut scored: Vec<ScoredAsset> = assets
+        .into_iter()
+        .map(|asset| ScoredAsset {
+            score: fuzzy_score(&query, &asset),
+            asset,
+        })
+        .filter(|s| s.score >= FUZZY_MIN_SCORE)
+        .collect();
+    scored.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then(a.asset.symbol.len().cmp(&b.asset.symbol.len()))
+            .then_with(|| a.asset.symbol.cmp(&b.asset.symbol))
+    });
+    scored.truncate(limit);
+    scored
+}
+
+/// Typo-tolerant search over cached symbols and company names for the symbol
+/// picker, best matches first.
+#[tauri::command]
+pub async fn assets_fuzzy_search(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    query: String,
+    class: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ScoredAsset>, String> {
+    if let Some(class) = &class {
+        validate_asset_class(class)?;
+    }
+    let pool = workspace.pool();
+    refresh_if_stale(&pool).await?;
+    let assets = db::run_blocking(&pool, assets_cache_get).await?;
+    let assets = match class {
+        Some(class) => assets.into_iter().filter(|a| a.asset_class == class).collect(),
+        None => assets,
     };
+    Ok(fuzzy_rank(
+        assets,
+        &query,
+        limit.unwrap_or(FUZZY_DEFAULT_LIMIT).clamp(1, SEARCH_MAX_LIMIT as usize),
+    ))
+}
 
-    // Fetch from Alpaca API
-    let client = reqwest::Client::new();
-    let response = client
-        .get("https://paper-api.alpaca.markets/v2/assets")
-        .query(&[("status", "active")])
-        .header("APCA-API-KEY-ID", &key_id)
-        .header("APCA-API-SECRET-KEY", &secret_key)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to fetch assets: {}", e))?;
+/// A symbol's trading details from `GET /v2/assets/{symbol}`, as returned by `asset_get`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AssetDetail {
+    pub symbol: String,
+    pub name: String,
+    pub exchange: String,
+    #[serde(alias = "class")]
+    pub asset_class: String,
+    pub status: String,
+    pub tradable: bool,
+    pub shortable: bool,
+    pub marginable: bool,
+    #[serde(default)]
+    pub easy_to_borrow: bool,
+    #[serde(default)]
+    pub fractionable: bool,
+    /// Smallest order quantity; Alpaca only reports it for crypto.
+    #[serde(default)]
+    pub min_order_size: Option<String>,
+    #[serde(default)]
+    pub min_trade_increment: Option<String>,
+    #[serde(default)]
+    pub price_increment: Option<String>,
+    /// When the details were fetched (UTC, SQLite datetime).
+    #[serde(default)]
+    pub fetched_at: Option<String>,
+}
 
-    if !response.status().is_success() {
-        // Try returning stale cache on API error
-        let cached = assets_cache_get(&pool)?;
-        if !cached.is_empty() {
-            return Ok(cached);
+/// Store a symbol's details, stamping them with the current time.
+pub fn asset_detail_set_db(pool: &DbPool, detail: &AssetDetail) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO asset_details (symbol, name, exchange, asset_class, status, tradable,
+             shortable, marginable, easy_to_borrow, fractionable, min_order_size,
+             min_trade_increment, price_increment, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, datetime('now'))
+         ON CONFLICT(symbol) DO UPDATE SET
+             name = ?2, exchange = ?3, asset_class = ?4, status = ?5, tradable = ?6,
+             shortable = ?7, marginable = ?8, easy_to_borrow = ?9, fractionable = ?10,
+             min_order_size = ?11, min_trade_increment = ?12, price_increment = ?13,
+             fetched_at = datetime('now')",
+        rusqlite::params![
+            detail.symbol,
+            detail.name,
+            detail.exchange,
+            detail.asset_class,
+            detail.status,
+            detail.tradable,
+            detail.shortable,
+            detail.marginable,
+            detail.easy_to_borrow,
+            detail.fractionable,
+            detail.min_order_size,
+            detail.min_trade_increment,
+            detail.price_increment,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Cached details for `symbol`, with whether they are older than `max_age_secs`.
+pub fn asset_detail_get_db(
+    pool: &DbPool,
+    symbol: &str,
+    max_age_secs: i64,
+) -> Result<Option<(AssetDetail, bool)>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    match conn.query_row(
+        "SELECT symbol, name, exchange, asset_class, status, tradable, shortable, marginable,
+                easy_to_borrow, fractionable, min_order_size, min_trade_increment,
+                price_increment, fetched_at, fetched_at <= datetime('now', ?2)
+         FROM asset_details WHERE symbol = ?1",
+        rusqlite::params![symbol, format!("-{} seconds", max_age_secs)],
+        |row| {
+            Ok((
+                AssetDetail {
+                    symbol: row.get(0)?,
+                    name: row.get(1)?,
+                    exchange: row.get(2)?,
+                    asset_class: row.get(3)?,
+                    status: row.get(4)?,
+                    tradable: row.get(5)?,
+                    shortable: row.get(6)?,
+                    marginable: row.get(7)?,
+                    easy_to_borrow: row.get(8)?,
+                    fractionable: row.get(9)?,
+                    min_order_size: row.get(10)?,
+                    min_trade_increment: row.get(11)?,
+                    price_increment: row.get(12)?,
+                    fetched_at: row.get(13)?,
+                },
+                row.get(14)?,
+            ))
+        },
+    ) {
+        Ok(found) => Ok(Some(found)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Trading details for `symbol`, cached for `assets.detailTtlMinutes`. When
+/// Alpaca cannot be reached, stale cached details are returned instead.
+#[tauri::command]
+pub async fn asset_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbol: String,
+    force: Option<bool>,
+) -> Result<AssetDetail, String> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol must not be empty".to_string());
+    }
+    let pool = workspace.pool();
+    let lookup = symbol.clone();
+    let cached = db::run_blocking(&pool, move |pool| {
+        let app_config = crate::commands::config::config_effective_db(pool)?;
+        let ttl = assets_config(&app_config).detail_ttl_minutes * 60;
+        asset_detail_get_db(pool, &lookup, ttl as i64)
+    })
+    .await?;
+    if let Some((detail, false)) = &cached {
+        if !force.unwrap_or(false) {
+            return Ok(detail.clone());
         }
-        return Err(format!("Alpaca API error: {}", response.status()));
     }
 
-    #[derive(Deserialize)]
-    struct AlpacaAsset {
-        symbol: String,
-        name: String,
-        exchange: String,
-        class: String,
-        status: String,
-        tradable: bool,
+    match fetch_asset_detail(&pool, &symbol).await {
+        Ok(detail) => {
+            db::run_blocking(&pool, move |pool| {
+                asset_detail_set_db(pool, &detail)?;
+                Ok(asset_detail_get_db(pool, &detail.symbol, i64::MAX)?.map(|(d, _)| d).unwrap_or(detail))
+            })
+            .await
+        }
+        Err(e) => match cached {
+            Some((detail, _)) => {
+                tracing::warn!(error = %e, symbol, "Asset detail refresh failed, returning cached details");
+                Ok(detail)
+            }
+            None => Err(e),
+        },
     }
+}
 
-    let alpaca_assets: Vec<AlpacaAsset> = response
-        .json()
+async fn fetch_asset_detail(pool: &DbPool, symbol: &str) -> Result<AssetDetail, String> {
+    let (key_id, secret_key) = alpaca_keys(pool).await?;
+    let mut url = reqwest::Url::parse(ASSETS_URL)
+        .map_err(|e| e.to_string())?;
+    // Encodes the slash of crypto pairs such as BTC/USD
+    url.path_segments_mut()
+        .map_err(|_| "Invalid asset URL".to_string())?
+        .push(symbol);
+    let request = http::client()?
+        .get(url)
+        .header("APCA-API-KEY-ID", &key_id)
+        .header("APCA-API-SECRET-KEY", &secret_key);
+    let response = http::send(request)
         .await
-        .map_err(|e| format!("Failed to parse assets: {}", e))?;
+        .map_err(|e| format!("Failed to fetch asset {}: {}", symbol, e))?;
+    match response.status() {
+        s if s.is_success() => response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse asset {}: {}", symbol, e)),
+        reqwest::StatusCode::NOT_FOUND => Err(format!("Unknown symbol: {}", symbol)),
+        s => Err(format!("Alpaca API error: {}", s)),
+    }
+}
 
-    let assets: Vec<Asset> = alpaca_assets
-        .into_iter()
-        .filter(|a| a.tradable)
-        .map(|a| Asset {
+/// An entry of Alpaca's `GET /v2/assets` response.
+#[derive(Deserialize)]
+struct AlpacaAsset {
+    symbol: String,
+    name: String,
+    exchange: String,
+    class: String,
+    status: String,
+    tradable: bool,
+}
+
+impl From<AlpacaAsset> for Asset {
+    fn from(a: AlpacaAsset) -> Self {
+        Asset {
             symbol: a.symbol,
             name: a.name,
             exchange: a.exchange,
             asset_class: a.class,
             status: a.status,
-        })
-        .collect();
+            fetched_at: None,
+        }
+    }
+}
 
-    assets_cache_set(&pool, &assets)?;
-    Ok(assets)
+/// Key ID and secret for asset lookups: the active paper credentials, then env vars.
+async fn alpaca_keys(pool: &DbPool) -> Result<(String, String), String> {
+    let creds = db::run_blocking(pool, |pool| {
+        ensure_online(pool)?;
+        crate::commands::credentials::credentials_get_active(pool, "paper")
+    })
+    .await?;
+    match creds {
+        Some(c) => Ok((c.key_id, c.secret_key)),
+        None => {
+            let key = std::env::var("ALPACA_KEY_ID")
+                .map_err(|_| "Alpaca credentials not configured. Set them in Settings.".to_string())?;
+            let secret = std::env::var("ALPACA_SECRET_KEY")
+                .map_err(|_| "ALPACA_SECRET_KEY not set.".to_string())?;
+            Ok((key, secret))
+        }
+    }
+}
+
+/// Cache TTL from the app config, in seconds.
+fn cache_ttl_secs(pool: &DbPool) -> Result<i64, String> {
+    let app_config = crate::commands::config::config_effective_db(pool)?;
+    Ok((assets_config(&app_config).cache_ttl_hours * 3600) as i64)
+}
+
+/// The cached assets if fresh, otherwise a refreshed list from Alpaca.
+async fn fetch_assets(pool: &DbPool, force: bool) -> Result<Vec<Asset>, String> {
+    // Return cache if fresh, or at any age while offline
+    let fresh = db::run_blocking(pool, move |pool| {
+        if is_offline(pool) {
+            let cached = assets_cache_get(pool)?;
+            if cached.is_empty() {
+                return Err("Offline mode is on and no assets are cached".to_string());
+            }
+            Ok(Some(cached))
+        } else if force || assets_cache_is_stale(pool, cache_ttl_secs(pool)?)? {
+            Ok(None)
+        } else {
+            assets_cache_get(pool).map(Some)
+        }
+    })
+    .await?;
+    if let Some(cached) = fresh {
+        return Ok(cached);
+    }
+
+    let (key_id, secret_key) = alpaca_keys(pool).await?;
+    let cached = db::run_blocking(pool, assets_cache_get).await?;
+
+    // Fetch each class from Alpaca API, revalidating classes already cached
+    let client = http::client()?;
+    let mut assets = Vec::new();
+    let mut validators = Vec::new();
+    for class in ASSET_CLASSES {
+        let request = client
+            .get(ASSETS_URL)
+            .query(&[("status", "active"), ("asset_class", *class)])
+            .header("APCA-API-KEY-ID", &key_id)
+            .header("APCA-API-SECRET-KEY", &secret_key);
+        let url = request
+            .try_clone()
+            .and_then(|r| r.build().ok())
+            .map(|r| r.url().to_string())
+            .unwrap_or_default();
+        let cached_class: Vec<Asset> = cached.iter().filter(|a| a.asset_class == *class).cloned().collect();
+        let known = if cached_class.is_empty() {
+            Validators::default()
+        } else {
+            let url = url.clone();
+            db::run_blocking(pool, move |pool| http::validators_get_db(pool, &url)).await?
+        };
+        let response = http::send(known.apply(request))
+            .await
+            .map_err(|e| format!("Failed to fetch assets: {}", e))?;
+
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            assets.extend(cached_class);
+            continue;
+        }
+        if !response.status().is_success() {
+            // Try returning stale cache on API error
+            if !cached.is_empty() {
+                return Ok(cached);
+            }
+            return Err(format!("Alpaca API error: {}", response.status()));
+        }
+
+        validators.push((url, Validators::from_response(&response)));
+        let alpaca_assets: Vec<AlpacaAsset> = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse {} assets: {}", class, e))?;
+        assets.extend(alpaca_assets.into_iter().filter(|a| a.tradable).map(Asset::from));
+    }
+
+    // Read back so the rows carry their new fetched_at
+    db::run_blocking(pool, move |pool| {
+        if validators.is_empty() {
+            tracing::debug!("Asset lists unchanged");
+            assets_cache_touch(pool)?;
+        } else {
+            assets_cache_set(pool, &assets)?;
+            for (url, v) in &validators {
+                http::validators_set_db(pool, url, v)?;
+            }
+        }
+        assets_cache_get(pool)
+    })
+    .await
 }
 
 /// Check whether the cache is stale (older than `max_age_secs`).
@@ -143,6 +719,55 @@ pub fn assets_cache_is_stale(pool: &DbPool, max_age_secs: i64) -> Result<bool, S
     Ok(count == 0)
 }
 
+/// Age of the newest cached asset in seconds, or `None` when the cache is empty.
+pub fn assets_cache_age_secs(pool: &DbPool) -> Result<Option<i64>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT CAST(strftime('%s', 'now') AS INTEGER) - CAST(strftime('%s', MAX(fetched_at)) AS INTEGER)
+         FROM assets",
+        [],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Whether `now` (epoch seconds) falls outside the US regular session. The
+/// session is taken as 13:00–21:00 UTC on weekdays, which covers it in both
+/// standard and daylight time.
+pub fn is_off_hours(now: i64) -> bool {
+    let days = now.div_euclid(86_400);
+    // 1970-01-01 was a Thursday; 0 = Sunday
+    let weekday = (days + 4).rem_euclid(7);
+    let hour = now.rem_euclid(86_400) / 3_600;
+    weekday == 0 || weekday == 6 || !(13..21).contains(&hour)
+}
+
+/// Whether the scheduler should refresh a cache that is `age_secs` old at `now`.
+/// An empty cache is left for the first `assets_fetch` to fill.
+pub fn background_refresh_due(config: &AssetsConfig, age_secs: Option<i64>, now: i64) -> bool {
+    let Some(age) = age_secs else {
+        return false;
+    };
+    let ttl = config.cache_ttl_hours.saturating_mul(3_600) as i64;
+    let lead = config.refresh_lead_hours.saturating_mul(3_600) as i64;
+    config.background_refresh && is_off_hours(now) && age >= ttl - lead
+}
+
+/// Scheduled task: refresh the asset cache outside market hours once it is
+/// within `assets.refreshLeadHours` of expiring.
+pub fn refresh_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let app_config = crate::commands::config::config_effective_db(&pool)?;
+    let age = assets_cache_age_secs(&pool)?;
+    if !background_refresh_due(&assets_config(&app_config), age, now_secs() as i64) {
+        return Ok(TaskOutcome::Skipped);
+    }
+    let assets = tauri::async_runtime::block_on(fetch_assets(&pool, true))?;
+    tracing::info!(count = assets.len(), "Background asset cache refresh complete");
+    let _ = emit_event(app, event_names::ASSETS_REFRESHED, assets.len());
+    Ok(TaskOutcome::Ran)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -173,6 +798,7 @@ mod tests {
                 exchange: "NASDAQ".to_string(),
                 asset_class: "us_equity".to_string(),
                 status: "active".to_string(),
+                fetched_at: None,
             },
             Asset {
                 symbol: "BTC/USD".to_string(),
@@ -180,6 +806,7 @@ mod tests {
                 exchange: "CRYPTO".to_string(),
                 asset_class: "crypto".to_string(),
                 status: "active".to_string(),
+                fetched_at: None,
             },
         ];
         assets_cache_set(&pool, &assets).unwrap();
@@ -188,6 +815,30 @@ mod tests {
         assert_eq!(result[0].symbol, "AAPL");
     }
 
+    #[test]
+    fn cache_touch_refreshes_fetched_at_without_rewriting() {
+        let pool = test_pool();
+        let assets = vec![Asset {
+            symbol: "AAPL".to_string(),
+            name: "Apple Inc.".to_string(),
+            exchange: "NASDAQ".to_string(),
+            asset_class: "us_equity".to_string(),
+            status: "active".to_string(),
+            fetched_at: None,
+        }];
+        assets_cache_set(&pool, &assets).unwrap();
+        pool.get()
+            .unwrap()
+            .execute("UPDATE assets SET fetched_at = '2000-01-01 00:00:00'", [])
+            .unwrap();
+
+        assets_cache_touch(&pool).unwrap();
+        let result = assets_cache_get(&pool).unwrap();
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].name, "Apple Inc.");
+        assert_ne!(result[0].fetched_at.as_deref(), Some("2000-01-01 00:00:00"));
+    }
+
     #[test]
     fn cache_set_replaces_existing() {
         let pool = test_pool();
@@ -197,6 +848,7 @@ mod tests {
             exchange: "NASDAQ".to_string(),
             asset_class: "us_equity".to_string(),
             status: "active".to_string(),
+            fetched_at: None,
         }];
         assets_cache_set(&pool, &v1).unwrap();
 
@@ -206,6 +858,7 @@ mod tests {
             exchange: "NASDAQ".to_string(),
             asset_class: "us_equity".to_string(),
             status: "active".to_string(),
+            fetched_at: None,
         }];
         assets_cache_set(&pool, &v2).unwrap();
 
@@ -214,6 +867,324 @@ mod tests {
         assert_eq!(result[0].name, "Apple Inc.");
     }
 
+    fn asset(symbol: &str, name: &str, exchange: &str, class: &str) -> Asset {
+        Asset {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            exchange: exchange.to_string(),
+            asset_class: class.to_string(),
+            status: "active".to_string(),
+            fetched_at: None,
+        }
+    }
+
+    fn search_pool() -> DbPool {
+        let pool = test_pool();
+        assets_cache_set(
+            &pool,
+            &[
+                asset("AAPL", "Apple Inc.", "NASDAQ", "us_equity"),
+                asset("AA", "Alcoa Corp", "NYSE", "us_equity"),
+                asset("MAPL", "Maple Holdings", "NYSE", "us_equity"),
+                asset("BTC/USD", "Bitcoin", "CRYPTO", "crypto"),
+                asset("BRK_B", "Berkshire 100% Class B", "NYSE", "us_equity"),
+            ],
+        )
+        .unwrap();
+        pool
+    }
+
+    fn symbols(page: &AssetPage) -> Vec<&str> {
+        page.assets.iter().map(|a| a.symbol.as_str()).collect()
+    }
+
+    #[test]
+    fn search_ranks_exact_then_prefix_then_name_matches() {
+        let pool = search_pool();
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                query: Some("aa".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(symbols(&page), vec!["AA", "AAPL"]);
+
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                query: Some("apl".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        // Not a symbol prefix of AAPL, but a substring of "Maple"
+        assert_eq!(symbols(&page), vec!["MAPL"]);
+
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                query: Some("ple".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(symbols(&page), vec!["AAPL", "MAPL"]);
+    }
+
+    #[test]
+    fn search_filters_and_paginates() {
+        let pool = search_pool();
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                exchange: Some("NYSE".to_string()),
+                limit: Some(2),
+                offset: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 3);
+        assert_eq!(symbols(&page), vec!["BRK_B", "MAPL"]);
+
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                asset_class: Some("crypto".to_string()),
+                query: Some(" ".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(symbols(&page), vec!["BTC/USD"]);
+    }
+
+    #[test]
+    fn search_treats_like_wildcards_literally() {
+        let pool = search_pool();
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                query: Some("100%".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(symbols(&page), vec!["BRK_B"]);
+
+        let page = assets_search_db(
+            &pool,
+            &AssetQuery {
+                query: Some("A_".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(page.total, 0);
+    }
+
+    #[test]
+    fn cache_set_drops_delisted_assets_and_keeps_others() {
+        let pool = test_pool();
+        assets_cache_set(
+            &pool,
+            &[
+                asset("AAPL", "Apple", "NASDAQ", "us_equity"),
+                asset("TWTR", "Twitter", "NYSE", "us_equity"),
+            ],
+        )
+        .unwrap();
+        assets_cache_set(
+            &pool,
+            &[
+                asset("AAPL", "Apple Inc.", "NASDAQ", "us_equity"),
+                asset("MSFT", "Microsoft", "NASDAQ", "us_equity"),
+            ],
+        )
+        .unwrap();
+
+        let result = assets_cache_get(&pool).unwrap();
+        let symbols: Vec<_> = result.iter().map(|a| a.symbol.as_str()).collect();
+        assert_eq!(symbols, vec!["AAPL", "MSFT"]);
+        assert_eq!(result[0].name, "Apple Inc.");
+    }
+
+    #[test]
+    fn cache_set_with_empty_list_clears_cache() {
+        let pool = search_pool();
+        assets_cache_set(&pool, &[]).unwrap();
+        assert!(assets_cache_get(&pool).unwrap().is_empty());
+    }
+
+    #[test]
+    fn alpaca_crypto_assets_keep_their_class() {
+        let json = r#"[
+            {"symbol":"BTC/USD","name":"Bitcoin / US Dollar","exchange":"CRYPTO",
+             "class":"crypto","status":"active","tradable":true},
+            {"symbol":"AAPL","name":"Apple Inc.","exchange":"NASDAQ",
+             "class":"us_equity","status":"active","tradable":true,"fractionable":true}
+        ]"#;
+        let parsed: Vec<AlpacaAsset> = serde_json::from_str(json).unwrap();
+        let assets: Vec<Asset> = parsed.into_iter().map(Asset::from).collect();
+        assert_eq!(assets[0].asset_class, "crypto");
+        assert_eq!(assets[1].asset_class, "us_equity");
+    }
+
+    #[test]
+    fn asset_class_filter_is_validated() {
+        assert!(validate_asset_class("crypto").is_ok());
+        assert!(validate_asset_class("us_equity").is_ok());
+        assert!(validate_asset_class("options").is_err());
+    }
+
+    #[test]
+    fn cached_assets_carry_fetched_at() {
+        let pool = test_pool();
+        assets_cache_set(&pool, &[asset("AAPL", "Apple", "NASDAQ", "us_equity")]).unwrap();
+        assert!(assets_cache_get(&pool).unwrap()[0].fetched_at.is_some());
+    }
+
+    #[test]
+    fn assets_config_defaults_to_one_day() {
+        assert_eq!(assets_config(&serde_json::json!({})).cache_ttl_hours, 24);
+        let config = serde_json::json!({ "assets": { "cacheTtlHours": 2 } });
+        assert_eq!(assets_config(&config).cache_ttl_hours, 2);
+        assert_eq!(assets_config(&config).detail_ttl_minutes, 60);
+    }
+
+    fn detail() -> AssetDetail {
+        let json = r#"{
+            "id": "276e2673-764b-4ab6-a611-caf665ca6340",
+            "class": "crypto", "exchange": "CRYPTO", "symbol": "BTC/USD",
+            "name": "Bitcoin  / US Dollar", "status": "active", "tradable": true,
+            "marginable": false, "maintenance_margin_requirement": 100,
+            "shortable": false, "easy_to_borrow": false, "fractionable": true,
+            "min_order_size": "0.0001", "min_trade_increment": "0.000000001",
+            "price_increment": "1"
+        }"#;
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn asset_detail_parses_alpaca_response() {
+        let d = detail();
+        assert_eq!(d.asset_class, "crypto");
+        assert!(d.fractionable);
+        assert_eq!(d.min_order_size.as_deref(), Some("0.0001"));
+        assert_eq!(d.fetched_at, None);
+    }
+
+    #[test]
+    fn asset_detail_cache_roundtrip_and_staleness() {
+        let pool = test_pool();
+        assert_eq!(asset_detail_get_db(&pool, "BTC/USD", 3600).unwrap(), None);
+
+        asset_detail_set_db(&pool, &detail()).unwrap();
+        let (cached, stale) = asset_detail_get_db(&pool, "BTC/USD", 3600).unwrap().unwrap();
+        assert!(!stale);
+        assert!(cached.fetched_at.is_some());
+        assert_eq!(cached.min_trade_increment.as_deref(), Some("0.000000001"));
+
+        let (_, stale) = asset_detail_get_db(&pool, "BTC/USD", 0).unwrap().unwrap();
+        assert!(stale);
+    }
+
+    fn listed(symbol: &str, name: &str) -> Asset {
+        Asset {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            exchange: "NASDAQ".to_string(),
+            asset_class: "us_equity".to_string(),
+            status: "active".to_string(),
+            fetched_at: None,
+        }
+    }
+
+    fn universe() -> Vec<Asset> {
+        vec![
+            listed("AMAT", "Applied Materials, Inc. Common Stock"),
+            listed("AAPL", "Apple Inc. Common Stock"),
+            listed("APP", "AppLovin Corporation Class A Common Stock"),
+            listed("MSFT", "Microsoft Corporation Common Stock"),
+            listed("PL", "Planet Labs PBC Class A Common Stock"),
+        ]
+    }
+
+    #[test]
+    fn fuzzy_exact_symbol_ranks_first() {
+        let ranked = fuzzy_rank(universe(), "msft", 10);
+        assert_eq!(ranked[0].asset.symbol, "MSFT");
+        assert_eq!(ranked[0].score, 1.0);
+    }
+
+    #[test]
+    fn fuzzy_name_prefix_and_typos_surface_aapl() {
+        let ranked = fuzzy_rank(universe(), "appl", 10);
+        assert_eq!(ranked[0].asset.symbol, "AAPL");
+        let ranked = fuzzy_rank(universe(), "aple", 10);
+        assert_eq!(ranked[0].asset.symbol, "AAPL");
+        assert!(ranked.iter().all(|s| s.asset.symbol != "MSFT"));
+    }
+
+    #[test]
+    fn fuzzy_symbol_prefix_beats_name_match() {
+        let ranked = fuzzy_rank(universe(), "ap", 10);
+        assert_eq!(ranked[0].asset.symbol, "APP");
+        assert!(ranked[0].score > ranked[1].score);
+    }
+
+    #[test]
+    fn fuzzy_respects_limit_and_blank_query() {
+        assert_eq!(fuzzy_rank(universe(), "a", 2).len(), 2);
+        assert!(fuzzy_rank(universe(), "  ", 10).is_empty());
+    }
+
+    #[test]
+    fn cache_age_is_none_when_empty() {
+        let pool = test_pool();
+        assert_eq!(assets_cache_age_secs(&pool).unwrap(), None);
+        let asset = Asset {
+            symbol: "AAPL".to_string(),
+            name: "Apple Inc.".to_string(),
+            exchange: "NASDAQ".to_string(),
+            asset_class: "us_equity".to_string(),
+            status: "active".to_string(),
+            fetched_at: None,
+        };
+        assets_cache_set(&pool, &[asset]).unwrap();
+        let age = assets_cache_age_secs(&pool).unwrap().unwrap();
+        assert!((0..5).contains(&age));
+    }
+
+    #[test]
+    fn off_hours_excludes_weekday_session() {
+        // 2024-01-03 is a Wednesday
+        let wednesday = 1_704_240_000;
+        assert!(is_off_hours(wednesday + 3 * 3_600));
+        assert!(!is_off_hours(wednesday + 15 * 3_600));
+        assert!(is_off_hours(wednesday + 21 * 3_600));
+        // 2024-01-06 is a Saturday
+        assert!(is_off_hours(wednesday + 3 * 86_400 + 15 * 3_600));
+    }
+
+    #[test]
+    fn background_refresh_due_near_expiry_off_hours() {
+        let config = AssetsConfig::default();
+        let night = 1_704_240_000 + 3 * 3_600;
+        let session = 1_704_240_000 + 15 * 3_600;
+        assert!(!background_refresh_due(&config, None, night));
+        assert!(!background_refresh_due(&config, Some(17 * 3_600), night));
+        assert!(background_refresh_due(&config, Some(18 * 3_600), night));
+        assert!(!background_refresh_due(&config, Some(30 * 3_600), session));
+        let disabled = AssetsConfig {
+            background_refresh: false,
+            ..config
+        };
+        assert!(!background_refresh_due(&disabled, Some(30 * 3_600), night));
+    }
+
     #[test]
     fn cache_is_stale_when_empty() {
         let pool = test_pool();
@@ -229,6 +1200,7 @@ mod tests {
             exchange: "NASDAQ".to_string(),
             asset_class: "us_equity".to_string(),
             status: "active".to_string(),
+            fetched_at: None,
         }];
         assets_cache_set(&pool, &assets).unwrap();
         // Just inserted, should not be stale with 24h TTL
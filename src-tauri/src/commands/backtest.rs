@@ -1,23 +1,32 @@
+use std::collections::hash_map::{Entry, HashMap};
+
 use tracing::warn;
 
 use crate::bridge::SidecarBridge;
-use crate::commands::agent::config_or_env;
-use crate::db::DbPool;
-use crate::types::backtest::{BacktestConfig, BacktestSummary, BacktestTrade};
+use crate::bridge_error::BridgeError;
+use crate::commands::agent::{
+    load_app_config, resolve_trading_mode, sidecar_command, sidecar_launch_config, AgentSecrets,
+};
+use crate::commands::bars::{bars_query_db, timeframe_millis, Bar};
+use crate::commands::profiles::active_profile_db;
+use crate::db::{self, DbPool};
+use crate::indicators::{compute_bars, BollingerSpec, IndicatorSpec, PeriodSpec};
+use crate::time::now_millis;
+use crate::types::backtest::{BacktestConfig, BacktestSummary, BacktestTrade, TradeContext};
+use crate::types::config::DEFAULT_MODEL;
+use crate::workspace::WorkspaceDb;
 
 /// Insert a new backtest run into the database with status `"running"`.
 ///
-/// Stores the full config JSON and records the current timestamp as `created_at`.
-pub fn backtest_insert_db(pool: &DbPool, id: &str, config_json: &str) -> Result<(), String> {
+/// Stores the full config JSON and trading mode, and records the current
+/// timestamp as `created_at`.
+pub fn backtest_insert_db(pool: &DbPool, id: &str, config_json: &str, mode: &str) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| e.to_string())?
-        .as_millis() as i64;
+    let now = now_millis() as i64;
 
     conn.execute(
-        "INSERT INTO backtests (id, status, config, created_at) VALUES (?1, 'running', ?2, ?3)",
-        rusqlite::params![id, config_json, now],
+        "INSERT INTO backtests (id, status, config, created_at, mode) VALUES (?1, 'running', ?2, ?3, ?4)",
+        rusqlite::params![id, config_json, now, mode],
     )
     .map_err(|e| e.to_string())?;
     Ok(())
@@ -35,10 +44,7 @@ pub fn backtest_update_status_db(
     error: Option<&str>,
 ) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| e.to_string())?
-        .as_millis() as i64;
+    let now = now_millis() as i64;
 
     conn.execute(
         "UPDATE backtests SET status = ?1, metrics = ?2, completed_at = ?3, error = ?4 WHERE id = ?5",
@@ -64,16 +70,86 @@ pub fn backtest_update_progress_db(
     Ok(())
 }
 
+/// Bars before the fill bar loaded for a trade's indicator snapshot; enough
+/// for the 20-bar Bollinger window and for Wilder smoothing to settle.
+const TRADE_CONTEXT_BARS: u64 = 100;
+
+/// Indicator snapshot at the last of `bars`, the bar a trade filled on.
+pub fn trade_context(bars: &[Bar]) -> Option<TradeContext> {
+    let fill = bars.last()?;
+    let spec = IndicatorSpec {
+        rsi: Some(PeriodSpec::default()),
+        macd: None,
+        bollinger: Some(BollingerSpec::default()),
+        atr: Some(PeriodSpec::default()),
+    };
+    let indicators = compute_bars("", "", bars, &spec);
+    let last = |values: Option<Vec<f64>>| values.and_then(|v| v.last().copied()).filter(|v| v.is_finite());
+    Some(TradeContext {
+        bar_timestamp: fill.timestamp,
+        rsi: last(indicators.rsi),
+        atr: last(indicators.atr),
+        percent_b: last(indicators.bollinger.map(|b| b.iter().map(|p| p.percent_b).collect())),
+    })
+}
+
+/// Snapshot for `trade` from cached bars of the backtest's timeframe, or
+/// `None` when none are cached up to the fill.
+fn cached_trade_context(pool: &DbPool, trade: &BacktestTrade, timeframe: &str) -> Result<Option<TradeContext>, String> {
+    let fill = trade.timestamp.max(0) as u64;
+    let lookback = timeframe_millis(timeframe)?.saturating_mul(TRADE_CONTEXT_BARS);
+    let symbol = trade.symbol.trim().to_uppercase();
+    let bars = bars_query_db(pool, &symbol, timeframe, Some(fill.saturating_sub(lookback)), Some(fill))?;
+    Ok(trade_context(&bars))
+}
+
+fn backtest_timeframe_db(pool: &DbPool, backtest_id: &str) -> Result<Option<String>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let config: String =
+        match conn.query_row("SELECT config FROM backtests WHERE id = ?1", [backtest_id], |row| row.get(0)) {
+            Ok(config) => config,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+    Ok(serde_json::from_str::<serde_json::Value>(&config)
+        .ok()
+        .and_then(|c| c.get("timeframe").and_then(|t| t.as_str()).map(String::from)))
+}
+
 /// Insert a batch of trades for a backtest run inside a single transaction.
 ///
-/// If any insert fails, the entire batch is rolled back to maintain atomicity.
+/// Trades without a `trade_context` get one computed from the cached bars of
+/// the backtest's timeframe. If any insert fails, the entire batch is rolled
+/// back to maintain atomicity.
 pub fn backtest_insert_trades_db(pool: &DbPool, trades: &[BacktestTrade]) -> Result<(), String> {
+    let mut contexts = Vec::with_capacity(trades.len());
+    let mut timeframes = HashMap::new();
+    for trade in trades {
+        if trade.trade_context.is_some() {
+            contexts.push(trade.trade_context);
+            continue;
+        }
+        let timeframe = match timeframes.entry(trade.backtest_id.clone()) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => e.insert(backtest_timeframe_db(pool, &trade.backtest_id)?),
+        };
+        let context = match timeframe {
+            Some(timeframe) => cached_trade_context(pool, trade, timeframe).unwrap_or_else(|e| {
+                warn!(trade_id = %trade.id, error = %e, "Failed to snapshot indicators for backtest trade");
+                None
+            }),
+            None => None,
+        };
+        contexts.push(context);
+    }
+
     let mut conn = pool.get().map_err(|e| e.to_string())?;
     let tx = conn.transaction().map_err(|e| e.to_string())?;
-    for trade in trades {
+    for (trade, context) in trades.iter().zip(contexts) {
+        let context = context.map(|c| serde_json::to_string(&c)).transpose().map_err(|e| e.to_string())?;
         tx.execute(
-            "INSERT INTO backtest_trades (id, backtest_id, symbol, side, qty, fill_price, timestamp, anomaly_id, rationale, realized_pnl)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            "INSERT INTO backtest_trades (id, backtest_id, symbol, side, qty, fill_price, timestamp, anomaly_id, rationale, realized_pnl, trade_context)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
             rusqlite::params![
                 trade.id,
                 trade.backtest_id,
@@ -85,6 +161,7 @@ pub fn backtest_insert_trades_db(pool: &DbPool, trades: &[BacktestTrade]) -> Res
                 trade.anomaly_id,
                 trade.rationale,
                 trade.realized_pnl,
+                context,
             ],
         )
         .map_err(|e| e.to_string())?;
@@ -93,11 +170,29 @@ pub fn backtest_insert_trades_db(pool: &DbPool, trades: &[BacktestTrade]) -> Res
     Ok(())
 }
 
+/// Store the trades of a `backtest:complete` payload. The agent numbers
+/// trades per run, so each id is prefixed with its backtest id.
+///
+/// Returns how many trades were stored.
+pub fn backtest_record_complete_db(pool: &DbPool, payload: &serde_json::Value) -> Result<usize, String> {
+    let mut trades: Vec<BacktestTrade> = match payload.get("trades") {
+        Some(trades) if !trades.is_null() => {
+            serde_json::from_value(trades.clone()).map_err(|e| format!("Invalid backtest trades: {}", e))?
+        }
+        _ => Vec::new(),
+    };
+    for trade in &mut trades {
+        trade.id = format!("{}:{}", trade.backtest_id, trade.id);
+    }
+    backtest_insert_trades_db(pool, &trades)?;
+    Ok(trades.len())
+}
+
 /// List all backtest runs ordered by creation time (newest first).
 pub fn backtest_list_db(pool: &DbPool) -> Result<Vec<BacktestSummary>, String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT id, status, config, metrics, created_at, completed_at, ticks_processed, total_ticks, error FROM backtests ORDER BY created_at DESC")
+        .prepare("SELECT id, status, config, metrics, created_at, completed_at, ticks_processed, total_ticks, error, mode FROM backtests ORDER BY created_at DESC")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
@@ -122,6 +217,7 @@ pub fn backtest_list_db(pool: &DbPool) -> Result<Vec<BacktestSummary>, String> {
                 ticks_processed: row.get(6)?,
                 total_ticks: row.get(7)?,
                 error: row.get(8)?,
+                mode: row.get(9)?,
             })
         })
         .map_err(|e| e.to_string())?;
@@ -139,7 +235,7 @@ pub fn backtest_list_db(pool: &DbPool) -> Result<Vec<BacktestSummary>, String> {
 pub fn backtest_get_db(pool: &DbPool, id: &str) -> Result<BacktestSummary, String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT id, status, config, metrics, created_at, completed_at, ticks_processed, total_ticks, error FROM backtests WHERE id = ?1")
+        .prepare("SELECT id, status, config, metrics, created_at, completed_at, ticks_processed, total_ticks, error, mode FROM backtests WHERE id = ?1")
         .map_err(|e| e.to_string())?;
 
     stmt.query_row([id], |row| {
@@ -163,6 +259,7 @@ pub fn backtest_get_db(pool: &DbPool, id: &str) -> Result<BacktestSummary, Strin
             ticks_processed: row.get(6)?,
             total_ticks: row.get(7)?,
             error: row.get(8)?,
+            mode: row.get(9)?,
         })
     })
     .map_err(|e| e.to_string())
@@ -172,7 +269,7 @@ pub fn backtest_get_db(pool: &DbPool, id: &str) -> Result<BacktestSummary, Strin
 pub fn backtest_get_trades_db(pool: &DbPool, backtest_id: &str) -> Result<Vec<BacktestTrade>, String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     let mut stmt = conn
-        .prepare("SELECT id, backtest_id, symbol, side, qty, fill_price, timestamp, anomaly_id, rationale, realized_pnl FROM backtest_trades WHERE backtest_id = ?1 ORDER BY timestamp")
+        .prepare("SELECT id, backtest_id, symbol, side, qty, fill_price, timestamp, anomaly_id, rationale, realized_pnl, trade_context FROM backtest_trades WHERE backtest_id = ?1 ORDER BY timestamp")
         .map_err(|e| e.to_string())?;
 
     let rows = stmt
@@ -188,6 +285,9 @@ pub fn backtest_get_trades_db(pool: &DbPool, backtest_id: &str) -> Result<Vec<Ba
                 anomaly_id: row.get::<_, Option<String>>(7)?.unwrap_or_default(),
                 rationale: row.get::<_, Option<String>>(8)?.unwrap_or_default(),
                 realized_pnl: row.get(9)?,
+                trade_context: row
+                    .get::<_, Option<String>>(10)?
+                    .and_then(|c| serde_json::from_str(&c).ok()),
             })
         })
         .map_err(|e| e.to_string())?;
@@ -222,43 +322,43 @@ pub fn backtest_delete_db(pool: &DbPool, id: &str) -> Result<(), String> {
 #[tauri::command]
 pub async fn backtest_start(
     app: tauri::AppHandle,
-    pool: tauri::State<'_, DbPool>,
+    workspace: tauri::State<'_, WorkspaceDb>,
     bridge: tauri::State<'_, SidecarBridge>,
     config: String,
-) -> Result<String, String> {
+    mode: Option<String>,
+) -> Result<String, BridgeError> {
+    let pool = workspace.pool();
     let parsed: BacktestConfig = serde_json::from_str(&config)
         .map_err(|e| format!("Invalid backtest config: {}", e))?;
-    backtest_insert_db(&pool, &parsed.id, &config)?;
-
-    // Resolve Alpaca credentials: DB first, then env vars
-    let creds = crate::commands::credentials::credentials_get_db(&pool, "paper")?;
-    let (alpaca_key, alpaca_secret) = match creds {
-        Some(c) => (c.key_id, c.secret_key),
-        None => {
-            let key = std::env::var("ALPACA_KEY_ID")
-                .map_err(|_| "Alpaca credentials not set. Configure in Settings or set ALPACA_KEY_ID/ALPACA_SECRET_KEY env vars.")?;
-            let secret = std::env::var("ALPACA_SECRET_KEY")
-                .map_err(|_| "ALPACA_SECRET_KEY env var not set.")?;
-            (key, secret)
-        }
-    };
-
-    // Resolve LLM keys from config DB, falling back to env vars
-    let app_config = crate::commands::config::config_get_db(&pool)?;
-    let app_config: serde_json::Value =
-        serde_json::from_str(&app_config).unwrap_or(serde_json::json!({}));
-
-    let anthropic_key = config_or_env(&app_config, "anthropicApiKey", "ANTHROPIC_API_KEY");
-    let openrouter_key = config_or_env(&app_config, "openrouterApiKey", "OPENROUTER_API_KEY");
+    let mode = resolve_trading_mode(mode)?;
+
+    // Record the run and resolve credentials and LLM keys off the IPC thread
+    let (backtest_id, raw_config, run_mode) = (parsed.id.clone(), config.clone(), mode.clone());
+    let (app_config, secrets, routing, profile) = db::run_blocking(&pool, move |pool| {
+        backtest_insert_db(pool, &backtest_id, &raw_config, &run_mode)?;
+        let app_config = load_app_config(pool)?;
+        let secrets = AgentSecrets::resolve(pool, &app_config, &run_mode)?;
+        let routing = secrets.provider_routing(pool, &app_config)?;
+        Ok((app_config, secrets, routing, active_profile_db(pool)?))
+    })
+    .await?;
+    let launch = sidecar_launch_config(&app_config)?;
+    let (alpaca_key, alpaca_secret) = secrets.alpaca_params(launch.secrets_via_env);
+    let (anthropic_key, openrouter_key) = secrets.llm_params(launch.secrets_via_env);
 
     let model = app_config
         .get("model")
         .and_then(|m| m.as_str())
-        .unwrap_or("claude-haiku-4-5-20251001");
+        .unwrap_or(DEFAULT_MODEL);
+
+    bridge.apply_launch_config(&launch);
 
-    // Auto-spawn sidecar if not running
+    // Auto-spawn sidecar if not running. The backtest's mode travels in its
+    // request; the bridge's mode stays that of the monitoring agent.
     if !bridge.is_running() {
-        bridge.spawn(app, "agent/src/index.ts")?;
+        bridge
+            .spawn(app, &sidecar_command(&launch, &secrets)?)
+            .map_err(BridgeError::sidecar_down)?;
     }
 
     // Send backtest:run JSON-RPC request
@@ -266,51 +366,57 @@ pub async fn backtest_start(
         .map_err(|e| format!("Invalid config: {}", e))?;
     let backtest_params = serde_json::json!({
         "config": parsed_config,
-        "alpaca": { "keyId": alpaca_key, "secretKey": alpaca_secret },
+        "alpaca": { "keyId": alpaca_key, "secretKey": alpaca_secret, "mode": mode },
         "llm": {
             "anthropicApiKey": anthropic_key,
             "openrouterApiKey": openrouter_key,
             "model": model,
             "maxTokens": 4096,
-            "temperature": 0.3
-        }
+            "temperature": 0.3,
+            "providers": routing.order
+        },
+        "profile": profile
     });
-    bridge.send_request("backtest:run", Some(backtest_params))?;
+    bridge.call("backtest:run", Some(backtest_params))?;
 
     Ok(parsed.id)
 }
 
 /// List all backtest runs, newest first.
 #[tauri::command]
-pub fn backtest_list(pool: tauri::State<'_, DbPool>) -> Result<Vec<BacktestSummary>, String> {
-    backtest_list_db(&pool)
+pub async fn backtest_list(workspace: tauri::State<'_, WorkspaceDb>) -> Result<Vec<BacktestSummary>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, backtest_list_db).await
 }
 
 /// Retrieve a single backtest run by ID.
 #[tauri::command]
-pub fn backtest_get(
-    pool: tauri::State<'_, DbPool>,
+pub async fn backtest_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
     backtest_id: String,
 ) -> Result<BacktestSummary, String> {
-    backtest_get_db(&pool, &backtest_id)
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| backtest_get_db(pool, &backtest_id)).await
 }
 
 /// Retrieve all trades for a given backtest run.
 #[tauri::command]
-pub fn backtest_get_trades(
-    pool: tauri::State<'_, DbPool>,
+pub async fn backtest_get_trades(
+    workspace: tauri::State<'_, WorkspaceDb>,
     backtest_id: String,
 ) -> Result<Vec<BacktestTrade>, String> {
-    backtest_get_trades_db(&pool, &backtest_id)
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| backtest_get_trades_db(pool, &backtest_id)).await
 }
 
 /// Delete a backtest run and its associated trades (via CASCADE).
 #[tauri::command]
-pub fn backtest_delete(
-    pool: tauri::State<'_, DbPool>,
+pub async fn backtest_delete(
+    workspace: tauri::State<'_, WorkspaceDb>,
     backtest_id: String,
 ) -> Result<(), String> {
-    backtest_delete_db(&pool, &backtest_id)
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| backtest_delete_db(pool, &backtest_id)).await
 }
 
 /// Cancel a running backtest by setting its status to `"cancelled"`.
@@ -318,22 +424,25 @@ pub fn backtest_delete(
 /// Updates the DB status and sends a `backtest:cancel` JSON-RPC request
 /// to the agent sidecar (best-effort).
 #[tauri::command]
-pub fn backtest_cancel(
-    pool: tauri::State<'_, DbPool>,
+pub async fn backtest_cancel(
+    workspace: tauri::State<'_, WorkspaceDb>,
     bridge: tauri::State<'_, SidecarBridge>,
     backtest_id: String,
 ) -> Result<(), String> {
-    let conn = pool.get().map_err(|e| e.to_string())?;
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| e.to_string())?
-        .as_millis() as i64;
-
-    conn.execute(
-        "UPDATE backtests SET status = 'cancelled', completed_at = ?1 WHERE id = ?2 AND status = 'running'",
-        rusqlite::params![now, backtest_id],
-    )
-    .map_err(|e| e.to_string())?;
+    let pool = workspace.pool();
+    let id = backtest_id.clone();
+    db::run_blocking(&pool, move |pool| {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        let now = now_millis() as i64;
+
+        conn.execute(
+            "UPDATE backtests SET status = 'cancelled', completed_at = ?1 WHERE id = ?2 AND status = 'running'",
+            rusqlite::params![now, id],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    })
+    .await?;
 
     // Best-effort: notify the agent to cancel the running backtest
     if bridge.is_running() {
@@ -348,14 +457,18 @@ pub fn backtest_cancel(
 /// Called when the UI receives a `backtest:complete` event to persist
 /// the final status, metrics, and any error message to the database.
 #[tauri::command]
-pub fn backtest_update_status(
-    pool: tauri::State<'_, DbPool>,
+pub async fn backtest_update_status(
+    workspace: tauri::State<'_, WorkspaceDb>,
     backtest_id: String,
     status: String,
     metrics: Option<String>,
     error: Option<String>,
 ) -> Result<(), String> {
-    backtest_update_status_db(&pool, &backtest_id, &status, metrics.as_deref(), error.as_deref())
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        backtest_update_status_db(pool, &backtest_id, &status, metrics.as_deref(), error.as_deref())
+    })
+    .await
 }
 
 #[cfg(test)]
@@ -388,6 +501,7 @@ mod tests {
             anomaly_id: "anom-1".to_string(),
             rationale: "Test trade".to_string(),
             realized_pnl: None,
+            trade_context: None,
         }
     }
 
@@ -395,7 +509,7 @@ mod tests {
     fn backtest_insert_and_get() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-1", config).unwrap();
+        backtest_insert_db(&pool, "bt-1", config, "paper").unwrap();
 
         let result = backtest_get_db(&pool, "bt-1").unwrap();
         assert_eq!(result.id, "bt-1");
@@ -404,14 +518,23 @@ mod tests {
         let parsed: serde_json::Value = serde_json::from_value(result.config).unwrap();
         assert_eq!(parsed["id"], "bt-1");
         assert_eq!(parsed["symbols"][0], "AAPL");
+        assert_eq!(result.mode, "paper");
+    }
+
+    #[test]
+    fn backtest_records_trading_mode() {
+        let pool = test_pool();
+        backtest_insert_db(&pool, "bt-live", sample_config_json(), "live").unwrap();
+        assert_eq!(backtest_get_db(&pool, "bt-live").unwrap().mode, "live");
+        assert_eq!(backtest_list_db(&pool).unwrap()[0].mode, "live");
     }
 
     #[test]
     fn backtest_insert_duplicate_fails() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-dup", config).unwrap();
-        let result = backtest_insert_db(&pool, "bt-dup", config);
+        backtest_insert_db(&pool, "bt-dup", config, "paper").unwrap();
+        let result = backtest_insert_db(&pool, "bt-dup", config, "paper");
         assert!(result.is_err());
     }
 
@@ -419,9 +542,9 @@ mod tests {
     fn backtest_list_returns_all() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-a", config).unwrap();
-        backtest_insert_db(&pool, "bt-b", config).unwrap();
-        backtest_insert_db(&pool, "bt-c", config).unwrap();
+        backtest_insert_db(&pool, "bt-a", config, "paper").unwrap();
+        backtest_insert_db(&pool, "bt-b", config, "paper").unwrap();
+        backtest_insert_db(&pool, "bt-c", config, "paper").unwrap();
 
         let list = backtest_list_db(&pool).unwrap();
         assert_eq!(list.len(), 3);
@@ -431,8 +554,8 @@ mod tests {
     fn backtest_list_orders_by_created_at_desc() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-1", config).unwrap();
-        backtest_insert_db(&pool, "bt-2", config).unwrap();
+        backtest_insert_db(&pool, "bt-1", config, "paper").unwrap();
+        backtest_insert_db(&pool, "bt-2", config, "paper").unwrap();
 
         let list = backtest_list_db(&pool).unwrap();
         assert_eq!(list.len(), 2);
@@ -443,7 +566,7 @@ mod tests {
     fn backtest_update_status() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-status", config).unwrap();
+        backtest_insert_db(&pool, "bt-status", config, "paper").unwrap();
 
         let metrics_json = r#"{"totalReturn":0.15,"sharpeRatio":1.2}"#;
         backtest_update_status_db(&pool, "bt-status", "completed", Some(metrics_json), None)
@@ -462,7 +585,7 @@ mod tests {
     fn backtest_delete_removes_record() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-del", config).unwrap();
+        backtest_insert_db(&pool, "bt-del", config, "paper").unwrap();
 
         backtest_delete_db(&pool, "bt-del").unwrap();
 
@@ -474,7 +597,7 @@ mod tests {
     fn backtest_delete_cascades_to_trades() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-cascade", config).unwrap();
+        backtest_insert_db(&pool, "bt-cascade", config, "paper").unwrap();
 
         let trades = vec![
             sample_trade("btt-1", "bt-cascade"),
@@ -509,7 +632,7 @@ mod tests {
     fn backtest_insert_trades_in_transaction() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-trades", config).unwrap();
+        backtest_insert_db(&pool, "bt-trades", config, "paper").unwrap();
 
         let trades = vec![
             sample_trade("btt-1", "bt-trades"),
@@ -525,6 +648,7 @@ mod tests {
                 anomaly_id: "anom-2".to_string(),
                 rationale: "Sell signal".to_string(),
                 realized_pnl: Some(250.0),
+                trade_context: None,
             },
         ];
         backtest_insert_trades_db(&pool, &trades).unwrap();
@@ -538,11 +662,91 @@ mod tests {
         assert_eq!(stored[2].realized_pnl, Some(250.0));
     }
 
+    #[test]
+    fn backtest_trades_snapshot_indicators_at_fill_bar() {
+        let pool = test_pool();
+        backtest_insert_db(&pool, "bt-ctx", sample_config_json(), "paper").unwrap();
+        let day = 86_400_000u64;
+        let bars: Vec<Bar> = (0..40u64)
+            .map(|i| {
+                let close = 100.0 + (i % 5) as f64;
+                Bar {
+                    timestamp: i * day,
+                    open: close,
+                    high: close + 1.0,
+                    low: close - 1.0,
+                    close,
+                    volume: 1000.0,
+                }
+            })
+            .collect();
+        crate::commands::bars::bars_insert_db(&pool, "AAPL", "1Day", "test", &bars).unwrap();
+
+        let mut early = sample_trade("ctx-early", "bt-ctx");
+        early.timestamp = (5 * day) as i64;
+        let mut late = sample_trade("ctx-late", "bt-ctx");
+        late.timestamp = (30 * day + 3_600_000) as i64;
+        let mut uncached = sample_trade("ctx-msft", "bt-ctx");
+        uncached.symbol = "MSFT".to_string();
+        backtest_insert_trades_db(&pool, &[early, late, uncached]).unwrap();
+
+        let stored = backtest_get_trades_db(&pool, "bt-ctx").unwrap();
+        let context = |id: &str| stored.iter().find(|t| t.id == id).unwrap().trade_context;
+        let early = context("ctx-early").unwrap();
+        assert_eq!(early.bar_timestamp, 5 * day);
+        assert_eq!((early.rsi, early.atr, early.percent_b), (None, None, None));
+        let late = context("ctx-late").unwrap();
+        assert_eq!(late.bar_timestamp, 30 * day);
+        assert!(late.rsi.is_some_and(|r| (0.0..=100.0).contains(&r)));
+        assert!(late.atr.is_some_and(|a| a > 0.0));
+        assert!(late.percent_b.is_some());
+        assert_eq!(context("ctx-msft"), None);
+    }
+
+    #[test]
+    fn backtest_complete_payload_stores_trades_per_run() {
+        let pool = test_pool();
+        backtest_insert_db(&pool, "bt-a", sample_config_json(), "paper").unwrap();
+        backtest_insert_db(&pool, "bt-b", sample_config_json(), "paper").unwrap();
+        crate::commands::bars::bars_insert_db(
+            &pool,
+            "AAPL",
+            "1Day",
+            "test",
+            &[Bar {
+                timestamp: 1_706_745_600_000,
+                open: 185.0,
+                high: 186.0,
+                low: 184.0,
+                close: 185.5,
+                volume: 1000.0,
+            }],
+        )
+        .unwrap();
+
+        let payload = |backtest_id: &str| {
+            let mut trade = sample_trade("btt-1", backtest_id);
+            trade.timestamp = 1_706_800_000_000;
+            serde_json::json!({ "backtestId": backtest_id, "status": "completed", "trades": [trade] })
+        };
+        assert_eq!(backtest_record_complete_db(&pool, &payload("bt-a")).unwrap(), 1);
+        assert_eq!(backtest_record_complete_db(&pool, &payload("bt-b")).unwrap(), 1);
+
+        let stored = backtest_get_trades_db(&pool, "bt-b").unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].id, "bt-b:btt-1");
+        assert_eq!(stored[0].trade_context.unwrap().bar_timestamp, 1_706_745_600_000);
+
+        let failed = serde_json::json!({ "backtestId": "bt-a", "status": "failed", "trades": [] });
+        assert_eq!(backtest_record_complete_db(&pool, &failed).unwrap(), 0);
+        assert!(backtest_record_complete_db(&pool, &serde_json::json!({ "trades": [{}] })).is_err());
+    }
+
     #[test]
     fn backtest_update_progress() {
         let pool = test_pool();
         let config = sample_config_json();
-        backtest_insert_db(&pool, "bt-progress", config).unwrap();
+        backtest_insert_db(&pool, "bt-progress", config, "paper").unwrap();
 
         backtest_update_progress_db(&pool, "bt-progress", 50, 200).unwrap();
 
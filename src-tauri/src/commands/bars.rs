@@ -0,0 +1,434 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::alpaca::AlpacaClient;
+use crate::csv_source::{parse_timestamp, split_record, TimestampFormat};
+use crate::db::{self, DbPool};
+use crate::time::now_millis;
+use crate::types::data::TickRange;
+use crate::workspace::WorkspaceDb;
+
+/// Bar sizes the cache accepts, in Alpaca's notation.
+pub const TIMEFRAMES: &[&str] = &["1Min", "5Min", "15Min", "30Min", "1Hour", "4Hour", "1Day", "1Week"];
+/// Most row problems listed in an import error.
+const MAX_REPORTED_ERRORS: usize = 10;
+/// Bars fetched when a range without a start misses the cache.
+const DEFAULT_FETCH_BARS: u64 = 500;
+
+/// One OHLCV bar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bar {
+    /// Epoch millis of the bar's start.
+    pub timestamp: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+}
+
+/// Which CSV columns hold each bar field.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BarCsvMapping {
+    pub timestamp: String,
+    pub timestamp_format: TimestampFormat,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    /// Bars without a volume column get a volume of zero.
+    pub volume: Option<String>,
+    pub delimiter: char,
+}
+
+impl Default for BarCsvMapping {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            timestamp_format: TimestampFormat::Auto,
+            open: "open".to_string(),
+            high: "high".to_string(),
+            low: "low".to_string(),
+            close: "close".to_string(),
+            volume: Some("volume".to_string()),
+            delimiter: ',',
+        }
+    }
+}
+
+/// Outcome of `bars_import_csv`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BarImportReport {
+    pub symbol: String,
+    pub timeframe: String,
+    pub imported: usize,
+    /// Epoch millis of the first and last imported bars.
+    pub first: Option<u64>,
+    pub last: Option<u64>,
+}
+
+pub fn validate_timeframe(timeframe: &str) -> Result<(), String> {
+    if TIMEFRAMES.contains(&timeframe) {
+        Ok(())
+    } else {
+        Err(format!("Invalid timeframe '{}'; expected one of {}", timeframe, TIMEFRAMES.join(", ")))
+    }
+}
+
+/// Length of one `timeframe` bar in millis.
+pub fn timeframe_millis(timeframe: &str) -> Result<u64, String> {
+    const MINUTE: u64 = 60 * 1000;
+    validate_timeframe(timeframe)?;
+    Ok(match timeframe {
+        "1Min" => MINUTE,
+        "5Min" => 5 * MINUTE,
+        "15Min" => 15 * MINUTE,
+        "30Min" => 30 * MINUTE,
+        "1Hour" => 60 * MINUTE,
+        "4Hour" => 4 * 60 * MINUTE,
+        "1Day" => 24 * 60 * MINUTE,
+        "1Week" => 7 * 24 * 60 * MINUTE,
+        _ => unreachable!("validated timeframe"),
+    })
+}
+
+/// Why `bar` is not a plausible OHLCV bar, if it is not.
+fn bar_problem(bar: &Bar) -> Option<&'static str> {
+    let prices = [bar.open, bar.high, bar.low, bar.close];
+    if prices.iter().any(|p| !(p.is_finite() && *p > 0.0)) {
+        return Some("prices must be positive");
+    }
+    if !(bar.volume.is_finite() && bar.volume >= 0.0) {
+        return Some("volume must not be negative");
+    }
+    if bar.high < bar.open.max(bar.close).max(bar.low) || bar.low > bar.open.min(bar.close) {
+        return Some("high/low do not bracket open and close");
+    }
+    None
+}
+
+/// Parse and validate CSV `text` (header row first) into bars, oldest first.
+/// Any bad row fails the whole file, listing the first problems by line number.
+pub fn parse_bars_csv(text: &str, mapping: &BarCsvMapping) -> Result<Vec<Bar>, String> {
+    let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let (_, header) = lines.next().ok_or("CSV file is empty")?;
+    let header = split_record(header, mapping.delimiter);
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Missing column '{}'", name))
+    };
+    let ts_idx = column(&mapping.timestamp)?;
+    let price_idx = [
+        column(&mapping.open)?,
+        column(&mapping.high)?,
+        column(&mapping.low)?,
+        column(&mapping.close)?,
+    ];
+    let volume_idx = mapping.volume.as_deref().map(column).transpose()?;
+
+    let mut bars = Vec::new();
+    let mut seen = HashSet::new();
+    let mut errors = Vec::new();
+    for (index, line) in lines {
+        let line_no = index + 1;
+        let fields = split_record(line, mapping.delimiter);
+        let number = |i: usize| fields.get(i).and_then(|v| v.parse::<f64>().ok());
+        let Some(timestamp) = fields
+            .get(ts_idx)
+            .and_then(|v| parse_timestamp(v, mapping.timestamp_format))
+        else {
+            errors.push(format!("line {}: invalid timestamp", line_no));
+            continue;
+        };
+        let [Some(open), Some(high), Some(low), Some(close)] = price_idx.map(number) else {
+            errors.push(format!("line {}: missing or non-numeric price", line_no));
+            continue;
+        };
+        let volume = match volume_idx {
+            Some(i) => match number(i) {
+                Some(v) => v,
+                None => {
+                    errors.push(format!("line {}: missing or non-numeric volume", line_no));
+                    continue;
+                }
+            },
+            None => 0.0,
+        };
+        let bar = Bar { timestamp, open, high, low, close, volume };
+        if let Some(problem) = bar_problem(&bar) {
+            errors.push(format!("line {}: {}", line_no, problem));
+            continue;
+        }
+        if !seen.insert(timestamp) {
+            errors.push(format!("line {}: duplicate timestamp", line_no));
+            continue;
+        }
+        bars.push(bar);
+    }
+    if !errors.is_empty() {
+        let shown = errors.len().min(MAX_REPORTED_ERRORS);
+        let more = errors.len() - shown;
+        let mut message = format!("{} invalid rows: {}", errors.len(), errors[..shown].join("; "));
+        if more > 0 {
+            message.push_str(&format!("; and {} more", more));
+        }
+        return Err(message);
+    }
+    if bars.is_empty() {
+        return Err("CSV file has no bars".to_string());
+    }
+    bars.sort_by_key(|b| b.timestamp);
+    Ok(bars)
+}
+
+/// Store `bars` for `symbol` and `timeframe` in one transaction, replacing
+/// cached bars with the same timestamps.
+pub fn bars_insert_db(pool: &DbPool, symbol: &str, timeframe: &str, source: &str, bars: &[Bar]) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO bars (symbol, timeframe, timestamp, open, high, low, close, volume, source)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .map_err(|e| e.to_string())?;
+        for bar in bars {
+            stmt.execute(rusqlite::params![
+                symbol,
+                timeframe,
+                bar.timestamp as i64,
+                bar.open,
+                bar.high,
+                bar.low,
+                bar.close,
+                bar.volume,
+                source,
+            ])
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Cached bars for `symbol` and `timeframe` between `from` and `to` (epoch
+/// millis, inclusive), oldest first.
+pub fn bars_query_db(
+    pool: &DbPool,
+    symbol: &str,
+    timeframe: &str,
+    from: Option<u64>,
+    to: Option<u64>,
+) -> Result<Vec<Bar>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT timestamp, open, high, low, close, volume FROM bars
+             WHERE symbol = ?1 AND timeframe = ?2 AND timestamp >= ?3 AND timestamp <= ?4
+             ORDER BY timestamp",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                symbol,
+                timeframe,
+                from.unwrap_or(0) as i64,
+                to.map(|t| t as i64).unwrap_or(i64::MAX),
+            ],
+            |row| {
+                Ok(Bar {
+                    timestamp: row.get::<_, i64>(0)?.max(0) as u64,
+                    open: row.get(1)?,
+                    high: row.get(2)?,
+                    low: row.get(3)?,
+                    close: row.get(4)?,
+                    volume: row.get(5)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Keep the last `limit` bars when a limit is set.
+fn take_last(mut bars: Vec<Bar>, limit: Option<u32>) -> Vec<Bar> {
+    if let Some(limit) = limit {
+        let excess = bars.len().saturating_sub(limit as usize);
+        bars.drain(..excess);
+    }
+    bars
+}
+
+/// Bars for `symbol` and `timeframe` in `range`, from the cache when it has
+/// any and otherwise fetched from Alpaca and cached; offline mode only reads
+/// the cache. `range.limit` keeps the most recent bars.
+pub async fn bars_load(pool: &DbPool, symbol: &str, timeframe: &str, range: &TickRange) -> Result<Vec<Bar>, String> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol must not be empty".to_string());
+    }
+    let bar_ms = timeframe_millis(timeframe)?;
+    let (from, to) = (range.from, range.to);
+    let cached = {
+        let (symbol, timeframe) = (symbol.clone(), timeframe.to_string());
+        db::run_blocking(pool, move |pool| bars_query_db(pool, &symbol, &timeframe, from, to)).await?
+    };
+    if !cached.is_empty() {
+        return Ok(take_last(cached, range.limit));
+    }
+
+    let end = to.unwrap_or_else(now_millis);
+    let lookback = u64::from(range.limit.unwrap_or(0)).max(DEFAULT_FETCH_BARS);
+    let start = from.unwrap_or_else(|| end.saturating_sub(lookback.saturating_mul(bar_ms)));
+    let client = match AlpacaClient::for_mode(pool, "paper").await {
+        Ok(c) => c,
+        Err(_) => AlpacaClient::for_mode(pool, "live").await?,
+    };
+    let fetched = client.bars(&symbol, timeframe, start, end).await?;
+    tracing::info!(symbol, timeframe, bars = fetched.len(), "Fetched bars from Alpaca");
+    if !fetched.is_empty() {
+        let (symbol, timeframe, bars) = (symbol.clone(), timeframe.to_string(), fetched.clone());
+        db::run_blocking(pool, move |pool| bars_insert_db(pool, &symbol, &timeframe, "alpaca", &bars)).await?;
+    }
+    Ok(take_last(fetched, range.limit))
+}
+
+/// Validate and load the file at `path` into the bars cache.
+pub fn import_csv(
+    pool: &DbPool,
+    path: &Path,
+    symbol: &str,
+    timeframe: &str,
+    mapping: &BarCsvMapping,
+) -> Result<BarImportReport, String> {
+    let symbol = symbol.trim().to_uppercase();
+    if symbol.is_empty() {
+        return Err("Symbol must not be empty".to_string());
+    }
+    validate_timeframe(timeframe)?;
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let bars = parse_bars_csv(&text, mapping)?;
+    let source = format!("csv:{}", path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default());
+    bars_insert_db(pool, &symbol, timeframe, &source, &bars)?;
+    tracing::info!(symbol, timeframe, bars = bars.len(), "Imported bars from CSV");
+    Ok(BarImportReport {
+        symbol,
+        timeframe: timeframe.to_string(),
+        imported: bars.len(),
+        first: bars.first().map(|b| b.timestamp),
+        last: bars.last().map(|b| b.timestamp),
+    })
+}
+
+/// Load user-provided OHLCV bars for `symbol` from the CSV at `path`. The file
+/// is imported in full or not at all.
+#[tauri::command]
+pub async fn bars_import_csv(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+    symbol: String,
+    timeframe: String,
+    mapping: Option<BarCsvMapping>,
+) -> Result<BarImportReport, String> {
+    let mapping = mapping.unwrap_or_default();
+    db::run_blocking(&workspace.pool(), move |pool| {
+        import_csv(pool, Path::new(&path), &symbol, &timeframe, &mapping)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn parses_and_sorts_bars() {
+        let text = "Date,Open,High,Low,Close,Volume\n\
+                    2025-01-03,101,103,100,102,1500\n\
+                    2025-01-02,100,102,99,101,1000\n";
+        let mapping = BarCsvMapping { timestamp: "date".to_string(), ..Default::default() };
+        let bars = parse_bars_csv(text, &mapping).unwrap();
+        assert_eq!(bars.len(), 2);
+        assert!(bars[0].timestamp < bars[1].timestamp);
+        assert_eq!(bars[0].close, 101.0);
+        assert_eq!(bars[1].volume, 1500.0);
+    }
+
+    #[test]
+    fn timeframes_have_lengths_and_limits_keep_the_latest() {
+        assert_eq!(timeframe_millis("5Min").unwrap(), 300_000);
+        assert_eq!(timeframe_millis("1Week").unwrap(), 604_800_000);
+        assert!(timeframe_millis("2Day").is_err());
+        for timeframe in TIMEFRAMES {
+            assert!(timeframe_millis(timeframe).unwrap() > 0);
+        }
+
+        let bar = |timestamp| Bar { timestamp, open: 1.0, high: 1.0, low: 1.0, close: 1.0, volume: 0.0 };
+        let bars = take_last(vec![bar(1), bar(2), bar(3)], Some(2));
+        assert_eq!(bars.iter().map(|b| b.timestamp).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(take_last(vec![bar(1)], None).len(), 1);
+    }
+
+    #[test]
+    fn invalid_rows_fail_the_whole_file() {
+        let text = "timestamp,open,high,low,close,volume\n\
+                    1735776000,100,102,99,101,1000\n\
+                    1735862400,100,99,98,101,1000\n\
+                    1735862400,100,102,99,101,1000\n\
+                    soon,100,102,99,101,1000\n";
+        let err = parse_bars_csv(text, &BarCsvMapping::default()).unwrap_err();
+        assert!(err.starts_with("2 invalid rows"), "{err}");
+        assert!(err.contains("line 3: high/low"), "{err}");
+        assert!(err.contains("line 5: invalid timestamp"), "{err}");
+
+        let missing = "timestamp,open,high,low\n1,1,1,1\n";
+        assert_eq!(parse_bars_csv(missing, &BarCsvMapping::default()).unwrap_err(), "Missing column 'close'");
+    }
+
+    #[test]
+    fn import_replaces_overlapping_bars() {
+        let (dir, pool) = test_pool();
+        let path = dir.path().join("spy.csv");
+        std::fs::write(&path, "t,o,h,l,c\n1000,1,2,1,1.5\n2000,1.5,2,1,1.8\n").unwrap();
+        let mapping = BarCsvMapping {
+            timestamp: "t".to_string(),
+            timestamp_format: TimestampFormat::Ms,
+            open: "o".to_string(),
+            high: "h".to_string(),
+            low: "l".to_string(),
+            close: "c".to_string(),
+            volume: None,
+            delimiter: ',',
+        };
+        let report = import_csv(&pool, &path, "spy", "1Day", &mapping).unwrap();
+        assert_eq!(report.symbol, "SPY");
+        assert_eq!(report.imported, 2);
+        assert_eq!((report.first, report.last), (Some(1000), Some(2000)));
+
+        std::fs::write(&path, "t,o,h,l,c\n2000,1.5,2.5,1,2.4\n").unwrap();
+        import_csv(&pool, &path, "SPY", "1Day", &mapping).unwrap();
+        let bars = bars_query_db(&pool, "SPY", "1Day", None, None).unwrap();
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[1].close, 2.4);
+        assert_eq!(bars[0].volume, 0.0);
+        assert!(bars_query_db(&pool, "SPY", "1Hour", None, None).unwrap().is_empty());
+
+        assert!(import_csv(&pool, &path, "SPY", "2Day", &mapping).is_err());
+    }
+}
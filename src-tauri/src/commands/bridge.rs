@@ -0,0 +1,21 @@
+use crate::bridge::SidecarBridge;
+use crate::bridge_journal::JournalSnapshot;
+use crate::bridge_metrics::BridgeMetricsSnapshot;
+
+/// Per-method JSON-RPC call counts, error counts, and latency percentiles.
+#[tauri::command]
+pub fn bridge_metrics(bridge: tauri::State<'_, SidecarBridge>) -> BridgeMetricsSnapshot {
+    bridge.metrics_snapshot()
+}
+
+/// Clear all recorded bridge RPC statistics.
+#[tauri::command]
+pub fn bridge_metrics_reset(bridge: tauri::State<'_, SidecarBridge>) {
+    bridge.reset_metrics();
+}
+
+/// Request journaling mode and replay counters.
+#[tauri::command]
+pub fn bridge_journal_status(bridge: tauri::State<'_, SidecarBridge>) -> JournalSnapshot {
+    bridge.journal_snapshot()
+}
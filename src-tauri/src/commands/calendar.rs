@@ -0,0 +1,502 @@
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::alpaca::AlpacaClient;
+use crate::db::{self, DbPool};
+use crate::http::{self, Conditional};
+use crate::market_calendar::{self, MarketSession};
+use crate::tasks::TaskOutcome;
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// `config` table key holding the date range the cached calendar covers.
+const COVERAGE_KEY: &str = "market_calendar_coverage";
+/// Refetch the calendar once a day.
+const REFRESH_AFTER_MS: u64 = 24 * 60 * 60 * 1000;
+/// Days before and after today to fetch, widened to whole months so the
+/// request stays the same, and can be answered with `304`, all month.
+const FETCH_PAST_DAYS: u64 = 7;
+const FETCH_AHEAD_DAYS: u64 = 180;
+/// Extended hours: pre-market opens at 04:00, after-hours ends four hours after the close.
+const PRE_MARKET_MS: i64 = 330 * 60_000;
+const AFTER_HOURS_MS: i64 = 4 * 60 * 60_000;
+
+/// The date range of the fetched calendar in the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarCoverage {
+    /// First and last covered dates, `YYYY-MM-DD`.
+    pub start: String,
+    pub end: String,
+    pub fetched_at: u64,
+}
+
+fn date_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Where a market status came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CalendarSource {
+    Alpaca,
+    Builtin,
+}
+
+/// NYSE sessions from the cached Alpaca calendar, falling back to the
+/// built-in holiday table outside the fetched range.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Calendar {
+    sessions: Vec<MarketSession>,
+    coverage: Option<CalendarCoverage>,
+}
+
+impl Calendar {
+    /// A calendar using only the built-in holiday table.
+    pub fn builtin() -> Self {
+        Self::default()
+    }
+
+    pub fn source(&self, date: NaiveDate) -> CalendarSource {
+        let key = date_key(date);
+        match &self.coverage {
+            Some(c) if c.start <= key && key <= c.end => CalendarSource::Alpaca,
+            _ => CalendarSource::Builtin,
+        }
+    }
+
+    /// The session on `date`, or `None` if the market is closed that day.
+    pub fn session(&self, date: NaiveDate) -> Option<MarketSession> {
+        match self.source(date) {
+            CalendarSource::Alpaca => {
+                let key = date_key(date);
+                self.sessions.iter().find(|s| s.date == key).cloned()
+            }
+            CalendarSource::Builtin => market_calendar::session(date),
+        }
+    }
+
+    /// The session in progress at `now` (epoch millis), or else the next one.
+    pub fn next_session(&self, now: i64) -> Option<MarketSession> {
+        market_calendar::next_session_with(now, |date| self.session(date))
+    }
+
+    /// Whether the regular session is open at `now` (epoch millis).
+    pub fn is_open(&self, now: i64) -> bool {
+        self.next_session(now).is_some_and(|s| s.open_at <= now)
+    }
+}
+
+/// A day of Alpaca's `/v2/calendar`, in New York time.
+#[derive(Debug, Deserialize)]
+struct AlpacaCalendarDay {
+    date: String,
+    open: String,
+    close: String,
+}
+
+fn parse_hhmm(value: &str) -> Result<(u32, u32), String> {
+    let time = NaiveTime::parse_from_str(value, "%H:%M").map_err(|_| format!("Invalid calendar time '{}'", value))?;
+    Ok((time.hour(), time.minute()))
+}
+
+impl TryFrom<AlpacaCalendarDay> for MarketSession {
+    type Error = String;
+
+    fn try_from(day: AlpacaCalendarDay) -> Result<Self, String> {
+        let date = NaiveDate::parse_from_str(&day.date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid calendar date '{}'", day.date))?;
+        let close = parse_hhmm(&day.close)?;
+        Ok(MarketSession {
+            date: day.date,
+            open_at: market_calendar::new_york_to_epoch_millis(date, parse_hhmm(&day.open)?),
+            close_at: market_calendar::new_york_to_epoch_millis(date, close),
+            early_close: close < (16, 0),
+        })
+    }
+}
+
+/// Replace the cached sessions for `coverage` with `sessions`.
+pub fn calendar_store_db(pool: &DbPool, coverage: &CalendarCoverage, sessions: &[MarketSession]) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM market_calendar WHERE date BETWEEN ?1 AND ?2",
+        rusqlite::params![coverage.start, coverage.end],
+    )
+    .map_err(|e| e.to_string())?;
+    for s in sessions {
+        tx.execute(
+            "INSERT OR REPLACE INTO market_calendar (date, open_at, close_at, early_close) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![s.date, s.open_at, s.close_at, s.early_close],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    store_coverage(&tx, coverage)?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn store_coverage(conn: &rusqlite::Connection, coverage: &CalendarCoverage) -> Result<(), String> {
+    let json = serde_json::to_string(coverage).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![COVERAGE_KEY, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Record that the cached sessions for `coverage` were confirmed current.
+pub fn calendar_touch_db(pool: &DbPool, coverage: &CalendarCoverage) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    store_coverage(&conn, coverage)
+}
+
+/// The cached calendar; only the built-in table if nothing was fetched yet.
+pub fn calendar_load_db(pool: &DbPool) -> Result<Calendar, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let coverage: Option<CalendarCoverage> = conn
+        .query_row("SELECT value FROM config WHERE key = ?1", [COVERAGE_KEY], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .and_then(|v| serde_json::from_str(&v).ok());
+    let Some(coverage) = coverage else {
+        return Ok(Calendar::builtin());
+    };
+    let mut stmt = conn
+        .prepare("SELECT date, open_at, close_at, early_close FROM market_calendar ORDER BY date")
+        .map_err(|e| e.to_string())?;
+    let sessions = stmt
+        .query_map([], |row| {
+            Ok(MarketSession {
+                date: row.get(0)?,
+                open_at: row.get(1)?,
+                close_at: row.get(2)?,
+                early_close: row.get(3)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    Ok(Calendar {
+        sessions,
+        coverage: Some(coverage),
+    })
+}
+
+/// Fetch the calendar around today from Alpaca and cache it. The calendar is
+/// the same for both environments, so either mode's credentials will do.
+pub async fn refresh(pool: &DbPool) -> Result<CalendarCoverage, String> {
+    let client = match AlpacaClient::for_mode(pool, "paper").await {
+        Ok(c) => c,
+        Err(_) => AlpacaClient::for_mode(pool, "live").await?,
+    };
+    let fetched_at = now_millis();
+    let today = DateTime::from_timestamp_millis(fetched_at as i64)
+        .ok_or("Invalid clock")?
+        .date_naive();
+    let (start, end) = fetch_window(today);
+    let coverage = CalendarCoverage {
+        start: date_key(start),
+        end: date_key(end),
+        fetched_at,
+    };
+    let query = [("start", coverage.start.as_str()), ("end", coverage.end.as_str())];
+    let url = client.url("/v2/calendar", &query);
+    let validators = {
+        let (url, coverage) = (url.clone(), coverage.clone());
+        db::run_blocking(pool, move |pool| {
+            // Only a cache holding this exact window may be revalidated
+            let cached = calendar_load_db(pool)?.coverage;
+            if cached.is_some_and(|c| c.start == coverage.start && c.end == coverage.end) {
+                http::validators_get_db(pool, &url)
+            } else {
+                Ok(Default::default())
+            }
+        })
+        .await?
+    };
+    let response = client
+        .get_conditional::<Vec<AlpacaCalendarDay>>("/v2/calendar", &query, &validators)
+        .await?;
+    let stored = coverage.clone();
+    match response {
+        Conditional::NotModified => {
+            db::run_blocking(pool, move |pool| calendar_touch_db(pool, &stored)).await?;
+            tracing::info!(start = coverage.start, end = coverage.end, "Market calendar unchanged");
+        }
+        Conditional::Modified(days, validators) => {
+            let sessions = days
+                .into_iter()
+                .map(MarketSession::try_from)
+                .collect::<Result<Vec<_>, _>>()?;
+            db::run_blocking(pool, move |pool| {
+                calendar_store_db(pool, &stored, &sessions)?;
+                http::validators_set_db(pool, &url, &validators)
+            })
+            .await?;
+            tracing::info!(start = coverage.start, end = coverage.end, "Market calendar refreshed");
+        }
+    }
+    Ok(coverage)
+}
+
+/// First and last dates to fetch around `today`: whole months covering
+/// `FETCH_PAST_DAYS` before and `FETCH_AHEAD_DAYS` after.
+fn fetch_window(today: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first_of_month = |d: NaiveDate| d.with_day(1).unwrap_or(d);
+    let start = first_of_month(today - Days::new(FETCH_PAST_DAYS));
+    let ahead = first_of_month(today + Days::new(FETCH_AHEAD_DAYS));
+    let end = ahead
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(ahead);
+    (start, end)
+}
+
+fn needs_refresh(coverage: Option<&CalendarCoverage>, now_ms: u64) -> bool {
+    coverage.is_none_or(|c| now_ms.saturating_sub(c.fetched_at) >= REFRESH_AFTER_MS)
+}
+
+/// Scheduled task: keep the cached calendar fresh. Without Alpaca
+/// credentials the built-in table is used.
+pub fn refresh_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let coverage = calendar_load_db(&pool).ok().and_then(|c| c.coverage);
+    if !needs_refresh(coverage.as_ref(), now_millis()) {
+        return Ok(TaskOutcome::Skipped);
+    }
+    if let Err(e) = tauri::async_runtime::block_on(refresh(&pool)) {
+        tracing::debug!(error = %e, "Market calendar refresh failed; using built-in calendar");
+        return Ok(TaskOutcome::Skipped);
+    }
+    Ok(TaskOutcome::Ran)
+}
+
+/// Asset classes `market_status` knows the hours of.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AssetClass {
+    #[default]
+    UsEquity,
+    Crypto,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionType {
+    PreMarket,
+    Regular,
+    AfterHours,
+    Closed,
+    /// Trades around the clock.
+    Continuous,
+}
+
+/// Whether a market is open now and when that next changes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketStatus {
+    pub asset_class: AssetClass,
+    /// Whether the regular session is open.
+    pub is_open: bool,
+    pub session_type: SessionType,
+    /// Whether the current or next session closes early.
+    pub early_close: bool,
+    /// Epoch millis of the next opening bell; `None` for continuous markets.
+    pub next_open: Option<i64>,
+    /// Epoch millis of the next closing bell; `None` for continuous markets.
+    pub next_close: Option<i64>,
+    pub source: CalendarSource,
+}
+
+/// The status of `asset_class` at `now` (epoch millis).
+pub fn status_at(calendar: &Calendar, asset_class: AssetClass, now: i64) -> MarketStatus {
+    if asset_class == AssetClass::Crypto {
+        return MarketStatus {
+            asset_class,
+            is_open: true,
+            session_type: SessionType::Continuous,
+            early_close: false,
+            next_open: None,
+            next_close: None,
+            source: CalendarSource::Builtin,
+        };
+    }
+    let today = DateTime::from_timestamp_millis(now).map(|t| t.date_naive());
+    let source = today.map_or(CalendarSource::Builtin, |d| calendar.source(d));
+    let current = calendar.next_session(now);
+    let is_open = current.as_ref().is_some_and(|s| s.open_at <= now);
+    let (next_open, next_close) = match &current {
+        Some(s) if is_open => (calendar.next_session(s.close_at).map(|n| n.open_at), Some(s.close_at)),
+        Some(s) => (Some(s.open_at), Some(s.close_at)),
+        None => (None, None),
+    };
+    // Extended hours of yesterday's or today's session (in UTC dates)
+    let nearby = today
+        .into_iter()
+        .flat_map(|d| [d - Days::new(1), d])
+        .filter_map(|d| calendar.session(d))
+        .collect::<Vec<_>>();
+    let session_type = if is_open {
+        SessionType::Regular
+    } else if nearby.iter().any(|s| s.open_at - PRE_MARKET_MS <= now && now < s.open_at) {
+        SessionType::PreMarket
+    } else if nearby.iter().any(|s| s.close_at <= now && now < s.close_at + AFTER_HOURS_MS) {
+        SessionType::AfterHours
+    } else {
+        SessionType::Closed
+    };
+    MarketStatus {
+        asset_class,
+        is_open,
+        session_type,
+        early_close: current.is_some_and(|s| s.early_close),
+        next_open,
+        next_close,
+        source,
+    }
+}
+
+/// Whether the market for `asset_class` (US equities when omitted) is open,
+/// and when it next opens and closes.
+#[tauri::command]
+pub async fn market_status(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    asset_class: Option<AssetClass>,
+) -> Result<MarketStatus, String> {
+    let calendar = db::run_blocking(&workspace.pool(), calendar_load_db).await?;
+    Ok(status_at(&calendar, asset_class.unwrap_or_default(), now_millis() as i64))
+}
+
+/// Whether the regular session for `asset_class` is open right now.
+#[tauri::command]
+pub async fn is_market_open(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    asset_class: Option<AssetClass>,
+) -> Result<bool, String> {
+    Ok(market_status(workspace, asset_class).await?.is_open)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn utc_millis(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        date(y, m, d).and_hms_opt(h, min, 0).unwrap().and_utc().timestamp_millis()
+    }
+
+    fn alpaca_day(date: &str, open: &str, close: &str) -> MarketSession {
+        let day: AlpacaCalendarDay =
+            serde_json::from_value(serde_json::json!({"date": date, "open": open, "close": close})).unwrap();
+        MarketSession::try_from(day).unwrap()
+    }
+
+    #[test]
+    fn alpaca_days_convert_to_sessions() {
+        let s = alpaca_day("2025-11-28", "09:30", "13:00");
+        assert_eq!(s.open_at, utc_millis(2025, 11, 28, 14, 30));
+        assert_eq!(s.close_at, utc_millis(2025, 11, 28, 18, 0));
+        assert!(s.early_close);
+        assert!(!alpaca_day("2025-06-16", "09:30", "16:00").early_close);
+    }
+
+    #[test]
+    fn cached_calendar_overrides_builtin_inside_coverage() {
+        let (_dir, pool) = test_pool();
+        assert_eq!(calendar_load_db(&pool).unwrap(), Calendar::builtin());
+
+        // A fetched calendar without 2025-06-17, e.g. an unscheduled closure
+        let coverage = CalendarCoverage {
+            start: "2025-06-16".to_string(),
+            end: "2025-06-18".to_string(),
+            fetched_at: 1,
+        };
+        let sessions = [alpaca_day("2025-06-16", "09:30", "16:00"), alpaca_day("2025-06-18", "09:30", "16:00")];
+        calendar_store_db(&pool, &coverage, &sessions).unwrap();
+        let calendar = calendar_load_db(&pool).unwrap();
+        assert_eq!(calendar.source(date(2025, 6, 17)), CalendarSource::Alpaca);
+        assert!(calendar.session(date(2025, 6, 17)).is_none());
+        assert!(!calendar.is_open(utc_millis(2025, 6, 17, 15, 0)));
+        // Outside the fetched range the built-in table applies
+        assert_eq!(calendar.source(date(2025, 6, 19)), CalendarSource::Builtin);
+        assert_eq!(calendar.next_session(utc_millis(2025, 6, 19, 15, 0)).unwrap().date, "2025-06-20");
+    }
+
+    #[test]
+    fn status_reports_session_type_and_next_bells() {
+        let calendar = Calendar::builtin();
+        // 2025-06-16 regular session: 13:30–20:00 UTC
+        let open = status_at(&calendar, AssetClass::UsEquity, utc_millis(2025, 6, 16, 15, 0));
+        assert!(open.is_open);
+        assert_eq!(open.session_type, SessionType::Regular);
+        assert_eq!(open.next_close, Some(utc_millis(2025, 6, 16, 20, 0)));
+        assert_eq!(open.next_open, Some(utc_millis(2025, 6, 17, 13, 30)));
+
+        let pre = status_at(&calendar, AssetClass::UsEquity, utc_millis(2025, 6, 16, 9, 0));
+        assert_eq!(pre.session_type, SessionType::PreMarket);
+        assert_eq!(pre.next_open, Some(utc_millis(2025, 6, 16, 13, 30)));
+
+        let post = status_at(&calendar, AssetClass::UsEquity, utc_millis(2025, 6, 16, 22, 0));
+        assert_eq!(post.session_type, SessionType::AfterHours);
+
+        let weekend = status_at(&calendar, AssetClass::UsEquity, utc_millis(2025, 6, 14, 15, 0));
+        assert_eq!(weekend.session_type, SessionType::Closed);
+        assert_eq!(weekend.next_open, Some(utc_millis(2025, 6, 16, 13, 30)));
+
+        let crypto = status_at(&calendar, AssetClass::Crypto, utc_millis(2025, 6, 14, 15, 0));
+        assert!(crypto.is_open);
+        assert_eq!(crypto.session_type, SessionType::Continuous);
+    }
+
+    #[test]
+    fn refresh_is_due_daily() {
+        let coverage = CalendarCoverage {
+            start: "2025-06-01".to_string(),
+            end: "2025-12-01".to_string(),
+            fetched_at: 1_000,
+        };
+        assert!(needs_refresh(None, 0));
+        assert!(!needs_refresh(Some(&coverage), 1_000 + REFRESH_AFTER_MS - 1));
+        assert!(needs_refresh(Some(&coverage), 1_000 + REFRESH_AFTER_MS));
+    }
+
+    #[test]
+    fn fetch_window_is_stable_within_a_month() {
+        assert_eq!(fetch_window(date(2025, 6, 16)), (date(2025, 6, 1), date(2025, 12, 31)));
+        assert_eq!(fetch_window(date(2025, 6, 20)), fetch_window(date(2025, 6, 16)));
+        // A week back crosses into the previous month early on
+        assert_eq!(fetch_window(date(2025, 3, 3)).0, date(2025, 2, 1));
+        assert_eq!(fetch_window(date(2025, 8, 20)).1, date(2026, 2, 28));
+    }
+
+    #[test]
+    fn touch_keeps_sessions_and_moves_fetched_at() {
+        let (_dir, pool) = test_pool();
+        let coverage = CalendarCoverage {
+            start: "2025-06-16".to_string(),
+            end: "2025-06-18".to_string(),
+            fetched_at: 1,
+        };
+        calendar_store_db(&pool, &coverage, &[alpaca_day("2025-06-16", "09:30", "16:00")]).unwrap();
+        calendar_touch_db(&pool, &CalendarCoverage { fetched_at: 99, ..coverage }).unwrap();
+        let calendar = calendar_load_db(&pool).unwrap();
+        assert_eq!(calendar.coverage.unwrap().fetched_at, 99);
+        assert_eq!(calendar.sessions.len(), 1);
+    }
+}
@@ -1,4 +1,72 @@
-use crate::db::DbPool;
+use std::fmt;
+use std::path::Path;
+
+use serde::ser::{Serialize, Serializer};
+use tauri::Manager;
+use tracing::warn;
+
+use crate::alpaca_stream::{alpaca_stream_config, AlpacaStream};
+use crate::bridge::SidecarBridge;
+use crate::commands::offline::{config_queued_db, is_offline, queue_config_db, QueuedOp};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::http::{self, network_config};
+use crate::types::config::{config_defaults, AppConfig, FieldError, SECRET_FIELDS};
+use crate::watcher::{watcher_config, FileWatcher};
+use crate::local_api::{local_api_config, LocalApiServer};
+use crate::webhook::{webhook_config, WebhookServer};
+use crate::workspace::WorkspaceDb;
+
+/// Failure of `config_update`. Serialized as `{ kind, message, fields }` so the
+/// settings UI can highlight the offending inputs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigError {
+    /// The patch failed validation; one entry per bad setting.
+    Invalid(Vec<FieldError>),
+    Internal(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Invalid(fields) => {
+                let details: Vec<String> = fields
+                    .iter()
+                    .map(|e| {
+                        if e.path.is_empty() {
+                            e.message.clone()
+                        } else {
+                            format!("{}: {}", e.path, e.message)
+                        }
+                    })
+                    .collect();
+                write!(f, "Invalid config: {}", details.join("; "))
+            }
+            ConfigError::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl Serialize for ConfigError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (kind, fields) = match self {
+            ConfigError::Invalid(fields) => ("invalid", fields.as_slice()),
+            ConfigError::Internal(_) => ("internal", &[][..]),
+        };
+        serde_json::json!({
+            "kind": kind,
+            "message": self.to_string(),
+            "fields": fields,
+        })
+        .serialize(serializer)
+    }
+}
+
+impl From<String> for ConfigError {
+    fn from(message: String) -> Self {
+        ConfigError::Internal(message)
+    }
+}
 
 /// Direct DB access for testing (no Tauri State)
 pub fn config_get_db(pool: &DbPool) -> Result<String, String> {
@@ -13,6 +81,40 @@ pub fn config_get_db(pool: &DbPool) -> Result<String, String> {
     Ok(result.unwrap_or_else(|| "{}".to_string()))
 }
 
+/// Stored config deep-merged over [`config_defaults`], so settings added in
+/// newer versions always have a value.
+pub fn config_effective_db(pool: &DbPool) -> Result<serde_json::Value, String> {
+    let stored: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).unwrap_or(serde_json::json!({}));
+    let mut effective = config_defaults();
+    merge_json(&mut effective, &stored);
+    Ok(effective)
+}
+
+/// Restore defaults for one top-level `section`, or for the whole config when
+/// `None`. A full reset keeps API keys. Returns the effective config.
+pub fn config_reset_db(pool: &DbPool, section: Option<&str>) -> Result<String, String> {
+    let current = config_get_db(pool)?;
+    let mut stored: serde_json::Value =
+        serde_json::from_str(&current).unwrap_or(serde_json::json!({}));
+    let map = stored
+        .as_object_mut()
+        .ok_or("Stored config is not a JSON object")?;
+    match section {
+        Some(section) => {
+            if config_defaults().get(section).is_none() {
+                return Err(format!("Unknown config section '{}'", section));
+            }
+            map.remove(section);
+        }
+        None => map.retain(|key, _| SECRET_FIELDS.contains(&key.as_str())),
+    }
+    let next = serde_json::to_string(&stored).map_err(|e| e.to_string())?;
+    snapshot_config(pool, &current)?;
+    config_set_db(pool, &next)?;
+    serde_json::to_string(&config_effective_db(pool)?).map_err(|e| e.to_string())
+}
+
 pub fn config_set_db(pool: &DbPool, json: &str) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
     conn.execute(
@@ -24,20 +126,257 @@ pub fn config_set_db(pool: &DbPool, json: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub fn config_update_db(pool: &DbPool, patch_json: &str) -> Result<String, String> {
+/// Validate `patch_json` against [`AppConfig`] and merge it into the stored config.
+pub fn config_update_db(pool: &DbPool, patch_json: &str) -> Result<String, ConfigError> {
+    let patch_val = parse_config_json(patch_json, "Patch")?;
+    apply_config(pool, &patch_val, ImportMode::Merge)
+}
+
+pub(crate) fn parse_config_json(json: &str, what: &str) -> Result<serde_json::Value, ConfigError> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| {
+        ConfigError::Invalid(vec![FieldError {
+            path: String::new(),
+            message: format!("{} is not valid JSON: {}", what, e),
+        }])
+    })?;
+    AppConfig::validate(&value).map_err(ConfigError::Invalid)?;
+    Ok(value)
+}
+
+/// Write an already-validated config, snapshotting the previous value.
+pub(crate) fn apply_config(
+    pool: &DbPool,
+    value: &serde_json::Value,
+    mode: ImportMode,
+) -> Result<String, ConfigError> {
+    let current = config_get_db(pool)?;
+    let next = match mode {
+        ImportMode::Merge => {
+            let mut current_val: serde_json::Value =
+                serde_json::from_str(&current).map_err(|e| e.to_string())?;
+            merge_json(&mut current_val, value);
+            current_val
+        }
+        ImportMode::Replace => value.clone(),
+    };
+    let next = serde_json::to_string(&next).map_err(|e| e.to_string())?;
+    snapshot_config(pool, &current)?;
+    config_set_db(pool, &next)?;
+    Ok(next)
+}
+
+/// Split a dot path like `monitor.analysisIntervalMs` into its segments.
+fn parse_key_path(path: &str) -> Result<Vec<&str>, String> {
+    let segments: Vec<&str> = path.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return Err(format!("Invalid config path '{}'", path));
+    }
+    Ok(segments)
+}
+
+/// Read a single setting by dot path; `None` if it is not set.
+pub fn config_get_key_db(pool: &DbPool, path: &str) -> Result<Option<serde_json::Value>, String> {
+    let segments = parse_key_path(path)?;
+    let config: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    let mut node = &config;
+    for segment in segments {
+        match node.get(segment) {
+            Some(child) => node = child,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(node.clone()))
+}
+
+/// Validate `value` for the setting at `path` as if it were sent in a
+/// `config_update` patch.
+pub(crate) fn validate_key(path: &str, value: &serde_json::Value) -> Result<(), ConfigError> {
+    let patch = parse_key_path(path)?
+        .iter()
+        .rev()
+        .fold(value.clone(), |inner, segment| serde_json::json!({ *segment: inner }));
+    AppConfig::validate(&patch).map_err(ConfigError::Invalid)
+}
+
+/// Set a single setting by dot path, replacing whatever was there. The value is
+/// validated as if it were sent in a `config_update` patch.
+pub fn config_set_key_db(
+    pool: &DbPool,
+    path: &str,
+    value: serde_json::Value,
+) -> Result<String, ConfigError> {
+    validate_key(path, &value)?;
     let current = config_get_db(pool)?;
-    let mut current_val: serde_json::Value =
-        serde_json::from_str(&current).map_err(|e| e.to_string())?;
-    let patch_val: serde_json::Value =
-        serde_json::from_str(patch_json).map_err(|e| e.to_string())?;
+    let mut config: serde_json::Value = serde_json::from_str(&current).map_err(|e| e.to_string())?;
+    set_key(&mut config, path, value)?;
+
+    let next = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    snapshot_config(pool, &current)?;
+    config_set_db(pool, &next)?;
+    Ok(next)
+}
+
+/// Set `path` in `config` to `value`, creating missing parent objects.
+pub(crate) fn set_key(config: &mut serde_json::Value, path: &str, value: serde_json::Value) -> Result<(), String> {
+    let segments = parse_key_path(path)?;
+    let Some((last, parents)) = segments.split_last() else {
+        return Err(format!("Invalid config path '{}'", path));
+    };
+    let mut node = config;
+    for segment in parents {
+        let map = node
+            .as_object_mut()
+            .ok_or_else(|| format!("Cannot set '{}': parent is not an object", path))?;
+        node = map
+            .entry(segment.to_string())
+            .or_insert_with(|| serde_json::json!({}));
+    }
+    node.as_object_mut()
+        .ok_or_else(|| format!("Cannot set '{}': parent is not an object", path))?
+        .insert(last.to_string(), value);
+    Ok(())
+}
 
-    merge_json(&mut current_val, &patch_val);
-    let merged = serde_json::to_string(&current_val).map_err(|e| e.to_string())?;
-    config_set_db(pool, &merged)?;
-    Ok(merged)
+/// How `config_import` combines the file with the stored config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportMode {
+    /// Deep-merge the file over the current config, like `config_update`.
+    Merge,
+    /// Discard the current config and use the file as-is.
+    Replace,
 }
 
-fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
+/// Remove API keys so an exported config can be shared safely.
+pub fn redact_secrets(config: &mut serde_json::Value) {
+    if let Some(map) = config.as_object_mut() {
+        for field in SECRET_FIELDS {
+            map.remove(*field);
+        }
+    }
+}
+
+/// Write the main config to `path` as pretty JSON, without secrets unless asked.
+pub fn config_export_db(pool: &DbPool, path: &Path, include_secrets: bool) -> Result<(), String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    if !include_secrets {
+        redact_secrets(&mut value);
+    }
+    let json = serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Failed to write config file: {}", e))
+}
+
+/// Load a config file exported by `config_export`, validating it before applying.
+pub fn config_import_db(pool: &DbPool, path: &Path, mode: ImportMode) -> Result<String, ConfigError> {
+    let json = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file: {}", e))?;
+    let value = parse_config_json(&json, "Config file")?;
+    apply_config(pool, &value, mode)
+}
+
+/// Number of previous config values kept in `config_history`.
+const HISTORY_LIMIT: i64 = 100;
+
+/// A previous value of the main config, recorded before it was overwritten.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigHistoryEntry {
+    pub version: i64,
+    /// The config JSON as it was before the change.
+    pub value: String,
+    pub created_at: String,
+}
+
+/// Record `value` as a history entry and trim the oldest beyond `HISTORY_LIMIT`.
+fn snapshot_config(pool: &DbPool, value: &str) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config_history (key, value) VALUES ('main', ?1)",
+        [value],
+    )
+    .map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM config_history WHERE key = 'main' AND version NOT IN
+         (SELECT version FROM config_history WHERE key = 'main' ORDER BY version DESC LIMIT ?1)",
+        [HISTORY_LIMIT],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Remove top-level `fields` from every config history entry.
+pub(crate) fn scrub_history_fields(pool: &DbPool, fields: &[&str]) -> Result<(), String> {
+    for entry in config_history_list_db(pool)? {
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(&entry.value) else {
+            continue;
+        };
+        let Some(map) = value.as_object_mut() else {
+            continue;
+        };
+        let before = map.len();
+        for field in fields {
+            map.remove(*field);
+        }
+        if map.len() == before {
+            continue;
+        }
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        conn.execute(
+            "UPDATE config_history SET value = ?1 WHERE version = ?2",
+            rusqlite::params![serde_json::to_string(&value).map_err(|e| e.to_string())?, entry.version],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Previous config values, newest first.
+pub fn config_history_list_db(pool: &DbPool) -> Result<Vec<ConfigHistoryEntry>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT version, value, created_at FROM config_history
+             WHERE key = 'main' ORDER BY version DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ConfigHistoryEntry {
+                version: row.get(0)?,
+                value: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+/// Restore the config recorded as `version`. The value being replaced is
+/// snapshotted too, so a rollback can itself be undone.
+pub fn config_rollback_db(pool: &DbPool, version: i64) -> Result<String, String> {
+    let value: String = {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT value FROM config_history WHERE key = 'main' AND version = ?1",
+            [version],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Config version {} not found", version))?
+    };
+    let current = config_get_db(pool)?;
+    snapshot_config(pool, &current)?;
+    config_set_db(pool, &value)?;
+    Ok(value)
+}
+
+pub(crate) fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
     if let (serde_json::Value::Object(base_map), serde_json::Value::Object(patch_map)) =
         (base, patch)
     {
@@ -51,13 +390,512 @@ fn merge_json(base: &mut serde_json::Value, patch: &serde_json::Value) {
     }
 }
 
-// Tauri command wrappers — these use State<DbPool>
+/// Settings that differ between `old` and `new`: changed or added values, and
+/// `null` for removed keys. Nested objects are diffed recursively.
+pub fn config_diff(old: &serde_json::Value, new: &serde_json::Value) -> serde_json::Value {
+    let mut diff = serde_json::Map::new();
+    let (Some(old_map), Some(new_map)) = (old.as_object(), new.as_object()) else {
+        return new.clone();
+    };
+    for (key, new_value) in new_map {
+        match old_map.get(key) {
+            Some(old_value) if old_value == new_value => {}
+            Some(old_value) if old_value.is_object() && new_value.is_object() => {
+                diff.insert(key.clone(), config_diff(old_value, new_value));
+            }
+            _ => {
+                diff.insert(key.clone(), new_value.clone());
+            }
+        }
+    }
+    for key in old_map.keys() {
+        if !new_map.contains_key(key) {
+            diff.insert(key.clone(), serde_json::Value::Null);
+        }
+    }
+    serde_json::Value::Object(diff)
+}
+
+/// Merge a config file edited outside the app into the stored config. Returns
+/// what changed in the effective config.
+pub fn config_reload_file_db(pool: &DbPool, path: &Path) -> Result<serde_json::Value, ConfigError> {
+    let before = config_effective_db(pool)?;
+    config_import_db(pool, path, ImportMode::Merge)?;
+    Ok(config_diff(&before, &config_effective_db(pool)?))
+}
+
+/// Run a config mutation, then tell the UI and the running agent what changed.
+pub(crate) async fn mutate_config<F>(
+    app: &tauri::AppHandle,
+    workspace: &WorkspaceDb,
+    bridge: &SidecarBridge,
+    mutate: F,
+) -> Result<String, ConfigError>
+where
+    F: FnOnce(&DbPool) -> Result<String, ConfigError> + Send + 'static,
+{
+    let pool = workspace.pool();
+    let (result, diff) = db::run_blocking(&pool, move |pool| {
+        if is_offline(pool) && config_queued_db(pool)? {
+            return Err("Config changes are queued offline; go online to apply them first".to_string());
+        }
+        let before = config_effective_db(pool)?;
+        let result = mutate(pool);
+        let diff = match result {
+            Ok(_) => config_diff(&before, &config_effective_db(pool)?),
+            Err(_) => serde_json::json!({}),
+        };
+        Ok((result, diff))
+    })
+    .await?;
+    let value = result?;
+    publish_config_change(app, bridge, diff);
+    Ok(value)
+}
+
+/// Emit `config:changed` and push a `config:update` notification to the agent.
+/// API keys are never included.
+pub(crate) fn publish_config_change(app: &tauri::AppHandle, bridge: &SidecarBridge, mut diff: serde_json::Value) {
+    redact_secrets(&mut diff);
+    if diff.as_object().is_none_or(|m| m.is_empty()) {
+        return;
+    }
+    if diff.get("watcher").is_some() {
+        if let (Some(watcher), Ok(config)) = (
+            app.try_state::<FileWatcher>(),
+            config_effective_db(&app.state::<WorkspaceDb>().pool()),
+        ) {
+            watcher.set_config(watcher_config(&config));
+        }
+    }
+    if diff.get("webhook").is_some() {
+        if let (Some(webhook), Ok(config)) = (
+            app.try_state::<WebhookServer>(),
+            config_effective_db(&app.state::<WorkspaceDb>().pool()),
+        ) {
+            if let Err(e) = webhook.apply(app.clone(), webhook_config(&config)) {
+                warn!(error = %e, "Failed to apply webhook config");
+            }
+        }
+    }
+    if diff.get("localApi").is_some() {
+        if let (Some(local_api), Ok(config)) = (
+            app.try_state::<LocalApiServer>(),
+            config_effective_db(&app.state::<WorkspaceDb>().pool()),
+        ) {
+            if let Err(e) = local_api.apply(app.clone(), local_api_config(&config)) {
+                warn!(error = %e, "Failed to apply local API config");
+            }
+        }
+    }
+    if diff.get("network").is_some() {
+        if let Ok(config) = config_effective_db(&app.state::<WorkspaceDb>().pool()) {
+            http::configure(network_config(&config));
+        }
+    }
+    if diff.get("alpacaStream").is_some() {
+        if let (Some(stream), Ok(config)) = (
+            app.try_state::<AlpacaStream>(),
+            config_effective_db(&app.state::<WorkspaceDb>().pool()),
+        ) {
+            if let Err(e) = stream.apply(app.clone(), alpaca_stream_config(&config)) {
+                warn!(error = %e, "Failed to apply Alpaca stream config");
+            }
+        }
+    }
+    let payload = serde_json::json!({ "changes": diff });
+    let _ = emit_event(app, event_names::CONFIG_CHANGED, payload.clone());
+    if bridge.is_running() {
+        if let Err(e) = bridge.send_notification("config:update", Some(payload)) {
+            warn!(error = %e, "Failed to push config change to agent");
+        }
+    }
+}
+
+// Tauri command wrappers — these use State<WorkspaceDb>
+#[tauri::command]
+pub async fn config_get(workspace: tauri::State<'_, WorkspaceDb>) -> Result<String, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, |pool| {
+        serde_json::to_string(&config_effective_db(pool)?).map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// Restore defaults for one section, or everything except API keys.
 #[tauri::command]
-pub fn config_get(pool: tauri::State<'_, DbPool>) -> Result<String, String> {
-    config_get_db(&pool)
+pub async fn config_reset(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    section: Option<String>,
+) -> Result<String, ConfigError> {
+    mutate_config(&app, &workspace, &bridge, move |pool| {
+        Ok(config_reset_db(pool, section.as_deref())?)
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn config_update(pool: tauri::State<'_, DbPool>, patch: String) -> Result<String, String> {
-    config_update_db(&pool, &patch)
+pub async fn config_update(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    patch: String,
+) -> Result<String, ConfigError> {
+    let pool = workspace.pool();
+    if db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await? {
+        let patch = parse_config_json(&patch, "Patch")?;
+        return Ok(db::run_blocking(&pool, move |pool| queue_config_db(pool, QueuedOp::ConfigUpdate { patch })).await?);
+    }
+    mutate_config(&app, &workspace, &bridge, move |pool| config_update_db(pool, &patch)).await
+}
+
+/// Previous config values, newest first.
+#[tauri::command]
+pub async fn config_history_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Vec<ConfigHistoryEntry>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, config_history_list_db).await
+}
+
+/// Restore a previous config version and return it.
+#[tauri::command]
+pub async fn config_rollback(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    version: i64,
+) -> Result<String, ConfigError> {
+    mutate_config(&app, &workspace, &bridge, move |pool| {
+        Ok(config_rollback_db(pool, version)?)
+    })
+    .await
+}
+
+/// Read one setting by dot path, e.g. `monitor.analysisIntervalMs`.
+#[tauri::command]
+pub async fn config_get_key(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+) -> Result<Option<serde_json::Value>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| config_get_key_db(pool, &path)).await
+}
+
+/// Set one setting by dot path and return the full updated config.
+#[tauri::command]
+pub async fn config_set_key(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    path: String,
+    value: serde_json::Value,
+) -> Result<String, ConfigError> {
+    let pool = workspace.pool();
+    if db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await? {
+        validate_key(&path, &value)?;
+        let op = QueuedOp::ConfigSetKey { path, value };
+        return Ok(db::run_blocking(&pool, move |pool| queue_config_db(pool, op)).await?);
+    }
+    mutate_config(&app, &workspace, &bridge, move |pool| {
+        config_set_key_db(pool, &path, value)
+    })
+    .await
+}
+
+/// Export the main config to a JSON file. API keys are omitted unless `includeSecrets` is set.
+#[tauri::command]
+pub async fn config_export(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+    include_secrets: Option<bool>,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        config_export_db(pool, Path::new(&path), include_secrets.unwrap_or(false))
+    })
+    .await
+}
+
+/// Import a config file, merging it into or replacing the current config.
+#[tauri::command]
+pub async fn config_import(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    path: String,
+    mode: ImportMode,
+) -> Result<String, ConfigError> {
+    mutate_config(&app, &workspace, &bridge, move |pool| {
+        config_import_db(pool, Path::new(&path), mode)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn update_snapshots_previous_value() {
+        let (_dir, pool) = test_pool();
+        config_update_db(&pool, r#"{"model":"a"}"#).unwrap();
+        config_update_db(&pool, r#"{"model":"b"}"#).unwrap();
+        let history = config_history_list_db(&pool).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].value, r#"{"model":"a"}"#);
+        assert_eq!(history[1].value, "{}");
+    }
+
+    #[test]
+    fn rollback_restores_version_and_is_undoable() {
+        let (_dir, pool) = test_pool();
+        config_update_db(&pool, r#"{"model":"good"}"#).unwrap();
+        config_update_db(&pool, r#"{"model":"bad"}"#).unwrap();
+        let good = config_history_list_db(&pool).unwrap()[0].version;
+
+        assert_eq!(config_rollback_db(&pool, good).unwrap(), r#"{"model":"good"}"#);
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"model":"good"}"#);
+        assert_eq!(config_history_list_db(&pool).unwrap()[0].value, r#"{"model":"bad"}"#);
+    }
+
+    #[test]
+    fn rollback_to_unknown_version_fails() {
+        let (_dir, pool) = test_pool();
+        assert!(config_rollback_db(&pool, 42).is_err());
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let (_dir, pool) = test_pool();
+        for i in 0..(HISTORY_LIMIT + 5) {
+            config_update_db(&pool, &format!(r#"{{"model":"m{}"}}"#, i)).unwrap();
+        }
+        assert_eq!(config_history_list_db(&pool).unwrap().len(), HISTORY_LIMIT as usize);
+    }
+
+    #[test]
+    fn update_rejects_invalid_patch_without_writing() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m"}"#).unwrap();
+        let err = config_update_db(&pool, r#"{"model":"x","bogus":1}"#).unwrap_err();
+        match &err {
+            ConfigError::Invalid(fields) => assert_eq!(fields[0].path, "bogus"),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"model":"m"}"#);
+    }
+
+    #[test]
+    fn update_rejects_malformed_json() {
+        let (_dir, pool) = test_pool();
+        assert!(matches!(
+            config_update_db(&pool, "{not json"),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn config_error_serializes_field_errors() {
+        let err = ConfigError::Invalid(vec![FieldError {
+            path: "monitor.analysisIntervalMs".to_string(),
+            message: "must be between 1000 and 86400000".to_string(),
+        }]);
+        let json = serde_json::to_value(&err).unwrap();
+        assert_eq!(json["kind"], "invalid");
+        assert_eq!(json["fields"][0]["path"], "monitor.analysisIntervalMs");
+        assert_eq!(
+            json["message"],
+            "Invalid config: monitor.analysisIntervalMs: must be between 1000 and 86400000"
+        );
+    }
+
+    #[test]
+    fn export_redacts_secrets_by_default() {
+        let (dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m","anthropicApiKey":"sk-1"}"#).unwrap();
+        let path = dir.path().join("config.json");
+
+        config_export_db(&pool, &path, false).unwrap();
+        let exported: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(exported, serde_json::json!({"model": "m"}));
+
+        config_export_db(&pool, &path, true).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("sk-1"));
+    }
+
+    #[test]
+    fn import_merge_keeps_local_secrets() {
+        let (dir, pool) = test_pool();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"model":"shared","feed":"sip"}"#).unwrap();
+        config_set_db(&pool, r#"{"model":"m","anthropicApiKey":"sk-1"}"#).unwrap();
+
+        let merged = config_import_db(&pool, &path, ImportMode::Merge).unwrap();
+        let merged: serde_json::Value = serde_json::from_str(&merged).unwrap();
+        assert_eq!(merged["model"], "shared");
+        assert_eq!(merged["anthropicApiKey"], "sk-1");
+        assert_eq!(config_history_list_db(&pool).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn import_replace_discards_current() {
+        let (dir, pool) = test_pool();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"model":"shared"}"#).unwrap();
+        config_set_db(&pool, r#"{"model":"m","feed":"iex"}"#).unwrap();
+
+        config_import_db(&pool, &path, ImportMode::Replace).unwrap();
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"model":"shared"}"#);
+    }
+
+    #[test]
+    fn import_validates_file() {
+        let (dir, pool) = test_pool();
+        let path = dir.path().join("config.json");
+        std::fs::write(&path, r#"{"monitor":{"analysisIntervalMs":1}}"#).unwrap();
+        assert!(matches!(
+            config_import_db(&pool, &path, ImportMode::Replace),
+            Err(ConfigError::Invalid(_))
+        ));
+        assert_eq!(config_get_db(&pool).unwrap(), "{}");
+    }
+
+    #[test]
+    fn import_mode_deserializes() {
+        let mode: ImportMode = serde_json::from_str(r#""replace""#).unwrap();
+        assert_eq!(mode, ImportMode::Replace);
+    }
+
+    #[test]
+    fn get_key_reads_nested_values() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"monitor":{"analysisIntervalMs":60000}}"#).unwrap();
+        assert_eq!(
+            config_get_key_db(&pool, "monitor.analysisIntervalMs").unwrap(),
+            Some(serde_json::json!(60000))
+        );
+        assert_eq!(config_get_key_db(&pool, "monitor.missing").unwrap(), None);
+        assert!(config_get_key_db(&pool, "monitor..x").is_err());
+    }
+
+    #[test]
+    fn set_key_creates_parents_and_records_history() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m"}"#).unwrap();
+        config_set_key_db(&pool, "monitor.analysisIntervalMs", serde_json::json!(30000)).unwrap();
+        assert_eq!(
+            config_get_key_db(&pool, "monitor.analysisIntervalMs").unwrap(),
+            Some(serde_json::json!(30000))
+        );
+        assert_eq!(config_get_key_db(&pool, "model").unwrap(), Some(serde_json::json!("m")));
+        assert_eq!(config_history_list_db(&pool).unwrap()[0].value, r#"{"model":"m"}"#);
+    }
+
+    #[test]
+    fn set_key_replaces_objects_instead_of_merging() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"ticks":{"enabled":true,"symbols":["NET"]}}"#).unwrap();
+        config_set_key_db(&pool, "ticks", serde_json::json!({"enabled": false})).unwrap();
+        assert_eq!(
+            config_get_key_db(&pool, "ticks").unwrap(),
+            Some(serde_json::json!({"enabled": false}))
+        );
+    }
+
+    #[test]
+    fn set_key_validates_value() {
+        let (_dir, pool) = test_pool();
+        let err = config_set_key_db(&pool, "monitor.analysisIntervalMs", serde_json::json!("x"))
+            .unwrap_err();
+        match err {
+            ConfigError::Invalid(fields) => assert_eq!(fields[0].path, "monitor.analysisIntervalMs"),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert!(matches!(
+            config_set_key_db(&pool, "bogus.key", serde_json::json!(1)),
+            Err(ConfigError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn effective_config_fills_in_defaults() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m","monitor":{"analysisIntervalMs":5000}}"#).unwrap();
+        let effective = config_effective_db(&pool).unwrap();
+        assert_eq!(effective["model"], "m");
+        assert_eq!(effective["monitor"]["analysisIntervalMs"], 5000);
+        assert_eq!(effective["monitor"]["maxCycleAgeMs"], 14_400_000);
+        assert_eq!(effective["feed"], "iex");
+    }
+
+    #[test]
+    fn reset_section_restores_its_defaults() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m","monitor":{"analysisIntervalMs":5000}}"#).unwrap();
+        let reset: serde_json::Value =
+            serde_json::from_str(&config_reset_db(&pool, Some("monitor")).unwrap()).unwrap();
+        assert_eq!(reset["monitor"]["analysisIntervalMs"], 60_000);
+        assert_eq!(reset["model"], "m");
+        assert_eq!(config_history_list_db(&pool).unwrap().len(), 1);
+        assert!(config_reset_db(&pool, Some("bogus")).is_err());
+    }
+
+    #[test]
+    fn full_reset_keeps_api_keys() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m","feed":"sip","anthropicApiKey":"sk-1"}"#).unwrap();
+        config_reset_db(&pool, None).unwrap();
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"anthropicApiKey":"sk-1"}"#);
+        let effective = config_effective_db(&pool).unwrap();
+        assert_eq!(effective["feed"], "iex");
+    }
+
+    #[test]
+    fn diff_reports_changed_added_and_removed_keys() {
+        let old = serde_json::json!({
+            "model": "a",
+            "feed": "iex",
+            "monitor": {"analysisIntervalMs": 60000, "maxCycleAgeMs": 1},
+        });
+        let new = serde_json::json!({
+            "model": "b",
+            "monitor": {"analysisIntervalMs": 30000, "maxCycleAgeMs": 1},
+            "symbols": ["NET"],
+        });
+        assert_eq!(
+            config_diff(&old, &new),
+            serde_json::json!({
+                "model": "b",
+                "monitor": {"analysisIntervalMs": 30000},
+                "symbols": ["NET"],
+                "feed": null,
+            })
+        );
+        assert_eq!(config_diff(&new, &new), serde_json::json!({}));
+    }
+
+    #[test]
+    fn reload_file_returns_effective_diff() {
+        let (dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"a"}"#).unwrap();
+        let file = dir.path().join("config.json");
+        std::fs::write(&file, r#"{"model":"b","feed":"iex"}"#).unwrap();
+        // feed already defaults to iex, so only the model changes
+        assert_eq!(
+            config_reload_file_db(&pool, &file).unwrap(),
+            serde_json::json!({"model": "b"})
+        );
+    }
 }
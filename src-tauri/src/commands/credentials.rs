@@ -1,5 +1,11 @@
-use crate::db::DbPool;
+use crate::bridge::SidecarBridge;
+use crate::broker::{BrokerProvider, BrokerProviderInfo, ProviderCredentials};
+use crate::commands::agent::{ENV_ALPACA_KEY_ID, ENV_ALPACA_SECRET_KEY};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::workspace::WorkspaceDb;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct AlpacaCredentials {
@@ -11,17 +17,202 @@ pub struct AlpacaCredentials {
 pub struct AlpacaCredentialsMasked {
     pub key_id: String,
     pub has_secret: bool,
+    pub metadata: Option<CredentialMetadata>,
 }
 
-/// Store credentials for a given mode ("paper" or "live").
-pub fn credentials_set_db(
+/// Non-secret facts about a stored credential set.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialMetadata {
+    /// See `credential_fingerprint`.
+    pub fingerprint: String,
+    pub created_at: String,
+    pub last_validated_at: Option<String>,
+    pub last_used_at: Option<String>,
+}
+
+/// First and last four characters of `value`, or a placeholder when that
+/// would reveal most of it.
+pub fn key_fingerprint(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 12 {
+        return "••••".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+/// Fingerprint of the provider's identifying field: its ends when the field
+/// is not secret, like Alpaca's key id, otherwise the start of its SHA-256.
+pub fn credential_fingerprint(provider: BrokerProvider, creds: &ProviderCredentials) -> String {
+    let field = provider.fields()[0];
+    let value = creds.get(field).map(String::as_str).unwrap_or_default();
+    if !provider.secret_fields().contains(&field) {
+        return key_fingerprint(value);
+    }
+    if value.is_empty() {
+        return "••••".to_string();
+    }
+    let digest = Sha256::digest(value.as_bytes());
+    let hex: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256:{}", hex)
+}
+
+/// Record that credentials were (re)stored, resetting validation and usage.
+pub fn credential_metadata_stored_db(
     pool: &DbPool,
+    provider: BrokerProvider,
     mode: &str,
-    creds: &AlpacaCredentials,
+    profile: &str,
+    creds: &ProviderCredentials,
+) -> Result<(), String> {
+    let fingerprint = credential_fingerprint(provider, creds);
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO credential_metadata (provider, mode, profile, fingerprint)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(provider, mode, profile) DO UPDATE SET
+             fingerprint = ?4, created_at = datetime('now'),
+             last_validated_at = NULL, last_used_at = NULL",
+        rusqlite::params![provider.as_str(), mode, profile, fingerprint],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Which timestamp `credential_metadata_touch_db` updates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CredentialEvent {
+    Validated,
+    Used,
+}
+
+/// Set `last_validated_at` or `last_used_at` to now. No-op for credentials
+/// stored before metadata was tracked.
+pub fn credential_metadata_touch_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    event: CredentialEvent,
 ) -> Result<(), String> {
-    validate_mode(mode)?;
+    let column = match event {
+        CredentialEvent::Validated => "last_validated_at",
+        CredentialEvent::Used => "last_used_at",
+    };
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        &format!(
+            "UPDATE credential_metadata SET {} = datetime('now')
+             WHERE provider = ?1 AND mode = ?2 AND profile = ?3",
+            column
+        ),
+        rusqlite::params![provider.as_str(), mode, profile],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Metadata for a credential set, if any was recorded.
+pub fn credential_metadata_get_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<Option<CredentialMetadata>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    match conn.query_row(
+        "SELECT fingerprint, created_at, last_validated_at, last_used_at
+         FROM credential_metadata WHERE provider = ?1 AND mode = ?2 AND profile = ?3",
+        rusqlite::params![provider.as_str(), mode, profile],
+        |row| {
+            Ok(CredentialMetadata {
+                fingerprint: row.get(0)?,
+                created_at: row.get(1)?,
+                last_validated_at: row.get(2)?,
+                last_used_at: row.get(3)?,
+            })
+        },
+    ) {
+        Ok(metadata) => Ok(Some(metadata)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Forget the metadata of a deleted credential set.
+pub fn credential_metadata_delete_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "DELETE FROM credential_metadata WHERE provider = ?1 AND mode = ?2 AND profile = ?3",
+        rusqlite::params![provider.as_str(), mode, profile],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+impl AlpacaCredentials {
+    pub(crate) fn into_fields(self) -> ProviderCredentials {
+        ProviderCredentials::from([
+            ("key_id".to_string(), self.key_id),
+            ("secret_key".to_string(), self.secret_key),
+        ])
+    }
+
+    pub(crate) fn from_fields(mut fields: ProviderCredentials) -> Self {
+        Self {
+            key_id: fields.remove("key_id").unwrap_or_default(),
+            secret_key: fields.remove("secret_key").unwrap_or_default(),
+        }
+    }
+}
+
+/// A stored credential set with its secret fields masked.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokerCredentialsMasked {
+    pub provider: BrokerProvider,
+    pub mode: String,
+    pub profile: String,
+    pub fields: ProviderCredentials,
+    pub metadata: Option<CredentialMetadata>,
+}
+
+impl BrokerCredentialsMasked {
+    fn new(provider: BrokerProvider, mode: &str, profile: &str, mut fields: ProviderCredentials) -> Self {
+        for field in provider.secret_fields() {
+            if let Some(value) = fields.get_mut(*field) {
+                *value = mask_secret(value);
+            }
+        }
+        Self {
+            provider,
+            mode: mode.to_string(),
+            profile: profile.to_string(),
+            fields,
+            metadata: None,
+        }
+    }
+}
+
+/// Store a provider's credentials for a mode and profile.
+pub fn provider_credentials_set_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    creds: &ProviderCredentials,
+) -> Result<(), String> {
+    provider.validate_mode(mode)?;
+    provider.validate_credentials(creds)?;
     let json = serde_json::to_string(creds).map_err(|e| e.to_string())?;
-    let key = credential_key(mode);
+    let key = credential_key(provider, mode, profile)?;
     let conn = pool.get().map_err(|e| e.to_string())?;
     conn.execute(
         "INSERT INTO config (key, value) VALUES (?1, ?2)
@@ -32,13 +223,15 @@ pub fn credentials_set_db(
     Ok(())
 }
 
-/// Retrieve credentials for a given mode. Returns None if not set.
-pub fn credentials_get_db(
+/// Retrieve a provider's credentials for a mode and profile. Returns None if not set.
+pub fn provider_credentials_get_db(
     pool: &DbPool,
+    provider: BrokerProvider,
     mode: &str,
-) -> Result<Option<AlpacaCredentials>, String> {
-    validate_mode(mode)?;
-    let key = credential_key(mode);
+    profile: &str,
+) -> Result<Option<ProviderCredentials>, String> {
+    provider.validate_mode(mode)?;
+    let key = credential_key(provider, mode, profile)?;
     let conn = pool.get().map_err(|e| e.to_string())?;
     let result: Option<String> = match conn.query_row(
         "SELECT value FROM config WHERE key = ?1",
@@ -51,7 +244,7 @@ pub fn credentials_get_db(
     };
     match result {
         Some(json) => {
-            let creds: AlpacaCredentials =
+            let creds: ProviderCredentials =
                 serde_json::from_str(&json).map_err(|e| e.to_string())?;
             Ok(Some(creds))
         }
@@ -59,10 +252,15 @@ pub fn credentials_get_db(
     }
 }
 
-/// Check whether credentials exist for a given mode.
-pub fn credentials_exists_db(pool: &DbPool, mode: &str) -> Result<bool, String> {
-    validate_mode(mode)?;
-    let key = credential_key(mode);
+/// Check whether a provider has credentials for a mode and profile.
+pub fn provider_credentials_exists_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<bool, String> {
+    provider.validate_mode(mode)?;
+    let key = credential_key(provider, mode, profile)?;
     let conn = pool.get().map_err(|e| e.to_string())?;
     let count: i64 = conn
         .query_row(
@@ -74,76 +272,906 @@ pub fn credentials_exists_db(pool: &DbPool, mode: &str) -> Result<bool, String>
     Ok(count > 0)
 }
 
-fn credential_key(mode: &str) -> String {
-    format!("alpaca_credentials_{}", mode)
+/// Remove a provider's credentials for a mode and profile. Returns whether any were stored.
+pub fn provider_credentials_delete_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<bool, String> {
+    provider.validate_mode(mode)?;
+    let key = credential_key(provider, mode, profile)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    // Overwrite the freed page so the secret does not linger in the file
+    conn.execute_batch("PRAGMA secure_delete = ON;")
+        .map_err(|e| e.to_string())?;
+    let removed = conn
+        .execute("DELETE FROM config WHERE key = ?1", [key])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
 }
 
-fn validate_mode(mode: &str) -> Result<(), String> {
-    match mode {
-        "paper" | "live" => Ok(()),
-        _ => Err(format!("Invalid trading mode: '{}'. Must be 'paper' or 'live'", mode)),
+/// Every credential set stored in the DB, as (provider, mode, profile).
+pub fn provider_credentials_list_db(pool: &DbPool) -> Result<Vec<(BrokerProvider, String, String)>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT key FROM config WHERE key LIKE '%\\_credentials\\_%' ESCAPE '\\' ORDER BY key")
+        .map_err(|e| e.to_string())?;
+    let keys = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| e.to_string())?;
+    let mut results = Vec::new();
+    for key in keys {
+        if let Some(entry) = parse_credential_key(&key.map_err(|e| e.to_string())?) {
+            results.push(entry);
+        }
     }
+    Ok(results)
 }
 
-/// Get credentials, trying keychain first, then falling back to DB.
-pub fn credentials_get_any(pool: &DbPool, mode: &str) -> Result<Option<AlpacaCredentials>, String> {
+/// Inverse of `credential_key`.
+fn parse_credential_key(key: &str) -> Option<(BrokerProvider, String, String)> {
+    let (provider, rest) = key.split_once("_credentials_")?;
+    let provider = BrokerProvider::parse(provider).ok()?;
+    let (mode, profile) = rest.split_once(':').unwrap_or((rest, DEFAULT_CREDENTIAL_PROFILE));
+    provider.validate_mode(mode).ok()?;
+    validate_credential_profile(profile).ok()?;
+    Some((provider, mode.to_string(), profile.to_string()))
+}
+
+/// Store Alpaca credentials for a given mode ("paper" or "live") and profile.
+pub fn credentials_set_db(
+    pool: &DbPool,
+    mode: &str,
+    profile: &str,
+    creds: &AlpacaCredentials,
+) -> Result<(), String> {
+    provider_credentials_set_db(pool, BrokerProvider::Alpaca, mode, profile, &creds.clone().into_fields())
+}
+
+/// Retrieve Alpaca credentials for a given mode and profile. Returns None if not set.
+pub fn credentials_get_db(
+    pool: &DbPool,
+    mode: &str,
+    profile: &str,
+) -> Result<Option<AlpacaCredentials>, String> {
+    Ok(provider_credentials_get_db(pool, BrokerProvider::Alpaca, mode, profile)?
+        .map(AlpacaCredentials::from_fields))
+}
+
+/// Check whether Alpaca credentials exist for a given mode and profile.
+pub fn credentials_exists_db(pool: &DbPool, mode: &str, profile: &str) -> Result<bool, String> {
+    provider_credentials_exists_db(pool, BrokerProvider::Alpaca, mode, profile)
+}
+
+/// Remove stored Alpaca credentials for a given mode and profile. Returns whether any were stored.
+pub fn credentials_delete_db(pool: &DbPool, mode: &str, profile: &str) -> Result<bool, String> {
+    provider_credentials_delete_db(pool, BrokerProvider::Alpaca, mode, profile)
+}
+
+/// Credential profile used when none has been selected. Its storage keys are
+/// the ones used before profiles existed, so older installs keep working.
+pub const DEFAULT_CREDENTIAL_PROFILE: &str = "default";
+
+/// Check a credential profile name: 1-64 letters, digits, '-' or '_'.
+pub fn validate_credential_profile(profile: &str) -> Result<(), String> {
+    if profile.is_empty() || profile.len() > 64 {
+        return Err("Credential profile name must be 1-64 characters".to_string());
+    }
+    if !profile
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid credential profile '{}': use letters, digits, '-' or '_'",
+            profile
+        ));
+    }
+    Ok(())
+}
+
+/// Storage suffix for `profile`: empty for the default profile, `:<profile>` otherwise.
+pub(crate) fn profile_suffix(profile: &str) -> Result<String, String> {
+    validate_credential_profile(profile)?;
+    if profile == DEFAULT_CREDENTIAL_PROFILE {
+        Ok(String::new())
+    } else {
+        Ok(format!(":{}", profile))
+    }
+}
+
+fn credential_key(provider: BrokerProvider, mode: &str, profile: &str) -> Result<String, String> {
+    Ok(format!(
+        "{}_credentials_{}{}",
+        provider.as_str(),
+        mode,
+        profile_suffix(profile)?
+    ))
+}
+
+fn active_profile_key(provider: BrokerProvider, mode: &str) -> String {
+    format!("{}_active_profile_{}", provider.as_str(), mode)
+}
+
+/// The credential profile selected for a provider and mode, or the default profile.
+pub fn provider_active_profile_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+) -> Result<String, String> {
+    provider.validate_mode(mode)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    match conn.query_row(
+        "SELECT value FROM config WHERE key = ?1",
+        [active_profile_key(provider, mode)],
+        |row| row.get(0),
+    ) {
+        Ok(profile) => Ok(profile),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(DEFAULT_CREDENTIAL_PROFILE.to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Make `profile` the one used for a provider and mode. Does not check that it has credentials.
+pub fn provider_select_profile_db(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<(), String> {
+    provider.validate_mode(mode)?;
+    validate_credential_profile(profile)?;
+    let key = active_profile_key(provider, mode);
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    if profile == DEFAULT_CREDENTIAL_PROFILE {
+        conn.execute("DELETE FROM config WHERE key = ?1", [&key])
+    } else {
+        conn.execute(
+            "INSERT INTO config (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+            [&key, profile],
+        )
+    }
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The Alpaca credential profile selected for `mode`, or the default profile.
+pub fn credentials_active_profile_db(pool: &DbPool, mode: &str) -> Result<String, String> {
+    provider_active_profile_db(pool, BrokerProvider::Alpaca, mode)
+}
+
+/// Make `profile` the Alpaca profile used for `mode`. Does not check that it has credentials.
+pub fn credentials_select_profile_db(pool: &DbPool, mode: &str, profile: &str) -> Result<(), String> {
+    provider_select_profile_db(pool, BrokerProvider::Alpaca, mode, profile)
+}
+
+/// `profile` if given, otherwise the active profile for the provider and mode.
+fn resolve_profile(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: Option<String>,
+) -> Result<String, String> {
+    match profile {
+        Some(profile) => {
+            validate_credential_profile(&profile)?;
+            Ok(profile)
+        }
+        None => provider_active_profile_db(pool, provider, mode),
+    }
+}
+
+fn validate_mode(mode: &str) -> Result<(), String> {
+    BrokerProvider::Alpaca.validate_mode(mode)
+}
+
+/// Get a provider's credentials, trying keychain first, then falling back to DB.
+pub fn provider_credentials_get_any(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<Option<ProviderCredentials>, String> {
     // Try keychain first
-    match crate::keychain::keychain_get(mode) {
+    match crate::keychain::provider_keychain_get(provider, mode, profile) {
         Ok(Some(creds)) => return Ok(Some(creds)),
         Ok(None) => {}
         Err(e) => {
-            tracing::warn!(error = %e, mode, "Keychain read failed, falling back to DB");
+            tracing::warn!(error = %e, provider = provider.as_str(), mode, "Keychain read failed, falling back to DB");
         }
     }
     // Fall back to DB
-    credentials_get_db(pool, mode)
+    provider_credentials_get_db(pool, provider, mode, profile)
+}
+
+/// Get Alpaca credentials, trying keychain first, then falling back to DB.
+pub fn credentials_get_any(
+    pool: &DbPool,
+    mode: &str,
+    profile: &str,
+) -> Result<Option<AlpacaCredentials>, String> {
+    Ok(provider_credentials_get_any(pool, BrokerProvider::Alpaca, mode, profile)?
+        .map(AlpacaCredentials::from_fields))
+}
+
+/// Alpaca credentials of the active profile for `mode`, for use against the
+/// broker. Records the use in the credential metadata.
+pub fn credentials_get_active(pool: &DbPool, mode: &str) -> Result<Option<AlpacaCredentials>, String> {
+    let profile = credentials_active_profile_db(pool, mode)?;
+    let creds = credentials_get_any(pool, mode, &profile)?;
+    if creds.is_some() {
+        if let Err(e) =
+            credential_metadata_touch_db(pool, BrokerProvider::Alpaca, mode, &profile, CredentialEvent::Used)
+        {
+            tracing::warn!(error = %e, "Failed to record credential use");
+        }
+    }
+    Ok(creds)
+}
+
+fn provider_credentials_exists_any(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<bool, String> {
+    match crate::keychain::provider_keychain_exists(provider, mode, profile) {
+        Ok(true) => return Ok(true),
+        Ok(false) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "Keychain check failed, falling back to DB");
+        }
+    }
+    provider_credentials_exists_db(pool, provider, mode, profile)
+}
+
+/// Store a provider's credentials in the keychain, falling back to the DB.
+fn provider_credentials_store(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    creds: &ProviderCredentials,
+) -> Result<(), String> {
+    provider.validate_credentials(creds)?;
+    match crate::keychain::provider_keychain_set(provider, mode, profile, creds) {
+        Ok(()) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, "Keychain write failed, falling back to DB");
+            provider_credentials_set_db(pool, provider, mode, profile, creds)?;
+        }
+    }
+    credential_metadata_stored_db(pool, provider, mode, profile, creds)
+}
+
+/// Delete a provider's credentials (the active profile when `profile` is
+/// omitted) from the keychain and the DB. Deleting the active profile selects
+/// the default one again. Returns the profile and whether it was active.
+fn provider_credentials_remove(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: Option<String>,
+) -> Result<(String, bool), String> {
+    provider.validate_mode(mode)?;
+    let active = provider_active_profile_db(pool, provider, mode)?;
+    let profile = profile.unwrap_or_else(|| active.clone());
+    crate::keychain::provider_keychain_delete(provider, mode, &profile)?;
+    provider_credentials_delete_db(pool, provider, mode, &profile)?;
+    credential_metadata_delete_db(pool, provider, mode, &profile)?;
+    let was_active = profile == active;
+    if was_active {
+        provider_select_profile_db(pool, provider, mode, DEFAULT_CREDENTIAL_PROFILE)?;
+    }
+    Ok((profile, was_active))
+}
+
+/// After deleting credentials: if the agent runs with them, tell the sidecar
+/// to stop using them and drop them from the environment it would be
+/// respawned with. Always emits `CREDENTIALS_REVOKED`.
+fn publish_credentials_revoked(
+    app: &tauri::AppHandle,
+    bridge: &SidecarBridge,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    was_active: bool,
+) {
+    tracing::info!(provider = provider.as_str(), mode, profile, "Credentials deleted");
+    let agent_mode = bridge.trading_mode();
+    if provider == BrokerProvider::Alpaca && agent_mode.as_deref() == Some(mode) && was_active {
+        bridge.forget_env(&[ENV_ALPACA_KEY_ID, ENV_ALPACA_SECRET_KEY]);
+        if bridge.is_running() {
+            let params = serde_json::json!({ "mode": mode });
+            if let Err(e) = bridge.send_notification("credentials:revoked", Some(params)) {
+                tracing::warn!(error = %e, "Failed to notify agent of revoked credentials");
+            }
+        }
+    }
+    let _ = emit_event(
+        app,
+        event_names::CREDENTIALS_REVOKED,
+        serde_json::json!({ "provider": provider, "mode": mode, "profile": profile }),
+    );
+}
+
+/// LLM providers whose API keys are kept in the keychain, with the legacy
+/// config field each key used to live in.
+pub const LLM_PROVIDERS: &[(&str, &str)] = &[
+    ("anthropic", "anthropicApiKey"),
+    ("openrouter", "openrouterApiKey"),
+];
+
+/// The legacy config field for `provider`; errors on unknown providers.
+pub fn llm_config_field(provider: &str) -> Result<&'static str, String> {
+    LLM_PROVIDERS
+        .iter()
+        .find(|(p, _)| *p == provider)
+        .map(|(_, field)| *field)
+        .ok_or_else(|| {
+            format!(
+                "Invalid LLM provider: '{}'. Must be 'anthropic' or 'openrouter'",
+                provider
+            )
+        })
+}
+
+/// What the UI may see of a stored LLM key.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmKeyMasked {
+    pub provider: String,
+    /// The key with all but its last four characters hidden.
+    pub masked: String,
+}
+
+/// Hide all but the last four characters of a secret.
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return "••••".to_string();
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("••••{}", tail)
+}
+
+/// Get an LLM API key, trying the keychain first, then the legacy config field.
+pub fn llm_key_get_any(app_config: &serde_json::Value, provider: &str) -> Result<Option<String>, String> {
+    let field = llm_config_field(provider)?;
+    match crate::keychain::llm_key_get(provider) {
+        Ok(Some(key)) => return Ok(Some(key)),
+        Ok(None) => {}
+        Err(e) => {
+            tracing::warn!(error = %e, provider, "Keychain read failed, falling back to config");
+        }
+    }
+    Ok(app_config
+        .get(field)
+        .and_then(|k| k.as_str())
+        .filter(|k| !k.is_empty())
+        .map(String::from))
+}
+
+/// Store an LLM API key in the keychain, falling back to the config field.
+fn llm_key_store(pool: &DbPool, provider: &str, key: &str) -> Result<(), String> {
+    let field = llm_config_field(provider)?;
+    match crate::keychain::llm_key_set(provider, key) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            tracing::warn!(error = %e, "Keychain write failed, falling back to DB");
+            crate::commands::config::config_update_db(
+                pool,
+                &serde_json::json!({ field: key }).to_string(),
+            )
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Environment variables read by `credentials_import_env`.
+const ENV_IMPORT_ALPACA: (&str, &str) = ("ALPACA_KEY_ID", "ALPACA_SECRET_KEY");
+const ENV_IMPORT_LLM: &[(&str, &str)] = &[
+    ("anthropic", "ANTHROPIC_API_KEY"),
+    ("openrouter", "OPENROUTER_API_KEY"),
+];
+
+/// What `credentials_import_env` stored. Values are never echoed back.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnvImportReport {
+    pub mode: String,
+    pub profile: String,
+    /// Whether Alpaca credentials were imported.
+    pub alpaca: bool,
+    /// LLM providers whose keys were imported.
+    pub llm_providers: Vec<String>,
+    /// Variables that were unset or empty.
+    pub missing: Vec<String>,
+}
+
+/// Copy credentials from environment variables using the given store functions.
+fn import_env(
+    env: impl Fn(&str) -> Option<String>,
+    mut store_alpaca: impl FnMut(&ProviderCredentials) -> Result<(), String>,
+    mut store_llm: impl FnMut(&str, &str) -> Result<(), String>,
+) -> Result<EnvImportReport, String> {
+    let var = |name: &str| env(name).filter(|v| !v.trim().is_empty());
+    let mut report = EnvImportReport::default();
+
+    let (key_var, secret_var) = ENV_IMPORT_ALPACA;
+    match (var(key_var), var(secret_var)) {
+        (Some(key_id), Some(secret_key)) => {
+            store_alpaca(&AlpacaCredentials { key_id, secret_key }.into_fields())?;
+            report.alpaca = true;
+        }
+        (key_id, secret_key) => {
+            if key_id.is_none() {
+                report.missing.push(key_var.to_string());
+            }
+            if secret_key.is_none() {
+                report.missing.push(secret_var.to_string());
+            }
+        }
+    }
+    for (provider, name) in ENV_IMPORT_LLM {
+        match var(name) {
+            Some(key) => {
+                store_llm(provider, &key)?;
+                report.llm_providers.push(provider.to_string());
+            }
+            None => report.missing.push(name.to_string()),
+        }
+    }
+    Ok(report)
+}
+
+/// How long `credentials_validate` waits for Alpaca before giving up.
+const VALIDATE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Trading API base URL for a mode.
+pub(crate) fn alpaca_trading_url(mode: &str) -> &'static str {
+    match mode {
+        "live" => "https://api.alpaca.markets",
+        _ => "https://paper-api.alpaca.markets",
+    }
+}
+
+/// Result of checking stored credentials against `GET /v2/account`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialValidation {
+    pub valid: bool,
+    /// The mode the credentials are stored under.
+    pub mode: String,
+    /// `"paper"` or `"live"`: which Alpaca environment accepted the key, if any.
+    pub key_environment: Option<String>,
+    pub account_status: Option<String>,
+    pub buying_power: Option<String>,
+    pub currency: Option<String>,
+    pub trading_blocked: Option<bool>,
+    pub error: Option<String>,
+}
+
+impl CredentialValidation {
+    fn invalid(mode: &str, key_environment: Option<&str>, error: String) -> Self {
+        Self {
+            valid: false,
+            mode: mode.to_string(),
+            key_environment: key_environment.map(String::from),
+            account_status: None,
+            buying_power: None,
+            currency: None,
+            trading_blocked: None,
+            error: Some(error),
+        }
+    }
+
+    /// Build a report from an account response fetched from `environment`.
+    pub fn from_account(mode: &str, environment: &str, account: &serde_json::Value) -> Self {
+        let text = |key: &str| account.get(key).and_then(|v| v.as_str()).map(String::from);
+        if environment != mode {
+            return Self::invalid(
+                mode,
+                Some(environment),
+                format!(
+                    "These are {} keys but are saved as {} credentials",
+                    environment, mode
+                ),
+            );
+        }
+        Self {
+            valid: true,
+            mode: mode.to_string(),
+            key_environment: Some(environment.to_string()),
+            account_status: text("status"),
+            buying_power: text("buying_power"),
+            currency: text("currency"),
+            trading_blocked: account.get("trading_blocked").and_then(|v| v.as_bool()),
+            error: None,
+        }
+    }
+}
+
+/// `GET /v2/account` against one environment. `Ok(None)` means the key was rejected.
+async fn fetch_account(
+    client: &reqwest::Client,
+    environment: &str,
+    creds: &AlpacaCredentials,
+) -> Result<Option<serde_json::Value>, String> {
+    let response = client
+        .get(format!("{}/v2/account", alpaca_trading_url(environment)))
+        .header("APCA-API-KEY-ID", &creds.key_id)
+        .header("APCA-API-SECRET-KEY", &creds.secret_key)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach Alpaca: {}", e))?;
+    match response.status() {
+        s if s.is_success() => response
+            .json()
+            .await
+            .map(Some)
+            .map_err(|e| format!("Failed to parse account: {}", e)),
+        reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => Ok(None),
+        s => Err(format!("Alpaca API error: {}", s)),
+    }
 }
 
 // --- Tauri command wrappers ---
 
+/// Store credentials for `mode` under `profile`, or the active profile when omitted.
 #[tauri::command]
-pub fn credentials_set(
-    pool: tauri::State<'_, DbPool>,
+pub async fn credentials_set(
+    workspace: tauri::State<'_, WorkspaceDb>,
     mode: String,
     key_id: String,
     secret_key: String,
+    profile: Option<String>,
 ) -> Result<(), String> {
+    let pool = workspace.pool();
     let creds = AlpacaCredentials { key_id, secret_key };
-    // Store in keychain primarily, DB as fallback
-    match crate::keychain::keychain_set(&mode, &creds) {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            tracing::warn!(error = %e, "Keychain write failed, falling back to DB");
-            credentials_set_db(&pool, &mode, &creds)
-        }
-    }
+    db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, BrokerProvider::Alpaca, &mode, profile)?;
+        provider_credentials_store(pool, BrokerProvider::Alpaca, &mode, &profile, &creds.into_fields())
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn credentials_get(
-    pool: tauri::State<'_, DbPool>,
+pub async fn credentials_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
     mode: String,
+    profile: Option<String>,
 ) -> Result<Option<AlpacaCredentialsMasked>, String> {
-    let creds = credentials_get_any(&pool, &mode)?;
-    Ok(creds.map(|c| AlpacaCredentialsMasked {
-        key_id: c.key_id,
-        has_secret: !c.secret_key.is_empty(),
-    }))
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, BrokerProvider::Alpaca, &mode, profile)?;
+        let Some(creds) = credentials_get_any(pool, &mode, &profile)? else {
+            return Ok(None);
+        };
+        Ok(Some(AlpacaCredentialsMasked {
+            key_id: creds.key_id,
+            has_secret: !creds.secret_key.is_empty(),
+            metadata: credential_metadata_get_db(pool, BrokerProvider::Alpaca, &mode, &profile)?,
+        }))
+    })
+    .await
 }
 
 #[tauri::command]
-pub fn credentials_exists(
-    pool: tauri::State<'_, DbPool>,
+pub async fn credentials_exists(
+    workspace: tauri::State<'_, WorkspaceDb>,
     mode: String,
+    profile: Option<String>,
 ) -> Result<bool, String> {
-    match crate::keychain::keychain_exists(&mode) {
-        Ok(true) => return Ok(true),
-        Ok(false) => {}
-        Err(e) => {
-            tracing::warn!(error = %e, "Keychain check failed, falling back to DB");
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, BrokerProvider::Alpaca, &mode, profile)?;
+        provider_credentials_exists_any(pool, BrokerProvider::Alpaca, &mode, &profile)
+    })
+    .await
+}
+
+/// The credential profile `agent_start` and `backtest_start` use for `mode`.
+#[tauri::command]
+pub async fn credentials_profile_active(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    mode: String,
+) -> Result<String, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| credentials_active_profile_db(pool, &mode)).await
+}
+
+/// Select the credential profile used for `mode`. The profile must have
+/// credentials stored; a running agent keeps its keys until restarted.
+#[tauri::command]
+pub async fn credentials_profile_select(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    mode: String,
+    profile: String,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        validate_mode(&mode)?;
+        validate_credential_profile(&profile)?;
+        if !provider_credentials_exists_any(pool, BrokerProvider::Alpaca, &mode, &profile)? {
+            return Err(format!("No {} credentials stored for profile '{}'", mode, profile));
+        }
+        credentials_select_profile_db(pool, &mode, &profile)?;
+        tracing::info!(mode, profile, "Credential profile selected");
+        Ok(())
+    })
+    .await
+}
+
+/// Check the stored credentials for `mode` with a live account lookup. A key
+/// rejected for `mode` is retried against the other environment so a
+/// paper/live mix-up is reported as such.
+#[tauri::command]
+pub async fn credentials_validate(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    mode: String,
+    profile: Option<String>,
+) -> Result<CredentialValidation, String> {
+    validate_mode(&mode)?;
+    let pool = workspace.pool();
+    let lookup_mode = mode.clone();
+    let (profile, creds) = db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, BrokerProvider::Alpaca, &lookup_mode, profile)?;
+        let creds = credentials_get_any(pool, &lookup_mode, &profile)?;
+        Ok((profile, creds))
+    })
+    .await?;
+    let creds =
+        creds.ok_or_else(|| format!("No {} credentials stored for profile '{}'", mode, profile))?;
+
+    let client = crate::http::client_builder()?
+        .timeout(VALIDATE_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let other = if mode == "paper" { "live" } else { "paper" };
+    for environment in [mode.as_str(), other] {
+        if let Some(account) = fetch_account(&client, environment, &creds).await? {
+            let validation = CredentialValidation::from_account(&mode, environment, &account);
+            if validation.valid {
+                let mode = mode.clone();
+                db::run_blocking(&pool, move |pool| {
+                    credential_metadata_touch_db(pool, BrokerProvider::Alpaca, &mode, &profile, CredentialEvent::Validated)
+                })
+                .await?;
+            }
+            return Ok(validation);
         }
     }
-    credentials_exists_db(&pool, &mode)
+    Ok(CredentialValidation::invalid(
+        &mode,
+        None,
+        "Alpaca rejected the key ID or secret".to_string(),
+    ))
+}
+
+/// Delete credentials for `mode` and `profile` (the active profile when
+/// omitted) from the keychain and the DB. Deleting the active profile selects
+/// the default one again. If the agent runs with the deleted credentials, the
+/// sidecar is told to stop using them.
+#[tauri::command]
+pub async fn credentials_delete(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    mode: String,
+    profile: Option<String>,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    let deleted_mode = mode.clone();
+    let (profile, was_active) = db::run_blocking(&pool, move |pool| {
+        provider_credentials_remove(pool, BrokerProvider::Alpaca, &mode, profile)
+    })
+    .await?;
+    publish_credentials_revoked(&app, &bridge, BrokerProvider::Alpaca, &deleted_mode, &profile, was_active);
+    Ok(())
+}
+
+/// Delete plaintext credential rows from SQLite that are safely stored in the
+/// keychain, for users who migrated before migration removed them.
+#[tauri::command]
+pub async fn credentials_scrub_db(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<crate::keychain::CredentialScrubReport, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, crate::keychain::scrub_db_credentials).await
+}
+
+/// Store `ALPACA_KEY_ID`/`ALPACA_SECRET_KEY` for `mode` (under `profile`, or
+/// the active profile) and the LLM key variables, like the Settings form does.
+/// Variables loaded from `.env` count; the `.env` file itself is not changed.
+#[tauri::command]
+pub async fn credentials_import_env(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    mode: String,
+    profile: Option<String>,
+) -> Result<EnvImportReport, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        validate_mode(&mode)?;
+        let profile = resolve_profile(pool, BrokerProvider::Alpaca, &mode, profile)?;
+        let mut report = import_env(
+            |name| std::env::var(name).ok(),
+            |creds| provider_credentials_store(pool, BrokerProvider::Alpaca, &mode, &profile, creds),
+            |provider, key| llm_key_store(pool, provider, key),
+        )?;
+        tracing::info!(mode, profile, alpaca = report.alpaca, llm = ?report.llm_providers, "Imported credentials from environment");
+        report.mode = mode;
+        report.profile = profile;
+        Ok(report)
+    })
+    .await
+}
+
+/// Supported brokers and data vendors with their credential fields and modes.
+#[tauri::command]
+pub fn broker_providers() -> Vec<BrokerProviderInfo> {
+    BrokerProvider::ALL.into_iter().map(BrokerProviderInfo::from).collect()
+}
+
+/// Store credentials for any broker or data vendor. `fields` must match the
+/// provider's schema; `profile` defaults to the active profile.
+#[tauri::command]
+pub async fn broker_credentials_set(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+    mode: String,
+    fields: ProviderCredentials,
+    profile: Option<String>,
+) -> Result<(), String> {
+    let provider = BrokerProvider::parse(&provider)?;
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        provider.validate_mode(&mode)?;
+        let profile = resolve_profile(pool, provider, &mode, profile)?;
+        provider_credentials_store(pool, provider, &mode, &profile, &fields)
+    })
+    .await
+}
+
+/// Stored credentials for a provider with secret fields masked. `None` when not set.
+#[tauri::command]
+pub async fn broker_credentials_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+    mode: String,
+    profile: Option<String>,
+) -> Result<Option<BrokerCredentialsMasked>, String> {
+    let provider = BrokerProvider::parse(&provider)?;
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, provider, &mode, profile)?;
+        let Some(fields) = provider_credentials_get_any(pool, provider, &mode, &profile)? else {
+            return Ok(None);
+        };
+        let mut masked = BrokerCredentialsMasked::new(provider, &mode, &profile, fields);
+        masked.metadata = credential_metadata_get_db(pool, provider, &mode, &profile)?;
+        Ok(Some(masked))
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn broker_credentials_exists(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+    mode: String,
+    profile: Option<String>,
+) -> Result<bool, String> {
+    let provider = BrokerProvider::parse(&provider)?;
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let profile = resolve_profile(pool, provider, &mode, profile)?;
+        provider_credentials_exists_any(pool, provider, &mode, &profile)
+    })
+    .await
+}
+
+/// Delete a provider's credentials, like `credentials_delete` for Alpaca.
+#[tauri::command]
+pub async fn broker_credentials_delete(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    provider: String,
+    mode: String,
+    profile: Option<String>,
+) -> Result<(), String> {
+    let provider = BrokerProvider::parse(&provider)?;
+    let pool = workspace.pool();
+    let deleted_mode = mode.clone();
+    let (profile, was_active) = db::run_blocking(&pool, move |pool| {
+        provider_credentials_remove(pool, provider, &mode, profile)
+    })
+    .await?;
+    publish_credentials_revoked(&app, &bridge, provider, &deleted_mode, &profile, was_active);
+    Ok(())
+}
+
+/// Store an LLM API key in the keychain. Falls back to the config when the
+/// keychain is unavailable.
+#[tauri::command]
+pub async fn llm_key_set(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+    key: String,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| llm_key_store(pool, &provider, &key)).await
+}
+
+/// The stored key for `provider`, masked. `None` when no key is set.
+#[tauri::command]
+pub async fn llm_key_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+) -> Result<Option<LlmKeyMasked>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let app_config = crate::commands::config::config_effective_db(pool)?;
+        Ok(llm_key_get_any(&app_config, &provider)?.map(|key| LlmKeyMasked {
+            masked: mask_secret(&key),
+            provider,
+        }))
+    })
+    .await
+}
+
+/// Whether a key is stored for `provider`, in the keychain or the legacy config field.
+#[tauri::command]
+pub async fn llm_key_exists(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+) -> Result<bool, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let field = llm_config_field(&provider)?;
+        match crate::keychain::llm_key_exists(&provider) {
+            Ok(true) => return Ok(true),
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(error = %e, "Keychain check failed, falling back to config");
+            }
+        }
+        let app_config = crate::commands::config::config_effective_db(pool)?;
+        Ok(app_config
+            .get(field)
+            .and_then(|k| k.as_str())
+            .is_some_and(|k| !k.is_empty()))
+    })
+    .await
+}
+
+/// Remove the key for `provider` from the keychain and the config.
+#[tauri::command]
+pub async fn llm_key_delete(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: String,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| {
+        let field = llm_config_field(&provider)?;
+        crate::keychain::llm_key_delete(&provider)?;
+        let mut config: serde_json::Value =
+            serde_json::from_str(&crate::commands::config::config_get_db(pool)?)
+                .map_err(|e| e.to_string())?;
+        if let Some(map) = config.as_object_mut() {
+            if map.remove(field).is_some() {
+                crate::commands::config::config_set_db(
+                    pool,
+                    &serde_json::to_string(&config).map_err(|e| e.to_string())?,
+                )?;
+            }
+        }
+        Ok(())
+    })
+    .await
 }
 
 #[cfg(test)]
@@ -155,14 +1183,15 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
         db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
         pool
     }
 
     #[test]
     fn credentials_exists_returns_false_when_not_set() {
         let pool = test_pool();
-        assert!(!credentials_exists_db(&pool, "paper").unwrap());
-        assert!(!credentials_exists_db(&pool, "live").unwrap());
+        assert!(!credentials_exists_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap());
+        assert!(!credentials_exists_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap());
     }
 
     #[test]
@@ -172,8 +1201,8 @@ mod tests {
             key_id: "PKTEST123".to_string(),
             secret_key: "secret456".to_string(),
         };
-        credentials_set_db(&pool, "paper", &creds).unwrap();
-        let result = credentials_get_db(&pool, "paper").unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        let result = credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
         assert_eq!(result, Some(creds));
     }
 
@@ -184,8 +1213,8 @@ mod tests {
             key_id: "KEY".to_string(),
             secret_key: "SECRET".to_string(),
         };
-        credentials_set_db(&pool, "live", &creds).unwrap();
-        assert!(credentials_exists_db(&pool, "live").unwrap());
+        credentials_set_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        assert!(credentials_exists_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap());
     }
 
     #[test]
@@ -199,11 +1228,11 @@ mod tests {
             key_id: "LIVE_KEY".to_string(),
             secret_key: "LIVE_SECRET".to_string(),
         };
-        credentials_set_db(&pool, "paper", &paper).unwrap();
-        credentials_set_db(&pool, "live", &live).unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &paper).unwrap();
+        credentials_set_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE, &live).unwrap();
 
-        let got_paper = credentials_get_db(&pool, "paper").unwrap().unwrap();
-        let got_live = credentials_get_db(&pool, "live").unwrap().unwrap();
+        let got_paper = credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap().unwrap();
+        let got_live = credentials_get_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap().unwrap();
         assert_eq!(got_paper.key_id, "PAPER_KEY");
         assert_eq!(got_live.key_id, "LIVE_KEY");
     }
@@ -215,15 +1244,15 @@ mod tests {
             key_id: "OLD".to_string(),
             secret_key: "OLD_SECRET".to_string(),
         };
-        credentials_set_db(&pool, "paper", &old).unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &old).unwrap();
 
         let new = AlpacaCredentials {
             key_id: "NEW".to_string(),
             secret_key: "NEW_SECRET".to_string(),
         };
-        credentials_set_db(&pool, "paper", &new).unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &new).unwrap();
 
-        let result = credentials_get_db(&pool, "paper").unwrap().unwrap();
+        let result = credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap().unwrap();
         assert_eq!(result.key_id, "NEW");
         assert_eq!(result.secret_key, "NEW_SECRET");
     }
@@ -231,7 +1260,7 @@ mod tests {
     #[test]
     fn credentials_get_returns_none_when_not_set() {
         let pool = test_pool();
-        let result = credentials_get_db(&pool, "paper").unwrap();
+        let result = credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
         assert_eq!(result, None);
     }
 
@@ -242,8 +1271,8 @@ mod tests {
             key_id: "PKFULL123".to_string(),
             secret_key: "full_secret_456".to_string(),
         };
-        credentials_set_db(&pool, "paper", &creds).unwrap();
-        let result = credentials_get_db(&pool, "paper").unwrap().unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        let result = credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap().unwrap();
         assert_eq!(result.key_id, "PKFULL123");
         assert_eq!(result.secret_key, "full_secret_456");
     }
@@ -255,8 +1284,264 @@ mod tests {
             key_id: "KEY".to_string(),
             secret_key: "SECRET".to_string(),
         };
-        assert!(credentials_set_db(&pool, "invalid", &creds).is_err());
-        assert!(credentials_get_db(&pool, "invalid").is_err());
-        assert!(credentials_exists_db(&pool, "invalid").is_err());
+        assert!(credentials_set_db(&pool, "invalid", DEFAULT_CREDENTIAL_PROFILE, &creds).is_err());
+        assert!(credentials_get_db(&pool, "invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+        assert!(credentials_exists_db(&pool, "invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+    }
+
+    #[test]
+    fn credentials_delete_removes_only_that_mode() {
+        let pool = test_pool();
+        let creds = AlpacaCredentials {
+            key_id: "KEY".to_string(),
+            secret_key: "SECRET".to_string(),
+        };
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        credentials_set_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        assert!(credentials_delete_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap());
+        assert!(!credentials_delete_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap());
+        assert_eq!(credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap(), None);
+        assert!(credentials_exists_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap());
+        assert!(credentials_delete_db(&pool, "invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+    }
+
+    #[test]
+    fn profiles_store_separate_credentials_per_mode() {
+        let pool = test_pool();
+        let first = AlpacaCredentials {
+            key_id: "PK_FIRST".to_string(),
+            secret_key: "s1".to_string(),
+        };
+        let second = AlpacaCredentials {
+            key_id: "PK_SECOND".to_string(),
+            secret_key: "s2".to_string(),
+        };
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &first).unwrap();
+        credentials_set_db(&pool, "paper", "second", &second).unwrap();
+
+        assert_eq!(credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap(), Some(first));
+        assert_eq!(credentials_get_db(&pool, "paper", "second").unwrap(), Some(second));
+        assert!(!credentials_exists_db(&pool, "live", "second").unwrap());
+        assert!(credentials_set_db(&pool, "paper", "bad name", &AlpacaCredentials {
+            key_id: "K".to_string(),
+            secret_key: "S".to_string(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn provider_credentials_roundtrip_per_provider() {
+        let pool = test_pool();
+        let polygon = ProviderCredentials::from([("api_key".to_string(), "poly-key".to_string())]);
+        provider_credentials_set_db(&pool, BrokerProvider::Polygon, "live", DEFAULT_CREDENTIAL_PROFILE, &polygon)
+            .unwrap();
+
+        assert_eq!(
+            provider_credentials_get_db(&pool, BrokerProvider::Polygon, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap(),
+            Some(polygon.clone())
+        );
+        assert!(!provider_credentials_exists_db(&pool, BrokerProvider::Finnhub, "live", DEFAULT_CREDENTIAL_PROFILE)
+            .unwrap());
+        // Wrong schema and wrong mode are rejected
+        assert!(provider_credentials_set_db(&pool, BrokerProvider::Tradier, "live", DEFAULT_CREDENTIAL_PROFILE, &polygon)
+            .is_err());
+        assert!(provider_credentials_set_db(&pool, BrokerProvider::Polygon, "paper", DEFAULT_CREDENTIAL_PROFILE, &polygon)
+            .is_err());
+    }
+
+    #[test]
+    fn alpaca_credentials_share_provider_storage() {
+        let pool = test_pool();
+        let creds = AlpacaCredentials {
+            key_id: "PK_SHARED".to_string(),
+            secret_key: "shared".to_string(),
+        };
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        let fields = provider_credentials_get_db(&pool, BrokerProvider::Alpaca, "paper", DEFAULT_CREDENTIAL_PROFILE)
+            .unwrap()
+            .unwrap();
+        assert_eq!(AlpacaCredentials::from_fields(fields), creds);
+    }
+
+    #[test]
+    fn list_returns_every_stored_credential_set() {
+        let pool = test_pool();
+        let creds = AlpacaCredentials {
+            key_id: "K".to_string(),
+            secret_key: "S".to_string(),
+        };
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        credentials_set_db(&pool, "live", "second", &creds).unwrap();
+        let polygon = ProviderCredentials::from([("api_key".to_string(), "k".to_string())]);
+        provider_credentials_set_db(&pool, BrokerProvider::Polygon, "live", DEFAULT_CREDENTIAL_PROFILE, &polygon)
+            .unwrap();
+        credentials_select_profile_db(&pool, "paper", "second").unwrap();
+
+        let rows = provider_credentials_list_db(&pool).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                (BrokerProvider::Alpaca, "live".to_string(), "second".to_string()),
+                (BrokerProvider::Alpaca, "paper".to_string(), "default".to_string()),
+                (BrokerProvider::Polygon, "live".to_string(), "default".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn import_env_stores_set_variables_and_reports_missing() {
+        let env = |name: &str| match name {
+            "ALPACA_KEY_ID" => Some("PK_ENV".to_string()),
+            "ALPACA_SECRET_KEY" => Some("env-secret".to_string()),
+            "ANTHROPIC_API_KEY" => Some("sk-ant-env".to_string()),
+            "OPENROUTER_API_KEY" => Some("  ".to_string()),
+            _ => None,
+        };
+        let mut alpaca = Vec::new();
+        let mut llm = Vec::new();
+        let report = import_env(
+            env,
+            |creds| {
+                alpaca.push(creds.clone());
+                Ok(())
+            },
+            |provider, key| {
+                llm.push((provider.to_string(), key.to_string()));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert!(report.alpaca);
+        assert_eq!(report.llm_providers, vec!["anthropic"]);
+        assert_eq!(report.missing, vec!["OPENROUTER_API_KEY"]);
+        assert_eq!(AlpacaCredentials::from_fields(alpaca[0].clone()).key_id, "PK_ENV");
+        assert_eq!(llm, vec![("anthropic".to_string(), "sk-ant-env".to_string())]);
+    }
+
+    #[test]
+    fn import_env_skips_alpaca_without_both_variables() {
+        let env = |name: &str| (name == "ALPACA_KEY_ID").then(|| "PK_ONLY".to_string());
+        let report = import_env(env, |_| panic!("must not store"), |_, _| Ok(())).unwrap();
+        assert!(!report.alpaca);
+        assert!(report.missing.contains(&"ALPACA_SECRET_KEY".to_string()));
+        assert!(!report.missing.contains(&"ALPACA_KEY_ID".to_string()));
+    }
+
+    #[test]
+    fn key_fingerprint_shows_only_ends_of_long_values() {
+        assert_eq!(key_fingerprint("PKABCDEFGHIJKLMN1234"), "PKAB…1234");
+        assert_eq!(key_fingerprint("short-key"), "••••");
+    }
+
+    #[test]
+    fn secret_identities_are_fingerprinted_by_hash() {
+        let key = "pk_live_ABCDEFGHIJKLMNOP9876";
+        let creds: ProviderCredentials = [("api_key".to_string(), key.to_string())].into();
+        let fingerprint = credential_fingerprint(BrokerProvider::Polygon, &creds);
+        assert!(fingerprint.starts_with("sha256:"));
+        assert_eq!(fingerprint.len(), "sha256:".len() + 12);
+        assert!(!fingerprint.contains("pk_l") && !fingerprint.contains("9876"));
+        assert_eq!(credential_fingerprint(BrokerProvider::Finnhub, &creds), fingerprint);
+        assert_eq!(credential_fingerprint(BrokerProvider::Polygon, &ProviderCredentials::new()), "••••");
+
+        let tradier: ProviderCredentials = [
+            ("account_id".to_string(), "VA12345678901234".to_string()),
+            ("access_token".to_string(), "token".to_string()),
+        ]
+        .into();
+        assert_eq!(credential_fingerprint(BrokerProvider::Tradier, &tradier), "VA12…1234");
+    }
+
+    #[test]
+    fn metadata_tracks_store_validation_and_use() {
+        let pool = test_pool();
+        let alpaca = BrokerProvider::Alpaca;
+        assert_eq!(credential_metadata_get_db(&pool, alpaca, "paper", "default").unwrap(), None);
+
+        let creds = AlpacaCredentials {
+            key_id: "PKABCDEFGHIJKLMN1234".to_string(),
+            secret_key: "secret".to_string(),
+        }
+        .into_fields();
+        credential_metadata_stored_db(&pool, alpaca, "paper", "default", &creds).unwrap();
+        let stored = credential_metadata_get_db(&pool, alpaca, "paper", "default").unwrap().unwrap();
+        assert_eq!(stored.fingerprint, "PKAB…1234");
+        assert_eq!(stored.last_validated_at, None);
+
+        credential_metadata_touch_db(&pool, alpaca, "paper", "default", CredentialEvent::Validated).unwrap();
+        credential_metadata_touch_db(&pool, alpaca, "paper", "default", CredentialEvent::Used).unwrap();
+        let touched = credential_metadata_get_db(&pool, alpaca, "paper", "default").unwrap().unwrap();
+        assert!(touched.last_validated_at.is_some());
+        assert!(touched.last_used_at.is_some());
+
+        // Storing a new key resets the timestamps
+        credential_metadata_stored_db(&pool, alpaca, "paper", "default", &creds).unwrap();
+        let reset = credential_metadata_get_db(&pool, alpaca, "paper", "default").unwrap().unwrap();
+        assert_eq!(reset.last_used_at, None);
+
+        credential_metadata_delete_db(&pool, alpaca, "paper", "default").unwrap();
+        assert_eq!(credential_metadata_get_db(&pool, alpaca, "paper", "default").unwrap(), None);
+    }
+
+    #[test]
+    fn masked_broker_credentials_hide_only_secret_fields() {
+        let fields = ProviderCredentials::from([
+            ("account_id".to_string(), "VA123456".to_string()),
+            ("access_token".to_string(), "tradier-token-abcd".to_string()),
+        ]);
+        let masked = BrokerCredentialsMasked::new(BrokerProvider::Tradier, "paper", "default", fields);
+        assert_eq!(masked.fields["account_id"], "VA123456");
+        assert_eq!(masked.fields["access_token"], "••••abcd");
+    }
+
+    #[test]
+    fn active_profile_defaults_and_can_be_selected() {
+        let pool = test_pool();
+        assert_eq!(credentials_active_profile_db(&pool, "paper").unwrap(), "default");
+
+        credentials_select_profile_db(&pool, "paper", "second").unwrap();
+        assert_eq!(credentials_active_profile_db(&pool, "paper").unwrap(), "second");
+        assert_eq!(credentials_active_profile_db(&pool, "live").unwrap(), "default");
+
+        credentials_select_profile_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
+        assert_eq!(credentials_active_profile_db(&pool, "paper").unwrap(), "default");
+        assert!(credentials_select_profile_db(&pool, "invalid", "second").is_err());
+    }
+
+    #[test]
+    fn validation_reads_account_fields() {
+        let account = serde_json::json!({
+            "status": "ACTIVE",
+            "buying_power": "200000.00",
+            "currency": "USD",
+            "trading_blocked": false,
+        });
+        let report = CredentialValidation::from_account("paper", "paper", &account);
+        assert!(report.valid);
+        assert_eq!(report.account_status.as_deref(), Some("ACTIVE"));
+        assert_eq!(report.buying_power.as_deref(), Some("200000.00"));
+        assert_eq!(report.trading_blocked, Some(false));
+    }
+
+    #[test]
+    fn validation_flags_environment_mismatch() {
+        let report = CredentialValidation::from_account("paper", "live", &serde_json::json!({}));
+        assert!(!report.valid);
+        assert_eq!(report.key_environment.as_deref(), Some("live"));
+        assert!(report.error.unwrap().contains("live keys"));
+    }
+
+    #[test]
+    fn mask_secret_shows_only_last_four() {
+        assert_eq!(mask_secret("sk-ant-api03-abcd1234"), "••••1234");
+        assert_eq!(mask_secret("short"), "••••");
+    }
+
+    #[test]
+    fn llm_config_field_maps_providers() {
+        assert_eq!(llm_config_field("anthropic").unwrap(), "anthropicApiKey");
+        assert_eq!(llm_config_field("openrouter").unwrap(), "openrouterApiKey");
+        assert!(llm_config_field("openai").is_err());
     }
 }
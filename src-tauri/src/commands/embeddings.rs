@@ -0,0 +1,126 @@
+use serde::Deserialize;
+
+use crate::bridge::SidecarBridge;
+use crate::bridge_error::BridgeError;
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::ensure_online;
+use crate::db::{self, DbPool};
+use crate::embeddings::{
+    self, embeddings_config, local_model, models_dir, ready_model, EmbeddingsStatus, LocalEmbedder, LocalModel,
+};
+use crate::events::{emit_event, event_names};
+use crate::workspace::WorkspaceDb;
+
+/// Entries embedded per `memory:unembedded` round trip.
+const BACKFILL_BATCH: u32 = 32;
+
+async fn ready(pool: &DbPool) -> Result<Option<&'static LocalModel>, String> {
+    let config = db::run_blocking(pool, |pool| Ok(embeddings_config(&config_effective_db(pool)?))).await?;
+    Ok(ready_model(&config, &models_dir()))
+}
+
+/// Embed `query` with the local model when it is enabled and downloaded.
+pub(crate) async fn embed_query(
+    pool: &DbPool,
+    embedder: &LocalEmbedder,
+    query: &str,
+) -> Result<Option<Vec<f32>>, String> {
+    if query.trim().is_empty() {
+        return Ok(None);
+    }
+    let Some(model) = ready(pool).await? else {
+        return Ok(None);
+    };
+    let embedded = embedder.embed(model, &models_dir().join(model.name), &[query.to_string()])?;
+    Ok(embedded.into_iter().next())
+}
+
+#[tauri::command]
+pub async fn embeddings_status(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    embedder: tauri::State<'_, LocalEmbedder>,
+) -> Result<EmbeddingsStatus, String> {
+    let config = db::run_blocking(&workspace.pool(), |pool| {
+        Ok(embeddings_config(&config_effective_db(pool)?))
+    })
+    .await?;
+    Ok(embeddings::embeddings_status(&config, &models_dir(), &embedder))
+}
+
+/// Download the configured local model, emitting `embeddings:download` before
+/// each file. With `force`, files already downloaded are fetched again.
+#[tauri::command]
+pub async fn embeddings_download(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    embedder: tauri::State<'_, LocalEmbedder>,
+    force: Option<bool>,
+) -> Result<EmbeddingsStatus, String> {
+    let config = db::run_blocking(&workspace.pool(), |pool| {
+        ensure_online(pool)?;
+        Ok(embeddings_config(&config_effective_db(pool)?))
+    })
+    .await?;
+    let model = local_model(&config.model).ok_or_else(|| format!("Unknown local embedding model: {}", config.model))?;
+    let dir = models_dir().join(model.name);
+    let bytes = embeddings::download_model(model, &dir, force.unwrap_or(false), |index, total, file| {
+        let payload = serde_json::json!({ "model": model.name, "file": file, "index": index, "total": total });
+        if let Err(e) = emit_event(&app, event_names::EMBEDDINGS_DOWNLOAD, payload) {
+            tracing::warn!(error = %e, "Failed to emit embeddings download progress");
+        }
+    })
+    .await?;
+    embedder.unload();
+    tracing::info!(model = model.name, bytes, "Downloaded local embedding model");
+    Ok(embeddings::embeddings_status(&config, &models_dir(), &embedder))
+}
+
+#[derive(Deserialize)]
+struct Unembedded {
+    id: String,
+    content: String,
+}
+
+/// Embed the agent's memory entries that have no embedding yet with the local
+/// model, newest first, up to `limit` entries. Returns how many were embedded.
+#[tauri::command]
+pub async fn embeddings_backfill(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    embedder: tauri::State<'_, LocalEmbedder>,
+    limit: Option<u32>,
+) -> Result<u64, BridgeError> {
+    let model = ready(&workspace.pool())
+        .await?
+        .ok_or("Local embeddings are not enabled or the model is not downloaded".to_string())?;
+    let dir = models_dir().join(model.name);
+    let limit = limit.unwrap_or(u32::MAX);
+    let mut embedded = 0u64;
+    while embedded < limit as u64 {
+        let batch = BACKFILL_BATCH.min(limit - embedded as u32);
+        let result = bridge.call("memory:unembedded", Some(serde_json::json!({ "limit": batch })))?;
+        let entries: Vec<Unembedded> = serde_json::from_value(result.get("entries").cloned().unwrap_or_default())
+            .map_err(|e| format!("Invalid memory:unembedded response: {}", e))?;
+        if entries.is_empty() {
+            break;
+        }
+        let texts: Vec<String> = entries.iter().map(|e| e.content.clone()).collect();
+        let vectors = embedder.embed(model, &dir, &texts)?;
+        let embeddings: Vec<serde_json::Value> = entries
+            .iter()
+            .zip(vectors)
+            .map(|(entry, embedding)| serde_json::json!({ "id": entry.id, "embedding": embedding }))
+            .collect();
+        let result = bridge.call(
+            "memory:set_embeddings",
+            Some(serde_json::json!({ "embeddings": embeddings })),
+        )?;
+        let updated = result.get("updated").and_then(|v| v.as_u64()).unwrap_or(0);
+        embedded += updated;
+        if updated == 0 || entries.len() < batch as usize {
+            break;
+        }
+    }
+    tracing::info!(model = model.name, embedded, "Backfilled memory embeddings locally");
+    Ok(embedded)
+}
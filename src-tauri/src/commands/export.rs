@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::parquet_export::{self, ExportDataset, ExportFilter, ExportSummary};
+use crate::report::{self, ReportKind, ReportSummary};
+use crate::workspace::WorkspaceDb;
+
+/// Export `dataset` (narrowed by `filter`) from the active workspace to a
+/// Parquet file at `path`, for analysis in pandas or polars.
+#[tauri::command]
+pub async fn export_parquet(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    dataset: ExportDataset,
+    filter: Option<ExportFilter>,
+    path: String,
+) -> Result<ExportSummary, String> {
+    let pool = workspace.pool();
+    let filter = filter.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || parquet_export::export(&pool, dataset, &filter, Path::new(&path)))
+        .await
+        .map_err(|e| format!("Export task failed: {}", e))?
+}
+
+/// Render a standalone HTML report of a backtest or a monitoring session to
+/// `path`, for sharing results outside the app.
+#[tauri::command]
+pub async fn report_generate(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    kind: ReportKind,
+    id: String,
+    path: String,
+) -> Result<ReportSummary, String> {
+    let pool = workspace.pool();
+    tauri::async_runtime::spawn_blocking(move || report::generate(&pool, kind, &id, Path::new(&path)))
+        .await
+        .map_err(|e| format!("Report task failed: {}", e))?
+}
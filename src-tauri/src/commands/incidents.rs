@@ -0,0 +1,272 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::tasks::TaskOutcome;
+use crate::types::anomaly::{Incident, IncidentFilter, Severity};
+use crate::workspace::WorkspaceDb;
+
+/// The `incidents` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IncidentsConfig {
+    /// How far apart two anomalies on a symbol may be and still share an incident.
+    pub window_minutes: u64,
+}
+
+impl Default for IncidentsConfig {
+    fn default() -> Self {
+        Self { window_minutes: 30 }
+    }
+}
+
+/// Parse the `incidents` section of the app config.
+pub fn incidents_config(app_config: &serde_json::Value) -> IncidentsConfig {
+    app_config
+        .get("incidents")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Severities by rank, matching the `CASE` in `incidents_list_db`.
+const SEVERITY_RANKS: [Severity; 4] = [Severity::Low, Severity::Medium, Severity::High, Severity::Critical];
+
+/// Assign every ungrouped anomaly that has a symbol to an incident and return
+/// how many were grouped. An anomaly within `window_ms` of an incident's span
+/// joins it; one that bridges two incidents merges them.
+pub fn incidents_group_db(pool: &DbPool, window_ms: u64) -> Result<usize, String> {
+    let window = window_ms as i64;
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    let pending: Vec<(String, String, i64)> = tx
+        .prepare(
+            "SELECT id, symbol, timestamp FROM anomalies
+             WHERE incident_id IS NULL AND symbol IS NOT NULL
+             ORDER BY symbol, timestamp",
+        )
+        .map_err(|e| e.to_string())?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+        .map_err(|e| e.to_string())?
+        .collect::<Result<_, _>>()
+        .map_err(|e| e.to_string())?;
+
+    for (id, symbol, timestamp) in &pending {
+        let matches: Vec<(i64, i64, i64)> = tx
+            .prepare_cached(
+                "SELECT id, started_at, ended_at FROM incidents
+                 WHERE symbol = ?1 AND started_at - ?3 <= ?2 AND ended_at + ?3 >= ?2
+                 ORDER BY started_at",
+            )
+            .map_err(|e| e.to_string())?
+            .query_map(rusqlite::params![symbol, timestamp, window], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })
+            .map_err(|e| e.to_string())?
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?;
+
+        let incident_id = match matches.split_first() {
+            None => {
+                tx.execute(
+                    "INSERT INTO incidents (symbol, started_at, ended_at) VALUES (?1, ?2, ?2)",
+                    rusqlite::params![symbol, timestamp],
+                )
+                .map_err(|e| e.to_string())?;
+                tx.last_insert_rowid()
+            }
+            Some((&(keep, started_at, ended_at), rest)) => {
+                let started_at = rest.iter().map(|m| m.1).fold(started_at.min(*timestamp), i64::min);
+                let ended_at = rest.iter().map(|m| m.2).fold(ended_at.max(*timestamp), i64::max);
+                for &(other, _, _) in rest {
+                    tx.execute(
+                        "UPDATE anomalies SET incident_id = ?1 WHERE incident_id = ?2",
+                        rusqlite::params![keep, other],
+                    )
+                    .map_err(|e| e.to_string())?;
+                    tx.execute("DELETE FROM incidents WHERE id = ?1", [other])
+                        .map_err(|e| e.to_string())?;
+                }
+                tx.execute(
+                    "UPDATE incidents SET started_at = ?2, ended_at = ?3 WHERE id = ?1",
+                    rusqlite::params![keep, started_at, ended_at],
+                )
+                .map_err(|e| e.to_string())?;
+                keep
+            }
+        };
+        tx.execute(
+            "UPDATE anomalies SET incident_id = ?1 WHERE id = ?2",
+            rusqlite::params![incident_id, id],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    tx.commit().map_err(|e| e.to_string())?;
+    Ok(pending.len())
+}
+
+/// Incidents with their member count and highest effective severity, most
+/// recent first.
+pub fn incidents_list_db(pool: &DbPool, filter: &IncidentFilter) -> Result<Vec<Incident>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT i.id, i.symbol, i.started_at, i.ended_at, COUNT(a.id),
+                    MAX(CASE COALESCE(a.override_severity, a.severity)
+                        WHEN 'critical' THEN 3 WHEN 'high' THEN 2 WHEN 'medium' THEN 1 ELSE 0 END)
+             FROM incidents i JOIN anomalies a ON a.incident_id = i.id
+             WHERE (?1 IS NULL OR i.symbol = ?1) AND (?2 IS NULL OR i.ended_at >= ?2)
+             GROUP BY i.id
+             ORDER BY i.ended_at DESC
+             LIMIT ?3",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                filter.symbol,
+                filter.since.map(|t| t as i64),
+                filter.limit.map(i64::from).unwrap_or(-1),
+            ],
+            |row| {
+                let rank: i64 = row.get(5)?;
+                Ok(Incident {
+                    id: row.get(0)?,
+                    symbol: row.get(1)?,
+                    started_at: row.get::<_, i64>(2)?.max(0) as u64,
+                    ended_at: row.get::<_, i64>(3)?.max(0) as u64,
+                    anomaly_count: row.get(4)?,
+                    max_severity: SEVERITY_RANKS[rank.clamp(0, 3) as usize],
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+fn group_pending(pool: &DbPool) -> Result<usize, String> {
+    let app_config = crate::commands::config::config_effective_db(pool)?;
+    let window_ms = incidents_config(&app_config).window_minutes * 60_000;
+    incidents_group_db(pool, window_ms)
+}
+
+/// Scheduled grouping pass over newly recorded anomalies.
+pub fn grouping_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let grouped = group_pending(&pool)?;
+    if grouped == 0 {
+        return Ok(TaskOutcome::Skipped);
+    }
+    tracing::debug!(grouped, "Grouped anomalies into incidents");
+    let _ = emit_event(app, event_names::INCIDENTS_UPDATED, grouped);
+    Ok(TaskOutcome::Ran)
+}
+
+/// List incidents, grouping any anomalies recorded since the last pass first.
+#[tauri::command]
+pub async fn incidents_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    filter: Option<IncidentFilter>,
+) -> Result<Vec<Incident>, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        group_pending(pool)?;
+        incidents_list_db(pool, &filter.unwrap_or_default())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::anomalies::{anomalies_insert_db, anomalies_list_db};
+    use crate::types::anomaly::{Anomaly, AnomalyFilter, AnomalyStatus};
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    fn anomaly(id: &str, symbol: Option<&str>, timestamp: u64, severity: Severity) -> Anomaly {
+        Anomaly {
+            id: id.to_string(),
+            severity,
+            source: "test".to_string(),
+            symbol: symbol.map(str::to_string),
+            timestamp,
+            description: "test".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.5,
+            session_id: "s1".to_string(),
+            status: AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        }
+    }
+
+    #[test]
+    fn nearby_anomalies_on_a_symbol_share_an_incident() {
+        let pool = test_pool();
+        let minute = 60_000;
+        for (i, t) in [0, 10, 20, 30].iter().enumerate() {
+            anomalies_insert_db(
+                &pool,
+                &anomaly(&format!("net-{}", i), Some("NET"), t * minute, Severity::Low),
+            )
+            .unwrap();
+        }
+        anomalies_insert_db(&pool, &anomaly("net-late", Some("NET"), 200 * minute, Severity::Medium)).unwrap();
+        anomalies_insert_db(&pool, &anomaly("aapl", Some("AAPL"), 5 * minute, Severity::High)).unwrap();
+        anomalies_insert_db(&pool, &anomaly("market", None, 5 * minute, Severity::Critical)).unwrap();
+
+        assert_eq!(incidents_group_db(&pool, 15 * minute).unwrap(), 6);
+        assert_eq!(incidents_group_db(&pool, 15 * minute).unwrap(), 0);
+        let incidents = incidents_list_db(&pool, &IncidentFilter::default()).unwrap();
+        let summary: Vec<(&str, u64, u64, u32)> = incidents
+            .iter()
+            .map(|i| {
+                (
+                    i.symbol.as_str(),
+                    i.started_at / minute,
+                    i.ended_at / minute,
+                    i.anomaly_count,
+                )
+            })
+            .collect();
+        assert_eq!(summary, [("NET", 200, 200, 1), ("NET", 0, 30, 4), ("AAPL", 5, 5, 1)]);
+        assert_eq!(incidents[2].max_severity, Severity::High);
+
+        let members = AnomalyFilter {
+            incident_id: Some(incidents[1].id),
+            ..AnomalyFilter::default()
+        };
+        assert_eq!(anomalies_list_db(&pool, &Some(members)).unwrap().len(), 4);
+
+        let aapl = IncidentFilter {
+            symbol: Some("AAPL".to_string()),
+            ..IncidentFilter::default()
+        };
+        assert_eq!(incidents_list_db(&pool, &aapl).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn an_anomaly_bridging_two_incidents_merges_them() {
+        let pool = test_pool();
+        let minute = 60_000;
+        anomalies_insert_db(&pool, &anomaly("a", Some("NET"), 0, Severity::Low)).unwrap();
+        anomalies_insert_db(&pool, &anomaly("b", Some("NET"), 30 * minute, Severity::Critical)).unwrap();
+        incidents_group_db(&pool, 20 * minute).unwrap();
+        assert_eq!(incidents_list_db(&pool, &IncidentFilter::default()).unwrap().len(), 2);
+
+        anomalies_insert_db(&pool, &anomaly("c", Some("NET"), 15 * minute, Severity::Low)).unwrap();
+        incidents_group_db(&pool, 20 * minute).unwrap();
+        let incidents = incidents_list_db(&pool, &IncidentFilter::default()).unwrap();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!((incidents[0].started_at, incidents[0].ended_at), (0, 30 * minute));
+        assert_eq!(incidents[0].anomaly_count, 3);
+        assert_eq!(incidents[0].max_severity, Severity::Critical);
+    }
+}
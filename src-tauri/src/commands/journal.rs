@@ -0,0 +1,474 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::anomalies::anomaly_get_db;
+use crate::db::{self, DbPool};
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// Direction of a manually logged trade.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalSide {
+    Long,
+    Short,
+}
+
+impl JournalSide {
+    fn as_str(self) -> &'static str {
+        match self {
+            JournalSide::Long => "long",
+            JournalSide::Short => "short",
+        }
+    }
+
+    fn parse(s: &str) -> JournalSide {
+        match s {
+            "short" => JournalSide::Short,
+            _ => JournalSide::Long,
+        }
+    }
+}
+
+/// A real-world trade as the user logs it. The trade is open until both
+/// `exit_price` and `closed_at` are set.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalTradeInput {
+    pub symbol: String,
+    pub side: JournalSide,
+    pub qty: f64,
+    pub entry_price: f64,
+    #[serde(default)]
+    pub exit_price: Option<f64>,
+    /// Commissions and fees for the round trip, in USD.
+    #[serde(default)]
+    pub fees: f64,
+    /// Epoch millis.
+    pub opened_at: u64,
+    /// Epoch millis.
+    #[serde(default)]
+    pub closed_at: Option<u64>,
+    /// The anomaly the trade acted on, if any.
+    #[serde(default)]
+    pub anomaly_id: Option<String>,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalTrade {
+    pub id: i64,
+    #[serde(flatten)]
+    pub trade: JournalTradeInput,
+    /// Profit after fees; `None` while the trade is open.
+    pub pnl: Option<f64>,
+    /// Epoch millis.
+    pub created_at: u64,
+    /// Epoch millis of the last edit, if any.
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct JournalFilter {
+    pub symbol: Option<String>,
+    pub anomaly_id: Option<String>,
+    pub tag: Option<String>,
+    /// Only trades opened at or after this epoch millis.
+    pub from: Option<u64>,
+    /// Only trades opened at or before this epoch millis.
+    pub to: Option<u64>,
+}
+
+/// Outcome statistics over a set of journal trades. Ratios are `None` when
+/// there are no closed trades to compute them from.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalStats {
+    pub trades: u64,
+    pub open: u64,
+    pub winners: u64,
+    pub losers: u64,
+    pub win_rate: Option<f64>,
+    pub total_pnl: f64,
+    pub avg_win: Option<f64>,
+    pub avg_loss: Option<f64>,
+    /// Gross profit over gross loss; `None` without losses.
+    pub profit_factor: Option<f64>,
+}
+
+/// Journal statistics overall and split by whether the trade acted on an anomaly.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalSummary {
+    pub all: JournalStats,
+    pub linked: JournalStats,
+    pub unlinked: JournalStats,
+}
+
+/// Profit of a closed trade after fees.
+pub fn journal_pnl(trade: &JournalTradeInput) -> Option<f64> {
+    let exit = trade.exit_price?;
+    trade.closed_at?;
+    let direction = match trade.side {
+        JournalSide::Long => 1.0,
+        JournalSide::Short => -1.0,
+    };
+    Some((exit - trade.entry_price) * trade.qty * direction - trade.fees)
+}
+
+/// `trade` with its symbol upper-cased and its notes and tags tidied, or why
+/// it cannot be stored.
+fn normalize(pool: &DbPool, mut trade: JournalTradeInput) -> Result<JournalTradeInput, String> {
+    trade.symbol = trade.symbol.trim().to_uppercase();
+    if trade.symbol.is_empty() {
+        return Err("Symbol must not be empty".to_string());
+    }
+    if !(trade.qty.is_finite() && trade.qty > 0.0) {
+        return Err("Quantity must be positive".to_string());
+    }
+    let mut prices = std::iter::once(trade.entry_price).chain(trade.exit_price);
+    if prices.any(|p| !(p.is_finite() && p > 0.0)) {
+        return Err("Prices must be positive".to_string());
+    }
+    if !(trade.fees.is_finite() && trade.fees >= 0.0) {
+        return Err("Fees must not be negative".to_string());
+    }
+    if trade.exit_price.is_some() != trade.closed_at.is_some() {
+        return Err("A closed trade needs both an exit price and a close time".to_string());
+    }
+    if trade.closed_at.is_some_and(|closed| closed < trade.opened_at) {
+        return Err("A trade cannot close before it opens".to_string());
+    }
+    trade.anomaly_id = trade
+        .anomaly_id
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty());
+    if let Some(anomaly_id) = &trade.anomaly_id {
+        if anomaly_get_db(pool, anomaly_id)?.is_none() {
+            return Err(format!("Unknown anomaly {}", anomaly_id));
+        }
+    }
+    trade.notes = trade.notes.trim().to_string();
+    let mut tags: Vec<String> = Vec::new();
+    for tag in trade.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+        if !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    trade.tags = tags;
+    Ok(trade)
+}
+
+const JOURNAL_COLUMNS: &str = "id, symbol, side, qty, entry_price, exit_price, fees, opened_at, closed_at, \
+                               anomaly_id, notes, tags, created_at, updated_at";
+
+fn journal_from_row(row: &rusqlite::Row) -> rusqlite::Result<JournalTrade> {
+    let trade = JournalTradeInput {
+        symbol: row.get(1)?,
+        side: JournalSide::parse(&row.get::<_, String>(2)?),
+        qty: row.get(3)?,
+        entry_price: row.get(4)?,
+        exit_price: row.get(5)?,
+        fees: row.get(6)?,
+        opened_at: row.get::<_, i64>(7)?.max(0) as u64,
+        closed_at: row.get::<_, Option<i64>>(8)?.map(|t| t.max(0) as u64),
+        anomaly_id: row.get(9)?,
+        notes: row.get(10)?,
+        tags: serde_json::from_str(&row.get::<_, String>(11)?).unwrap_or_default(),
+    };
+    Ok(JournalTrade {
+        id: row.get(0)?,
+        pnl: journal_pnl(&trade),
+        trade,
+        created_at: row.get::<_, i64>(12)?.max(0) as u64,
+        updated_at: row.get::<_, Option<i64>>(13)?.map(|t| t.max(0) as u64),
+    })
+}
+
+pub fn journal_get_db(pool: &DbPool, id: i64) -> Result<JournalTrade, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!("SELECT {} FROM journal_trades WHERE id = ?1", JOURNAL_COLUMNS);
+    match conn.query_row(&sql, [id], journal_from_row) {
+        Ok(trade) => Ok(trade),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Err(format!("Unknown journal trade {}", id)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Journal trades matching `filter`, most recently opened first.
+pub fn journal_list_db(pool: &DbPool, filter: &JournalFilter) -> Result<Vec<JournalTrade>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!(
+        "SELECT {} FROM journal_trades
+         WHERE (?1 IS NULL OR symbol = ?1)
+           AND (?2 IS NULL OR anomaly_id = ?2)
+           AND (?3 IS NULL OR EXISTS (SELECT 1 FROM json_each(tags) WHERE value = ?3))
+           AND opened_at >= ?4 AND opened_at <= ?5
+         ORDER BY opened_at DESC, id DESC",
+        JOURNAL_COLUMNS
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                filter.symbol.as_deref().map(|s| s.trim().to_uppercase()),
+                filter.anomaly_id,
+                filter.tag,
+                filter.from.unwrap_or(0) as i64,
+                filter.to.map(|t| t as i64).unwrap_or(i64::MAX),
+            ],
+            journal_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn journal_add_db(pool: &DbPool, trade: JournalTradeInput, now_ms: u64) -> Result<JournalTrade, String> {
+    let trade = normalize(pool, trade)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO journal_trades
+            (symbol, side, qty, entry_price, exit_price, fees, opened_at, closed_at, anomaly_id, notes, tags, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        rusqlite::params![
+            trade.symbol,
+            trade.side.as_str(),
+            trade.qty,
+            trade.entry_price,
+            trade.exit_price,
+            trade.fees,
+            trade.opened_at as i64,
+            trade.closed_at.map(|t| t as i64),
+            trade.anomaly_id,
+            trade.notes,
+            serde_json::to_string(&trade.tags).map_err(|e| e.to_string())?,
+            now_ms as i64,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    let id = conn.last_insert_rowid();
+    drop(conn);
+    journal_get_db(pool, id)
+}
+
+/// Replace every field of journal trade `id`, e.g. to close it.
+pub fn journal_update_db(
+    pool: &DbPool,
+    id: i64,
+    trade: JournalTradeInput,
+    now_ms: u64,
+) -> Result<JournalTrade, String> {
+    let trade = normalize(pool, trade)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let updated = conn
+        .execute(
+            "UPDATE journal_trades SET symbol = ?2, side = ?3, qty = ?4, entry_price = ?5, exit_price = ?6,
+                 fees = ?7, opened_at = ?8, closed_at = ?9, anomaly_id = ?10, notes = ?11, tags = ?12, updated_at = ?13
+             WHERE id = ?1",
+            rusqlite::params![
+                id,
+                trade.symbol,
+                trade.side.as_str(),
+                trade.qty,
+                trade.entry_price,
+                trade.exit_price,
+                trade.fees,
+                trade.opened_at as i64,
+                trade.closed_at.map(|t| t as i64),
+                trade.anomaly_id,
+                trade.notes,
+                serde_json::to_string(&trade.tags).map_err(|e| e.to_string())?,
+                now_ms as i64,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    drop(conn);
+    if updated == 0 {
+        return Err(format!("Unknown journal trade {}", id));
+    }
+    journal_get_db(pool, id)
+}
+
+pub fn journal_delete_db(pool: &DbPool, id: i64) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let deleted = conn
+        .execute("DELETE FROM journal_trades WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if deleted == 0 {
+        return Err(format!("Unknown journal trade {}", id));
+    }
+    Ok(())
+}
+
+fn stats<'a>(trades: impl Iterator<Item = &'a JournalTrade>) -> JournalStats {
+    let mut stats = JournalStats::default();
+    let (mut gross_profit, mut gross_loss) = (0.0, 0.0);
+    for trade in trades {
+        stats.trades += 1;
+        match trade.pnl {
+            None => stats.open += 1,
+            Some(pnl) if pnl > 0.0 => {
+                stats.winners += 1;
+                gross_profit += pnl;
+            }
+            Some(pnl) => {
+                stats.losers += 1;
+                gross_loss -= pnl;
+            }
+        }
+    }
+    let closed = stats.winners + stats.losers;
+    stats.total_pnl = gross_profit - gross_loss;
+    stats.win_rate = (closed > 0).then(|| stats.winners as f64 / closed as f64);
+    stats.avg_win = (stats.winners > 0).then(|| gross_profit / stats.winners as f64);
+    stats.avg_loss = (stats.losers > 0).then(|| -gross_loss / stats.losers as f64);
+    stats.profit_factor = (gross_loss > 0.0).then(|| gross_profit / gross_loss);
+    stats
+}
+
+/// Statistics over `trades`, overall and split by whether they link an anomaly.
+pub fn journal_summary(trades: &[JournalTrade]) -> JournalSummary {
+    JournalSummary {
+        all: stats(trades.iter()),
+        linked: stats(trades.iter().filter(|t| t.trade.anomaly_id.is_some())),
+        unlinked: stats(trades.iter().filter(|t| t.trade.anomaly_id.is_none())),
+    }
+}
+
+/// Logged trades matching `filter`, most recently opened first.
+#[tauri::command]
+pub async fn journal_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    filter: Option<JournalFilter>,
+) -> Result<Vec<JournalTrade>, String> {
+    let filter = filter.unwrap_or_default();
+    db::run_blocking(&workspace.pool(), move |pool| journal_list_db(pool, &filter)).await
+}
+
+#[tauri::command]
+pub async fn journal_get(workspace: tauri::State<'_, WorkspaceDb>, id: i64) -> Result<JournalTrade, String> {
+    db::run_blocking(&workspace.pool(), move |pool| journal_get_db(pool, id)).await
+}
+
+/// Log a real-world trade, optionally linked to the anomaly it acted on.
+#[tauri::command]
+pub async fn journal_add(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    trade: JournalTradeInput,
+) -> Result<JournalTrade, String> {
+    db::run_blocking(&workspace.pool(), move |pool| journal_add_db(pool, trade, now_millis())).await
+}
+
+#[tauri::command]
+pub async fn journal_update(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: i64,
+    trade: JournalTradeInput,
+) -> Result<JournalTrade, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        journal_update_db(pool, id, trade, now_millis())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn journal_delete(workspace: tauri::State<'_, WorkspaceDb>, id: i64) -> Result<(), String> {
+    db::run_blocking(&workspace.pool(), move |pool| journal_delete_db(pool, id)).await
+}
+
+/// Win rate, P&L and profit factor of the trades matching `filter`, overall
+/// and split by whether they acted on an anomaly.
+#[tauri::command]
+pub async fn journal_summary_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    filter: Option<JournalFilter>,
+) -> Result<JournalSummary, String> {
+    let filter = filter.unwrap_or_default();
+    let trades = db::run_blocking(&workspace.pool(), move |pool| journal_list_db(pool, &filter)).await?;
+    Ok(journal_summary(&trades))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    fn trade(symbol: &str, side: JournalSide, entry: f64, exit: Option<f64>) -> JournalTradeInput {
+        JournalTradeInput {
+            symbol: symbol.to_string(),
+            side,
+            qty: 10.0,
+            entry_price: entry,
+            exit_price: exit,
+            fees: 1.0,
+            opened_at: 1_000,
+            closed_at: exit.map(|_| 2_000),
+            anomaly_id: None,
+            notes: String::new(),
+            tags: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn journal_crud_and_summary() {
+        let pool = test_pool();
+        let mut linked = trade(" aapl ", JournalSide::Long, 100.0, Some(110.0));
+        linked.anomaly_id = Some("missing".to_string());
+        assert!(journal_add_db(&pool, linked.clone(), 5)
+            .unwrap_err()
+            .contains("Unknown anomaly"));
+        let conn = pool.get().unwrap();
+        conn.execute(
+            "INSERT INTO anomalies (id, severity, source, timestamp, description, metrics, pre_screen_score, session_id)
+             VALUES ('a-1', 'high', 'test', 1000, 'spike', '{}', 0.9, 's-1')",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        linked.anomaly_id = Some("a-1".to_string());
+        linked.tags = vec!["earnings".to_string(), " earnings ".to_string(), String::new()];
+        let added = journal_add_db(&pool, linked, 5).unwrap();
+        assert_eq!(added.trade.symbol, "AAPL");
+        assert_eq!(added.trade.tags, ["earnings"]);
+        assert_eq!(added.pnl, Some(99.0));
+
+        let short = journal_add_db(&pool, trade("TSLA", JournalSide::Short, 200.0, Some(210.0)), 6).unwrap();
+        assert_eq!(short.pnl, Some(-101.0));
+        let open = journal_add_db(&pool, trade("MSFT", JournalSide::Long, 400.0, None), 7).unwrap();
+        assert_eq!(open.pnl, None);
+
+        let mut half_closed = trade("MSFT", JournalSide::Long, 400.0, None);
+        half_closed.exit_price = Some(410.0);
+        assert!(journal_update_db(&pool, open.id, half_closed, 8).is_err());
+        let closed =
+            journal_update_db(&pool, open.id, trade("MSFT", JournalSide::Long, 400.0, Some(410.0)), 8).unwrap();
+        assert_eq!((closed.pnl, closed.updated_at), (Some(99.0), Some(8)));
+
+        let tagged = JournalFilter {
+            tag: Some("earnings".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(journal_list_db(&pool, &tagged).unwrap().len(), 1);
+        let summary = journal_summary(&journal_list_db(&pool, &JournalFilter::default()).unwrap());
+        assert_eq!((summary.all.trades, summary.all.winners, summary.all.losers), (3, 2, 1));
+        assert_eq!(summary.all.total_pnl, 97.0);
+        assert_eq!(summary.all.profit_factor, Some(198.0 / 101.0));
+        assert_eq!(summary.linked.trades, 1);
+        assert_eq!(summary.unlinked.win_rate, Some(0.5));
+
+        journal_delete_db(&pool, short.id).unwrap();
+        assert!(journal_delete_db(&pool, short.id).is_err());
+        assert!(journal_get_db(&pool, short.id).is_err());
+    }
+}
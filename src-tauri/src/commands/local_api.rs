@@ -0,0 +1,43 @@
+use crate::commands::config::config_effective_db;
+use crate::local_api::{ensure_token, local_api_config, LocalApiServer, LocalApiStatus};
+use crate::loopback::generate_token;
+use crate::workspace::WorkspaceDb;
+
+#[tauri::command]
+pub fn local_api_status(local_api: tauri::State<'_, LocalApiServer>) -> LocalApiStatus {
+    local_api.status()
+}
+
+/// Serve the read-only API on the configured localhost port, even if
+/// `localApi.enabled` is off.
+#[tauri::command]
+pub fn local_api_start(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    local_api: tauri::State<'_, LocalApiServer>,
+) -> Result<LocalApiStatus, String> {
+    let config = local_api_config(&config_effective_db(&workspace.pool())?);
+    local_api.start(app, config.port, ensure_token()?)
+}
+
+/// Stop the local API listener. Returns whether one was running.
+#[tauri::command]
+pub fn local_api_stop(local_api: tauri::State<'_, LocalApiServer>) -> bool {
+    local_api.stop()
+}
+
+/// The bearer token scripts must send, created on first use.
+#[tauri::command]
+pub fn local_api_token_get() -> Result<String, String> {
+    ensure_token()
+}
+
+/// Replace the bearer token. A running listener switches to it at once, so
+/// the old one stops working immediately.
+#[tauri::command]
+pub fn local_api_token_rotate(local_api: tauri::State<'_, LocalApiServer>) -> Result<String, String> {
+    let token = generate_token()?;
+    crate::keychain::local_api_token_set(&token)?;
+    local_api.set_token(token.clone());
+    Ok(token)
+}
@@ -0,0 +1,37 @@
+use crate::app_logs::{self, AppLogLine};
+use crate::crash::{self, LastCrash};
+
+/// Default number of log lines returned by `logs_tail`.
+const DEFAULT_LOG_LINES: usize = 200;
+
+/// Replace the log filter (`RUST_LOG` syntax, e.g. `finwatch=debug`) without
+/// restarting. Returns the filter now in effect.
+#[tauri::command]
+pub fn log_level_set(filter: String) -> Result<String, String> {
+    app_logs::set_filter(&filter)
+}
+
+/// The log filter in effect.
+#[tauri::command]
+pub fn log_level_get() -> Option<String> {
+    app_logs::current_filter()
+}
+
+/// Read the most recent app log lines from `~/.finwatch/logs/app/`.
+#[tauri::command]
+pub fn logs_tail(lines: Option<usize>) -> Result<Vec<AppLogLine>, String> {
+    app_logs::tail(&app_logs::app_logs_dir(), lines.unwrap_or(DEFAULT_LOG_LINES))
+}
+
+/// The newest crash record, so the UI can say finwatch recovered from a crash
+/// and offer the diagnostics. `seen` is set once dismissed.
+#[tauri::command]
+pub fn crash_report_get_last() -> Result<Option<LastCrash>, String> {
+    crash::last_crash(&crash::crashes_dir())
+}
+
+/// Stop announcing the newest crash record.
+#[tauri::command]
+pub fn crash_report_dismiss() -> Result<(), String> {
+    crash::dismiss(&crash::crashes_dir())
+}
@@ -0,0 +1,311 @@
+use std::path::PathBuf;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::bridge::SidecarBridge;
+use crate::commands::activity::{activity_config, activity_prune_db};
+use crate::commands::agent::load_app_config;
+use crate::commands::ticks::{tick_recording_config, ticks_prune_db};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::tasks::TaskOutcome;
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// `config` table key holding the last maintenance report.
+const LAST_RUN_KEY: &str = "maintenance_last_run";
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// The `maintenance` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MaintenanceConfig {
+    /// Run maintenance automatically while the agent is idle.
+    pub enabled: bool,
+    /// Minimum time between automatic runs.
+    pub interval_hours: u64,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: 24,
+        }
+    }
+}
+
+/// Result of one maintenance pass.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    /// Main database file plus WAL before maintenance.
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub wal_before_bytes: u64,
+    pub reclaimed_bytes: u64,
+    pub duration_ms: u64,
+    /// Epoch millis when the pass finished.
+    pub ran_at: i64,
+}
+
+fn db_file_path(pool: &DbPool) -> Result<Option<PathBuf>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let file: String = conn
+        .query_row("PRAGMA database_list", [], |row| row.get(2))
+        .map_err(|e| e.to_string())?;
+    Ok((!file.is_empty()).then(|| PathBuf::from(file)))
+}
+
+fn file_size(path: &std::path::Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Sizes of the main database file and its `-wal` file.
+fn db_sizes(path: Option<&std::path::Path>) -> (u64, u64) {
+    match path {
+        Some(path) => {
+            let mut wal = path.as_os_str().to_owned();
+            wal.push("-wal");
+            (file_size(path), file_size(std::path::Path::new(&wal)))
+        }
+        None => (0, 0),
+    }
+}
+
+/// Checkpoint and truncate the WAL, then `VACUUM` and `ANALYZE`.
+pub fn db_maintenance_db(pool: &DbPool) -> Result<MaintenanceReport, String> {
+    let started = Instant::now();
+    let path = db_file_path(pool)?;
+    let (main_before, wal_before) = db_sizes(path.as_deref());
+
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE); VACUUM; ANALYZE;")
+        .map_err(|e| format!("Database maintenance failed: {}", e))?;
+    // VACUUM rewrites through the WAL; fold that back into the main file
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")
+        .map_err(|e| format!("Database maintenance failed: {}", e))?;
+
+    let (main_after, wal_after) = db_sizes(path.as_deref());
+    let size_before = main_before + wal_before;
+    let size_after = main_after + wal_after;
+    let report = MaintenanceReport {
+        size_before_bytes: size_before,
+        size_after_bytes: size_after,
+        wal_before_bytes: wal_before,
+        reclaimed_bytes: size_before.saturating_sub(size_after),
+        duration_ms: started.elapsed().as_millis() as u64,
+        ran_at: now_millis() as i64,
+    };
+    record_last_run(pool, &report)?;
+    Ok(report)
+}
+
+fn record_last_run(pool: &DbPool, report: &MaintenanceReport) -> Result<(), String> {
+    let json = serde_json::to_string(report).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![LAST_RUN_KEY, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// The report from the most recent maintenance pass, if any.
+pub fn last_run_db(pool: &DbPool) -> Result<Option<MaintenanceReport>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM config WHERE key = ?1", [LAST_RUN_KEY], |row| {
+            row.get(0)
+        })
+        .ok();
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+}
+
+/// Parse the `maintenance` section of the app config.
+pub fn maintenance_config(app_config: &serde_json::Value) -> MaintenanceConfig {
+    app_config
+        .get("maintenance")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Whether an automatic run is due at `now` (epoch millis).
+pub fn is_due(config: &MaintenanceConfig, last_run: Option<&MaintenanceReport>, now: i64) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    let interval_ms = config.interval_hours.saturating_mul(3_600_000) as i64;
+    last_run.is_none_or(|r| now - r.ran_at >= interval_ms)
+}
+
+/// Scheduled task: run maintenance when it is due and the agent is idle (not
+/// running, or no requests in flight).
+pub fn maintenance_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let config = maintenance_config(&load_app_config(&pool)?);
+    let last_run = last_run_db(&pool).ok().flatten();
+    if !is_due(&config, last_run.as_ref(), now_millis() as i64) {
+        return Ok(TaskOutcome::Skipped);
+    }
+    let idle = app
+        .try_state::<SidecarBridge>()
+        .is_none_or(|bridge| !bridge.is_running() || bridge.metrics_snapshot().pending == 0);
+    if !idle {
+        tracing::debug!("Agent busy, deferring database maintenance");
+        return Ok(TaskOutcome::Skipped);
+    }
+    let report = db_maintenance_db(&pool)?;
+    tracing::info!(
+        reclaimed_bytes = report.reclaimed_bytes,
+        duration_ms = report.duration_ms,
+        "Scheduled database maintenance complete"
+    );
+    let _ = emit_event(app, event_names::DB_MAINTENANCE, report);
+    Ok(TaskOutcome::Ran)
+}
+
+/// Delete recorded ticks and agent activity older than their configured
+/// retention windows. Returns the number of rows removed.
+pub fn retention_prune_db(pool: &DbPool, app_config: &serde_json::Value, now_ms: u64) -> Result<usize, String> {
+    let ticks_days = tick_recording_config(app_config).retention_days;
+    let activity_days = activity_config(app_config).retention_days;
+    let ticks = ticks_prune_db(pool, now_ms.saturating_sub(ticks_days.saturating_mul(DAY_MS)))?;
+    let activity = activity_prune_db(pool, now_ms.saturating_sub(activity_days.saturating_mul(DAY_MS)))?;
+    Ok(ticks + activity)
+}
+
+/// Scheduled task: prune retained data even while nothing new is recorded.
+pub fn retention_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let pruned = retention_prune_db(&pool, &load_app_config(&pool)?, now_millis())?;
+    if pruned == 0 {
+        return Ok(TaskOutcome::Skipped);
+    }
+    tracing::debug!(pruned, "Pruned data past retention");
+    Ok(TaskOutcome::Ran)
+}
+
+/// Run database maintenance now and report the reclaimed space.
+#[tauri::command]
+pub async fn db_maintenance(workspace: tauri::State<'_, WorkspaceDb>) -> Result<MaintenanceReport, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, db_maintenance_db).await
+}
+
+/// The report from the most recent maintenance pass.
+#[tauri::command]
+pub async fn db_maintenance_last_run(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Option<MaintenanceReport>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, last_run_db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn maintenance_reclaims_deleted_rows() {
+        let (_dir, pool) = test_pool();
+        {
+            let conn = pool.get().unwrap();
+            let payload = "x".repeat(2000);
+            for i in 0..500 {
+                conn.execute(
+                    "INSERT INTO anomalies (id, severity, source, timestamp, description, metrics, pre_screen_score, session_id)
+                     VALUES (?1, 'low', 'test', ?2, ?3, '{}', 0.1, 's1')",
+                    rusqlite::params![format!("a{}", i), i, payload],
+                )
+                .unwrap();
+            }
+            conn.execute("DELETE FROM anomalies", []).unwrap();
+        }
+
+        let report = db_maintenance_db(&pool).unwrap();
+        assert!(report.size_before_bytes > report.size_after_bytes);
+        assert_eq!(
+            report.reclaimed_bytes,
+            report.size_before_bytes - report.size_after_bytes
+        );
+        assert_eq!(last_run_db(&pool).unwrap().unwrap().ran_at, report.ran_at);
+    }
+
+    #[test]
+    fn retention_prunes_ticks_and_activity_by_their_own_windows() {
+        let (_dir, pool) = test_pool();
+        let tick = crate::types::data::DataTick {
+            source_id: "alpaca".to_string(),
+            timestamp: 1_000,
+            symbol: Some("NET".to_string()),
+            metrics: Default::default(),
+            metadata: Default::default(),
+            raw: None,
+        };
+        crate::commands::ticks::ticks_insert_db(&pool, &tick).unwrap();
+        let config = serde_json::json!({"ticks": {"retentionDays": 1}, "activity": {"retentionDays": 30}});
+        assert_eq!(retention_prune_db(&pool, &config, 1_000 + DAY_MS / 2).unwrap(), 0);
+        assert_eq!(retention_prune_db(&pool, &config, 1_000 + 2 * DAY_MS).unwrap(), 1);
+    }
+
+    #[test]
+    fn last_run_is_none_before_first_pass() {
+        let (_dir, pool) = test_pool();
+        assert!(last_run_db(&pool).unwrap().is_none());
+    }
+
+    #[test]
+    fn maintenance_does_not_clobber_main_config() {
+        let (_dir, pool) = test_pool();
+        crate::commands::config::config_set_db(&pool, r#"{"model":"m"}"#).unwrap();
+        db_maintenance_db(&pool).unwrap();
+        assert_eq!(
+            crate::commands::config::config_get_db(&pool).unwrap(),
+            r#"{"model":"m"}"#
+        );
+    }
+
+    #[test]
+    fn config_defaults_to_disabled_daily() {
+        let config = maintenance_config(&serde_json::json!({}));
+        assert_eq!(config, MaintenanceConfig::default());
+        let config = maintenance_config(&serde_json::json!({"maintenance": {"enabled": true}}));
+        assert!(config.enabled);
+        assert_eq!(config.interval_hours, 24);
+    }
+
+    #[test]
+    fn due_only_when_enabled_and_interval_elapsed() {
+        let enabled = MaintenanceConfig {
+            enabled: true,
+            interval_hours: 1,
+        };
+        let last = MaintenanceReport {
+            size_before_bytes: 0,
+            size_after_bytes: 0,
+            wal_before_bytes: 0,
+            reclaimed_bytes: 0,
+            duration_ms: 0,
+            ran_at: 1_000,
+        };
+        assert!(!is_due(&MaintenanceConfig::default(), None, 0));
+        assert!(is_due(&enabled, None, 0));
+        assert!(!is_due(&enabled, Some(&last), 1_000 + 3_599_999));
+        assert!(is_due(&enabled, Some(&last), 1_000 + 3_600_000));
+    }
+}
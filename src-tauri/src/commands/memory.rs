@@ -1,7 +1,325 @@
-use crate::types::memory::SearchResult;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
 
+use crate::bridge::SidecarBridge;
+use crate::bridge_error::BridgeError;
+use crate::commands::embeddings::embed_query;
+use crate::db::{self, DbPool};
+use crate::embeddings::LocalEmbedder;
+use crate::time::now_millis;
+use crate::types::memory::{
+    MemoryCompaction, MemoryCompactionResult, MemoryEntry, MemoryImportResult, MemoryMergeStrategy, MemorySearchFilter,
+    SearchResult,
+};
+use crate::workspace::WorkspaceDb;
+
+/// Cosine similarity at or above which `memory_compact` merges entries by default.
+pub const DEFAULT_COMPACT_THRESHOLD: f64 = 0.92;
+/// Default number of runs returned by `memory_compactions`.
+const COMPACTIONS_DEFAULT_LIMIT: u32 = 20;
+/// Default number of results returned by `memory_search`.
+const SEARCH_DEFAULT_LIMIT: u32 = 10;
+
+/// Search the agent's memory for `query` among entries matching `filter`. The
+/// filter is applied in the agent's queries, so `limit` counts matching
+/// entries only. A blank query lists matching entries newest first. When local
+/// embeddings are ready the query is embedded here for a hybrid search.
+#[tauri::command]
+pub async fn memory_search(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    embedder: tauri::State<'_, LocalEmbedder>,
+    query: String,
+    filter: Option<MemorySearchFilter>,
+    limit: Option<u32>,
+) -> Result<Vec<SearchResult>, BridgeError> {
+    let filter = filter.unwrap_or_default();
+    filter.validate()?;
+    let embedding = embed_query(&workspace.pool(), &embedder, &query).await?;
+    let params = serde_json::json!({
+        "query": query,
+        "filter": filter,
+        "limit": limit.unwrap_or(SEARCH_DEFAULT_LIMIT),
+        "embedding": embedding,
+    });
+    let result = bridge.call("memory:search", Some(params))?;
+    serde_json::from_value(result.get("results").cloned().unwrap_or_default())
+        .map_err(|e| format!("Invalid memory:search response: {}", e).into())
+}
+
+/// Write `entries` to `path` as JSONL, one entry per line.
+pub fn memory_write_jsonl(path: &Path, entries: &[MemoryEntry]) -> Result<(), String> {
+    let file = std::fs::File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut writer = BufWriter::new(file);
+    for entry in entries {
+        serde_json::to_writer(&mut writer, entry).map_err(|e| e.to_string())?;
+        writer.write_all(b"\n").map_err(|e| e.to_string())?;
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Read the entries of a JSONL file written by `memory_write_jsonl`. Blank
+/// lines are skipped; any other unparseable line fails the whole read.
+pub fn memory_read_jsonl(path: &Path) -> Result<Vec<MemoryEntry>, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry =
+            serde_json::from_str(&line).map_err(|e| format!("Invalid memory entry on line {}: {}", i + 1, e))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Dump the agent's memory entries, embeddings included, to `path` as JSONL.
+/// Returns how many entries were written.
+#[tauri::command]
+pub async fn memory_export(bridge: tauri::State<'_, SidecarBridge>, path: String) -> Result<usize, BridgeError> {
+    let result = bridge.call("memory:export", None)?;
+    let entries: Vec<MemoryEntry> = serde_json::from_value(result.get("entries").cloned().unwrap_or_default())
+        .map_err(|e| format!("Invalid memory:export response: {}", e))?;
+    memory_write_jsonl(Path::new(&path), &entries)?;
+    tracing::info!(count = entries.len(), path = %path, "Exported agent memory");
+    Ok(entries.len())
+}
+
+/// Load memory entries from a JSONL file at `path` into the agent. Entries
+/// whose id already exists are kept or replaced per `merge_strategy`.
 #[tauri::command]
-pub fn memory_search(query: String) -> Vec<SearchResult> {
-    let _ = query;
-    Vec::new()
+pub async fn memory_import(
+    bridge: tauri::State<'_, SidecarBridge>,
+    path: String,
+    merge_strategy: Option<MemoryMergeStrategy>,
+) -> Result<MemoryImportResult, BridgeError> {
+    let entries = memory_read_jsonl(Path::new(&path))?;
+    let params = serde_json::json!({
+        "entries": entries,
+        "strategy": merge_strategy.unwrap_or_default(),
+    });
+    let result = bridge.call("memory:import", Some(params))?;
+    let imported: MemoryImportResult =
+        serde_json::from_value(result).map_err(|e| format!("Invalid memory:import response: {}", e))?;
+    tracing::info!(imported = imported.imported, skipped = imported.skipped, path = %path, "Imported agent memory");
+    Ok(imported)
+}
+
+/// Record a `memory_compact` run, failed or not.
+pub fn memory_compaction_record_db(
+    pool: &DbPool,
+    started_at: u64,
+    finished_at: u64,
+    threshold: f64,
+    summarize: bool,
+    outcome: Result<MemoryCompactionResult, String>,
+) -> Result<MemoryCompaction, String> {
+    let (result, error) = match outcome {
+        Ok(result) => (Some(result), None),
+        Err(e) => (None, Some(e)),
+    };
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO memory_compactions
+            (started_at, finished_at, threshold, summarize, entries_before, entries_after, clusters, summarized, error)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            started_at as i64,
+            finished_at as i64,
+            threshold,
+            summarize,
+            result.map(|r| r.entries_before as i64),
+            result.map(|r| r.entries_after as i64),
+            result.map(|r| r.clusters as i64),
+            result.map(|r| r.summarized as i64),
+            error,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(MemoryCompaction {
+        id: conn.last_insert_rowid(),
+        started_at,
+        finished_at,
+        threshold,
+        summarize,
+        result,
+        error,
+    })
+}
+
+/// Recorded compaction runs, most recent first.
+pub fn memory_compactions_list_db(pool: &DbPool, limit: u32) -> Result<Vec<MemoryCompaction>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, started_at, finished_at, threshold, summarize,
+                    entries_before, entries_after, clusters, summarized, error
+             FROM memory_compactions ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([limit], |row| {
+            let counts: [Option<i64>; 4] = [row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?];
+            Ok(MemoryCompaction {
+                id: row.get(0)?,
+                started_at: row.get::<_, i64>(1)?.max(0) as u64,
+                finished_at: row.get::<_, i64>(2)?.max(0) as u64,
+                threshold: row.get(3)?,
+                summarize: row.get(4)?,
+                result: match counts {
+                    [Some(before), Some(after), Some(clusters), Some(summarized)] => Some(MemoryCompactionResult {
+                        entries_before: before.max(0) as u64,
+                        entries_after: after.max(0) as u64,
+                        clusters: clusters.max(0) as u64,
+                        summarized: summarized.max(0) as u64,
+                    }),
+                    _ => None,
+                },
+                error: row.get(9)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Ask the agent to merge memory entries whose embeddings are at least
+/// `threshold` cosine-similar, optionally having the LLM summarize each merged
+/// group. Every run is recorded, including failed ones.
+#[tauri::command]
+pub async fn memory_compact(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    threshold: Option<f64>,
+    summarize: Option<bool>,
+) -> Result<MemoryCompaction, BridgeError> {
+    let threshold = threshold.unwrap_or(DEFAULT_COMPACT_THRESHOLD);
+    if !(threshold > 0.0 && threshold <= 1.0) {
+        return Err(format!("Threshold must be above 0 and at most 1, got {}", threshold).into());
+    }
+    let summarize = summarize.unwrap_or(false);
+    let started_at = now_millis();
+    let outcome = bridge
+        .call(
+            "memory:compact",
+            Some(serde_json::json!({ "threshold": threshold, "summarize": summarize })),
+        )
+        .and_then(|v| {
+            serde_json::from_value::<MemoryCompactionResult>(v)
+                .map_err(|e| BridgeError::from(format!("Invalid memory:compact response: {}", e)))
+        });
+    let finished_at = now_millis();
+    let recorded = outcome.clone().map_err(String::from);
+    let run = db::run_blocking(&workspace.pool(), move |pool| {
+        memory_compaction_record_db(pool, started_at, finished_at, threshold, summarize, recorded)
+    })
+    .await?;
+    outcome?;
+    Ok(run)
+}
+
+/// Recorded `memory_compact` runs, most recent first.
+#[tauri::command]
+pub async fn memory_compactions(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    limit: Option<u32>,
+) -> Result<Vec<MemoryCompaction>, String> {
+    let limit = limit.unwrap_or(COMPACTIONS_DEFAULT_LIMIT);
+    db::run_blocking(&workspace.pool(), move |pool| memory_compactions_list_db(pool, limit)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compaction_runs_are_recorded_with_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+
+        let result = MemoryCompactionResult {
+            entries_before: 120,
+            entries_after: 95,
+            clusters: 18,
+            summarized: 18,
+        };
+        memory_compaction_record_db(&pool, 1_000, 4_000, 0.92, true, Ok(result)).unwrap();
+        let failed = memory_compaction_record_db(
+            &pool,
+            5_000,
+            5_010,
+            0.9,
+            false,
+            Err("Agent memory is not enabled".to_string()),
+        )
+        .unwrap();
+
+        let runs = memory_compactions_list_db(&pool, 10).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0], failed);
+        assert_eq!(runs[0].result, None);
+        assert_eq!(runs[1].result, Some(result));
+        assert!(runs[1].summarize);
+        assert_eq!(memory_compactions_list_db(&pool, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn search_filter_serializes_only_set_fields() {
+        let filter = MemorySearchFilter {
+            tags: vec!["AAPL".to_string()],
+            since: Some(1_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(&filter).unwrap(),
+            serde_json::json!({"tags": ["AAPL"], "since": 1000})
+        );
+        assert!(filter.validate().is_ok());
+        let reversed = MemorySearchFilter {
+            since: Some(2),
+            until: Some(1),
+            ..Default::default()
+        };
+        assert!(reversed.validate().is_err());
+    }
+
+    #[test]
+    fn jsonl_round_trips_entries_with_embeddings() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory.jsonl");
+        let entries = vec![
+            MemoryEntry {
+                id: "m1".to_string(),
+                content: "NVDA gaps up after earnings".to_string(),
+                embedding: vec![0.25, -0.5, 1.0],
+                source: "file:2024-01-01-00-m1.md".to_string(),
+                timestamp: 1_704_067_200_000,
+                tags: vec!["earnings".to_string()],
+            },
+            MemoryEntry {
+                id: "m2".to_string(),
+                content: "line one\nline two".to_string(),
+                embedding: Vec::new(),
+                source: "manual".to_string(),
+                timestamp: 1_704_067_260_000,
+                tags: Vec::new(),
+            },
+        ];
+        memory_write_jsonl(&path, &entries).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+        let read = memory_read_jsonl(&path).unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].embedding, [0.25, -0.5, 1.0]);
+        assert_eq!(read[1].content, "line one\nline two");
+
+        std::fs::write(&path, "\n{\"id\":\"m1\"}\n").unwrap();
+        let err = memory_read_jsonl(&path).unwrap_err();
+        assert!(err.contains("line 2"), "{err}");
+    }
 }
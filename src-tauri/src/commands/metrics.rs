@@ -0,0 +1,13 @@
+use crate::metrics::{self, MetricsSnapshot};
+
+/// Counters, gauges, and latency histograms collected since startup.
+#[tauri::command]
+pub fn metrics_get() -> MetricsSnapshot {
+    metrics::global().snapshot()
+}
+
+/// The same metrics in the Prometheus text exposition format.
+#[tauri::command]
+pub fn metrics_prometheus() -> String {
+    metrics::global().render_prometheus()
+}
@@ -0,0 +1,15 @@
+use crate::db;
+use crate::migrations::{self, MigrationStatus};
+use crate::workspace::WorkspaceDb;
+
+/// Applied and available schema migrations of the active workspace.
+#[tauri::command]
+pub async fn migrations_status(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Vec<MigrationStatus>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, |pool| {
+        migrations::status(pool).map_err(|e| format!("Failed to read migrations: {}", e))
+    })
+    .await
+}
@@ -1,11 +1,43 @@
+pub mod activity;
 pub mod agent;
+pub mod analytics;
+pub mod alerts;
+pub mod alpaca_stream;
 pub mod assets;
+pub mod bridge;
 pub mod config;
 pub mod anomalies;
 pub mod credentials;
+pub mod embeddings;
+pub mod export;
+pub mod incidents;
+pub mod journal;
+pub mod local_api;
+pub mod logs;
+pub mod maintenance;
 pub mod memory;
+pub mod metrics;
+pub mod migrations;
+pub mod models;
+pub mod offline;
+pub mod orders;
+pub mod performance;
+pub mod portfolio;
+pub mod profiles;
+pub mod prompts;
+pub mod providers;
+pub mod schedule;
 pub mod sources;
+pub mod tasks;
+pub mod ticks;
+pub mod trading;
+pub mod usage;
 pub mod backtest;
+pub mod bars;
+pub mod calendar;
+pub mod watcher;
+pub mod webhook;
+pub mod workspace;
 
 #[cfg(test)]
 mod tests {
@@ -16,6 +48,7 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
         db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
         pool
     }
 
@@ -41,16 +74,17 @@ mod tests {
     #[test]
     fn config_update_merges_patch() {
         let pool = test_pool();
-        let initial = serde_json::json!({ "a": 1, "b": 2 });
+        let initial = serde_json::json!({ "model": "m", "monitor": { "analysisIntervalMs": 60000 } });
         config::config_set_db(&pool, &initial.to_string()).unwrap();
 
-        let patch = serde_json::json!({ "b": 99, "c": 3 });
+        let patch = serde_json::json!({ "monitor": { "maxCycleAgeMs": 120000 }, "feed": "sip" });
         let result = config::config_update_db(&pool, &patch.to_string()).unwrap();
         let parsed: serde_json::Value = serde_json::from_str(&result).unwrap();
 
-        assert_eq!(parsed["a"], 1);
-        assert_eq!(parsed["b"], 99);
-        assert_eq!(parsed["c"], 3);
+        assert_eq!(parsed["model"], "m");
+        assert_eq!(parsed["monitor"]["analysisIntervalMs"], 60000);
+        assert_eq!(parsed["monitor"]["maxCycleAgeMs"], 120000);
+        assert_eq!(parsed["feed"], "sip");
     }
 
     // agent_status now requires Tauri State<SidecarBridge>, tested via bridge module
@@ -68,6 +102,9 @@ mod tests {
             metrics: [("volume".to_string(), 5000000.0)].into(),
             pre_screen_score: 0.85,
             session_id: "cycle-001".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
         };
         anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
         let list = anomalies::anomalies_list_db(&pool, &None).unwrap();
@@ -88,6 +125,9 @@ mod tests {
             metrics: Default::default(),
             pre_screen_score: 0.3,
             session_id: "s1".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
         };
         anomalies::anomalies_insert_db(&pool, &a1).unwrap();
         a1.id = "anom-high".to_string();
@@ -100,6 +140,9 @@ mod tests {
             symbol: None,
             since: None,
             limit: None,
+            status: None,
+            include_snoozed: None,
+            incident_id: None,
         };
         let list = anomalies::anomalies_list_db(&pool, &Some(filter)).unwrap();
         assert_eq!(list.len(), 1);
@@ -119,6 +162,9 @@ mod tests {
             metrics: Default::default(),
             pre_screen_score: 0.5,
             session_id: "s1".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
         };
         anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
 
@@ -131,10 +177,206 @@ mod tests {
         anomalies::anomalies_feedback_db(&pool, &fb).unwrap();
     }
 
+    #[test]
+    fn snoozed_anomalies_are_hidden_until_the_snooze_ends() {
+        use crate::types::anomaly::{AnomalyFilter, AnomalyStatus};
+
+        let pool = test_pool();
+        let mut anomaly = crate::types::anomaly::Anomaly {
+            id: "anom-a".to_string(),
+            severity: crate::types::anomaly::Severity::Medium,
+            source: "test".to_string(),
+            symbol: None,
+            timestamp: 1000,
+            description: "test".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.5,
+            session_id: "s1".to_string(),
+            status: AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        };
+        anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
+        anomaly.id = "anom-b".to_string();
+        anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
+
+        let acked = anomalies::anomaly_status_set_db(&pool, "anom-a", AnomalyStatus::Acknowledged, None).unwrap();
+        assert_eq!(acked.status, AnomalyStatus::Acknowledged);
+        let far = 4_102_444_800_000; // 2100-01-01
+        let snoozed = anomalies::anomaly_snooze_db(&pool, "anom-b", far, 5_000).unwrap();
+        assert_eq!(snoozed.snoozed_until, Some(far));
+        assert!(anomalies::anomaly_snooze_db(&pool, "anom-b", 4_000, 5_000).is_err());
+        assert!(anomalies::anomaly_status_set_db(&pool, "missing", AnomalyStatus::Resolved, None).is_err());
+
+        let ids = |filter: Option<AnomalyFilter>| -> Vec<String> {
+            let mut ids: Vec<String> = anomalies::anomalies_list_db(&pool, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|a| a.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(None), ["anom-a"]);
+        let with_snoozed = AnomalyFilter {
+            include_snoozed: Some(true),
+            ..AnomalyFilter::default()
+        };
+        assert_eq!(ids(Some(with_snoozed)), ["anom-a", "anom-b"]);
+        let new_only = AnomalyFilter {
+            status: Some(vec![AnomalyStatus::New]),
+            ..AnomalyFilter::default()
+        };
+        assert!(ids(Some(new_only)).is_empty());
+
+        // An ended snooze lists the anomaly again; resolving clears the snooze
+        pool.get()
+            .unwrap()
+            .execute("UPDATE anomalies SET snoozed_until = 1 WHERE id = 'anom-b'", [])
+            .unwrap();
+        assert_eq!(ids(None), ["anom-a", "anom-b"]);
+        let resolved = anomalies::anomaly_status_set_db(&pool, "anom-b", AnomalyStatus::Resolved, Some(far)).unwrap();
+        assert_eq!(resolved.snoozed_until, None);
+    }
+
+    #[test]
+    fn anomaly_notes_add_edit_and_delete() {
+        let pool = test_pool();
+        let anomaly = crate::types::anomaly::Anomaly {
+            id: "anom-n".to_string(),
+            severity: crate::types::anomaly::Severity::Medium,
+            source: "test".to_string(),
+            symbol: None,
+            timestamp: 1000,
+            description: "test".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.5,
+            session_id: "s1".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        };
+        anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
+
+        let first = anomalies::anomaly_note_add_db(&pool, "anom-n", "  Checked the feed  ", 2000).unwrap();
+        assert_eq!(first.body, "Checked the feed");
+        assert_eq!(first.updated_at, None);
+        let second = anomalies::anomaly_note_add_db(&pool, "anom-n", "Earnings leak", 3000).unwrap();
+        assert!(anomalies::anomaly_note_add_db(&pool, "anom-n", "   ", 3000).is_err());
+        assert!(anomalies::anomaly_note_add_db(&pool, "missing", "note", 3000).is_err());
+
+        let edited = anomalies::anomaly_note_edit_db(&pool, first.id, "Feed was fine", 4000).unwrap();
+        assert_eq!(edited.body, "Feed was fine");
+        assert_eq!(edited.created_at, 2000);
+        assert_eq!(edited.updated_at, Some(4000));
+
+        let notes = anomalies::anomaly_notes_list_db(&pool, "anom-n").unwrap();
+        assert_eq!(notes, vec![edited, second.clone()]);
+
+        anomalies::anomaly_note_delete_db(&pool, first.id).unwrap();
+        assert!(anomalies::anomaly_note_delete_db(&pool, first.id).is_err());
+        assert!(anomalies::anomaly_note_edit_db(&pool, first.id, "gone", 5000).is_err());
+        assert_eq!(anomalies::anomaly_notes_list_db(&pool, "anom-n").unwrap(), vec![second]);
+    }
+
+    #[test]
+    fn severity_override_keeps_the_detected_severity() {
+        use crate::types::anomaly::{AnomalyFilter, Severity};
+
+        let pool = test_pool();
+        let anomaly = crate::types::anomaly::Anomaly {
+            id: "anom-s".to_string(),
+            severity: Severity::Low,
+            source: "test".to_string(),
+            symbol: None,
+            timestamp: 1000,
+            description: "test".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.5,
+            session_id: "s1".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        };
+        anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
+
+        let raised = anomalies::anomaly_severity_set_db(&pool, "anom-s", Some(Severity::Critical)).unwrap();
+        assert_eq!(raised.severity, Severity::Critical);
+        assert_eq!(raised.original_severity, Some(Severity::Low));
+        assert!(anomalies::anomaly_severity_set_db(&pool, "missing", Some(Severity::High)).is_err());
+
+        let by_severity = |severity: Severity| {
+            let filter = AnomalyFilter {
+                severity: Some(vec![severity]),
+                ..AnomalyFilter::default()
+            };
+            anomalies::anomalies_list_db(&pool, &Some(filter)).unwrap().len()
+        };
+        assert_eq!(by_severity(Severity::Critical), 1);
+        assert_eq!(by_severity(Severity::Low), 0);
+
+        // Overriding back to the detected severity clears the override
+        let restored = anomalies::anomaly_severity_set_db(&pool, "anom-s", Some(Severity::Low)).unwrap();
+        assert_eq!(restored.severity, Severity::Low);
+        assert_eq!(restored.original_severity, None);
+        anomalies::anomaly_severity_set_db(&pool, "anom-s", Some(Severity::High)).unwrap();
+        let cleared = anomalies::anomaly_severity_set_db(&pool, "anom-s", None).unwrap();
+        assert_eq!(cleared.severity, Severity::Low);
+        assert_eq!(cleared.original_severity, None);
+    }
+
+    #[test]
+    fn heatmap_counts_anomalies_per_bucket() {
+        use crate::types::anomaly::{HeatmapBucket, HeatmapCell};
+
+        let pool = test_pool();
+        let hour = 3_600_000;
+        let day = 24 * hour;
+        let mut anomaly = crate::types::anomaly::Anomaly {
+            id: String::new(),
+            severity: crate::types::anomaly::Severity::Low,
+            source: "test".to_string(),
+            symbol: Some("NET".to_string()),
+            timestamp: 0,
+            description: "test".to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.5,
+            session_id: "s1".to_string(),
+            status: crate::types::anomaly::AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        };
+        // 2024-01-01T00:00Z plus offsets
+        let base = 1_704_067_200_000;
+        for (i, (offset, symbol)) in [(hour, "NET"), (hour + 60_000, "NET"), (day + 2 * hour, "AAPL"), (0, "NET")]
+            .into_iter()
+            .enumerate()
+        {
+            anomaly.id = format!("anom-{}", i);
+            anomaly.timestamp = base + offset;
+            anomaly.symbol = Some(symbol.to_string());
+            anomalies::anomalies_insert_db(&pool, &anomaly).unwrap();
+        }
+        let cell = |day: &str, hour: Option<u32>, symbol: Option<&str>, count: u32| HeatmapCell {
+            day: day.to_string(),
+            hour,
+            symbol: symbol.map(str::to_string),
+            count,
+        };
+
+        let by_hour = anomalies::anomalies_heatmap_db(&pool, Some(base + hour), HeatmapBucket::DayHour).unwrap();
+        assert_eq!(by_hour, vec![cell("2024-01-01", Some(1), None, 2), cell("2024-01-02", Some(2), None, 1)]);
+
+        let by_symbol = anomalies::anomalies_heatmap_db(&pool, None, HeatmapBucket::SymbolDay).unwrap();
+        assert_eq!(
+            by_symbol,
+            vec![cell("2024-01-02", None, Some("AAPL"), 1), cell("2024-01-01", None, Some("NET"), 3)]
+        );
+    }
+
     #[test]
     fn sources_health_set_and_get() {
         let pool = test_pool();
-        crate::migrations::run_pending(&pool).unwrap();
         let health = crate::types::data::SourceHealth {
             source_id: "yahoo".to_string(),
             status: crate::types::data::SourceHealthStatus::Healthy,
@@ -0,0 +1,361 @@
+use serde::{Deserialize, Serialize};
+
+use crate::commands::agent::llm_key;
+use crate::commands::offline::is_offline;
+use crate::commands::providers::KNOWN_PROVIDERS;
+use crate::commands::usage::{model_price, usage_config, ModelPrice, UsageConfig};
+use crate::db::{self, DbPool};
+use crate::http;
+use crate::workspace::WorkspaceDb;
+
+const ANTHROPIC_MODELS_URL: &str = "https://api.anthropic.com/v1/models";
+const OPENROUTER_MODELS_URL: &str = "https://openrouter.ai/api/v1/models";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// How long a provider's model list is served before it is refetched.
+const MODELS_CACHE_TTL_SECS: i64 = 24 * 3600;
+
+/// A model offered by an LLM provider, as returned by `models_list`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    pub provider_id: String,
+    /// The id to put in the `model` setting.
+    pub id: String,
+    pub name: String,
+    /// Maximum input tokens, when the provider reports it.
+    pub context_window: Option<u64>,
+    /// The provider's price, or for Anthropic the `usage` price table's.
+    pub pricing: Option<ModelPrice>,
+    /// When the list was fetched (UTC, SQLite datetime).
+    #[serde(default)]
+    pub fetched_at: Option<String>,
+}
+
+/// Replace `provider_id`'s cached models, keeping the provider's order.
+pub fn models_cache_set_db(pool: &DbPool, provider_id: &str, models: &[ModelInfo]) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM llm_models WHERE provider_id = ?1", [provider_id])
+        .map_err(|e| e.to_string())?;
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT OR REPLACE INTO llm_models
+                    (provider_id, id, name, position, context_window, input_per_mtok, output_per_mtok)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )
+            .map_err(|e| e.to_string())?;
+        for (position, model) in models.iter().enumerate() {
+            insert
+                .execute(rusqlite::params![
+                    provider_id,
+                    model.id,
+                    model.name,
+                    position as i64,
+                    model.context_window.map(|c| c as i64),
+                    model.pricing.map(|p| p.input_per_mtok),
+                    model.pricing.map(|p| p.output_per_mtok),
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Cached models of `provider_id`, in the provider's order.
+pub fn models_cache_get_db(pool: &DbPool, provider_id: &str) -> Result<Vec<ModelInfo>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT provider_id, id, name, context_window, input_per_mtok, output_per_mtok, fetched_at
+             FROM llm_models WHERE provider_id = ?1 ORDER BY position",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([provider_id], |row| {
+            let input: Option<f64> = row.get(4)?;
+            let output: Option<f64> = row.get(5)?;
+            Ok(ModelInfo {
+                provider_id: row.get(0)?,
+                id: row.get(1)?,
+                name: row.get(2)?,
+                context_window: row.get::<_, Option<i64>>(3)?.map(|c| c.max(0) as u64),
+                pricing: input.zip(output).map(|(input_per_mtok, output_per_mtok)| ModelPrice {
+                    input_per_mtok,
+                    output_per_mtok,
+                }),
+                fetched_at: row.get(6)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Whether `provider_id`'s list is missing or older than `max_age_secs`.
+pub fn models_cache_is_stale(pool: &DbPool, provider_id: &str, max_age_secs: i64) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM llm_models WHERE provider_id = ?1 AND fetched_at > datetime('now', ?2)",
+            rusqlite::params![provider_id, format!("-{} seconds", max_age_secs)],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
+    Ok(count == 0)
+}
+
+/// Anthropic's `GET /v1/models` response.
+#[derive(Deserialize)]
+struct AnthropicModels {
+    data: Vec<AnthropicModel>,
+}
+
+#[derive(Deserialize)]
+struct AnthropicModel {
+    id: String,
+    display_name: String,
+    #[serde(default)]
+    max_input_tokens: Option<u64>,
+}
+
+/// Models from an Anthropic `GET /v1/models` body. Anthropic publishes no
+/// prices through the API, so they come from the `usage` price table.
+pub fn parse_anthropic_models(body: &str, prices: &UsageConfig) -> Result<Vec<ModelInfo>, String> {
+    let list: AnthropicModels =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse Anthropic models: {}", e))?;
+    Ok(list
+        .data
+        .into_iter()
+        .map(|m| ModelInfo {
+            provider_id: "anthropic".to_string(),
+            pricing: model_price(prices, &m.id),
+            id: m.id,
+            name: m.display_name,
+            context_window: m.max_input_tokens,
+            fetched_at: None,
+        })
+        .collect())
+}
+
+/// OpenRouter's `GET /api/v1/models` response.
+#[derive(Deserialize)]
+struct OpenRouterModels {
+    data: Vec<OpenRouterModel>,
+}
+
+#[derive(Deserialize)]
+struct OpenRouterModel {
+    id: String,
+    name: String,
+    #[serde(default)]
+    context_length: Option<u64>,
+    #[serde(default)]
+    pricing: Option<OpenRouterPricing>,
+}
+
+/// USD per token, as decimal strings.
+#[derive(Deserialize)]
+struct OpenRouterPricing {
+    prompt: String,
+    completion: String,
+}
+
+/// A per-token price string in USD per million tokens. OpenRouter marks
+/// variable-priced routers with negative prices.
+fn per_mtok(per_token: &str) -> Option<f64> {
+    let price: f64 = per_token.trim().parse().ok()?;
+    (price.is_finite() && price >= 0.0).then_some(price * 1_000_000.0)
+}
+
+/// Models from an OpenRouter `GET /api/v1/models` body.
+pub fn parse_openrouter_models(body: &str) -> Result<Vec<ModelInfo>, String> {
+    let list: OpenRouterModels =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse OpenRouter models: {}", e))?;
+    Ok(list
+        .data
+        .into_iter()
+        .map(|m| ModelInfo {
+            provider_id: "openrouter".to_string(),
+            pricing: m.pricing.and_then(|p| {
+                Some(ModelPrice {
+                    input_per_mtok: per_mtok(&p.prompt)?,
+                    output_per_mtok: per_mtok(&p.completion)?,
+                })
+            }),
+            id: m.id,
+            name: m.name,
+            context_window: m.context_length,
+            fetched_at: None,
+        })
+        .collect())
+}
+
+/// Fetch `provider`'s model list. Anthropic needs `key`; OpenRouter's list is public.
+async fn fetch_models(provider: &str, key: &str, prices: &UsageConfig) -> Result<Vec<ModelInfo>, String> {
+    let client = http::client()?;
+    let request = match provider {
+        "anthropic" => client
+            .get(ANTHROPIC_MODELS_URL)
+            .query(&[("limit", "1000")])
+            .header("x-api-key", key)
+            .header("anthropic-version", ANTHROPIC_VERSION),
+        _ => client.get(OPENROUTER_MODELS_URL),
+    };
+    let response = http::send(request)
+        .await
+        .map_err(|e| format!("Failed to fetch {} models: {}", provider, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{} models API error: {}", provider, response.status()));
+    }
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {} models: {}", provider, e))?;
+    match provider {
+        "anthropic" => parse_anthropic_models(&body, prices),
+        _ => parse_openrouter_models(&body),
+    }
+}
+
+/// `provider`'s models, cached for a day. Stale models are served while
+/// offline, without an Anthropic key, or when the refresh fails.
+async fn provider_models(pool: &DbPool, provider: &'static str, force: bool) -> Result<Vec<ModelInfo>, String> {
+    let (cached, fresh, key, prices) = db::run_blocking(pool, move |pool| {
+        let app_config = crate::commands::config::config_effective_db(pool)?;
+        let cached = models_cache_get_db(pool, provider)?;
+        let fresh = is_offline(pool) || (!force && !models_cache_is_stale(pool, provider, MODELS_CACHE_TTL_SECS)?);
+        Ok((
+            cached,
+            fresh,
+            llm_key(&app_config, provider)?,
+            usage_config(&app_config),
+        ))
+    })
+    .await?;
+    if fresh || (provider == "anthropic" && key.is_empty()) {
+        return Ok(cached);
+    }
+
+    match fetch_models(provider, &key, &prices).await {
+        Ok(models) => {
+            db::run_blocking(pool, move |pool| {
+                models_cache_set_db(pool, provider, &models)?;
+                models_cache_get_db(pool, provider)
+            })
+            .await
+        }
+        Err(e) if !cached.is_empty() => {
+            tracing::warn!(error = %e, provider, "Model list refresh failed, returning cached models");
+            Ok(cached)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Models of `provider`, or of every known provider, for the model picker.
+/// A provider whose list cannot be fetched is left out of the combined list.
+#[tauri::command]
+pub async fn models_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider: Option<String>,
+    force: Option<bool>,
+) -> Result<Vec<ModelInfo>, String> {
+    let pool = workspace.pool();
+    let force = force.unwrap_or(false);
+    if let Some(provider) = provider {
+        let Some(known) = KNOWN_PROVIDERS.iter().copied().find(|p| *p == provider) else {
+            return Err(format!(
+                "Unknown provider: '{}'. Must be one of: {}",
+                provider,
+                KNOWN_PROVIDERS.join(", ")
+            ));
+        };
+        return provider_models(&pool, known, force).await;
+    }
+    let mut models = Vec::new();
+    for &provider in KNOWN_PROVIDERS {
+        match provider_models(&pool, provider, force).await {
+            Ok(listed) => models.extend(listed),
+            Err(e) => tracing::warn!(error = %e, provider, "Failed to list models"),
+        }
+    }
+    Ok(models)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    #[test]
+    fn parses_both_provider_lists() {
+        let anthropic = parse_anthropic_models(
+            r#"{"data":[
+                {"type":"model","id":"claude-sonnet-4-5-20250929","display_name":"Claude Sonnet 4.5",
+                 "created_at":"2025-09-29T00:00:00Z"},
+                {"type":"model","id":"claude-next","display_name":"Claude Next","max_input_tokens":500000}
+            ],"has_more":false}"#,
+            &UsageConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(anthropic[0].name, "Claude Sonnet 4.5");
+        assert_eq!(anthropic[0].pricing.unwrap().input_per_mtok, 3.0);
+        assert_eq!(anthropic[1].pricing, None);
+        assert_eq!(anthropic[1].context_window, Some(500_000));
+
+        let openrouter = parse_openrouter_models(
+            r#"{"data":[
+                {"id":"anthropic/claude-haiku-4.5","name":"Anthropic: Claude Haiku 4.5","context_length":200000,
+                 "pricing":{"prompt":"0.000001","completion":"0.000005","request":"0"}},
+                {"id":"openrouter/auto","name":"Auto Router","context_length":2000000,
+                 "pricing":{"prompt":"-1","completion":"-1"}}
+            ]}"#,
+        )
+        .unwrap();
+        let haiku = openrouter[0].pricing.unwrap();
+        assert!((haiku.input_per_mtok - 1.0).abs() < 1e-9 && (haiku.output_per_mtok - 5.0).abs() < 1e-9);
+        assert_eq!(openrouter[0].context_window, Some(200_000));
+        assert_eq!(openrouter[1].pricing, None);
+        assert!(parse_openrouter_models("<html>").is_err());
+    }
+
+    #[test]
+    fn cache_keeps_provider_order_per_provider() {
+        let pool = test_pool();
+        let model = |provider: &str, id: &str| ModelInfo {
+            provider_id: provider.to_string(),
+            id: id.to_string(),
+            name: id.to_uppercase(),
+            context_window: Some(200_000),
+            pricing: None,
+            fetched_at: None,
+        };
+        assert!(models_cache_is_stale(&pool, "anthropic", 3600).unwrap());
+        models_cache_set_db(
+            &pool,
+            "anthropic",
+            &[model("anthropic", "z-new"), model("anthropic", "a-old")],
+        )
+        .unwrap();
+        models_cache_set_db(&pool, "openrouter", &[model("openrouter", "x")]).unwrap();
+        assert!(!models_cache_is_stale(&pool, "anthropic", 3600).unwrap());
+
+        let ids: Vec<String> = models_cache_get_db(&pool, "anthropic")
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, ["z-new", "a-old"]);
+
+        models_cache_set_db(&pool, "anthropic", &[model("anthropic", "only")]).unwrap();
+        assert_eq!(models_cache_get_db(&pool, "anthropic").unwrap().len(), 1);
+        assert_eq!(models_cache_get_db(&pool, "openrouter").unwrap().len(), 1);
+    }
+}
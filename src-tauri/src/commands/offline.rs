@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::bridge::SidecarBridge;
+use crate::commands::anomalies::anomalies_feedback_db;
+use crate::commands::config::{config_get_db, config_set_key_db, config_update_db, merge_json, mutate_config, set_key};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::anomaly::AnomalyFeedback;
+use crate::workspace::WorkspaceDb;
+
+/// `config` table key holding the offline switch.
+const OFFLINE_KEY: &str = "offline_mode";
+
+/// The persisted offline switch. While offline, nothing reaches the network:
+/// commands answer from the caches and mutations wait in the queue.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineMode {
+    pub offline: bool,
+    /// When the switch last changed, epoch millis.
+    pub changed_at: Option<u64>,
+}
+
+/// The switch with the number of operations waiting to replay.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineStatus {
+    pub offline: bool,
+    pub changed_at: Option<u64>,
+    pub queued: u64,
+}
+
+/// A mutation made while offline, replayed in order on reconnect.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QueuedOp {
+    #[serde(rename_all = "camelCase")]
+    Feedback { feedback: AnomalyFeedback },
+    /// A `config_update` patch.
+    #[serde(rename_all = "camelCase")]
+    ConfigUpdate { patch: serde_json::Value },
+    /// A `config_set_key` call.
+    #[serde(rename_all = "camelCase")]
+    ConfigSetKey { path: String, value: serde_json::Value },
+}
+
+impl QueuedOp {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            QueuedOp::Feedback { .. } => "feedback",
+            QueuedOp::ConfigUpdate { .. } => "configUpdate",
+            QueuedOp::ConfigSetKey { .. } => "configSetKey",
+        }
+    }
+
+    fn is_config(&self) -> bool {
+        !matches!(self, QueuedOp::Feedback { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedEntry {
+    pub id: i64,
+    pub op: QueuedOp,
+    /// Epoch millis.
+    pub queued_at: u64,
+    /// Failed replays so far.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// What one replay of the queue did.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub applied: u32,
+    /// Entries that failed and stay queued.
+    pub failed: u32,
+}
+
+/// The current switch; online if it was never set.
+pub fn offline_get_db(pool: &DbPool) -> Result<OfflineMode, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM config WHERE key = ?1", [OFFLINE_KEY], |row| {
+            row.get(0)
+        })
+        .ok();
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or_default())
+}
+
+/// Set the switch and return the stored state.
+pub fn offline_set_db(pool: &DbPool, offline: bool) -> Result<OfflineMode, String> {
+    let mode = OfflineMode {
+        offline,
+        changed_at: Some(now_millis()),
+    };
+    let json = serde_json::to_string(&mode).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![OFFLINE_KEY, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(mode)
+}
+
+/// Whether offline mode is on. An unreadable switch counts as online.
+pub fn is_offline(pool: &DbPool) -> bool {
+    offline_get_db(pool).is_ok_and(|m| m.offline)
+}
+
+/// An error for network calls attempted while offline.
+pub fn ensure_online(pool: &DbPool) -> Result<(), String> {
+    if is_offline(pool) {
+        return Err("Offline mode is on; turn it off to reach the network".to_string());
+    }
+    Ok(())
+}
+
+pub fn offline_status_db(pool: &DbPool) -> Result<OfflineStatus, String> {
+    let mode = offline_get_db(pool)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let queued: i64 = conn
+        .query_row("SELECT COUNT(*) FROM offline_queue", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+    Ok(OfflineStatus {
+        offline: mode.offline,
+        changed_at: mode.changed_at,
+        queued: queued as u64,
+    })
+}
+
+/// Queue `op` behind everything already queued.
+pub fn queue_push_db(pool: &DbPool, op: &QueuedOp) -> Result<i64, String> {
+    let json = serde_json::to_string(op).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO offline_queue (op, queued_at) VALUES (?1, ?2)",
+        rusqlite::params![json, now_millis() as i64],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Every queued operation, oldest first.
+pub fn queue_list_db(pool: &DbPool) -> Result<Vec<QueuedEntry>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id, op, queued_at, attempts, last_error FROM offline_queue ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, u32>(3)?,
+                row.get::<_, Option<String>>(4)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let (id, op, queued_at, attempts, last_error) = row.map_err(|e| e.to_string())?;
+        let op = serde_json::from_str(&op).map_err(|e| format!("Corrupt queued operation {}: {}", id, e))?;
+        entries.push(QueuedEntry {
+            id,
+            op,
+            queued_at: queued_at as u64,
+            attempts,
+            last_error,
+        });
+    }
+    Ok(entries)
+}
+
+/// Drop a queued operation. Returns `false` if there was none with `id`.
+pub fn queue_remove_db(pool: &DbPool, id: i64) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let removed = conn
+        .execute("DELETE FROM offline_queue WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+fn queue_fail_db(pool: &DbPool, id: i64, error: &str) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "UPDATE offline_queue SET attempts = attempts + 1, last_error = ?2 WHERE id = ?1",
+        rusqlite::params![id, error],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Whether any config change is waiting to replay.
+pub fn config_queued_db(pool: &DbPool) -> Result<bool, String> {
+    Ok(queue_list_db(pool)?.iter().any(|e| e.op.is_config()))
+}
+
+/// Apply a config op to `config` in memory.
+fn preview(config: &mut serde_json::Value, op: &QueuedOp) -> Result<(), String> {
+    match op {
+        QueuedOp::ConfigUpdate { patch } => merge_json(config, patch),
+        QueuedOp::ConfigSetKey { path, value } => set_key(config, path, value.clone())?,
+        QueuedOp::Feedback { .. } => {}
+    }
+    Ok(())
+}
+
+/// Queue an already-validated config op and return the stored config as it
+/// will read once the queue replays.
+pub fn queue_config_db(pool: &DbPool, op: QueuedOp) -> Result<String, String> {
+    let mut config: serde_json::Value = serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    for entry in queue_list_db(pool)? {
+        preview(&mut config, &entry.op)?;
+    }
+    preview(&mut config, &op)?;
+    queue_push_db(pool, &op)?;
+    tracing::info!(kind = op.kind(), "Queued config change while offline");
+    serde_json::to_string(&config).map_err(|e| e.to_string())
+}
+
+async fn apply(app: &AppHandle, op: QueuedOp) -> Result<(), String> {
+    let workspace = app.state::<WorkspaceDb>();
+    let bridge = app.state::<SidecarBridge>();
+    match op {
+        QueuedOp::Feedback { feedback } => {
+            db::run_blocking(&workspace.pool(), move |pool| anomalies_feedback_db(pool, &feedback)).await
+        }
+        QueuedOp::ConfigUpdate { patch } => mutate_config(app, &workspace, &bridge, move |pool| {
+            config_update_db(pool, &patch.to_string())
+        })
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string()),
+        QueuedOp::ConfigSetKey { path, value } => mutate_config(app, &workspace, &bridge, move |pool| {
+            config_set_key_db(pool, &path, value)
+        })
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string()),
+    }
+}
+
+/// Apply every queued operation in order. Failures stay queued with their
+/// error so the user can retry or discard them.
+pub async fn replay(app: &AppHandle) -> Result<ReplayReport, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let mut report = ReplayReport::default();
+    for entry in db::run_blocking(&pool, queue_list_db).await? {
+        let (id, entry_kind) = (entry.id, entry.op.kind());
+        match apply(app, entry.op).await {
+            Ok(()) => {
+                report.applied += 1;
+                db::run_blocking(&pool, move |pool| queue_remove_db(pool, id)).await?;
+            }
+            Err(e) => {
+                tracing::warn!(id, kind = entry_kind, error = %e, "Queued offline operation failed");
+                report.failed += 1;
+                db::run_blocking(&pool, move |pool| queue_fail_db(pool, id, &e)).await?;
+            }
+        }
+    }
+    if report.applied > 0 || report.failed > 0 {
+        tracing::info!(
+            applied = report.applied,
+            failed = report.failed,
+            "Replayed offline queue"
+        );
+    }
+    Ok(report)
+}
+
+fn publish_status(app: &AppHandle, pool: &DbPool) -> Result<OfflineStatus, String> {
+    let status = offline_status_db(pool)?;
+    let _ = emit_event(app, event_names::OFFLINE_CHANGED, &status);
+    Ok(status)
+}
+
+/// Replay anything left queued from an earlier offline session, if online.
+pub fn spawn_startup_replay(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let pool = app.state::<WorkspaceDb>().pool();
+        if is_offline(&pool) {
+            return;
+        }
+        match replay(&app).await {
+            Ok(report) if report.applied > 0 => {
+                let _ = publish_status(&app, &pool);
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(error = %e, "Could not replay offline queue"),
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn offline_status(workspace: tauri::State<'_, WorkspaceDb>) -> Result<OfflineStatus, String> {
+    db::run_blocking(&workspace.pool(), offline_status_db).await
+}
+
+/// Go offline: serve cached data and queue mutations until `offline_disable`.
+#[tauri::command]
+pub async fn offline_enable(app: AppHandle, workspace: tauri::State<'_, WorkspaceDb>) -> Result<OfflineStatus, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, |pool| offline_set_db(pool, true)).await?;
+    tracing::info!("Offline mode on");
+    publish_status(&app, &pool)
+}
+
+/// Go back online and replay the queue.
+#[tauri::command]
+pub async fn offline_disable(app: AppHandle, workspace: tauri::State<'_, WorkspaceDb>) -> Result<ReplayReport, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, |pool| offline_set_db(pool, false)).await?;
+    tracing::info!("Offline mode off");
+    let report = replay(&app).await?;
+    publish_status(&app, &pool)?;
+    Ok(report)
+}
+
+/// Queued operations, oldest first.
+#[tauri::command]
+pub async fn offline_queue_list(workspace: tauri::State<'_, WorkspaceDb>) -> Result<Vec<QueuedEntry>, String> {
+    db::run_blocking(&workspace.pool(), queue_list_db).await
+}
+
+/// Drop a queued operation without applying it.
+#[tauri::command]
+pub async fn offline_queue_discard(
+    app: AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: i64,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    if !db::run_blocking(&pool, move |pool| queue_remove_db(pool, id)).await? {
+        return Err(format!("No queued operation {}", id));
+    }
+    publish_status(&app, &pool)?;
+    Ok(())
+}
+
+/// Retry the queue while online, e.g. after discarding a failed entry.
+#[tauri::command]
+pub async fn offline_queue_replay(
+    app: AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<ReplayReport, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, ensure_online).await?;
+    let report = replay(&app).await?;
+    publish_status(&app, &pool)?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::anomaly::FeedbackVerdict;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn switch_persists_and_reports_the_queue() {
+        let (_dir, pool) = test_pool();
+        assert!(!is_offline(&pool));
+
+        offline_set_db(&pool, true).unwrap();
+        assert!(is_offline(&pool));
+        let feedback = AnomalyFeedback {
+            anomaly_id: "anom-1".to_string(),
+            verdict: FeedbackVerdict::Confirmed,
+            note: None,
+            timestamp: 1,
+        };
+        let id = queue_push_db(&pool, &QueuedOp::Feedback { feedback }).unwrap();
+        let status = offline_status_db(&pool).unwrap();
+        assert!(status.offline);
+        assert_eq!(status.queued, 1);
+        assert!(!config_queued_db(&pool).unwrap());
+
+        queue_fail_db(&pool, id, "boom").unwrap();
+        let entries = queue_list_db(&pool).unwrap();
+        assert_eq!(entries[0].attempts, 1);
+        assert_eq!(entries[0].last_error.as_deref(), Some("boom"));
+        assert!(queue_remove_db(&pool, id).unwrap());
+        assert!(!queue_remove_db(&pool, id).unwrap());
+    }
+
+    #[test]
+    fn queued_config_previews_in_order_without_writing() {
+        let (_dir, pool) = test_pool();
+        crate::commands::config::config_set_db(&pool, r#"{"model":"m","ticks":{"enabled":true}}"#).unwrap();
+
+        queue_config_db(
+            &pool,
+            QueuedOp::ConfigUpdate {
+                patch: serde_json::json!({"feed": "sip"}),
+            },
+        )
+        .unwrap();
+        let preview = queue_config_db(
+            &pool,
+            QueuedOp::ConfigSetKey {
+                path: "ticks".to_string(),
+                value: serde_json::json!({"retentionDays": 3}),
+            },
+        )
+        .unwrap();
+        let preview: serde_json::Value = serde_json::from_str(&preview).unwrap();
+        assert_eq!(
+            preview,
+            serde_json::json!({"model": "m", "feed": "sip", "ticks": {"retentionDays": 3}})
+        );
+
+        assert_eq!(
+            config_get_db(&pool).unwrap(),
+            r#"{"model":"m","ticks":{"enabled":true}}"#
+        );
+        assert!(config_queued_db(&pool).unwrap());
+        let kinds: Vec<_> = queue_list_db(&pool).unwrap().into_iter().map(|e| e.op).collect();
+        assert!(matches!(kinds[0], QueuedOp::ConfigUpdate { .. }));
+        assert!(matches!(kinds[1], QueuedOp::ConfigSetKey { .. }));
+    }
+}
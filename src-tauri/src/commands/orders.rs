@@ -0,0 +1,376 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::alpaca::AlpacaClient;
+use crate::bridge::SidecarBridge;
+use crate::commands::config::config_effective_db;
+use crate::commands::portfolio::{self, order_upsert_db, AlpacaOrder, Order, PortfolioSnapshot};
+use crate::commands::trading::{
+    check_guardrails, daily_loss_breach, guardrails, publish_halt, trading_halt_get_db, trading_halt_set_db,
+};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::workspace::WorkspaceDb;
+
+/// Orders are only placed against the paper account.
+const ORDER_MODE: &str = "paper";
+/// How often a placed order is polled for fills.
+const FILL_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// How long a placed order is polled before the mirror refresh takes over.
+const FILL_POLL_LIMIT: Duration = Duration::from_secs(10 * 60);
+
+/// The `risk` section of the app config: limits every placed order must pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RiskLimits {
+    /// Largest order value, in account currency.
+    pub max_notional: f64,
+    /// Largest position, after the order, as a percent of equity.
+    pub max_position_pct: f64,
+    /// Most orders that may be open at once, including this one.
+    pub max_open_orders: u64,
+}
+
+impl Default for RiskLimits {
+    fn default() -> Self {
+        Self {
+            max_notional: 10_000.0,
+            max_position_pct: 20.0,
+            max_open_orders: 10,
+        }
+    }
+}
+
+/// Parse the `risk` section of the app config.
+pub fn risk_limits(app_config: &serde_json::Value) -> RiskLimits {
+    app_config
+        .get("risk")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderType {
+    Market,
+    Limit,
+    Stop,
+    StopLimit,
+}
+
+/// What `order_place` submits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub qty: f64,
+    #[serde(rename = "type", default = "default_order_type")]
+    pub order_type: OrderType,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    /// `day`, `gtc`, `opg`, `cls`, `ioc` or `fok`.
+    #[serde(default = "default_time_in_force")]
+    pub time_in_force: String,
+}
+
+fn default_order_type() -> OrderType {
+    OrderType::Market
+}
+
+fn default_time_in_force() -> String {
+    "day".to_string()
+}
+
+const TIME_IN_FORCE: &[&str] = &["day", "gtc", "opg", "cls", "ioc", "fok"];
+
+/// Check that `request` is well-formed before any risk check.
+pub fn validate_order(request: &OrderRequest) -> Result<(), String> {
+    if request.symbol.trim().is_empty() {
+        return Err("Order symbol must not be empty".to_string());
+    }
+    if !(request.qty.is_finite() && request.qty > 0.0) {
+        return Err("Order quantity must be positive".to_string());
+    }
+    let needs_limit = matches!(request.order_type, OrderType::Limit | OrderType::StopLimit);
+    let needs_stop = matches!(request.order_type, OrderType::Stop | OrderType::StopLimit);
+    let positive = |p: Option<f64>| p.is_some_and(|p| p.is_finite() && p > 0.0);
+    if needs_limit && !positive(request.limit_price) {
+        return Err("Limit orders need a positive limit price".to_string());
+    }
+    if needs_stop && !positive(request.stop_price) {
+        return Err("Stop orders need a positive stop price".to_string());
+    }
+    if !TIME_IN_FORCE.contains(&request.time_in_force.as_str()) {
+        return Err(format!("Invalid time in force: '{}'", request.time_in_force));
+    }
+    Ok(())
+}
+
+/// Check `request`, valued at `price`, against `limits` and the account state.
+pub fn check_risk(
+    limits: &RiskLimits,
+    request: &OrderRequest,
+    price: f64,
+    portfolio: &PortfolioSnapshot,
+) -> Result<(), String> {
+    let notional = request.qty * price;
+    if notional > limits.max_notional {
+        return Err(format!(
+            "Order value {:.2} exceeds the {:.2} limit",
+            notional, limits.max_notional
+        ));
+    }
+    let open_orders = portfolio.open_orders.len() as u64;
+    if open_orders >= limits.max_open_orders {
+        return Err(format!(
+            "{} orders are already open; the limit is {}",
+            open_orders, limits.max_open_orders
+        ));
+    }
+    let equity = portfolio
+        .account
+        .as_ref()
+        .map(|a| a.equity)
+        .filter(|e| *e > 0.0)
+        .ok_or("Account equity is unknown")?;
+    let current = portfolio
+        .positions
+        .iter()
+        .find(|p| p.symbol.eq_ignore_ascii_case(&request.symbol))
+        .map_or(0.0, |p| p.qty * p.current_price);
+    let after = match request.side {
+        OrderSide::Buy => current + notional,
+        OrderSide::Sell => current - notional,
+    };
+    let pct = after.abs() / equity * 100.0;
+    // Orders that shrink a position are always allowed
+    if pct > limits.max_position_pct && after.abs() > current.abs() {
+        return Err(format!(
+            "Position in {} would be {:.1}% of equity; the limit is {:.1}%",
+            request.symbol, pct, limits.max_position_pct
+        ));
+    }
+    Ok(())
+}
+
+fn order_body(request: &OrderRequest) -> serde_json::Value {
+    let mut body = serde_json::json!({
+        "symbol": request.symbol.trim().to_uppercase(),
+        "qty": request.qty.to_string(),
+        "side": request.side,
+        "type": request.order_type,
+        "time_in_force": request.time_in_force,
+    });
+    if let Some(limit) = request.limit_price {
+        body["limit_price"] = limit.to_string().into();
+    }
+    if let Some(stop) = request.stop_price {
+        body["stop_price"] = stop.to_string().into();
+    }
+    body
+}
+
+/// Store `order` and emit `order:updated`.
+fn publish_order<R: Runtime>(app: &AppHandle<R>, pool: &DbPool, order: &Order) {
+    if let Err(e) = order_upsert_db(pool, ORDER_MODE, order) {
+        tracing::warn!(error = %e, order_id = order.id, "Failed to store order");
+    }
+    let _ = emit_event(app, event_names::ORDER_UPDATED, order);
+}
+
+/// Poll a placed order until it closes, emitting `order:updated` whenever its
+/// status or filled quantity changes.
+fn spawn_fill_tracker<R: Runtime>(app: AppHandle<R>, placed: Order) {
+    std::thread::spawn(move || {
+        let pool = app.state::<WorkspaceDb>().pool();
+        let started = std::time::Instant::now();
+        let mut last = placed;
+        while last.is_open() && started.elapsed() < FILL_POLL_LIMIT {
+            std::thread::sleep(FILL_POLL_INTERVAL);
+            let path = format!("/v2/orders/{}", last.id);
+            let fetched = tauri::async_runtime::block_on(async {
+                let client = AlpacaClient::for_mode(&pool, ORDER_MODE).await?;
+                client.get::<AlpacaOrder>(&path, &[]).await
+            });
+            match fetched.map(Order::from) {
+                Ok(order) => {
+                    if order.status != last.status || order.filled_qty != last.filled_qty {
+                        publish_order(&app, &pool, &order);
+                    }
+                    last = order;
+                }
+                Err(e) => tracing::warn!(error = %e, order_id = last.id, "Failed to poll order"),
+            }
+        }
+    });
+}
+
+/// Submit a paper order after checking the kill switch, the `guardrails`
+/// hard caps and the `risk` limits. Breaching the daily loss limit halts
+/// trading.
+#[tauri::command]
+pub async fn order_place(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    request: OrderRequest,
+) -> Result<Order, String> {
+    validate_order(&request)?;
+    let pool = workspace.pool();
+    let (limits, caps, halt) = db::run_blocking(&pool, |pool| {
+        let config = config_effective_db(pool)?;
+        Ok((risk_limits(&config), guardrails(&config), trading_halt_get_db(pool)?))
+    })
+    .await?;
+    // Fail fast while halted, before any request reaches Alpaca
+    halt.ensure_trading()?;
+    let client = AlpacaClient::for_mode(&pool, ORDER_MODE).await?;
+    let portfolio = portfolio::refresh(&pool, ORDER_MODE).await?;
+    if let Some(breach) = daily_loss_breach(&caps, &portfolio) {
+        let halt = db::run_blocking(&pool, move |pool| trading_halt_set_db(pool, true, Some(breach))).await?;
+        publish_halt(&app, &bridge, &halt);
+    }
+    let symbol = request.symbol.trim().to_uppercase();
+    let price = match request.limit_price.or(request.stop_price) {
+        Some(p) => p,
+        None => client.latest_price(&symbol).await?,
+    };
+    // Re-read the switch: it may have just been engaged by the loss check
+    let halt = db::run_blocking(&pool, trading_halt_get_db).await?;
+    check_guardrails(&halt, &caps, request.qty * price, &portfolio)?;
+    check_risk(&limits, &request, price, &portfolio)?;
+
+    let placed: AlpacaOrder = client.post("/v2/orders", &order_body(&request)).await?;
+    let order = Order::from(placed);
+    tracing::info!(order_id = order.id, symbol, "Placed paper order");
+    publish_order(&app, &pool, &order);
+    spawn_fill_tracker(app, order.clone());
+    Ok(order)
+}
+
+/// Cancel an open paper order and return its updated state. Cancels are
+/// allowed while trading is halted.
+#[tauri::command]
+pub async fn order_cancel(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    id: String,
+) -> Result<Order, String> {
+    let pool = workspace.pool();
+    let client = AlpacaClient::for_mode(&pool, ORDER_MODE).await?;
+    let path = format!("/v2/orders/{}", id);
+    client.delete(&path).await?;
+    let order = Order::from(client.get::<AlpacaOrder>(&path, &[]).await?);
+    publish_order(&app, &pool, &order);
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portfolio::{AccountSummary, Position};
+
+    fn request(side: OrderSide, qty: f64) -> OrderRequest {
+        OrderRequest {
+            symbol: "AAPL".to_string(),
+            side,
+            qty,
+            order_type: OrderType::Market,
+            limit_price: None,
+            stop_price: None,
+            time_in_force: "day".to_string(),
+        }
+    }
+
+    fn portfolio(position_qty: f64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            mode: "paper".to_string(),
+            account: Some(AccountSummary {
+                equity: 100_000.0,
+                cash: 100_000.0,
+                buying_power: 200_000.0,
+                last_equity: 100_000.0,
+                currency: "USD".to_string(),
+            }),
+            positions: vec![Position {
+                symbol: "AAPL".to_string(),
+                qty: position_qty,
+                avg_entry_price: 100.0,
+                current_price: 100.0,
+                market_value: position_qty * 100.0,
+                cost_basis: position_qty * 100.0,
+                unrealized_pl: 0.0,
+                unrealized_plpc: 0.0,
+            }],
+            open_orders: Vec::new(),
+            fetched_at: Some(0),
+            stale: false,
+        }
+    }
+
+    #[test]
+    fn validation_requires_prices_for_limit_and_stop_orders() {
+        assert!(validate_order(&request(OrderSide::Buy, 1.0)).is_ok());
+        assert!(validate_order(&request(OrderSide::Buy, 0.0)).is_err());
+        let limit = OrderRequest { order_type: OrderType::Limit, ..request(OrderSide::Buy, 1.0) };
+        assert!(validate_order(&limit).is_err());
+        assert!(validate_order(&OrderRequest { limit_price: Some(99.0), ..limit }).is_ok());
+        let stop_limit = OrderRequest {
+            order_type: OrderType::StopLimit,
+            limit_price: Some(99.0),
+            ..request(OrderSide::Sell, 1.0)
+        };
+        assert!(validate_order(&stop_limit).is_err());
+        let bad_tif = OrderRequest { time_in_force: "week".to_string(), ..request(OrderSide::Buy, 1.0) };
+        assert!(validate_order(&bad_tif).is_err());
+    }
+
+    #[test]
+    fn risk_limits_notional_and_position_size() {
+        let limits = RiskLimits::default();
+        assert!(check_risk(&limits, &request(OrderSide::Buy, 50.0), 100.0, &portfolio(0.0)).is_ok());
+        let err = check_risk(&limits, &request(OrderSide::Buy, 101.0), 100.0, &portfolio(0.0)).unwrap_err();
+        assert!(err.contains("exceeds"), "{err}");
+        // 150 shares held + 90 bought = 24% of equity
+        let err = check_risk(&limits, &request(OrderSide::Buy, 90.0), 100.0, &portfolio(150.0)).unwrap_err();
+        assert!(err.contains("24.0%"), "{err}");
+        // Selling down an oversized position is allowed
+        assert!(check_risk(&limits, &request(OrderSide::Sell, 50.0), 100.0, &portfolio(300.0)).is_ok());
+    }
+
+    #[test]
+    fn risk_limits_open_orders_and_requires_equity() {
+        let limits = RiskLimits { max_open_orders: 0, ..RiskLimits::default() };
+        assert!(check_risk(&limits, &request(OrderSide::Buy, 1.0), 100.0, &portfolio(0.0)).is_err());
+        let mut no_account = portfolio(0.0);
+        no_account.account = None;
+        assert!(check_risk(&RiskLimits::default(), &request(OrderSide::Buy, 1.0), 100.0, &no_account).is_err());
+    }
+
+    #[test]
+    fn order_body_uses_alpaca_field_names() {
+        let body = order_body(&OrderRequest {
+            symbol: " aapl ".to_string(),
+            order_type: OrderType::StopLimit,
+            limit_price: Some(99.5),
+            stop_price: Some(100.0),
+            ..request(OrderSide::Sell, 2.0)
+        });
+        assert_eq!(body["symbol"], "AAPL");
+        assert_eq!(body["qty"], "2");
+        assert_eq!(body["side"], "sell");
+        assert_eq!(body["type"], "stop_limit");
+        assert_eq!(body["limit_price"], "99.5");
+    }
+}
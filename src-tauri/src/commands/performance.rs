@@ -0,0 +1,359 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::agent::resolve_trading_mode;
+use crate::commands::calendar::{calendar_load_db, Calendar};
+use crate::commands::config::config_effective_db;
+use crate::commands::portfolio::{self, portfolio_config, Position};
+use crate::db::{self, DbPool};
+use crate::market_calendar::MarketSession;
+use crate::tasks::TaskOutcome;
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// Wait this long after the closing bell so closing prices have settled.
+const SNAPSHOT_DELAY_MS: i64 = 15 * 60_000;
+
+/// The account at the end of one trading day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DailySnapshot {
+    pub mode: String,
+    /// Trading date, `YYYY-MM-DD`.
+    pub date: String,
+    pub equity: f64,
+    pub cash: f64,
+    /// Equity at the previous close, per Alpaca.
+    pub last_equity: f64,
+    pub positions: Vec<Position>,
+    pub recorded_at: u64,
+}
+
+/// Store `snapshot`, replacing any earlier one for the same day.
+pub fn snapshot_insert_db(pool: &DbPool, snapshot: &DailySnapshot) -> Result<(), String> {
+    let positions = serde_json::to_string(&snapshot.positions).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT OR REPLACE INTO portfolio_snapshots
+             (mode, date, equity, cash, last_equity, positions, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            snapshot.mode,
+            snapshot.date,
+            snapshot.equity,
+            snapshot.cash,
+            snapshot.last_equity,
+            positions,
+            snapshot.recorded_at as i64,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn snapshot_exists_db(pool: &DbPool, mode: &str, date: &str) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM portfolio_snapshots WHERE mode = ?1 AND date = ?2)",
+        [mode, date],
+        |row| row.get(0),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Snapshots for `mode` on or after `since` (`YYYY-MM-DD`), oldest first.
+pub fn snapshots_list_db(pool: &DbPool, mode: &str, since: Option<&str>) -> Result<Vec<DailySnapshot>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT mode, date, equity, cash, last_equity, positions, recorded_at
+             FROM portfolio_snapshots
+             WHERE mode = ?1 AND (?2 IS NULL OR date >= ?2)
+             ORDER BY date",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params![mode, since], |row| {
+            let positions: String = row.get(5)?;
+            Ok(DailySnapshot {
+                mode: row.get(0)?,
+                date: row.get(1)?,
+                equity: row.get(2)?,
+                cash: row.get(3)?,
+                last_equity: row.get(4)?,
+                positions: serde_json::from_str(&positions).unwrap_or_default(),
+                recorded_at: row.get::<_, i64>(6)?.max(0) as u64,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// The most recent session that closed at least [`SNAPSHOT_DELAY_MS`] before
+/// `now`, if the market has not reopened since.
+pub fn snapshot_due(calendar: &Calendar, now: i64) -> Option<MarketSession> {
+    if calendar.is_open(now) {
+        return None;
+    }
+    let today = DateTime::from_timestamp_millis(now)?.date_naive();
+    [today - Days::new(1), today]
+        .into_iter()
+        .filter_map(|d| calendar.session(d))
+        .filter(|s| s.close_at + SNAPSHOT_DELAY_MS <= now)
+        .max_by_key(|s| s.close_at)
+}
+
+/// Refresh the portfolio for `mode` and record it as the snapshot for `date`.
+async fn record_snapshot(pool: &DbPool, mode: &str, date: &str) -> Result<DailySnapshot, String> {
+    let current = portfolio::refresh(pool, mode).await?;
+    let account = current.account.ok_or("Account balances are unknown")?;
+    let snapshot = DailySnapshot {
+        mode: mode.to_string(),
+        date: date.to_string(),
+        equity: account.equity,
+        cash: account.cash,
+        last_equity: account.last_equity,
+        positions: current.positions,
+        recorded_at: now_millis(),
+    };
+    let stored = snapshot.clone();
+    db::run_blocking(pool, move |pool| snapshot_insert_db(pool, &stored)).await?;
+    Ok(snapshot)
+}
+
+/// Scheduled task: record the day's snapshot once a session has closed,
+/// while `portfolio.enabled` is set.
+pub fn snapshot_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let config = portfolio_config(&config_effective_db(&pool)?);
+    let calendar = calendar_load_db(&pool)?;
+    if !config.enabled {
+        return Ok(TaskOutcome::Skipped);
+    }
+    let Some(session) = snapshot_due(&calendar, now_millis() as i64) else {
+        return Ok(TaskOutcome::Skipped);
+    };
+    if snapshot_exists_db(&pool, &config.mode, &session.date).unwrap_or(true) {
+        return Ok(TaskOutcome::Skipped);
+    }
+    let snapshot = tauri::async_runtime::block_on(record_snapshot(&pool, &config.mode, &session.date))?;
+    tracing::info!(date = snapshot.date, equity = snapshot.equity, "Recorded daily portfolio snapshot");
+    Ok(TaskOutcome::Ran)
+}
+
+/// How far back `performance_history` looks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PerformanceRange {
+    #[serde(rename = "1m")]
+    Month,
+    #[serde(rename = "3m")]
+    #[default]
+    Quarter,
+    #[serde(rename = "1y")]
+    Year,
+    #[serde(rename = "ytd")]
+    YearToDate,
+    #[serde(rename = "all")]
+    All,
+}
+
+impl PerformanceRange {
+    /// The first date in range as of `today`, or `None` for all history.
+    pub fn start(self, today: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            Self::Month => today.checked_sub_months(chrono::Months::new(1)),
+            Self::Quarter => today.checked_sub_months(chrono::Months::new(3)),
+            Self::Year => today.checked_sub_months(chrono::Months::new(12)),
+            Self::YearToDate => NaiveDate::from_ymd_opt(today.year(), 1, 1),
+            Self::All => None,
+        }
+    }
+
+    /// Epoch millis of the first UTC midnight in range as of `now_ms`.
+    pub fn start_millis(self, now_ms: u64) -> Option<u64> {
+        let today = DateTime::from_timestamp_millis(now_ms as i64)?.date_naive();
+        let start = self.start(today)?.and_time(chrono::NaiveTime::MIN).and_utc();
+        Some(start.timestamp_millis().max(0) as u64)
+    }
+}
+
+/// One day of `performance_history`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformancePoint {
+    pub date: String,
+    pub equity: f64,
+    /// Return since the previous close, as a fraction.
+    pub daily_return: f64,
+    /// P&L since the close before the first day in range.
+    pub cumulative_pl: f64,
+    /// Fall from the running equity peak, as a non-positive fraction.
+    pub drawdown: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceHistory {
+    pub mode: String,
+    pub range: PerformanceRange,
+    pub points: Vec<PerformancePoint>,
+    /// Return over the whole range, as a fraction.
+    pub total_return: f64,
+    /// Deepest drawdown in range, as a non-positive fraction.
+    pub max_drawdown: f64,
+}
+
+fn ratio(value: f64, base: f64) -> f64 {
+    if base > 0.0 {
+        value / base - 1.0
+    } else {
+        0.0
+    }
+}
+
+/// Daily returns, cumulative P&L, and drawdown from consecutive snapshots.
+pub fn performance_from_snapshots(mode: &str, range: PerformanceRange, snapshots: &[DailySnapshot]) -> PerformanceHistory {
+    let base = snapshots.first().map_or(0.0, |s| s.last_equity);
+    let mut peak = base;
+    let mut previous = base;
+    let mut max_drawdown: f64 = 0.0;
+    let points = snapshots
+        .iter()
+        .map(|s| {
+            peak = peak.max(s.equity);
+            let drawdown = ratio(s.equity, peak).min(0.0);
+            max_drawdown = max_drawdown.min(drawdown);
+            let point = PerformancePoint {
+                date: s.date.clone(),
+                equity: s.equity,
+                daily_return: ratio(s.equity, previous),
+                cumulative_pl: s.equity - base,
+                drawdown,
+            };
+            previous = s.equity;
+            point
+        })
+        .collect::<Vec<_>>();
+    PerformanceHistory {
+        mode: mode.to_string(),
+        range,
+        total_return: points.last().map_or(0.0, |p| ratio(p.equity, base)),
+        points,
+        max_drawdown,
+    }
+}
+
+/// Daily performance of the account in `mode` (the mirrored mode when
+/// omitted) over `range`, from the recorded snapshots.
+#[tauri::command]
+pub async fn performance_history(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    range: Option<PerformanceRange>,
+    mode: Option<String>,
+) -> Result<PerformanceHistory, String> {
+    let range = range.unwrap_or_default();
+    let mode = mode.map(|m| resolve_trading_mode(Some(m))).transpose()?;
+    let today = DateTime::from_timestamp_millis(now_millis() as i64)
+        .ok_or("Invalid clock")?
+        .date_naive();
+    let since = range.start(today).map(|d| d.format("%Y-%m-%d").to_string());
+    db::run_blocking(&workspace.pool(), move |pool| {
+        let mode = match mode {
+            Some(m) => m,
+            None => portfolio_config(&config_effective_db(pool)?).mode,
+        };
+        let snapshots = snapshots_list_db(pool, &mode, since.as_deref())?;
+        Ok(performance_from_snapshots(&mode, range, &snapshots))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn snapshot(date: &str, last_equity: f64, equity: f64) -> DailySnapshot {
+        DailySnapshot {
+            mode: "paper".to_string(),
+            date: date.to_string(),
+            equity,
+            cash: equity,
+            last_equity,
+            positions: Vec::new(),
+            recorded_at: 0,
+        }
+    }
+
+    fn utc_millis(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    #[test]
+    fn snapshots_round_trip_and_filter_by_date() {
+        let (_dir, pool) = test_pool();
+        snapshot_insert_db(&pool, &snapshot("2025-06-16", 100.0, 101.0)).unwrap();
+        snapshot_insert_db(&pool, &snapshot("2025-06-17", 101.0, 99.0)).unwrap();
+        // Re-recording a day replaces it
+        snapshot_insert_db(&pool, &snapshot("2025-06-17", 101.0, 98.0)).unwrap();
+        assert!(snapshot_exists_db(&pool, "paper", "2025-06-17").unwrap());
+        assert!(!snapshot_exists_db(&pool, "live", "2025-06-17").unwrap());
+
+        let all = snapshots_list_db(&pool, "paper", None).unwrap();
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].equity, 98.0);
+        assert_eq!(snapshots_list_db(&pool, "paper", Some("2025-06-17")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn history_tracks_returns_and_drawdown() {
+        let snapshots = [
+            snapshot("2025-06-16", 100.0, 110.0),
+            snapshot("2025-06-17", 110.0, 99.0),
+            snapshot("2025-06-18", 99.0, 104.5),
+        ];
+        let history = performance_from_snapshots("paper", PerformanceRange::All, &snapshots);
+        let p = &history.points;
+        assert!((p[0].daily_return - 0.10).abs() < 1e-9);
+        assert!((p[1].daily_return + 0.10).abs() < 1e-9);
+        assert_eq!(p[1].cumulative_pl, -1.0);
+        assert!((p[1].drawdown + 0.10).abs() < 1e-9);
+        assert!((history.max_drawdown + 0.10).abs() < 1e-9);
+        assert!((history.total_return - 0.045).abs() < 1e-9);
+        assert!(performance_from_snapshots("paper", PerformanceRange::All, &[]).points.is_empty());
+    }
+
+    #[test]
+    fn snapshot_waits_for_the_close_and_stops_at_the_open() {
+        let calendar = Calendar::builtin();
+        // 2025-06-16 closes at 20:00 UTC
+        assert!(snapshot_due(&calendar, utc_millis(2025, 6, 16, 20, 5)).is_none());
+        assert_eq!(snapshot_due(&calendar, utc_millis(2025, 6, 16, 20, 15)).unwrap().date, "2025-06-16");
+        // Past midnight UTC, the previous day's session is still due
+        assert_eq!(snapshot_due(&calendar, utc_millis(2025, 6, 17, 2, 0)).unwrap().date, "2025-06-16");
+        assert!(snapshot_due(&calendar, utc_millis(2025, 6, 17, 15, 0)).is_none());
+    }
+
+    #[test]
+    fn ranges_start_relative_to_today() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 18).unwrap();
+        assert_eq!(PerformanceRange::Month.start(today), NaiveDate::from_ymd_opt(2025, 5, 18));
+        assert_eq!(PerformanceRange::YearToDate.start(today), NaiveDate::from_ymd_opt(2025, 1, 1));
+        assert_eq!(PerformanceRange::All.start(today), None);
+        let range: PerformanceRange = serde_json::from_str("\"1y\"").unwrap();
+        assert_eq!(range, PerformanceRange::Year);
+    }
+}
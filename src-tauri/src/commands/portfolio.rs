@@ -0,0 +1,658 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::alpaca::{de_decimal, de_opt_decimal, AlpacaClient};
+use crate::commands::agent::resolve_trading_mode;
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::is_offline;
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// How often the refresher wakes to check whether a refresh is due.
+const REFRESHER_TICK: Duration = Duration::from_secs(5);
+/// Most recent orders mirrored per refresh, open or not.
+const ORDERS_FETCH_LIMIT: &str = "100";
+const DEFAULT_ORDERS_LIMIT: u32 = 100;
+
+/// The `portfolio` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PortfolioConfig {
+    /// Refresh the mirror in the background.
+    pub enabled: bool,
+    /// Which account is mirrored: `paper` or `live`.
+    pub mode: String,
+    pub refresh_secs: u64,
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            mode: "paper".to_string(),
+            refresh_secs: 60,
+        }
+    }
+}
+
+/// Parse the `portfolio` section of the app config.
+pub fn portfolio_config(app_config: &serde_json::Value) -> PortfolioConfig {
+    app_config
+        .get("portfolio")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Account balances from `GET /v2/account`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountSummary {
+    pub equity: f64,
+    pub cash: f64,
+    pub buying_power: f64,
+    /// Equity at the previous close.
+    pub last_equity: f64,
+    pub currency: String,
+}
+
+/// An open position from `GET /v2/positions`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Position {
+    pub symbol: String,
+    /// Negative for short positions.
+    pub qty: f64,
+    pub avg_entry_price: f64,
+    pub current_price: f64,
+    pub market_value: f64,
+    pub cost_basis: f64,
+    pub unrealized_pl: f64,
+    pub unrealized_plpc: f64,
+}
+
+/// An order from `GET /v2/orders`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub id: String,
+    pub client_order_id: String,
+    pub symbol: String,
+    pub side: String,
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub time_in_force: String,
+    pub qty: Option<f64>,
+    pub notional: Option<f64>,
+    pub filled_qty: f64,
+    pub filled_avg_price: Option<f64>,
+    pub limit_price: Option<f64>,
+    pub stop_price: Option<f64>,
+    pub status: String,
+    /// RFC 3339, as reported by Alpaca.
+    pub submitted_at: Option<String>,
+    pub updated_at: Option<String>,
+}
+
+/// Statuses after which an order can no longer fill.
+const CLOSED_ORDER_STATUSES: &[&str] = &["filled", "canceled", "expired", "rejected", "replaced", "done_for_day"];
+
+impl Order {
+    pub fn is_open(&self) -> bool {
+        !CLOSED_ORDER_STATUSES.contains(&self.status.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct AlpacaAccount {
+    #[serde(deserialize_with = "de_decimal")]
+    equity: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    cash: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    buying_power: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    last_equity: f64,
+    currency: String,
+}
+
+impl From<AlpacaAccount> for AccountSummary {
+    fn from(a: AlpacaAccount) -> Self {
+        AccountSummary {
+            equity: a.equity,
+            cash: a.cash,
+            buying_power: a.buying_power,
+            last_equity: a.last_equity,
+            currency: a.currency,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AlpacaPosition {
+    symbol: String,
+    #[serde(deserialize_with = "de_decimal")]
+    qty: f64,
+    side: String,
+    #[serde(deserialize_with = "de_decimal")]
+    avg_entry_price: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    current_price: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    market_value: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    cost_basis: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    unrealized_pl: f64,
+    #[serde(deserialize_with = "de_decimal")]
+    unrealized_plpc: f64,
+}
+
+impl From<AlpacaPosition> for Position {
+    fn from(p: AlpacaPosition) -> Self {
+        let qty = if p.side == "short" { -p.qty.abs() } else { p.qty };
+        Position {
+            symbol: p.symbol,
+            qty,
+            avg_entry_price: p.avg_entry_price,
+            current_price: p.current_price,
+            market_value: p.market_value,
+            cost_basis: p.cost_basis,
+            unrealized_pl: p.unrealized_pl,
+            unrealized_plpc: p.unrealized_plpc,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AlpacaOrder {
+    id: String,
+    client_order_id: String,
+    symbol: String,
+    side: String,
+    #[serde(rename = "type")]
+    order_type: String,
+    time_in_force: String,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    qty: Option<f64>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    notional: Option<f64>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    filled_qty: Option<f64>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    filled_avg_price: Option<f64>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    limit_price: Option<f64>,
+    #[serde(default, deserialize_with = "de_opt_decimal")]
+    stop_price: Option<f64>,
+    status: String,
+    submitted_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+impl From<AlpacaOrder> for Order {
+    fn from(o: AlpacaOrder) -> Self {
+        Order {
+            id: o.id,
+            client_order_id: o.client_order_id,
+            symbol: o.symbol,
+            side: o.side,
+            order_type: o.order_type,
+            time_in_force: o.time_in_force,
+            qty: o.qty,
+            notional: o.notional,
+            filled_qty: o.filled_qty.unwrap_or(0.0),
+            filled_avg_price: o.filled_avg_price,
+            limit_price: o.limit_price,
+            stop_price: o.stop_price,
+            status: o.status,
+            submitted_at: o.submitted_at,
+            updated_at: o.updated_at,
+        }
+    }
+}
+
+/// The mirrored state of one account; the payload of `portfolio:updated`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortfolioSnapshot {
+    pub mode: String,
+    pub account: Option<AccountSummary>,
+    pub positions: Vec<Position>,
+    /// Orders that can still fill.
+    pub open_orders: Vec<Order>,
+    /// Epoch millis of the last successful refresh; `None` if never fetched.
+    pub fetched_at: Option<u64>,
+    /// Served from the cache in offline mode; Alpaca may have moved on since `fetched_at`.
+    #[serde(default)]
+    pub stale: bool,
+}
+
+/// Which orders `orders_list` returns.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderFilter {
+    pub mode: Option<String>,
+    /// Only orders that can still fill.
+    #[serde(default)]
+    pub open_only: bool,
+    pub symbol: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Replace the cached account and positions for `mode` and upsert `orders`,
+/// all in one transaction.
+pub fn portfolio_set_db(
+    pool: &DbPool,
+    mode: &str,
+    account: &AccountSummary,
+    positions: &[Position],
+    orders: &[Order],
+    fetched_at: u64,
+) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO portfolio_accounts (mode, equity, cash, buying_power, last_equity, currency, fetched_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(mode) DO UPDATE SET
+             equity = ?2, cash = ?3, buying_power = ?4, last_equity = ?5, currency = ?6, fetched_at = ?7",
+        rusqlite::params![
+            mode,
+            account.equity,
+            account.cash,
+            account.buying_power,
+            account.last_equity,
+            account.currency,
+            fetched_at as i64,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM portfolio_positions WHERE mode = ?1", [mode])
+        .map_err(|e| e.to_string())?;
+    {
+        let mut insert = tx
+            .prepare(
+                "INSERT INTO portfolio_positions (mode, symbol, qty, avg_entry_price, current_price,
+                     market_value, cost_basis, unrealized_pl, unrealized_plpc)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            )
+            .map_err(|e| e.to_string())?;
+        for p in positions {
+            insert
+                .execute(rusqlite::params![
+                    mode,
+                    p.symbol,
+                    p.qty,
+                    p.avg_entry_price,
+                    p.current_price,
+                    p.market_value,
+                    p.cost_basis,
+                    p.unrealized_pl,
+                    p.unrealized_plpc,
+                ])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    for order in orders {
+        order_upsert_tx(&tx, mode, order)?;
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn order_upsert_tx(conn: &rusqlite::Connection, mode: &str, o: &Order) -> Result<(), String> {
+    conn.execute(
+        "INSERT INTO orders (id, mode, client_order_id, symbol, side, type, time_in_force, qty, notional,
+             filled_qty, filled_avg_price, limit_price, stop_price, status, submitted_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16)
+         ON CONFLICT(id) DO UPDATE SET
+             filled_qty = ?10, filled_avg_price = ?11, limit_price = ?12, stop_price = ?13,
+             status = ?14, updated_at = ?16",
+        rusqlite::params![
+            o.id,
+            mode,
+            o.client_order_id,
+            o.symbol,
+            o.side,
+            o.order_type,
+            o.time_in_force,
+            o.qty,
+            o.notional,
+            o.filled_qty,
+            o.filled_avg_price,
+            o.limit_price,
+            o.stop_price,
+            o.status,
+            o.submitted_at,
+            o.updated_at,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Insert or update one order in the local mirror.
+pub fn order_upsert_db(pool: &DbPool, mode: &str, order: &Order) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    order_upsert_tx(&conn, mode, order)
+}
+
+const ORDER_COLUMNS: &str = "id, client_order_id, symbol, side, type, time_in_force, qty, notional,
+    filled_qty, filled_avg_price, limit_price, stop_price, status, submitted_at, updated_at";
+
+fn order_from_row(row: &rusqlite::Row) -> rusqlite::Result<Order> {
+    Ok(Order {
+        id: row.get(0)?,
+        client_order_id: row.get(1)?,
+        symbol: row.get(2)?,
+        side: row.get(3)?,
+        order_type: row.get(4)?,
+        time_in_force: row.get(5)?,
+        qty: row.get(6)?,
+        notional: row.get(7)?,
+        filled_qty: row.get(8)?,
+        filled_avg_price: row.get(9)?,
+        limit_price: row.get(10)?,
+        stop_price: row.get(11)?,
+        status: row.get(12)?,
+        submitted_at: row.get(13)?,
+        updated_at: row.get(14)?,
+    })
+}
+
+/// Mirrored orders matching `filter`, newest first.
+pub fn orders_list_db(pool: &DbPool, filter: &OrderFilter) -> Result<Vec<Order>, String> {
+    let closed = format!(",{},", CLOSED_ORDER_STATUSES.join(","));
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {ORDER_COLUMNS} FROM orders
+             WHERE (?1 IS NULL OR mode = ?1)
+               AND (?2 = 0 OR instr(?3, ',' || status || ',') = 0)
+               AND (?4 IS NULL OR symbol = ?4)
+             ORDER BY submitted_at DESC, id LIMIT ?5"
+        ))
+        .map_err(|e| e.to_string())?;
+    let symbol = filter.symbol.as_deref().map(|s| s.trim().to_uppercase());
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                filter.mode,
+                filter.open_only,
+                closed,
+                symbol,
+                filter.limit.unwrap_or(DEFAULT_ORDERS_LIMIT),
+            ],
+            order_from_row,
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// The cached account, positions, and open orders for `mode`.
+pub fn portfolio_get_db(pool: &DbPool, mode: &str) -> Result<PortfolioSnapshot, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let account = conn.query_row(
+        "SELECT equity, cash, buying_power, last_equity, currency, fetched_at
+         FROM portfolio_accounts WHERE mode = ?1",
+        [mode],
+        |row| {
+            let account = AccountSummary {
+                equity: row.get(0)?,
+                cash: row.get(1)?,
+                buying_power: row.get(2)?,
+                last_equity: row.get(3)?,
+                currency: row.get(4)?,
+            };
+            Ok((account, row.get::<_, i64>(5)?.max(0) as u64))
+        },
+    );
+    let (account, fetched_at) = match account {
+        Ok((account, at)) => (Some(account), Some(at)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => (None, None),
+        Err(e) => return Err(e.to_string()),
+    };
+    let mut stmt = conn
+        .prepare(
+            "SELECT symbol, qty, avg_entry_price, current_price, market_value, cost_basis,
+                 unrealized_pl, unrealized_plpc
+             FROM portfolio_positions WHERE mode = ?1 ORDER BY symbol",
+        )
+        .map_err(|e| e.to_string())?;
+    let positions = stmt
+        .query_map([mode], |row| {
+            Ok(Position {
+                symbol: row.get(0)?,
+                qty: row.get(1)?,
+                avg_entry_price: row.get(2)?,
+                current_price: row.get(3)?,
+                market_value: row.get(4)?,
+                cost_basis: row.get(5)?,
+                unrealized_pl: row.get(6)?,
+                unrealized_plpc: row.get(7)?,
+            })
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    drop(stmt);
+    drop(conn);
+    let filter = OrderFilter {
+        mode: Some(mode.to_string()),
+        open_only: true,
+        symbol: None,
+        limit: Some(u32::MAX),
+    };
+    Ok(PortfolioSnapshot {
+        mode: mode.to_string(),
+        account,
+        positions,
+        open_orders: orders_list_db(pool, &filter)?,
+        fetched_at,
+        stale: false,
+    })
+}
+
+/// Fetch the account, positions, and recent orders for `mode` from Alpaca,
+/// cache them, and return the refreshed snapshot.
+pub async fn refresh(pool: &DbPool, mode: &str) -> Result<PortfolioSnapshot, String> {
+    let client = AlpacaClient::for_mode(pool, mode).await?;
+    let account: AlpacaAccount = client.get("/v2/account", &[]).await?;
+    let positions: Vec<AlpacaPosition> = client.get("/v2/positions", &[]).await?;
+    let orders: Vec<AlpacaOrder> = client
+        .get(
+            "/v2/orders",
+            &[("status", "all"), ("limit", ORDERS_FETCH_LIMIT), ("direction", "desc")],
+        )
+        .await?;
+    let account = AccountSummary::from(account);
+    let positions: Vec<Position> = positions.into_iter().map(Position::from).collect();
+    let orders: Vec<Order> = orders.into_iter().map(Order::from).collect();
+    let mode = mode.to_string();
+    db::run_blocking(pool, move |pool| {
+        portfolio_set_db(pool, &mode, &account, &positions, &orders, now_millis())?;
+        portfolio_get_db(pool, &mode)
+    })
+    .await
+}
+
+/// Start the background thread that refreshes the mirror every
+/// `portfolio.refreshSecs` while `portfolio.enabled` is set.
+pub fn spawn_refresher<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let mut last_refresh: Option<Instant> = None;
+        loop {
+            std::thread::sleep(REFRESHER_TICK);
+            let pool = app.state::<WorkspaceDb>().pool();
+            let config = match config_effective_db(&pool) {
+                Ok(c) => portfolio_config(&c),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Portfolio refresher could not read config");
+                    continue;
+                }
+            };
+            if !config.enabled || is_offline(&pool) {
+                continue;
+            }
+            if last_refresh.is_some_and(|t| t.elapsed() < Duration::from_secs(config.refresh_secs)) {
+                continue;
+            }
+            last_refresh = Some(Instant::now());
+            match tauri::async_runtime::block_on(refresh(&pool, &config.mode)) {
+                Ok(snapshot) => {
+                    let _ = emit_event(&app, event_names::PORTFOLIO_UPDATED, &snapshot);
+                }
+                Err(e) => tracing::warn!(error = %e, "Portfolio refresh failed"),
+            }
+        }
+    });
+}
+
+/// The mirrored portfolio for `mode` (the configured mode when omitted).
+/// With `refresh`, fetch it from Alpaca first.
+#[tauri::command]
+pub async fn portfolio_get(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    mode: Option<String>,
+    refresh: Option<bool>,
+) -> Result<PortfolioSnapshot, String> {
+    let pool = workspace.pool();
+    let mode = match mode {
+        Some(m) => resolve_trading_mode(Some(m))?,
+        None => db::run_blocking(&pool, |pool| Ok(portfolio_config(&config_effective_db(pool)?).mode)).await?,
+    };
+    if db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await? {
+        let mut snapshot = db::run_blocking(&pool, move |pool| portfolio_get_db(pool, &mode)).await?;
+        snapshot.stale = true;
+        return Ok(snapshot);
+    }
+    if refresh.unwrap_or(false) {
+        let snapshot = self::refresh(&pool, &mode).await?;
+        let _ = emit_event(&app, event_names::PORTFOLIO_UPDATED, &snapshot);
+        return Ok(snapshot);
+    }
+    db::run_blocking(&pool, move |pool| portfolio_get_db(pool, &mode)).await
+}
+
+/// Mirrored orders, newest first.
+#[tauri::command]
+pub async fn orders_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    filter: Option<OrderFilter>,
+) -> Result<Vec<Order>, String> {
+    let pool = workspace.pool();
+    let filter = filter.unwrap_or_default();
+    db::run_blocking(&pool, move |pool| orders_list_db(pool, &filter)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    const ORDER: &str = r#"{
+        "id": "61e69015-8549-4bfd-b9c3-01e75843f47d", "client_order_id": "eb9e2aaa",
+        "symbol": "AAPL", "side": "buy", "type": "limit", "time_in_force": "day",
+        "qty": "10", "notional": null, "filled_qty": "4", "filled_avg_price": "189.5",
+        "limit_price": "190", "stop_price": null, "status": "partially_filled",
+        "submitted_at": "2024-06-10T14:00:00Z", "updated_at": "2024-06-10T14:01:00Z"
+    }"#;
+
+    fn order(id: &str, status: &str, submitted_at: &str) -> Order {
+        let mut order = Order::from(serde_json::from_str::<AlpacaOrder>(ORDER).unwrap());
+        order.id = id.to_string();
+        order.status = status.to_string();
+        order.submitted_at = Some(submitted_at.to_string());
+        order
+    }
+
+    fn account() -> AccountSummary {
+        AccountSummary {
+            equity: 100_500.0,
+            cash: 50_000.0,
+            buying_power: 150_000.0,
+            last_equity: 100_000.0,
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_alpaca_payloads() {
+        let o = Order::from(serde_json::from_str::<AlpacaOrder>(ORDER).unwrap());
+        assert_eq!(o.qty, Some(10.0));
+        assert_eq!(o.filled_qty, 4.0);
+        assert!(o.is_open());
+
+        let p: AlpacaPosition = serde_json::from_str(
+            r#"{"symbol": "TSLA", "qty": "5", "side": "short", "avg_entry_price": "200",
+                "current_price": "190", "market_value": "-950", "cost_basis": "-1000",
+                "unrealized_pl": "50", "unrealized_plpc": "0.05", "exchange": "NASDAQ"}"#,
+        )
+        .unwrap();
+        assert_eq!(Position::from(p).qty, -5.0);
+    }
+
+    #[test]
+    fn snapshot_roundtrip_replaces_positions() {
+        let (_dir, pool) = test_pool();
+        assert_eq!(portfolio_get_db(&pool, "paper").unwrap().account, None);
+
+        let position = |symbol: &str| Position {
+            symbol: symbol.to_string(),
+            qty: 1.0,
+            avg_entry_price: 10.0,
+            current_price: 11.0,
+            market_value: 11.0,
+            cost_basis: 10.0,
+            unrealized_pl: 1.0,
+            unrealized_plpc: 0.1,
+        };
+        let orders = [order("a", "new", "2024-06-10T14:00:00Z"), order("b", "filled", "2024-06-10T15:00:00Z")];
+        portfolio_set_db(&pool, "paper", &account(), &[position("AAPL"), position("MSFT")], &orders, 1_000).unwrap();
+        portfolio_set_db(&pool, "paper", &account(), &[position("MSFT")], &[], 2_000).unwrap();
+
+        let snapshot = portfolio_get_db(&pool, "paper").unwrap();
+        assert_eq!(snapshot.account, Some(account()));
+        assert_eq!(snapshot.fetched_at, Some(2_000));
+        assert_eq!(snapshot.positions.len(), 1);
+        assert_eq!(snapshot.open_orders.iter().map(|o| o.id.as_str()).collect::<Vec<_>>(), vec!["a"]);
+        assert!(portfolio_get_db(&pool, "live").unwrap().positions.is_empty());
+    }
+
+    #[test]
+    fn orders_list_filters_and_upserts_status() {
+        let (_dir, pool) = test_pool();
+        order_upsert_db(&pool, "paper", &order("a", "new", "2024-06-10T14:00:00Z")).unwrap();
+        order_upsert_db(&pool, "paper", &order("b", "new", "2024-06-10T15:00:00Z")).unwrap();
+        order_upsert_db(&pool, "paper", &order("a", "filled", "2024-06-10T14:00:00Z")).unwrap();
+
+        let all = orders_list_db(&pool, &OrderFilter::default()).unwrap();
+        assert_eq!(all.iter().map(|o| o.id.as_str()).collect::<Vec<_>>(), vec!["b", "a"]);
+        assert_eq!(all[1].status, "filled");
+
+        let open = OrderFilter { open_only: true, ..Default::default() };
+        assert_eq!(orders_list_db(&pool, &open).unwrap().len(), 1);
+        let other = OrderFilter { symbol: Some("msft".to_string()), ..Default::default() };
+        assert!(orders_list_db(&pool, &other).unwrap().is_empty());
+        let live = OrderFilter { mode: Some("live".to_string()), ..Default::default() };
+        assert!(orders_list_db(&pool, &live).unwrap().is_empty());
+    }
+
+    #[test]
+    fn config_defaults_to_disabled_paper() {
+        assert_eq!(portfolio_config(&serde_json::json!({})), PortfolioConfig::default());
+        assert_eq!(PortfolioConfig::default().mode, "paper");
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+
+use crate::bridge::SidecarBridge;
+use crate::commands::config::{
+    apply_config, config_get_db, mutate_config, redact_secrets, ConfigError, ImportMode,
+};
+use crate::db::{self, DbPool};
+use crate::types::config::{AppConfig, SECRET_FIELDS};
+use crate::workspace::WorkspaceDb;
+
+/// Profiles live in the `config` table as `profile:<name>` rows.
+const PROFILE_PREFIX: &str = "profile:";
+/// `config` row holding the name of the last activated profile.
+const ACTIVE_PROFILE_KEY: &str = "activeProfile";
+
+/// A named config saved with `config_profile_save`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProfile {
+    pub name: String,
+    pub active: bool,
+    pub updated_at: String,
+}
+
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Profile name must be 1-64 characters".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid profile name '{}': use letters, digits, '-' or '_'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Name of the active profile, if one has been activated.
+pub fn active_profile_db(pool: &DbPool) -> Result<Option<String>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    Ok(conn
+        .query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            [ACTIVE_PROFILE_KEY],
+            |row| row.get(0),
+        )
+        .ok())
+}
+
+/// Saved profiles sorted by name.
+pub fn profile_list_db(pool: &DbPool) -> Result<Vec<ConfigProfile>, String> {
+    let active = active_profile_db(pool)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT substr(key, ?2), updated_at FROM config
+             WHERE key LIKE ?1 || '%' ORDER BY key",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![PROFILE_PREFIX, PROFILE_PREFIX.len() + 1],
+            |row| {
+                let name: String = row.get(0)?;
+                Ok(ConfigProfile {
+                    active: active.as_deref() == Some(name.as_str()),
+                    name,
+                    updated_at: row.get(1)?,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+/// Save the current main config as profile `name`, overwriting any existing
+/// profile with that name. API keys are not stored in profiles.
+pub fn profile_save_db(pool: &DbPool, name: &str) -> Result<(), String> {
+    validate_profile_name(name)?;
+    let mut value: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    redact_secrets(&mut value);
+    let json = serde_json::to_string(&value).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![format!("{}{}", PROFILE_PREFIX, name), json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replace the main config with profile `name`, keeping the current API keys,
+/// and mark it active. Returns the new main config.
+pub fn profile_activate_db(pool: &DbPool, name: &str) -> Result<String, ConfigError> {
+    validate_profile_name(name)?;
+    let stored: String = {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        conn.query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            [format!("{}{}", PROFILE_PREFIX, name)],
+            |row| row.get(0),
+        )
+        .map_err(|_| format!("Profile '{}' not found", name))?
+    };
+    let mut profile: serde_json::Value =
+        serde_json::from_str(&stored).map_err(|e| e.to_string())?;
+    let current: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    if let (Some(profile), Some(current)) = (profile.as_object_mut(), current.as_object()) {
+        for field in SECRET_FIELDS {
+            if let Some(secret) = current.get(*field) {
+                profile.insert(field.to_string(), secret.clone());
+            }
+        }
+    }
+    // The schema may have changed since the profile was saved
+    AppConfig::validate(&profile).map_err(ConfigError::Invalid)?;
+    let next = apply_config(pool, &profile, ImportMode::Replace)?;
+
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        [ACTIVE_PROFILE_KEY, name],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(next)
+}
+
+/// Saved config profiles, with the active one flagged.
+#[tauri::command]
+pub async fn config_profile_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Vec<ConfigProfile>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, profile_list_db).await
+}
+
+/// Save the current config under `name`.
+#[tauri::command]
+pub async fn config_profile_save(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: String,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, move |pool| profile_save_db(pool, &name)).await
+}
+
+/// Switch the main config to a saved profile and push the change to the agent.
+#[tauri::command]
+pub async fn config_profile_activate(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    name: String,
+) -> Result<String, ConfigError> {
+    mutate_config(&app, &workspace, &bridge, move |pool| {
+        profile_activate_db(pool, &name)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::config::{config_history_list_db, config_set_db};
+    use crate::migrations;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn save_and_list_profiles() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"m","anthropicApiKey":"sk"}"#).unwrap();
+        profile_save_db(&pool, "conservative").unwrap();
+        profile_save_db(&pool, "aggressive").unwrap();
+
+        let profiles = profile_list_db(&pool).unwrap();
+        let names: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["aggressive", "conservative"]);
+        assert!(profiles.iter().all(|p| !p.active));
+        assert_eq!(active_profile_db(&pool).unwrap(), None);
+    }
+
+    #[test]
+    fn save_rejects_bad_names() {
+        let (_dir, pool) = test_pool();
+        assert!(profile_save_db(&pool, "").is_err());
+        assert!(profile_save_db(&pool, "a/b").is_err());
+    }
+
+    #[test]
+    fn activate_swaps_config_and_keeps_api_keys() {
+        let (_dir, pool) = test_pool();
+        config_set_db(&pool, r#"{"model":"fast","anthropicApiKey":"old"}"#).unwrap();
+        profile_save_db(&pool, "aggressive").unwrap();
+        config_set_db(&pool, r#"{"model":"slow","anthropicApiKey":"new"}"#).unwrap();
+
+        let next = profile_activate_db(&pool, "aggressive").unwrap();
+        let next: serde_json::Value = serde_json::from_str(&next).unwrap();
+        assert_eq!(next["model"], "fast");
+        assert_eq!(next["anthropicApiKey"], "new");
+        assert_eq!(active_profile_db(&pool).unwrap().as_deref(), Some("aggressive"));
+        assert!(profile_list_db(&pool).unwrap()[0].active);
+        assert_eq!(config_history_list_db(&pool).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn activate_unknown_profile_fails() {
+        let (_dir, pool) = test_pool();
+        assert!(profile_activate_db(&pool, "missing").is_err());
+        assert_eq!(config_get_db(&pool).unwrap(), "{}");
+    }
+}
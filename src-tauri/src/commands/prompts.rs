@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, DbPool};
+use crate::workspace::WorkspaceDb;
+
+/// Prompt templates the agent reads from `agent:start`. A prompt with no
+/// stored version uses the agent's built-in template.
+pub const PROMPT_NAMES: &[&str] = &["analysis"];
+
+/// One version of a prompt template. The newest version of a prompt is the
+/// active one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptVersion {
+    pub name: String,
+    pub version: i64,
+    pub body: String,
+    /// The version this one restored, when it was created by `prompts_revert`.
+    pub reverted_from: Option<i64>,
+    pub created_at: String,
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if PROMPT_NAMES.contains(&name) {
+        return Ok(());
+    }
+    Err(format!(
+        "Unknown prompt: '{}'. Must be one of: {}",
+        name,
+        PROMPT_NAMES.join(", ")
+    ))
+}
+
+fn version_from_row(row: &rusqlite::Row) -> rusqlite::Result<PromptVersion> {
+    Ok(PromptVersion {
+        name: row.get(0)?,
+        version: row.get(1)?,
+        body: row.get(2)?,
+        reverted_from: row.get(3)?,
+        created_at: row.get(4)?,
+    })
+}
+
+/// `version` of prompt `name`, or its active version when `version` is `None`.
+pub fn prompts_get_db(pool: &DbPool, name: &str, version: Option<i64>) -> Result<Option<PromptVersion>, String> {
+    validate_name(name)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    match conn.query_row(
+        "SELECT name, version, body, reverted_from, created_at FROM prompts
+         WHERE name = ?1 AND (?2 IS NULL OR version = ?2)
+         ORDER BY version DESC LIMIT 1",
+        rusqlite::params![name, version],
+        version_from_row,
+    ) {
+        Ok(found) => Ok(Some(found)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn insert_version(pool: &DbPool, name: &str, body: &str, reverted_from: Option<i64>) -> Result<PromptVersion, String> {
+    {
+        let conn = pool.get().map_err(|e| e.to_string())?;
+        conn.execute(
+            "INSERT INTO prompts (name, version, body, reverted_from)
+             SELECT ?1, COALESCE(MAX(version), 0) + 1, ?2, ?3 FROM prompts WHERE name = ?1",
+            rusqlite::params![name, body, reverted_from],
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    prompts_get_db(pool, name, None)?.ok_or_else(|| format!("Prompt {} was not saved", name))
+}
+
+/// Save `body` as the new active version of prompt `name`. Saving the active
+/// body again returns the active version without adding one.
+pub fn prompts_set_db(pool: &DbPool, name: &str, body: &str) -> Result<PromptVersion, String> {
+    validate_name(name)?;
+    if body.trim().is_empty() {
+        return Err("Prompt body must not be empty".to_string());
+    }
+    match prompts_get_db(pool, name, None)? {
+        Some(active) if active.body == body => Ok(active),
+        _ => insert_version(pool, name, body, None),
+    }
+}
+
+/// Every version of prompt `name`, newest first, or with no `name` the active
+/// version of each stored prompt.
+pub fn prompts_list_db(pool: &DbPool, name: Option<&str>) -> Result<Vec<PromptVersion>, String> {
+    if let Some(name) = name {
+        validate_name(name)?;
+    }
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT name, version, body, reverted_from, created_at FROM prompts p
+             WHERE (?1 IS NULL AND version = (SELECT MAX(version) FROM prompts WHERE name = p.name))
+                OR name = ?1
+             ORDER BY name, version DESC",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt.query_map([name], version_from_row).map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Make `version` of prompt `name` active again by saving its body as a new
+/// version, so the revert itself shows up in the history.
+pub fn prompts_revert_db(pool: &DbPool, name: &str, version: i64) -> Result<PromptVersion, String> {
+    let target = prompts_get_db(pool, name, Some(version))?
+        .ok_or_else(|| format!("Prompt {} version {} not found", name, version))?;
+    match prompts_get_db(pool, name, None)? {
+        Some(active) if active.body == target.body => Ok(active),
+        _ => insert_version(pool, name, &target.body, Some(version)),
+    }
+}
+
+/// The `prompts` param of `agent:start`: each stored prompt's active version and body.
+pub fn prompts_agent_params(active: &[PromptVersion]) -> serde_json::Value {
+    active
+        .iter()
+        .map(|p| {
+            (
+                p.name.clone(),
+                serde_json::json!({ "version": p.version, "body": p.body }),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+/// `version` of prompt `name`, or its active version. `None` means the agent
+/// uses its built-in template.
+#[tauri::command]
+pub async fn prompts_get(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: String,
+    version: Option<i64>,
+) -> Result<Option<PromptVersion>, String> {
+    db::run_blocking(&workspace.pool(), move |pool| prompts_get_db(pool, &name, version)).await
+}
+
+/// Save a new version of a prompt. The agent picks it up on its next start.
+#[tauri::command]
+pub async fn prompts_set(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: String,
+    body: String,
+) -> Result<PromptVersion, String> {
+    db::run_blocking(&workspace.pool(), move |pool| prompts_set_db(pool, &name, &body)).await
+}
+
+/// A prompt's version history, or the active version of every stored prompt.
+#[tauri::command]
+pub async fn prompts_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: Option<String>,
+) -> Result<Vec<PromptVersion>, String> {
+    db::run_blocking(&workspace.pool(), move |pool| prompts_list_db(pool, name.as_deref())).await
+}
+
+/// Restore a previous version of a prompt as its active version.
+#[tauri::command]
+pub async fn prompts_revert(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: String,
+    version: i64,
+) -> Result<PromptVersion, String> {
+    db::run_blocking(&workspace.pool(), move |pool| prompts_revert_db(pool, &name, version)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    #[test]
+    fn versions_accumulate_and_revert_appends() {
+        let pool = test_pool();
+        assert_eq!(prompts_get_db(&pool, "analysis", None).unwrap(), None);
+        assert!(prompts_set_db(&pool, "analyis", "typo").is_err());
+        assert!(prompts_set_db(&pool, "analysis", "  ").is_err());
+
+        assert_eq!(prompts_set_db(&pool, "analysis", "v1").unwrap().version, 1);
+        assert_eq!(prompts_set_db(&pool, "analysis", "v2").unwrap().version, 2);
+        assert_eq!(prompts_set_db(&pool, "analysis", "v2").unwrap().version, 2);
+
+        let reverted = prompts_revert_db(&pool, "analysis", 1).unwrap();
+        assert_eq!((reverted.version, reverted.body.as_str()), (3, "v1"));
+        assert_eq!(reverted.reverted_from, Some(1));
+        assert_eq!(prompts_revert_db(&pool, "analysis", 1).unwrap().version, 3);
+        assert!(prompts_revert_db(&pool, "analysis", 9).is_err());
+
+        let history: Vec<i64> = prompts_list_db(&pool, Some("analysis"))
+            .unwrap()
+            .iter()
+            .map(|p| p.version)
+            .collect();
+        assert_eq!(history, [3, 2, 1]);
+        assert_eq!(prompts_get_db(&pool, "analysis", Some(2)).unwrap().unwrap().body, "v2");
+
+        let active = prompts_list_db(&pool, None).unwrap();
+        assert_eq!(active.len(), 1);
+        assert_eq!(
+            prompts_agent_params(&active),
+            serde_json::json!({"analysis": {"version": 3, "body": "v1"}})
+        );
+    }
+}
@@ -0,0 +1,408 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::agent::{llm_key_configured, load_app_config};
+use crate::db::{self, DbPool};
+use crate::time::now_millis;
+use crate::types::provider::{ProviderHealth, ProviderHealthStatus};
+use crate::workspace::WorkspaceDb;
+
+/// Health changes kept per provider; older ones are dropped as new ones arrive.
+const HISTORY_PER_PROVIDER: i64 = 500;
+
+/// LLM providers the agent can route to.
+pub const KNOWN_PROVIDERS: &[&str] = &["anthropic", "openrouter"];
+
+/// The `providers` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProvidersConfig {
+    /// Providers in the order the agent tries them.
+    pub fallback: Vec<String>,
+    /// How long a provider last reported offline or rate limited is skipped.
+    pub cooldown_secs: u64,
+}
+
+impl Default for ProvidersConfig {
+    fn default() -> Self {
+        Self {
+            fallback: KNOWN_PROVIDERS.iter().map(|p| p.to_string()).collect(),
+            cooldown_secs: 300,
+        }
+    }
+}
+
+/// Parse the `providers` section of the app config.
+pub fn providers_config(app_config: &serde_json::Value) -> ProvidersConfig {
+    app_config
+        .get("providers")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// One provider of the fallback chain and why it is or is not routed to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRoute {
+    pub provider_id: String,
+    pub has_key: bool,
+    /// Last reported health, if the provider ever reported any.
+    pub status: Option<ProviderHealthStatus>,
+    /// Epoch millis the provider's cooldown ends, while it lasts.
+    pub cooldown_until: Option<u64>,
+}
+
+/// The providers the agent should use, in order, with the chain they came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderRouting {
+    pub order: Vec<String>,
+    pub chain: Vec<ProviderRoute>,
+}
+
+/// One recorded health change of an LLM provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderHealthEntry {
+    pub provider_id: String,
+    pub status: ProviderHealthStatus,
+    pub latency_ms: u64,
+    pub last_error: Option<String>,
+    /// Epoch millis the change was recorded.
+    pub recorded_at: u64,
+}
+
+fn status_str(status: ProviderHealthStatus) -> Result<String, String> {
+    Ok(serde_json::to_value(status)
+        .map_err(|e| e.to_string())?
+        .as_str()
+        .unwrap_or("offline")
+        .to_string())
+}
+
+fn parse_status(s: &str) -> ProviderHealthStatus {
+    serde_json::from_str(&format!("\"{}\"", s)).unwrap_or(ProviderHealthStatus::Offline)
+}
+
+/// Store `health` as the provider's current state and append it to its history.
+pub fn providers_health_set_db(pool: &DbPool, health: &ProviderHealth, now_ms: u64) -> Result<(), String> {
+    let status = status_str(health.status)?;
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO provider_health (provider_id, status, latency_ms, last_success, last_error, cooldown_until)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(provider_id) DO UPDATE SET
+            status = ?2, latency_ms = ?3, last_success = ?4, last_error = ?5, cooldown_until = ?6,
+            updated_at = datetime('now')",
+        rusqlite::params![
+            health.provider_id,
+            status,
+            health.latency_ms as i64,
+            health.last_success.map(|t| t as i64),
+            health.last_error,
+            health.cooldown_until.map(|t| t as i64),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "INSERT INTO provider_health_history (provider_id, status, latency_ms, last_error, recorded_at)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            health.provider_id,
+            status,
+            health.latency_ms as i64,
+            health.last_error,
+            now_ms as i64
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.execute(
+        "DELETE FROM provider_health_history WHERE provider_id = ?1 AND id NOT IN (
+            SELECT id FROM provider_health_history WHERE provider_id = ?1 ORDER BY id DESC LIMIT ?2
+         )",
+        rusqlite::params![health.provider_id, HISTORY_PER_PROVIDER],
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+pub fn providers_health_db(pool: &DbPool) -> Result<HashMap<String, ProviderHealth>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT provider_id, status, latency_ms, last_success, last_error, cooldown_until FROM provider_health",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let status: String = row.get(1)?;
+            Ok(ProviderHealth {
+                provider_id: row.get(0)?,
+                status: parse_status(&status),
+                latency_ms: row.get::<_, i64>(2)?.max(0) as u64,
+                last_success: row.get::<_, Option<i64>>(3)?.map(|t| t.max(0) as u64),
+                last_error: row.get(4)?,
+                cooldown_until: row.get::<_, Option<i64>>(5)?.map(|t| t.max(0) as u64),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut map = HashMap::new();
+    for row in rows {
+        let health = row.map_err(|e| e.to_string())?;
+        map.insert(health.provider_id.clone(), health);
+    }
+    Ok(map)
+}
+
+/// Recorded health changes, newest first, optionally for one provider.
+pub fn providers_health_history_db(
+    pool: &DbPool,
+    provider_id: Option<&str>,
+    limit: Option<u32>,
+) -> Result<Vec<ProviderHealthEntry>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT provider_id, status, latency_ms, last_error, recorded_at FROM provider_health_history
+             WHERE ?1 IS NULL OR provider_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![provider_id, limit.map(i64::from).unwrap_or(-1)],
+            |row| {
+                let status: String = row.get(1)?;
+                Ok(ProviderHealthEntry {
+                    provider_id: row.get(0)?,
+                    status: parse_status(&status),
+                    latency_ms: row.get::<_, i64>(2)?.max(0) as u64,
+                    last_error: row.get(3)?,
+                    recorded_at: row.get::<_, i64>(4)?.max(0) as u64,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Epoch millis of each provider's latest recorded health change.
+fn last_changes_db(pool: &DbPool) -> Result<HashMap<String, u64>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT provider_id, MAX(recorded_at) FROM provider_health_history GROUP BY provider_id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?.max(0) as u64))
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<HashMap<_, _>, _>>().map_err(|e| e.to_string())
+}
+
+/// Route the fallback chain: providers without a key are dropped, and so are
+/// providers cooling down, either by their own `cooldownUntil` or for
+/// `cooldownSecs` after reporting offline or rate limited. When every keyed
+/// provider is cooling down the whole keyed chain is kept, since trying beats
+/// not analyzing at all.
+pub fn provider_routing(
+    config: &ProvidersConfig,
+    has_key: impl Fn(&str) -> bool,
+    health: &HashMap<String, ProviderHealth>,
+    last_changes: &HashMap<String, u64>,
+    now_ms: u64,
+) -> ProviderRouting {
+    let chain: Vec<ProviderRoute> = config
+        .fallback
+        .iter()
+        .map(|id| {
+            let health = health.get(id);
+            let reported = health.and_then(|h| h.cooldown_until);
+            let implied = health
+                .filter(|h| {
+                    matches!(
+                        h.status,
+                        ProviderHealthStatus::Offline | ProviderHealthStatus::RateLimited
+                    )
+                })
+                .and_then(|_| last_changes.get(id))
+                .map(|t| t + config.cooldown_secs * 1_000);
+            ProviderRoute {
+                provider_id: id.clone(),
+                has_key: has_key(id),
+                status: health.map(|h| h.status),
+                cooldown_until: reported.max(implied).filter(|&t| t > now_ms),
+            }
+        })
+        .collect();
+    let keyed = chain.iter().filter(|r| r.has_key);
+    let mut order: Vec<String> = keyed
+        .clone()
+        .filter(|r| r.cooldown_until.is_none())
+        .map(|r| r.provider_id.clone())
+        .collect();
+    if order.is_empty() {
+        order = keyed.map(|r| r.provider_id.clone()).collect();
+    }
+    ProviderRouting { order, chain }
+}
+
+/// Route the configured chain against the persisted provider health.
+pub fn providers_routing_db(
+    pool: &DbPool,
+    app_config: &serde_json::Value,
+    has_key: impl Fn(&str) -> bool,
+    now_ms: u64,
+) -> Result<ProviderRouting, String> {
+    Ok(provider_routing(
+        &providers_config(app_config),
+        has_key,
+        &providers_health_db(pool)?,
+        &last_changes_db(pool)?,
+        now_ms,
+    ))
+}
+
+// Tauri command wrappers
+#[tauri::command]
+pub async fn providers_health(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<HashMap<String, ProviderHealth>, String> {
+    db::run_blocking(&workspace.pool(), providers_health_db).await
+}
+
+#[tauri::command]
+pub async fn providers_health_history(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    provider_id: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<ProviderHealthEntry>, String> {
+    db::run_blocking(&workspace.pool(), move |pool| {
+        providers_health_history_db(pool, provider_id.as_deref(), limit)
+    })
+    .await
+}
+
+/// The providers `agent_start` would route to right now.
+#[tauri::command]
+pub async fn providers_effective(workspace: tauri::State<'_, WorkspaceDb>) -> Result<ProviderRouting, String> {
+    db::run_blocking(&workspace.pool(), |pool| {
+        let app_config = load_app_config(pool)?;
+        let keys = KNOWN_PROVIDERS
+            .iter()
+            .map(|p| Ok((*p, llm_key_configured(&app_config, p)?)))
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        providers_routing_db(
+            pool,
+            &app_config,
+            |id| keys.get(id).copied().unwrap_or(false),
+            now_millis(),
+        )
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    fn health(provider_id: &str, status: ProviderHealthStatus, last_error: Option<&str>) -> ProviderHealth {
+        ProviderHealth {
+            provider_id: provider_id.to_string(),
+            status,
+            latency_ms: 250,
+            last_success: Some(1_000),
+            last_error: last_error.map(str::to_string),
+            cooldown_until: None,
+        }
+    }
+
+    #[test]
+    fn health_changes_update_current_state_and_history() {
+        let pool = test_pool();
+        providers_health_set_db(&pool, &health("anthropic", ProviderHealthStatus::Healthy, None), 1_000).unwrap();
+        providers_health_set_db(&pool, &health("openrouter", ProviderHealthStatus::Healthy, None), 1_500).unwrap();
+        let mut limited = health("anthropic", ProviderHealthStatus::RateLimited, Some("429"));
+        limited.cooldown_until = Some(9_000);
+        providers_health_set_db(&pool, &limited, 2_000).unwrap();
+
+        let current = providers_health_db(&pool).unwrap();
+        assert_eq!(current.len(), 2);
+        assert_eq!(current["anthropic"].status, ProviderHealthStatus::RateLimited);
+        assert_eq!(current["anthropic"].cooldown_until, Some(9_000));
+        assert_eq!(current["openrouter"].status, ProviderHealthStatus::Healthy);
+
+        let history = providers_health_history_db(&pool, Some("anthropic"), None).unwrap();
+        let statuses: Vec<_> = history.iter().map(|e| (e.status, e.recorded_at)).collect();
+        assert_eq!(
+            statuses,
+            [
+                (ProviderHealthStatus::RateLimited, 2_000),
+                (ProviderHealthStatus::Healthy, 1_000)
+            ]
+        );
+        assert_eq!(history[0].last_error.as_deref(), Some("429"));
+        assert_eq!(providers_health_history_db(&pool, None, Some(1)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn routing_skips_keyless_and_cooling_providers() {
+        let config = ProvidersConfig::default();
+        let mut reports = HashMap::new();
+        let mut last_changes = HashMap::new();
+        let all_keys = |_: &str| true;
+
+        let routing = provider_routing(&config, all_keys, &reports, &last_changes, 10_000);
+        assert_eq!(routing.order, ["anthropic", "openrouter"]);
+        let routing = provider_routing(&config, |id| id == "openrouter", &reports, &last_changes, 10_000);
+        assert_eq!(routing.order, ["openrouter"]);
+        assert!(!routing.chain[0].has_key);
+
+        // Rate limited a minute ago: skipped for the configured cooldown
+        reports.insert(
+            "anthropic".to_string(),
+            health("anthropic", ProviderHealthStatus::RateLimited, None),
+        );
+        last_changes.insert("anthropic".to_string(), 10_000);
+        let routing = provider_routing(&config, all_keys, &reports, &last_changes, 70_000);
+        assert_eq!(routing.order, ["openrouter"]);
+        assert_eq!(routing.chain[0].cooldown_until, Some(310_000));
+        let routing = provider_routing(&config, all_keys, &reports, &last_changes, 310_001);
+        assert_eq!(routing.order, ["anthropic", "openrouter"]);
+
+        // A reported cooldown applies whatever the status
+        let mut openrouter = health("openrouter", ProviderHealthStatus::Degraded, None);
+        openrouter.cooldown_until = Some(500_000);
+        reports.insert("openrouter".to_string(), openrouter);
+        let routing = provider_routing(&config, all_keys, &reports, &last_changes, 70_000);
+        assert_eq!(
+            routing.order,
+            ["anthropic", "openrouter"],
+            "all cooling keeps the keyed chain"
+        );
+        let routing = provider_routing(&config, all_keys, &reports, &last_changes, 400_000);
+        assert_eq!(routing.order, ["anthropic"]);
+
+        let reversed = ProvidersConfig {
+            fallback: vec!["openrouter".to_string(), "anthropic".to_string()],
+            ..ProvidersConfig::default()
+        };
+        assert_eq!(
+            provider_routing(&reversed, all_keys, &HashMap::new(), &HashMap::new(), 0).order,
+            ["openrouter", "anthropic"]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::bridge::SidecarBridge;
+use crate::commands::agent::{last_start_db, load_app_config, start_agent, LastAgentStart, AGENT_TRADING_MODE};
+use crate::commands::calendar::{calendar_load_db, Calendar};
+use crate::commands::config::{config_update_db, mutate_config, ConfigError};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::market_calendar::MarketSession;
+use crate::tasks::TaskOutcome;
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// Whether the last check found the agent's run window open.
+static WAS_IN_WINDOW: AtomicBool = AtomicBool::new(false);
+
+/// The `schedule` section of the app config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ScheduleConfig {
+    /// Start the agent at the open and stop it at the close of each NYSE session.
+    pub enabled: bool,
+    /// Start this long before the opening bell, e.g. to warm up indicators.
+    pub start_before_open_minutes: u64,
+    /// Keep running this long after the closing bell.
+    pub stop_after_close_minutes: u64,
+}
+
+/// Parse the `schedule` section of the app config.
+pub fn schedule_config(app_config: &serde_json::Value) -> ScheduleConfig {
+    app_config
+        .get("schedule")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    Start,
+    Stop,
+}
+
+/// A scheduled start or stop: upcoming in `schedule_get`, or carried out in a
+/// `schedule:transition` event.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledTransition {
+    pub action: ScheduledAction,
+    /// Epoch millis the transition is due.
+    pub at: i64,
+    /// Why the transition failed, for events only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The schedule settings with the market state they act on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleStatus {
+    pub config: ScheduleConfig,
+    pub market_open: bool,
+    /// The session in progress, or the next one.
+    pub session: Option<MarketSession>,
+    /// What the scheduler will do next, when enabled.
+    pub next_transition: Option<ScheduledTransition>,
+}
+
+fn minutes_ms(minutes: u64) -> i64 {
+    minutes.saturating_mul(60_000) as i64
+}
+
+/// The session whose run window (widened by the configured margins) is in
+/// progress at `now`, or else the next one.
+fn window_session(config: &ScheduleConfig, calendar: &Calendar, now: i64) -> Option<MarketSession> {
+    calendar.next_session(now - minutes_ms(config.stop_after_close_minutes))
+}
+
+/// Whether the agent should be running at `now` (epoch millis).
+pub fn in_run_window(config: &ScheduleConfig, calendar: &Calendar, now: i64) -> bool {
+    window_session(config, calendar, now)
+        .is_some_and(|s| s.open_at - minutes_ms(config.start_before_open_minutes) <= now)
+}
+
+/// Schedule state at `now` (epoch millis).
+pub fn schedule_status(config: ScheduleConfig, calendar: &Calendar, now: i64) -> ScheduleStatus {
+    let session = window_session(&config, calendar, now);
+    let next_transition = session.as_ref().filter(|_| config.enabled).map(|s| {
+        if in_run_window(&config, calendar, now) {
+            ScheduledTransition {
+                action: ScheduledAction::Stop,
+                at: s.close_at + minutes_ms(config.stop_after_close_minutes),
+                error: None,
+            }
+        } else {
+            ScheduledTransition {
+                action: ScheduledAction::Start,
+                at: s.open_at - minutes_ms(config.start_before_open_minutes),
+                error: None,
+            }
+        }
+    });
+    ScheduleStatus {
+        config,
+        market_open: calendar.is_open(now),
+        session,
+        next_transition,
+    }
+}
+
+/// Start the agent with its last `agent_start` parameters, or the defaults.
+fn scheduled_start(app: &AppHandle, pool: &DbPool) -> Result<(), String> {
+    let start = last_start_db(pool)?.unwrap_or_else(|| LastAgentStart {
+        config: serde_json::json!({}),
+        mode: AGENT_TRADING_MODE.to_string(),
+    });
+    let bridge = app.state::<SidecarBridge>();
+    tauri::async_runtime::block_on(start_agent(app.clone(), pool, &bridge, start))
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+/// Scheduled task: start the agent when a session's run window opens and stop
+/// it when the window closes, while `schedule.enabled` is set. Agents started
+/// by hand outside the window are left alone.
+pub fn schedule_task(app: &AppHandle) -> Result<TaskOutcome, String> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let config = schedule_config(&load_app_config(&pool)?);
+    let calendar = calendar_load_db(&pool)?;
+    let now = now_millis() as i64;
+    let in_window = config.enabled && in_run_window(&config, &calendar, now);
+    if in_window == WAS_IN_WINDOW.swap(in_window, Ordering::SeqCst) {
+        return Ok(TaskOutcome::Skipped);
+    }
+    // Disabling the schedule mid-session leaves the agent running
+    if !config.enabled {
+        return Ok(TaskOutcome::Skipped);
+    }
+
+    let (action, result) = if in_window {
+        tracing::info!("Market session starting, starting agent");
+        (ScheduledAction::Start, scheduled_start(app, &pool))
+    } else {
+        tracing::info!("Market session over, stopping agent");
+        let bridge = app.state::<SidecarBridge>();
+        (ScheduledAction::Stop, bridge.shutdown(crate::SHUTDOWN_GRACE))
+    };
+    let transition = ScheduledTransition {
+        action,
+        at: now,
+        error: result.clone().err(),
+    };
+    let _ = emit_event(app, event_names::SCHEDULE_TRANSITION, transition);
+    result.map(|_| TaskOutcome::Ran)
+}
+
+/// The schedule settings, market state, and next scheduled transition.
+#[tauri::command]
+pub async fn schedule_get(workspace: tauri::State<'_, WorkspaceDb>) -> Result<ScheduleStatus, String> {
+    let pool = workspace.pool();
+    let (config, calendar) = db::run_blocking(&pool, |pool| {
+        Ok((schedule_config(&load_app_config(pool)?), calendar_load_db(pool)?))
+    })
+    .await?;
+    Ok(schedule_status(config, &calendar, now_millis() as i64))
+}
+
+/// Replace the schedule settings and return the resulting status.
+#[tauri::command]
+pub async fn schedule_set(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    config: ScheduleConfig,
+) -> Result<ScheduleStatus, ConfigError> {
+    let patch = serde_json::json!({ "schedule": config }).to_string();
+    mutate_config(&app, &workspace, &bridge, move |pool| config_update_db(pool, &patch)).await?;
+    let calendar = db::run_blocking(&workspace.pool(), calendar_load_db).await?;
+    Ok(schedule_status(config, &calendar, now_millis() as i64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc_millis(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        chrono::NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp_millis()
+    }
+
+    fn enabled(before: u64, after: u64) -> ScheduleConfig {
+        ScheduleConfig {
+            enabled: true,
+            start_before_open_minutes: before,
+            stop_after_close_minutes: after,
+        }
+    }
+
+    #[test]
+    fn config_defaults_to_disabled() {
+        assert_eq!(schedule_config(&serde_json::json!({})), ScheduleConfig::default());
+        let config = schedule_config(&serde_json::json!({"schedule": {"enabled": true}}));
+        assert_eq!(config, enabled(0, 0));
+    }
+
+    #[test]
+    fn run_window_includes_margins() {
+        // 2025-06-16 session: 13:30–20:00 UTC
+        let config = enabled(15, 30);
+        assert!(!in_run_window(&config, &Calendar::builtin(), utc_millis(2025, 6, 16, 13, 14)));
+        assert!(in_run_window(&config, &Calendar::builtin(), utc_millis(2025, 6, 16, 13, 15)));
+        assert!(in_run_window(&config, &Calendar::builtin(), utc_millis(2025, 6, 16, 20, 29)));
+        assert!(!in_run_window(&config, &Calendar::builtin(), utc_millis(2025, 6, 16, 20, 30)));
+        assert!(!in_run_window(&enabled(0, 0), &Calendar::builtin(), utc_millis(2025, 6, 16, 20, 0)));
+    }
+
+    #[test]
+    fn status_reports_next_transition() {
+        let config = enabled(0, 0);
+        let status = schedule_status(config, &Calendar::builtin(), utc_millis(2025, 6, 16, 15, 0));
+        assert!(status.market_open);
+        let next = status.next_transition.unwrap();
+        assert_eq!(next.action, ScheduledAction::Stop);
+        assert_eq!(next.at, utc_millis(2025, 6, 16, 20, 0));
+
+        // Saturday: next start is Monday's open
+        let status = schedule_status(config, &Calendar::builtin(), utc_millis(2025, 6, 14, 15, 0));
+        assert!(!status.market_open);
+        let next = status.next_transition.unwrap();
+        assert_eq!(next.action, ScheduledAction::Start);
+        assert_eq!(next.at, utc_millis(2025, 6, 16, 13, 30));
+
+        let disabled = schedule_status(ScheduleConfig::default(), &Calendar::builtin(), utc_millis(2025, 6, 16, 15, 0));
+        assert!(disabled.next_transition.is_none());
+    }
+}
@@ -1,6 +1,19 @@
-use crate::db::DbPool;
-use crate::types::data::{SourceHealth, SourceHealthStatus};
+pub mod rss;
+pub mod yahoo;
+
+use crate::bridge::SidecarBridge;
+use crate::csv_source::{self, CsvIngestReport};
+use crate::db::{self, DbPool};
+use crate::types::data::{SourceConfig, SourceHealth, SourceHealthStatus, SourceType};
+use crate::workspace::WorkspaceDb;
 use std::collections::HashMap;
+use tracing::warn;
+
+/// Ids of the Alpaca streams the agent registers itself from `agent_start`,
+/// and of the app's own native stream.
+const RESERVED_SOURCE_IDS: &[&str] = &["alpaca-stream", "alpaca-crypto-stream", "alpaca-native"];
+/// Shortest poll interval accepted for polling sources.
+const MIN_POLL_INTERVAL_MS: u64 = 1_000;
 
 pub fn sources_health_set_db(pool: &DbPool, health: &SourceHealth) -> Result<(), String> {
     let conn = pool.get().map_err(|e| e.to_string())?;
@@ -63,8 +76,312 @@ pub fn sources_health_db(pool: &DbPool) -> Result<HashMap<String, SourceHealth>,
 
 // Tauri command wrapper
 #[tauri::command]
-pub fn sources_health(
-    pool: tauri::State<'_, DbPool>,
+pub async fn sources_health(
+    workspace: tauri::State<'_, WorkspaceDb>,
 ) -> Result<HashMap<String, SourceHealth>, String> {
-    sources_health_db(&pool)
+    let pool = workspace.pool();
+    db::run_blocking(&pool, sources_health_db).await
+}
+
+/// Ingest a CSV file now, as if it had been dropped into the data dir.
+#[tauri::command]
+pub async fn sources_csv_ingest(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+) -> Result<CsvIngestReport, String> {
+    let pool = workspace.pool();
+    let ingest = db::run_blocking(&pool, move |pool| {
+        let config = csv_source::csv_source_config(&crate::commands::config::config_effective_db(pool)?);
+        csv_source::ingest_file(pool, std::path::Path::new(&path), &config)
+    })
+    .await?;
+    Ok(csv_source::publish_ingest(&app, ingest))
+}
+
+/// Check a source definition before it is stored.
+pub fn validate_source(source: &SourceConfig) -> Result<(), String> {
+    let id_ok = !source.id.is_empty()
+        && source
+            .id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !id_ok {
+        return Err(format!(
+            "Invalid source id '{}': use lowercase letters, digits, '-' and '_'",
+            source.id
+        ));
+    }
+    if RESERVED_SOURCE_IDS.contains(&source.id.as_str()) {
+        return Err(format!("Source id '{}' is reserved", source.id));
+    }
+    if source.name.trim().is_empty() {
+        return Err("Source name must not be empty".to_string());
+    }
+    if source.plugin.trim().is_empty() {
+        return Err("Source plugin must not be empty".to_string());
+    }
+    if !source.config.is_object() {
+        return Err("Source config must be an object".to_string());
+    }
+    let interval_ok = source.poll_interval_ms.is_some_and(|ms| ms >= MIN_POLL_INTERVAL_MS);
+    if source.source_type == SourceType::Polling && !interval_ok {
+        return Err(format!(
+            "Polling sources need a poll interval of at least {MIN_POLL_INTERVAL_MS} ms"
+        ));
+    }
+    let has_directory = source
+        .config
+        .get("directory")
+        .and_then(|d| d.as_str())
+        .is_some_and(|d| !d.is_empty());
+    if source.source_type == SourceType::File && !has_directory {
+        return Err("File sources need a 'directory' in their config".to_string());
+    }
+    Ok(())
+}
+
+fn source_type_str(source_type: SourceType) -> Result<String, String> {
+    serde_json::to_value(source_type)
+        .map_err(|e| e.to_string())
+        .map(|v| v.as_str().unwrap_or("polling").to_string())
+}
+
+fn source_from_row(row: &rusqlite::Row) -> rusqlite::Result<SourceConfig> {
+    let type_str: String = row.get(2)?;
+    let config: String = row.get(4)?;
+    Ok(SourceConfig {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        source_type: serde_json::from_str(&format!("\"{}\"", type_str))
+            .unwrap_or(SourceType::Polling),
+        plugin: row.get(3)?,
+        config: serde_json::from_str(&config).unwrap_or_else(|_| serde_json::json!({})),
+        poll_interval_ms: row.get(5)?,
+        enabled: row.get(6)?,
+    })
+}
+
+const SOURCE_COLUMNS: &str = "id, name, type, plugin, config, poll_interval_ms, enabled";
+
+/// All configured sources, ordered by id.
+pub fn sources_list_db(pool: &DbPool) -> Result<Vec<SourceConfig>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(&format!("SELECT {SOURCE_COLUMNS} FROM sources ORDER BY id"))
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], source_from_row)
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+pub fn sources_get_db(pool: &DbPool, id: &str) -> Result<Option<SourceConfig>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let result = conn.query_row(
+        &format!("SELECT {SOURCE_COLUMNS} FROM sources WHERE id = ?1"),
+        [id],
+        source_from_row,
+    );
+    match result {
+        Ok(source) => Ok(Some(source)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Store a new source; fails if the id is taken.
+pub fn sources_add_db(pool: &DbPool, source: &SourceConfig) -> Result<(), String> {
+    validate_source(source)?;
+    if sources_get_db(pool, &source.id)?.is_some() {
+        return Err(format!("Source already exists: {}", source.id));
+    }
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO sources (id, name, type, plugin, config, poll_interval_ms, enabled)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        rusqlite::params![
+            source.id,
+            source.name,
+            source_type_str(source.source_type)?,
+            source.plugin,
+            source.config.to_string(),
+            source.poll_interval_ms,
+            source.enabled,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replace the definition of an existing source.
+pub fn sources_update_db(pool: &DbPool, source: &SourceConfig) -> Result<(), String> {
+    validate_source(source)?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let changed = conn
+        .execute(
+            "UPDATE sources SET name = ?2, type = ?3, plugin = ?4, config = ?5,
+                poll_interval_ms = ?6, enabled = ?7, updated_at = datetime('now')
+             WHERE id = ?1",
+            rusqlite::params![
+                source.id,
+                source.name,
+                source_type_str(source.source_type)?,
+                source.plugin,
+                source.config.to_string(),
+                source.poll_interval_ms,
+                source.enabled,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Source not found: {}", source.id));
+    }
+    Ok(())
+}
+
+/// Delete a source and its health row.
+pub fn sources_remove_db(pool: &DbPool, id: &str) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let changed = conn
+        .execute("DELETE FROM sources WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    if changed == 0 {
+        return Err(format!("Source not found: {id}"));
+    }
+    conn.execute("DELETE FROM source_health WHERE source_id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Tell a running agent about a source change; it picks up the stored
+/// sources on its next start either way.
+fn push_source_change(bridge: &SidecarBridge, method: &str, params: serde_json::Value) {
+    if bridge.is_running() {
+        if let Err(e) = bridge.send_notification(method, Some(params)) {
+            warn!(error = %e, method, "Failed to push source change to agent");
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn sources_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Vec<SourceConfig>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, sources_list_db).await
+}
+
+#[tauri::command]
+pub async fn sources_add(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    source: SourceConfig,
+) -> Result<SourceConfig, String> {
+    let pool = workspace.pool();
+    let stored = source.clone();
+    db::run_blocking(&pool, move |pool| sources_add_db(pool, &stored)).await?;
+    push_source_change(&bridge, "sources:upsert", serde_json::json!({ "source": source }));
+    Ok(source)
+}
+
+#[tauri::command]
+pub async fn sources_update(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    source: SourceConfig,
+) -> Result<SourceConfig, String> {
+    let pool = workspace.pool();
+    let stored = source.clone();
+    db::run_blocking(&pool, move |pool| sources_update_db(pool, &stored)).await?;
+    push_source_change(&bridge, "sources:upsert", serde_json::json!({ "source": source }));
+    Ok(source)
+}
+
+#[tauri::command]
+pub async fn sources_remove(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    id: String,
+) -> Result<(), String> {
+    let pool = workspace.pool();
+    let removed = id.clone();
+    db::run_blocking(&pool, move |pool| sources_remove_db(pool, &removed)).await?;
+    push_source_change(&bridge, "sources:remove", serde_json::json!({ "id": id }));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn yahoo() -> SourceConfig {
+        SourceConfig {
+            id: "yahoo-spy".to_string(),
+            name: "Yahoo SPY".to_string(),
+            source_type: SourceType::Polling,
+            plugin: "yahoo".to_string(),
+            config: serde_json::json!({ "symbols": ["SPY"] }),
+            poll_interval_ms: Some(60_000),
+            enabled: true,
+        }
+    }
+
+    #[test]
+    fn add_update_remove_roundtrip() {
+        let (_dir, pool) = test_pool();
+        let mut source = yahoo();
+        sources_add_db(&pool, &source).unwrap();
+        assert!(sources_add_db(&pool, &source).is_err());
+        assert_eq!(sources_list_db(&pool).unwrap(), vec![source.clone()]);
+
+        source.enabled = false;
+        source.poll_interval_ms = Some(5_000);
+        sources_update_db(&pool, &source).unwrap();
+        assert_eq!(sources_get_db(&pool, "yahoo-spy").unwrap(), Some(source));
+
+        sources_remove_db(&pool, "yahoo-spy").unwrap();
+        assert!(sources_list_db(&pool).unwrap().is_empty());
+        assert!(sources_remove_db(&pool, "yahoo-spy").is_err());
+    }
+
+    #[test]
+    fn update_requires_existing_source() {
+        let (_dir, pool) = test_pool();
+        assert!(sources_update_db(&pool, &yahoo()).is_err());
+    }
+
+    #[test]
+    fn validation_rejects_bad_definitions() {
+        assert!(validate_source(&yahoo()).is_ok());
+        let bad = [
+            SourceConfig { id: "Yahoo SPY".to_string(), ..yahoo() },
+            SourceConfig { id: "alpaca-stream".to_string(), ..yahoo() },
+            SourceConfig { name: " ".to_string(), ..yahoo() },
+            SourceConfig { poll_interval_ms: None, ..yahoo() },
+            SourceConfig { poll_interval_ms: Some(10), ..yahoo() },
+            SourceConfig { config: serde_json::json!([]), ..yahoo() },
+            SourceConfig { source_type: SourceType::File, plugin: "csv".to_string(), ..yahoo() },
+        ];
+        for source in bad {
+            assert!(validate_source(&source).is_err(), "{source:?}");
+        }
+        let csv = SourceConfig {
+            source_type: SourceType::File,
+            plugin: "csv".to_string(),
+            config: serde_json::json!({ "directory": "/tmp/csv" }),
+            poll_interval_ms: None,
+            ..yahoo()
+        };
+        assert!(validate_source(&csv).is_ok());
+    }
 }
@@ -0,0 +1,536 @@
+use std::time::{Duration, Instant};
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::commands::agent::session_symbols;
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::{ensure_online, is_offline};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// How often the poller wakes to check whether a poll is due.
+const POLLER_TICK: Duration = Duration::from_secs(5);
+const DEFAULT_LIST_LIMIT: u32 = 100;
+
+/// The `rss` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RssConfig {
+    pub enabled: bool,
+    /// RSS or Atom feed URLs.
+    pub feeds: Vec<String>,
+    /// Words or phrases matched case-insensitively against headlines, on top
+    /// of the watchlist symbols.
+    pub keywords: Vec<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for RssConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            feeds: Vec::new(),
+            keywords: Vec::new(),
+            interval_secs: 300,
+        }
+    }
+}
+
+/// Parse the `rss` section of the app config.
+pub fn rss_config(app_config: &serde_json::Value) -> RssConfig {
+    app_config
+        .get("rss")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// One `<item>` or `<entry>` of a feed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeedEntry {
+    pub guid: String,
+    pub title: String,
+    pub link: Option<String>,
+    /// Epoch millis, if the feed gave a parseable date.
+    pub published: Option<u64>,
+}
+
+/// A stored headline that mentioned a watched symbol or keyword.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewsItem {
+    pub id: i64,
+    pub feed: String,
+    pub guid: String,
+    pub title: String,
+    pub link: Option<String>,
+    pub published: u64,
+    pub symbols: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+#[derive(Default)]
+struct RawEntry {
+    guid: Option<String>,
+    title: String,
+    link: Option<String>,
+    date: Option<String>,
+}
+
+impl RawEntry {
+    fn set(&mut self, field: &str, value: String) {
+        match field {
+            "title" => self.title = value,
+            "link" if self.link.is_none() && !value.is_empty() => self.link = Some(value),
+            "guid" | "id" => self.guid = Some(value),
+            "pubDate" | "published" | "date" => self.date = Some(value),
+            "updated" if self.date.is_none() => self.date = Some(value),
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> Option<FeedEntry> {
+        if self.title.is_empty() {
+            return None;
+        }
+        let guid = self
+            .guid
+            .filter(|g| !g.is_empty())
+            .or_else(|| self.link.clone())
+            .unwrap_or_else(|| self.title.clone());
+        Some(FeedEntry {
+            guid,
+            published: self.date.as_deref().and_then(parse_date),
+            title: self.title,
+            link: self.link,
+        })
+    }
+}
+
+/// RFC 2822 (RSS) or RFC 3339 (Atom) date as epoch millis.
+fn parse_date(value: &str) -> Option<u64> {
+    chrono::DateTime::parse_from_rfc2822(value)
+        .or_else(|_| chrono::DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|d| d.timestamp_millis().max(0) as u64)
+}
+
+fn local_name(name: &[u8]) -> String {
+    String::from_utf8_lossy(name).into_owned()
+}
+
+/// The `href` of an Atom `<link>` pointing at the article itself.
+fn atom_href(element: &BytesStart) -> Option<String> {
+    let rel = element.try_get_attribute("rel").ok().flatten();
+    if rel.is_some_and(|r| r.value.as_ref() != b"alternate") {
+        return None;
+    }
+    let href = element.try_get_attribute("href").ok().flatten()?;
+    href.unescape_value().ok().map(|v| v.into_owned())
+}
+
+/// Read the entries of an RSS 2.0 or Atom document.
+pub fn parse_feed(body: &str) -> Result<Vec<FeedEntry>, String> {
+    let invalid = |e: &dyn std::fmt::Display| format!("Invalid feed: {}", e);
+    let mut reader = Reader::from_str(body);
+    let mut entries = Vec::new();
+    let mut current: Option<RawEntry> = None;
+    let mut field: Option<String> = None;
+    let mut text = String::new();
+    loop {
+        match reader.read_event().map_err(|e| invalid(&e))? {
+            Event::Start(e) => {
+                let name = local_name(e.local_name().as_ref());
+                if name == "item" || name == "entry" {
+                    current = Some(RawEntry::default());
+                } else if let Some(entry) = current.as_mut() {
+                    if name == "link" {
+                        if let Some(href) = atom_href(&e) {
+                            entry.set("link", href);
+                        }
+                    }
+                    field = Some(name);
+                    text.clear();
+                }
+            }
+            Event::Empty(e) => {
+                if let Some(entry) = current.as_mut() {
+                    if e.local_name().as_ref() == b"link" {
+                        if let Some(href) = atom_href(&e) {
+                            entry.set("link", href);
+                        }
+                    }
+                }
+            }
+            Event::Text(t) if field.is_some() => text.push_str(&t.decode().map_err(|e| invalid(&e))?),
+            Event::CData(c) if field.is_some() => text.push_str(&c.decode().map_err(|e| invalid(&e))?),
+            Event::GeneralRef(r) if field.is_some() => {
+                if let Some(c) = r.resolve_char_ref().map_err(|e| invalid(&e))? {
+                    text.push(c);
+                } else {
+                    let name = r.decode().map_err(|e| invalid(&e))?;
+                    let resolved = quick_xml::escape::resolve_predefined_entity(&name)
+                        .map_or_else(|| format!("&{};", name), str::to_string);
+                    text.push_str(&resolved);
+                }
+            }
+            Event::End(e) => {
+                let name = local_name(e.local_name().as_ref());
+                if name == "item" || name == "entry" {
+                    entries.extend(current.take().and_then(RawEntry::finish));
+                    field = None;
+                } else if field.as_deref() == Some(name.as_str()) {
+                    if let Some(entry) = current.as_mut() {
+                        entry.set(&name, text.split_whitespace().collect::<Vec<_>>().join(" "));
+                    }
+                    field = None;
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(entries)
+}
+
+/// Whether `needle` occurs in `haystack` with no letter or digit on either side.
+fn contains_word(haystack: &str, needle: &str) -> bool {
+    !needle.is_empty()
+        && haystack.match_indices(needle).any(|(i, _)| {
+            let before = haystack[..i].chars().next_back();
+            let after = haystack[i + needle.len()..].chars().next();
+            !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+        })
+}
+
+/// The symbols and keywords `title` mentions. Symbols match case-sensitively
+/// as whole words; single-letter symbols only match as cashtags (`$F`).
+pub fn match_headline(title: &str, symbols: &[String], keywords: &[String]) -> (Vec<String>, Vec<String>) {
+    let matched_symbols = symbols
+        .iter()
+        .filter(|s| {
+            if s.chars().count() == 1 {
+                contains_word(title, &format!("${}", s))
+            } else {
+                contains_word(title, s)
+            }
+        })
+        .cloned()
+        .collect();
+    let lower = title.to_lowercase();
+    let matched_keywords = keywords
+        .iter()
+        .filter(|k| contains_word(&lower, k.trim().to_lowercase().as_str()))
+        .cloned()
+        .collect();
+    (matched_symbols, matched_keywords)
+}
+
+/// Store a matched headline. Returns None if the feed already delivered it.
+pub fn news_insert_db(
+    pool: &DbPool,
+    feed: &str,
+    entry: &FeedEntry,
+    symbols: &[String],
+    keywords: &[String],
+    received_at: u64,
+) -> Result<Option<NewsItem>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let published = entry.published.unwrap_or(received_at);
+    let inserted = conn
+        .execute(
+            "INSERT OR IGNORE INTO news_items (feed, guid, title, link, published, symbols, keywords)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                feed,
+                entry.guid,
+                entry.title,
+                entry.link,
+                published as i64,
+                serde_json::to_string(symbols).map_err(|e| e.to_string())?,
+                serde_json::to_string(keywords).map_err(|e| e.to_string())?,
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+    if inserted == 0 {
+        return Ok(None);
+    }
+    Ok(Some(NewsItem {
+        id: conn.last_insert_rowid(),
+        feed: feed.to_string(),
+        guid: entry.guid.clone(),
+        title: entry.title.clone(),
+        link: entry.link.clone(),
+        published,
+        symbols: symbols.to_vec(),
+        keywords: keywords.to_vec(),
+    }))
+}
+
+/// Stored headlines, newest first, optionally only those mentioning `symbol`.
+pub fn news_list_db(pool: &DbPool, symbol: Option<&str>, limit: u32) -> Result<Vec<NewsItem>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, feed, guid, title, link, published, symbols, keywords FROM news_items
+             WHERE ?1 IS NULL OR EXISTS (SELECT 1 FROM json_each(symbols) WHERE value = ?1)
+             ORDER BY published DESC, id DESC LIMIT ?2",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(rusqlite::params![symbol, limit], |row| {
+            let symbols: String = row.get(6)?;
+            let keywords: String = row.get(7)?;
+            Ok(NewsItem {
+                id: row.get(0)?,
+                feed: row.get(1)?,
+                guid: row.get(2)?,
+                title: row.get(3)?,
+                link: row.get(4)?,
+                published: row.get::<_, i64>(5)?.max(0) as u64,
+                symbols: serde_json::from_str(&symbols).unwrap_or_default(),
+                keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+/// Result of one poll of every configured feed.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RssPollReport {
+    /// Headlines matched and stored for the first time.
+    pub items: Vec<NewsItem>,
+    /// Feeds that could not be fetched or parsed, with the reason.
+    pub failed: Vec<String>,
+}
+
+async fn fetch_feed(client: &reqwest::Client, url: &str) -> Result<Vec<FeedEntry>, String> {
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{}: HTTP {}", url, response.status()));
+    }
+    let body = response.text().await.map_err(|e| format!("{}: {}", url, e))?;
+    parse_feed(&body).map_err(|e| format!("{}: {}", url, e))
+}
+
+/// Fetch every configured feed and store the headlines that mention a
+/// watchlist symbol or keyword.
+pub async fn poll(pool: &DbPool, config: &RssConfig) -> Result<RssPollReport, String> {
+    let symbols = db::run_blocking(pool, |pool| Ok(session_symbols(&config_effective_db(pool)?))).await?;
+    let client = crate::http::client()?;
+    let mut fetched = Vec::new();
+    let mut failed = Vec::new();
+    for feed in &config.feeds {
+        match fetch_feed(&client, feed).await {
+            Ok(entries) => fetched.push((feed.clone(), entries)),
+            Err(e) => failed.push(e),
+        }
+    }
+    let keywords = config.keywords.clone();
+    let items = db::run_blocking(pool, move |pool| {
+        let now = now_millis();
+        let mut items = Vec::new();
+        for (feed, entries) in &fetched {
+            for entry in entries {
+                let (symbols, keywords) = match_headline(&entry.title, &symbols, &keywords);
+                if symbols.is_empty() && keywords.is_empty() {
+                    continue;
+                }
+                items.extend(news_insert_db(pool, feed, entry, &symbols, &keywords, now)?);
+            }
+        }
+        Ok(items)
+    })
+    .await?;
+    Ok(RssPollReport { items, failed })
+}
+
+/// Emit `news:item` for each newly stored headline.
+pub fn publish_poll<R: Runtime>(app: &AppHandle<R>, report: &RssPollReport) {
+    for item in &report.items {
+        let _ = emit_event(app, event_names::NEWS_ITEM, item);
+    }
+}
+
+/// Start the background thread that polls the configured feeds every
+/// `rss.intervalSecs` while `rss.enabled` is set.
+pub fn spawn_poller<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let mut last_poll: Option<Instant> = None;
+        loop {
+            std::thread::sleep(POLLER_TICK);
+            let pool = app.state::<WorkspaceDb>().pool();
+            let config = match config_effective_db(&pool) {
+                Ok(c) => rss_config(&c),
+                Err(e) => {
+                    tracing::warn!(error = %e, "RSS poller could not read config");
+                    continue;
+                }
+            };
+            if !config.enabled || config.feeds.is_empty() || is_offline(&pool) {
+                continue;
+            }
+            let interval = Duration::from_secs(config.interval_secs);
+            if last_poll.is_some_and(|t| t.elapsed() < interval) {
+                continue;
+            }
+            last_poll = Some(Instant::now());
+            match tauri::async_runtime::block_on(poll(&pool, &config)) {
+                Ok(report) => {
+                    for failure in &report.failed {
+                        tracing::warn!(error = %failure, "RSS feed fetch failed");
+                    }
+                    publish_poll(&app, &report);
+                }
+                Err(e) => tracing::warn!(error = %e, "RSS poll failed"),
+            }
+        }
+    });
+}
+
+/// Poll the configured feeds now, regardless of the interval.
+#[tauri::command]
+pub async fn sources_rss_poll(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<RssPollReport, String> {
+    let pool = workspace.pool();
+    let config = db::run_blocking(&pool, |pool| {
+        ensure_online(pool)?;
+        Ok(rss_config(&config_effective_db(pool)?))
+    })
+    .await?;
+    if config.feeds.is_empty() {
+        return Err("No RSS feeds are configured".to_string());
+    }
+    let report = poll(&pool, &config).await?;
+    publish_poll(&app, &report);
+    Ok(report)
+}
+
+/// Matched headlines, newest first.
+#[tauri::command]
+pub async fn news_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbol: Option<String>,
+    limit: Option<u32>,
+) -> Result<Vec<NewsItem>, String> {
+    let pool = workspace.pool();
+    let limit = limit.unwrap_or(DEFAULT_LIST_LIMIT);
+    db::run_blocking(&pool, move |pool| news_list_db(pool, symbol.as_deref(), limit)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+        <rss version="2.0"><channel><title>Markets</title>
+          <item>
+            <title>NET beats estimates, AT&amp;T slips</title>
+            <link>https://example.com/net</link>
+            <guid isPermaLink="false">net-1</guid>
+            <pubDate>Mon, 10 Jun 2024 12:00:00 GMT</pubDate>
+          </item>
+          <item><title><![CDATA[Fed holds rates]]></title><link>https://example.com/fed</link></item>
+        </channel></rss>"#;
+
+    const ATOM: &str = r#"<feed xmlns="http://www.w3.org/2005/Atom">
+          <title>Wire</title>
+          <entry>
+            <title type="html">Ford &#38; GM recall</title>
+            <link rel="alternate" href="https://example.com/recall"/>
+            <id>urn:wire:1</id>
+            <updated>2024-06-10T12:00:00Z</updated>
+            <author><name>Desk</name></author>
+          </entry>
+        </feed>"#;
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_rss_items() {
+        let entries = parse_feed(RSS).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "NET beats estimates, AT&T slips");
+        assert_eq!(entries[0].guid, "net-1");
+        assert_eq!(entries[0].published, Some(1_718_020_800_000));
+        assert_eq!(entries[1].title, "Fed holds rates");
+        assert_eq!(entries[1].guid, "https://example.com/fed");
+        assert_eq!(entries[1].published, None);
+    }
+
+    #[test]
+    fn parses_atom_entries() {
+        let entries = parse_feed(ATOM).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].title, "Ford & GM recall");
+        assert_eq!(entries[0].link.as_deref(), Some("https://example.com/recall"));
+        assert_eq!(entries[0].guid, "urn:wire:1");
+        assert_eq!(entries[0].published, Some(1_718_020_800_000));
+    }
+
+    #[test]
+    fn rejects_malformed_xml() {
+        assert!(parse_feed("<rss><channel><item></channel>").is_err());
+    }
+
+    #[test]
+    fn matches_symbols_and_keywords_as_whole_words() {
+        let symbols = strings(&["NET", "F", "AAPL"]);
+        let keywords = strings(&["rate cut", "recall"]);
+        let (s, k) = match_headline("NET and $F rally as Fed signals Rate Cut", &symbols, &keywords);
+        assert_eq!(s, strings(&["NET", "F"]));
+        assert_eq!(k, strings(&["rate cut"]));
+
+        let (s, k) = match_headline("Netflix: F-150 recalls NETWORK", &symbols, &keywords);
+        assert!(s.is_empty());
+        assert!(k.is_empty());
+    }
+
+    #[test]
+    fn stores_each_headline_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        let entry = &parse_feed(RSS).unwrap()[0];
+        let feed = "https://example.com/rss";
+        let symbols = strings(&["NET"]);
+        let item = news_insert_db(&pool, feed, entry, &symbols, &[], 5).unwrap().unwrap();
+        assert_eq!(item.published, 1_718_020_800_000);
+        assert_eq!(news_insert_db(&pool, feed, entry, &symbols, &[], 6).unwrap(), None);
+
+        assert_eq!(news_list_db(&pool, None, 10).unwrap(), vec![item.clone()]);
+        assert_eq!(news_list_db(&pool, Some("NET"), 10).unwrap(), vec![item]);
+        assert!(news_list_db(&pool, Some("AAPL"), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn config_defaults_to_disabled() {
+        assert_eq!(rss_config(&serde_json::json!({})), RssConfig::default());
+        let config = rss_config(&serde_json::json!({"rss": {"enabled": true, "feeds": ["https://x/rss"]}}));
+        assert!(config.enabled);
+        assert_eq!(config.interval_secs, 300);
+    }
+}
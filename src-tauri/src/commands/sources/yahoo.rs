@@ -0,0 +1,354 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::{ensure_online, is_offline};
+use crate::commands::sources::{sources_health_db, sources_health_set_db};
+use crate::commands::ticks::{tick_recording_config, ticks_insert_db};
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::data::{DataTick, SourceHealth, SourceHealthStatus};
+use crate::workspace::WorkspaceDb;
+
+/// Source id the ticks and health row are recorded under.
+pub const YAHOO_SOURCE_ID: &str = "yahoo";
+const CHART_URL: &str = "https://query1.finance.yahoo.com/v8/finance/chart";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the poller wakes to check whether a poll is due.
+const POLLER_TICK: Duration = Duration::from_secs(5);
+/// Consecutive failed polls after which the source is reported offline.
+const OFFLINE_AFTER_FAILURES: u32 = 3;
+
+/// The `yahoo` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct YahooSourceConfig {
+    pub enabled: bool,
+    pub symbols: Vec<String>,
+    pub interval_secs: u64,
+}
+
+impl Default for YahooSourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbols: Vec::new(),
+            interval_secs: 60,
+        }
+    }
+}
+
+/// Parse the `yahoo` section of the app config.
+pub fn yahoo_source_config(app_config: &serde_json::Value) -> YahooSourceConfig {
+    app_config
+        .get("yahoo")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartResponse {
+    chart: Chart,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chart {
+    result: Option<Vec<ChartResult>>,
+    error: Option<ChartError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartError {
+    description: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartResult {
+    meta: ChartMeta,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChartMeta {
+    symbol: String,
+    currency: Option<String>,
+    exchange_name: Option<String>,
+    regular_market_price: Option<f64>,
+    /// Epoch seconds of the quote.
+    regular_market_time: Option<i64>,
+    regular_market_volume: Option<f64>,
+    regular_market_day_high: Option<f64>,
+    regular_market_day_low: Option<f64>,
+    chart_previous_close: Option<f64>,
+}
+
+/// Turn a chart API response body into a quote tick.
+pub fn parse_quote(body: &str, received_at: u64) -> Result<DataTick, String> {
+    let response: ChartResponse =
+        serde_json::from_str(body).map_err(|e| format!("Invalid Yahoo response: {}", e))?;
+    if let Some(error) = response.chart.error {
+        return Err(error.description);
+    }
+    let meta = response
+        .chart
+        .result
+        .and_then(|r| r.into_iter().next())
+        .map(|r| r.meta)
+        .ok_or("Yahoo returned no chart data")?;
+    let price = meta
+        .regular_market_price
+        .ok_or_else(|| format!("No price for {}", meta.symbol))?;
+
+    let mut metrics = HashMap::from([("price".to_string(), price)]);
+    let optional = [
+        ("volume", meta.regular_market_volume),
+        ("dayHigh", meta.regular_market_day_high),
+        ("dayLow", meta.regular_market_day_low),
+        ("previousClose", meta.chart_previous_close),
+    ];
+    metrics.extend(optional.into_iter().filter_map(|(k, v)| Some((k.to_string(), v?))));
+    if let Some(prev) = meta.chart_previous_close.filter(|p| *p != 0.0) {
+        metrics.insert("changePct".to_string(), (price - prev) / prev * 100.0);
+    }
+
+    let mut metadata = HashMap::new();
+    if let Some(currency) = meta.currency {
+        metadata.insert("currency".to_string(), currency.into());
+    }
+    if let Some(exchange) = meta.exchange_name {
+        metadata.insert("exchange".to_string(), exchange.into());
+    }
+    Ok(DataTick {
+        source_id: YAHOO_SOURCE_ID.to_string(),
+        timestamp: meta
+            .regular_market_time
+            .map_or(received_at, |t| t.max(0) as u64 * 1000),
+        symbol: Some(meta.symbol),
+        metrics,
+        metadata,
+        raw: None,
+    })
+}
+
+/// Health after a poll in which `failed` of `total` symbols could not be fetched.
+pub fn poll_health(
+    previous: Option<&SourceHealth>,
+    total: usize,
+    failed: &[String],
+    latency_ms: u64,
+    now: u64,
+) -> SourceHealth {
+    let all_failed = total > 0 && failed.len() == total;
+    let fail_count = if failed.is_empty() {
+        0
+    } else {
+        previous.map_or(0, |p| p.fail_count) + 1
+    };
+    let status = if failed.is_empty() {
+        SourceHealthStatus::Healthy
+    } else if all_failed && fail_count >= OFFLINE_AFTER_FAILURES {
+        SourceHealthStatus::Offline
+    } else {
+        SourceHealthStatus::Degraded
+    };
+    let previous_success = previous.map_or(0, |p| p.last_success);
+    SourceHealth {
+        source_id: YAHOO_SOURCE_ID.to_string(),
+        status,
+        last_success: if all_failed { previous_success } else { now },
+        last_failure: if failed.is_empty() {
+            previous.and_then(|p| p.last_failure)
+        } else {
+            Some(now)
+        },
+        fail_count,
+        latency_ms,
+        message: (!failed.is_empty()).then(|| failed.join("; ")),
+    }
+}
+
+/// Result of one poll of every configured symbol.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct YahooPollReport {
+    pub ticks: Vec<DataTick>,
+    pub health: SourceHealth,
+}
+
+async fn fetch_quote(client: &reqwest::Client, symbol: &str) -> Result<DataTick, String> {
+    let mut url = reqwest::Url::parse(CHART_URL).map_err(|e| e.to_string())?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid chart URL".to_string())?
+        .push(symbol);
+    url.query_pairs_mut()
+        .append_pair("range", "1d")
+        .append_pair("interval", "1m");
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("{}: {}", symbol, e))?;
+    if !response.status().is_success() {
+        return Err(format!("{}: HTTP {}", symbol, response.status()));
+    }
+    let body = response.text().await.map_err(|e| format!("{}: {}", symbol, e))?;
+    parse_quote(&body, now_millis()).map_err(|e| format!("{}: {}", symbol, e))
+}
+
+/// Fetch a quote for every configured symbol, record the source's health and
+/// any ticks tick recording selects, and return both.
+pub async fn poll(pool: &DbPool, config: &YahooSourceConfig) -> Result<YahooPollReport, String> {
+    let client = crate::http::client()?;
+    let started = Instant::now();
+    let mut ticks = Vec::new();
+    let mut failed = Vec::new();
+    for symbol in &config.symbols {
+        match fetch_quote(&client, symbol).await {
+            Ok(tick) => ticks.push(tick),
+            Err(e) => failed.push(e),
+        }
+    }
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let total = config.symbols.len();
+    let recorded = ticks.clone();
+    let health = db::run_blocking(pool, move |pool| {
+        let previous = sources_health_db(pool)?.remove(YAHOO_SOURCE_ID);
+        let health = poll_health(previous.as_ref(), total, &failed, latency_ms, now_millis());
+        sources_health_set_db(pool, &health)?;
+        let recording = tick_recording_config(&config_effective_db(pool)?);
+        for tick in recorded.iter().filter(|t| recording.should_record(t.symbol.as_deref())) {
+            ticks_insert_db(pool, tick)?;
+        }
+        Ok(health)
+    })
+    .await?;
+    Ok(YahooPollReport { ticks, health })
+}
+
+/// Emit `data:tick` for each quote, check it against alert rules, and emit
+/// `source:health-change` for the source.
+pub fn publish_poll<R: Runtime>(app: &AppHandle<R>, report: &YahooPollReport) {
+    for tick in &report.ticks {
+        let _ = emit_event(app, event_names::DATA_TICK, tick);
+    }
+    crate::alerts::check_ticks(app, &report.ticks);
+    let _ = emit_event(app, event_names::SOURCE_HEALTH_CHANGE, &report.health);
+}
+
+/// Start the background thread that polls Yahoo every `yahoo.intervalSecs`
+/// while `yahoo.enabled` is set and symbols are configured.
+pub fn spawn_poller<R: Runtime>(app: AppHandle<R>) {
+    std::thread::spawn(move || {
+        let mut last_poll: Option<Instant> = None;
+        loop {
+            std::thread::sleep(POLLER_TICK);
+            let pool = app.state::<WorkspaceDb>().pool();
+            let config = match config_effective_db(&pool) {
+                Ok(c) => yahoo_source_config(&c),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Yahoo poller could not read config");
+                    continue;
+                }
+            };
+            if !config.enabled || config.symbols.is_empty() || is_offline(&pool) {
+                continue;
+            }
+            let interval = Duration::from_secs(config.interval_secs);
+            if last_poll.is_some_and(|t| t.elapsed() < interval) {
+                continue;
+            }
+            last_poll = Some(Instant::now());
+            match tauri::async_runtime::block_on(poll(&pool, &config)) {
+                Ok(report) => publish_poll(&app, &report),
+                Err(e) => tracing::warn!(error = %e, "Yahoo poll failed"),
+            }
+        }
+    });
+}
+
+/// Poll Yahoo now, regardless of the interval, and return what was fetched.
+#[tauri::command]
+pub async fn sources_yahoo_poll(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<YahooPollReport, String> {
+    let pool = workspace.pool();
+    let config = db::run_blocking(&pool, |pool| {
+        ensure_online(pool)?;
+        Ok(yahoo_source_config(&config_effective_db(pool)?))
+    })
+    .await?;
+    if config.symbols.is_empty() {
+        return Err("No Yahoo symbols are configured".to_string());
+    }
+    let report = poll(&pool, &config).await?;
+    publish_poll(&app, &report);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHART: &str = r#"{"chart":{"result":[{"meta":{
+        "symbol":"SPY","currency":"USD","exchangeName":"PCX",
+        "regularMarketPrice":505.0,"regularMarketTime":1718035200,
+        "regularMarketVolume":1000000,"regularMarketDayHigh":507.5,
+        "regularMarketDayLow":501.0,"chartPreviousClose":500.0
+    },"timestamp":[],"indicators":{"quote":[{}]}}],"error":null}}"#;
+
+    #[test]
+    fn parses_chart_meta_into_tick() {
+        let tick = parse_quote(CHART, 0).unwrap();
+        assert_eq!(tick.source_id, "yahoo");
+        assert_eq!(tick.symbol.as_deref(), Some("SPY"));
+        assert_eq!(tick.timestamp, 1_718_035_200_000);
+        assert_eq!(tick.metrics["price"], 505.0);
+        assert_eq!(tick.metrics["dayLow"], 501.0);
+        assert!((tick.metrics["changePct"] - 1.0).abs() < 1e-9);
+        assert_eq!(tick.metadata["currency"], "USD");
+    }
+
+    #[test]
+    fn reports_api_errors() {
+        let body = r#"{"chart":{"result":null,"error":{"code":"Not Found","description":"No data found, symbol may be delisted"}}}"#;
+        assert_eq!(parse_quote(body, 0).unwrap_err(), "No data found, symbol may be delisted");
+        assert!(parse_quote("<html>", 0).is_err());
+    }
+
+    #[test]
+    fn health_degrades_then_goes_offline() {
+        let ok = poll_health(None, 2, &[], 120, 1_000);
+        assert_eq!(ok.status, SourceHealthStatus::Healthy);
+        assert_eq!(ok.last_success, 1_000);
+
+        let partial = poll_health(Some(&ok), 2, &["SPY: HTTP 500".to_string()], 80, 2_000);
+        assert_eq!(partial.status, SourceHealthStatus::Degraded);
+        assert_eq!(partial.last_success, 2_000);
+        assert_eq!(partial.message.as_deref(), Some("SPY: HTTP 500"));
+
+        let failed = vec!["SPY: timeout".to_string(), "QQQ: timeout".to_string()];
+        let mut health = partial;
+        for now in [3_000, 4_000] {
+            health = poll_health(Some(&health), 2, &failed, 10_000, now);
+        }
+        assert_eq!(health.status, SourceHealthStatus::Offline);
+        assert_eq!(health.fail_count, 3);
+        assert_eq!(health.last_success, 2_000);
+        assert_eq!(poll_health(Some(&health), 2, &[], 90, 5_000).fail_count, 0);
+    }
+
+    #[test]
+    fn config_defaults_to_disabled() {
+        assert_eq!(yahoo_source_config(&serde_json::json!({})), YahooSourceConfig::default());
+        let config = yahoo_source_config(&serde_json::json!({"yahoo": {"enabled": true, "symbols": ["SPY"]}}));
+        assert!(config.enabled);
+        assert_eq!(config.interval_secs, 60);
+    }
+}
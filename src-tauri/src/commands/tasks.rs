@@ -0,0 +1,10 @@
+use crate::db;
+use crate::tasks::{tasks_list_db, TaskInfo};
+use crate::workspace::WorkspaceDb;
+
+/// Every recurring background task with its switch, interval, and last and
+/// next run.
+#[tauri::command]
+pub async fn tasks_list(workspace: tauri::State<'_, WorkspaceDb>) -> Result<Vec<TaskInfo>, String> {
+    db::run_blocking(&workspace.pool(), tasks_list_db).await
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, DbPool};
+use crate::types::data::{DataTick, TickRange};
+use crate::workspace::WorkspaceDb;
+
+/// Rows returned by `ticks_query` when the range has no explicit limit.
+const DEFAULT_QUERY_LIMIT: u32 = 5_000;
+/// Hard cap on rows returned by a single query.
+const MAX_QUERY_LIMIT: u32 = 50_000;
+
+/// The `ticks` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TickRecordingConfig {
+    /// Persist incoming `data:tick` notifications.
+    pub enabled: bool,
+    /// Only record these symbols; `None` records every tick.
+    pub symbols: Option<Vec<String>>,
+    /// Ticks older than this are pruned.
+    pub retention_days: u64,
+}
+
+impl Default for TickRecordingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            symbols: None,
+            retention_days: 7,
+        }
+    }
+}
+
+impl TickRecordingConfig {
+    /// Whether a tick for `symbol` should be persisted.
+    pub fn should_record(&self, symbol: Option<&str>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match (&self.symbols, symbol) {
+            (None, _) => true,
+            (Some(symbols), Some(symbol)) => symbols.iter().any(|s| s.eq_ignore_ascii_case(symbol)),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// Parse the `ticks` section of the app config.
+pub fn tick_recording_config(app_config: &serde_json::Value) -> TickRecordingConfig {
+    app_config
+        .get("ticks")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn ticks_insert_db(pool: &DbPool, tick: &DataTick) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let metrics_json = serde_json::to_string(&tick.metrics).map_err(|e| e.to_string())?;
+    let metadata_json = serde_json::to_string(&tick.metadata).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO ticks (source_id, symbol, timestamp, metrics, metadata)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            tick.source_id,
+            tick.symbol,
+            tick.timestamp,
+            metrics_json,
+            metadata_json,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Replace every tick stored for `source_id` with `ticks`, atomically.
+pub fn ticks_replace_source_db(pool: &DbPool, source_id: &str, ticks: &[DataTick]) -> Result<(), String> {
+    let mut conn = pool.get().map_err(|e| e.to_string())?;
+    let tx = conn.transaction().map_err(|e| e.to_string())?;
+    tx.execute("DELETE FROM ticks WHERE source_id = ?1", [source_id])
+        .map_err(|e| e.to_string())?;
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT INTO ticks (source_id, symbol, timestamp, metrics, metadata)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .map_err(|e| e.to_string())?;
+        for tick in ticks {
+            let metrics_json = serde_json::to_string(&tick.metrics).map_err(|e| e.to_string())?;
+            let metadata_json = serde_json::to_string(&tick.metadata).map_err(|e| e.to_string())?;
+            stmt.execute(rusqlite::params![
+                source_id,
+                tick.symbol,
+                tick.timestamp,
+                metrics_json,
+                metadata_json,
+            ])
+            .map_err(|e| e.to_string())?;
+        }
+    }
+    tx.commit().map_err(|e| e.to_string())
+}
+
+/// Recorded ticks for `symbol` in `range`, oldest first.
+pub fn ticks_query_db(pool: &DbPool, symbol: &str, range: &TickRange) -> Result<Vec<DataTick>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let limit = range
+        .limit
+        .unwrap_or(DEFAULT_QUERY_LIMIT)
+        .min(MAX_QUERY_LIMIT);
+    let mut stmt = conn
+        .prepare(
+            "SELECT source_id, symbol, timestamp, metrics, metadata FROM ticks
+             WHERE symbol = ?1 AND timestamp >= ?2 AND timestamp <= ?3
+             ORDER BY timestamp ASC LIMIT ?4",
+        )
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                symbol,
+                range.from.unwrap_or(0) as i64,
+                range.to.map(|t| t as i64).unwrap_or(i64::MAX),
+                limit,
+            ],
+            |row| {
+                let metrics: String = row.get(3)?;
+                let metadata: String = row.get(4)?;
+                Ok(DataTick {
+                    source_id: row.get(0)?,
+                    symbol: row.get(1)?,
+                    timestamp: row.get::<_, i64>(2)? as u64,
+                    metrics: serde_json::from_str::<HashMap<String, f64>>(&metrics)
+                        .unwrap_or_default(),
+                    metadata: serde_json::from_str(&metadata).unwrap_or_default(),
+                    raw: None,
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+/// Delete ticks recorded before `cutoff` (epoch millis). Returns rows removed.
+pub fn ticks_prune_db(pool: &DbPool, cutoff: u64) -> Result<usize, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM ticks WHERE timestamp < ?1", [cutoff as i64])
+        .map_err(|e| e.to_string())
+}
+
+/// Recorded tick history for one symbol.
+#[tauri::command]
+pub async fn ticks_query(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbol: String,
+    range: Option<TickRange>,
+) -> Result<Vec<DataTick>, String> {
+    let pool = workspace.pool();
+    let range = range.unwrap_or_default();
+    db::run_blocking(&pool, move |pool| ticks_query_db(pool, &symbol, &range)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::migrations;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn tick(symbol: &str, timestamp: u64, price: f64) -> DataTick {
+        DataTick {
+            source_id: "alpaca".to_string(),
+            timestamp,
+            symbol: Some(symbol.to_string()),
+            metrics: [("close".to_string(), price)].into(),
+            metadata: HashMap::new(),
+            raw: None,
+        }
+    }
+
+    #[test]
+    fn query_filters_by_symbol_and_range() {
+        let (_dir, pool) = test_pool();
+        for (i, ts) in [1_000, 2_000, 3_000, 4_000].iter().enumerate() {
+            ticks_insert_db(&pool, &tick("NET", *ts, i as f64)).unwrap();
+        }
+        ticks_insert_db(&pool, &tick("AAPL", 2_500, 1.0)).unwrap();
+
+        let range = TickRange {
+            from: Some(2_000),
+            to: Some(3_000),
+            limit: None,
+        };
+        let ticks = ticks_query_db(&pool, "NET", &range).unwrap();
+        assert_eq!(ticks.len(), 2);
+        assert_eq!(ticks[0].timestamp, 2_000);
+        assert_eq!(ticks[1].metrics["close"], 2.0);
+    }
+
+    #[test]
+    fn query_respects_limit() {
+        let (_dir, pool) = test_pool();
+        for ts in 0..10 {
+            ticks_insert_db(&pool, &tick("NET", ts, 1.0)).unwrap();
+        }
+        let range = TickRange {
+            limit: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(ticks_query_db(&pool, "NET", &range).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn prune_removes_old_ticks() {
+        let (_dir, pool) = test_pool();
+        ticks_insert_db(&pool, &tick("NET", 1_000, 1.0)).unwrap();
+        ticks_insert_db(&pool, &tick("NET", 5_000, 1.0)).unwrap();
+        assert_eq!(ticks_prune_db(&pool, 2_000).unwrap(), 1);
+        let remaining = ticks_query_db(&pool, "NET", &TickRange::default()).unwrap();
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].timestamp, 5_000);
+    }
+
+    #[test]
+    fn recording_config_filters_symbols() {
+        let config = tick_recording_config(&serde_json::json!({}));
+        assert!(!config.should_record(Some("NET")));
+
+        let config = tick_recording_config(&serde_json::json!({
+            "ticks": {"enabled": true, "symbols": ["NET"]}
+        }));
+        assert_eq!(config.retention_days, 7);
+        assert!(config.should_record(Some("net")));
+        assert!(!config.should_record(Some("AAPL")));
+        assert!(!config.should_record(None));
+
+        let all = tick_recording_config(&serde_json::json!({"ticks": {"enabled": true}}));
+        assert!(all.should_record(None));
+    }
+}
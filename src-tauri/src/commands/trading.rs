@@ -0,0 +1,251 @@
+use serde::{Deserialize, Serialize};
+
+use crate::bridge::SidecarBridge;
+use crate::commands::portfolio::PortfolioSnapshot;
+use crate::db::{self, DbPool};
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// `config` table key holding the kill-switch state.
+const HALT_KEY: &str = "trading_halt";
+
+/// The `guardrails` section of the app config: hard caps on every order,
+/// enforced here whatever the agent or the `risk` limits allow.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Guardrails {
+    /// Loss since the previous close, in account currency, at which trading halts.
+    pub daily_loss_limit: f64,
+    /// Largest value of any single order, in account currency.
+    pub max_order_value: f64,
+}
+
+impl Default for Guardrails {
+    fn default() -> Self {
+        Self {
+            daily_loss_limit: 2_000.0,
+            max_order_value: 25_000.0,
+        }
+    }
+}
+
+/// Parse the `guardrails` section of the app config.
+pub fn guardrails(app_config: &serde_json::Value) -> Guardrails {
+    app_config
+        .get("guardrails")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The persisted kill switch. While halted, no order can be placed; cancels
+/// are still allowed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradingHalt {
+    pub halted: bool,
+    pub reason: Option<String>,
+    /// When the switch last changed, epoch millis.
+    pub changed_at: Option<u64>,
+}
+
+impl TradingHalt {
+    /// An error naming the reason if trading is halted.
+    pub fn ensure_trading(&self) -> Result<(), String> {
+        if !self.halted {
+            return Ok(());
+        }
+        Err(match &self.reason {
+            Some(reason) => format!("Trading is halted: {}", reason),
+            None => "Trading is halted".to_string(),
+        })
+    }
+}
+
+/// The current kill-switch state; never halted if it was never set.
+pub fn trading_halt_get_db(pool: &DbPool) -> Result<TradingHalt, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let value: Option<String> = conn
+        .query_row("SELECT value FROM config WHERE key = ?1", [HALT_KEY], |row| row.get(0))
+        .ok();
+    Ok(value.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or_default())
+}
+
+/// Set the kill switch and return the stored state.
+pub fn trading_halt_set_db(pool: &DbPool, halted: bool, reason: Option<String>) -> Result<TradingHalt, String> {
+    let halt = TradingHalt {
+        halted,
+        reason: reason.filter(|r| !r.trim().is_empty()),
+        changed_at: Some(now_millis()),
+    };
+    let json = serde_json::to_string(&halt).map_err(|e| e.to_string())?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![HALT_KEY, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(halt)
+}
+
+/// The `trading` params sent to the agent: the kill switch and the caps it must respect.
+pub(crate) fn agent_trading_params(halt: &TradingHalt, guardrails: &Guardrails) -> serde_json::Value {
+    serde_json::json!({
+        "halted": halt.halted,
+        "reason": halt.reason,
+        "guardrails": guardrails,
+    })
+}
+
+/// Why the account's loss today breaches `guardrails`, if it does.
+pub fn daily_loss_breach(guardrails: &Guardrails, portfolio: &PortfolioSnapshot) -> Option<String> {
+    let account = portfolio.account.as_ref()?;
+    let loss = account.last_equity - account.equity;
+    (loss >= guardrails.daily_loss_limit).then(|| {
+        format!(
+            "Daily loss of {:.2} reached the {:.2} limit",
+            loss, guardrails.daily_loss_limit
+        )
+    })
+}
+
+/// Check an order worth `order_value` against the kill switch and the hard caps.
+pub fn check_guardrails(
+    halt: &TradingHalt,
+    guardrails: &Guardrails,
+    order_value: f64,
+    portfolio: &PortfolioSnapshot,
+) -> Result<(), String> {
+    halt.ensure_trading()?;
+    if order_value > guardrails.max_order_value {
+        return Err(format!(
+            "Order value {:.2} exceeds the {:.2} hard cap",
+            order_value, guardrails.max_order_value
+        ));
+    }
+    if let Some(breach) = daily_loss_breach(guardrails, portfolio) {
+        return Err(breach);
+    }
+    Ok(())
+}
+
+/// Emit `trading:halt-changed` and tell a running agent about the new state.
+pub(crate) fn publish_halt(app: &tauri::AppHandle, bridge: &SidecarBridge, halt: &TradingHalt) {
+    tracing::warn!(halted = halt.halted, reason = ?halt.reason, "Trading kill switch changed");
+    let _ = emit_event(app, event_names::TRADING_HALT_CHANGED, halt);
+    if bridge.is_running() {
+        let method = if halt.halted { "trading:halt" } else { "trading:resume" };
+        let params = serde_json::json!({ "reason": halt.reason });
+        if let Err(e) = bridge.send_notification(method, Some(params)) {
+            tracing::warn!(error = %e, "Failed to forward kill switch to agent");
+        }
+    }
+}
+
+/// Engage the kill switch: block every new order until `trading_resume`.
+#[tauri::command]
+pub async fn trading_halt(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    reason: Option<String>,
+) -> Result<TradingHalt, String> {
+    let halt = db::run_blocking(&workspace.pool(), move |pool| trading_halt_set_db(pool, true, reason)).await?;
+    publish_halt(&app, &bridge, &halt);
+    Ok(halt)
+}
+
+/// Release the kill switch.
+#[tauri::command]
+pub async fn trading_resume(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+) -> Result<TradingHalt, String> {
+    let halt = db::run_blocking(&workspace.pool(), |pool| trading_halt_set_db(pool, false, None)).await?;
+    publish_halt(&app, &bridge, &halt);
+    Ok(halt)
+}
+
+#[tauri::command]
+pub async fn trading_status(workspace: tauri::State<'_, WorkspaceDb>) -> Result<TradingHalt, String> {
+    db::run_blocking(&workspace.pool(), trading_halt_get_db).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::portfolio::AccountSummary;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn portfolio(equity: f64, last_equity: f64) -> PortfolioSnapshot {
+        PortfolioSnapshot {
+            mode: "live".to_string(),
+            account: Some(AccountSummary {
+                equity,
+                cash: equity,
+                buying_power: equity,
+                last_equity,
+                currency: "USD".to_string(),
+            }),
+            positions: Vec::new(),
+            open_orders: Vec::new(),
+            fetched_at: Some(0),
+            stale: false,
+        }
+    }
+
+    #[test]
+    fn halt_state_persists_and_resumes() {
+        let (_dir, pool) = test_pool();
+        assert!(!trading_halt_get_db(&pool).unwrap().halted);
+
+        trading_halt_set_db(&pool, true, Some("manual".to_string())).unwrap();
+        let halt = trading_halt_get_db(&pool).unwrap();
+        assert!(halt.halted);
+        assert_eq!(halt.reason.as_deref(), Some("manual"));
+        assert!(halt.changed_at.is_some());
+
+        trading_halt_set_db(&pool, false, Some("  ".to_string())).unwrap();
+        let halt = trading_halt_get_db(&pool).unwrap();
+        assert!(!halt.halted);
+        assert_eq!(halt.reason, None);
+    }
+
+    #[test]
+    fn guardrails_block_halted_oversized_and_losing_days() {
+        let caps = Guardrails::default();
+        let calm = portfolio(100_000.0, 100_500.0);
+        assert!(check_guardrails(&TradingHalt::default(), &caps, 1_000.0, &calm).is_ok());
+
+        let halted = TradingHalt { halted: true, reason: Some("manual".to_string()), changed_at: None };
+        let err = check_guardrails(&halted, &caps, 1_000.0, &calm).unwrap_err();
+        assert!(err.contains("manual"), "{err}");
+
+        let err = check_guardrails(&TradingHalt::default(), &caps, 30_000.0, &calm).unwrap_err();
+        assert!(err.contains("hard cap"), "{err}");
+
+        let losing = portfolio(97_000.0, 100_000.0);
+        assert!(daily_loss_breach(&caps, &calm).is_none());
+        let err = check_guardrails(&TradingHalt::default(), &caps, 1_000.0, &losing).unwrap_err();
+        assert!(err.contains("3000.00"), "{err}");
+    }
+
+    #[test]
+    fn agent_params_carry_switch_and_caps() {
+        let halt = TradingHalt { halted: true, reason: Some("loss".to_string()), changed_at: Some(1) };
+        let params = agent_trading_params(&halt, &Guardrails::default());
+        assert_eq!(params["halted"], true);
+        assert_eq!(params["reason"], "loss");
+        assert_eq!(params["guardrails"]["maxOrderValue"], 25_000.0);
+    }
+}
@@ -0,0 +1,317 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::performance::PerformanceRange;
+use crate::db::{self, DbPool};
+use crate::time::now_millis;
+use crate::types::provider::LlmUsage;
+use crate::workspace::WorkspaceDb;
+
+/// USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// List prices keyed by model id prefix; the longest matching prefix wins.
+const BUILTIN_PRICES: &[(&str, ModelPrice)] = &[
+    (
+        "claude-opus-4-5",
+        ModelPrice {
+            input_per_mtok: 5.0,
+            output_per_mtok: 25.0,
+        },
+    ),
+    (
+        "claude-opus-4",
+        ModelPrice {
+            input_per_mtok: 15.0,
+            output_per_mtok: 75.0,
+        },
+    ),
+    (
+        "claude-sonnet-4",
+        ModelPrice {
+            input_per_mtok: 3.0,
+            output_per_mtok: 15.0,
+        },
+    ),
+    (
+        "claude-haiku-4-5",
+        ModelPrice {
+            input_per_mtok: 1.0,
+            output_per_mtok: 5.0,
+        },
+    ),
+    (
+        "claude-3-5-haiku",
+        ModelPrice {
+            input_per_mtok: 0.8,
+            output_per_mtok: 4.0,
+        },
+    ),
+    (
+        "claude-3-haiku",
+        ModelPrice {
+            input_per_mtok: 0.25,
+            output_per_mtok: 1.25,
+        },
+    ),
+];
+
+/// The `usage` section of the app config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageConfig {
+    /// Prices keyed by model id prefix, overriding the built-in list prices.
+    pub prices: BTreeMap<String, ModelPrice>,
+}
+
+/// Parse the `usage` section of the app config.
+pub fn usage_config(app_config: &serde_json::Value) -> UsageConfig {
+    app_config
+        .get("usage")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+fn longest_prefix<'a>(prices: impl Iterator<Item = (&'a str, ModelPrice)>, model: &str) -> Option<ModelPrice> {
+    prices
+        .filter(|(prefix, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _)| prefix.len())
+        .map(|(_, price)| price)
+}
+
+/// The price of `model`, from the configured prices first, then the built-in
+/// ones. OpenRouter's `vendor/` prefix is ignored.
+pub fn model_price(config: &UsageConfig, model: &str) -> Option<ModelPrice> {
+    let model = model.rsplit('/').next().unwrap_or(model);
+    longest_prefix(config.prices.iter().map(|(k, v)| (k.as_str(), *v)), model)
+        .or_else(|| longest_prefix(BUILTIN_PRICES.iter().copied(), model))
+}
+
+/// Record one LLM call, priced at the current price of its model. Calls to
+/// unpriced models are stored without a cost.
+pub fn usage_record_db(pool: &DbPool, usage: &LlmUsage, config: &UsageConfig) -> Result<(), String> {
+    let cost = model_price(config, &usage.model).map(|p| {
+        (usage.input_tokens as f64 * p.input_per_mtok + usage.output_tokens as f64 * p.output_per_mtok) / 1_000_000.0
+    });
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO llm_usage
+            (provider_id, model, input_tokens, output_tokens, latency_ms, timestamp, session_id, cycle_id, cost_usd)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        rusqlite::params![
+            usage.provider_id,
+            usage.model,
+            usage.input_tokens as i64,
+            usage.output_tokens as i64,
+            usage.latency_ms as i64,
+            usage.timestamp as i64,
+            usage.session_id,
+            usage.cycle_id,
+            cost,
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Calls, tokens, and spend over some set of LLM calls.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageTotals {
+    pub calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    /// Calls to models without a price, left out of `cost_usd`.
+    pub unpriced_calls: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageBucket {
+    /// The day (`YYYY-MM-DD`, UTC), model, or session id; empty for calls
+    /// outside any session.
+    pub key: String,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageSummary {
+    pub range: PerformanceRange,
+    pub totals: UsageTotals,
+    pub by_day: Vec<UsageBucket>,
+    pub by_model: Vec<UsageBucket>,
+    pub by_session: Vec<UsageBucket>,
+}
+
+fn usage_buckets_db(pool: &DbPool, key_sql: &str, since: Option<u64>) -> Result<Vec<UsageBucket>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let sql = format!(
+        "SELECT {}, COUNT(*), SUM(input_tokens), SUM(output_tokens), TOTAL(cost_usd), SUM(cost_usd IS NULL)
+         FROM llm_usage WHERE ?1 IS NULL OR timestamp >= ?1
+         GROUP BY 1 ORDER BY 1",
+        key_sql
+    );
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([since.map(|t| t as i64)], |row| {
+            Ok(UsageBucket {
+                key: row.get(0)?,
+                totals: UsageTotals {
+                    calls: row.get::<_, i64>(1)?.max(0) as u64,
+                    input_tokens: row.get::<_, i64>(2)?.max(0) as u64,
+                    output_tokens: row.get::<_, i64>(3)?.max(0) as u64,
+                    cost_usd: row.get(4)?,
+                    unpriced_calls: row.get::<_, i64>(5)?.max(0) as u64,
+                },
+            })
+        })
+        .map_err(|e| e.to_string())?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())
+}
+
+/// Spend since `since` (epoch millis), in total and per day, model, and session.
+pub fn usage_summary_db(pool: &DbPool, range: PerformanceRange, since: Option<u64>) -> Result<UsageSummary, String> {
+    let totals = usage_buckets_db(pool, "''", since)?
+        .pop()
+        .map(|b| b.totals)
+        .unwrap_or_default();
+    Ok(UsageSummary {
+        range,
+        totals,
+        by_day: usage_buckets_db(pool, "strftime('%Y-%m-%d', timestamp / 1000, 'unixepoch')", since)?,
+        by_model: usage_buckets_db(pool, "model", since)?,
+        by_session: usage_buckets_db(pool, "COALESCE(session_id, '')", since)?,
+    })
+}
+
+/// What LLM calls cost over `range`.
+#[tauri::command]
+pub async fn usage_summary(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    range: Option<PerformanceRange>,
+) -> Result<UsageSummary, String> {
+    let range = range.unwrap_or_default();
+    let since = range.start_millis(now_millis());
+    db::run_blocking(&workspace.pool(), move |pool| usage_summary_db(pool, range, since)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> DbPool {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        pool
+    }
+
+    fn usage(model: &str, session_id: Option<&str>, timestamp: u64) -> LlmUsage {
+        LlmUsage {
+            provider_id: "anthropic".to_string(),
+            model: model.to_string(),
+            input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            latency_ms: 800,
+            timestamp,
+            session_id: session_id.map(str::to_string),
+            cycle_id: None,
+        }
+    }
+
+    #[test]
+    fn prices_match_the_longest_prefix_with_config_first() {
+        let mut config = UsageConfig::default();
+        let haiku = model_price(&config, "claude-haiku-4-5-20251001").unwrap();
+        assert_eq!(haiku.input_per_mtok, 1.0);
+        assert_eq!(
+            model_price(&config, "claude-opus-4-5-20251101").unwrap().input_per_mtok,
+            5.0
+        );
+        assert_eq!(model_price(&config, "claude-opus-4-1").unwrap().input_per_mtok, 15.0);
+        assert_eq!(
+            model_price(&config, "anthropic/claude-sonnet-4.5")
+                .unwrap()
+                .output_per_mtok,
+            15.0
+        );
+        assert!(model_price(&config, "gpt-4o").is_none());
+
+        config.prices.insert(
+            "claude-haiku".to_string(),
+            ModelPrice {
+                input_per_mtok: 2.0,
+                output_per_mtok: 8.0,
+            },
+        );
+        assert_eq!(
+            model_price(&config, "claude-haiku-4-5-20251001")
+                .unwrap()
+                .input_per_mtok,
+            2.0
+        );
+    }
+
+    #[test]
+    fn summary_reports_spend_per_day_model_and_session() {
+        let pool = test_pool();
+        let config = UsageConfig::default();
+        let day = 86_400_000;
+        // 2024-01-01T00:00Z
+        let base = 1_704_067_200_000;
+        usage_record_db(
+            &pool,
+            &usage("claude-haiku-4-5-20251001", Some("monitor-1"), base),
+            &config,
+        )
+        .unwrap();
+        usage_record_db(
+            &pool,
+            &usage("claude-haiku-4-5-20251001", Some("monitor-1"), base + 60_000),
+            &config,
+        )
+        .unwrap();
+        usage_record_db(&pool, &usage("claude-sonnet-4-5", None, base + day), &config).unwrap();
+        usage_record_db(&pool, &usage("mystery-model", Some("bt-1"), base + day), &config).unwrap();
+
+        let summary = usage_summary_db(&pool, PerformanceRange::All, None).unwrap();
+        assert_eq!(summary.totals.calls, 4);
+        assert_eq!(summary.totals.unpriced_calls, 1);
+        assert_eq!(summary.totals.input_tokens, 4_000_000);
+        // Haiku: 1.0 + 0.5 per call; Sonnet: 3.0 + 1.5
+        assert!((summary.totals.cost_usd - 7.5).abs() < 1e-9);
+
+        let days: Vec<(&str, u64)> = summary
+            .by_day
+            .iter()
+            .map(|b| (b.key.as_str(), b.totals.calls))
+            .collect();
+        assert_eq!(days, [("2024-01-01", 2), ("2024-01-02", 2)]);
+        let sessions: Vec<&str> = summary.by_session.iter().map(|b| b.key.as_str()).collect();
+        assert_eq!(sessions, ["", "bt-1", "monitor-1"]);
+        let haiku = summary
+            .by_model
+            .iter()
+            .find(|b| b.key.starts_with("claude-haiku"))
+            .unwrap();
+        assert!((haiku.totals.cost_usd - 3.0).abs() < 1e-9);
+
+        let recent = usage_summary_db(&pool, PerformanceRange::All, Some(base + day)).unwrap();
+        assert_eq!(recent.totals.calls, 2);
+        assert!(usage_summary_db(&pool, PerformanceRange::All, Some(base + 2 * day))
+            .unwrap()
+            .by_day
+            .is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+
+use crate::db::{self, DbPool};
+use crate::watcher::{FileWatcher, WatchPath, WatcherStatus};
+use crate::workspace::WorkspaceDb;
+
+/// User-registered watch directories, oldest first.
+pub fn watch_paths_list_db(pool: &DbPool) -> Result<Vec<WatchPath>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare("SELECT id, dir, pattern FROM watch_paths ORDER BY id")
+        .map_err(|e| e.to_string())?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(WatchPath {
+                id: row.get(0)?,
+                dir: row.get(1)?,
+                pattern: row.get(2)?,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for row in rows {
+        results.push(row.map_err(|e| e.to_string())?);
+    }
+    Ok(results)
+}
+
+/// Register `dir` to be watched for file names matching `pattern`.
+pub fn watch_paths_add_db(pool: &DbPool, dir: &str, pattern: &str) -> Result<WatchPath, String> {
+    if !Path::new(dir).is_absolute() {
+        return Err(format!("Watch directory must be an absolute path: '{}'", dir));
+    }
+    if pattern.contains(['/', '\\']) {
+        return Err("Watch pattern matches file names and cannot contain a path separator".to_string());
+    }
+    glob::Pattern::new(pattern).map_err(|e| format!("Invalid watch pattern '{}': {}", pattern, e))?;
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO watch_paths (dir, pattern) VALUES (?1, ?2)",
+        [dir, pattern],
+    )
+    .map_err(|e| match e {
+        rusqlite::Error::SqliteFailure(f, _) if f.code == rusqlite::ErrorCode::ConstraintViolation => {
+            format!("'{}' is already watched for '{}'", dir, pattern)
+        }
+        e => e.to_string(),
+    })?;
+    Ok(WatchPath {
+        id: conn.last_insert_rowid(),
+        dir: dir.to_string(),
+        pattern: pattern.to_string(),
+    })
+}
+
+/// Remove a watch path. Returns whether it existed.
+pub fn watch_paths_remove_db(pool: &DbPool, id: i64) -> Result<bool, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let removed = conn
+        .execute("DELETE FROM watch_paths WHERE id = ?1", [id])
+        .map_err(|e| e.to_string())?;
+    Ok(removed > 0)
+}
+
+/// Restart a running watcher so it picks up registry changes.
+fn restart_if_running(app: tauri::AppHandle, workspace: &WorkspaceDb, watcher: &FileWatcher) -> Result<(), String> {
+    if watcher.status().running {
+        watcher.start(app, &workspace.dir())?;
+    }
+    Ok(())
+}
+
+/// Watch the active workspace directory for config and source file changes.
+#[tauri::command]
+pub fn watcher_start(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    watcher: tauri::State<'_, FileWatcher>,
+) -> Result<WatcherStatus, String> {
+    watcher.start(app, &workspace.dir())
+}
+
+/// Stop the file watcher. Returns whether one was running.
+#[tauri::command]
+pub fn watcher_stop(watcher: tauri::State<'_, FileWatcher>) -> bool {
+    watcher.stop()
+}
+
+#[tauri::command]
+pub fn watcher_status(watcher: tauri::State<'_, FileWatcher>) -> WatcherStatus {
+    watcher.status()
+}
+
+/// User-registered watch directories. The data dir is always watched for
+/// CSV, JSON Lines and Parquet files.
+#[tauri::command]
+pub async fn watch_paths_list(workspace: tauri::State<'_, WorkspaceDb>) -> Result<Vec<WatchPath>, String> {
+    let pool = workspace.pool();
+    db::run_blocking(&pool, watch_paths_list_db).await
+}
+
+/// Watch `dir` for files matching `pattern` (e.g. `*.jsonl`).
+#[tauri::command]
+pub async fn watch_paths_add(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    watcher: tauri::State<'_, FileWatcher>,
+    dir: String,
+    pattern: String,
+) -> Result<WatchPath, String> {
+    let pool = workspace.pool();
+    let added = db::run_blocking(&pool, move |pool| watch_paths_add_db(pool, &dir, &pattern)).await?;
+    restart_if_running(app, &workspace, &watcher)?;
+    Ok(added)
+}
+
+/// Stop watching a registered path. Returns whether it existed.
+#[tauri::command]
+pub async fn watch_paths_remove(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    watcher: tauri::State<'_, FileWatcher>,
+    id: i64,
+) -> Result<bool, String> {
+    let pool = workspace.pool();
+    let removed = db::run_blocking(&pool, move |pool| watch_paths_remove_db(pool, id)).await?;
+    if removed {
+        restart_if_running(app, &workspace, &watcher)?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn add_list_and_remove() {
+        let (_dir, pool) = test_pool();
+        let added = watch_paths_add_db(&pool, "/data/feeds", "*.jsonl").unwrap();
+        assert_eq!(watch_paths_list_db(&pool).unwrap(), vec![added.clone()]);
+        assert!(watch_paths_remove_db(&pool, added.id).unwrap());
+        assert!(!watch_paths_remove_db(&pool, added.id).unwrap());
+        assert!(watch_paths_list_db(&pool).unwrap().is_empty());
+    }
+
+    #[test]
+    fn add_rejects_duplicates_and_bad_input() {
+        let (_dir, pool) = test_pool();
+        watch_paths_add_db(&pool, "/data", "*.csv").unwrap();
+        assert!(watch_paths_add_db(&pool, "/data", "*.csv").unwrap_err().contains("already watched"));
+        assert!(watch_paths_add_db(&pool, "relative", "*.csv").is_err());
+        assert!(watch_paths_add_db(&pool, "/data", "sub/*.csv").is_err());
+        assert!(watch_paths_add_db(&pool, "/data", "[").is_err());
+    }
+}
@@ -0,0 +1,42 @@
+use crate::commands::config::config_effective_db;
+use crate::loopback::generate_token;
+use crate::webhook::{ensure_token, webhook_config, WebhookServer, WebhookStatus};
+use crate::workspace::WorkspaceDb;
+
+#[tauri::command]
+pub fn webhook_status(webhook: tauri::State<'_, WebhookServer>) -> WebhookStatus {
+    webhook.status()
+}
+
+/// Listen on the configured localhost port, even if `webhook.enabled` is off.
+#[tauri::command]
+pub fn webhook_start(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    webhook: tauri::State<'_, WebhookServer>,
+) -> Result<WebhookStatus, String> {
+    let config = webhook_config(&config_effective_db(&workspace.pool())?);
+    webhook.start(app, config.port, ensure_token()?)
+}
+
+/// Stop the webhook listener. Returns whether one was running.
+#[tauri::command]
+pub fn webhook_stop(webhook: tauri::State<'_, WebhookServer>) -> bool {
+    webhook.stop()
+}
+
+/// The bearer token external scripts must send, created on first use.
+#[tauri::command]
+pub fn webhook_token_get() -> Result<String, String> {
+    ensure_token()
+}
+
+/// Replace the bearer token. A running listener switches to it at once, so
+/// the old one stops working immediately.
+#[tauri::command]
+pub fn webhook_token_rotate(webhook: tauri::State<'_, WebhookServer>) -> Result<String, String> {
+    let token = generate_token()?;
+    crate::keychain::webhook_token_set(&token)?;
+    webhook.set_token(token.clone());
+    Ok(token)
+}
@@ -0,0 +1,114 @@
+use std::path::Path;
+
+use tauri::Manager;
+
+use crate::bridge::SidecarBridge;
+use crate::events::{emit_event, event_names};
+use crate::tasks::TaskOutcome;
+use crate::time::now_millis;
+use crate::watcher::FileWatcher;
+use crate::workspace::{self, WorkspaceDb, WorkspaceInfo};
+use crate::workspace_archive::{self, ArchiveManifest};
+
+/// All workspaces, with the active one flagged.
+#[tauri::command]
+pub async fn workspace_list(
+    workspace: tauri::State<'_, WorkspaceDb>,
+) -> Result<Vec<WorkspaceInfo>, String> {
+    let root = workspace.root().to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || workspace::list(&root))
+        .await
+        .map_err(|e| format!("Workspace task failed: {}", e))?
+}
+
+/// Create a new, empty workspace. Does not switch to it.
+#[tauri::command]
+pub async fn workspace_create(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    name: String,
+) -> Result<WorkspaceInfo, String> {
+    let root = workspace.root().to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || workspace::create(&root, &name))
+        .await
+        .map_err(|e| format!("Workspace task failed: {}", e))?
+}
+
+/// Make `name` the active workspace. Refused while the agent is running, since
+/// it would keep writing into the previous workspace's history.
+#[tauri::command]
+pub async fn workspace_switch(
+    app: tauri::AppHandle,
+    workspace: tauri::State<'_, WorkspaceDb>,
+    bridge: tauri::State<'_, SidecarBridge>,
+    watcher: tauri::State<'_, FileWatcher>,
+    name: String,
+) -> Result<WorkspaceInfo, String> {
+    if bridge.is_running() {
+        return Err("Stop the agent before switching workspaces".to_string());
+    }
+    let info = workspace.switch(&name)?;
+    if watcher.status().running {
+        if let Err(e) = watcher.start(app.clone(), Path::new(&info.path)) {
+            tracing::warn!(error = %e, "Failed to move file watcher to new workspace");
+        }
+    }
+    let _ = emit_event(&app, event_names::WORKSPACE_SWITCHED, info.clone());
+    Ok(info)
+}
+
+/// Export the active workspace (database and config) to a zip at `path`.
+#[tauri::command]
+pub async fn workspace_export(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+) -> Result<ArchiveManifest, String> {
+    let pool = workspace.pool();
+    let name = workspace.name();
+    tauri::async_runtime::spawn_blocking(move || {
+        workspace_archive::export(&pool, &name, Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Workspace task failed: {}", e))?
+}
+
+/// Restore an exported archive as a new workspace. Defaults to the workspace
+/// name recorded in the archive; does not switch to it.
+#[tauri::command]
+pub async fn workspace_import(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    path: String,
+    name: Option<String>,
+) -> Result<WorkspaceInfo, String> {
+    let root = workspace.root().to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let src = Path::new(&path);
+        let name = match name {
+            Some(name) => name,
+            None => workspace_archive::read_manifest(src)?.workspace,
+        };
+        workspace_archive::import(&root, &name, src)
+    })
+    .await
+    .map_err(|e| format!("Workspace task failed: {}", e))?
+}
+
+/// Scheduled task: archive the active workspace to `~/.finwatch/backups/`
+/// once a day, keeping the last week.
+pub fn backup_task(app: &tauri::AppHandle) -> Result<TaskOutcome, String> {
+    let workspace = app.state::<WorkspaceDb>();
+    let now = now_millis() as i64;
+    let made = workspace_archive::backup_if_due(
+        &workspace.pool(),
+        &workspace.name(),
+        &workspace_archive::backups_dir(),
+        now,
+        workspace_archive::BACKUPS_KEPT,
+    )?;
+    Ok(match made {
+        Some(path) => {
+            tracing::info!(path = %path.display(), "Workspace backed up");
+            TaskOutcome::Ran
+        }
+        None => TaskOutcome::Skipped,
+    })
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_logs::{self, AppLogLine};
+use crate::time::now_millis;
+
+/// Crash records kept; older ones are deleted when a new one is written.
+const MAX_RECORDS: usize = 10;
+/// App log lines attached to each crash record.
+const LOG_TAIL_LINES: usize = 100;
+/// File naming the last record the user has seen.
+const SEEN_FILE: &str = "seen";
+
+/// Directory holding crash records: `~/.finwatch/crashes/`.
+pub fn crashes_dir() -> PathBuf {
+    crate::db::finwatch_root_dir().join("crashes")
+}
+
+/// What a panic left behind for the next launch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashRecord {
+    /// Epoch millis of the panic.
+    pub timestamp: u64,
+    pub version: String,
+    pub message: String,
+    /// `file:line:column` of the panic, if known.
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    /// The most recent app log lines, as written.
+    pub log_tail: Vec<String>,
+}
+
+/// The newest crash record and whether the user has already dismissed it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastCrash {
+    pub record: CrashRecord,
+    pub path: String,
+    pub seen: bool,
+}
+
+fn panic_message(info: &std::panic::PanicHookInfo<'_>) -> String {
+    if let Some(s) = info.payload().downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = info.payload().downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".to_string()
+    }
+}
+
+fn log_tail() -> Vec<String> {
+    app_logs::tail(&app_logs::app_logs_dir(), LOG_TAIL_LINES)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|line| match line {
+            AppLogLine::Record(record) => record.to_string(),
+            AppLogLine::Text(text) => text,
+        })
+        .collect()
+}
+
+/// Write `record` to `dir` as `crash-<timestamp>.json`, pruning old records.
+pub fn write_record(dir: &Path, record: &CrashRecord) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create crash dir: {}", e))?;
+    let path = dir.join(format!("crash-{}.json", record.timestamp));
+    let json = serde_json::to_string_pretty(record).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write crash record: {}", e))?;
+    let records = record_paths(dir);
+    for old in records.iter().take(records.len().saturating_sub(MAX_RECORDS)) {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Crash record files in `dir`, oldest first.
+fn record_paths(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<(u64, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let ts = name.strip_prefix("crash-")?.strip_suffix(".json")?.parse().ok()?;
+            Some((ts, entry.path()))
+        })
+        .collect();
+    paths.sort();
+    paths.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The newest crash record in `dir`, if any.
+pub fn last_crash(dir: &Path) -> Result<Option<LastCrash>, String> {
+    let Some(path) = record_paths(dir).pop() else {
+        return Ok(None);
+    };
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read crash record: {}", e))?;
+    let record: CrashRecord = serde_json::from_str(&json).map_err(|e| format!("Corrupt crash record: {}", e))?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let seen = fs::read_to_string(dir.join(SEEN_FILE)).is_ok_and(|s| s.trim() == name);
+    Ok(Some(LastCrash {
+        record,
+        path: path.display().to_string(),
+        seen,
+    }))
+}
+
+/// Mark the newest crash record as seen so it is no longer announced.
+pub fn dismiss(dir: &Path) -> Result<(), String> {
+    let Some(path) = record_paths(dir).pop() else {
+        return Ok(());
+    };
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    fs::write(dir.join(SEEN_FILE), name).map_err(|e| format!("Failed to dismiss crash: {}", e))
+}
+
+/// Record every panic to `~/.finwatch/crashes/` before running the default hook.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let record = CrashRecord {
+            timestamp: now_millis(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            message: panic_message(info),
+            location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+            thread: std::thread::current().name().map(str::to_string),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            log_tail: log_tail(),
+        };
+        tracing::error!(message = %record.message, location = ?record.location, "Panic");
+        if let Err(e) = write_record(&crashes_dir(), &record) {
+            eprintln!("Failed to record crash: {}", e);
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(timestamp: u64) -> CrashRecord {
+        CrashRecord {
+            timestamp,
+            version: "0.1.0".to_string(),
+            message: format!("boom {}", timestamp),
+            location: Some("src/lib.rs:1:1".to_string()),
+            thread: Some("main".to_string()),
+            backtrace: String::new(),
+            log_tail: vec!["{\"level\":\"INFO\"}".to_string()],
+        }
+    }
+
+    #[test]
+    fn last_crash_is_the_newest_until_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(last_crash(dir.path()).unwrap(), None);
+
+        write_record(dir.path(), &record(20)).unwrap();
+        write_record(dir.path(), &record(100)).unwrap();
+        let last = last_crash(dir.path()).unwrap().unwrap();
+        assert_eq!(last.record.message, "boom 100");
+        assert!(!last.seen);
+
+        dismiss(dir.path()).unwrap();
+        assert!(last_crash(dir.path()).unwrap().unwrap().seen);
+
+        write_record(dir.path(), &record(200)).unwrap();
+        assert!(!last_crash(dir.path()).unwrap().unwrap().seen);
+    }
+
+    #[test]
+    fn old_records_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        for ts in 1..=(MAX_RECORDS as u64 + 3) {
+            write_record(dir.path(), &record(ts)).unwrap();
+        }
+        let paths = record_paths(dir.path());
+        assert_eq!(paths.len(), MAX_RECORDS);
+        assert!(paths[0].ends_with("crash-4.json"));
+    }
+}
@@ -0,0 +1,738 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::time::Instant;
+
+use arrow::array::{Array, ArrayRef, AsArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Float64Type, Int64Type, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::commands::sources::{sources_health_db, sources_health_set_db};
+use crate::commands::ticks::ticks_replace_source_db;
+use crate::db::DbPool;
+use crate::events::{emit_event, event_names};
+use crate::time::now_millis;
+use crate::types::data::{DataTick, SourceHealth, SourceHealthStatus};
+
+/// Numeric timestamps above this are taken as millis rather than seconds.
+const MILLIS_THRESHOLD: f64 = 100_000_000_000.0;
+
+/// How the timestamp column is written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFormat {
+    /// Epoch seconds or millis (by magnitude), or an ISO date/time.
+    #[default]
+    Auto,
+    Ms,
+    S,
+    /// RFC 3339, `YYYY-MM-DD HH:MM:SS` or `YYYY-MM-DD`; zoneless values are UTC.
+    Iso,
+}
+
+/// The `csv` section of the app config: how CSV files dropped into the data
+/// dir are turned into ticks.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CsvSourceConfig {
+    /// Ingest CSV files when they appear or change.
+    pub enabled: bool,
+    pub timestamp_column: String,
+    pub timestamp_format: TimestampFormat,
+    /// Column holding the symbol; rows without one produce symbol-less ticks.
+    pub symbol_column: Option<String>,
+    /// CSV column to metric name. `None` turns every numeric column into a
+    /// metric under its header name.
+    pub metrics: Option<HashMap<String, String>>,
+    pub delimiter: char,
+}
+
+impl Default for CsvSourceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timestamp_column: "timestamp".to_string(),
+            timestamp_format: TimestampFormat::Auto,
+            symbol_column: Some("symbol".to_string()),
+            metrics: None,
+            delimiter: ',',
+        }
+    }
+}
+
+/// Parse the `csv` section of the app config.
+pub fn csv_source_config(app_config: &serde_json::Value) -> CsvSourceConfig {
+    app_config
+        .get("csv")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Outcome of ingesting one file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CsvIngestReport {
+    pub source_id: String,
+    pub file: String,
+    pub imported: usize,
+    /// Rows dropped for a missing or unparseable timestamp.
+    pub skipped: usize,
+    pub error: Option<String>,
+}
+
+/// Everything the caller needs to publish an ingestion.
+pub struct CsvIngest {
+    pub report: CsvIngestReport,
+    pub health: SourceHealth,
+    pub ticks: Vec<DataTick>,
+}
+
+/// Each file is its own source, so its health is tracked separately, e.g.
+/// `csv:trades` for `trades.csv`.
+pub fn source_id_for(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let kind = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_else(|| "file".to_string());
+    format!("{}:{}", kind, stem)
+}
+
+/// Parse `path` (CSV, JSON Lines for `.jsonl`/`.ndjson`, or Parquet) and replace the
+/// ticks previously stored for it, recording the result in `source_health`.
+/// Only fails if the health row cannot be written.
+pub fn ingest_file(pool: &DbPool, path: &Path, config: &CsvSourceConfig) -> Result<CsvIngest, String> {
+    let source_id = source_id_for(path);
+    let started = Instant::now();
+    let result = read_ticks(path, config, &source_id).and_then(|(ticks, skipped)| {
+        ticks_replace_source_db(pool, &source_id, &ticks)?;
+        Ok((ticks, skipped))
+    });
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let previous = sources_health_db(pool)?.remove(&source_id);
+    let now = now_millis();
+    let (health, report, ticks) = match result {
+        Ok((ticks, skipped)) => {
+            let health = SourceHealth {
+                source_id: source_id.clone(),
+                status: if skipped > 0 {
+                    SourceHealthStatus::Degraded
+                } else {
+                    SourceHealthStatus::Healthy
+                },
+                last_success: now,
+                last_failure: previous.and_then(|p| p.last_failure),
+                fail_count: 0,
+                latency_ms,
+                message: Some(format!("Imported {} rows, skipped {}", ticks.len(), skipped)),
+            };
+            let report = CsvIngestReport {
+                source_id,
+                file: path.display().to_string(),
+                imported: ticks.len(),
+                skipped,
+                error: None,
+            };
+            (health, report, ticks)
+        }
+        Err(e) => {
+            let health = SourceHealth {
+                source_id: source_id.clone(),
+                status: SourceHealthStatus::Offline,
+                last_success: previous.as_ref().map_or(0, |p| p.last_success),
+                last_failure: Some(now),
+                fail_count: previous.map_or(0, |p| p.fail_count) + 1,
+                latency_ms,
+                message: Some(e.clone()),
+            };
+            let report = CsvIngestReport {
+                source_id,
+                file: path.display().to_string(),
+                imported: 0,
+                skipped: 0,
+                error: Some(e),
+            };
+            (health, report, Vec::new())
+        }
+    };
+    sources_health_set_db(pool, &health)?;
+    Ok(CsvIngest {
+        report,
+        health,
+        ticks,
+    })
+}
+
+fn read_ticks(
+    path: &Path,
+    config: &CsvSourceConfig,
+    source_id: &str,
+) -> Result<(Vec<DataTick>, usize), String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    if extension == "parquet" {
+        return parse_parquet(path, config, source_id);
+    }
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    match extension {
+        "jsonl" | "ndjson" => Ok(parse_jsonl(&text, config, source_id)),
+        _ => parse_csv(&text, config, source_id),
+    }
+}
+
+/// Emit `data:tick` for every ingested row and `source:health-change` for the
+/// file's source, then hand back the report.
+pub fn publish_ingest(app: &tauri::AppHandle, ingest: CsvIngest) -> CsvIngestReport {
+    for tick in &ingest.ticks {
+        let _ = emit_event(app, event_names::DATA_TICK, tick);
+    }
+    crate::alerts::check_ticks(app, &ingest.ticks);
+    let _ = emit_event(app, event_names::SOURCE_HEALTH_CHANGE, &ingest.health);
+    ingest.report
+}
+
+/// Parse CSV `text` (header row first) into ticks. Returns the ticks and the
+/// number of rows skipped. Quoted fields may not span lines.
+pub fn parse_csv(
+    text: &str,
+    config: &CsvSourceConfig,
+    source_id: &str,
+) -> Result<(Vec<DataTick>, usize), String> {
+    let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+    let header = split_record(lines.next().ok_or("CSV file is empty")?, config.delimiter);
+    let column = |name: &str| header.iter().position(|h| h == name);
+    let ts_idx = column(&config.timestamp_column)
+        .ok_or_else(|| format!("Missing timestamp column '{}'", config.timestamp_column))?;
+    let symbol_idx = match &config.symbol_column {
+        Some(name) => column(name),
+        None => None,
+    };
+    let metric_names: Vec<Option<&str>> = header
+        .iter()
+        .enumerate()
+        .map(|(i, h)| {
+            if i == ts_idx || Some(i) == symbol_idx {
+                return None;
+            }
+            match &config.metrics {
+                Some(mapping) => mapping.get(h).map(String::as_str),
+                None => Some(h.as_str()),
+            }
+        })
+        .collect();
+    if let Some(mapping) = &config.metrics {
+        if let Some(missing) = mapping.keys().find(|c| column(c).is_none()) {
+            return Err(format!("Missing metric column '{}'", missing));
+        }
+    }
+
+    let mut ticks = Vec::new();
+    let mut skipped = 0;
+    for line in lines {
+        let fields = split_record(line, config.delimiter);
+        let Some(timestamp) = fields
+            .get(ts_idx)
+            .and_then(|v| parse_timestamp(v, config.timestamp_format))
+        else {
+            skipped += 1;
+            continue;
+        };
+        let mut metrics = HashMap::new();
+        let mut metadata = HashMap::new();
+        for (i, value) in fields.iter().enumerate() {
+            if i == ts_idx || Some(i) == symbol_idx || value.is_empty() {
+                continue;
+            }
+            let Some(column) = header.get(i) else {
+                continue;
+            };
+            match (metric_names[i], value.parse::<f64>()) {
+                (Some(metric), Ok(number)) if number.is_finite() => {
+                    metrics.insert(metric.to_string(), number);
+                }
+                _ => {
+                    metadata.insert(column.clone(), serde_json::Value::String(value.clone()));
+                }
+            }
+        }
+        ticks.push(DataTick {
+            source_id: source_id.to_string(),
+            timestamp,
+            symbol: symbol_idx
+                .and_then(|i| fields.get(i))
+                .filter(|s| !s.is_empty())
+                .cloned(),
+            metrics,
+            metadata,
+            raw: None,
+        });
+    }
+    Ok((ticks, skipped))
+}
+
+/// Parse JSON Lines: one object per line, either in the `DataTick` shape
+/// (`timestamp`, `symbol`, `metrics`, `metadata`) or as a flat record such as
+/// pandas' `to_json(orient="records", lines=True)`, which is mapped with the
+/// same column settings as CSV. Returns the ticks and the number of lines skipped.
+pub fn parse_jsonl(text: &str, config: &CsvSourceConfig, source_id: &str) -> (Vec<DataTick>, usize) {
+    let mut ticks = Vec::new();
+    let mut skipped = 0;
+    for line in text.lines().filter(|l| !l.trim().is_empty()) {
+        let record = match serde_json::from_str::<serde_json::Value>(line) {
+            Ok(serde_json::Value::Object(record)) => record,
+            _ => {
+                skipped += 1;
+                continue;
+            }
+        };
+        match jsonl_tick(record, config, source_id) {
+            Some(tick) => ticks.push(tick),
+            None => skipped += 1,
+        }
+    }
+    (ticks, skipped)
+}
+
+fn jsonl_tick(
+    mut record: serde_json::Map<String, serde_json::Value>,
+    config: &CsvSourceConfig,
+    source_id: &str,
+) -> Option<DataTick> {
+    let structured = record.get("metrics").is_some_and(|m| m.is_object());
+    let (ts_key, symbol_key) = if structured {
+        ("timestamp", Some("symbol"))
+    } else {
+        (config.timestamp_column.as_str(), config.symbol_column.as_deref())
+    };
+    let timestamp = match record.remove(ts_key)? {
+        serde_json::Value::Number(n) => parse_timestamp(&n.to_string(), config.timestamp_format),
+        serde_json::Value::String(s) => parse_timestamp(&s, config.timestamp_format),
+        _ => None,
+    }?;
+    let symbol = symbol_key
+        .and_then(|key| record.remove(key))
+        .and_then(|v| v.as_str().map(String::from))
+        .filter(|s| !s.is_empty());
+
+    let mut metrics = HashMap::new();
+    let mut metadata = HashMap::new();
+    if structured {
+        metrics = serde_json::from_value(record.remove("metrics")?).ok()?;
+        if let Some(serde_json::Value::Object(meta)) = record.remove("metadata") {
+            metadata.extend(meta);
+        }
+    } else {
+        for (key, value) in record {
+            let metric = match &config.metrics {
+                Some(mapping) => mapping.get(&key).map(String::as_str),
+                None => Some(key.as_str()),
+            };
+            match (metric, value.as_f64()) {
+                (Some(metric), Some(number)) => {
+                    metrics.insert(metric.to_string(), number);
+                }
+                _ if !value.is_null() => {
+                    metadata.insert(key, value);
+                }
+                _ => {}
+            }
+        }
+    }
+    Some(DataTick {
+        source_id: source_id.to_string(),
+        timestamp,
+        symbol,
+        metrics,
+        metadata,
+        raw: None,
+    })
+}
+
+/// Read a Parquet file such as pandas' `to_parquet()` with the same column
+/// settings as CSV. The timestamp column may hold Arrow timestamps or dates,
+/// or values `timestamp_format` accepts. Returns the ticks and the number of
+/// rows skipped.
+pub fn parse_parquet(
+    path: &Path,
+    config: &CsvSourceConfig,
+    source_id: &str,
+) -> Result<(Vec<DataTick>, usize), String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Invalid Parquet file {}: {}", path.display(), e))?;
+    let mut ticks = Vec::new();
+    let mut skipped = 0;
+    for batch in reader {
+        let batch = batch.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let (batch_ticks, batch_skipped) = parquet_batch_ticks(&batch, config, source_id)?;
+        ticks.extend(batch_ticks);
+        skipped += batch_skipped;
+    }
+    Ok((ticks, skipped))
+}
+
+/// A non-timestamp, non-symbol Parquet column: numeric columns mapped to a
+/// metric keep their values, everything else becomes metadata text.
+enum ParquetColumn {
+    Metric(String, ArrayRef),
+    Metadata(String, Vec<Option<String>>),
+}
+
+fn parquet_batch_ticks(
+    batch: &RecordBatch,
+    config: &CsvSourceConfig,
+    source_id: &str,
+) -> Result<(Vec<DataTick>, usize), String> {
+    let schema = batch.schema();
+    let column = |name: &str| schema.index_of(name).ok();
+    let ts_idx = column(&config.timestamp_column)
+        .ok_or_else(|| format!("Missing timestamp column '{}'", config.timestamp_column))?;
+    let symbol_idx = config.symbol_column.as_deref().and_then(column);
+    if let Some(mapping) = &config.metrics {
+        if let Some(missing) = mapping.keys().find(|c| column(c).is_none()) {
+            return Err(format!("Missing metric column '{}'", missing));
+        }
+    }
+
+    let timestamps = parquet_timestamps(batch.column(ts_idx), config.timestamp_format)?;
+    let symbols = symbol_idx.map(|i| display_values(batch.column(i))).transpose()?;
+    let mut columns = Vec::new();
+    for (i, field) in schema.fields().iter().enumerate() {
+        if i == ts_idx || Some(i) == symbol_idx {
+            continue;
+        }
+        let metric = match &config.metrics {
+            Some(mapping) => mapping.get(field.name()).cloned(),
+            None => Some(field.name().clone()),
+        };
+        columns.push(match metric {
+            Some(metric) if field.data_type().is_numeric() => {
+                let values = cast(batch.column(i), &DataType::Float64).map_err(|e| e.to_string())?;
+                ParquetColumn::Metric(metric, values)
+            }
+            _ => ParquetColumn::Metadata(field.name().clone(), display_values(batch.column(i))?),
+        });
+    }
+
+    let mut ticks = Vec::new();
+    let mut skipped = 0;
+    for (row, timestamp) in timestamps.into_iter().enumerate() {
+        let Some(timestamp) = timestamp else {
+            skipped += 1;
+            continue;
+        };
+        let mut metrics = HashMap::new();
+        let mut metadata = HashMap::new();
+        for column in &columns {
+            match column {
+                ParquetColumn::Metric(metric, values) => {
+                    let values = values.as_primitive::<Float64Type>();
+                    if values.is_valid(row) && values.value(row).is_finite() {
+                        metrics.insert(metric.clone(), values.value(row));
+                    }
+                }
+                ParquetColumn::Metadata(name, values) => {
+                    if let Some(value) = values[row].as_ref().filter(|v| !v.is_empty()) {
+                        metadata.insert(name.clone(), serde_json::Value::String(value.clone()));
+                    }
+                }
+            }
+        }
+        ticks.push(DataTick {
+            source_id: source_id.to_string(),
+            timestamp,
+            symbol: symbols.as_ref().and_then(|s| s[row].clone()).filter(|s| !s.is_empty()),
+            metrics,
+            metadata,
+            raw: None,
+        });
+    }
+    Ok((ticks, skipped))
+}
+
+/// Epoch millis per row of a Parquet timestamp column.
+fn parquet_timestamps(column: &ArrayRef, format: TimestampFormat) -> Result<Vec<Option<u64>>, String> {
+    // Raw values are rescaled rather than cast to another timestamp type,
+    // which would need named time zones such as "UTC" to be resolvable
+    let to_millis = |value: i64| match column.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => value.checked_mul(1000),
+        DataType::Timestamp(TimeUnit::Microsecond, _) => Some(value / 1000),
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => Some(value / 1_000_000),
+        DataType::Date32 => value.checked_mul(86_400_000),
+        _ => Some(value),
+    };
+    match column.data_type() {
+        DataType::Timestamp(..) | DataType::Date32 | DataType::Date64 => {
+            let raw = cast(column, &DataType::Int64).map_err(|e| e.to_string())?;
+            Ok(raw
+                .as_primitive::<Int64Type>()
+                .iter()
+                .map(|v| v.and_then(to_millis).and_then(|ms| u64::try_from(ms).ok()))
+                .collect())
+        }
+        _ => Ok(display_values(column)?
+            .into_iter()
+            .map(|v| v.and_then(|v| parse_timestamp(&v, format)))
+            .collect()),
+    }
+}
+
+/// Each value of `column` as text, `None` for nulls.
+fn display_values(column: &ArrayRef) -> Result<Vec<Option<String>>, String> {
+    // Zoned timestamps are shown as their UTC time, since named zones cannot be resolved
+    let column = match column.data_type() {
+        DataType::Timestamp(unit, Some(_)) => cast(column, &DataType::Int64)
+            .and_then(|raw| cast(&raw, &DataType::Timestamp(*unit, None)))
+            .map_err(|e| e.to_string())?,
+        _ => column.clone(),
+    };
+    let formatter = ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default()).map_err(|e| e.to_string())?;
+    Ok((0..column.len())
+        .map(|i| column.is_valid(i).then(|| formatter.value(i).to_string()))
+        .collect())
+}
+
+/// Split one CSV line, honouring double quotes and `""` escapes.
+pub(crate) fn split_record(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => {
+                fields.push(field.trim().to_string());
+                field.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// Epoch millis for `value`, or `None` if it does not match `format`.
+pub(crate) fn parse_timestamp(value: &str, format: TimestampFormat) -> Option<u64> {
+    let numeric = || value.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0);
+    match format {
+        TimestampFormat::Ms => numeric().map(|n| n as u64),
+        TimestampFormat::S => numeric().map(|n| (n * 1000.0) as u64),
+        TimestampFormat::Iso => parse_iso(value),
+        TimestampFormat::Auto => match numeric() {
+            Some(n) if n >= MILLIS_THRESHOLD => Some(n as u64),
+            Some(n) => Some((n * 1000.0) as u64),
+            None => parse_iso(value),
+        },
+    }
+}
+
+fn parse_iso(value: &str) -> Option<u64> {
+    let millis = if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        dt.timestamp_millis()
+    } else if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        dt.and_utc().timestamp_millis()
+    } else {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .ok()?
+            .and_hms_opt(0, 0, 0)?
+            .and_utc()
+            .timestamp_millis()
+    };
+    u64::try_from(millis).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ticks::ticks_query_db;
+    use crate::types::data::TickRange;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn parses_rows_with_default_mapping() {
+        let csv = "timestamp,symbol,close,volume,venue\n\
+                   1700000000,NET,71.5,1200,\"NYSE, Arca\"\n\
+                   bad,NET,1,1,x\n";
+        let (ticks, skipped) = parse_csv(csv, &CsvSourceConfig::default(), "csv:t").unwrap();
+        assert_eq!(skipped, 1);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].timestamp, 1_700_000_000_000);
+        assert_eq!(ticks[0].symbol.as_deref(), Some("NET"));
+        assert_eq!(ticks[0].metrics["close"], 71.5);
+        assert_eq!(ticks[0].metrics["volume"], 1200.0);
+        assert_eq!(ticks[0].metadata["venue"], "NYSE, Arca");
+    }
+
+    #[test]
+    fn explicit_mapping_renames_and_limits_metrics() {
+        let config = CsvSourceConfig {
+            timestamp_column: "date".to_string(),
+            timestamp_format: TimestampFormat::Iso,
+            symbol_column: Some("ticker".to_string()),
+            metrics: Some([("Adj Close".to_string(), "close".to_string())].into()),
+            delimiter: ';',
+            ..Default::default()
+        };
+        let csv = "date;ticker;Adj Close;Open\n2024-01-02;NET;80.1;79\n";
+        let (ticks, _) = parse_csv(csv, &config, "csv:t").unwrap();
+        assert_eq!(ticks[0].timestamp, 1_704_153_600_000);
+        assert_eq!(ticks[0].metrics.len(), 1);
+        assert_eq!(ticks[0].metrics["close"], 80.1);
+        assert_eq!(ticks[0].metadata["Open"], "79");
+    }
+
+    #[test]
+    fn missing_columns_are_errors() {
+        assert!(parse_csv("time,close\n1,2\n", &CsvSourceConfig::default(), "s").is_err());
+        let config = CsvSourceConfig {
+            metrics: Some([("nope".to_string(), "x".to_string())].into()),
+            ..Default::default()
+        };
+        assert!(parse_csv("timestamp,close\n1,2\n", &config, "s").is_err());
+        assert!(parse_csv("", &CsvSourceConfig::default(), "s").is_err());
+    }
+
+    #[test]
+    fn parses_structured_jsonl_lines() {
+        let text = "{\"timestamp\":1700000000000,\"symbol\":\"NET\",\"metrics\":{\"close\":2}}\nnot json\n\n";
+        let (ticks, skipped) = parse_jsonl(text, &CsvSourceConfig::default(), "jsonl:feed");
+        assert_eq!(skipped, 1);
+        assert_eq!(ticks[0].source_id, "jsonl:feed");
+        assert_eq!(ticks[0].timestamp, 1_700_000_000_000);
+        assert_eq!(ticks[0].metrics["close"], 2.0);
+    }
+
+    #[test]
+    fn parses_flat_pandas_records() {
+        let text = r#"{"timestamp":"2024-01-02T00:00:00Z","symbol":"NET","close":80.5,"venue":"X","note":null}
+{"symbol":"NET","close":1}"#;
+        let (ticks, skipped) = parse_jsonl(text, &CsvSourceConfig::default(), "jsonl:export");
+        assert_eq!(skipped, 1);
+        assert_eq!(ticks[0].timestamp, 1_704_153_600_000);
+        assert_eq!(ticks[0].symbol.as_deref(), Some("NET"));
+        assert_eq!(ticks[0].metrics["close"], 80.5);
+        assert_eq!(ticks[0].metadata["venue"], "X");
+        assert!(!ticks[0].metadata.contains_key("note"));
+    }
+
+    #[test]
+    fn parquet_files_round_trip() {
+        use std::sync::Arc;
+
+        use arrow::array::{Float64Array, Int64Array, StringArray, TimestampMillisecondArray};
+        use arrow::datatypes::{Field, Schema};
+        use parquet::arrow::ArrowWriter;
+
+        let (dir, pool) = test_pool();
+        let file = dir.path().join("bars.parquet");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new(
+                "timestamp",
+                DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+                true,
+            ),
+            Field::new("symbol", DataType::Utf8, true),
+            Field::new("close", DataType::Float64, true),
+            Field::new("volume", DataType::Int64, true),
+            Field::new("venue", DataType::Utf8, true),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(TimestampMillisecondArray::from(vec![Some(1_704_153_600_000), None]).with_timezone("UTC")),
+            Arc::new(StringArray::from(vec![Some("NET"), Some("NET")])),
+            Arc::new(Float64Array::from(vec![Some(80.5), Some(81.0)])),
+            Arc::new(Int64Array::from(vec![Some(1200), Some(900)])),
+            Arc::new(StringArray::from(vec![Some("NYSE"), None])),
+        ];
+        let batch = RecordBatch::try_new(schema.clone(), columns).unwrap();
+        let mut writer = ArrowWriter::try_new(File::create(&file).unwrap(), schema, None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let result = ingest_file(&pool, &file, &CsvSourceConfig::default()).unwrap();
+        assert_eq!(result.report.error, None);
+        assert_eq!(result.report.source_id, "parquet:bars");
+        assert_eq!((result.report.imported, result.report.skipped), (1, 1));
+        let stored = ticks_query_db(&pool, "NET", &TickRange::default()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].timestamp, 1_704_153_600_000);
+        assert_eq!(stored[0].metrics["close"], 80.5);
+        assert_eq!(stored[0].metrics["volume"], 1200.0);
+        assert_eq!(stored[0].metadata["venue"], "NYSE");
+
+        std::fs::write(&file, b"PAR1").unwrap();
+        let broken = ingest_file(&pool, &file, &CsvSourceConfig::default()).unwrap();
+        assert!(broken.report.error.unwrap().contains("Invalid Parquet file"));
+    }
+
+    #[test]
+    fn timestamp_formats() {
+        assert_eq!(parse_timestamp("1700000000000", TimestampFormat::Auto), Some(1_700_000_000_000));
+        assert_eq!(parse_timestamp("1700000000", TimestampFormat::Auto), Some(1_700_000_000_000));
+        assert_eq!(parse_timestamp("1700000000", TimestampFormat::Ms), Some(1_700_000_000));
+        assert_eq!(
+            parse_timestamp("2024-01-02T00:00:01Z", TimestampFormat::Auto),
+            Some(1_704_153_601_000)
+        );
+        assert_eq!(
+            parse_timestamp("2024-01-02 00:00:01", TimestampFormat::Iso),
+            Some(1_704_153_601_000)
+        );
+        assert_eq!(parse_timestamp("yesterday", TimestampFormat::Auto), None);
+    }
+
+    #[test]
+    fn ingest_replaces_previous_rows_and_records_health() {
+        let (dir, pool) = test_pool();
+        let file = dir.path().join("trades.csv");
+        std::fs::write(&file, "timestamp,symbol,close\n1000000000000,NET,1\n1000000001000,NET,2\n").unwrap();
+        let config = CsvSourceConfig::default();
+        let first = ingest_file(&pool, &file, &config).unwrap();
+        assert_eq!(first.report.imported, 2);
+        assert_eq!(first.health.status, SourceHealthStatus::Healthy);
+
+        std::fs::write(&file, "timestamp,symbol,close\n1000000002000,NET,3\n").unwrap();
+        ingest_file(&pool, &file, &config).unwrap();
+        let stored = ticks_query_db(&pool, "NET", &TickRange::default()).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].source_id, "csv:trades");
+
+        let health = &sources_health_db(&pool).unwrap()["csv:trades"];
+        assert_eq!(health.fail_count, 0);
+    }
+
+    #[test]
+    fn failed_ingest_marks_source_offline() {
+        let (dir, pool) = test_pool();
+        let file = dir.path().join("broken.csv");
+        std::fs::write(&file, "when,close\n1,2\n").unwrap();
+        let result = ingest_file(&pool, &file, &CsvSourceConfig::default()).unwrap();
+        assert!(result.report.error.is_some());
+        assert_eq!(result.health.status, SourceHealthStatus::Offline);
+        assert_eq!(result.health.fail_count, 1);
+        let again = ingest_file(&pool, &file, &CsvSourceConfig::default()).unwrap();
+        assert_eq!(again.health.fail_count, 2);
+    }
+}
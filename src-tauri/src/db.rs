@@ -4,12 +4,20 @@ use std::path::PathBuf;
 
 pub type DbPool = Pool<SqliteConnectionManager>;
 
-pub fn finwatch_data_dir() -> PathBuf {
+/// Root of all FinWatch data (`~/.finwatch`), shared by every workspace.
+pub fn finwatch_root_dir() -> PathBuf {
     dirs::home_dir()
         .expect("Could not determine home directory")
         .join(".finwatch")
 }
 
+/// Data directory of the active workspace.
+pub fn finwatch_data_dir() -> PathBuf {
+    let root = finwatch_root_dir();
+    let active = crate::workspace::read_active(&root);
+    crate::workspace::workspace_dir(&root, &active)
+}
+
 pub fn create_pool(db_path: &std::path::Path) -> Result<DbPool, Box<dyn std::error::Error>> {
     if let Some(parent) = db_path.parent() {
         std::fs::create_dir_all(parent)?;
@@ -73,16 +81,47 @@ pub fn init_db(pool: &DbPool) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Flush the WAL into the main database file. Called on shutdown so no
+/// committed writes are left only in the `-wal` sidecar file.
+pub fn checkpoint(pool: &DbPool) -> Result<(), Box<dyn std::error::Error>> {
+    let conn = pool.get()?;
+    conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+    Ok(())
+}
+
+/// Run blocking database work on the blocking thread pool so async Tauri
+/// commands never stall the IPC runtime. The pool handle is cloned into the task.
+pub async fn run_blocking<T, F>(pool: &DbPool, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce(&DbPool) -> Result<T, String> + Send + 'static,
+{
+    let pool = pool.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let started = std::time::Instant::now();
+        let result = f(&pool);
+        crate::metrics::global().observe(crate::metrics::DB_TASK_DURATION, &[], started.elapsed());
+        result
+    })
+        .await
+        .map_err(|e| format!("Database task failed: {}", e))?
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn finwatch_data_dir_ends_with_finwatch() {
-        let dir = finwatch_data_dir();
+    fn finwatch_root_dir_ends_with_finwatch() {
+        let dir = finwatch_root_dir();
         assert!(dir.ends_with(".finwatch"));
     }
 
+    #[test]
+    fn finwatch_data_dir_is_under_root() {
+        assert!(finwatch_data_dir().starts_with(finwatch_root_dir()));
+    }
+
     #[test]
     fn create_pool_returns_valid_pool() {
         let dir = tempfile::tempdir().unwrap();
@@ -131,4 +170,43 @@ mod tests {
         init_db(&pool).unwrap();
         init_db(&pool).unwrap(); // second call should not fail
     }
+
+    #[test]
+    fn checkpoint_succeeds_on_wal_db() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("test.sqlite");
+        let pool = create_pool(&db_path).unwrap();
+        init_db(&pool).unwrap();
+        checkpoint(&pool).unwrap();
+    }
+
+    #[test]
+    fn run_blocking_returns_closure_result() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = create_pool(&dir.path().join("test.sqlite")).unwrap();
+        let value = tauri::async_runtime::block_on(run_blocking(&pool, |pool| {
+            let conn = pool.get().map_err(|e| e.to_string())?;
+            conn.query_row("SELECT 41 + 1", [], |row| row.get::<_, i64>(0))
+                .map_err(|e| e.to_string())
+        }))
+        .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn run_blocking_propagates_errors_and_panics() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = create_pool(&dir.path().join("test.sqlite")).unwrap();
+        let err = tauri::async_runtime::block_on(run_blocking(&pool, |_| {
+            Err::<(), _>("no such table".to_string())
+        }))
+        .unwrap_err();
+        assert_eq!(err, "no such table");
+
+        let err = tauri::async_runtime::block_on(run_blocking(&pool, |_| -> Result<(), String> {
+            panic!("boom")
+        }))
+        .unwrap_err();
+        assert!(err.starts_with("Database task failed"));
+    }
 }
@@ -0,0 +1,379 @@
+//! On-device embedding of agent memory, so memories can be searched by meaning
+//! without an API key. Inference is only built with the `local-embeddings`
+//! feature; model files are downloaded on request into `~/.finwatch/models`.
+
+use std::path::{Path, PathBuf};
+#[cfg(feature = "local-embeddings")]
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::http;
+
+/// Whether this build can run local embedding models.
+pub const AVAILABLE: bool = cfg!(feature = "local-embeddings");
+
+/// How token embeddings are reduced to one sentence embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Average of all token embeddings.
+    Mean,
+    /// The embedding of the leading `[CLS]` token.
+    Cls,
+}
+
+/// A sentence embedding model that can run on-device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocalModel {
+    /// Name used in the `embeddings.model` setting and as its directory name.
+    pub name: &'static str,
+    /// Hugging Face repository holding the ONNX export.
+    pub repo: &'static str,
+    /// Commit of `repo` the files are downloaded from; empty until pinned.
+    pub revision: &'static str,
+    /// Expected SHA-256 of each of `MODEL_FILES`, as lowercase hex.
+    pub checksums: &'static [(&'static str, &'static str)],
+    pub dimensions: usize,
+    /// The pooling the model was trained with.
+    pub pooling: Pooling,
+}
+
+impl LocalModel {
+    fn checksum(&self, file: &str) -> Option<&'static str> {
+        self.checksums.iter().find(|(f, _)| *f == file).map(|(_, sha)| *sha)
+    }
+}
+
+// Downloads are refused until a model's revision and checksums are pinned
+pub const LOCAL_MODELS: &[LocalModel] = &[
+    LocalModel {
+        name: "all-MiniLM-L6-v2",
+        repo: "Xenova/all-MiniLM-L6-v2",
+        revision: "",
+        checksums: &[],
+        dimensions: 384,
+        pooling: Pooling::Mean,
+    },
+    LocalModel {
+        name: "bge-small-en-v1.5",
+        repo: "Xenova/bge-small-en-v1.5",
+        revision: "",
+        checksums: &[],
+        dimensions: 384,
+        pooling: Pooling::Cls,
+    },
+];
+
+pub const DEFAULT_LOCAL_MODEL: &str = "all-MiniLM-L6-v2";
+
+/// Files every model needs, relative to its repository root.
+pub const MODEL_FILES: &[&str] = &[
+    "onnx/model_quantized.onnx",
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+
+const NOT_BUILT: &str = "This build does not include local embeddings (the `local-embeddings` feature)";
+
+/// The `embeddings` section of the app config.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmbeddingsConfig {
+    /// Embed memories with the local model once it is downloaded.
+    pub local: bool,
+    /// One of [`LOCAL_MODELS`] by name.
+    pub model: String,
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            local: false,
+            model: DEFAULT_LOCAL_MODEL.to_string(),
+        }
+    }
+}
+
+/// Parse the `embeddings` section of the app config.
+pub fn embeddings_config(app_config: &serde_json::Value) -> EmbeddingsConfig {
+    app_config
+        .get("embeddings")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+pub fn local_model(name: &str) -> Option<&'static LocalModel> {
+    LOCAL_MODELS.iter().find(|m| m.name == name)
+}
+
+/// Where downloaded models live, shared by every workspace.
+pub fn models_dir() -> PathBuf {
+    crate::db::finwatch_root_dir().join("models")
+}
+
+/// Files of `MODEL_FILES` not yet downloaded into `dir`.
+pub fn missing_files(dir: &Path) -> Vec<&'static str> {
+    MODEL_FILES.iter().copied().filter(|f| !dir.join(f).is_file()).collect()
+}
+
+/// Total size of the model files present in `dir`.
+pub fn downloaded_bytes(dir: &Path) -> u64 {
+    MODEL_FILES
+        .iter()
+        .filter_map(|f| std::fs::metadata(dir.join(f)).ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// The configured model when local embedding is enabled, built in, and
+/// fully downloaded under `root`.
+pub fn ready_model(config: &EmbeddingsConfig, root: &Path) -> Option<&'static LocalModel> {
+    let model = local_model(&config.model)?;
+    (AVAILABLE && config.local && missing_files(&root.join(model.name)).is_empty()).then_some(model)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbeddingsStatus {
+    /// Whether this build includes local embeddings at all.
+    pub available: bool,
+    /// The `embeddings.local` setting.
+    pub enabled: bool,
+    pub model: String,
+    /// `None` when `model` is not a known local model.
+    pub dimensions: Option<usize>,
+    pub missing_files: Vec<String>,
+    pub size_bytes: u64,
+    /// Whether the model is loaded in memory.
+    pub loaded: bool,
+    /// Whether memories are embedded and searched with the local model.
+    pub ready: bool,
+}
+
+pub fn embeddings_status(config: &EmbeddingsConfig, root: &Path, embedder: &LocalEmbedder) -> EmbeddingsStatus {
+    let model = local_model(&config.model);
+    let dir = root.join(&config.model);
+    EmbeddingsStatus {
+        available: AVAILABLE,
+        enabled: config.local,
+        model: config.model.clone(),
+        dimensions: model.map(|m| m.dimensions),
+        missing_files: match model {
+            Some(_) => missing_files(&dir).into_iter().map(String::from).collect(),
+            None => MODEL_FILES.iter().map(|f| f.to_string()).collect(),
+        },
+        size_bytes: downloaded_bytes(&dir),
+        loaded: embedder.is_loaded(&config.model),
+        ready: ready_model(config, root).is_some(),
+    }
+}
+
+/// Check `bytes` against the SHA-256 pinned for `file` of `model`.
+pub fn verify_checksum(model: &LocalModel, file: &str, bytes: &[u8]) -> Result<(), String> {
+    let expected = model
+        .checksum(file)
+        .ok_or_else(|| format!("No checksum is pinned for {} of {}", file, model.name))?;
+    let actual: String = Sha256::digest(bytes).iter().map(|b| format!("{:02x}", b)).collect();
+    if actual != expected {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            file, expected, actual
+        ));
+    }
+    Ok(())
+}
+
+/// Download `model`'s missing files into `dir`, or all of them with `force`,
+/// from its pinned revision. Each file is written next to its destination,
+/// checked against its pinned SHA-256 and only then renamed into place, so an
+/// interrupted or tampered download never leaves a model file. `on_file` is
+/// called with the file's index, the number of files to fetch, and its name
+/// before each download. Returns the bytes downloaded.
+pub async fn download_model(
+    model: &LocalModel,
+    dir: &Path,
+    force: bool,
+    on_file: impl Fn(usize, usize, &str),
+) -> Result<u64, String> {
+    if !AVAILABLE {
+        return Err(NOT_BUILT.to_string());
+    }
+    if model.revision.is_empty() {
+        return Err(format!(
+            "No revision is pinned for {}; refusing an unverified download",
+            model.name
+        ));
+    }
+    let files: Vec<&str> = if force {
+        MODEL_FILES.to_vec()
+    } else {
+        missing_files(dir)
+    };
+    let client = http::client()?;
+    let mut total = 0;
+    for (i, file) in files.iter().enumerate() {
+        on_file(i, files.len(), file);
+        let url = format!(
+            "https://huggingface.co/{}/resolve/{}/{}",
+            model.repo, model.revision, file
+        );
+        let response = http::send(client.get(&url))
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", file, e))?;
+        if !response.status().is_success() {
+            return Err(format!("Failed to download {}: {}", file, response.status()));
+        }
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", file, e))?;
+        let dest = dir.join(file);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let partial = dest.with_file_name(format!("{}.part", file.rsplit('/').next().unwrap_or(file)));
+        std::fs::write(&partial, &bytes).map_err(|e| format!("Failed to write {}: {}", partial.display(), e))?;
+        if let Err(e) = verify_checksum(model, file, &bytes) {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+        std::fs::rename(&partial, &dest).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+        total += bytes.len() as u64;
+    }
+    Ok(total)
+}
+
+/// The loaded local model, loaded on first use and kept for the app's lifetime.
+pub struct LocalEmbedder {
+    #[cfg(feature = "local-embeddings")]
+    loaded: Mutex<Option<(&'static str, fastembed::TextEmbedding)>>,
+}
+
+impl LocalEmbedder {
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "local-embeddings")]
+            loaded: Mutex::new(None),
+        }
+    }
+
+    #[cfg(feature = "local-embeddings")]
+    pub fn is_loaded(&self, model: &str) -> bool {
+        let loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        loaded.as_ref().is_some_and(|(name, _)| *name == model)
+    }
+
+    #[cfg(not(feature = "local-embeddings"))]
+    pub fn is_loaded(&self, _model: &str) -> bool {
+        false
+    }
+
+    /// Drop the loaded model, so the next call reloads it from disk.
+    pub fn unload(&self) {
+        #[cfg(feature = "local-embeddings")]
+        self.loaded.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    /// Embed `texts` with `model`, loading it from `dir` if it is not the
+    /// model already loaded.
+    #[cfg(feature = "local-embeddings")]
+    pub fn embed(&self, model: &'static LocalModel, dir: &Path, texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        let mut loaded = self.loaded.lock().unwrap_or_else(|e| e.into_inner());
+        if !loaded.as_ref().is_some_and(|(name, _)| *name == model.name) {
+            *loaded = Some((model.name, load_model(model, dir)?));
+            tracing::info!(model = model.name, "Loaded local embedding model");
+        }
+        let (_, embedding) = loaded.as_ref().expect("model was just loaded");
+        embedding.embed(texts.to_vec(), None).map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(feature = "local-embeddings"))]
+    pub fn embed(&self, _model: &'static LocalModel, _dir: &Path, _texts: &[String]) -> Result<Vec<Vec<f32>>, String> {
+        Err(NOT_BUILT.to_string())
+    }
+}
+
+impl Default for LocalEmbedder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "local-embeddings")]
+fn load_model(model: &LocalModel, dir: &Path) -> Result<fastembed::TextEmbedding, String> {
+    let read =
+        |file: &str| std::fs::read(dir.join(file)).map_err(|e| format!("Failed to read model file {}: {}", file, e));
+    let tokenizer_files = fastembed::TokenizerFiles {
+        tokenizer_file: read("tokenizer.json")?,
+        config_file: read("config.json")?,
+        special_tokens_map_file: read("special_tokens_map.json")?,
+        tokenizer_config_file: read("tokenizer_config.json")?,
+    };
+    let pooling = match model.pooling {
+        Pooling::Mean => fastembed::Pooling::Mean,
+        Pooling::Cls => fastembed::Pooling::Cls,
+    };
+    let user_model = fastembed::UserDefinedEmbeddingModel::new(read("onnx/model_quantized.onnx")?, tokenizer_files)
+        .with_pooling(pooling);
+    fastembed::TextEmbedding::try_new_from_user_defined(user_model, fastembed::InitOptionsUserDefined::default())
+        .map_err(|e| format!("Failed to load local embedding model: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_reports_missing_files_until_downloaded() {
+        let root = tempfile::tempdir().unwrap();
+        let config = EmbeddingsConfig {
+            local: true,
+            ..Default::default()
+        };
+        let embedder = LocalEmbedder::new();
+        let status = embeddings_status(&config, root.path(), &embedder);
+        assert_eq!(status.missing_files.len(), MODEL_FILES.len());
+        assert_eq!(status.dimensions, Some(384));
+        assert!(!status.ready);
+
+        let dir = root.path().join(DEFAULT_LOCAL_MODEL);
+        for file in MODEL_FILES {
+            let path = dir.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"{}").unwrap();
+        }
+        let status = embeddings_status(&config, root.path(), &embedder);
+        assert!(status.missing_files.is_empty());
+        assert_eq!(status.size_bytes, 2 * MODEL_FILES.len() as u64);
+        assert_eq!(status.ready, AVAILABLE);
+
+        let unknown = EmbeddingsConfig {
+            local: true,
+            model: "word2vec".to_string(),
+        };
+        assert_eq!(ready_model(&unknown, root.path()), None);
+        assert_eq!(embeddings_config(&serde_json::json!({})), EmbeddingsConfig::default());
+    }
+
+    #[test]
+    fn downloads_are_checked_against_pinned_checksums() {
+        let model = LocalModel {
+            checksums: &[(
+                "config.json",
+                // SHA-256 of "{}"
+                "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a",
+            )],
+            ..*local_model(DEFAULT_LOCAL_MODEL).unwrap()
+        };
+        assert!(verify_checksum(&model, "config.json", b"{}").is_ok());
+        assert!(verify_checksum(&model, "config.json", b"{ }")
+            .unwrap_err()
+            .contains("mismatch"));
+        assert!(verify_checksum(&model, "tokenizer.json", b"{}").is_err());
+        assert_eq!(local_model("bge-small-en-v1.5").unwrap().pooling, Pooling::Cls);
+    }
+}
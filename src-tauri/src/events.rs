@@ -6,10 +6,35 @@ pub mod event_names {
     pub const AGENT_ACTIVITY: &str = "agent:activity";
     pub const DATA_TICK: &str = "data:tick";
     pub const ANOMALY_DETECTED: &str = "anomaly:detected";
+    pub const ANOMALY_UPDATED: &str = "anomaly:updated";
     pub const SOURCE_HEALTH_CHANGE: &str = "source:health-change";
+    pub const PROVIDER_HEALTH_CHANGE: &str = "provider:health-change";
+    pub const LLM_USAGE: &str = "llm:usage";
     pub const MEMORY_UPDATED: &str = "memory:updated";
+    pub const EMBEDDINGS_DOWNLOAD: &str = "embeddings:download";
     pub const BACKTEST_PROGRESS: &str = "backtest:progress";
     pub const BACKTEST_COMPLETE: &str = "backtest:complete";
+    pub const SIDECAR_CRASHED: &str = "sidecar:crashed";
+    pub const SIDECAR_RESTARTING: &str = "sidecar:restarting";
+    pub const SIDECAR_RESTARTED: &str = "sidecar:restarted";
+    pub const SIDECAR_INCOMPATIBLE: &str = "sidecar:incompatible";
+    pub const SIDECAR_RESOURCE_LIMIT: &str = "sidecar:resource-limit";
+    pub const DB_MAINTENANCE: &str = "db:maintenance";
+    pub const WORKSPACE_SWITCHED: &str = "workspace:switched";
+    pub const CONFIG_CHANGED: &str = "config:changed";
+    pub const CREDENTIALS_REVOKED: &str = "credentials:revoked";
+    pub const ASSETS_REFRESHED: &str = "assets:refreshed";
+    pub const AGENT_RESTART: &str = "agent:restart";
+    pub const SCHEDULE_TRANSITION: &str = "schedule:transition";
+    pub const AGENT_UNHEALTHY: &str = "agent:unhealthy";
+    pub const SOURCE_QUARANTINED: &str = "source:quarantined";
+    pub const NEWS_ITEM: &str = "news:item";
+    pub const ALERT_TRIGGERED: &str = "alert:triggered";
+    pub const PORTFOLIO_UPDATED: &str = "portfolio:updated";
+    pub const ORDER_UPDATED: &str = "order:updated";
+    pub const TRADING_HALT_CHANGED: &str = "trading:halt-changed";
+    pub const OFFLINE_CHANGED: &str = "offline:changed";
+    pub const INCIDENTS_UPDATED: &str = "incidents:updated";
 }
 
 pub fn emit_event<R: Runtime, T: Serialize + Clone>(
@@ -17,6 +42,11 @@ pub fn emit_event<R: Runtime, T: Serialize + Clone>(
     event: &str,
     payload: T,
 ) -> Result<(), String> {
+    match event {
+        event_names::DATA_TICK => crate::metrics::global().record_ticks(1),
+        event_names::ANOMALY_DETECTED => crate::metrics::global().record_anomalies(1),
+        _ => {}
+    }
     app.emit(event, payload).map_err(|e| e.to_string())
 }
 
@@ -30,11 +60,40 @@ mod tests {
         assert_eq!(DATA_TICK, "data:tick");
         assert_eq!(ANOMALY_DETECTED, "anomaly:detected");
         assert_eq!(SOURCE_HEALTH_CHANGE, "source:health-change");
+        assert_eq!(PROVIDER_HEALTH_CHANGE, "provider:health-change");
+        assert_eq!(LLM_USAGE, "llm:usage");
         assert_eq!(MEMORY_UPDATED, "memory:updated");
+        assert_eq!(EMBEDDINGS_DOWNLOAD, "embeddings:download");
         assert_eq!(BACKTEST_PROGRESS, "backtest:progress");
         assert_eq!(BACKTEST_COMPLETE, "backtest:complete");
     }
 
+    #[test]
+    fn sidecar_lifecycle_event_names() {
+        assert_eq!(SIDECAR_CRASHED, "sidecar:crashed");
+        assert_eq!(SIDECAR_RESTARTING, "sidecar:restarting");
+        assert_eq!(SIDECAR_RESTARTED, "sidecar:restarted");
+        assert_eq!(SIDECAR_INCOMPATIBLE, "sidecar:incompatible");
+        assert_eq!(SIDECAR_RESOURCE_LIMIT, "sidecar:resource-limit");
+        assert_eq!(DB_MAINTENANCE, "db:maintenance");
+        assert_eq!(WORKSPACE_SWITCHED, "workspace:switched");
+        assert_eq!(CONFIG_CHANGED, "config:changed");
+        assert_eq!(CREDENTIALS_REVOKED, "credentials:revoked");
+        assert_eq!(ASSETS_REFRESHED, "assets:refreshed");
+        assert_eq!(AGENT_RESTART, "agent:restart");
+        assert_eq!(SCHEDULE_TRANSITION, "schedule:transition");
+        assert_eq!(AGENT_UNHEALTHY, "agent:unhealthy");
+        assert_eq!(SOURCE_QUARANTINED, "source:quarantined");
+        assert_eq!(NEWS_ITEM, "news:item");
+        assert_eq!(ALERT_TRIGGERED, "alert:triggered");
+        assert_eq!(PORTFOLIO_UPDATED, "portfolio:updated");
+        assert_eq!(ORDER_UPDATED, "order:updated");
+        assert_eq!(TRADING_HALT_CHANGED, "trading:halt-changed");
+        assert_eq!(OFFLINE_CHANGED, "offline:changed");
+        assert_eq!(ANOMALY_UPDATED, "anomaly:updated");
+        assert_eq!(INCIDENTS_UPDATED, "incidents:updated");
+    }
+
     #[test]
     fn emit_event_compiles_with_typed_payloads() {
         // This test verifies the function signature compiles with our types.
@@ -0,0 +1,475 @@
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Certificate, Client, ClientBuilder, Method, NoProxy, Proxy, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+use crate::metrics::{self, HTTP_DURATION, HTTP_ERRORS, HTTP_RETRIES};
+
+/// Retries after the first attempt of a request.
+const MAX_RETRIES: u32 = 4;
+const BASE_DELAY: Duration = Duration::from_millis(500);
+const MAX_DELAY: Duration = Duration::from_secs(30);
+/// Prefix of the `config` table keys holding cached response validators.
+const VALIDATORS_KEY_PREFIX: &str = "http_validators:";
+
+/// URL schemes accepted for `network.proxy`.
+pub const PROXY_SCHEMES: &[&str] = &["http", "https", "socks5", "socks5h"];
+
+/// The `network` section of the app config: how outbound HTTP reaches the internet.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConfig {
+    /// Proxy URL for every request, e.g. `http://proxy:3128` or
+    /// `socks5h://127.0.0.1:1080`. Empty falls back to the `HTTPS_PROXY`,
+    /// `HTTP_PROXY` and `ALL_PROXY` environment variables.
+    pub proxy: String,
+    /// Hosts that bypass `proxy`, in `NO_PROXY` syntax (`localhost`, `.corp.example`, CIDRs).
+    pub no_proxy: Vec<String>,
+    /// Path of a PEM file with extra root certificates to trust, for proxies
+    /// that intercept TLS with a corporate CA.
+    pub ca_bundle: String,
+}
+
+/// Parse the `network` section of `app_config`, falling back to defaults.
+pub fn network_config(app_config: &serde_json::Value) -> NetworkConfig {
+    app_config
+        .get("network")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Why `proxy` is not a usable proxy URL, if it is not.
+pub fn proxy_error(proxy: &str) -> Option<String> {
+    match reqwest::Url::parse(proxy) {
+        Ok(url) if !PROXY_SCHEMES.contains(&url.scheme()) => {
+            Some(format!("must use one of {}", PROXY_SCHEMES.join(", ")))
+        }
+        Ok(url) if url.host_str().is_none() => Some("must name a host".to_string()),
+        Ok(_) => None,
+        Err(e) => Some(format!("is not a URL: {}", e)),
+    }
+}
+
+fn network() -> &'static RwLock<NetworkConfig> {
+    static NETWORK: OnceLock<RwLock<NetworkConfig>> = OnceLock::new();
+    NETWORK.get_or_init(Default::default)
+}
+
+/// Use `config` for every client built from now on.
+pub fn configure(config: NetworkConfig) {
+    *network().write().unwrap_or_else(|e| e.into_inner()) = config;
+}
+
+/// Apply `config`'s proxy and extra roots to `builder`.
+fn apply_network(mut builder: ClientBuilder, config: &NetworkConfig) -> Result<ClientBuilder, String> {
+    let proxy = config.proxy.trim();
+    if !proxy.is_empty() {
+        if let Some(e) = proxy_error(proxy) {
+            return Err(format!("Invalid proxy '{}': {}", proxy, e));
+        }
+        let no_proxy = NoProxy::from_string(&config.no_proxy.join(","));
+        let proxy = Proxy::all(proxy).map_err(|e| format!("Invalid proxy '{}': {}", proxy, e))?;
+        builder = builder.proxy(proxy.no_proxy(no_proxy));
+    }
+    let ca_bundle = config.ca_bundle.trim();
+    if !ca_bundle.is_empty() {
+        let pem = std::fs::read(ca_bundle).map_err(|e| format!("Failed to read CA bundle '{}': {}", ca_bundle, e))?;
+        let certs =
+            Certificate::from_pem_bundle(&pem).map_err(|e| format!("Invalid CA bundle '{}': {}", ca_bundle, e))?;
+        if certs.is_empty() {
+            return Err(format!("CA bundle '{}' holds no certificates", ca_bundle));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(cert);
+        }
+    }
+    Ok(builder)
+}
+
+/// A client builder carrying the configured proxy and CA bundle. Every
+/// outbound client starts from this so corporate networks work everywhere.
+pub fn client_builder() -> Result<ClientBuilder, String> {
+    let config = network().read().unwrap_or_else(|e| e.into_inner()).clone();
+    apply_network(Client::builder(), &config)
+}
+
+/// A client with the network settings and reqwest's other defaults.
+pub fn client() -> Result<Client, String> {
+    client_builder()?.build().map_err(|e| e.to_string())
+}
+
+/// Sustained request rate and burst allowed against one host.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HostLimit {
+    pub per_second: f64,
+    pub burst: f64,
+}
+
+/// The limit for `host`: Alpaca allows 200 requests a minute per key, other
+/// hosts get a looser default.
+pub fn host_limit(host: &str) -> HostLimit {
+    if host == "alpaca.markets" || host.ends_with(".alpaca.markets") {
+        HostLimit {
+            per_second: 200.0 / 60.0,
+            burst: 20.0,
+        }
+    } else {
+        HostLimit {
+            per_second: 10.0,
+            burst: 10.0,
+        }
+    }
+}
+
+/// Token bucket of one host. Tokens may go negative: a caller that reserves
+/// an empty bucket waits for its token instead of racing other callers.
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(limit: HostLimit, now: Instant) -> Self {
+        Self {
+            tokens: limit.burst,
+            updated: now,
+        }
+    }
+
+    /// Take one token, returning how long the caller must wait for it.
+    fn reserve(&mut self, limit: HostLimit, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.per_second).min(limit.burst);
+        self.updated = now;
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / limit.per_second)
+        }
+    }
+}
+
+/// Per-host token buckets shared by every request the app makes.
+#[derive(Default)]
+struct RateLimiter {
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    fn reserve(&self, host: &str) -> Duration {
+        let limit = host_limit(host);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(host.to_string())
+            .or_insert_with(|| Bucket::new(limit, now))
+            .reserve(limit, now)
+    }
+}
+
+fn limiter() -> &'static RateLimiter {
+    static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+    LIMITER.get_or_init(RateLimiter::default)
+}
+
+/// Whether replaying a `method` request is harmless if the first one took effect.
+fn is_idempotent(method: &Method) -> bool {
+    [Method::GET, Method::HEAD, Method::PUT, Method::DELETE, Method::OPTIONS].contains(method)
+}
+
+/// Statuses worth retrying: throttling always, server errors only for
+/// idempotent requests so an order is never submitted twice.
+pub fn is_retryable(method: &Method, status: StatusCode) -> bool {
+    status == StatusCode::TOO_MANY_REQUESTS || (status.is_server_error() && is_idempotent(method))
+}
+
+/// Delay before retry number `attempt` (0-based): exponential from
+/// `BASE_DELAY`, capped at `MAX_DELAY`, scaled by `jitter` in `[0, 1)` to
+/// between half and all of that.
+pub fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let exp = BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt)).min(MAX_DELAY);
+    exp.mul_f64(0.5 + jitter.clamp(0.0, 1.0) / 2.0)
+}
+
+/// Seconds from a `Retry-After` header, when it holds a number.
+pub fn retry_after(value: Option<&str>) -> Option<Duration> {
+    let secs: u64 = value?.trim().parse().ok()?;
+    Some(Duration::from_secs(secs).min(MAX_DELAY))
+}
+
+/// A random fraction in `[0, 1)`, from the std hasher's per-instance keys.
+fn jitter() -> f64 {
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Send `request`, waiting for the host's rate limit and retrying throttled,
+/// failed, or timed-out attempts with backoff. The final response is
+/// returned whatever its status; only transport errors become `Err`.
+pub async fn send(request: RequestBuilder) -> Result<Response, reqwest::Error> {
+    let (client, request) = request.build_split();
+    let request = request?;
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let method = request.method().clone();
+    let registry = metrics::global();
+    let mut attempt = 0;
+    loop {
+        let wait = limiter().reserve(&host);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        let started = Instant::now();
+        let Some(this_attempt) = request.try_clone() else {
+            // Streaming bodies cannot be replayed, so they get a single attempt
+            let result = client.execute(request).await;
+            registry.observe(HTTP_DURATION, &[("host", &host)], started.elapsed());
+            return result;
+        };
+        let result = client.execute(this_attempt).await;
+        registry.observe(HTTP_DURATION, &[("host", &host)], started.elapsed());
+        let delay = match &result {
+            Ok(response) if is_retryable(&method, response.status()) => {
+                let header = response.headers().get(reqwest::header::RETRY_AFTER);
+                Some(
+                    retry_after(header.and_then(|v| v.to_str().ok()))
+                        .unwrap_or_else(|| backoff_delay(attempt, jitter())),
+                )
+            }
+            Ok(_) => return result,
+            // A connect error means nothing was sent; a timeout may have been processed
+            Err(e) if e.is_connect() || (e.is_timeout() && is_idempotent(&method)) => {
+                Some(backoff_delay(attempt, jitter()))
+            }
+            Err(_) => None,
+        };
+        let Some(delay) = delay.filter(|_| attempt < MAX_RETRIES) else {
+            registry.counter_add(HTTP_ERRORS, &[("host", &host)], 1);
+            return result;
+        };
+        tracing::debug!(
+            host = %host,
+            attempt,
+            delay_ms = delay.as_millis() as u64,
+            "Retrying HTTP request"
+        );
+        registry.counter_add(HTTP_RETRIES, &[("host", &host)], 1);
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// `ETag` and `Last-Modified` of a cached response, sent back so an
+/// unchanged resource costs a `304 Not Modified` instead of a download.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validators {
+    pub fn from_response(response: &Response) -> Self {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        Self {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Make `request` conditional on the resource having changed.
+    pub fn apply(&self, mut request: RequestBuilder) -> RequestBuilder {
+        if let Some(etag) = &self.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &self.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        request
+    }
+}
+
+/// Outcome of a conditional request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conditional<T> {
+    /// The resource changed (or nothing was cached): its new body and validators.
+    Modified(T, Validators),
+    /// The cached copy is still current.
+    NotModified,
+}
+
+/// Validators stored for `url` by `validators_set_db`.
+pub fn validators_get_db(pool: &DbPool, url: &str) -> Result<Validators, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let stored: Option<String> = conn
+        .query_row(
+            "SELECT value FROM config WHERE key = ?1",
+            [format!("{}{}", VALIDATORS_KEY_PREFIX, url)],
+            |row| row.get(0),
+        )
+        .ok();
+    Ok(stored.and_then(|v| serde_json::from_str(&v).ok()).unwrap_or_default())
+}
+
+/// Remember the validators of the response for `url`. Store them only once
+/// the body is cached, or a later `304` would leave nothing to serve.
+pub fn validators_set_db(pool: &DbPool, url: &str, validators: &Validators) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let key = format!("{}{}", VALIDATORS_KEY_PREFIX, url);
+    if validators.is_empty() {
+        conn.execute("DELETE FROM config WHERE key = ?1", [key])
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+    let json = serde_json::to_string(validators).map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO config (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = datetime('now')",
+        rusqlite::params![key, json],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn network_config_checks_proxy_and_ca_bundle() {
+        assert_eq!(proxy_error("socks5h://127.0.0.1:1080"), None);
+        assert_eq!(proxy_error("http://proxy.corp:3128"), None);
+        assert!(proxy_error("ftp://proxy.corp").unwrap().contains("must use one of"));
+        assert!(proxy_error("proxy.corp:3128").is_some());
+
+        let config = network_config(&serde_json::json!({
+            "network": { "proxy": "http://proxy.corp:3128", "noProxy": ["localhost", ".corp"] }
+        }));
+        assert_eq!(config.no_proxy, ["localhost", ".corp"]);
+        assert!(apply_network(Client::builder(), &config).unwrap().build().is_ok());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ca.pem");
+        std::fs::write(&path, "not a certificate").unwrap();
+        let config = NetworkConfig {
+            ca_bundle: path.display().to_string(),
+            ..NetworkConfig::default()
+        };
+        assert!(apply_network(Client::builder(), &config).is_err());
+        let missing = NetworkConfig {
+            ca_bundle: dir.path().join("missing.pem").display().to_string(),
+            ..NetworkConfig::default()
+        };
+        let err = apply_network(Client::builder(), &missing).unwrap_err();
+        assert!(err.contains("Failed to read CA bundle"), "{err}");
+    }
+
+    #[test]
+    fn backoff_grows_with_jitter_and_caps() {
+        assert_eq!(backoff_delay(0, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(0, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(3, 1.0), Duration::from_secs(4));
+        assert_eq!(backoff_delay(20, 1.0), MAX_DELAY);
+        for _ in 0..100 {
+            let j = jitter();
+            assert!((0.0..1.0).contains(&j));
+        }
+    }
+
+    #[test]
+    fn retries_throttling_and_server_errors_only() {
+        assert!(is_retryable(&Method::GET, StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(&Method::POST, StatusCode::TOO_MANY_REQUESTS));
+        assert!(is_retryable(&Method::GET, StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable(&Method::POST, StatusCode::SERVICE_UNAVAILABLE));
+        assert!(!is_retryable(&Method::GET, StatusCode::NOT_FOUND));
+        assert!(!is_retryable(&Method::DELETE, StatusCode::UNAUTHORIZED));
+        assert_eq!(retry_after(Some(" 3 ")), Some(Duration::from_secs(3)));
+        assert_eq!(retry_after(Some("3600")), Some(MAX_DELAY));
+        assert_eq!(retry_after(Some("Wed, 21 Oct 2015 07:28:00 GMT")), None);
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_paces() {
+        let limit = HostLimit {
+            per_second: 2.0,
+            burst: 3.0,
+        };
+        let start = Instant::now();
+        let mut bucket = Bucket::new(limit, start);
+        for _ in 0..3 {
+            assert_eq!(bucket.reserve(limit, start), Duration::ZERO);
+        }
+        assert_eq!(bucket.reserve(limit, start), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(limit, start), Duration::from_secs(1));
+        // Two seconds refill four tokens, two of which repay the reservations
+        assert_eq!(bucket.reserve(limit, start + Duration::from_secs(2)), Duration::ZERO);
+    }
+
+    #[test]
+    fn alpaca_hosts_share_the_api_limit() {
+        assert_eq!(
+            host_limit("paper-api.alpaca.markets"),
+            host_limit("data.alpaca.markets")
+        );
+        assert!(host_limit("data.alpaca.markets").per_second < host_limit("query1.finance.yahoo.com").per_second);
+    }
+
+    #[test]
+    fn validators_make_requests_conditional() {
+        let validators = Validators {
+            etag: Some("W/\"abc\"".to_string()),
+            last_modified: Some("Wed, 21 Oct 2015 07:28:00 GMT".to_string()),
+        };
+        let request = validators
+            .apply(reqwest::Client::new().get("https://example.com/v2/assets"))
+            .build()
+            .unwrap();
+        assert_eq!(request.headers()[IF_NONE_MATCH], "W/\"abc\"");
+        assert_eq!(request.headers()[IF_MODIFIED_SINCE], "Wed, 21 Oct 2015 07:28:00 GMT");
+
+        let plain = Validators::default()
+            .apply(reqwest::Client::new().get("https://example.com/v2/assets"))
+            .build()
+            .unwrap();
+        assert!(plain.headers().get(IF_NONE_MATCH).is_none());
+    }
+
+    #[test]
+    fn validators_round_trip_per_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+
+        let url = "https://paper-api.alpaca.markets/v2/assets?asset_class=crypto";
+        assert_eq!(validators_get_db(&pool, url).unwrap(), Validators::default());
+        let validators = Validators {
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+        };
+        validators_set_db(&pool, url, &validators).unwrap();
+        assert_eq!(validators_get_db(&pool, url).unwrap(), validators);
+        assert!(validators_get_db(&pool, "https://other").unwrap().is_empty());
+
+        validators_set_db(&pool, url, &Validators::default()).unwrap();
+        assert!(validators_get_db(&pool, url).unwrap().is_empty());
+    }
+}
@@ -5,6 +5,12 @@ pub mod rsi;
 
 use serde::{Deserialize, Serialize};
 
+use crate::commands::bars::{bars_load, validate_timeframe, Bar};
+use crate::commands::offline::is_offline;
+use crate::db;
+use crate::types::data::TickRange;
+use crate::workspace::WorkspaceDb;
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct TickInput {
     pub timestamp: i64,
@@ -64,6 +70,166 @@ pub fn indicators_compute(
     })
 }
 
+/// Lookback period of a single-period indicator.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct PeriodSpec {
+    pub period: usize,
+}
+
+impl Default for PeriodSpec {
+    fn default() -> Self {
+        Self { period: 14 }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct MacdSpec {
+    pub fast: usize,
+    pub slow: usize,
+    pub signal: usize,
+}
+
+impl Default for MacdSpec {
+    fn default() -> Self {
+        Self { fast: 12, slow: 26, signal: 9 }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BollingerSpec {
+    pub period: usize,
+    pub std_dev: f64,
+}
+
+impl Default for BollingerSpec {
+    fn default() -> Self {
+        Self { period: 20, std_dev: 2.0 }
+    }
+}
+
+/// Which indicators `indicators_compute_symbol` returns; unset ones are
+/// skipped. `{"rsi": {}}` asks for RSI with its default period.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct IndicatorSpec {
+    pub rsi: Option<PeriodSpec>,
+    pub macd: Option<MacdSpec>,
+    pub bollinger: Option<BollingerSpec>,
+    pub atr: Option<PeriodSpec>,
+}
+
+impl Default for IndicatorSpec {
+    /// Every indicator with the periods `indicators_compute` uses.
+    fn default() -> Self {
+        Self {
+            rsi: Some(PeriodSpec::default()),
+            macd: Some(MacdSpec::default()),
+            bollinger: Some(BollingerSpec::default()),
+            atr: Some(PeriodSpec::default()),
+        }
+    }
+}
+
+impl IndicatorSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        let periods = [
+            ("rsi.period", self.rsi.map(|s| s.period)),
+            ("macd.fast", self.macd.map(|s| s.fast)),
+            ("macd.signal", self.macd.map(|s| s.signal)),
+            ("bollinger.period", self.bollinger.map(|s| s.period)),
+            ("atr.period", self.atr.map(|s| s.period)),
+        ];
+        for (name, period) in periods {
+            if period == Some(0) {
+                return Err(format!("{} must be positive", name));
+            }
+        }
+        if let Some(macd) = self.macd {
+            if macd.slow <= macd.fast {
+                return Err("macd.slow must be greater than macd.fast".to_string());
+            }
+        }
+        if let Some(bollinger) = self.bollinger {
+            if !(bollinger.std_dev.is_finite() && bollinger.std_dev > 0.0) {
+                return Err("bollinger.stdDev must be positive".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Indicators over cached bars, one value per bar; skipped indicators are omitted.
+#[derive(Serialize, Clone, Debug)]
+pub struct SymbolIndicators {
+    pub symbol: String,
+    pub timeframe: String,
+    /// Epoch millis of each bar's start.
+    pub timestamps: Vec<u64>,
+    pub close: Vec<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rsi: Option<Vec<f64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macd: Option<Vec<MacdPoint>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bollinger: Option<Vec<BollingerPoint>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub atr: Option<Vec<f64>>,
+    /// Computed from cached bars in offline mode; bars after the newest cached one are missing.
+    pub stale: bool,
+}
+
+/// Compute the indicators in `spec` over `bars`, oldest first.
+pub fn compute_bars(symbol: &str, timeframe: &str, bars: &[Bar], spec: &IndicatorSpec) -> SymbolIndicators {
+    let close: Vec<f64> = bars.iter().map(|b| b.close).collect();
+    let ticks: Vec<TickInput> = bars
+        .iter()
+        .map(|b| TickInput {
+            timestamp: b.timestamp as i64,
+            open: b.open,
+            high: b.high,
+            low: b.low,
+            close: b.close,
+            volume: b.volume,
+        })
+        .collect();
+    SymbolIndicators {
+        symbol: symbol.to_string(),
+        timeframe: timeframe.to_string(),
+        timestamps: bars.iter().map(|b| b.timestamp).collect(),
+        rsi: spec.rsi.map(|s| rsi::compute(&close, s.period)),
+        macd: spec.macd.map(|s| macd::compute(&close, s.fast, s.slow, s.signal)),
+        bollinger: spec.bollinger.map(|s| bollinger::compute(&close, s.period, s.std_dev)),
+        atr: spec.atr.map(|s| atr::compute(&ticks, s.period)),
+        close,
+        stale: false,
+    }
+}
+
+/// Compute indicators for `symbol` from the local bars cache, fetching the
+/// bars from Alpaca when the cache has none in `range`.
+#[tauri::command]
+pub async fn indicators_compute_symbol(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    symbol: String,
+    timeframe: String,
+    range: Option<TickRange>,
+    spec: Option<IndicatorSpec>,
+) -> Result<SymbolIndicators, String> {
+    validate_timeframe(&timeframe)?;
+    let spec = spec.unwrap_or_default();
+    spec.validate()?;
+    let pool = workspace.pool();
+    let bars = bars_load(&pool, &symbol, &timeframe, &range.unwrap_or_default()).await?;
+    if bars.is_empty() {
+        return Err(format!("No {} bars for {}", timeframe, symbol.trim().to_uppercase()));
+    }
+    let mut indicators = compute_bars(&symbol.trim().to_uppercase(), &timeframe, &bars, &spec);
+    indicators.stale = db::run_blocking(&pool, |pool| Ok(is_offline(pool))).await?;
+    Ok(indicators)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,4 +271,40 @@ mod tests {
         let result = indicators_compute("AAPL".to_string(), vec![]);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn compute_bars_follows_spec() {
+        let bars: Vec<Bar> = (0..30)
+            .map(|i| Bar {
+                timestamp: i * 1_000,
+                open: 10.0,
+                high: 11.0,
+                low: 9.0,
+                close: 10.0 + i as f64,
+                volume: 100.0,
+            })
+            .collect();
+        let spec: IndicatorSpec = serde_json::from_str(r#"{"rsi": {"period": 5}, "atr": {}}"#).unwrap();
+        let result = compute_bars("SPY", "1Day", &bars, &spec);
+        assert_eq!(result.timestamps.len(), 30);
+        assert_eq!(result.rsi.as_ref().unwrap().len(), 30);
+        assert!(result.rsi.as_ref().unwrap()[5].is_finite());
+        assert!(result.atr.is_some());
+        assert!(result.macd.is_none() && result.bollinger.is_none());
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert!(json.get("macd").is_none());
+
+        let all = compute_bars("SPY", "1Day", &bars, &IndicatorSpec::default());
+        assert!(all.macd.is_some() && all.bollinger.is_some());
+    }
+
+    #[test]
+    fn spec_rejects_bad_periods() {
+        assert!(IndicatorSpec::default().validate().is_ok());
+        let zero: IndicatorSpec = serde_json::from_str(r#"{"rsi": {"period": 0}}"#).unwrap();
+        assert_eq!(zero.validate().unwrap_err(), "rsi.period must be positive");
+        let macd: IndicatorSpec = serde_json::from_str(r#"{"macd": {"fast": 26, "slow": 12}}"#).unwrap();
+        assert!(macd.validate().is_err());
+    }
 }
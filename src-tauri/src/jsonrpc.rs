@@ -1,8 +1,76 @@
 use serde::{Deserialize, Serialize};
+use std::io::BufRead;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 static REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
+/// Version of the stdio protocol spoken with the agent sidecar.
+/// Must match `PROTOCOL_VERSION` in agent/src/index.ts.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Wire framing for the stdio channel, negotiated during `agent:hello`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Framing {
+    /// One JSON message per line (the pre-handshake default).
+    #[default]
+    Newline,
+    /// LSP-style `Content-Length: N\r\n\r\n` header followed by N bytes of JSON.
+    ContentLength,
+}
+
+impl Framing {
+    /// Encode a serialized JSON message for the wire.
+    pub fn encode(self, json: &str) -> String {
+        match self {
+            Framing::Newline => format!("{}\n", json),
+            Framing::ContentLength => format!("Content-Length: {}\r\n\r\n{}", json.len(), json),
+        }
+    }
+}
+
+fn parse_content_length(header: &str) -> Option<usize> {
+    let (name, value) = header.split_once(':')?;
+    if !name.trim().eq_ignore_ascii_case("content-length") {
+        return None;
+    }
+    value.trim().parse().ok()
+}
+
+/// Read the next message from the agent, detecting the framing per message:
+/// a `Content-Length` header starts a length-prefixed frame, anything else is
+/// a newline-delimited JSON line. Returns `None` at end of stream.
+pub fn read_message<R: BufRead>(reader: &mut R) -> std::io::Result<Option<String>> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let Some(len) = parse_content_length(trimmed) else {
+            return Ok(Some(trimmed.to_string()));
+        };
+        // Skip any remaining headers up to the blank separator line
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header)? == 0 {
+                return Ok(None);
+            }
+            if header.trim().is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0u8; len];
+        reader.read_exact(&mut body)?;
+        return String::from_utf8(body)
+            .map(Some)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcRequest {
     pub jsonrpc: String,
@@ -12,6 +80,16 @@ pub struct JsonRpcRequest {
     pub params: Option<serde_json::Value>,
 }
 
+/// A JSON-RPC notification: a method call without an `id`, which the peer
+/// must not answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcResponse {
     pub jsonrpc: String,
@@ -44,6 +122,26 @@ impl JsonRpcRequest {
         s.push('\n');
         Ok(s)
     }
+
+    /// Serialize and encode for the wire with the given framing.
+    pub fn to_frame(&self, framing: Framing) -> Result<String, serde_json::Error> {
+        Ok(framing.encode(&serde_json::to_string(self)?))
+    }
+}
+
+impl JsonRpcNotification {
+    pub fn new(method: &str, params: Option<serde_json::Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// Serialize and encode for the wire with the given framing.
+    pub fn to_frame(&self, framing: Framing) -> Result<String, serde_json::Error> {
+        Ok(framing.encode(&serde_json::to_string(self)?))
+    }
 }
 
 impl JsonRpcResponse {
@@ -56,6 +154,51 @@ impl JsonRpcResponse {
     }
 }
 
+/// Result of the `agent:hello` handshake sent right after spawn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HelloResponse {
+    pub protocol_version: u32,
+    pub agent_version: String,
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Framing the agent switches to after sending this response.
+    #[serde(default)]
+    pub framing: Framing,
+}
+
+/// Params for the `agent:hello` request.
+pub fn hello_params() -> serde_json::Value {
+    serde_json::json!({
+        "protocolVersion": PROTOCOL_VERSION,
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "framings": [Framing::ContentLength, Framing::Newline],
+    })
+}
+
+/// Validate an `agent:hello` response against our protocol version.
+pub fn check_hello(response: &JsonRpcResponse) -> Result<HelloResponse, String> {
+    if let Some(ref err) = response.error {
+        return Err(format!(
+            "Agent does not support the protocol handshake (protocol {} required): {}",
+            PROTOCOL_VERSION, err.message
+        ));
+    }
+    let result = response
+        .result
+        .clone()
+        .ok_or("Empty agent:hello response")?;
+    let hello: HelloResponse = serde_json::from_value(result)
+        .map_err(|e| format!("Malformed agent:hello response: {}", e))?;
+    if hello.protocol_version != PROTOCOL_VERSION {
+        return Err(format!(
+            "Incompatible agent protocol version {} (expected {}); agent version {}",
+            hello.protocol_version, PROTOCOL_VERSION, hello.agent_version
+        ));
+    }
+    Ok(hello)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -112,4 +255,101 @@ mod tests {
         assert!(parsed.get("id").is_some());
         assert!(parsed.get("method").is_some());
     }
+
+    #[test]
+    fn hello_params_include_protocol_version() {
+        let params = hello_params();
+        assert_eq!(params["protocolVersion"], PROTOCOL_VERSION);
+        assert!(params["appVersion"].is_string());
+    }
+
+    #[test]
+    fn check_hello_accepts_matching_version() {
+        let json = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":{{"protocolVersion":{},"agentVersion":"0.0.1","methods":["ping"]}}}}"#,
+            PROTOCOL_VERSION
+        );
+        let resp = JsonRpcResponse::from_line(&json).unwrap();
+        let hello = check_hello(&resp).unwrap();
+        assert_eq!(hello.agent_version, "0.0.1");
+        assert_eq!(hello.methods, vec!["ping"]);
+    }
+
+    #[test]
+    fn check_hello_rejects_mismatched_version() {
+        let json = r#"{"jsonrpc":"2.0","id":1,"result":{"protocolVersion":999,"agentVersion":"9.0.0"}}"#;
+        let resp = JsonRpcResponse::from_line(json).unwrap();
+        let err = check_hello(&resp).unwrap_err();
+        assert!(err.contains("Incompatible"));
+    }
+
+    #[test]
+    fn check_hello_rejects_agent_without_handshake() {
+        let json = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found: agent:hello"}}"#;
+        let resp = JsonRpcResponse::from_line(json).unwrap();
+        assert!(check_hello(&resp).is_err());
+    }
+
+    #[test]
+    fn framing_encodes_content_length_in_bytes() {
+        let frame = Framing::ContentLength.encode(r#"{"s":"é"}"#);
+        assert_eq!(frame, "Content-Length: 10\r\n\r\n{\"s\":\"é\"}");
+        assert_eq!(Framing::Newline.encode("{}"), "{}\n");
+    }
+
+    #[test]
+    fn read_message_handles_mixed_framings() {
+        let body = "{\"text\":\"a\nb\"}";
+        let stream = format!(
+            "{{\"id\":1}}\n\n{}{}",
+            Framing::ContentLength.encode(body),
+            Framing::Newline.encode("{\"id\":3}")
+        );
+        let mut reader = std::io::BufReader::new(stream.as_bytes());
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), "{\"id\":1}");
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), body);
+        assert_eq!(read_message(&mut reader).unwrap().unwrap(), "{\"id\":3}");
+        assert!(read_message(&mut reader).unwrap().is_none());
+    }
+
+    #[test]
+    fn read_message_truncated_body_is_error() {
+        let stream = "Content-Length: 50\r\n\r\n{}";
+        let mut reader = std::io::BufReader::new(stream.as_bytes());
+        assert!(read_message(&mut reader).is_err());
+    }
+
+    #[test]
+    fn hello_framing_defaults_to_newline() {
+        let json = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"result":{{"protocolVersion":{},"agentVersion":"0.0.1"}}}}"#,
+            PROTOCOL_VERSION
+        );
+        let hello = check_hello(&JsonRpcResponse::from_line(&json).unwrap()).unwrap();
+        assert_eq!(hello.framing, Framing::Newline);
+        assert_eq!(hello_params()["framings"][0], "content-length");
+    }
+
+    #[test]
+    fn notification_serializes_without_id() {
+        let notif = JsonRpcNotification::new(
+            "backtest:cancel",
+            Some(serde_json::json!({"backtestId": "bt-1"})),
+        );
+        let frame = notif.to_frame(Framing::Newline).unwrap();
+        let value: serde_json::Value = serde_json::from_str(frame.trim()).unwrap();
+        assert!(value.get("id").is_none());
+        assert_eq!(value["method"], "backtest:cancel");
+        assert_eq!(value["params"]["backtestId"], "bt-1");
+    }
+
+    #[test]
+    fn notification_parses_from_agent_output() {
+        let notif: JsonRpcNotification = serde_json::from_str(
+            r#"{"jsonrpc":"2.0","method":"data:tick","params":{"symbol":"NET"}}"#,
+        )
+        .unwrap();
+        assert_eq!(notif.method, "data:tick");
+        assert_eq!(notif.params.unwrap()["symbol"], "NET");
+    }
 }
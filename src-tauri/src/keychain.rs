@@ -1,45 +1,48 @@
+use serde::Serialize;
 use tracing::debug;
 
-use crate::commands::credentials::AlpacaCredentials;
+use crate::broker::{BrokerProvider, ProviderCredentials};
+use crate::commands::credentials::{
+    llm_config_field, profile_suffix, AlpacaCredentials, DEFAULT_CREDENTIAL_PROFILE, LLM_PROVIDERS,
+};
 use crate::db::DbPool;
 
 const SERVICE: &str = "dev.finwatch";
 
-fn keychain_key(mode: &str) -> String {
-    format!("alpaca_{}", mode)
+fn keychain_key(provider: BrokerProvider, mode: &str, profile: &str) -> Result<String, String> {
+    provider.validate_mode(mode)?;
+    Ok(format!("{}_{}{}", provider.as_str(), mode, profile_suffix(profile)?))
 }
 
-fn validate_mode(mode: &str) -> Result<(), String> {
-    match mode {
-        "paper" | "live" => Ok(()),
-        _ => Err(format!(
-            "Invalid trading mode: '{}'. Must be 'paper' or 'live'",
-            mode
-        )),
-    }
+fn entry(provider: BrokerProvider, mode: &str, profile: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(SERVICE, &keychain_key(provider, mode, profile)?)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))
 }
 
-/// Store credentials in the OS keychain.
-pub fn keychain_set(mode: &str, creds: &AlpacaCredentials) -> Result<(), String> {
-    validate_mode(mode)?;
+/// Store a provider's credentials for a mode and profile in the OS keychain.
+pub fn provider_keychain_set(
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    creds: &ProviderCredentials,
+) -> Result<(), String> {
     let json = serde_json::to_string(creds).map_err(|e| e.to_string())?;
-    let entry = keyring::Entry::new(SERVICE, &keychain_key(mode))
-        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
-    entry
+    entry(provider, mode, profile)?
         .set_password(&json)
         .map_err(|e| format!("Failed to store in keychain: {}", e))?;
-    debug!(mode, "Credentials stored in keychain");
+    debug!(provider = provider.as_str(), mode, profile, "Credentials stored in keychain");
     Ok(())
 }
 
-/// Retrieve credentials from the OS keychain. Returns None if not set.
-pub fn keychain_get(mode: &str) -> Result<Option<AlpacaCredentials>, String> {
-    validate_mode(mode)?;
-    let entry = keyring::Entry::new(SERVICE, &keychain_key(mode))
-        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
-    match entry.get_password() {
+/// Retrieve a provider's credentials from the OS keychain. Returns None if not set.
+pub fn provider_keychain_get(
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+) -> Result<Option<ProviderCredentials>, String> {
+    match entry(provider, mode, profile)?.get_password() {
         Ok(json) => {
-            let creds: AlpacaCredentials =
+            let creds: ProviderCredentials =
                 serde_json::from_str(&json).map_err(|e| e.to_string())?;
             Ok(Some(creds))
         }
@@ -48,14 +51,11 @@ pub fn keychain_get(mode: &str) -> Result<Option<AlpacaCredentials>, String> {
     }
 }
 
-/// Delete credentials from the OS keychain.
-pub fn keychain_delete(mode: &str) -> Result<(), String> {
-    validate_mode(mode)?;
-    let entry = keyring::Entry::new(SERVICE, &keychain_key(mode))
-        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
-    match entry.delete_credential() {
+/// Delete a provider's credentials from the OS keychain.
+pub fn provider_keychain_delete(provider: BrokerProvider, mode: &str, profile: &str) -> Result<(), String> {
+    match entry(provider, mode, profile)?.delete_credential() {
         Ok(()) => {
-            debug!(mode, "Credentials deleted from keychain");
+            debug!(provider = provider.as_str(), mode, profile, "Credentials deleted from keychain");
             Ok(())
         }
         Err(keyring::Error::NoEntry) => Ok(()), // Already gone
@@ -63,43 +63,263 @@ pub fn keychain_delete(mode: &str) -> Result<(), String> {
     }
 }
 
-/// Check whether credentials exist in the OS keychain.
-pub fn keychain_exists(mode: &str) -> Result<bool, String> {
-    validate_mode(mode)?;
-    let entry = keyring::Entry::new(SERVICE, &keychain_key(mode))
-        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
-    match entry.get_password() {
+/// Check whether a provider has credentials in the OS keychain.
+pub fn provider_keychain_exists(provider: BrokerProvider, mode: &str, profile: &str) -> Result<bool, String> {
+    match entry(provider, mode, profile)?.get_password() {
         Ok(_) => Ok(true),
         Err(keyring::Error::NoEntry) => Ok(false),
         Err(e) => Err(format!("Failed to check keychain: {}", e)),
     }
 }
 
-/// Migrate credentials from SQLite to OS keychain (idempotent).
-/// Reads from DB, writes to keychain, then deletes from DB.
+/// Store Alpaca credentials for a mode and profile in the OS keychain.
+pub fn keychain_set(mode: &str, profile: &str, creds: &AlpacaCredentials) -> Result<(), String> {
+    provider_keychain_set(BrokerProvider::Alpaca, mode, profile, &creds.clone().into_fields())
+}
+
+/// Retrieve Alpaca credentials from the OS keychain. Returns None if not set.
+pub fn keychain_get(mode: &str, profile: &str) -> Result<Option<AlpacaCredentials>, String> {
+    Ok(provider_keychain_get(BrokerProvider::Alpaca, mode, profile)?.map(AlpacaCredentials::from_fields))
+}
+
+/// Delete Alpaca credentials from the OS keychain.
+pub fn keychain_delete(mode: &str, profile: &str) -> Result<(), String> {
+    provider_keychain_delete(BrokerProvider::Alpaca, mode, profile)
+}
+
+/// Check whether Alpaca credentials exist in the OS keychain.
+pub fn keychain_exists(mode: &str, profile: &str) -> Result<bool, String> {
+    provider_keychain_exists(BrokerProvider::Alpaca, mode, profile)
+}
+
+/// Migrate default-profile credentials from SQLite to OS keychain (idempotent).
+/// Reads from DB, writes to keychain, then deletes the DB row once the
+/// keychain copy reads back identical.
 pub fn migrate_db_to_keychain(pool: &DbPool, mode: &str) -> Result<(), String> {
     use crate::commands::credentials::credentials_get_db;
 
-    // Check if already in keychain
-    if keychain_exists(mode)? {
-        debug!(mode, "Credentials already in keychain, skipping migration");
+    let profile = DEFAULT_CREDENTIAL_PROFILE;
+    let Some(creds) = credentials_get_db(pool, mode, profile)? else {
+        debug!(mode, "No credentials in DB to migrate");
         return Ok(());
+    };
+    // A keychain copy wins over the DB row, so only fill it in when missing
+    if !keychain_exists(mode, profile)? {
+        keychain_set(mode, profile, &creds)?;
+        debug!(mode, "Migrated credentials from DB to keychain");
     }
+    verified_delete(pool, BrokerProvider::Alpaca, mode, profile, provider_keychain_get)?;
+    Ok(())
+}
 
-    // Read from DB
-    let creds = credentials_get_db(pool, mode)?;
-    if let Some(creds) = creds {
-        // Write to keychain
-        keychain_set(mode, &creds)?;
-        // Delete from DB by writing empty value (or we can leave it since keychain takes priority)
-        debug!(mode, "Migrated credentials from DB to keychain");
-    } else {
-        debug!(mode, "No credentials in DB to migrate");
+/// One credential set considered by `scrub_db_credentials`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScrubbedCredential {
+    pub provider: BrokerProvider,
+    pub mode: String,
+    pub profile: String,
+}
+
+/// Outcome of `scrub_db_credentials`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialScrubReport {
+    /// Rows deleted from SQLite because the keychain holds the same values.
+    pub scrubbed: Vec<ScrubbedCredential>,
+    /// Rows kept because the keychain has no copy of them.
+    pub missing_in_keychain: Vec<ScrubbedCredential>,
+    /// Rows kept because the keychain copy differs.
+    pub mismatched: Vec<ScrubbedCredential>,
+}
+
+enum VerifiedDelete {
+    Deleted,
+    Missing,
+    Mismatch,
+}
+
+/// Delete a DB credential row only if the keychain holds the same values.
+fn verified_delete(
+    pool: &DbPool,
+    provider: BrokerProvider,
+    mode: &str,
+    profile: &str,
+    load: impl Fn(BrokerProvider, &str, &str) -> Result<Option<ProviderCredentials>, String>,
+) -> Result<VerifiedDelete, String> {
+    use crate::commands::credentials::{provider_credentials_delete_db, provider_credentials_get_db};
+
+    let Some(stored) = provider_credentials_get_db(pool, provider, mode, profile)? else {
+        return Ok(VerifiedDelete::Deleted);
+    };
+    match load(provider, mode, profile)? {
+        None => Ok(VerifiedDelete::Missing),
+        Some(keychain) if keychain != stored => {
+            tracing::warn!(provider = provider.as_str(), mode, profile, "Keychain credentials differ from DB, keeping DB row");
+            Ok(VerifiedDelete::Mismatch)
+        }
+        Some(_) => {
+            provider_credentials_delete_db(pool, provider, mode, profile)?;
+            debug!(provider = provider.as_str(), mode, profile, "Scrubbed credentials from DB");
+            Ok(VerifiedDelete::Deleted)
+        }
+    }
+}
+
+/// Delete every plaintext credential row that the keychain holds an identical
+/// copy of, then truncate the WAL so the old pages leave the disk.
+pub fn scrub_db_credentials(pool: &DbPool) -> Result<CredentialScrubReport, String> {
+    scrub_credentials(pool, provider_keychain_get)
+}
+
+fn scrub_credentials(
+    pool: &DbPool,
+    load: impl Fn(BrokerProvider, &str, &str) -> Result<Option<ProviderCredentials>, String>,
+) -> Result<CredentialScrubReport, String> {
+    use crate::commands::credentials::provider_credentials_list_db;
+
+    let mut report = CredentialScrubReport::default();
+    for (provider, mode, profile) in provider_credentials_list_db(pool)? {
+        let outcome = verified_delete(pool, provider, &mode, &profile, &load)?;
+        let entry = ScrubbedCredential { provider, mode, profile };
+        match outcome {
+            VerifiedDelete::Deleted => report.scrubbed.push(entry),
+            VerifiedDelete::Missing => report.missing_in_keychain.push(entry),
+            VerifiedDelete::Mismatch => report.mismatched.push(entry),
+        }
+    }
+    if !report.scrubbed.is_empty() {
+        crate::db::checkpoint(pool).map_err(|e| e.to_string())?;
     }
+    Ok(report)
+}
 
+fn llm_keychain_key(provider: &str) -> Result<String, String> {
+    llm_config_field(provider)?;
+    Ok(format!("llm_{}", provider))
+}
+
+/// Store an LLM provider API key in the OS keychain.
+pub fn llm_key_set(provider: &str, key: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(SERVICE, &llm_keychain_key(provider)?)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    entry
+        .set_password(key)
+        .map_err(|e| format!("Failed to store in keychain: {}", e))?;
+    debug!(provider, "LLM key stored in keychain");
     Ok(())
 }
 
+/// Retrieve an LLM provider API key from the OS keychain. Returns None if not set.
+pub fn llm_key_get(provider: &str) -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(SERVICE, &llm_keychain_key(provider)?)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    match entry.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+    }
+}
+
+/// Check whether an LLM provider API key exists in the OS keychain.
+pub fn llm_key_exists(provider: &str) -> Result<bool, String> {
+    llm_key_get(provider).map(|key| key.is_some())
+}
+
+/// Delete an LLM provider API key from the OS keychain.
+pub fn llm_key_delete(provider: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(SERVICE, &llm_keychain_key(provider)?)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete from keychain: {}", e)),
+    }
+}
+
+const WEBHOOK_TOKEN_KEY: &str = "webhook_token";
+
+/// Store the local webhook bearer token in the OS keychain.
+pub fn webhook_token_set(token: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(SERVICE, WEBHOOK_TOKEN_KEY)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    entry
+        .set_password(token)
+        .map_err(|e| format!("Failed to store in keychain: {}", e))
+}
+
+/// Retrieve the local webhook bearer token. Returns None if not set.
+pub fn webhook_token_get() -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(SERVICE, WEBHOOK_TOKEN_KEY)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    match entry.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+    }
+}
+
+const LOCAL_API_TOKEN_KEY: &str = "local_api_token";
+
+/// Store the local REST API bearer token in the OS keychain.
+pub fn local_api_token_set(token: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(SERVICE, LOCAL_API_TOKEN_KEY)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    entry
+        .set_password(token)
+        .map_err(|e| format!("Failed to store in keychain: {}", e))
+}
+
+/// Retrieve the local REST API bearer token. Returns None if not set.
+pub fn local_api_token_get() -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(SERVICE, LOCAL_API_TOKEN_KEY)
+        .map_err(|e| format!("Failed to create keychain entry: {}", e))?;
+    match entry.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read from keychain: {}", e)),
+    }
+}
+
+/// Move LLM API keys from the config JSON into the OS keychain (idempotent).
+/// Keys are only removed from the DB once stored, and are scrubbed from config
+/// history too.
+pub fn migrate_llm_keys_to_keychain(pool: &DbPool) -> Result<(), String> {
+    migrate_llm_keys(pool, llm_key_set)
+}
+
+fn migrate_llm_keys(
+    pool: &DbPool,
+    store: impl Fn(&str, &str) -> Result<(), String>,
+) -> Result<(), String> {
+    use crate::commands::config::{config_get_db, config_set_db, scrub_history_fields};
+
+    let mut config: serde_json::Value =
+        serde_json::from_str(&config_get_db(pool)?).map_err(|e| e.to_string())?;
+    let Some(map) = config.as_object_mut() else {
+        return Ok(());
+    };
+    let mut moved = Vec::new();
+    for (provider, field) in LLM_PROVIDERS {
+        let Some(value) = map.get(*field) else {
+            continue;
+        };
+        if let Some(key) = value.as_str().filter(|k| !k.is_empty()) {
+            if let Err(e) = store(provider, key) {
+                tracing::warn!(error = %e, provider, "Keychain write failed, keeping LLM key in DB");
+                continue;
+            }
+            debug!(provider, "Migrated LLM key from DB to keychain");
+        }
+        map.remove(*field);
+        moved.push(*field);
+    }
+    if moved.is_empty() {
+        return Ok(());
+    }
+    config_set_db(pool, &serde_json::to_string(&config).map_err(|e| e.to_string())?)?;
+    scrub_history_fields(pool, &moved)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,19 +334,19 @@ mod tests {
             key_id: "TEST_KEY_123".to_string(),
             secret_key: "test_secret_456".to_string(),
         };
-        keychain_set("paper", &creds).unwrap();
-        let result = keychain_get("paper").unwrap();
+        keychain_set("paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        let result = keychain_get("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
         assert_eq!(result, Some(creds));
         // Cleanup
-        keychain_delete("paper").unwrap();
+        keychain_delete("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
     }
 
     #[test]
     #[ignore]
     fn keychain_get_returns_none_when_empty() {
         // Ensure it's deleted first
-        let _ = keychain_delete("paper");
-        let result = keychain_get("paper").unwrap();
+        let _ = keychain_delete("paper", DEFAULT_CREDENTIAL_PROFILE);
+        let result = keychain_get("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
         assert_eq!(result, None);
     }
 
@@ -137,17 +357,17 @@ mod tests {
             key_id: "DEL_KEY".to_string(),
             secret_key: "del_secret".to_string(),
         };
-        keychain_set("paper", &creds).unwrap();
-        assert!(keychain_exists("paper").unwrap());
-        keychain_delete("paper").unwrap();
-        assert!(!keychain_exists("paper").unwrap());
+        keychain_set("paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
+        assert!(keychain_exists("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap());
+        keychain_delete("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
+        assert!(!keychain_exists("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap());
     }
 
     #[test]
     #[ignore]
     fn keychain_exists_returns_false_when_empty() {
-        let _ = keychain_delete("live");
-        assert!(!keychain_exists("live").unwrap());
+        let _ = keychain_delete("live", DEFAULT_CREDENTIAL_PROFILE);
+        assert!(!keychain_exists("live", DEFAULT_CREDENTIAL_PROFILE).unwrap());
     }
 
     #[test]
@@ -156,10 +376,23 @@ mod tests {
             key_id: "KEY".to_string(),
             secret_key: "SECRET".to_string(),
         };
-        assert!(keychain_set("invalid", &creds).is_err());
-        assert!(keychain_get("invalid").is_err());
-        assert!(keychain_delete("invalid").is_err());
-        assert!(keychain_exists("invalid").is_err());
+        assert!(keychain_set("invalid", DEFAULT_CREDENTIAL_PROFILE, &creds).is_err());
+        assert!(keychain_get("invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+        assert!(keychain_delete("invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+        assert!(keychain_exists("invalid", DEFAULT_CREDENTIAL_PROFILE).is_err());
+        assert!(keychain_get("paper", "bad/name").is_err());
+    }
+
+    #[test]
+    fn keychain_key_keeps_legacy_name_for_default_profile() {
+        let alpaca = BrokerProvider::Alpaca;
+        assert_eq!(keychain_key(alpaca, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap(), "alpaca_paper");
+        assert_eq!(keychain_key(alpaca, "paper", "second").unwrap(), "alpaca_paper:second");
+        assert_eq!(
+            keychain_key(BrokerProvider::Polygon, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap(),
+            "polygon_live"
+        );
+        assert!(keychain_key(BrokerProvider::Polygon, "paper", DEFAULT_CREDENTIAL_PROFILE).is_err());
     }
 
     #[test]
@@ -173,20 +406,106 @@ mod tests {
         db::init_db(&pool).unwrap();
 
         // Ensure keychain is clean
-        let _ = keychain_delete("paper");
+        let _ = keychain_delete("paper", DEFAULT_CREDENTIAL_PROFILE);
 
         let creds = AlpacaCredentials {
             key_id: "MIGRATE_KEY".to_string(),
             secret_key: "migrate_secret".to_string(),
         };
-        credentials_set_db(&pool, "paper", &creds).unwrap();
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds).unwrap();
 
         migrate_db_to_keychain(&pool, "paper").unwrap();
 
-        let result = keychain_get("paper").unwrap();
+        let result = keychain_get("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
         assert_eq!(result, Some(creds));
+        let db_row = crate::commands::credentials::credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE);
+        assert_eq!(db_row.unwrap(), None);
 
         // Cleanup
-        keychain_delete("paper").unwrap();
+        keychain_delete("paper", DEFAULT_CREDENTIAL_PROFILE).unwrap();
+    }
+
+    #[test]
+    fn scrub_deletes_only_rows_the_keychain_matches() {
+        use crate::commands::credentials::{credentials_get_db, credentials_set_db};
+
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        let creds = |key: &str| AlpacaCredentials {
+            key_id: key.to_string(),
+            secret_key: "secret".to_string(),
+        };
+        credentials_set_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE, &creds("PK_SAME")).unwrap();
+        credentials_set_db(&pool, "paper", "second", &creds("PK_DB")).unwrap();
+        credentials_set_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE, &creds("AK_LIVE")).unwrap();
+
+        // Keychain: paper/default matches, paper/second differs, live is absent
+        let report = scrub_credentials(&pool, |_, mode, profile| {
+            Ok(match (mode, profile) {
+                ("paper", "default") => Some(creds("PK_SAME").into_fields()),
+                ("paper", "second") => Some(creds("PK_KEYCHAIN").into_fields()),
+                _ => None,
+            })
+        })
+        .unwrap();
+
+        assert_eq!(report.scrubbed.len(), 1);
+        assert_eq!(report.scrubbed[0].mode, "paper");
+        assert_eq!(report.mismatched[0].profile, "second");
+        assert_eq!(report.missing_in_keychain[0].mode, "live");
+        assert_eq!(credentials_get_db(&pool, "paper", DEFAULT_CREDENTIAL_PROFILE).unwrap(), None);
+        assert!(credentials_get_db(&pool, "paper", "second").unwrap().is_some());
+        assert!(credentials_get_db(&pool, "live", DEFAULT_CREDENTIAL_PROFILE).unwrap().is_some());
+    }
+
+    #[test]
+    fn llm_key_invalid_provider_rejected() {
+        assert!(llm_key_set("openai", "k").is_err());
+        assert!(llm_key_get("openai").is_err());
+        assert!(llm_key_delete("openai").is_err());
+        assert!(llm_key_exists("openai").is_err());
+    }
+
+    #[test]
+    fn migrate_llm_keys_moves_keys_out_of_db() {
+        use crate::commands::config::{config_get_db, config_history_list_db, config_update_db};
+        use crate::db;
+        use std::cell::RefCell;
+
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        config_update_db(&pool, r#"{"model":"m","anthropicApiKey":"sk-ant"}"#).unwrap();
+        config_update_db(&pool, r#"{"openrouterApiKey":""}"#).unwrap();
+
+        let stored = RefCell::new(Vec::new());
+        migrate_llm_keys(&pool, |provider, key| {
+            stored.borrow_mut().push((provider.to_string(), key.to_string()));
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(stored.into_inner(), vec![("anthropic".to_string(), "sk-ant".to_string())]);
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"model":"m"}"#);
+        for entry in config_history_list_db(&pool).unwrap() {
+            assert!(!entry.value.contains("sk-ant"));
+        }
+    }
+
+    #[test]
+    fn migrate_llm_keys_keeps_keys_when_keychain_fails() {
+        use crate::commands::config::{config_get_db, config_set_db};
+        use crate::db;
+
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        config_set_db(&pool, r#"{"anthropicApiKey":"sk-ant"}"#).unwrap();
+
+        migrate_llm_keys(&pool, |_, _| Err("no keychain".to_string())).unwrap();
+        assert_eq!(config_get_db(&pool).unwrap(), r#"{"anthropicApiKey":"sk-ant"}"#);
     }
 }
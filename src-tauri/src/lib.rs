@@ -1,73 +1,302 @@
+pub mod activity_recorder;
+pub mod agent_logs;
+pub mod analytics;
+pub mod alpaca;
+pub mod alerts;
+pub mod alpaca_stream;
+pub mod app_logs;
+pub mod anomaly_notifier;
 pub mod bridge;
+pub mod bridge_error;
+pub mod bridge_journal;
+pub mod bridge_metrics;
 pub mod bridge_pending;
+pub mod broker;
 pub mod commands;
+pub mod crash;
+pub mod csv_source;
+pub mod embeddings;
 pub mod indicators;
 pub mod keychain;
+pub mod local_api;
+pub mod loopback;
+pub mod market_calendar;
+pub mod metrics;
 pub mod db;
 pub mod events;
+pub mod http;
 pub mod jsonrpc;
 pub mod migrations;
+pub mod parquet_export;
+pub mod report;
 pub mod sidecar;
+pub mod sidecar_resources;
+pub mod sizing;
+pub mod source_quarantine;
+pub mod tasks;
+pub mod tick_recorder;
+pub mod time;
 pub mod types;
 pub mod watcher;
+pub mod webhook;
+pub mod workspace;
+pub mod workspace_archive;
 
-use tracing_subscriber::EnvFilter;
+use std::time::Duration;
 
-/// Initialize structured logging with tracing.
+use tauri::Manager;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter};
+
+/// How long to wait for the agent to acknowledge `agent:stop` on app exit.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(3);
+
+/// Initialize structured logging with tracing: human-readable lines on stderr
+/// and JSON lines in `~/.finwatch/logs/app/`.
 /// Respects RUST_LOG env var; defaults to `info` level for finwatch crate.
+/// The filter can be changed at runtime with `log_level_set`.
 pub fn init_tracing() {
-    let filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("finwatch=info"));
+    let directives = std::env::var("RUST_LOG")
+        .ok()
+        .filter(|d| EnvFilter::try_new(d).is_ok())
+        .unwrap_or_else(|| app_logs::DEFAULT_FILTER.to_string());
+    let (filter, handle) = reload::Layer::new(EnvFilter::new(&directives));
 
-    tracing_subscriber::fmt()
-        .with_env_filter(filter)
-        .with_target(true)
-        .with_thread_ids(false)
-        .with_file(false)
-        .with_line_number(false)
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            fmt::layer()
+                .with_target(true)
+                .with_thread_ids(false)
+                .with_file(false)
+                .with_line_number(false),
+        )
+        .with(
+            fmt::layer()
+                .json()
+                .with_target(true)
+                .with_writer(app_logs::AppLogWriter::new(app_logs::app_logs_dir())),
+        )
         .init();
+    app_logs::install_level_control(handle, directives);
+}
+
+/// Stop the sidecar and flush the database before the process exits.
+fn graceful_shutdown<R: tauri::Runtime>(app: &tauri::AppHandle<R>) {
+    tracing::info!("Shutting down");
+    if let Some(watcher) = app.try_state::<watcher::FileWatcher>() {
+        watcher.stop();
+    }
+    if let Some(webhook) = app.try_state::<webhook::WebhookServer>() {
+        webhook.stop();
+    }
+    if let Some(local_api) = app.try_state::<local_api::LocalApiServer>() {
+        local_api.stop();
+    }
+    if let Some(stream) = app.try_state::<alpaca_stream::AlpacaStream>() {
+        stream.stop();
+    }
+    if let Some(bridge) = app.try_state::<bridge::SidecarBridge>() {
+        if let Err(e) = bridge.shutdown(SHUTDOWN_GRACE) {
+            tracing::warn!(error = %e, "Failed to stop sidecar");
+        }
+    }
+    if let Some(workspace) = app.try_state::<workspace::WorkspaceDb>() {
+        if let Err(e) = db::checkpoint(&workspace.pool()) {
+            tracing::warn!(error = %e, "Failed to checkpoint database");
+        }
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     init_tracing();
+    crash::install_panic_hook();
 
     // Load .env from project root (parent of src-tauri/)
     let manifest_dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
     let project_root = manifest_dir.parent().unwrap_or(manifest_dir);
     let env_path = project_root.join(".env");
     dotenvy::from_path(&env_path).ok();
-    let data_dir = db::finwatch_data_dir();
-    let db_path = data_dir.join("state").join("finwatch.sqlite");
-    let pool = db::create_pool(&db_path).expect("Failed to create database pool");
-    db::init_db(&pool).expect("Failed to initialize database");
-    migrations::run_pending(&pool).expect("Failed to run migrations");
+    let workspace = workspace::WorkspaceDb::open(db::finwatch_root_dir())
+        .expect("Failed to open workspace database");
+    let pool = workspace.pool();
 
     // Migrate credentials from DB to OS keychain (idempotent, best-effort)
     keychain::migrate_db_to_keychain(&pool, "paper").ok();
     keychain::migrate_db_to_keychain(&pool, "live").ok();
+    if let Err(e) = keychain::migrate_llm_keys_to_keychain(&pool) {
+        tracing::warn!(error = %e, "Failed to migrate LLM keys to keychain");
+    }
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_notification::init())
-        .manage(pool)
+        .manage(workspace)
         .manage(bridge::SidecarBridge::new())
+        .manage(watcher::FileWatcher::new())
+        .manage(webhook::WebhookServer::new())
+        .manage(local_api::LocalApiServer::new())
+        .manage(alpaca_stream::AlpacaStream::new())
+        .manage(alerts::AlertEngine::new())
+        .manage(embeddings::LocalEmbedder::new())
+        .setup(|app| {
+            let app_config = commands::config::config_effective_db(&app.state::<workspace::WorkspaceDb>().pool())
+                .unwrap_or_default();
+            http::configure(http::network_config(&app_config));
+            tasks::spawn_scheduler(app.handle().clone());
+            commands::sources::yahoo::spawn_poller(app.handle().clone());
+            commands::sources::rss::spawn_poller(app.handle().clone());
+            commands::portfolio::spawn_refresher(app.handle().clone());
+            commands::offline::spawn_startup_replay(app.handle().clone());
+            let dir = app.state::<workspace::WorkspaceDb>().dir();
+            if let Err(e) = app.state::<watcher::FileWatcher>().start(app.handle().clone(), &dir) {
+                tracing::warn!(error = %e, "Failed to start file watcher");
+            }
+            let config = webhook::webhook_config(&app_config);
+            if config.enabled {
+                if let Err(e) = app.state::<webhook::WebhookServer>().apply(app.handle().clone(), config) {
+                    tracing::warn!(error = %e, "Failed to start webhook listener");
+                }
+            }
+            let config = local_api::local_api_config(&app_config);
+            if config.enabled {
+                if let Err(e) = app.state::<local_api::LocalApiServer>().apply(app.handle().clone(), config) {
+                    tracing::warn!(error = %e, "Failed to start local API listener");
+                }
+            }
+            let config = alpaca_stream::alpaca_stream_config(&app_config);
+            if config.enabled {
+                if let Err(e) = app.state::<alpaca_stream::AlpacaStream>().apply(app.handle().clone(), config) {
+                    tracing::warn!(error = %e, "Failed to start Alpaca stream");
+                }
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::assets::assets_fetch,
+            commands::assets::assets_search,
+            commands::assets::assets_fuzzy_search,
+            commands::assets::asset_get,
             commands::agent::agent_start,
+            commands::agent::agent_restart,
+            commands::agent::agent_watch_symbol,
+            commands::agent::agent_unwatch_symbol,
             commands::agent::agent_stop,
             commands::agent::agent_status,
+            commands::activity::agent_activity_list,
+            commands::alerts::alerts_rules_list,
+            commands::alerts::alerts_rule_add,
+            commands::alerts::alerts_rule_update,
+            commands::alerts::alerts_rule_remove,
+            commands::alerts::alerts_history,
+            commands::schedule::schedule_get,
+            commands::schedule::schedule_set,
+            commands::calendar::market_status,
+            commands::calendar::is_market_open,
+            commands::agent::agent_logs_read,
+            commands::bridge::bridge_metrics,
+            commands::bridge::bridge_metrics_reset,
+            commands::bridge::bridge_journal_status,
             commands::config::config_get,
             commands::config::config_update,
+            commands::config::config_history_list,
+            commands::config::config_rollback,
+            commands::config::config_reset,
+            commands::config::config_get_key,
+            commands::config::config_set_key,
+            commands::config::config_export,
+            commands::config::config_import,
+            commands::profiles::config_profile_list,
+            commands::profiles::config_profile_save,
+            commands::profiles::config_profile_activate,
             commands::anomalies::anomalies_list,
+            commands::anomalies::anomalies_heatmap,
             commands::anomalies::anomalies_feedback,
+            commands::anomalies::anomalies_acknowledge,
+            commands::anomalies::anomalies_snooze,
+            commands::anomalies::anomalies_resolve,
+            commands::anomalies::anomalies_set_severity,
+            commands::anomalies::anomaly_notes_list,
+            commands::anomalies::anomaly_notes_add,
+            commands::anomalies::anomaly_notes_edit,
+            commands::anomalies::anomaly_notes_delete,
+            commands::incidents::incidents_list,
+            commands::journal::journal_list,
+            commands::journal::journal_get,
+            commands::journal::journal_add,
+            commands::journal::journal_update,
+            commands::journal::journal_delete,
+            commands::journal::journal_summary_get,
             commands::memory::memory_search,
+            commands::memory::memory_export,
+            commands::memory::memory_import,
+            commands::memory::memory_compact,
+            commands::memory::memory_compactions,
+            commands::embeddings::embeddings_status,
+            commands::embeddings::embeddings_download,
+            commands::embeddings::embeddings_backfill,
             commands::sources::sources_health,
+            commands::providers::providers_health,
+            commands::providers::providers_health_history,
+            commands::providers::providers_effective,
+            commands::usage::usage_summary,
+            commands::models::models_list,
+            commands::prompts::prompts_get,
+            commands::prompts::prompts_set,
+            commands::prompts::prompts_list,
+            commands::prompts::prompts_revert,
+            commands::sources::sources_list,
+            commands::sources::sources_add,
+            commands::sources::sources_update,
+            commands::sources::sources_remove,
+            commands::sources::sources_csv_ingest,
+            commands::sources::yahoo::sources_yahoo_poll,
+            commands::alpaca_stream::alpaca_stream_status,
+            commands::alpaca_stream::alpaca_stream_start,
+            commands::alpaca_stream::alpaca_stream_stop,
+            commands::sources::rss::sources_rss_poll,
+            commands::sources::rss::news_list,
+            commands::ticks::ticks_query,
+            commands::portfolio::portfolio_get,
+            commands::portfolio::orders_list,
+            commands::performance::performance_history,
+            commands::analytics::risk_metrics,
+            commands::analytics::correlation_matrix,
+            commands::analytics::returns_stats,
+            commands::analytics::drawdown_analysis,
+            commands::orders::order_place,
+            commands::orders::order_cancel,
+            commands::trading::trading_halt,
+            commands::trading::trading_resume,
+            commands::trading::trading_status,
+            commands::offline::offline_status,
+            commands::offline::offline_enable,
+            commands::offline::offline_disable,
+            commands::offline::offline_queue_list,
+            commands::offline::offline_queue_discard,
+            commands::offline::offline_queue_replay,
             commands::credentials::credentials_set,
             commands::credentials::credentials_get,
             commands::credentials::credentials_exists,
+            commands::credentials::credentials_delete,
+            commands::credentials::credentials_validate,
+            commands::credentials::credentials_profile_active,
+            commands::credentials::credentials_profile_select,
+            commands::credentials::credentials_scrub_db,
+            commands::credentials::credentials_import_env,
+            commands::credentials::broker_providers,
+            commands::credentials::broker_credentials_set,
+            commands::credentials::broker_credentials_get,
+            commands::credentials::broker_credentials_exists,
+            commands::credentials::broker_credentials_delete,
+            commands::credentials::llm_key_set,
+            commands::credentials::llm_key_get,
+            commands::credentials::llm_key_exists,
+            commands::credentials::llm_key_delete,
             commands::backtest::backtest_start,
             commands::backtest::backtest_list,
             commands::backtest::backtest_get,
@@ -75,8 +304,50 @@ pub fn run() {
             commands::backtest::backtest_delete,
             commands::backtest::backtest_cancel,
             commands::backtest::backtest_update_status,
+            commands::maintenance::db_maintenance,
+            commands::maintenance::db_maintenance_last_run,
+            commands::migrations::migrations_status,
+            commands::watcher::watcher_start,
+            commands::watcher::watcher_stop,
+            commands::watcher::watcher_status,
+            commands::watcher::watch_paths_list,
+            commands::watcher::watch_paths_add,
+            commands::watcher::watch_paths_remove,
+            commands::webhook::webhook_status,
+            commands::webhook::webhook_start,
+            commands::webhook::webhook_stop,
+            commands::webhook::webhook_token_get,
+            commands::webhook::webhook_token_rotate,
+            commands::local_api::local_api_status,
+            commands::local_api::local_api_start,
+            commands::local_api::local_api_stop,
+            commands::local_api::local_api_token_get,
+            commands::local_api::local_api_token_rotate,
+            commands::metrics::metrics_get,
+            commands::metrics::metrics_prometheus,
+            commands::logs::log_level_set,
+            commands::logs::log_level_get,
+            commands::logs::logs_tail,
+            commands::logs::crash_report_get_last,
+            commands::logs::crash_report_dismiss,
+            commands::tasks::tasks_list,
+            commands::workspace::workspace_list,
+            commands::workspace::workspace_create,
+            commands::workspace::workspace_switch,
+            commands::workspace::workspace_export,
+            commands::export::export_parquet,
+            commands::export::report_generate,
+            commands::bars::bars_import_csv,
+            commands::workspace::workspace_import,
             indicators::indicators_compute,
+            indicators::indicators_compute_symbol,
+            sizing::position_size,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::Exit = event {
+                graceful_shutdown(app);
+            }
+        });
 }
@@ -0,0 +1,294 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::bridge::SidecarBridge;
+use crate::commands::anomalies::anomalies_list_db;
+use crate::commands::backtest::{backtest_get_db, backtest_get_trades_db, backtest_list_db};
+use crate::commands::sources::sources_health_db;
+use crate::types::anomaly::{AnomalyFilter, AnomalyStatus, Severity};
+use crate::commands::config::config_effective_db;
+use crate::loopback::{Body, HttpError, HttpRequest, ListenerStatus, LoopbackServer};
+use crate::workspace::WorkspaceDb;
+
+/// Anomalies returned when the request sets no `limit`.
+const DEFAULT_ANOMALY_LIMIT: u32 = 500;
+
+/// The `localApi` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LocalApiConfig {
+    /// Serve read-only JSON endpoints on localhost for scripts and notebooks.
+    pub enabled: bool,
+    pub port: u16,
+    /// Also serve `GET /metrics` in the Prometheus text format.
+    pub metrics: bool,
+}
+
+impl Default for LocalApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8788,
+            metrics: false,
+        }
+    }
+}
+
+/// Parse the `localApi` section of the app config.
+pub fn local_api_config(app_config: &serde_json::Value) -> LocalApiConfig {
+    app_config
+        .get("localApi")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The stored API token, creating one on first use.
+pub fn ensure_token() -> Result<String, String> {
+    crate::loopback::ensure_token(
+        crate::keychain::local_api_token_get,
+        crate::keychain::local_api_token_set,
+    )
+}
+
+/// Decode `a=1&b=x%20y` into a map; later keys win.
+pub fn parse_query(query: &str) -> HashMap<String, String> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (percent_decode(key), percent_decode(value))
+        })
+        .collect()
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' => match text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                Some(b) => {
+                    out.push(b);
+                    i += 2;
+                }
+                None => out.push(b'%'),
+            },
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A read-only endpoint, mirroring the Tauri command of the same name.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Route {
+    /// `GET /anomalies?severity=high,critical&source=&symbol=&since=&limit=`
+    Anomalies(AnomalyFilter),
+    /// `GET /backtests`
+    Backtests,
+    /// `GET /backtests/{id}`
+    Backtest(String),
+    /// `GET /backtests/{id}/trades`
+    BacktestTrades(String),
+    /// `GET /agent/status`
+    AgentStatus,
+    /// `GET /sources/health`
+    SourcesHealth,
+    /// `GET /metrics`, when `localApi.metrics` is on
+    Metrics,
+}
+
+fn anomaly_filter(query: &HashMap<String, String>) -> Result<AnomalyFilter, HttpError> {
+    let bad = |name: &str| HttpError(400, format!("Invalid '{}' parameter", name));
+    let severity = match query.get("severity") {
+        Some(list) => Some(
+            list.split(',')
+                .map(|s| serde_json::from_value::<Severity>(serde_json::Value::String(s.trim().to_string())))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| bad("severity"))?,
+        ),
+        None => None,
+    };
+    let status = match query.get("status") {
+        Some(list) => Some(
+            list.split(',')
+                .map(|s| serde_json::from_value::<AnomalyStatus>(serde_json::Value::String(s.trim().to_string())))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|_| bad("status"))?,
+        ),
+        None => None,
+    };
+    let since = query.get("since").map(|v| v.parse::<u64>()).transpose().map_err(|_| bad("since"))?;
+    let limit = query.get("limit").map(|v| v.parse::<u32>()).transpose().map_err(|_| bad("limit"))?;
+    let incident_id = query.get("incident").map(|v| v.parse::<i64>()).transpose().map_err(|_| bad("incident"))?;
+    Ok(AnomalyFilter {
+        severity,
+        source: query.get("source").cloned(),
+        symbol: query.get("symbol").cloned(),
+        since,
+        limit: Some(limit.unwrap_or(DEFAULT_ANOMALY_LIMIT)),
+        status,
+        include_snoozed: query.get("includeSnoozed").map(|v| v == "true"),
+        incident_id,
+    })
+}
+
+/// Route an authorized request. Every endpoint is `GET`.
+pub fn route(request: &HttpRequest) -> Result<Route, HttpError> {
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    let route = match segments.as_slice() {
+        ["anomalies"] => Route::Anomalies(anomaly_filter(&parse_query(&request.query))?),
+        ["backtests"] => Route::Backtests,
+        ["backtests", id] if !id.is_empty() => Route::Backtest(percent_decode(id)),
+        ["backtests", id, "trades"] if !id.is_empty() => Route::BacktestTrades(percent_decode(id)),
+        ["agent", "status"] => Route::AgentStatus,
+        ["sources", "health"] => Route::SourcesHealth,
+        ["metrics"] => Route::Metrics,
+        _ => return Err(HttpError(404, "Not found".to_string())),
+    };
+    if request.method != "GET" {
+        return Err(HttpError(405, "Use GET".to_string()));
+    }
+    Ok(route)
+}
+
+fn to_json<T: Serialize>(value: T) -> Result<serde_json::Value, HttpError> {
+    serde_json::to_value(value).map_err(|e| HttpError(500, e.to_string()))
+}
+
+/// JSON for the data endpoints, text for `/metrics`.
+fn respond<R: Runtime>(app: &AppHandle<R>, route: Route) -> Result<Body, HttpError> {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let internal = |e: String| HttpError(500, e);
+    let json = match route {
+        Route::Anomalies(filter) => to_json(anomalies_list_db(&pool, &Some(filter)).map_err(internal)?),
+        Route::Backtests => to_json(backtest_list_db(&pool).map_err(internal)?),
+        Route::Backtest(id) => {
+            to_json(backtest_get_db(&pool, &id).map_err(|_| HttpError(404, format!("No backtest {}", id)))?)
+        }
+        Route::BacktestTrades(id) => {
+            backtest_get_db(&pool, &id).map_err(|_| HttpError(404, format!("No backtest {}", id)))?;
+            to_json(backtest_get_trades_db(&pool, &id).map_err(internal)?)
+        }
+        Route::AgentStatus => to_json(crate::commands::agent::agent_status(app.state::<SidecarBridge>())),
+        Route::SourcesHealth => to_json(sources_health_db(&pool).map_err(internal)?),
+        Route::Metrics => {
+            if !local_api_config(&config_effective_db(&pool).map_err(internal)?).metrics {
+                return Err(HttpError(404, "Metrics endpoint is disabled".to_string()));
+            }
+            return Ok(Body::Text {
+                content_type: "text/plain; version=0.0.4",
+                text: crate::metrics::global().render_prometheus(),
+            });
+        }
+    };
+    json.map(Body::Json)
+}
+
+pub type LocalApiStatus = ListenerStatus;
+
+/// Managed state owning the localhost REST API listener.
+pub struct LocalApiServer(LoopbackServer);
+
+impl LocalApiServer {
+    pub fn new() -> Self {
+        Self(LoopbackServer::new("local-api"))
+    }
+
+    pub fn status(&self) -> LocalApiStatus {
+        self.0.status()
+    }
+
+    /// Listen on `127.0.0.1:port` with `token`; see [`LoopbackServer::start`].
+    pub fn start<R: Runtime>(&self, app: AppHandle<R>, port: u16, token: String) -> Result<LocalApiStatus, String> {
+        self.0.start(port, token, move |request| respond(&app, route(request)?))
+    }
+
+    /// Replace the token a running listener checks, without rebinding.
+    pub fn set_token(&self, token: String) {
+        self.0.set_token(token)
+    }
+
+    /// Stop listening. Returns whether a listener was running.
+    pub fn stop(&self) -> bool {
+        self.0.stop()
+    }
+
+    /// Start or stop the listener to match `config`. `metrics` is read per
+    /// request, so changing only that leaves the listener bound.
+    pub fn apply<R: Runtime>(&self, app: AppHandle<R>, config: LocalApiConfig) -> Result<LocalApiStatus, String> {
+        self.0.apply(config.enabled, config.port, ensure_token, move |request| {
+            respond(&app, route(request)?)
+        })
+    }
+}
+
+impl Default for LocalApiServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::read_request;
+
+    fn get(target: &str) -> HttpRequest {
+        let raw = format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target);
+        read_request(&mut raw.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn decodes_query_strings() {
+        let query = parse_query("symbol=BRK%2EB&source=my+feed&flag&bad=%zz");
+        assert_eq!(query["symbol"], "BRK.B");
+        assert_eq!(query["source"], "my feed");
+        assert_eq!(query["flag"], "");
+        assert_eq!(query["bad"], "%zz");
+    }
+
+    #[test]
+    fn routes_read_only_endpoints() {
+        assert_eq!(route(&get("/backtests")).unwrap(), Route::Backtests);
+        assert_eq!(route(&get("/backtests/bt-1/")).unwrap(), Route::Backtest("bt-1".to_string()));
+        assert_eq!(route(&get("/backtests/bt-1/trades")).unwrap(), Route::BacktestTrades("bt-1".to_string()));
+        assert_eq!(route(&get("/agent/status")).unwrap(), Route::AgentStatus);
+        assert_eq!(route(&get("/sources/health")).unwrap(), Route::SourcesHealth);
+        assert_eq!(route(&get("/metrics")).unwrap(), Route::Metrics);
+        assert_eq!(route(&get("/nope")).unwrap_err().0, 404);
+
+        let post = read_request(&mut "POST /backtests HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(route(&post).unwrap_err().0, 405);
+    }
+
+    #[test]
+    fn anomaly_filters_come_from_the_query() {
+        let Route::Anomalies(filter) = route(&get("/anomalies?severity=high,critical&symbol=AAPL&since=5")).unwrap()
+        else {
+            panic!("expected anomalies route");
+        };
+        assert_eq!(filter.severity, Some(vec![Severity::High, Severity::Critical]));
+        assert_eq!(filter.symbol.as_deref(), Some("AAPL"));
+        assert_eq!(filter.since, Some(5));
+        assert_eq!(filter.limit, Some(DEFAULT_ANOMALY_LIMIT));
+        assert_eq!(filter.include_snoozed, None);
+
+        let Route::Anomalies(filter) = route(&get("/anomalies?status=new,snoozed&includeSnoozed=true")).unwrap() else {
+            panic!("expected anomalies route");
+        };
+        assert_eq!(filter.status, Some(vec![AnomalyStatus::New, AnomalyStatus::Snoozed]));
+        assert_eq!(filter.include_snoozed, Some(true));
+
+        assert_eq!(route(&get("/anomalies?severity=extreme")).unwrap_err().0, 400);
+        assert_eq!(route(&get("/anomalies?status=done")).unwrap_err().0, 400);
+        assert_eq!(route(&get("/anomalies?limit=-1")).unwrap_err().0, 400);
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use serde::Serialize;
+
+/// Largest request body accepted.
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// How long the accept loop sleeps between checks for a stop request.
+const ACCEPT_POLL: Duration = Duration::from_millis(200);
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A new bearer token: 32 bytes from the OS random source as 64 hex characters.
+pub fn generate_token() -> Result<String, String> {
+    let mut bytes = [0u8; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Failed to generate token: {}", e))?;
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// The token stored through `get`, creating and storing one with `set` on first use.
+pub fn ensure_token(
+    get: fn() -> Result<Option<String>, String>,
+    set: fn(&str) -> Result<(), String>,
+) -> Result<String, String> {
+    match get()? {
+        Some(token) => Ok(token),
+        None => {
+            let token = generate_token()?;
+            set(&token)?;
+            Ok(token)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    /// The raw query string, without the `?`.
+    pub query: String,
+    /// Header names are lowercased.
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// An HTTP status and the message returned with it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpError(pub u16, pub String);
+
+/// Read one HTTP/1.1 request with a `Content-Length` body.
+pub fn read_request(reader: &mut impl BufRead) -> Result<HttpRequest, HttpError> {
+    let bad = |msg: &str| HttpError(400, msg.to_string());
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|_| bad("Unreadable request"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad("Malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (path, query) = (path.to_string(), query.to_string());
+    let method = method.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|_| bad("Unreadable headers"))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let (name, value) = header.split_once(':').ok_or_else(|| bad("Malformed header"))?;
+        headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+    }
+
+    let length = match headers.get("content-length") {
+        Some(v) => v.parse::<usize>().map_err(|_| bad("Invalid Content-Length"))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(HttpError(413, format!("Body exceeds {} bytes", MAX_BODY_BYTES)));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|_| bad("Truncated body"))?;
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    })
+}
+
+/// Whether the request carries `Authorization: Bearer <token>`.
+pub fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    let Some(given) = request
+        .headers
+        .get("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    // Compare without short-circuiting on the first differing byte
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+/// The body of a successful response.
+pub enum Body {
+    Json(serde_json::Value),
+    Text { content_type: &'static str, text: String },
+}
+
+fn write_response(stream: &mut TcpStream, status: u16, body: &serde_json::Value) {
+    write_body(stream, status, "application/json", &body.to_string());
+}
+
+/// Write a complete response with `body` as `content_type` and close.
+fn write_body(stream: &mut TcpStream, status: u16, content_type: &str, body: &str) {
+    let reason = match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        content_type,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn handle_connection<F>(name: &str, handler: &F, mut stream: TcpStream, token: &RwLock<String>)
+where
+    F: Fn(&HttpRequest) -> Result<Body, HttpError>,
+{
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+    let result = stream
+        .try_clone()
+        .map_err(|e| HttpError(500, e.to_string()))
+        .and_then(|s| read_request(&mut BufReader::new(s)))
+        .and_then(|request| {
+            if !is_authorized(&request, &token.read().unwrap_or_else(|e| e.into_inner())) {
+                return Err(HttpError(401, "Missing or invalid bearer token".to_string()));
+            }
+            handler(&request)
+        });
+    match result {
+        Ok(Body::Json(body)) => write_response(&mut stream, 200, &body),
+        Ok(Body::Text { content_type, text }) => write_body(&mut stream, 200, content_type, &text),
+        Err(HttpError(status, message)) => {
+            tracing::debug!(server = name, status, %message, "Request rejected");
+            write_response(&mut stream, status, &serde_json::json!({ "error": message }));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListenerStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+}
+
+struct ActiveListener {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+}
+
+/// A bearer-token HTTP listener on `127.0.0.1`, answering one request per
+/// connection on its own thread.
+pub struct LoopbackServer {
+    /// Names the thread and the log lines.
+    name: &'static str,
+    active: Mutex<Option<ActiveListener>>,
+    /// Read per connection, so a rotation takes effect without rebinding.
+    token: Arc<RwLock<String>>,
+}
+
+impl LoopbackServer {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            active: Mutex::new(None),
+            token: Arc::new(RwLock::new(String::new())),
+        }
+    }
+
+    pub fn status(&self) -> ListenerStatus {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        ListenerStatus {
+            running: active.is_some(),
+            port: active.as_ref().map(|a| a.port),
+        }
+    }
+
+    /// Listen on `127.0.0.1:port` with `token`, passing authorized requests to
+    /// `handler`. A listener already on `port` keeps running, and its handler,
+    /// with the new token; one on another port is replaced.
+    pub fn start<F>(&self, port: u16, token: String, handler: F) -> Result<ListenerStatus, String>
+    where
+        F: Fn(&HttpRequest) -> Result<Body, HttpError> + Send + 'static,
+    {
+        self.set_token(token);
+        if self.status().port == Some(port) {
+            return Ok(self.status());
+        }
+        self.stop();
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+            .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+        listener.set_nonblocking(true).map_err(|e| e.to_string())?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = Arc::clone(&stop);
+        let token = Arc::clone(&self.token);
+        let name = self.name;
+        let thread = std::thread::Builder::new()
+            .name(format!("finwatch-{}", name))
+            .spawn(move || {
+                while !stop_flag.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let _ = stream.set_nonblocking(false);
+                            handle_connection(name, &handler, stream, &token);
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => std::thread::sleep(ACCEPT_POLL),
+                        Err(e) => {
+                            tracing::warn!(server = name, error = %e, "Accept failed");
+                            std::thread::sleep(ACCEPT_POLL);
+                        }
+                    }
+                }
+            })
+            .map_err(|e| format!("Failed to spawn {} thread: {}", name, e))?;
+        tracing::info!(server = name, port, "Listener started");
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = Some(ActiveListener { port, stop, thread });
+        Ok(self.status())
+    }
+
+    /// Replace the token a running listener checks, without rebinding.
+    pub fn set_token(&self, token: String) {
+        *self.token.write().unwrap_or_else(|e| e.into_inner()) = token;
+    }
+
+    /// Stop listening and wait for the port to be released. Returns whether a
+    /// listener was running.
+    pub fn stop(&self) -> bool {
+        let Some(previous) = self.active.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return false;
+        };
+        previous.stop.store(true, Ordering::SeqCst);
+        if previous.thread.join().is_err() {
+            tracing::warn!(server = self.name, port = previous.port, "Listener thread panicked");
+        }
+        tracing::info!(server = self.name, port = previous.port, "Listener stopped");
+        true
+    }
+
+    /// Start or stop the listener to match `enabled` and `port`, leaving one
+    /// already on `port` bound. `token` is only called when enabled.
+    pub fn apply<F>(
+        &self,
+        enabled: bool,
+        port: u16,
+        token: impl FnOnce() -> Result<String, String>,
+        handler: F,
+    ) -> Result<ListenerStatus, String>
+    where
+        F: Fn(&HttpRequest) -> Result<Body, HttpError> + Send + 'static,
+    {
+        if !enabled {
+            self.stop();
+            return Ok(self.status());
+        }
+        self.start(port, token()?, handler)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    fn parse(raw: &str) -> Result<HttpRequest, HttpError> {
+        read_request(&mut raw.as_bytes())
+    }
+
+    #[test]
+    fn reads_request_line_headers_and_body() {
+        let raw = "POST /ticks?x=1 HTTP/1.1\r\nAuthorization: Bearer abc\r\nContent-Length: 2\r\n\r\n{}";
+        let req = parse(raw).unwrap();
+        assert_eq!(req.method, "POST");
+        assert_eq!(req.path, "/ticks");
+        assert_eq!(req.query, "x=1");
+        assert_eq!(req.headers["authorization"], "Bearer abc");
+        assert_eq!(req.body, b"{}");
+        assert!(is_authorized(&req, "abc"));
+        assert!(!is_authorized(&req, "abd"));
+        assert!(!is_authorized(&parse("POST /ticks HTTP/1.1\r\n\r\n").unwrap(), "abc"));
+    }
+
+    #[test]
+    fn rejects_oversized_bodies() {
+        let raw = format!("POST /ticks HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_BYTES + 1);
+        assert_eq!(parse(&raw).unwrap_err().0, 413);
+    }
+
+    #[test]
+    fn tokens_are_random_hex() {
+        let a = generate_token().unwrap();
+        assert_eq!(a.len(), 64);
+        assert!(a.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(a, generate_token().unwrap());
+    }
+
+    fn free_port() -> u16 {
+        TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    fn reply(text: &'static str) -> impl Fn(&HttpRequest) -> Result<Body, HttpError> + Send + 'static {
+        move |_| {
+            Ok(Body::Text {
+                content_type: "text/plain",
+                text: text.to_string(),
+            })
+        }
+    }
+
+    /// The status line and body returned for `GET /` with `token`.
+    fn fetch(port: u16, token: &str) -> (String, String) {
+        let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, port)).unwrap();
+        write!(stream, "GET / HTTP/1.1\r\nAuthorization: Bearer {}\r\n\r\n", token).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_string(), body.to_string())
+    }
+
+    #[test]
+    fn restarts_on_the_same_port() {
+        let server = LoopbackServer::new("test");
+        let port = free_port();
+        server.start(port, "first".to_string(), reply("a")).unwrap();
+        server.start(port, "second".to_string(), reply("b")).unwrap();
+        assert_eq!(fetch(port, "second"), ("HTTP/1.1 200 OK".to_string(), "a".to_string()));
+        assert_eq!(fetch(port, "first").0, "HTTP/1.1 401 Unauthorized");
+
+        assert!(server.stop());
+        server.start(port, "third".to_string(), reply("c")).unwrap();
+        assert_eq!(server.status().port, Some(port));
+        assert_eq!(fetch(port, "third").1, "c");
+
+        server.start(free_port(), "fourth".to_string(), reply("d")).unwrap();
+        server.start(port, "fifth".to_string(), reply("e")).unwrap();
+        assert_eq!(fetch(port, "fifth").1, "e");
+        assert!(server.stop());
+        assert!(!server.stop());
+    }
+
+    #[test]
+    fn apply_only_rebinds_when_enabled_or_port_change() {
+        let server = LoopbackServer::new("test");
+        let port = free_port();
+        let token = || Ok("t".to_string());
+        server.apply(true, port, token, reply("a")).unwrap();
+        server.apply(true, port, token, reply("b")).unwrap();
+        assert_eq!(fetch(port, "t").1, "a");
+
+        let disabled = || Err("unused".to_string());
+        assert_eq!(server.apply(false, port, disabled, reply("c")).unwrap().port, None);
+        server.apply(true, port, token, reply("d")).unwrap();
+        assert_eq!(fetch(port, "t").1, "d");
+        server.stop();
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::{DateTime, Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Regular NYSE session in New York time, as (hour, minute).
+const OPEN: (u32, u32) = (9, 30);
+const CLOSE: (u32, u32) = (16, 0);
+/// Close on the days before Independence Day and Christmas and after Thanksgiving.
+const EARLY_CLOSE: (u32, u32) = (13, 0);
+
+/// How far ahead `next_session` looks; covers the longest run of closed days.
+const MAX_CLOSED_DAYS: u64 = 10;
+
+/// One NYSE trading day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarketSession {
+    /// Trading date in New York, `YYYY-MM-DD`.
+    pub date: String,
+    /// Epoch millis of the opening bell.
+    pub open_at: i64,
+    /// Epoch millis of the closing bell.
+    pub close_at: i64,
+    pub early_close: bool,
+}
+
+fn nth_weekday(year: i32, month: u32, weekday: Weekday, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, n).expect("valid nth weekday")
+}
+
+fn last_weekday(year: i32, month: u32, weekday: Weekday) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, weekday, 5)
+        .unwrap_or_else(|| nth_weekday(year, month, weekday, 4))
+}
+
+fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+}
+
+/// Easter Sunday (anonymous Gregorian algorithm).
+fn easter(year: i32) -> NaiveDate {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    ymd(year, month as u32, day as u32)
+}
+
+/// Weekend holidays move to Friday or Monday.
+fn observed(date: NaiveDate) -> NaiveDate {
+    match date.weekday() {
+        Weekday::Sat => date - Days::new(1),
+        Weekday::Sun => date + Days::new(1),
+        _ => date,
+    }
+}
+
+/// NYSE full-day holidays observed in `year`.
+pub fn holidays(year: i32) -> Vec<NaiveDate> {
+    let mut days = Vec::with_capacity(10);
+    // New Year's Day on a Saturday is not made up on the Friday before
+    let new_year = ymd(year, 1, 1);
+    if new_year.weekday() != Weekday::Sat {
+        days.push(observed(new_year));
+    }
+    days.push(nth_weekday(year, 1, Weekday::Mon, 3));
+    days.push(nth_weekday(year, 2, Weekday::Mon, 3));
+    days.push(easter(year) - Days::new(2));
+    days.push(last_weekday(year, 5, Weekday::Mon));
+    if year >= 2022 {
+        days.push(observed(ymd(year, 6, 19)));
+    }
+    days.push(observed(ymd(year, 7, 4)));
+    days.push(nth_weekday(year, 9, Weekday::Mon, 1));
+    days.push(nth_weekday(year, 11, Weekday::Thu, 4));
+    days.push(observed(ymd(year, 12, 25)));
+    days
+}
+
+pub fn is_holiday(date: NaiveDate) -> bool {
+    holidays(date.year()).contains(&date)
+}
+
+fn is_trading_day(date: NaiveDate) -> bool {
+    !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_holiday(date)
+}
+
+/// Whether the session on `date` closes at 13:00.
+pub fn is_early_close(date: NaiveDate) -> bool {
+    let year = date.year();
+    let candidates = [
+        ymd(year, 7, 3),
+        nth_weekday(year, 11, Weekday::Thu, 4) + Days::new(1),
+        ymd(year, 12, 24),
+    ];
+    candidates.contains(&date) && is_trading_day(date)
+}
+
+/// New York's offset from UTC in hours on `date`, during trading hours.
+/// Daylight time runs from the second Sunday of March to the first Sunday of November.
+fn new_york_offset_hours(date: NaiveDate) -> i64 {
+    let dst_start = nth_weekday(date.year(), 3, Weekday::Sun, 2);
+    let dst_end = nth_weekday(date.year(), 11, Weekday::Sun, 1);
+    if date >= dst_start && date < dst_end {
+        -4
+    } else {
+        -5
+    }
+}
+
+/// Epoch millis of `(hour, minute)` New York time on `date`.
+pub fn new_york_to_epoch_millis(date: NaiveDate, (hour, minute): (u32, u32)) -> i64 {
+    let local = date.and_hms_opt(hour, minute, 0).expect("valid time");
+    local.and_utc().timestamp_millis() - new_york_offset_hours(date) * 3_600_000
+}
+
+/// The session on `date`, or `None` on weekends and holidays.
+pub fn session(date: NaiveDate) -> Option<MarketSession> {
+    if !is_trading_day(date) {
+        return None;
+    }
+    let early_close = is_early_close(date);
+    Some(MarketSession {
+        date: date.format("%Y-%m-%d").to_string(),
+        open_at: new_york_to_epoch_millis(date, OPEN),
+        close_at: new_york_to_epoch_millis(date, if early_close { EARLY_CLOSE } else { CLOSE }),
+        early_close,
+    })
+}
+
+/// The session in progress at `now` (epoch millis), or else the next one.
+pub fn next_session(now: i64) -> Option<MarketSession> {
+    next_session_with(now, session)
+}
+
+/// [`next_session`] over another source of sessions, such as a fetched calendar.
+pub fn next_session_with(now: i64, session: impl Fn(NaiveDate) -> Option<MarketSession>) -> Option<MarketSession> {
+    let utc = DateTime::from_timestamp_millis(now)?.naive_utc();
+    // Start a day early: the UTC date runs ahead of New York in the evening
+    let start = utc.date() - Days::new(1);
+    (0..=MAX_CLOSED_DAYS)
+        .filter_map(|d| session(start + Days::new(d)))
+        .find(|s| s.close_at > now)
+}
+
+/// Whether the market is open at `now` (epoch millis).
+pub fn is_open(now: i64) -> bool {
+    next_session(now).is_some_and(|s| s.open_at <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        ymd(y, m, d)
+    }
+
+    fn utc_millis(y: i32, m: u32, d: u32, h: u32, min: u32) -> i64 {
+        date(y, m, d).and_hms_opt(h, min, 0).unwrap().and_utc().timestamp_millis()
+    }
+
+    #[test]
+    fn holidays_match_published_2025_calendar() {
+        let expected = [
+            date(2025, 1, 1),
+            date(2025, 1, 20),
+            date(2025, 2, 17),
+            date(2025, 4, 18),
+            date(2025, 5, 26),
+            date(2025, 6, 19),
+            date(2025, 7, 4),
+            date(2025, 9, 1),
+            date(2025, 11, 27),
+            date(2025, 12, 25),
+        ];
+        assert_eq!(holidays(2025), expected);
+    }
+
+    #[test]
+    fn weekend_holidays_are_observed() {
+        // July 4th 2026 is a Saturday, Christmas 2022 a Sunday
+        assert!(is_holiday(date(2026, 7, 3)));
+        assert!(is_holiday(date(2022, 12, 26)));
+        // New Year's Day 2022 was a Saturday: Dec 31 2021 traded
+        assert!(!is_holiday(date(2021, 12, 31)));
+    }
+
+    #[test]
+    fn early_closes() {
+        assert!(is_early_close(date(2025, 7, 3)));
+        assert!(is_early_close(date(2025, 11, 28)));
+        assert!(is_early_close(date(2025, 12, 24)));
+        // July 3rd 2026 is the observed holiday, not an early close
+        assert!(!is_early_close(date(2026, 7, 3)));
+        let s = session(date(2025, 11, 28)).unwrap();
+        assert_eq!(s.close_at, utc_millis(2025, 11, 28, 18, 0));
+    }
+
+    #[test]
+    fn session_times_follow_daylight_saving() {
+        let winter = session(date(2025, 1, 15)).unwrap();
+        assert_eq!(winter.open_at, utc_millis(2025, 1, 15, 14, 30));
+        assert_eq!(winter.close_at, utc_millis(2025, 1, 15, 21, 0));
+        let summer = session(date(2025, 6, 16)).unwrap();
+        assert_eq!(summer.open_at, utc_millis(2025, 6, 16, 13, 30));
+        assert!(session(date(2025, 6, 14)).is_none());
+    }
+
+    #[test]
+    fn next_session_skips_weekends_and_holidays() {
+        // Friday evening before the Memorial Day weekend
+        let next = next_session(utc_millis(2025, 5, 23, 22, 0)).unwrap();
+        assert_eq!(next.date, "2025-05-27");
+        // During a session, the current one is returned
+        let current = next_session(utc_millis(2025, 5, 27, 15, 0)).unwrap();
+        assert_eq!(current.date, "2025-05-27");
+        assert!(is_open(utc_millis(2025, 5, 27, 15, 0)));
+        assert!(!is_open(utc_millis(2025, 5, 27, 13, 0)));
+    }
+}
@@ -0,0 +1,376 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::time::now_secs;
+
+pub const RPC_DURATION: &str = "finwatch_rpc_request_duration_seconds";
+pub const RPC_ERRORS: &str = "finwatch_rpc_errors_total";
+pub const DB_TASK_DURATION: &str = "finwatch_db_task_duration_seconds";
+pub const HTTP_DURATION: &str = "finwatch_http_request_duration_seconds";
+pub const HTTP_RETRIES: &str = "finwatch_http_retries_total";
+pub const HTTP_ERRORS: &str = "finwatch_http_errors_total";
+pub const TICKS: &str = "finwatch_ticks_total";
+pub const ANOMALIES: &str = "finwatch_anomalies_total";
+pub const SIDECAR_RESTARTS: &str = "finwatch_sidecar_restarts_total";
+pub const TICKS_PER_SECOND: &str = "finwatch_ticks_per_second";
+pub const ANOMALIES_PER_HOUR: &str = "finwatch_anomalies_per_hour";
+pub const UPTIME: &str = "finwatch_uptime_seconds";
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: &[f64] = &[0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+/// Seconds of per-second event counts kept for the rate gauges.
+const RATE_WINDOW_SECS: u64 = 3_600;
+/// Window of the ticks-per-second gauge.
+const TICK_RATE_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl MetricKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        }
+    }
+}
+
+/// Every metric the registry exports, with its Prometheus type and help text.
+const DESCRIPTORS: &[(&str, MetricKind, &str)] = &[
+    (RPC_DURATION, MetricKind::Histogram, "Latency of JSON-RPC requests to the agent."),
+    (RPC_ERRORS, MetricKind::Counter, "JSON-RPC requests that failed or timed out."),
+    (DB_TASK_DURATION, MetricKind::Histogram, "Time spent in blocking database tasks."),
+    (HTTP_DURATION, MetricKind::Histogram, "Latency of outgoing HTTP requests, per attempt."),
+    (HTTP_RETRIES, MetricKind::Counter, "Outgoing HTTP requests retried after throttling or failure."),
+    (HTTP_ERRORS, MetricKind::Counter, "Outgoing HTTP requests that failed after every retry."),
+    (TICKS, MetricKind::Counter, "Data ticks received from any source."),
+    (ANOMALIES, MetricKind::Counter, "Anomalies detected or ingested."),
+    (SIDECAR_RESTARTS, MetricKind::Counter, "Automatic restarts of the agent sidecar."),
+    (TICKS_PER_SECOND, MetricKind::Gauge, "Ticks received per second over the last minute."),
+    (ANOMALIES_PER_HOUR, MetricKind::Gauge, "Anomalies received in the last hour."),
+    (UPTIME, MetricKind::Gauge, "Seconds since the app started."),
+];
+
+type Labels = Vec<(&'static str, String)>;
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Cumulative count per bucket in `BUCKETS`.
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, value: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; BUCKETS.len()];
+        }
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if value <= *bound {
+                *bucket += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Per-second event counts over the last hour, oldest first.
+#[derive(Debug, Default)]
+struct RateWindow {
+    seconds: VecDeque<(u64, u64)>,
+}
+
+impl RateWindow {
+    fn mark(&mut self, second: u64, n: u64) {
+        match self.seconds.back_mut() {
+            Some((s, count)) if *s == second => *count += n,
+            _ => self.seconds.push_back((second, n)),
+        }
+        self.trim(second);
+    }
+
+    fn trim(&mut self, now: u64) {
+        while self.seconds.front().is_some_and(|(s, _)| s + RATE_WINDOW_SECS <= now) {
+            self.seconds.pop_front();
+        }
+    }
+
+    /// Events in the `window` seconds up to and including `now`.
+    fn total(&self, now: u64, window: u64) -> u64 {
+        self.seconds
+            .iter()
+            .filter(|(s, _)| s + window > now)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    counters: BTreeMap<(&'static str, Labels), u64>,
+    gauges: BTreeMap<(&'static str, Labels), f64>,
+    histograms: BTreeMap<(&'static str, Labels), Histogram>,
+    ticks: RateWindow,
+    anomalies: RateWindow,
+}
+
+/// One counter or gauge value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// Count and sum of a histogram; mean is `sum / count`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistogramSample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+/// Everything the registry holds, returned by `metrics_get`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    pub counters: Vec<Sample>,
+    pub gauges: Vec<Sample>,
+    pub histograms: Vec<HistogramSample>,
+}
+
+/// Counters, gauges, and latency histograms collected across the crate.
+pub struct Registry {
+    started: Instant,
+    inner: Mutex<Inner>,
+}
+
+fn labels(pairs: &[(&'static str, &str)]) -> Labels {
+    pairs.iter().map(|(k, v)| (*k, v.to_string())).collect()
+}
+
+fn label_map(labels: &Labels) -> BTreeMap<String, String> {
+    labels.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn counter_add(&self, name: &'static str, pairs: &[(&'static str, &str)], n: u64) {
+        *self.lock().counters.entry((name, labels(pairs))).or_default() += n;
+    }
+
+    pub fn gauge_set(&self, name: &'static str, pairs: &[(&'static str, &str)], value: f64) {
+        self.lock().gauges.insert((name, labels(pairs)), value);
+    }
+
+    /// Add a latency sample to a histogram.
+    pub fn observe(&self, name: &'static str, pairs: &[(&'static str, &str)], elapsed: Duration) {
+        self.lock()
+            .histograms
+            .entry((name, labels(pairs)))
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Count `n` ticks, feeding both the counter and the per-second gauge.
+    pub fn record_ticks(&self, n: u64) {
+        self.record_ticks_at(now_secs(), n);
+    }
+
+    /// Count `n` anomalies, feeding both the counter and the hourly gauge.
+    pub fn record_anomalies(&self, n: u64) {
+        self.record_anomalies_at(now_secs(), n);
+    }
+
+    fn record_ticks_at(&self, second: u64, n: u64) {
+        let mut inner = self.lock();
+        *inner.counters.entry((TICKS, Vec::new())).or_default() += n;
+        inner.ticks.mark(second, n);
+    }
+
+    fn record_anomalies_at(&self, second: u64, n: u64) {
+        let mut inner = self.lock();
+        *inner.counters.entry((ANOMALIES, Vec::new())).or_default() += n;
+        inner.anomalies.mark(second, n);
+    }
+
+    fn snapshot_at(&self, now: u64) -> MetricsSnapshot {
+        let mut inner = self.lock();
+        inner.ticks.trim(now);
+        inner.anomalies.trim(now);
+        let ticks_per_second = inner.ticks.total(now, TICK_RATE_SECS) as f64 / TICK_RATE_SECS as f64;
+        let anomalies_per_hour = inner.anomalies.total(now, RATE_WINDOW_SECS) as f64;
+        inner.gauges.insert((TICKS_PER_SECOND, Vec::new()), ticks_per_second);
+        inner.gauges.insert((ANOMALIES_PER_HOUR, Vec::new()), anomalies_per_hour);
+        inner.gauges.insert((UPTIME, Vec::new()), self.started.elapsed().as_secs() as f64);
+
+        let sample = |((name, labels), value): (&(&'static str, Labels), f64)| Sample {
+            name: name.to_string(),
+            labels: label_map(labels),
+            value,
+        };
+        MetricsSnapshot {
+            counters: inner.counters.iter().map(|(k, v)| sample((k, *v as f64))).collect(),
+            gauges: inner.gauges.iter().map(|(k, v)| sample((k, *v))).collect(),
+            histograms: inner
+                .histograms
+                .iter()
+                .map(|((name, labels), h)| HistogramSample {
+                    name: name.to_string(),
+                    labels: label_map(labels),
+                    count: h.count,
+                    sum: h.sum,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.snapshot_at(now_secs())
+    }
+
+    /// Render every metric in the Prometheus text exposition format.
+    pub fn render_prometheus(&self) -> String {
+        self.render_prometheus_at(now_secs())
+    }
+
+    fn render_prometheus_at(&self, now: u64) -> String {
+        // Refreshes the derived gauges
+        self.snapshot_at(now);
+        let inner = self.lock();
+        let mut out = String::new();
+        for (name, kind, help) in DESCRIPTORS {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} {}", name, kind.as_str());
+            match kind {
+                MetricKind::Counter => {
+                    for ((_, labels), value) in inner.counters.iter().filter(|((n, _), _)| n == name) {
+                        let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), value);
+                    }
+                }
+                MetricKind::Gauge => {
+                    for ((_, labels), value) in inner.gauges.iter().filter(|((n, _), _)| n == name) {
+                        let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), value);
+                    }
+                }
+                MetricKind::Histogram => {
+                    for ((_, labels), h) in inner.histograms.iter().filter(|((n, _), _)| n == name) {
+                        for (bound, count) in BUCKETS.iter().zip(&h.buckets) {
+                            let le = bound.to_string();
+                            let _ = writeln!(out, "{}_bucket{} {}", name, render_labels(labels, Some(&le)), count);
+                        }
+                        let _ = writeln!(out, "{}_bucket{} {}", name, render_labels(labels, Some("+Inf")), h.count);
+                        let _ = writeln!(out, "{}_sum{} {}", name, render_labels(labels, None), h.sum);
+                        let _ = writeln!(out, "{}_count{} {}", name, render_labels(labels, None), h.count);
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Drop every recorded value.
+    pub fn reset(&self) {
+        *self.lock() = Inner::default();
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `{a="x",le="0.5"}`, or nothing when there are no labels.
+fn render_labels(labels: &Labels, le: Option<&str>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+        .collect();
+    if let Some(le) = le {
+        parts.push(format!("le=\"{}\"", le));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+/// The process-wide registry.
+pub fn global() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(Registry::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_cover_their_windows() {
+        let registry = Registry::new();
+        registry.record_ticks_at(1_000, 30);
+        registry.record_ticks_at(1_050, 90);
+        registry.record_anomalies_at(1_000, 2);
+        registry.record_anomalies_at(4_000, 1);
+
+        let snapshot = registry.snapshot_at(4_030);
+        let gauge = |name: &str| snapshot.gauges.iter().find(|g| g.name == name).unwrap().value;
+        assert_eq!(gauge(TICKS_PER_SECOND), 0.0);
+        assert_eq!(gauge(ANOMALIES_PER_HOUR), 3.0);
+        let ticks = snapshot.counters.iter().find(|c| c.name == TICKS).unwrap();
+        assert_eq!(ticks.value, 120.0);
+
+        let snapshot = registry.snapshot_at(1_055);
+        assert_eq!(snapshot.gauges.iter().find(|g| g.name == TICKS_PER_SECOND).unwrap().value, 2.0);
+        let snapshot = registry.snapshot_at(4_700);
+        assert_eq!(snapshot.gauges.iter().find(|g| g.name == ANOMALIES_PER_HOUR).unwrap().value, 1.0);
+    }
+
+    #[test]
+    fn renders_prometheus_text() {
+        let registry = Registry::new();
+        registry.counter_add(RPC_ERRORS, &[("method", "agent:\"start\"")], 2);
+        registry.observe(RPC_DURATION, &[("method", "ping")], Duration::from_millis(20));
+        registry.observe(RPC_DURATION, &[("method", "ping")], Duration::from_secs(20));
+        registry.record_ticks_at(100, 1);
+
+        let text = registry.render_prometheus_at(100);
+        assert!(text.contains("# TYPE finwatch_rpc_request_duration_seconds histogram\n"));
+        assert!(text.contains("finwatch_rpc_errors_total{method=\"agent:\\\"start\\\"\"} 2\n"));
+        assert!(text.contains("finwatch_rpc_request_duration_seconds_bucket{method=\"ping\",le=\"0.01\"} 0\n"));
+        assert!(text.contains("finwatch_rpc_request_duration_seconds_bucket{method=\"ping\",le=\"0.025\"} 1\n"));
+        assert!(text.contains("finwatch_rpc_request_duration_seconds_bucket{method=\"ping\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("finwatch_rpc_request_duration_seconds_count{method=\"ping\"} 2\n"));
+        assert!(text.contains("finwatch_ticks_total 1\n"));
+        assert!(text.contains("# TYPE finwatch_uptime_seconds gauge\n"));
+
+        registry.reset();
+        assert!(registry.snapshot_at(100).counters.is_empty());
+    }
+}
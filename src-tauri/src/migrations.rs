@@ -1,8 +1,22 @@
+use serde::Serialize;
+
 use crate::db::DbPool;
 
 pub struct Migration {
     pub name: &'static str,
     pub sql: &'static str,
+    /// Reverses `sql`. Migrations without one cannot be rolled back.
+    pub down_sql: Option<&'static str>,
+}
+
+/// Applied/available state of one migration, as returned by `migrations_status`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationStatus {
+    pub name: String,
+    pub applied: bool,
+    pub applied_at: Option<String>,
+    pub reversible: bool,
 }
 
 pub fn all_migrations() -> Vec<Migration> {
@@ -11,6 +25,7 @@ pub fn all_migrations() -> Vec<Migration> {
             name: "001_initial_schema",
             sql: "-- initial schema created by init_db, this is a placeholder
                   SELECT 1;",
+            down_sql: None,
         },
         Migration {
             name: "002_source_health_table",
@@ -24,6 +39,7 @@ pub fn all_migrations() -> Vec<Migration> {
                       message TEXT,
                       updated_at TEXT NOT NULL DEFAULT (datetime('now'))
                   );",
+            down_sql: Some("DROP TABLE IF EXISTS source_health;"),
         },
         Migration {
             name: "003_backtest_tables",
@@ -55,6 +71,10 @@ pub fn all_migrations() -> Vec<Migration> {
                   CREATE INDEX IF NOT EXISTS idx_backtest_trades_backtest ON backtest_trades(backtest_id);
                   CREATE INDEX IF NOT EXISTS idx_backtests_status ON backtests(status);
                   CREATE INDEX IF NOT EXISTS idx_backtests_created ON backtests(created_at);",
+            down_sql: Some(
+                "DROP TABLE IF EXISTS backtest_trades;
+                 DROP TABLE IF EXISTS backtests;",
+            ),
         },
         Migration {
             name: "004_assets_cache",
@@ -68,6 +88,445 @@ pub fn all_migrations() -> Vec<Migration> {
                   );
                   CREATE INDEX IF NOT EXISTS idx_assets_class ON assets(asset_class);
                   CREATE INDEX IF NOT EXISTS idx_assets_exchange ON assets(exchange);",
+            down_sql: Some("DROP TABLE IF EXISTS assets;"),
+        },
+        Migration {
+            name: "005_ticks",
+            sql: "CREATE TABLE IF NOT EXISTS ticks (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      source_id TEXT NOT NULL,
+                      symbol TEXT,
+                      timestamp INTEGER NOT NULL,
+                      metrics TEXT NOT NULL,
+                      metadata TEXT NOT NULL DEFAULT '{}'
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_ticks_symbol_timestamp ON ticks(symbol, timestamp);
+                  CREATE INDEX IF NOT EXISTS idx_ticks_timestamp ON ticks(timestamp);",
+            down_sql: Some("DROP TABLE IF EXISTS ticks;"),
+        },
+        Migration {
+            name: "006_config_history",
+            sql: "CREATE TABLE IF NOT EXISTS config_history (
+                      version INTEGER PRIMARY KEY AUTOINCREMENT,
+                      key TEXT NOT NULL,
+                      value TEXT NOT NULL,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_config_history_key ON config_history(key);",
+            down_sql: Some("DROP TABLE IF EXISTS config_history;"),
+        },
+        Migration {
+            name: "007_watch_paths",
+            sql: "CREATE TABLE IF NOT EXISTS watch_paths (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      dir TEXT NOT NULL,
+                      pattern TEXT NOT NULL,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      UNIQUE(dir, pattern)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS watch_paths;"),
+        },
+        Migration {
+            name: "008_credential_metadata",
+            sql: "CREATE TABLE IF NOT EXISTS credential_metadata (
+                      provider TEXT NOT NULL,
+                      mode TEXT NOT NULL,
+                      profile TEXT NOT NULL,
+                      fingerprint TEXT NOT NULL,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      last_validated_at TEXT,
+                      last_used_at TEXT,
+                      PRIMARY KEY (provider, mode, profile)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS credential_metadata;"),
+        },
+        Migration {
+            name: "009_assets_symbol_search",
+            sql: "CREATE INDEX IF NOT EXISTS idx_assets_symbol_nocase ON assets(symbol COLLATE NOCASE);",
+            down_sql: Some("DROP INDEX IF EXISTS idx_assets_symbol_nocase;"),
+        },
+        Migration {
+            // Caches from before crypto assets were fetched are refreshed on next use
+            name: "010_assets_refetch_with_crypto",
+            sql: "UPDATE assets SET fetched_at = '1970-01-01 00:00:00';",
+            down_sql: Some("SELECT 1;"),
+        },
+        Migration {
+            name: "011_asset_details",
+            sql: "CREATE TABLE IF NOT EXISTS asset_details (
+                      symbol TEXT PRIMARY KEY,
+                      name TEXT NOT NULL DEFAULT '',
+                      exchange TEXT NOT NULL DEFAULT '',
+                      asset_class TEXT NOT NULL DEFAULT 'us_equity',
+                      status TEXT NOT NULL DEFAULT 'active',
+                      tradable INTEGER NOT NULL DEFAULT 0,
+                      shortable INTEGER NOT NULL DEFAULT 0,
+                      marginable INTEGER NOT NULL DEFAULT 0,
+                      easy_to_borrow INTEGER NOT NULL DEFAULT 0,
+                      fractionable INTEGER NOT NULL DEFAULT 0,
+                      min_order_size TEXT,
+                      min_trade_increment TEXT,
+                      price_increment TEXT,
+                      fetched_at TEXT NOT NULL DEFAULT (datetime('now'))
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS asset_details;"),
+        },
+        Migration {
+            name: "012_backtest_mode",
+            sql: "ALTER TABLE backtests ADD COLUMN mode TEXT NOT NULL DEFAULT 'paper';",
+            down_sql: Some("ALTER TABLE backtests DROP COLUMN mode;"),
+        },
+        Migration {
+            name: "013_agent_activity",
+            sql: "CREATE TABLE IF NOT EXISTS agent_activity (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      type TEXT NOT NULL,
+                      message TEXT NOT NULL,
+                      timestamp INTEGER NOT NULL,
+                      data TEXT
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_agent_activity_timestamp ON agent_activity(timestamp);",
+            down_sql: Some("DROP TABLE IF EXISTS agent_activity;"),
+        },
+        Migration {
+            name: "014_sources",
+            sql: "CREATE TABLE IF NOT EXISTS sources (
+                      id TEXT PRIMARY KEY,
+                      name TEXT NOT NULL,
+                      type TEXT NOT NULL,
+                      plugin TEXT NOT NULL,
+                      config TEXT NOT NULL DEFAULT '{}',
+                      poll_interval_ms INTEGER,
+                      enabled INTEGER NOT NULL DEFAULT 1,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS sources;"),
+        },
+        Migration {
+            name: "015_news_items",
+            sql: "CREATE TABLE IF NOT EXISTS news_items (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      feed TEXT NOT NULL,
+                      guid TEXT NOT NULL,
+                      title TEXT NOT NULL,
+                      link TEXT,
+                      published INTEGER NOT NULL,
+                      symbols TEXT NOT NULL DEFAULT '[]',
+                      keywords TEXT NOT NULL DEFAULT '[]',
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      UNIQUE(feed, guid)
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_news_items_published ON news_items(published);",
+            down_sql: Some("DROP TABLE IF EXISTS news_items;"),
+        },
+        Migration {
+            name: "016_alerts",
+            sql: "CREATE TABLE IF NOT EXISTS alert_rules (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      symbol TEXT NOT NULL,
+                      condition TEXT NOT NULL,
+                      threshold REAL NOT NULL,
+                      cooldown_secs INTEGER NOT NULL DEFAULT 300,
+                      enabled INTEGER NOT NULL DEFAULT 1,
+                      last_triggered INTEGER,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                  );
+                  CREATE TABLE IF NOT EXISTS alert_triggers (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      rule_id INTEGER NOT NULL,
+                      symbol TEXT NOT NULL,
+                      condition TEXT NOT NULL,
+                      threshold REAL NOT NULL,
+                      price REAL NOT NULL,
+                      triggered_at INTEGER NOT NULL,
+                      message TEXT NOT NULL
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_alert_triggers_rule ON alert_triggers(rule_id, triggered_at);",
+            down_sql: Some("DROP TABLE IF EXISTS alert_triggers; DROP TABLE IF EXISTS alert_rules;"),
+        },
+        Migration {
+            name: "017_anomaly_notifications",
+            sql: "CREATE TABLE IF NOT EXISTS anomaly_notifications (
+                      anomaly_id TEXT PRIMARY KEY,
+                      delivered_at INTEGER NOT NULL
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS anomaly_notifications;"),
+        },
+        Migration {
+            name: "018_portfolio",
+            sql: "CREATE TABLE IF NOT EXISTS portfolio_accounts (
+                      mode TEXT PRIMARY KEY,
+                      equity REAL NOT NULL,
+                      cash REAL NOT NULL,
+                      buying_power REAL NOT NULL,
+                      last_equity REAL NOT NULL,
+                      currency TEXT NOT NULL,
+                      fetched_at INTEGER NOT NULL
+                  );
+                  CREATE TABLE IF NOT EXISTS portfolio_positions (
+                      mode TEXT NOT NULL,
+                      symbol TEXT NOT NULL,
+                      qty REAL NOT NULL,
+                      avg_entry_price REAL NOT NULL,
+                      current_price REAL NOT NULL,
+                      market_value REAL NOT NULL,
+                      cost_basis REAL NOT NULL,
+                      unrealized_pl REAL NOT NULL,
+                      unrealized_plpc REAL NOT NULL,
+                      PRIMARY KEY (mode, symbol)
+                  );
+                  CREATE TABLE IF NOT EXISTS orders (
+                      id TEXT PRIMARY KEY,
+                      mode TEXT NOT NULL,
+                      client_order_id TEXT NOT NULL,
+                      symbol TEXT NOT NULL,
+                      side TEXT NOT NULL,
+                      type TEXT NOT NULL,
+                      time_in_force TEXT NOT NULL,
+                      qty REAL,
+                      notional REAL,
+                      filled_qty REAL NOT NULL DEFAULT 0,
+                      filled_avg_price REAL,
+                      limit_price REAL,
+                      stop_price REAL,
+                      status TEXT NOT NULL,
+                      submitted_at TEXT,
+                      updated_at TEXT
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_orders_mode_submitted ON orders(mode, submitted_at);",
+            down_sql: Some(
+                "DROP TABLE IF EXISTS orders;
+                 DROP TABLE IF EXISTS portfolio_positions;
+                 DROP TABLE IF EXISTS portfolio_accounts;",
+            ),
+        },
+        Migration {
+            name: "019_market_calendar",
+            sql: "CREATE TABLE IF NOT EXISTS market_calendar (
+                      date TEXT PRIMARY KEY,
+                      open_at INTEGER NOT NULL,
+                      close_at INTEGER NOT NULL,
+                      early_close INTEGER NOT NULL DEFAULT 0
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS market_calendar;"),
+        },
+        Migration {
+            name: "020_portfolio_snapshots",
+            sql: "CREATE TABLE IF NOT EXISTS portfolio_snapshots (
+                      mode TEXT NOT NULL,
+                      date TEXT NOT NULL,
+                      equity REAL NOT NULL,
+                      cash REAL NOT NULL,
+                      last_equity REAL NOT NULL,
+                      positions TEXT NOT NULL,
+                      recorded_at INTEGER NOT NULL,
+                      PRIMARY KEY (mode, date)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS portfolio_snapshots;"),
+        },
+        Migration {
+            name: "021_bars",
+            sql: "CREATE TABLE IF NOT EXISTS bars (
+                      symbol TEXT NOT NULL,
+                      timeframe TEXT NOT NULL,
+                      timestamp INTEGER NOT NULL,
+                      open REAL NOT NULL,
+                      high REAL NOT NULL,
+                      low REAL NOT NULL,
+                      close REAL NOT NULL,
+                      volume REAL NOT NULL,
+                      source TEXT NOT NULL,
+                      PRIMARY KEY (symbol, timeframe, timestamp)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS bars;"),
+        },
+        Migration {
+            name: "022_task_runs",
+            sql: "CREATE TABLE IF NOT EXISTS task_runs (
+                      id TEXT PRIMARY KEY,
+                      last_run_at INTEGER,
+                      next_run_at INTEGER NOT NULL,
+                      last_status TEXT NOT NULL,
+                      last_error TEXT,
+                      last_duration_ms INTEGER
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS task_runs;"),
+        },
+        Migration {
+            name: "023_offline_queue",
+            sql: "CREATE TABLE IF NOT EXISTS offline_queue (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      op TEXT NOT NULL,
+                      queued_at INTEGER NOT NULL,
+                      attempts INTEGER NOT NULL DEFAULT 0,
+                      last_error TEXT
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS offline_queue;"),
+        },
+        Migration {
+            name: "024_anomaly_status",
+            sql: "ALTER TABLE anomalies ADD COLUMN status TEXT NOT NULL DEFAULT 'new'
+                      CHECK(status IN ('new','acknowledged','snoozed','resolved'));
+                  ALTER TABLE anomalies ADD COLUMN snoozed_until INTEGER;
+                  CREATE INDEX IF NOT EXISTS idx_anomalies_status ON anomalies(status);",
+            down_sql: Some(
+                "DROP INDEX IF EXISTS idx_anomalies_status;
+                 ALTER TABLE anomalies DROP COLUMN snoozed_until;
+                 ALTER TABLE anomalies DROP COLUMN status;",
+            ),
+        },
+        Migration {
+            name: "025_anomaly_notes",
+            sql: "CREATE TABLE IF NOT EXISTS anomaly_notes (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      anomaly_id TEXT NOT NULL REFERENCES anomalies(id) ON DELETE CASCADE,
+                      body TEXT NOT NULL,
+                      created_at INTEGER NOT NULL,
+                      updated_at INTEGER
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_anomaly_notes_anomaly ON anomaly_notes(anomaly_id, created_at);",
+            down_sql: Some("DROP TABLE IF EXISTS anomaly_notes;"),
+        },
+        Migration {
+            name: "026_anomaly_severity_override",
+            sql: "ALTER TABLE anomalies ADD COLUMN override_severity TEXT
+                      CHECK(override_severity IN ('low','medium','high','critical'));",
+            down_sql: Some("ALTER TABLE anomalies DROP COLUMN override_severity;"),
+        },
+        Migration {
+            name: "027_incidents",
+            sql: "CREATE TABLE IF NOT EXISTS incidents (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      symbol TEXT NOT NULL,
+                      started_at INTEGER NOT NULL,
+                      ended_at INTEGER NOT NULL
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_incidents_symbol ON incidents(symbol, ended_at);
+                  ALTER TABLE anomalies ADD COLUMN incident_id INTEGER REFERENCES incidents(id) ON DELETE SET NULL;
+                  CREATE INDEX IF NOT EXISTS idx_anomalies_incident ON anomalies(incident_id);",
+            down_sql: Some(
+                "DROP INDEX IF EXISTS idx_anomalies_incident;
+                 ALTER TABLE anomalies DROP COLUMN incident_id;
+                 DROP TABLE IF EXISTS incidents;",
+            ),
+        },
+        Migration {
+            name: "028_provider_health",
+            sql: "CREATE TABLE IF NOT EXISTS provider_health (
+                      provider_id TEXT PRIMARY KEY,
+                      status TEXT NOT NULL,
+                      latency_ms INTEGER NOT NULL DEFAULT 0,
+                      last_success INTEGER,
+                      last_error TEXT,
+                      cooldown_until INTEGER,
+                      updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+                  );
+                  CREATE TABLE IF NOT EXISTS provider_health_history (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      provider_id TEXT NOT NULL,
+                      status TEXT NOT NULL,
+                      latency_ms INTEGER NOT NULL DEFAULT 0,
+                      last_error TEXT,
+                      recorded_at INTEGER NOT NULL
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_provider_health_history ON provider_health_history(provider_id, id);",
+            down_sql: Some("DROP TABLE IF EXISTS provider_health_history; DROP TABLE IF EXISTS provider_health;"),
+        },
+        Migration {
+            name: "029_llm_usage",
+            sql: "CREATE TABLE IF NOT EXISTS llm_usage (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      provider_id TEXT NOT NULL,
+                      model TEXT NOT NULL,
+                      input_tokens INTEGER NOT NULL,
+                      output_tokens INTEGER NOT NULL,
+                      latency_ms INTEGER NOT NULL,
+                      timestamp INTEGER NOT NULL,
+                      session_id TEXT,
+                      cycle_id TEXT,
+                      cost_usd REAL
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_llm_usage_timestamp ON llm_usage(timestamp);",
+            down_sql: Some("DROP TABLE IF EXISTS llm_usage;"),
+        },
+        Migration {
+            name: "030_llm_models",
+            sql: "CREATE TABLE IF NOT EXISTS llm_models (
+                      provider_id TEXT NOT NULL,
+                      id TEXT NOT NULL,
+                      name TEXT NOT NULL,
+                      position INTEGER NOT NULL,
+                      context_window INTEGER,
+                      input_per_mtok REAL,
+                      output_per_mtok REAL,
+                      fetched_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      PRIMARY KEY (provider_id, id)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS llm_models;"),
+        },
+        Migration {
+            name: "031_prompts",
+            sql: "CREATE TABLE IF NOT EXISTS prompts (
+                      name TEXT NOT NULL,
+                      version INTEGER NOT NULL,
+                      body TEXT NOT NULL,
+                      reverted_from INTEGER,
+                      created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                      PRIMARY KEY (name, version)
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS prompts;"),
+        },
+        Migration {
+            name: "032_memory_compactions",
+            sql: "CREATE TABLE IF NOT EXISTS memory_compactions (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      started_at INTEGER NOT NULL,
+                      finished_at INTEGER NOT NULL,
+                      threshold REAL NOT NULL,
+                      summarize INTEGER NOT NULL,
+                      entries_before INTEGER,
+                      entries_after INTEGER,
+                      clusters INTEGER,
+                      summarized INTEGER,
+                      error TEXT
+                  );",
+            down_sql: Some("DROP TABLE IF EXISTS memory_compactions;"),
+        },
+        Migration {
+            name: "033_backtest_trade_context",
+            sql: "ALTER TABLE backtest_trades ADD COLUMN trade_context TEXT;",
+            down_sql: Some("ALTER TABLE backtest_trades DROP COLUMN trade_context;"),
+        },
+        Migration {
+            name: "034_journal_trades",
+            sql: "CREATE TABLE IF NOT EXISTS journal_trades (
+                      id INTEGER PRIMARY KEY AUTOINCREMENT,
+                      symbol TEXT NOT NULL,
+                      side TEXT NOT NULL CHECK(side IN ('long','short')),
+                      qty REAL NOT NULL,
+                      entry_price REAL NOT NULL,
+                      exit_price REAL,
+                      fees REAL NOT NULL DEFAULT 0,
+                      opened_at INTEGER NOT NULL,
+                      closed_at INTEGER,
+                      anomaly_id TEXT REFERENCES anomalies(id) ON DELETE SET NULL,
+                      notes TEXT NOT NULL DEFAULT '',
+                      tags TEXT NOT NULL DEFAULT '[]',
+                      created_at INTEGER NOT NULL,
+                      updated_at INTEGER
+                  );
+                  CREATE INDEX IF NOT EXISTS idx_journal_trades_opened ON journal_trades(opened_at);
+                  CREATE INDEX IF NOT EXISTS idx_journal_trades_symbol ON journal_trades(symbol);
+                  CREATE INDEX IF NOT EXISTS idx_journal_trades_anomaly ON journal_trades(anomaly_id);",
+            down_sql: Some("DROP TABLE IF EXISTS journal_trades;"),
+        },
+        Migration {
+            // Fingerprints of these providers were taken from the API key itself
+            name: "035_redact_secret_fingerprints",
+            sql: "UPDATE credential_metadata SET fingerprint = '••••' WHERE provider IN ('polygon', 'finnhub');",
+            down_sql: Some("SELECT 1;"),
         },
     ]
 }
@@ -106,6 +565,78 @@ pub fn applied(pool: &DbPool) -> Result<Vec<String>, Box<dyn std::error::Error>>
     Ok(names)
 }
 
+/// Roll back every applied migration after `to`, newest first, keeping `to`
+/// itself. Returns the names rolled back. Fails without changing anything if
+/// one of them has no `down_sql`.
+pub fn rollback(pool: &DbPool, to: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    rollback_migrations(pool, &all_migrations(), to)
+}
+
+fn rollback_migrations(
+    pool: &DbPool,
+    all: &[Migration],
+    to: &str,
+) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let target = all
+        .iter()
+        .position(|m| m.name == to)
+        .ok_or_else(|| format!("Unknown migration '{}'", to))?;
+    let applied_names = applied(pool)?;
+    let to_undo: Vec<&Migration> = all[target + 1..]
+        .iter()
+        .rev()
+        .filter(|m| applied_names.iter().any(|a| a == m.name))
+        .collect();
+    if let Some(m) = to_undo.iter().find(|m| m.down_sql.is_none()) {
+        return Err(format!("Migration '{}' is not reversible", m.name).into());
+    }
+
+    let mut conn = pool.get()?;
+    let tx = conn.transaction()?;
+    let mut rolled_back = Vec::new();
+    for migration in to_undo {
+        tx.execute_batch(migration.down_sql.unwrap_or_default())?;
+        tx.execute("DELETE FROM migrations WHERE name = ?1", [migration.name])?;
+        rolled_back.push(migration.name.to_string());
+    }
+    tx.commit()?;
+    Ok(rolled_back)
+}
+
+/// Every known migration in order, plus applied migrations no longer in the
+/// binary (e.g. after running a newer build against this database).
+pub fn status(pool: &DbPool) -> Result<Vec<MigrationStatus>, Box<dyn std::error::Error>> {
+    let conn = pool.get()?;
+    let mut applied_at: Vec<(String, String)> = conn
+        .prepare("SELECT name, applied_at FROM migrations ORDER BY id")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(|r| r.ok())
+        .collect();
+
+    let mut statuses: Vec<MigrationStatus> = all_migrations()
+        .into_iter()
+        .map(|m| {
+            let at = applied_at
+                .iter()
+                .position(|(name, _)| name == m.name)
+                .map(|i| applied_at.remove(i).1);
+            MigrationStatus {
+                name: m.name.to_string(),
+                applied: at.is_some(),
+                applied_at: at,
+                reversible: m.down_sql.is_some(),
+            }
+        })
+        .collect();
+    statuses.extend(applied_at.into_iter().map(|(name, at)| MigrationStatus {
+        name,
+        applied: true,
+        applied_at: Some(at),
+        reversible: false,
+    }));
+    Ok(statuses)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,4 +684,69 @@ mod tests {
         conn.execute_batch("SELECT symbol, name, exchange, asset_class, status, fetched_at FROM assets LIMIT 0")
             .expect("assets table should exist with expected columns");
     }
+
+    #[test]
+    fn rollback_reverts_later_migrations() {
+        let pool = test_pool();
+        run_pending(&pool).unwrap();
+        let rolled_back = rollback(&pool, "002_source_health_table").unwrap();
+        let later: Vec<&str> = all_migrations()[2..].iter().map(|m| m.name).collect();
+        let mut expected = later.clone();
+        expected.reverse();
+        assert_eq!(rolled_back, expected);
+        assert_eq!(applied(&pool).unwrap().last().unwrap(), "002_source_health_table");
+        let conn = pool.get().unwrap();
+        assert!(conn.execute_batch("SELECT 1 FROM assets LIMIT 0").is_err());
+        assert!(conn.execute_batch("SELECT 1 FROM source_health LIMIT 0").is_ok());
+        drop(conn);
+
+        // Rolled-back migrations are re-applied on the next run
+        let reapplied = run_pending(&pool).unwrap();
+        assert_eq!(reapplied, later);
+    }
+
+    #[test]
+    fn rollback_to_unknown_migration_fails() {
+        let pool = test_pool();
+        run_pending(&pool).unwrap();
+        assert!(rollback(&pool, "000_missing").is_err());
+        assert_eq!(applied(&pool).unwrap().len(), all_migrations().len());
+    }
+
+    #[test]
+    fn rollback_refuses_irreversible_migrations() {
+        let pool = test_pool();
+        run_pending(&pool).unwrap();
+        let mut all = all_migrations();
+        all[3].down_sql = None;
+        assert!(rollback_migrations(&pool, &all, "002_source_health_table").is_err());
+        // Nothing was undone, including the reversible 003
+        assert_eq!(applied(&pool).unwrap().len(), all_migrations().len());
+    }
+
+    #[test]
+    fn status_lists_applied_and_pending() {
+        let pool = test_pool();
+        run_pending(&pool).unwrap();
+        rollback(&pool, "003_backtest_tables").unwrap();
+        let status = status(&pool).unwrap();
+        assert_eq!(status.len(), all_migrations().len());
+        assert!(status[0].applied && !status[0].reversible);
+        assert!(status[2].applied && status[2].applied_at.is_some());
+        assert!(!status[3].applied && status[3].reversible);
+    }
+
+    #[test]
+    fn status_reports_unknown_applied_migrations() {
+        let pool = test_pool();
+        run_pending(&pool).unwrap();
+        pool.get()
+            .unwrap()
+            .execute("INSERT INTO migrations (name) VALUES ('999_from_newer_build')", [])
+            .unwrap();
+        let status = status(&pool).unwrap();
+        let last = status.last().unwrap();
+        assert_eq!(last.name, "999_from_newer_build");
+        assert!(last.applied && !last.reversible);
+    }
 }
@@ -0,0 +1,408 @@
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampMillisecondArray};
+use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+
+use crate::db::DbPool;
+
+/// OHLCV fields of a cached bar, in column order.
+const BAR_FIELDS: &[&str] = &["open", "high", "low", "close", "volume"];
+
+/// What `export_parquet` can write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportDataset {
+    Anomalies,
+    BacktestTrades,
+    EquityCurve,
+    Bars,
+}
+
+/// Narrows the exported rows; unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExportFilter {
+    /// Backtest trades and equity curves of one backtest.
+    pub backtest_id: Option<String>,
+    pub symbol: Option<String>,
+    /// Bars of one timeframe, e.g. `1Day`.
+    pub timeframe: Option<String>,
+    /// Epoch millis, inclusive.
+    pub since: Option<i64>,
+    /// Epoch millis, inclusive.
+    pub until: Option<i64>,
+}
+
+impl ExportFilter {
+    fn covers(&self, timestamp: i64) -> bool {
+        self.since.is_none_or(|s| timestamp >= s) && self.until.is_none_or(|u| timestamp <= u)
+    }
+
+    fn matches_symbol(&self, symbol: Option<&str>) -> bool {
+        self.symbol
+            .as_deref()
+            .is_none_or(|want| symbol.is_some_and(|s| s.eq_ignore_ascii_case(want)))
+    }
+}
+
+/// What an export wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportSummary {
+    pub dataset: ExportDataset,
+    pub path: String,
+    pub rows: usize,
+}
+
+fn timestamp_field(name: &str) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false)
+}
+
+fn timestamps(values: Vec<i64>) -> ArrayRef {
+    Arc::new(TimestampMillisecondArray::from(values).with_timezone("UTC"))
+}
+
+fn strings(values: Vec<Option<String>>) -> ArrayRef {
+    Arc::new(StringArray::from(values))
+}
+
+fn floats(values: Vec<Option<f64>>) -> ArrayRef {
+    Arc::new(Float64Array::from(values))
+}
+
+fn batch(fields: Vec<Field>, columns: Vec<ArrayRef>) -> Result<RecordBatch, String> {
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(|e| e.to_string())
+}
+
+fn anomalies_batch(pool: &DbPool, filter: &ExportFilter) -> Result<RecordBatch, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, COALESCE(override_severity, severity), source, symbol, timestamp, description,
+                    pre_screen_score, session_id, metrics,
+                    CASE WHEN override_severity IS NULL THEN NULL ELSE severity END
+             FROM anomalies ORDER BY timestamp",
+        )
+        .map_err(|e| e.to_string())?;
+    type Row = (String, String, String, Option<String>, i64, String, f64, String, String, Option<String>);
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+                row.get(8)?,
+                row.get(9)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Row>, _>>()
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<Row> = rows
+        .into_iter()
+        .filter(|r| filter.covers(r.4) && filter.matches_symbol(r.3.as_deref()))
+        .collect();
+    batch(
+        vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("severity", DataType::Utf8, false),
+            Field::new("source", DataType::Utf8, false),
+            Field::new("symbol", DataType::Utf8, true),
+            timestamp_field("timestamp"),
+            Field::new("description", DataType::Utf8, false),
+            Field::new("pre_screen_score", DataType::Float64, false),
+            Field::new("session_id", DataType::Utf8, false),
+            Field::new("metrics", DataType::Utf8, false),
+            Field::new("original_severity", DataType::Utf8, true),
+        ],
+        vec![
+            strings(rows.iter().map(|r| Some(r.0.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.1.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.2.clone())).collect()),
+            strings(rows.iter().map(|r| r.3.clone()).collect()),
+            timestamps(rows.iter().map(|r| r.4).collect()),
+            strings(rows.iter().map(|r| Some(r.5.clone())).collect()),
+            floats(rows.iter().map(|r| Some(r.6)).collect()),
+            strings(rows.iter().map(|r| Some(r.7.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.8.clone())).collect()),
+            strings(rows.iter().map(|r| r.9.clone()).collect()),
+        ],
+    )
+}
+
+fn backtest_trades_batch(pool: &DbPool, filter: &ExportFilter) -> Result<RecordBatch, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT backtest_id, id, symbol, side, qty, fill_price, timestamp, anomaly_id, rationale, realized_pnl
+             FROM backtest_trades WHERE ?1 IS NULL OR backtest_id = ?1
+             ORDER BY backtest_id, timestamp",
+        )
+        .map_err(|e| e.to_string())?;
+    type Row = (String, String, String, String, f64, f64, i64, Option<String>, Option<String>, Option<f64>);
+    let rows = stmt
+        .query_map([&filter.backtest_id], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+                row.get(6)?,
+                row.get(7)?,
+                row.get(8)?,
+                row.get(9)?,
+            ))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Row>, _>>()
+        .map_err(|e| e.to_string())?;
+    let rows: Vec<Row> = rows
+        .into_iter()
+        .filter(|r| filter.covers(r.6) && filter.matches_symbol(Some(&r.2)))
+        .collect();
+    batch(
+        vec![
+            Field::new("backtest_id", DataType::Utf8, false),
+            Field::new("id", DataType::Utf8, false),
+            Field::new("symbol", DataType::Utf8, false),
+            Field::new("side", DataType::Utf8, false),
+            Field::new("qty", DataType::Float64, false),
+            Field::new("fill_price", DataType::Float64, false),
+            timestamp_field("timestamp"),
+            Field::new("anomaly_id", DataType::Utf8, true),
+            Field::new("rationale", DataType::Utf8, true),
+            Field::new("realized_pnl", DataType::Float64, true),
+        ],
+        vec![
+            strings(rows.iter().map(|r| Some(r.0.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.1.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.2.clone())).collect()),
+            strings(rows.iter().map(|r| Some(r.3.clone())).collect()),
+            floats(rows.iter().map(|r| Some(r.4)).collect()),
+            floats(rows.iter().map(|r| Some(r.5)).collect()),
+            timestamps(rows.iter().map(|r| r.6).collect()),
+            strings(rows.iter().map(|r| r.7.clone()).collect()),
+            strings(rows.iter().map(|r| r.8.clone()).collect()),
+            floats(rows.iter().map(|r| r.9).collect()),
+        ],
+    )
+}
+
+/// Points of the equity curves kept in completed backtests' metrics.
+fn equity_curve_batch(pool: &DbPool, filter: &ExportFilter) -> Result<RecordBatch, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, metrics FROM backtests
+             WHERE metrics IS NOT NULL AND (?1 IS NULL OR id = ?1)
+             ORDER BY created_at",
+        )
+        .map_err(|e| e.to_string())?;
+    let backtests = stmt
+        .query_map([&filter.backtest_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| e.to_string())?;
+    let (mut ids, mut dates, mut values) = (Vec::new(), Vec::new(), Vec::new());
+    for (id, metrics) in backtests {
+        let metrics: serde_json::Value = serde_json::from_str(&metrics).unwrap_or_default();
+        let points = metrics.get("equityCurve").and_then(|c| c.as_array()).cloned().unwrap_or_default();
+        for point in points {
+            let (Some(date), Some(value)) = (
+                point.get("date").and_then(|d| d.as_str()),
+                point.get("value").and_then(|v| v.as_f64()),
+            ) else {
+                continue;
+            };
+            ids.push(Some(id.clone()));
+            dates.push(Some(date.to_string()));
+            values.push(Some(value));
+        }
+    }
+    batch(
+        vec![
+            Field::new("backtest_id", DataType::Utf8, false),
+            Field::new("date", DataType::Utf8, false),
+            Field::new("value", DataType::Float64, false),
+        ],
+        vec![strings(ids), strings(dates), floats(values)],
+    )
+}
+
+/// Bars from the bars cache.
+fn bars_batch(pool: &DbPool, filter: &ExportFilter) -> Result<RecordBatch, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT symbol, timeframe, timestamp, open, high, low, close, volume, source FROM bars
+             WHERE (?1 IS NULL OR symbol = ?1) AND (?2 IS NULL OR timeframe = ?2)
+               AND timestamp >= ?3 AND timestamp <= ?4
+             ORDER BY symbol, timeframe, timestamp",
+        )
+        .map_err(|e| e.to_string())?;
+    let symbol = filter.symbol.as_ref().map(|s| s.to_uppercase());
+    type Row = (String, String, i64, [f64; 5], String);
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                symbol,
+                filter.timeframe,
+                filter.since.unwrap_or(0),
+                filter.until.unwrap_or(i64::MAX)
+            ],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    [row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?],
+                    row.get(8)?,
+                ))
+            },
+        )
+        .map_err(|e| e.to_string())?
+        .collect::<Result<Vec<Row>, _>>()
+        .map_err(|e| e.to_string())?;
+    let mut fields = vec![
+        Field::new("symbol", DataType::Utf8, false),
+        Field::new("timeframe", DataType::Utf8, false),
+        timestamp_field("timestamp"),
+    ];
+    let mut columns = vec![
+        strings(rows.iter().map(|r| Some(r.0.clone())).collect()),
+        strings(rows.iter().map(|r| Some(r.1.clone())).collect()),
+        timestamps(rows.iter().map(|r| r.2).collect()),
+    ];
+    for (i, name) in BAR_FIELDS.iter().enumerate() {
+        fields.push(Field::new(*name, DataType::Float64, false));
+        columns.push(floats(rows.iter().map(|r| Some(r.3[i])).collect()));
+    }
+    fields.push(Field::new("source", DataType::Utf8, false));
+    columns.push(strings(rows.iter().map(|r| Some(r.4.clone())).collect()));
+    batch(fields, columns)
+}
+
+/// Write `dataset`, narrowed by `filter`, to a Snappy-compressed Parquet file at `dest`.
+pub fn export(pool: &DbPool, dataset: ExportDataset, filter: &ExportFilter, dest: &Path) -> Result<ExportSummary, String> {
+    let batch = match dataset {
+        ExportDataset::Anomalies => anomalies_batch(pool, filter)?,
+        ExportDataset::BacktestTrades => backtest_trades_batch(pool, filter)?,
+        ExportDataset::EquityCurve => equity_curve_batch(pool, filter)?,
+        ExportDataset::Bars => bars_batch(pool, filter)?,
+    };
+    let file = File::create(dest).map_err(|e| format!("Failed to create {}: {}", dest.display(), e))?;
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props)).map_err(|e| e.to_string())?;
+    writer.write(&batch).map_err(|e| e.to_string())?;
+    writer.close().map_err(|e| e.to_string())?;
+    Ok(ExportSummary {
+        dataset,
+        path: dest.display().to_string(),
+        rows: batch.num_rows(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::bars::{bars_insert_db, Bar};
+    use crate::db;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn read_back(path: &Path) -> RecordBatch {
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<RecordBatch> = reader.map(|b| b.unwrap()).collect();
+        arrow::compute::concat_batches(&batches[0].schema(), &batches).unwrap()
+    }
+
+    #[test]
+    fn bars_export_typed_ohlcv_columns() {
+        let (dir, pool) = test_pool();
+        let bar = |timestamp, close| Bar { timestamp, open: 1.0, high: 2.0, low: 0.5, close, volume: 100.0 };
+        bars_insert_db(&pool, "AAPL", "1Day", "csv:aapl.csv", &[bar(1_000, 1.5), bar(2_000, 1.6)]).unwrap();
+        bars_insert_db(&pool, "AAPL", "1Hour", "csv:aapl.csv", &[bar(1_000, 1.5)]).unwrap();
+        bars_insert_db(&pool, "MSFT", "1Day", "csv:msft.csv", &[bar(1_000, 1.5)]).unwrap();
+
+        let path = dir.path().join("bars.parquet");
+        let filter = ExportFilter {
+            symbol: Some("aapl".to_string()),
+            timeframe: Some("1Day".to_string()),
+            ..Default::default()
+        };
+        let summary = export(&pool, ExportDataset::Bars, &filter, &path).unwrap();
+        assert_eq!(summary.rows, 2);
+
+        let batch = read_back(&path);
+        assert_eq!(batch.num_rows(), 2);
+        let close = batch.column_by_name("close").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(close.value(1), 1.6);
+        assert!(matches!(
+            batch.schema().field_with_name("timestamp").unwrap().data_type(),
+            DataType::Timestamp(TimeUnit::Millisecond, _)
+        ));
+    }
+
+    #[test]
+    fn equity_curves_come_from_backtest_metrics() {
+        let (dir, pool) = test_pool();
+        let conn = pool.get().unwrap();
+        let metrics = serde_json::json!({
+            "equityCurve": [{"date": "2025-01-02", "value": 100000.0}, {"date": "2025-01-03", "value": 100250.5}]
+        });
+        conn.execute(
+            "INSERT INTO backtests (id, status, config, metrics, created_at) VALUES ('bt-1', 'completed', '{}', ?1, 0)",
+            [metrics.to_string()],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO backtests (id, status, config, created_at) VALUES ('bt-2', 'running', '{}', 1)",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+
+        let path = dir.path().join("equity.parquet");
+        let summary = export(&pool, ExportDataset::EquityCurve, &ExportFilter::default(), &path).unwrap();
+        assert_eq!(summary.rows, 2);
+        let batch = read_back(&path);
+        let value = batch.column_by_name("value").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(value.value(1), 100250.5);
+    }
+
+    #[test]
+    fn empty_datasets_still_write_a_schema() {
+        let (dir, pool) = test_pool();
+        let path = dir.path().join("anomalies.parquet");
+        let summary = export(&pool, ExportDataset::Anomalies, &ExportFilter::default(), &path).unwrap();
+        assert_eq!(summary.rows, 0);
+        let reader = ParquetRecordBatchReaderBuilder::try_new(File::open(&path).unwrap()).unwrap();
+        assert!(reader.schema().field_with_name("severity").is_ok());
+    }
+}
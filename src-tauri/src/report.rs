@@ -0,0 +1,527 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::anomalies::anomalies_list_db;
+use crate::commands::backtest::{backtest_get_db, backtest_get_trades_db};
+use crate::db::DbPool;
+use crate::types::anomaly::{Anomaly, AnomalyFilter};
+use crate::types::backtest::{BacktestSummary, BacktestTrade};
+
+const CHART_WIDTH: f64 = 760.0;
+const CHART_HEIGHT: f64 = 220.0;
+const CHART_PAD: f64 = 40.0;
+
+/// Metrics shown first, in this order; any other numeric metric follows.
+const METRIC_ORDER: &[&str] = &[
+    "totalReturn",
+    "totalReturnPct",
+    "sharpeRatio",
+    "sortinoRatio",
+    "maxDrawdownPct",
+    "maxDrawdownDuration",
+    "recoveryFactor",
+    "winRate",
+    "totalTrades",
+    "profitFactor",
+    "avgWinLossRatio",
+    "maxConsecutiveWins",
+    "maxConsecutiveLosses",
+    "largestWin",
+    "largestLoss",
+    "avgTradeDuration",
+];
+
+const STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;margin:32px auto;max-width:860px;color:#1f2933}\
+h1{font-size:22px}h2{font-size:17px;margin-top:32px;border-bottom:1px solid #d9e2ec;padding-bottom:4px}\
+table{border-collapse:collapse;width:100%;font-size:13px}th,td{text-align:left;padding:4px 8px;border-bottom:1px solid #eef2f6}\
+td.num{text-align:right;font-variant-numeric:tabular-nums}.muted{color:#829ab1;font-size:12px}\
+.sev-critical{color:#c62828}.sev-high{color:#ef6c00}.sev-medium{color:#f9a825}.sev-low{color:#607d8b}";
+
+/// What `report_generate` can render.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportKind {
+    /// One backtest run: metrics, charts, trades, and the anomalies behind them.
+    Backtest,
+    /// One monitoring session: the anomalies it detected.
+    Session,
+}
+
+/// What a report generation wrote.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSummary {
+    pub kind: ReportKind,
+    pub id: String,
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// A dated point of an equity curve, as kept in backtest metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvePoint {
+    pub date: String,
+    pub value: f64,
+}
+
+/// Render the `kind` report for `id` and write it to `dest` as a single
+/// self-contained HTML file.
+pub fn generate(pool: &DbPool, kind: ReportKind, id: &str, dest: &Path) -> Result<ReportSummary, String> {
+    let every = Some(AnomalyFilter {
+        include_snoozed: Some(true),
+        ..AnomalyFilter::default()
+    });
+    let html = match kind {
+        ReportKind::Backtest => {
+            let summary = backtest_get_db(pool, id)?;
+            let trades = backtest_get_trades_db(pool, id)?;
+            let ids: HashSet<&str> = trades.iter().map(|t| t.anomaly_id.as_str()).collect();
+            let anomalies: Vec<Anomaly> = anomalies_list_db(pool, &every)?
+                .into_iter()
+                .filter(|a| ids.contains(a.id.as_str()))
+                .collect();
+            render_backtest(&summary, &trades, &anomalies)
+        }
+        ReportKind::Session => {
+            let anomalies: Vec<Anomaly> = anomalies_list_db(pool, &every)?
+                .into_iter()
+                .filter(|a| a.session_id == id)
+                .collect();
+            if anomalies.is_empty() {
+                return Err(format!("No anomalies recorded for session {}", id));
+            }
+            render_session(id, &anomalies)
+        }
+    };
+    std::fs::write(dest, &html).map_err(|e| format!("Failed to write {}: {}", dest.display(), e))?;
+    Ok(ReportSummary {
+        kind,
+        id: id.to_string(),
+        path: dest.display().to_string(),
+        bytes: html.len() as u64,
+    })
+}
+
+/// The backtest's equity curve: the one kept in its metrics if present,
+/// otherwise rebuilt from the initial capital and each trade's realized PnL.
+pub fn equity_curve(summary: &BacktestSummary, trades: &[BacktestTrade]) -> Vec<CurvePoint> {
+    let stored: Vec<CurvePoint> = summary
+        .metrics
+        .as_ref()
+        .and_then(|m| m.get("equityCurve"))
+        .and_then(|c| c.as_array())
+        .map(|points| {
+            points
+                .iter()
+                .filter_map(|p| {
+                    Some(CurvePoint {
+                        date: p.get("date")?.as_str()?.to_string(),
+                        value: p.get("value")?.as_f64()?,
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    if !stored.is_empty() {
+        return stored;
+    }
+
+    let mut equity = summary.config.get("initialCapital").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let mut points = vec![CurvePoint {
+        date: summary.config.get("startDate").and_then(|v| v.as_str()).unwrap_or("start").to_string(),
+        value: equity,
+    }];
+    for trade in trades {
+        let Some(pnl) = trade.realized_pnl else { continue };
+        equity += pnl;
+        points.push(CurvePoint { date: format_date(trade.timestamp), value: equity });
+    }
+    points
+}
+
+/// Percentage drawdown from the running peak at each point of `curve`.
+pub fn drawdown_curve(curve: &[CurvePoint]) -> Vec<CurvePoint> {
+    let mut peak = f64::MIN;
+    curve
+        .iter()
+        .map(|p| {
+            peak = peak.max(p.value);
+            let value = if peak > 0.0 { (p.value - peak) / peak * 100.0 } else { 0.0 };
+            CurvePoint { date: p.date.clone(), value }
+        })
+        .collect()
+}
+
+pub fn render_backtest(summary: &BacktestSummary, trades: &[BacktestTrade], anomalies: &[Anomaly]) -> String {
+    let mut body = String::new();
+    let symbols = summary
+        .config
+        .get("symbols")
+        .and_then(|s| s.as_array())
+        .map(|s| s.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>().join(", "))
+        .unwrap_or_default();
+    let _ = write!(
+        body,
+        "<h1>Backtest {}</h1><p class=\"muted\">{} · {} to {} · status {} · created {}</p>",
+        escape(&summary.id),
+        escape(&symbols),
+        escape(summary.config.get("startDate").and_then(|v| v.as_str()).unwrap_or("?")),
+        escape(summary.config.get("endDate").and_then(|v| v.as_str()).unwrap_or("?")),
+        escape(&summary.status),
+        format_time(summary.created_at),
+    );
+
+    body.push_str("<h2>Metrics</h2>");
+    match summary.metrics.as_ref().and_then(|m| m.as_object()) {
+        Some(metrics) => {
+            let mut keys: Vec<&str> = METRIC_ORDER.iter().copied().filter(|k| metrics.contains_key(*k)).collect();
+            keys.extend(
+                metrics
+                    .keys()
+                    .map(String::as_str)
+                    .filter(|k| !METRIC_ORDER.contains(k) && metrics[*k].is_number()),
+            );
+            body.push_str("<table>");
+            for key in keys {
+                let Some(value) = metrics[key].as_f64() else { continue };
+                let _ = write!(body, "<tr><th>{}</th><td class=\"num\">{}</td></tr>", humanize(key), format_number(value));
+            }
+            body.push_str("</table>");
+        }
+        None => body.push_str("<p class=\"muted\">No metrics recorded.</p>"),
+    }
+
+    let curve = equity_curve(summary, trades);
+    body.push_str("<h2>Equity</h2>");
+    body.push_str(&line_chart(&curve, "#1e88e5"));
+    body.push_str("<h2>Drawdown (%)</h2>");
+    body.push_str(&line_chart(&drawdown_curve(&curve), "#e53935"));
+
+    body.push_str("<h2>Anomalies</h2>");
+    body.push_str(&anomaly_summary(anomalies));
+
+    let _ = write!(body, "<h2>Trades ({})</h2>", trades.len());
+    if trades.is_empty() {
+        body.push_str("<p class=\"muted\">No trades.</p>");
+    } else {
+        body.push_str("<table><tr><th>Time</th><th>Symbol</th><th>Side</th><th>Qty</th><th>Price</th><th>Realized PnL</th><th>Rationale</th></tr>");
+        for t in trades {
+            let _ = write!(
+                body,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{}</td></tr>",
+                format_time(t.timestamp),
+                escape(&t.symbol),
+                escape(&t.side),
+                format_number(t.qty),
+                format_number(t.fill_price),
+                t.realized_pnl.map(format_number).unwrap_or_default(),
+                escape(&t.rationale),
+            );
+        }
+        body.push_str("</table>");
+    }
+
+    page(&format!("Backtest {}", summary.id), &body)
+}
+
+pub fn render_session(session_id: &str, anomalies: &[Anomaly]) -> String {
+    let mut body = String::new();
+    let first = anomalies.iter().map(|a| a.timestamp).min().unwrap_or(0);
+    let last = anomalies.iter().map(|a| a.timestamp).max().unwrap_or(0);
+    let _ = write!(
+        body,
+        "<h1>Session {}</h1><p class=\"muted\">{} to {}</p>",
+        escape(session_id),
+        format_time(first as i64),
+        format_time(last as i64),
+    );
+    body.push_str("<h2>Summary</h2>");
+    body.push_str(&anomaly_summary(anomalies));
+
+    let _ = write!(body, "<h2>Anomalies ({})</h2>", anomalies.len());
+    body.push_str(&anomaly_table(anomalies));
+    page(&format!("Session {}", session_id), &body)
+}
+
+/// Counts by severity, source, and symbol.
+fn anomaly_summary(anomalies: &[Anomaly]) -> String {
+    if anomalies.is_empty() {
+        return "<p class=\"muted\">No linked anomalies recorded.</p>".to_string();
+    }
+    let mut by_severity = BTreeMap::new();
+    let mut by_source = BTreeMap::new();
+    let mut by_symbol = BTreeMap::new();
+    for a in anomalies {
+        *by_severity.entry(std::cmp::Reverse(a.severity)).or_insert(0usize) += 1;
+        *by_source.entry(a.source.as_str()).or_insert(0usize) += 1;
+        *by_symbol.entry(a.symbol.as_deref().unwrap_or("—")).or_insert(0usize) += 1;
+    }
+    let mut out = format!("<p>{} anomalies.</p><table><tr><th>Severity</th><th>Count</th></tr>", anomalies.len());
+    for (severity, count) in by_severity {
+        let name = severity_name(severity.0);
+        let _ = write!(out, "<tr><td class=\"sev-{0}\">{0}</td><td class=\"num\">{1}</td></tr>", name, count);
+    }
+    out.push_str("</table><table><tr><th>Source</th><th>Count</th></tr>");
+    for (source, count) in by_source {
+        let _ = write!(out, "<tr><td>{}</td><td class=\"num\">{}</td></tr>", escape(source), count);
+    }
+    out.push_str("</table><table><tr><th>Symbol</th><th>Count</th></tr>");
+    for (symbol, count) in by_symbol {
+        let _ = write!(out, "<tr><td>{}</td><td class=\"num\">{}</td></tr>", escape(symbol), count);
+    }
+    out.push_str("</table>");
+    out
+}
+
+fn anomaly_table(anomalies: &[Anomaly]) -> String {
+    let mut sorted: Vec<&Anomaly> = anomalies.iter().collect();
+    sorted.sort_by_key(|a| a.timestamp);
+    let mut out = "<table><tr><th>Time</th><th>Severity</th><th>Source</th><th>Symbol</th><th>Description</th></tr>".to_string();
+    for a in sorted {
+        let _ = write!(
+            out,
+            "<tr><td>{0}</td><td class=\"sev-{1}\">{1}</td><td>{2}</td><td>{3}</td><td>{4}</td></tr>",
+            format_time(a.timestamp as i64),
+            severity_name(a.severity),
+            escape(&a.source),
+            escape(a.symbol.as_deref().unwrap_or("")),
+            escape(&a.description),
+        );
+    }
+    out.push_str("</table>");
+    out
+}
+
+/// An inline SVG line chart of `points`, labelled with the value range and
+/// the first and last dates.
+fn line_chart(points: &[CurvePoint], color: &str) -> String {
+    if points.len() < 2 {
+        return "<p class=\"muted\">Not enough data to chart.</p>".to_string();
+    }
+    let min = points.iter().map(|p| p.value).fold(f64::INFINITY, f64::min);
+    let max = points.iter().map(|p| p.value).fold(f64::NEG_INFINITY, f64::max);
+    let span = if max > min { max - min } else { 1.0 };
+    let inner_w = CHART_WIDTH - 2.0 * CHART_PAD;
+    let inner_h = CHART_HEIGHT - 2.0 * CHART_PAD;
+    let step = inner_w / (points.len() - 1) as f64;
+    let coords: Vec<String> = points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            let x = CHART_PAD + i as f64 * step;
+            let y = CHART_PAD + (max - p.value) / span * inner_h;
+            format!("{:.1},{:.1}", x, y)
+        })
+        .collect();
+    let bottom = CHART_HEIGHT - CHART_PAD;
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {w} {h}\" width=\"100%\" font-size=\"11\" fill=\"#52606d\">\
+<line x1=\"{p}\" y1=\"{p}\" x2=\"{p}\" y2=\"{bottom}\" stroke=\"#d9e2ec\"/>\
+<line x1=\"{p}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\" stroke=\"#d9e2ec\"/>\
+<text x=\"{label_x}\" y=\"{p}\" text-anchor=\"end\">{max}</text>\
+<text x=\"{label_x}\" y=\"{bottom}\" text-anchor=\"end\">{min}</text>\
+<text x=\"{p}\" y=\"{date_y}\">{first}</text>\
+<text x=\"{right}\" y=\"{date_y}\" text-anchor=\"end\">{last}</text>\
+<polyline fill=\"none\" stroke=\"{color}\" stroke-width=\"1.5\" points=\"{coords}\"/></svg>",
+        w = CHART_WIDTH,
+        h = CHART_HEIGHT,
+        p = CHART_PAD,
+        bottom = bottom,
+        right = CHART_WIDTH - CHART_PAD,
+        label_x = CHART_PAD - 4.0,
+        date_y = bottom + 16.0,
+        max = format_number(max),
+        min = format_number(min),
+        first = escape(&points[0].date),
+        last = escape(&points[points.len() - 1].date),
+        color = color,
+        coords = coords.join(" "),
+    )
+}
+
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html lang=\"en\"><head><meta charset=\"utf-8\"><title>{}</title><style>{}</style></head>\
+<body>{}<p class=\"muted\">Generated by finwatch.</p></body></html>\n",
+        escape(title),
+        STYLE,
+        body
+    )
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `totalReturnPct` -> `Total Return Pct`.
+fn humanize(key: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in key.chars().enumerate() {
+        if i == 0 {
+            out.extend(c.to_uppercase());
+        } else if c.is_uppercase() {
+            out.push(' ');
+            out.push(c);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn severity_name(severity: crate::types::anomaly::Severity) -> String {
+    serde_json::to_value(severity)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+fn format_time(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn format_date(millis: i64) -> String {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .map(|t| t.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::anomalies::anomalies_insert_db;
+    use crate::db;
+    use crate::types::anomaly::{AnomalyStatus, Severity};
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn anomaly(id: &str, severity: Severity, session_id: &str, description: &str) -> Anomaly {
+        Anomaly {
+            id: id.to_string(),
+            severity,
+            source: "alpaca".to_string(),
+            symbol: Some("AAPL".to_string()),
+            timestamp: 1_735_800_000_000,
+            description: description.to_string(),
+            metrics: Default::default(),
+            pre_screen_score: 0.8,
+            session_id: session_id.to_string(),
+            status: AnomalyStatus::New,
+            snoozed_until: None,
+            original_severity: None,
+        }
+    }
+
+    fn summary(metrics: Option<serde_json::Value>) -> BacktestSummary {
+        BacktestSummary {
+            id: "bt-1".to_string(),
+            status: "completed".to_string(),
+            config: serde_json::json!({"symbols": ["AAPL"], "startDate": "2025-01-01", "endDate": "2025-03-31", "initialCapital": 1000.0}),
+            metrics,
+            created_at: 0,
+            completed_at: Some(1),
+            ticks_processed: 10,
+            total_ticks: 10,
+            error: None,
+            mode: "paper".to_string(),
+        }
+    }
+
+    fn trade(timestamp: i64, realized_pnl: Option<f64>) -> BacktestTrade {
+        BacktestTrade {
+            id: format!("t-{}", timestamp),
+            backtest_id: "bt-1".to_string(),
+            symbol: "AAPL".to_string(),
+            side: if realized_pnl.is_some() { "sell" } else { "buy" }.to_string(),
+            qty: 10.0,
+            fill_price: 150.0,
+            timestamp,
+            anomaly_id: "a-1".to_string(),
+            rationale: "spike <fade>".to_string(),
+            realized_pnl,
+            trade_context: None,
+        }
+    }
+
+    #[test]
+    fn equity_curve_rebuilds_from_trades_without_stored_curve() {
+        let trades = [trade(1_736_000_000_000, None), trade(1_736_100_000_000, Some(100.0)), trade(1_736_200_000_000, Some(-220.0))];
+        let curve = equity_curve(&summary(None), &trades);
+        let values: Vec<f64> = curve.iter().map(|p| p.value).collect();
+        assert_eq!(values, vec![1000.0, 1100.0, 880.0]);
+        assert_eq!(curve[0].date, "2025-01-01");
+
+        let drawdown = drawdown_curve(&curve);
+        assert_eq!(drawdown[1].value, 0.0);
+        assert!((drawdown[2].value + 20.0).abs() < 1e-9);
+
+        let stored = summary(Some(serde_json::json!({"equityCurve": [{"date": "2025-01-02", "value": 5.0}]})));
+        assert_eq!(equity_curve(&stored, &trades), vec![CurvePoint { date: "2025-01-02".to_string(), value: 5.0 }]);
+    }
+
+    #[test]
+    fn backtest_report_has_metrics_charts_and_escaped_trades() {
+        let trades = [trade(1_736_000_000_000, None), trade(1_736_100_000_000, Some(100.0))];
+        let metrics = serde_json::json!({"totalReturnPct": 10.0, "sharpeRatio": 1.234, "monthlyReturns": []});
+        let html = render_backtest(&summary(Some(metrics)), &trades, &[anomaly("a-1", Severity::High, "s", "x")]);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<th>Total Return Pct</th><td class=\"num\">10</td>"));
+        assert!(html.contains("<td class=\"num\">1.23</td>"));
+        assert!(!html.contains("Monthly Returns"));
+        assert_eq!(html.matches("<svg").count(), 2);
+        assert!(html.contains("spike &lt;fade&gt;"));
+        assert!(html.contains("<h2>Trades (2)</h2>"));
+        assert!(html.contains("class=\"sev-high\">high"));
+    }
+
+    #[test]
+    fn session_report_writes_to_disk() {
+        let (dir, pool) = test_pool();
+        anomalies_insert_db(&pool, &anomaly("a-1", Severity::Critical, "sess-1", "volume <spike>")).unwrap();
+        anomalies_insert_db(&pool, &anomaly("a-2", Severity::Low, "sess-1", "drift")).unwrap();
+        anomalies_insert_db(&pool, &anomaly("a-3", Severity::Low, "sess-2", "elsewhere")).unwrap();
+
+        let path = dir.path().join("report.html");
+        let written = generate(&pool, ReportKind::Session, "sess-1", &path).unwrap();
+        assert_eq!(written.kind, ReportKind::Session);
+        let html = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.bytes, html.len() as u64);
+        assert!(html.contains("<h2>Anomalies (2)</h2>"));
+        assert!(html.contains("volume &lt;spike&gt;"));
+        assert!(!html.contains("elsewhere"));
+
+        let err = generate(&pool, ReportKind::Session, "missing", &path).unwrap_err();
+        assert!(err.contains("missing"), "{err}");
+        assert!(generate(&pool, ReportKind::Backtest, "missing", &path).is_err());
+    }
+}
@@ -1,5 +1,11 @@
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::sidecar_resources::ResourceLimits;
 
 /// Maximum backoff duration for restart attempts.
 const MAX_BACKOFF: Duration = Duration::from_secs(30);
@@ -12,35 +18,219 @@ pub enum SidecarState {
     Crashed { restart_count: u32 },
 }
 
+/// File name of the compiled agent binary shipped next to the app executable
+/// (Tauri `externalBin`, with the target-triple suffix stripped by the bundler).
+pub const BUNDLED_SIDECAR_NAME: &str = "finwatch-agent";
+/// Agent entry point used in development, relative to the project root.
+pub const DEFAULT_AGENT_SCRIPT: &str = "agent/src/index.ts";
+
+/// User overrides for locating the agent, read from the `sidecar` config section.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarLaunchConfig {
+    /// Explicit path to a compiled agent binary.
+    pub binary_path: Option<String>,
+    /// Node.js executable used to run `script` when no binary is available.
+    pub node_path: Option<String>,
+    /// Agent entry point for `node_path` or the dev `tsx` runner.
+    pub script: Option<String>,
+    /// Inject secrets as environment variables at spawn time and send only
+    /// `$env:` references in RPC payloads.
+    #[serde(default)]
+    pub secrets_via_env: bool,
+    /// Re-send idempotent requests that were in flight when the agent crashed.
+    #[serde(default)]
+    pub journal_requests: bool,
+    /// Overrides the default set of methods eligible for re-sending.
+    pub journal_methods: Option<Vec<String>>,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+}
+
+/// How many crashes the watchdog tolerates before giving up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RestartPolicy {
+    /// Crashes allowed within `window_secs`; the watchdog stops once this is reached.
+    pub max_restarts: u32,
+    /// Length of the sliding window crashes are counted in.
+    pub window_secs: u64,
+    /// Uptime after which the crash history is forgotten entirely.
+    pub healthy_uptime_secs: u64,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: 3,
+            window_secs: 600,
+            healthy_uptime_secs: 300,
+        }
+    }
+}
+
+/// A fully resolved command line for spawning the agent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SidecarCommand {
+    pub program: PathBuf,
+    pub args: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub env: Vec<(String, String)>,
+}
+
+impl SidecarCommand {
+    /// Resolve using the running executable's directory and the dev project root.
+    pub fn discover(config: &SidecarLaunchConfig) -> Result<Self, String> {
+        let exe_dir = std::env::current_exe()
+            .ok()
+            .and_then(|p| p.parent().map(Path::to_path_buf));
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let project_root = manifest_dir.parent().unwrap_or(manifest_dir);
+        resolve_sidecar_command(config, exe_dir.as_deref(), project_root)
+    }
+}
+
+fn executable_name(base: &str) -> String {
+    if cfg!(windows) {
+        format!("{}.exe", base)
+    } else {
+        base.to_string()
+    }
+}
+
+/// Pick how to launch the agent, in order of preference:
+/// 1. `binaryPath` from config
+/// 2. the bundled sidecar binary next to the app executable
+/// 3. `nodePath` from config running the agent script
+/// 4. the dev tree's `node_modules/.bin/tsx` running the agent script
+pub fn resolve_sidecar_command(
+    config: &SidecarLaunchConfig,
+    exe_dir: Option<&Path>,
+    project_root: &Path,
+) -> Result<SidecarCommand, String> {
+    if let Some(ref binary) = config.binary_path {
+        let path = PathBuf::from(binary);
+        if !path.is_file() {
+            return Err(format!("Configured sidecar binary not found: {}", binary));
+        }
+        return Ok(SidecarCommand {
+            program: path,
+            args: Vec::new(),
+            cwd: None,
+            env: Vec::new(),
+        });
+    }
+
+    let mut tried = Vec::new();
+    if let Some(dir) = exe_dir {
+        let bundled = dir.join(executable_name(BUNDLED_SIDECAR_NAME));
+        if bundled.is_file() {
+            return Ok(SidecarCommand {
+                program: bundled,
+                args: Vec::new(),
+                cwd: Some(dir.to_path_buf()),
+                env: Vec::new(),
+            });
+        }
+        tried.push(bundled.display().to_string());
+    }
+
+    let script = config
+        .script
+        .clone()
+        .unwrap_or_else(|| DEFAULT_AGENT_SCRIPT.to_string());
+
+    if let Some(ref node) = config.node_path {
+        return Ok(SidecarCommand {
+            program: PathBuf::from(node),
+            args: vec![script],
+            cwd: Some(project_root.to_path_buf()),
+            env: Vec::new(),
+        });
+    }
+
+    let tsx_bin = project_root.join("node_modules/.bin").join(executable_name("tsx"));
+    if tsx_bin.is_file() {
+        return Ok(SidecarCommand {
+            program: tsx_bin,
+            args: vec![script],
+            cwd: Some(project_root.to_path_buf()),
+            env: Vec::new(),
+        });
+    }
+    tried.push(tsx_bin.display().to_string());
+
+    Err(format!(
+        "Could not locate the agent sidecar (tried {}). Set sidecar.binaryPath or sidecar.nodePath in config.",
+        tried.join(", ")
+    ))
+}
+
+/// Payload for the `sidecar:crashed`, `sidecar:restarting`, and `sidecar:restarted` events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SidecarLifecycleEvent {
+    pub restart_count: u32,
+    pub max_restarts: u32,
+    /// Exit code of the crashed process, if the OS reported one.
+    pub exit_code: Option<i32>,
+    /// Delay before the next restart attempt, for `sidecar:restarting`.
+    pub backoff_ms: Option<u64>,
+    /// Whether the watchdog will attempt another restart.
+    pub will_restart: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Default)]
+struct CrashHistory {
+    crashes: VecDeque<Instant>,
+    started_at: Option<Instant>,
+}
+
+/// Tracks sidecar state and the crash budget. Clones share the same state,
+/// so the watchdog thread can hold its own handle.
+#[derive(Clone)]
 pub struct SidecarSupervisor {
     state: Arc<Mutex<SidecarState>>,
-    max_restarts: u32,
+    policy: Arc<Mutex<RestartPolicy>>,
+    history: Arc<Mutex<CrashHistory>>,
 }
 
 impl SidecarSupervisor {
+    /// Supervisor with the default window and decay, capped at `max_restarts`.
     pub fn new(max_restarts: u32) -> Self {
-        Self {
-            state: Arc::new(Mutex::new(SidecarState::Stopped)),
+        Self::with_policy(RestartPolicy {
             max_restarts,
-        }
+            ..Default::default()
+        })
     }
 
-    /// Create a supervisor from an existing state Arc (used by watchdog thread).
-    pub fn from_arc(state: Arc<Mutex<SidecarState>>, max_restarts: u32) -> Self {
+    pub fn with_policy(policy: RestartPolicy) -> Self {
         Self {
-            state,
-            max_restarts,
+            state: Arc::new(Mutex::new(SidecarState::Stopped)),
+            policy: Arc::new(Mutex::new(policy)),
+            history: Arc::new(Mutex::new(CrashHistory::default())),
         }
     }
 
+    pub fn policy(&self) -> RestartPolicy {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_policy(&self, policy: RestartPolicy) {
+        *self.policy.lock().unwrap_or_else(|e| e.into_inner()) = policy;
+    }
+
     /// Get a clone of the state Arc for sharing with other threads.
     pub fn state_arc(&self) -> Arc<Mutex<SidecarState>> {
         Arc::clone(&self.state)
     }
 
-    /// Get the maximum number of allowed restarts.
+    /// Get the maximum number of allowed restarts within the window.
     pub fn max_restarts(&self) -> u32 {
-        self.max_restarts
+        self.policy().max_restarts
     }
 
     pub fn state(&self) -> SidecarState {
@@ -53,27 +243,60 @@ impl SidecarSupervisor {
 
     pub fn should_restart(&self) -> bool {
         match self.state() {
-            SidecarState::Crashed { restart_count } => restart_count < self.max_restarts,
+            SidecarState::Crashed { restart_count } => restart_count < self.max_restarts(),
             _ => false,
         }
     }
 
+    /// Record a crash. The resulting restart count is the number of crashes
+    /// inside the policy window, after forgetting history if the process had
+    /// been healthy for `healthy_uptime_secs`.
     pub fn record_crash(&self) {
-        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
-        let count = match *state {
-            SidecarState::Crashed { restart_count } => restart_count + 1,
-            _ => 1,
+        self.record_crash_at(Instant::now());
+    }
+
+    fn record_crash_at(&self, now: Instant) {
+        let policy = self.policy();
+        let count = {
+            let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(started) = history.started_at.take() {
+                if now.saturating_duration_since(started)
+                    >= Duration::from_secs(policy.healthy_uptime_secs)
+                {
+                    history.crashes.clear();
+                }
+            }
+            let window = Duration::from_secs(policy.window_secs);
+            while history
+                .crashes
+                .front()
+                .is_some_and(|&t| now.saturating_duration_since(t) > window)
+            {
+                history.crashes.pop_front();
+            }
+            history.crashes.push_back(now);
+            history.crashes.len() as u32
         };
-        *state = SidecarState::Crashed {
+        *self.state.lock().unwrap_or_else(|e| e.into_inner()) = SidecarState::Crashed {
             restart_count: count,
         };
     }
 
     pub fn record_started(&self) {
+        self.record_started_at(Instant::now());
+    }
+
+    fn record_started_at(&self, now: Instant) {
+        self.history
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .started_at = Some(now);
         *self.state.lock().unwrap_or_else(|e| e.into_inner()) = SidecarState::Running;
     }
 
+    /// Manual stop: clears the crash history as well.
     pub fn record_stopped(&self) {
+        *self.history.lock().unwrap_or_else(|e| e.into_inner()) = CrashHistory::default();
         *self.state.lock().unwrap_or_else(|e| e.into_inner()) = SidecarState::Stopped;
     }
 
@@ -95,6 +318,18 @@ impl SidecarSupervisor {
         let secs = 1u64.checked_shl(count.min(31)).unwrap_or(u64::MAX);
         Duration::from_secs(secs).min(MAX_BACKOFF)
     }
+
+    /// Build a lifecycle event payload from the current supervisor state.
+    pub fn lifecycle_event(&self, exit_code: Option<i32>, message: Option<String>) -> SidecarLifecycleEvent {
+        SidecarLifecycleEvent {
+            restart_count: self.restart_count(),
+            max_restarts: self.max_restarts(),
+            exit_code,
+            backoff_ms: None,
+            will_restart: self.should_restart(),
+            message,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -235,6 +470,161 @@ mod tests {
         assert_eq!(sup.restart_count(), 2);
     }
 
+    #[test]
+    fn crashes_outside_window_are_forgotten() {
+        let sup = SidecarSupervisor::with_policy(RestartPolicy {
+            max_restarts: 3,
+            window_secs: 600,
+            healthy_uptime_secs: 3600,
+        });
+        let t0 = Instant::now();
+        sup.record_crash_at(t0);
+        sup.record_crash_at(t0 + Duration::from_secs(60));
+        assert_eq!(sup.restart_count(), 2);
+        // First crash has aged out of the 10 minute window
+        sup.record_crash_at(t0 + Duration::from_secs(620));
+        assert_eq!(sup.restart_count(), 2);
+        assert!(sup.should_restart());
+    }
+
+    #[test]
+    fn crashes_spread_over_days_never_exhaust_budget() {
+        let sup = SidecarSupervisor::new(3);
+        let t0 = Instant::now();
+        for day in 0..7 {
+            sup.record_crash_at(t0 + Duration::from_secs(day * 86_400));
+            assert!(sup.should_restart());
+        }
+    }
+
+    #[test]
+    fn healthy_uptime_decays_history() {
+        let sup = SidecarSupervisor::with_policy(RestartPolicy {
+            max_restarts: 3,
+            window_secs: 3600,
+            healthy_uptime_secs: 300,
+        });
+        let t0 = Instant::now();
+        sup.record_crash_at(t0);
+        sup.record_started_at(t0 + Duration::from_secs(5));
+        sup.record_crash_at(t0 + Duration::from_secs(10));
+        assert_eq!(sup.restart_count(), 2);
+
+        // Stays up longer than healthy_uptime_secs before the next crash
+        sup.record_started_at(t0 + Duration::from_secs(20));
+        sup.record_crash_at(t0 + Duration::from_secs(400));
+        assert_eq!(sup.restart_count(), 1);
+    }
+
+    #[test]
+    fn rapid_crashes_exhaust_budget_across_restarts() {
+        let sup = SidecarSupervisor::new(3);
+        let t0 = Instant::now();
+        for i in 0..3 {
+            sup.record_started_at(t0 + Duration::from_secs(i * 20));
+            sup.record_crash_at(t0 + Duration::from_secs(i * 20 + 10));
+        }
+        assert_eq!(sup.restart_count(), 3);
+        assert!(!sup.should_restart());
+    }
+
+    #[test]
+    fn policy_deserializes_with_defaults() {
+        let policy: RestartPolicy = serde_json::from_str(r#"{"maxRestarts":5}"#).unwrap();
+        assert_eq!(policy.max_restarts, 5);
+        assert_eq!(policy.window_secs, 600);
+        let config: SidecarLaunchConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.restart_policy, RestartPolicy::default());
+    }
+
+    #[test]
+    fn lifecycle_event_reflects_restart_budget() {
+        let sup = SidecarSupervisor::new(2);
+        sup.record_started();
+        sup.record_crash();
+        let event = sup.lifecycle_event(Some(1), None);
+        assert_eq!(event.restart_count, 1);
+        assert_eq!(event.max_restarts, 2);
+        assert!(event.will_restart);
+
+        sup.record_crash();
+        let event = sup.lifecycle_event(None, Some("gave up".to_string()));
+        assert!(!event.will_restart);
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["restartCount"], 2);
+        assert_eq!(json["willRestart"], false);
+    }
+
+    #[test]
+    fn resolve_prefers_configured_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let bin = dir.path().join("my-agent");
+        std::fs::write(&bin, "").unwrap();
+        let config = SidecarLaunchConfig {
+            binary_path: Some(bin.display().to_string()),
+            node_path: Some("/usr/bin/node".to_string()),
+            ..Default::default()
+        };
+        let cmd = resolve_sidecar_command(&config, None, dir.path()).unwrap();
+        assert_eq!(cmd.program, bin);
+        assert!(cmd.args.is_empty());
+    }
+
+    #[test]
+    fn resolve_rejects_missing_configured_binary() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SidecarLaunchConfig {
+            binary_path: Some("/nonexistent/agent".to_string()),
+            ..Default::default()
+        };
+        assert!(resolve_sidecar_command(&config, None, dir.path()).is_err());
+    }
+
+    #[test]
+    fn resolve_uses_bundled_binary_next_to_exe() {
+        let exe_dir = tempfile::tempdir().unwrap();
+        let bundled = exe_dir.path().join(executable_name(BUNDLED_SIDECAR_NAME));
+        std::fs::write(&bundled, "").unwrap();
+        let root = tempfile::tempdir().unwrap();
+        let cmd = resolve_sidecar_command(
+            &SidecarLaunchConfig::default(),
+            Some(exe_dir.path()),
+            root.path(),
+        )
+        .unwrap();
+        assert_eq!(cmd.program, bundled);
+    }
+
+    #[test]
+    fn resolve_falls_back_to_node_path_then_tsx() {
+        let root = tempfile::tempdir().unwrap();
+        let config = SidecarLaunchConfig {
+            node_path: Some("/opt/node/bin/node".to_string()),
+            script: Some("agent/dist/index.js".to_string()),
+            ..Default::default()
+        };
+        let cmd = resolve_sidecar_command(&config, None, root.path()).unwrap();
+        assert_eq!(cmd.program, PathBuf::from("/opt/node/bin/node"));
+        assert_eq!(cmd.args, vec!["agent/dist/index.js"]);
+
+        let bin_dir = root.path().join("node_modules/.bin");
+        std::fs::create_dir_all(&bin_dir).unwrap();
+        let tsx = bin_dir.join(executable_name("tsx"));
+        std::fs::write(&tsx, "").unwrap();
+        let cmd =
+            resolve_sidecar_command(&SidecarLaunchConfig::default(), None, root.path()).unwrap();
+        assert_eq!(cmd.program, tsx);
+        assert_eq!(cmd.args, vec![DEFAULT_AGENT_SCRIPT]);
+    }
+
+    #[test]
+    fn resolve_errors_when_nothing_found() {
+        let root = tempfile::tempdir().unwrap();
+        let err =
+            resolve_sidecar_command(&SidecarLaunchConfig::default(), None, root.path()).unwrap_err();
+        assert!(err.contains("sidecar.binaryPath"));
+    }
+
     #[test]
     fn set_state_recovers_from_poisoned_mutex() {
         let sup = SidecarSupervisor::new(3);
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::time::now_millis;
+
+/// Optional resource limits for the agent process, from the `sidecar.resourceLimits` config.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ResourceLimits {
+    /// Resident memory ceiling in megabytes.
+    pub max_rss_mb: Option<u64>,
+    /// Kill the child (and let the watchdog restart it) when a limit is exceeded.
+    /// When false, violations are only logged and reported.
+    pub kill_on_exceed: bool,
+}
+
+/// A CPU/memory sample of the agent process.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceSample {
+    pub pid: u32,
+    /// CPU usage since the previous sample; 100.0 = one full core.
+    pub cpu_percent: f32,
+    pub rss_bytes: u64,
+    pub sampled_at: i64,
+}
+
+impl ResourceSample {
+    pub fn rss_mb(&self) -> u64 {
+        self.rss_bytes / (1024 * 1024)
+    }
+}
+
+/// Describe the first limit the sample exceeds, if any.
+pub fn check_limits(sample: &ResourceSample, limits: &ResourceLimits) -> Option<String> {
+    match limits.max_rss_mb {
+        Some(max) if sample.rss_mb() > max => Some(format!(
+            "Agent RSS {} MB exceeds limit of {} MB",
+            sample.rss_mb(),
+            max
+        )),
+        _ => None,
+    }
+}
+
+/// Samples a single process. CPU usage is relative to the previous call, so
+/// the first sample for a pid reports 0%.
+pub struct ResourceMonitor {
+    system: System,
+}
+
+impl ResourceMonitor {
+    pub fn new() -> Self {
+        Self {
+            system: System::new(),
+        }
+    }
+
+    pub fn sample(&mut self, pid: u32) -> Option<ResourceSample> {
+        let sys_pid = Pid::from_u32(pid);
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::Some(&[sys_pid]),
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+        let process = self.system.process(sys_pid)?;
+        Some(ResourceSample {
+            pid,
+            cpu_percent: process.cpu_usage(),
+            rss_bytes: process.memory(),
+            sampled_at: now_millis() as i64,
+        })
+    }
+}
+
+impl Default for ResourceMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_mb(mb: u64) -> ResourceSample {
+        ResourceSample {
+            pid: 1,
+            cpu_percent: 0.0,
+            rss_bytes: mb * 1024 * 1024,
+            sampled_at: 0,
+        }
+    }
+
+    #[test]
+    fn no_limits_never_exceeded() {
+        assert!(check_limits(&sample_mb(10_000), &ResourceLimits::default()).is_none());
+    }
+
+    #[test]
+    fn rss_limit_is_exclusive() {
+        let limits = ResourceLimits {
+            max_rss_mb: Some(512),
+            kill_on_exceed: true,
+        };
+        assert!(check_limits(&sample_mb(512), &limits).is_none());
+        let msg = check_limits(&sample_mb(513), &limits).unwrap();
+        assert!(msg.contains("513 MB"));
+    }
+
+    #[test]
+    fn samples_current_process() {
+        let mut monitor = ResourceMonitor::new();
+        let sample = monitor.sample(std::process::id()).unwrap();
+        assert_eq!(sample.pid, std::process::id());
+        assert!(sample.rss_bytes > 0);
+    }
+
+    #[test]
+    fn unknown_pid_yields_none() {
+        let mut monitor = ResourceMonitor::new();
+        assert!(monitor.sample(u32::MAX - 1).is_none());
+    }
+
+    #[test]
+    fn limits_deserialize_from_camel_case() {
+        let limits: ResourceLimits =
+            serde_json::from_str(r#"{"maxRssMb":1024,"killOnExceed":true}"#).unwrap();
+        assert_eq!(limits.max_rss_mb, Some(1024));
+        assert!(limits.kill_on_exceed);
+    }
+}
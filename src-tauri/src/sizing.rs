@@ -0,0 +1,393 @@
+//! Position sizing shared by the UI and the backtest config builder, so both
+//! size trades with the same math. Equity and volatility default to the
+//! mirrored account and the bars cache when the caller does not pass them.
+
+use serde::{Deserialize, Serialize};
+
+use crate::commands::agent::resolve_trading_mode;
+use crate::commands::bars::{bars_query_db, timeframe_millis, validate_timeframe, Bar};
+use crate::commands::config::config_effective_db;
+use crate::commands::portfolio::{portfolio_config, portfolio_get_db};
+use crate::db::{self, DbPool};
+use crate::indicators::{compute_bars, IndicatorSpec, PeriodSpec};
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// Bars of the timeframe read from the cache per ATR period.
+const ATR_LOOKBACK_PERIODS: u64 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizingStrategy {
+    /// Commit a fixed fraction of equity, or risk it down to `stopPrice`.
+    FixedFractional,
+    /// Risk a fraction of equity over a stop `atrMultiplier` ATRs away.
+    Atr,
+    /// Commit the Kelly fraction for `winRate` and `payoffRatio`, scaled by `kellyScale`.
+    Kelly,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SizingParams {
+    /// Symbol whose cached bars supply the price and ATR when not given.
+    pub symbol: Option<String>,
+    /// Account equity; defaults to the mirrored account of `mode`.
+    pub equity: Option<f64>,
+    /// `paper` or `live`; defaults to the `portfolio.mode` setting.
+    pub mode: Option<String>,
+    /// Entry price; defaults to the last cached close.
+    pub price: Option<f64>,
+    /// Fraction of equity committed (fixed-fractional) or risked (with a stop, or ATR).
+    pub risk_fraction: f64,
+    pub stop_price: Option<f64>,
+    /// Average true range; computed from cached bars when not given.
+    pub atr: Option<f64>,
+    pub atr_period: usize,
+    pub atr_multiplier: f64,
+    pub timeframe: String,
+    pub win_rate: Option<f64>,
+    /// Average win divided by average loss.
+    pub payoff_ratio: Option<f64>,
+    /// Multiplier on the full Kelly fraction, e.g. 0.5 for half Kelly.
+    pub kelly_scale: f64,
+    /// Cap on the position's value as a fraction of equity.
+    pub max_fraction: Option<f64>,
+}
+
+impl Default for SizingParams {
+    fn default() -> Self {
+        Self {
+            symbol: None,
+            equity: None,
+            mode: None,
+            price: None,
+            risk_fraction: 0.01,
+            stop_price: None,
+            atr: None,
+            atr_period: 14,
+            atr_multiplier: 2.0,
+            timeframe: "1Day".to_string(),
+            win_rate: None,
+            payoff_ratio: None,
+            kelly_scale: 0.5,
+            max_fraction: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionSize {
+    pub strategy: SizingStrategy,
+    /// Whole shares.
+    pub qty: f64,
+    /// Shares before rounding down.
+    pub raw_qty: f64,
+    /// Value of `qty` shares at `price`.
+    pub notional: f64,
+    /// Fraction of equity `notional` represents.
+    pub fraction: f64,
+    /// Loss if the stop is hit; `None` when no stop is known.
+    pub risk_amount: Option<f64>,
+    pub equity: f64,
+    pub price: f64,
+    pub atr: Option<f64>,
+    /// Full Kelly fraction before `kellyScale`, for the Kelly strategy.
+    pub kelly_fraction: Option<f64>,
+    /// Whether `maxFraction` reduced the position.
+    pub capped: bool,
+}
+
+fn positive(name: &str, value: Option<f64>) -> Result<f64, String> {
+    match value {
+        Some(v) if v.is_finite() && v > 0.0 => Ok(v),
+        Some(_) => Err(format!("{} must be positive", name)),
+        None => Err(format!("{} is required", name)),
+    }
+}
+
+fn unit_fraction(name: &str, value: f64) -> Result<f64, String> {
+    if value.is_finite() && value > 0.0 && value <= 1.0 {
+        Ok(value)
+    } else {
+        Err(format!("{} must be in (0, 1]", name))
+    }
+}
+
+/// Full Kelly fraction `W - (1 - W) / R`, floored at zero.
+pub fn kelly_fraction(win_rate: f64, payoff_ratio: f64) -> f64 {
+    (win_rate - (1.0 - win_rate) / payoff_ratio).max(0.0)
+}
+
+/// Size a position with `strategy`. `params` must already carry the equity,
+/// the price, and (for ATR sizing) the ATR.
+pub fn size_position(strategy: SizingStrategy, params: &SizingParams) -> Result<PositionSize, String> {
+    let equity = positive("equity", params.equity)?;
+    let price = positive("price", params.price)?;
+    let mut atr = None;
+    let mut kelly = None;
+    let (raw_qty, stop_distance) = match strategy {
+        SizingStrategy::FixedFractional => {
+            let fraction = unit_fraction("riskFraction", params.risk_fraction)?;
+            match params.stop_price {
+                Some(stop) => {
+                    let distance = (price - positive("stopPrice", Some(stop))?).abs();
+                    if distance == 0.0 {
+                        return Err("stopPrice must differ from price".to_string());
+                    }
+                    (equity * fraction / distance, Some(distance))
+                }
+                None => (equity * fraction / price, None),
+            }
+        }
+        SizingStrategy::Atr => {
+            let fraction = unit_fraction("riskFraction", params.risk_fraction)?;
+            let value = positive("atr", params.atr)?;
+            let multiplier = positive("atrMultiplier", Some(params.atr_multiplier))?;
+            atr = Some(value);
+            let distance = value * multiplier;
+            (equity * fraction / distance, Some(distance))
+        }
+        SizingStrategy::Kelly => {
+            let win_rate = params.win_rate.ok_or("winRate is required")?;
+            if !(0.0..=1.0).contains(&win_rate) {
+                return Err("winRate must be between 0 and 1".to_string());
+            }
+            let payoff = positive("payoffRatio", params.payoff_ratio)?;
+            let scale = unit_fraction("kellyScale", params.kelly_scale)?;
+            let full = kelly_fraction(win_rate, payoff);
+            kelly = Some(full);
+            (equity * full * scale / price, None)
+        }
+    };
+
+    let mut raw_qty = raw_qty;
+    let mut capped = false;
+    if let Some(max) = params.max_fraction {
+        let max = unit_fraction("maxFraction", max)?;
+        let limit = equity * max / price;
+        if raw_qty > limit {
+            raw_qty = limit;
+            capped = true;
+        }
+    }
+    let qty = raw_qty.floor();
+    let notional = qty * price;
+    Ok(PositionSize {
+        strategy,
+        qty,
+        raw_qty,
+        notional,
+        fraction: notional / equity,
+        risk_amount: stop_distance.map(|d| qty * d),
+        equity,
+        price,
+        atr,
+        kelly_fraction: kelly,
+        capped,
+    })
+}
+
+/// Cached bars of `timeframe` for `symbol`, enough for an ATR of `period`.
+fn cached_bars(pool: &DbPool, symbol: &str, timeframe: &str, period: usize) -> Result<Vec<Bar>, String> {
+    let lookback = timeframe_millis(timeframe)?.saturating_mul(period as u64 * ATR_LOOKBACK_PERIODS);
+    let now = now_millis();
+    bars_query_db(pool, symbol, timeframe, Some(now.saturating_sub(lookback)), None)
+}
+
+/// Fill the equity, price, and ATR that `strategy` needs but `params` lacks
+/// from the mirrored account and the bars cache.
+pub fn resolve_params_db(
+    pool: &DbPool,
+    strategy: SizingStrategy,
+    mut params: SizingParams,
+) -> Result<SizingParams, String> {
+    if params.equity.is_none() {
+        let mode = match params.mode.clone() {
+            Some(mode) => resolve_trading_mode(Some(mode))?,
+            None => portfolio_config(&config_effective_db(pool)?).mode,
+        };
+        let account = portfolio_get_db(pool, &mode)?.account;
+        let account =
+            account.ok_or_else(|| format!("No cached {} account; pass equity or refresh the portfolio", mode))?;
+        params.equity = Some(account.equity);
+    }
+    let needs_atr = strategy == SizingStrategy::Atr && params.atr.is_none();
+    if params.price.is_some() && !needs_atr {
+        return Ok(params);
+    }
+    let symbol = params
+        .symbol
+        .as_deref()
+        .map(|s| s.trim().to_uppercase())
+        .filter(|s| !s.is_empty())
+        .ok_or("symbol is required when price or atr is not given")?;
+    validate_timeframe(&params.timeframe)?;
+    if params.atr_period == 0 {
+        return Err("atrPeriod must be positive".to_string());
+    }
+    let bars = cached_bars(pool, &symbol, &params.timeframe, params.atr_period)?;
+    let last = bars
+        .last()
+        .ok_or_else(|| format!("No recent cached {} bars for {}", params.timeframe, symbol))?;
+    params.price.get_or_insert(last.close);
+    if needs_atr {
+        let spec = IndicatorSpec {
+            rsi: None,
+            macd: None,
+            bollinger: None,
+            atr: Some(PeriodSpec {
+                period: params.atr_period,
+            }),
+        };
+        let indicators = compute_bars(&symbol, &params.timeframe, &bars, &spec);
+        let atr = indicators
+            .atr
+            .and_then(|atr| atr.last().copied())
+            .filter(|v| v.is_finite());
+        params.atr = Some(atr.ok_or_else(|| {
+            format!(
+                "Not enough cached {} bars for {} to compute a {}-period ATR",
+                params.timeframe, symbol, params.atr_period
+            )
+        })?);
+    }
+    Ok(params)
+}
+
+/// Size a position with `strategy`, taking the equity, price, and ATR not in
+/// `params` from the mirrored account and the bars cache.
+#[tauri::command]
+pub async fn position_size(
+    workspace: tauri::State<'_, WorkspaceDb>,
+    strategy: SizingStrategy,
+    params: Option<SizingParams>,
+) -> Result<PositionSize, String> {
+    let params = params.unwrap_or_default();
+    db::run_blocking(&workspace.pool(), move |pool| {
+        let params = resolve_params_db(pool, strategy, params)?;
+        size_position(strategy, &params)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params() -> SizingParams {
+        SizingParams {
+            equity: Some(100_000.0),
+            price: Some(50.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sizes_each_strategy() {
+        let fixed = size_position(SizingStrategy::FixedFractional, &params()).unwrap();
+        assert_eq!(fixed.qty, 20.0);
+        assert_eq!(fixed.risk_amount, None);
+
+        let stopped = SizingParams {
+            stop_price: Some(48.0),
+            ..params()
+        };
+        let stopped = size_position(SizingStrategy::FixedFractional, &stopped).unwrap();
+        assert_eq!(stopped.qty, 500.0);
+        assert_eq!(stopped.risk_amount, Some(1_000.0));
+
+        let atr = SizingParams {
+            atr: Some(1.5),
+            ..params()
+        };
+        let atr = size_position(SizingStrategy::Atr, &atr).unwrap();
+        assert_eq!(atr.qty, 333.0);
+        assert_eq!(atr.atr, Some(1.5));
+
+        // 0.6 - 0.4 / 2 = 0.4 of equity at full Kelly; half Kelly commits 20%.
+        let kelly = SizingParams {
+            win_rate: Some(0.6),
+            payoff_ratio: Some(2.0),
+            ..params()
+        };
+        let kelly = size_position(SizingStrategy::Kelly, &kelly).unwrap();
+        assert!((kelly.kelly_fraction.unwrap() - 0.4).abs() < 1e-12);
+        assert_eq!(kelly.qty, 400.0);
+        assert!(!kelly.capped);
+
+        let capped = SizingParams {
+            win_rate: Some(0.6),
+            payoff_ratio: Some(2.0),
+            max_fraction: Some(0.1),
+            ..params()
+        };
+        let capped = size_position(SizingStrategy::Kelly, &capped).unwrap();
+        assert_eq!(capped.qty, 200.0);
+        assert!(capped.capped);
+
+        let losing = SizingParams {
+            win_rate: Some(0.3),
+            payoff_ratio: Some(1.0),
+            ..params()
+        };
+        assert_eq!(size_position(SizingStrategy::Kelly, &losing).unwrap().qty, 0.0);
+    }
+
+    #[test]
+    fn rejects_missing_inputs() {
+        let no_equity = SizingParams {
+            equity: None,
+            ..params()
+        };
+        assert_eq!(
+            size_position(SizingStrategy::FixedFractional, &no_equity).unwrap_err(),
+            "equity is required"
+        );
+        assert_eq!(
+            size_position(SizingStrategy::Atr, &params()).unwrap_err(),
+            "atr is required"
+        );
+        let bad_fraction = SizingParams {
+            risk_fraction: 1.5,
+            ..params()
+        };
+        assert!(size_position(SizingStrategy::FixedFractional, &bad_fraction).is_err());
+    }
+
+    #[test]
+    fn resolves_price_and_atr_from_cached_bars() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+
+        let day = timeframe_millis("1Day").unwrap();
+        let now = now_millis();
+        let bars: Vec<Bar> = (0..30u64)
+            .map(|i| Bar {
+                timestamp: now - (30 - i) * day,
+                open: 100.0,
+                high: 101.0,
+                low: 99.0,
+                close: 100.0,
+                volume: 1_000.0,
+            })
+            .collect();
+        crate::commands::bars::bars_insert_db(&pool, "SPY", "1Day", "test", &bars).unwrap();
+
+        let params = SizingParams {
+            symbol: Some("spy".to_string()),
+            equity: Some(10_000.0),
+            ..Default::default()
+        };
+        let resolved = resolve_params_db(&pool, SizingStrategy::Atr, params.clone()).unwrap();
+        assert_eq!(resolved.price, Some(100.0));
+        assert!((resolved.atr.unwrap() - 2.0).abs() < 1e-9);
+
+        let no_account = SizingParams { equity: None, ..params };
+        let err = resolve_params_db(&pool, SizingStrategy::Atr, no_account).unwrap_err();
+        assert!(err.contains("No cached paper account"));
+    }
+}
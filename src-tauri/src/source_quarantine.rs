@@ -0,0 +1,197 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::data::{SourceHealth, SourceHealthStatus};
+
+/// The `quarantine` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuarantineConfig {
+    pub enabled: bool,
+    /// Window the up/down transitions are counted in.
+    pub window_secs: u64,
+    /// Transitions within the window that quarantine a source.
+    pub max_transitions: u64,
+    /// How long a quarantined source is skipped.
+    pub cooldown_secs: u64,
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window_secs: 600,
+            max_transitions: 4,
+            cooldown_secs: 900,
+        }
+    }
+}
+
+/// Parse the `quarantine` section of the app config.
+pub fn quarantine_config(app_config: &serde_json::Value) -> QuarantineConfig {
+    app_config
+        .get("quarantine")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Payload of `source:quarantined`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceQuarantined {
+    pub source_id: String,
+    /// Transitions seen within the window.
+    pub transitions: u64,
+    /// Epoch millis the quarantine ends.
+    pub until: u64,
+}
+
+#[derive(Default)]
+struct SourceState {
+    up: Option<bool>,
+    transitions: VecDeque<u64>,
+    quarantined_until: Option<u64>,
+}
+
+/// Tracks source health changes and quarantines sources that flap between
+/// up and offline too often.
+pub struct SourceQuarantine {
+    config: Mutex<QuarantineConfig>,
+    sources: Mutex<HashMap<String, SourceState>>,
+}
+
+impl SourceQuarantine {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(QuarantineConfig::default()),
+            sources: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_config(&self, config: QuarantineConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    /// Record a health update at `now_ms`. Returns the quarantine it triggers, if any.
+    pub fn observe(&self, health: &SourceHealth, now_ms: u64) -> Option<SourceQuarantined> {
+        let config = *self.config.lock().unwrap_or_else(|e| e.into_inner());
+        if !config.enabled {
+            return None;
+        }
+        let mut sources = self.sources.lock().unwrap_or_else(|e| e.into_inner());
+        let state = sources.entry(health.source_id.clone()).or_default();
+        if state.quarantined_until.is_some_and(|until| now_ms < until) {
+            return None;
+        }
+        state.quarantined_until = None;
+
+        let up = health.status != SourceHealthStatus::Offline;
+        let changed = state.up.is_some_and(|was_up| was_up != up);
+        state.up = Some(up);
+        if !changed {
+            return None;
+        }
+
+        let window_start = now_ms.saturating_sub(config.window_secs.saturating_mul(1000));
+        state.transitions.retain(|&t| t >= window_start);
+        state.transitions.push_back(now_ms);
+        let transitions = state.transitions.len() as u64;
+        if transitions < config.max_transitions {
+            return None;
+        }
+
+        let until = now_ms + config.cooldown_secs.saturating_mul(1000);
+        state.transitions.clear();
+        state.quarantined_until = Some(until);
+        Some(SourceQuarantined {
+            source_id: health.source_id.clone(),
+            transitions,
+            until,
+        })
+    }
+
+    /// Whether `source_id` is quarantined at `now_ms`.
+    pub fn is_quarantined(&self, source_id: &str, now_ms: u64) -> bool {
+        self.sources
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(source_id)
+            .and_then(|s| s.quarantined_until)
+            .is_some_and(|until| now_ms < until)
+    }
+}
+
+impl Default for SourceQuarantine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(status: SourceHealthStatus) -> SourceHealth {
+        SourceHealth {
+            source_id: "yahoo-spy".to_string(),
+            status,
+            last_success: 0,
+            last_failure: None,
+            fail_count: 0,
+            latency_ms: 0,
+            message: None,
+        }
+    }
+
+    fn flap(q: &SourceQuarantine, times: &[u64]) -> Option<SourceQuarantined> {
+        let mut result = None;
+        for (i, &t) in times.iter().enumerate() {
+            let status = if i % 2 == 0 {
+                SourceHealthStatus::Healthy
+            } else {
+                SourceHealthStatus::Offline
+            };
+            result = q.observe(&health(status), t);
+        }
+        result
+    }
+
+    #[test]
+    fn quarantines_after_repeated_transitions() {
+        let q = SourceQuarantine::new();
+        // The first update sets the baseline; four flips follow
+        let quarantined = flap(&q, &[0, 1_000, 2_000, 3_000, 4_000]).unwrap();
+        assert_eq!(quarantined.transitions, 4);
+        assert_eq!(quarantined.until, 4_000 + 900_000);
+        assert!(q.is_quarantined("yahoo-spy", 5_000));
+        assert!(!q.is_quarantined("yahoo-spy", 904_000));
+    }
+
+    #[test]
+    fn transitions_outside_window_do_not_count() {
+        let q = SourceQuarantine::new();
+        assert_eq!(flap(&q, &[0, 1_000, 2_000, 700_000, 701_000]), None);
+    }
+
+    #[test]
+    fn degraded_counts_as_up_and_updates_during_cooldown_are_ignored() {
+        let q = SourceQuarantine::new();
+        q.observe(&health(SourceHealthStatus::Healthy), 0);
+        assert_eq!(q.observe(&health(SourceHealthStatus::Degraded), 1_000), None);
+        flap(&q, &[2_000, 3_000, 4_000, 5_000, 6_000]).unwrap();
+        assert_eq!(q.observe(&health(SourceHealthStatus::Offline), 7_000), None);
+    }
+
+    #[test]
+    fn disabled_never_quarantines() {
+        let q = SourceQuarantine::new();
+        q.set_config(QuarantineConfig {
+            enabled: false,
+            ..QuarantineConfig::default()
+        });
+        assert_eq!(flap(&q, &[0, 1, 2, 3, 4, 5, 6]), None);
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::commands::config::config_effective_db;
+use crate::commands::offline::is_offline;
+use crate::db::DbPool;
+use crate::time::now_millis;
+use crate::workspace::WorkspaceDb;
+
+/// How often the scheduler looks for due tasks.
+const SCHEDULER_TICK: Duration = Duration::from_secs(15);
+
+/// What a task did when the scheduler ran it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskOutcome {
+    /// The task did its work.
+    Ran,
+    /// Nothing was due, or the task's own preconditions were not met.
+    Skipped,
+}
+
+/// A recurring background task.
+pub struct TaskDef {
+    pub id: &'static str,
+    pub description: &'static str,
+    /// How often the task is checked; the task decides whether work is due.
+    pub every: Duration,
+    /// Whether the task runs when `tasks.<id>.enabled` is unset.
+    pub enabled_by_default: bool,
+    /// Whether the task needs the network; such tasks wait while offline mode is on.
+    pub network: bool,
+    pub run: fn(&AppHandle) -> Result<TaskOutcome, String>,
+}
+
+/// Every task the scheduler manages.
+pub const TASKS: &[TaskDef] = &[
+    TaskDef {
+        id: "calendar_refresh",
+        description: "Refresh the cached Alpaca market calendar",
+        every: Duration::from_secs(60 * 60),
+        enabled_by_default: true,
+        network: true,
+        run: crate::commands::calendar::refresh_task,
+    },
+    TaskDef {
+        id: "asset_refresh",
+        description: "Refresh the tradable asset cache when it goes stale",
+        every: Duration::from_secs(15 * 60),
+        enabled_by_default: true,
+        network: true,
+        run: crate::commands::assets::refresh_task,
+    },
+    TaskDef {
+        id: "db_maintenance",
+        description: "Checkpoint, vacuum, and analyze the database while the agent is idle",
+        every: Duration::from_secs(15 * 60),
+        enabled_by_default: true,
+        network: false,
+        run: crate::commands::maintenance::maintenance_task,
+    },
+    TaskDef {
+        id: "retention_prune",
+        description: "Delete recorded ticks and agent activity past their retention windows",
+        every: Duration::from_secs(60 * 60),
+        enabled_by_default: true,
+        network: false,
+        run: crate::commands::maintenance::retention_task,
+    },
+    TaskDef {
+        id: "incident_grouping",
+        description: "Cluster new anomalies into per-symbol incidents",
+        every: Duration::from_secs(60),
+        enabled_by_default: true,
+        network: false,
+        run: crate::commands::incidents::grouping_task,
+    },
+    TaskDef {
+        id: "portfolio_snapshot",
+        description: "Record the daily portfolio snapshot after the close",
+        every: Duration::from_secs(5 * 60),
+        enabled_by_default: true,
+        network: true,
+        run: crate::commands::performance::snapshot_task,
+    },
+    TaskDef {
+        id: "agent_schedule",
+        description: "Start and stop the agent around market sessions",
+        every: Duration::from_secs(60),
+        enabled_by_default: true,
+        network: false,
+        run: crate::commands::schedule::schedule_task,
+    },
+    TaskDef {
+        id: "workspace_backup",
+        description: "Archive the active workspace to ~/.finwatch/backups/ once a day",
+        every: Duration::from_secs(60 * 60),
+        enabled_by_default: false,
+        network: false,
+        run: crate::commands::workspace::backup_task,
+    },
+];
+
+/// The task with `id`, if there is one.
+pub fn task_def(id: &str) -> Option<&'static TaskDef> {
+    TASKS.iter().find(|t| t.id == id)
+}
+
+/// One entry of the `tasks` section of the app config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskToggle {
+    pub enabled: Option<bool>,
+}
+
+/// The `tasks` section of the app config, keyed by task id.
+pub type TasksConfig = BTreeMap<String, TaskToggle>;
+
+/// Parse the `tasks` section of the app config.
+pub fn tasks_config(app_config: &serde_json::Value) -> TasksConfig {
+    app_config
+        .get("tasks")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// Default `tasks` section: every task with its default switch.
+pub fn tasks_defaults() -> serde_json::Value {
+    TASKS
+        .iter()
+        .map(|t| (t.id.to_string(), serde_json::json!({ "enabled": t.enabled_by_default })))
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+fn is_enabled(config: &TasksConfig, task: &TaskDef) -> bool {
+    config
+        .get(task.id)
+        .and_then(|t| t.enabled)
+        .unwrap_or(task.enabled_by_default)
+}
+
+/// Persisted scheduling state of one task.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskRun {
+    /// When the task last did work, epoch millis.
+    pub last_run_at: Option<i64>,
+    /// When the scheduler will next check the task, epoch millis.
+    pub next_run_at: i64,
+    /// `ran`, `skipped`, or `failed`.
+    pub last_status: String,
+    pub last_error: Option<String>,
+    pub last_duration_ms: Option<i64>,
+}
+
+/// A task as listed by `tasks_list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskInfo {
+    pub id: String,
+    pub description: String,
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// `None` until the scheduler first checks the task.
+    pub state: Option<TaskRun>,
+}
+
+pub fn task_run_get_db(pool: &DbPool, id: &str) -> Result<Option<TaskRun>, String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    let result = conn.query_row(
+        "SELECT last_run_at, next_run_at, last_status, last_error, last_duration_ms FROM task_runs WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(TaskRun {
+                last_run_at: row.get(0)?,
+                next_run_at: row.get(1)?,
+                last_status: row.get(2)?,
+                last_error: row.get(3)?,
+                last_duration_ms: row.get(4)?,
+            })
+        },
+    );
+    match result {
+        Ok(run) => Ok(Some(run)),
+        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn task_run_set_db(pool: &DbPool, id: &str, run: &TaskRun) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute(
+        "INSERT INTO task_runs (id, last_run_at, next_run_at, last_status, last_error, last_duration_ms)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT(id) DO UPDATE SET last_run_at = ?2, next_run_at = ?3, last_status = ?4,
+             last_error = ?5, last_duration_ms = ?6",
+        rusqlite::params![id, run.last_run_at, run.next_run_at, run.last_status, run.last_error, run.last_duration_ms],
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Every task with its switch and persisted state.
+pub fn tasks_list_db(pool: &DbPool) -> Result<Vec<TaskInfo>, String> {
+    let config = tasks_config(&config_effective_db(pool)?);
+    TASKS
+        .iter()
+        .map(|t| {
+            Ok(TaskInfo {
+                id: t.id.to_string(),
+                description: t.description.to_string(),
+                enabled: is_enabled(&config, t),
+                interval_secs: t.every.as_secs(),
+                state: task_run_get_db(pool, t.id)?,
+            })
+        })
+        .collect()
+}
+
+/// The state to store after running `task` at `now`, given what it returned.
+pub fn next_state(
+    task: &TaskDef,
+    previous: Option<&TaskRun>,
+    result: &Result<TaskOutcome, String>,
+    now: i64,
+    elapsed: Duration,
+) -> TaskRun {
+    let (last_run_at, status, error) = match result {
+        Ok(TaskOutcome::Ran) => (Some(now), "ran", None),
+        Ok(TaskOutcome::Skipped) => (previous.and_then(|p| p.last_run_at), "skipped", None),
+        Err(e) => (previous.and_then(|p| p.last_run_at), "failed", Some(e.clone())),
+    };
+    TaskRun {
+        last_run_at,
+        next_run_at: now + task.every.as_millis() as i64,
+        last_status: status.to_string(),
+        last_error: error,
+        last_duration_ms: Some(elapsed.as_millis() as i64),
+    }
+}
+
+/// Run every enabled task whose next run has come, recording the result.
+/// Network tasks are left due while offline so they run on reconnect.
+fn run_due(app: &AppHandle, pool: &DbPool, config: &TasksConfig) {
+    let offline = is_offline(pool);
+    for task in TASKS {
+        if !is_enabled(config, task) || (task.network && offline) {
+            continue;
+        }
+        let previous = match task_run_get_db(pool, task.id) {
+            Ok(previous) => previous,
+            Err(e) => {
+                tracing::warn!(task = task.id, error = %e, "Could not read task state");
+                continue;
+            }
+        };
+        let now = now_millis() as i64;
+        if previous.as_ref().is_some_and(|p| p.next_run_at > now) {
+            continue;
+        }
+        let started = Instant::now();
+        let result = (task.run)(app);
+        if let Err(e) = &result {
+            tracing::warn!(task = task.id, error = %e, "Scheduled task failed");
+        }
+        let state = next_state(task, previous.as_ref(), &result, now, started.elapsed());
+        if let Err(e) = task_run_set_db(pool, task.id, &state) {
+            tracing::warn!(task = task.id, error = %e, "Could not record task state");
+        }
+    }
+}
+
+/// Start the one background thread that runs every recurring task.
+pub fn spawn_scheduler(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let pool = app.state::<WorkspaceDb>().pool();
+        match config_effective_db(&pool) {
+            Ok(app_config) => run_due(&app, &pool, &tasks_config(&app_config)),
+            Err(e) => tracing::warn!(error = %e, "Task scheduler could not read config"),
+        }
+        std::thread::sleep(SCHEDULER_TICK);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    #[test]
+    fn task_ids_are_unique() {
+        let mut ids: Vec<&str> = TASKS.iter().map(|t| t.id).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), TASKS.len());
+    }
+
+    #[test]
+    fn config_overrides_default_switches() {
+        let backup = task_def("workspace_backup").unwrap();
+        let calendar = task_def("calendar_refresh").unwrap();
+        assert!(!is_enabled(&TasksConfig::new(), backup));
+        assert!(is_enabled(&TasksConfig::new(), calendar));
+
+        let config = tasks_config(&serde_json::json!({
+            "tasks": {"workspace_backup": {"enabled": true}, "calendar_refresh": {"enabled": false}}
+        }));
+        assert!(is_enabled(&config, backup));
+        assert!(!is_enabled(&config, calendar));
+        assert_eq!(tasks_defaults()["workspace_backup"]["enabled"], false);
+    }
+
+    #[test]
+    fn state_keeps_last_run_across_skips_and_failures() {
+        let task = task_def("agent_schedule").unwrap();
+        let ran = next_state(task, None, &Ok(TaskOutcome::Ran), 1_000, Duration::from_millis(5));
+        assert_eq!(ran.last_run_at, Some(1_000));
+        assert_eq!(ran.next_run_at, 61_000);
+        assert_eq!(ran.last_status, "ran");
+
+        let skipped = next_state(task, Some(&ran), &Ok(TaskOutcome::Skipped), 61_000, Duration::ZERO);
+        assert_eq!(skipped.last_run_at, Some(1_000));
+        assert_eq!(skipped.last_status, "skipped");
+
+        let failed = next_state(task, Some(&skipped), &Err("boom".to_string()), 121_000, Duration::ZERO);
+        assert_eq!(failed.last_run_at, Some(1_000));
+        assert_eq!(failed.last_error.as_deref(), Some("boom"));
+    }
+
+    #[test]
+    fn task_state_round_trips_and_lists() {
+        let (_dir, pool) = test_pool();
+        assert_eq!(task_run_get_db(&pool, "db_maintenance").unwrap(), None);
+        let run = TaskRun {
+            last_run_at: Some(5),
+            next_run_at: 10,
+            last_status: "ran".to_string(),
+            last_error: None,
+            last_duration_ms: Some(1),
+        };
+        task_run_set_db(&pool, "db_maintenance", &run).unwrap();
+        task_run_set_db(&pool, "db_maintenance", &TaskRun { next_run_at: 20, ..run.clone() }).unwrap();
+        assert_eq!(task_run_get_db(&pool, "db_maintenance").unwrap().unwrap().next_run_at, 20);
+
+        let list = tasks_list_db(&pool).unwrap();
+        assert_eq!(list.len(), TASKS.len());
+        let maintenance = list.iter().find(|t| t.id == "db_maintenance").unwrap();
+        assert!(maintenance.enabled);
+        assert_eq!(maintenance.interval_secs, 900);
+        assert_eq!(maintenance.state.as_ref().unwrap().last_run_at, Some(5));
+    }
+}
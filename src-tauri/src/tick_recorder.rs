@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+use crate::commands::ticks::{ticks_insert_db, ticks_prune_db, TickRecordingConfig};
+use crate::db::DbPool;
+use crate::types::data::DataTick;
+
+/// Minimum time between retention sweeps.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Persists `data:tick` notifications from the agent according to the
+/// `ticks` config, pruning past the retention window as it goes.
+pub struct TickRecorder {
+    config: Mutex<TickRecordingConfig>,
+    last_prune: Mutex<Option<Instant>>,
+}
+
+impl TickRecorder {
+    pub fn new() -> Self {
+        Self {
+            config: Mutex::new(TickRecordingConfig::default()),
+            last_prune: Mutex::new(None),
+        }
+    }
+
+    pub fn set_config(&self, config: TickRecordingConfig) {
+        *self.config.lock().unwrap_or_else(|e| e.into_inner()) = config;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.config.lock().unwrap_or_else(|e| e.into_inner()).enabled
+    }
+
+    /// Store a `data:tick` payload if recording is enabled for its symbol.
+    /// Returns whether the tick was stored.
+    pub fn record(&self, pool: &DbPool, payload: &Value) -> Result<bool, String> {
+        let config = self.config.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        let tick: DataTick = serde_json::from_value(payload.clone())
+            .map_err(|e| format!("Invalid tick payload: {}", e))?;
+        if !config.should_record(tick.symbol.as_deref()) {
+            return Ok(false);
+        }
+        ticks_insert_db(pool, &tick)?;
+        self.prune_if_due(pool, &config, tick.timestamp)?;
+        Ok(true)
+    }
+
+    fn prune_if_due(&self, pool: &DbPool, config: &TickRecordingConfig, now_ms: u64) -> Result<(), String> {
+        {
+            let mut last = self.last_prune.lock().unwrap_or_else(|e| e.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PRUNE_INTERVAL) {
+                return Ok(());
+            }
+            *last = Some(Instant::now());
+        }
+        let cutoff = now_ms.saturating_sub(config.retention_days.saturating_mul(DAY_MS));
+        let pruned = ticks_prune_db(pool, cutoff)?;
+        if pruned > 0 {
+            tracing::debug!(pruned, "Pruned recorded ticks past retention");
+        }
+        Ok(())
+    }
+}
+
+impl Default for TickRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::ticks::ticks_query_db;
+    use crate::types::data::TickRange;
+
+    fn test_pool() -> (tempfile::TempDir, DbPool) {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        (dir, pool)
+    }
+
+    fn payload(symbol: &str, timestamp: u64) -> Value {
+        serde_json::json!({
+            "sourceId": "alpaca",
+            "timestamp": timestamp,
+            "symbol": symbol,
+            "metrics": {"close": 10.0},
+            "metadata": {},
+        })
+    }
+
+    #[test]
+    fn disabled_recorder_stores_nothing() {
+        let (_dir, pool) = test_pool();
+        let recorder = TickRecorder::new();
+        assert!(!recorder.record(&pool, &payload("NET", 1_000)).unwrap());
+        assert!(ticks_query_db(&pool, "NET", &TickRange::default()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn records_configured_symbols_only() {
+        let (_dir, pool) = test_pool();
+        let recorder = TickRecorder::new();
+        recorder.set_config(TickRecordingConfig {
+            enabled: true,
+            symbols: Some(vec!["NET".to_string()]),
+            retention_days: 7,
+        });
+        assert!(recorder.record(&pool, &payload("NET", 1_000)).unwrap());
+        assert!(!recorder.record(&pool, &payload("AAPL", 1_000)).unwrap());
+        assert_eq!(ticks_query_db(&pool, "NET", &TickRange::default()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn first_record_prunes_past_retention() {
+        let (_dir, pool) = test_pool();
+        let old = DataTick {
+            source_id: "alpaca".to_string(),
+            timestamp: 1_000,
+            symbol: Some("NET".to_string()),
+            metrics: Default::default(),
+            metadata: Default::default(),
+            raw: None,
+        };
+        ticks_insert_db(&pool, &old).unwrap();
+
+        let recorder = TickRecorder::new();
+        recorder.set_config(TickRecordingConfig {
+            enabled: true,
+            symbols: None,
+            retention_days: 1,
+        });
+        recorder.record(&pool, &payload("NET", 1_000 + 2 * DAY_MS)).unwrap();
+        let ticks = ticks_query_db(&pool, "NET", &TickRange::default()).unwrap();
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].timestamp, 1_000 + 2 * DAY_MS);
+    }
+
+    #[test]
+    fn invalid_payload_is_an_error() {
+        let (_dir, pool) = test_pool();
+        let recorder = TickRecorder::new();
+        assert!(recorder.record(&pool, &serde_json::json!({"foo": 1})).is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Wall-clock time as epoch numbers, the form timestamps are stored in.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Current time in epoch millis; 0 if the clock is set before 1970.
+pub fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Current time in whole epoch seconds.
+pub fn now_secs() -> u64 {
+    now_millis() / 1000
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seconds_and_millis_agree() {
+        let before = now_millis();
+        let secs = now_secs();
+        let after = now_millis();
+        assert!(before > 1_600_000_000_000);
+        assert!(before / 1000 <= secs && secs <= after / 1000);
+    }
+}
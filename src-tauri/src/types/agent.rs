@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::sidecar_resources::ResourceSample;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum AgentState {
@@ -20,6 +22,49 @@ pub struct AgentStatus {
     pub total_anomalies: u64,
     pub uptime: u64,
     pub last_error: Option<String>,
+    /// Latest CPU/memory sample of the sidecar process.
+    #[serde(default)]
+    pub resources: Option<ResourceSample>,
+    #[serde(default)]
+    pub health: AgentHealth,
+}
+
+/// What the sidecar watchdog is doing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogState {
+    /// No agent process is supervised.
+    #[default]
+    Stopped,
+    Starting,
+    /// The agent is running and polled for crashes.
+    Watching,
+    /// The agent crashed and is being respawned.
+    Restarting,
+    /// The agent crashed too often and will not be respawned.
+    GaveUp,
+}
+
+/// Heartbeat and supervision details of the sidecar.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentHealth {
+    /// Millis since the agent last answered a ping, while it is running.
+    pub last_pong_age_ms: Option<u64>,
+    /// Consecutive pings that went unanswered.
+    pub missed_pings: u64,
+    /// Automatic restarts since the agent was last started.
+    pub restart_count: u64,
+    pub watchdog: WatchdogState,
+}
+
+/// Payload of `agent:unhealthy`, emitted once the agent has not answered a
+/// ping for longer than the allowed silence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentUnhealthy {
+    pub silence_ms: u64,
+    pub missed_pings: u64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -45,3 +90,21 @@ pub struct AgentActivity {
     pub timestamp: u64,
     pub data: Option<std::collections::HashMap<String, serde_json::Value>>,
 }
+
+/// Steps of `agent_restart`, reported through `agent:restart` events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentRestartPhase {
+    Stopping,
+    Starting,
+    Started,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentRestartProgress {
+    pub phase: AgentRestartPhase,
+    /// Why the restart failed, for the `failed` phase.
+    pub error: Option<String>,
+}
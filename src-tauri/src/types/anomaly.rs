@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Severity {
     Low,
@@ -10,10 +11,34 @@ pub enum Severity {
     Critical,
 }
 
+/// Where an anomaly is in triage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnomalyStatus {
+    #[default]
+    New,
+    Acknowledged,
+    /// Hidden from the default list until `snoozed_until`.
+    Snoozed,
+    Resolved,
+}
+
+impl AnomalyStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AnomalyStatus::New => "new",
+            AnomalyStatus::Acknowledged => "acknowledged",
+            AnomalyStatus::Snoozed => "snoozed",
+            AnomalyStatus::Resolved => "resolved",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Anomaly {
     pub id: String,
+    /// The user's override when there is one, otherwise the detected severity.
     pub severity: Severity,
     pub source: String,
     pub symbol: Option<String>,
@@ -22,6 +47,12 @@ pub struct Anomaly {
     pub metrics: HashMap<String, f64>,
     pub pre_screen_score: f64,
     pub session_id: String,
+    #[serde(default)]
+    pub status: AnomalyStatus,
+    /// Epoch millis a snooze ends; set only while snoozed.
+    pub snoozed_until: Option<u64>,
+    /// The detected severity, set only while the user has overridden it.
+    pub original_severity: Option<Severity>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,7 +63,7 @@ pub enum FeedbackVerdict {
     NeedsReview,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AnomalyFeedback {
     pub anomaly_id: String,
@@ -41,7 +72,21 @@ pub struct AnomalyFeedback {
     pub timestamp: u64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A timestamped investigation note; an anomaly can have many, unlike the
+/// single feedback verdict.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnomalyNote {
+    pub id: i64,
+    pub anomaly_id: String,
+    pub body: String,
+    /// Epoch millis.
+    pub created_at: u64,
+    /// Epoch millis of the last edit, if any.
+    pub updated_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AnomalyFilter {
     pub severity: Option<Vec<Severity>>,
@@ -49,4 +94,58 @@ pub struct AnomalyFilter {
     pub symbol: Option<String>,
     pub since: Option<u64>,
     pub limit: Option<u32>,
+    pub status: Option<Vec<AnomalyStatus>>,
+    /// Also list anomalies whose snooze has not ended. Off by default.
+    pub include_snoozed: Option<bool>,
+    /// Only members of this incident.
+    pub incident_id: Option<i64>,
+}
+
+/// How `anomalies_heatmap` buckets counts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapBucket {
+    /// One cell per UTC day and hour.
+    #[default]
+    DayHour,
+    /// One cell per symbol and UTC day.
+    SymbolDay,
+}
+
+/// Anomaly count in one heatmap bucket. Only non-empty buckets are returned.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapCell {
+    /// `YYYY-MM-DD`, UTC.
+    pub day: String,
+    /// Hour of day (0-23, UTC); set for `day_hour` buckets.
+    pub hour: Option<u32>,
+    /// Set for `symbol_day` buckets; `None` there groups market-wide anomalies.
+    pub symbol: Option<String>,
+    pub count: u32,
+}
+
+/// Anomalies on one symbol clustered by time, so a volatile stretch reads as
+/// a single event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Incident {
+    pub id: i64,
+    pub symbol: String,
+    /// Epoch millis of the first member.
+    pub started_at: u64,
+    /// Epoch millis of the last member.
+    pub ended_at: u64,
+    pub anomaly_count: u32,
+    /// Highest member severity, with overrides applied.
+    pub max_severity: Severity,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncidentFilter {
+    pub symbol: Option<String>,
+    /// Only incidents still active at or after this time (epoch millis).
+    pub since: Option<u64>,
+    pub limit: Option<u32>,
 }
@@ -76,6 +76,8 @@ pub struct BacktestSummary {
     pub total_ticks: i64,
     /// Error message if status is `"failed"`, otherwise `null`.
     pub error: Option<String>,
+    /// Trading mode (`"paper"` or `"live"`) whose credentials fetched the data.
+    pub mode: String,
 }
 
 /// A single trade executed during a backtest. Matches the TypeScript `BacktestTrade`.
@@ -103,4 +105,23 @@ pub struct BacktestTrade {
     pub rationale: String,
     /// Realized PnL for sell trades; `null` for buy trades.
     pub realized_pnl: Option<f64>,
+    /// Indicators at the fill bar, computed from cached bars when the trade is
+    /// stored; `null` when no bars were cached for the symbol.
+    #[serde(default)]
+    pub trade_context: Option<TradeContext>,
+}
+
+/// Compact indicator snapshot at the bar a backtest trade filled on. Each
+/// value is `null` when the bars before the fill are too few to compute it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradeContext {
+    /// Epoch millis of the fill bar's start.
+    pub bar_timestamp: u64,
+    /// RSI(14).
+    pub rsi: Option<f64>,
+    /// ATR(14).
+    pub atr: Option<f64>,
+    /// Bollinger %B (20, 2σ).
+    pub percent_b: Option<f64>,
 }
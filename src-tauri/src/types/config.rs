@@ -1,4 +1,30 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::alpaca_stream::{AlpacaStreamConfig, FEEDS};
+use crate::anomaly_notifier::{parse_clock, NotificationConfig};
+use crate::commands::activity::ActivityConfig;
+use crate::commands::assets::AssetsConfig;
+use crate::commands::incidents::IncidentsConfig;
+use crate::commands::maintenance::MaintenanceConfig;
+use crate::commands::orders::RiskLimits;
+use crate::commands::portfolio::PortfolioConfig;
+use crate::commands::providers::{ProvidersConfig, KNOWN_PROVIDERS};
+use crate::commands::schedule::ScheduleConfig;
+use crate::commands::sources::rss::RssConfig;
+use crate::commands::sources::yahoo::YahooSourceConfig;
+use crate::commands::ticks::TickRecordingConfig;
+use crate::commands::trading::Guardrails;
+use crate::commands::usage::UsageConfig;
+use crate::csv_source::CsvSourceConfig;
+use crate::embeddings::{local_model, EmbeddingsConfig, LOCAL_MODELS};
+use crate::http::{proxy_error, NetworkConfig};
+use crate::sidecar::SidecarLaunchConfig;
+use crate::source_quarantine::QuarantineConfig;
+use crate::watcher::WatcherConfig;
+use crate::local_api::LocalApiConfig;
+use crate::tasks::{self, TasksConfig};
+use crate::webhook::WebhookConfig;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
@@ -16,3 +42,519 @@ pub struct ProviderConfig {
     pub provider_type: ProviderType,
     pub api_key_env: Option<String>,
 }
+
+/// Settings the app itself reads from the stored config (`config` table, key `main`).
+/// Every field is optional so a partial patch parses on its own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AppConfig {
+    pub model: Option<String>,
+    pub anthropic_api_key: Option<String>,
+    pub openrouter_api_key: Option<String>,
+    pub symbols: Option<Vec<String>>,
+    pub feed: Option<String>,
+    pub monitor: Option<MonitorSettings>,
+    pub sidecar: Option<SidecarLaunchConfig>,
+    pub maintenance: Option<MaintenanceConfig>,
+    pub ticks: Option<TickRecordingConfig>,
+    pub csv: Option<CsvSourceConfig>,
+    pub watcher: Option<WatcherConfig>,
+    pub assets: Option<AssetsConfig>,
+    pub schedule: Option<ScheduleConfig>,
+    pub activity: Option<ActivityConfig>,
+    pub quarantine: Option<QuarantineConfig>,
+    pub yahoo: Option<YahooSourceConfig>,
+    pub alpaca_stream: Option<AlpacaStreamConfig>,
+    pub webhook: Option<WebhookConfig>,
+    pub local_api: Option<LocalApiConfig>,
+    pub rss: Option<RssConfig>,
+    pub notifications: Option<NotificationConfig>,
+    pub portfolio: Option<PortfolioConfig>,
+    pub risk: Option<RiskLimits>,
+    pub guardrails: Option<Guardrails>,
+    pub tasks: Option<TasksConfig>,
+    pub network: Option<NetworkConfig>,
+    pub incidents: Option<IncidentsConfig>,
+    pub providers: Option<ProvidersConfig>,
+    pub usage: Option<UsageConfig>,
+    pub embeddings: Option<EmbeddingsConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorSettings {
+    pub analysis_interval_ms: Option<u64>,
+    pub max_cycle_age_ms: Option<u64>,
+}
+
+/// LLM model used when the config does not name one.
+pub const DEFAULT_MODEL: &str = "claude-haiku-4-5-20251001";
+
+/// Canonical default values for every setting, keyed like the stored config.
+/// `config_get` merges stored values over these.
+pub fn config_defaults() -> Value {
+    let mut defaults = serde_json::json!({
+        "model": DEFAULT_MODEL,
+        "symbols": ["NET"],
+        "feed": "iex",
+        "monitor": {
+            "analysisIntervalMs": 60_000,
+            "maxCycleAgeMs": 14_400_000,
+        },
+        "sidecar": SidecarLaunchConfig::default(),
+        "maintenance": MaintenanceConfig::default(),
+        "ticks": TickRecordingConfig::default(),
+        "csv": CsvSourceConfig::default(),
+        "watcher": WatcherConfig::default(),
+        "assets": AssetsConfig::default(),
+        "schedule": ScheduleConfig::default(),
+        "activity": ActivityConfig::default(),
+        "quarantine": QuarantineConfig::default(),
+        "yahoo": YahooSourceConfig::default(),
+        "alpacaStream": AlpacaStreamConfig::default(),
+        "webhook": WebhookConfig::default(),
+        "localApi": LocalApiConfig::default(),
+        "rss": RssConfig::default(),
+        "notifications": NotificationConfig::default(),
+        "portfolio": PortfolioConfig::default(),
+        "risk": RiskLimits::default(),
+        "guardrails": Guardrails::default(),
+        "tasks": tasks::tasks_defaults(),
+        "network": NetworkConfig::default(),
+        "incidents": IncidentsConfig::default(),
+        "providers": ProvidersConfig::default(),
+        "usage": UsageConfig::default(),
+        "embeddings": EmbeddingsConfig::default(),
+    });
+    strip_nulls(&mut defaults);
+    defaults
+}
+
+/// Drop null members so optional settings without a default stay unset.
+fn strip_nulls(value: &mut Value) {
+    if let Value::Object(map) = value {
+        map.retain(|_, v| !v.is_null());
+        map.values_mut().for_each(strip_nulls);
+    }
+}
+
+/// Top-level settings holding API keys; stripped from exported configs.
+pub const SECRET_FIELDS: &[&str] = &["anthropicApiKey", "openrouterApiKey"];
+
+/// Market data feeds accepted by the agent's Alpaca stream.
+pub const ALPACA_FEEDS: &[&str] = &["iex", "sip"];
+
+/// A problem with one setting, addressed by its dotted path (e.g. `monitor.analysisIntervalMs`).
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldError {
+    pub path: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Parse `value` as config, rejecting unknown keys, wrong types, and
+    /// out-of-range values. Returns every problem found, not just the first.
+    pub fn validate(value: &Value) -> Result<AppConfig, Vec<FieldError>> {
+        if !value.is_object() {
+            return Err(vec![FieldError::new("", "Config must be a JSON object")]);
+        }
+        let config: AppConfig = serde_path_to_error::deserialize(value).map_err(|e| {
+            let path = e.path().to_string();
+            let path = if path == "." { String::new() } else { path };
+            vec![FieldError::new(path, e.into_inner().to_string())]
+        })?;
+
+        let mut errors = Vec::new();
+        let typed = serde_json::to_value(&config).unwrap_or(Value::Null);
+        unknown_keys(value, &typed, "", &mut errors);
+        config.check_ranges(&mut errors);
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn check_ranges(&self, errors: &mut Vec<FieldError>) {
+        if self.model.as_deref().is_some_and(|m| m.trim().is_empty()) {
+            errors.push(FieldError::new("model", "must not be empty"));
+        }
+        if let Some(symbols) = &self.symbols {
+            for (i, symbol) in symbols.iter().enumerate() {
+                if symbol.trim().is_empty() {
+                    errors.push(FieldError::new(format!("symbols[{}]", i), "must not be empty"));
+                }
+            }
+        }
+        if let Some(feed) = &self.feed {
+            if !ALPACA_FEEDS.contains(&feed.as_str()) {
+                errors.push(FieldError::new(
+                    "feed",
+                    format!("must be one of: {}", ALPACA_FEEDS.join(", ")),
+                ));
+            }
+        }
+        if let Some(monitor) = &self.monitor {
+            check_range(
+                errors,
+                "monitor.analysisIntervalMs",
+                monitor.analysis_interval_ms,
+                1_000,
+                86_400_000,
+            );
+            check_range(
+                errors,
+                "monitor.maxCycleAgeMs",
+                monitor.max_cycle_age_ms,
+                60_000,
+                u64::MAX,
+            );
+        }
+        if let Some(sidecar) = &self.sidecar {
+            let policy = &sidecar.restart_policy;
+            check_range(errors, "sidecar.restartPolicy.windowSecs", Some(policy.window_secs), 1, u64::MAX);
+            check_range(
+                errors,
+                "sidecar.restartPolicy.healthyUptimeSecs",
+                Some(policy.healthy_uptime_secs),
+                1,
+                u64::MAX,
+            );
+            check_range(
+                errors,
+                "sidecar.resourceLimits.maxRssMb",
+                sidecar.resource_limits.max_rss_mb,
+                64,
+                u64::MAX,
+            );
+        }
+        if let Some(maintenance) = &self.maintenance {
+            check_range(errors, "maintenance.intervalHours", Some(maintenance.interval_hours), 1, 8_760);
+        }
+        if let Some(ticks) = &self.ticks {
+            check_range(errors, "ticks.retentionDays", Some(ticks.retention_days), 1, 3_650);
+        }
+        if let Some(watcher) = &self.watcher {
+            check_range(errors, "watcher.debounceMs", Some(watcher.debounce_ms), 10, 60_000);
+        }
+        if let Some(assets) = &self.assets {
+            check_range(errors, "assets.cacheTtlHours", Some(assets.cache_ttl_hours), 1, 720);
+            check_range(errors, "assets.detailTtlMinutes", Some(assets.detail_ttl_minutes), 1, 10_080);
+            check_range(errors, "assets.refreshLeadHours", Some(assets.refresh_lead_hours), 0, 720);
+        }
+        if let Some(schedule) = &self.schedule {
+            check_range(errors, "schedule.startBeforeOpenMinutes", Some(schedule.start_before_open_minutes), 0, 240);
+            check_range(errors, "schedule.stopAfterCloseMinutes", Some(schedule.stop_after_close_minutes), 0, 240);
+        }
+        if let Some(incidents) = &self.incidents {
+            check_range(errors, "incidents.windowMinutes", Some(incidents.window_minutes), 1, 1_440);
+        }
+        if let Some(providers) = &self.providers {
+            if providers.fallback.is_empty() {
+                errors.push(FieldError::new("providers.fallback", "must name at least one provider"));
+            }
+            for (i, id) in providers.fallback.iter().enumerate() {
+                if !KNOWN_PROVIDERS.contains(&id.as_str()) {
+                    errors.push(FieldError::new(
+                        format!("providers.fallback[{}]", i),
+                        format!("must be one of {}", KNOWN_PROVIDERS.join(", ")),
+                    ));
+                } else if providers.fallback[..i].contains(id) {
+                    errors.push(FieldError::new(format!("providers.fallback[{}]", i), "is listed twice"));
+                }
+            }
+            check_range(errors, "providers.cooldownSecs", Some(providers.cooldown_secs), 0, 86_400);
+        }
+        if let Some(usage) = &self.usage {
+            for (model, price) in &usage.prices {
+                let fields = [("inputPerMtok", price.input_per_mtok), ("outputPerMtok", price.output_per_mtok)];
+                for (field, value) in fields {
+                    if !value.is_finite() || value < 0.0 {
+                        errors.push(FieldError::new(
+                            format!("usage.prices.{}.{}", model, field),
+                            "must be a non-negative number",
+                        ));
+                    }
+                }
+            }
+        }
+        if let Some(embeddings) = &self.embeddings {
+            if local_model(&embeddings.model).is_none() {
+                let names: Vec<&str> = LOCAL_MODELS.iter().map(|m| m.name).collect();
+                errors.push(FieldError::new(
+                    "embeddings.model",
+                    format!("must be one of {}", names.join(", ")),
+                ));
+            }
+        }
+        if let Some(activity) = &self.activity {
+            check_range(errors, "activity.retentionDays", Some(activity.retention_days), 1, 3_650);
+        }
+        if let Some(quarantine) = &self.quarantine {
+            check_range(errors, "quarantine.windowSecs", Some(quarantine.window_secs), 10, 86_400);
+            check_range(errors, "quarantine.maxTransitions", Some(quarantine.max_transitions), 2, 100);
+            check_range(errors, "quarantine.cooldownSecs", Some(quarantine.cooldown_secs), 10, 86_400);
+        }
+        if let Some(yahoo) = &self.yahoo {
+            check_range(errors, "yahoo.intervalSecs", Some(yahoo.interval_secs), 15, 86_400);
+            if yahoo.symbols.iter().any(|s| s.trim().is_empty()) {
+                errors.push(FieldError::new("yahoo.symbols", "must not contain empty symbols"));
+            }
+        }
+        if let Some(stream) = &self.alpaca_stream {
+            if !FEEDS.contains(&stream.feed.as_str()) {
+                errors.push(FieldError::new("alpacaStream.feed", format!("must be one of {}", FEEDS.join(", "))));
+            }
+            if stream.symbols.iter().any(|s| s.trim().is_empty()) {
+                errors.push(FieldError::new("alpacaStream.symbols", "must not contain empty symbols"));
+            }
+        }
+        if let Some(network) = &self.network {
+            let proxy = network.proxy.trim();
+            if let Some(e) = Some(proxy).filter(|p| !p.is_empty()).and_then(proxy_error) {
+                errors.push(FieldError::new("network.proxy", e));
+            }
+            if network.no_proxy.iter().any(|h| h.trim().is_empty()) {
+                errors.push(FieldError::new("network.noProxy", "must not contain empty hosts"));
+            }
+        }
+        if let Some(webhook) = &self.webhook {
+            check_range(errors, "webhook.port", Some(u64::from(webhook.port)), 1_024, 65_535);
+        }
+        if let Some(local_api) = &self.local_api {
+            check_range(errors, "localApi.port", Some(u64::from(local_api.port)), 1_024, 65_535);
+            if local_api.enabled && self.webhook.is_some_and(|w| w.enabled && w.port == local_api.port) {
+                errors.push(FieldError::new("localApi.port", "must differ from webhook.port"));
+            }
+        }
+        if let Some(rss) = &self.rss {
+            check_range(errors, "rss.intervalSecs", Some(rss.interval_secs), 60, 86_400);
+            for (i, feed) in rss.feeds.iter().enumerate() {
+                if !(feed.starts_with("https://") || feed.starts_with("http://")) {
+                    errors.push(FieldError::new(format!("rss.feeds[{}]", i), "must be an http(s) URL"));
+                }
+            }
+            if rss.keywords.iter().any(|k| k.trim().is_empty()) {
+                errors.push(FieldError::new("rss.keywords", "must not contain empty keywords"));
+            }
+        }
+        if let Some(quiet) = self.notifications.as_ref().and_then(|n| n.quiet_hours.as_ref()) {
+            for (field, value) in [("start", &quiet.start), ("end", &quiet.end)] {
+                if parse_clock(value).is_none() {
+                    errors.push(FieldError::new(format!("notifications.quietHours.{}", field), "must be HH:MM"));
+                }
+            }
+            if !(-720..=840).contains(&quiet.utc_offset_minutes) {
+                errors.push(FieldError::new(
+                    "notifications.quietHours.utcOffsetMinutes",
+                    "must be between -720 and 840",
+                ));
+            }
+        }
+        if let Some(portfolio) = &self.portfolio {
+            check_range(errors, "portfolio.refreshSecs", Some(portfolio.refresh_secs), 15, 86_400);
+            if let Err(e) = crate::broker::BrokerProvider::Alpaca.validate_mode(&portfolio.mode) {
+                errors.push(FieldError::new("portfolio.mode", e));
+            }
+        }
+        if let Some(risk) = &self.risk {
+            if !(risk.max_notional.is_finite() && risk.max_notional > 0.0) {
+                errors.push(FieldError::new("risk.maxNotional", "must be positive"));
+            }
+            if !(risk.max_position_pct > 0.0 && risk.max_position_pct <= 100.0) {
+                errors.push(FieldError::new("risk.maxPositionPct", "must be between 0 and 100"));
+            }
+            check_range(errors, "risk.maxOpenOrders", Some(risk.max_open_orders), 1, 1_000);
+        }
+        if let Some(guardrails) = &self.guardrails {
+            for (field, value) in [
+                ("dailyLossLimit", guardrails.daily_loss_limit),
+                ("maxOrderValue", guardrails.max_order_value),
+            ] {
+                if !(value.is_finite() && value > 0.0) {
+                    errors.push(FieldError::new(format!("guardrails.{}", field), "must be positive"));
+                }
+            }
+        }
+        if let Some(task_config) = &self.tasks {
+            for id in task_config.keys().filter(|id| tasks::task_def(id).is_none()) {
+                errors.push(FieldError::new(format!("tasks.{}", id), "unknown task"));
+            }
+        }
+        if let Some(csv) = &self.csv {
+            if csv.timestamp_column.trim().is_empty() {
+                errors.push(FieldError::new("csv.timestampColumn", "must not be empty"));
+            }
+            if matches!(csv.delimiter, '"' | '\n' | '\r') {
+                errors.push(FieldError::new("csv.delimiter", "must not be a quote or newline"));
+            }
+        }
+    }
+}
+
+fn check_range(errors: &mut Vec<FieldError>, path: &str, value: Option<u64>, min: u64, max: u64) {
+    match value {
+        Some(v) if v < min || v > max => {
+            let message = if max == u64::MAX {
+                format!("must be at least {}", min)
+            } else {
+                format!("must be between {} and {}", min, max)
+            };
+            errors.push(FieldError::new(path, message));
+        }
+        _ => {}
+    }
+}
+
+/// Keys present in `input` that did not survive parsing into the typed config.
+fn unknown_keys(input: &Value, typed: &Value, path: &str, errors: &mut Vec<FieldError>) {
+    let (Value::Object(input), Value::Object(typed)) = (input, typed) else {
+        return;
+    };
+    for (key, value) in input {
+        let child = if path.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", path, key)
+        };
+        match typed.get(key) {
+            Some(typed_value) => unknown_keys(value, typed_value, &child, errors),
+            None => errors.push(FieldError::new(child, "unknown setting")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn paths(errors: &[FieldError]) -> Vec<&str> {
+        errors.iter().map(|e| e.path.as_str()).collect()
+    }
+
+    #[test]
+    fn accepts_known_settings() {
+        let config = AppConfig::validate(&json!({
+            "model": "claude-haiku-4-5-20251001",
+            "symbols": ["NET", "AAPL"],
+            "feed": "iex",
+            "monitor": {"analysisIntervalMs": 60000},
+            "sidecar": {"restartPolicy": {"maxRestarts": 5}},
+            "ticks": {"enabled": true},
+        }))
+        .unwrap();
+        assert_eq!(config.symbols.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn reports_unknown_keys_at_every_level() {
+        let errors = AppConfig::validate(&json!({
+            "modle": "x",
+            "sidecar": {"restartPolicy": {"maxRestart": 5}},
+        }))
+        .unwrap_err();
+        assert_eq!(paths(&errors), vec!["modle", "sidecar.restartPolicy.maxRestart"]);
+    }
+
+    #[test]
+    fn reports_wrong_types_with_path() {
+        let errors = AppConfig::validate(&json!({
+            "monitor": {"analysisIntervalMs": "fast"},
+        }))
+        .unwrap_err();
+        assert_eq!(paths(&errors), vec!["monitor.analysisIntervalMs"]);
+        assert!(errors[0].message.contains("invalid type"));
+    }
+
+    #[test]
+    fn reports_out_of_range_values() {
+        let errors = AppConfig::validate(&json!({
+            "feed": "bogus",
+            "monitor": {"analysisIntervalMs": 10},
+            "maintenance": {"intervalHours": 0},
+            "symbols": ["NET", " "],
+        }))
+        .unwrap_err();
+        assert_eq!(
+            paths(&errors),
+            vec!["symbols[1]", "feed", "monitor.analysisIntervalMs", "maintenance.intervalHours"]
+        );
+    }
+
+    #[test]
+    fn defaults_are_valid_and_complete() {
+        let defaults = config_defaults();
+        assert!(AppConfig::validate(&defaults).is_ok());
+        assert_eq!(defaults["model"], DEFAULT_MODEL);
+        assert_eq!(defaults["sidecar"]["restartPolicy"]["maxRestarts"], 3);
+        assert!(defaults["sidecar"].get("binaryPath").is_none());
+        assert_eq!(defaults["ticks"]["retentionDays"], 7);
+    }
+
+    #[test]
+    fn local_api_cannot_share_the_webhook_port() {
+        let errors = AppConfig::validate(&json!({
+            "webhook": {"enabled": true, "port": 9000},
+            "localApi": {"enabled": true, "port": 9000},
+        }))
+        .unwrap_err();
+        assert_eq!(paths(&errors), vec!["localApi.port"]);
+    }
+
+    #[test]
+    fn network_proxy_must_be_a_proxy_url() {
+        assert!(AppConfig::validate(&json!({"network": {"proxy": "socks5://127.0.0.1:1080"}})).is_ok());
+        let errors = AppConfig::validate(&json!({
+            "network": {"proxy": "ftp://proxy.corp", "noProxy": [""]},
+        }))
+        .unwrap_err();
+        assert_eq!(paths(&errors), vec!["network.proxy", "network.noProxy"]);
+    }
+
+    #[test]
+    fn provider_fallback_must_name_known_providers_once() {
+        assert!(AppConfig::validate(&json!({"providers": {"fallback": ["openrouter", "anthropic"]}})).is_ok());
+        let errors = AppConfig::validate(&json!({
+            "providers": {"fallback": ["anthropic", "openai", "anthropic"], "cooldownSecs": 100_000},
+        }))
+        .unwrap_err();
+        assert_eq!(
+            paths(&errors),
+            vec!["providers.fallback[1]", "providers.fallback[2]", "providers.cooldownSecs"]
+        );
+    }
+
+    #[test]
+    fn embeddings_model_must_be_known() {
+        assert!(AppConfig::validate(&json!({"embeddings": {"local": true, "model": "bge-small-en-v1.5"}})).is_ok());
+        let errors = AppConfig::validate(&json!({"embeddings": {"model": "word2vec"}})).unwrap_err();
+        assert_eq!(paths(&errors), vec!["embeddings.model"]);
+    }
+
+    #[test]
+    fn usage_prices_must_be_non_negative() {
+        let priced = json!({"usage": {"prices": {"gpt-4o": {"inputPerMtok": 2.5, "outputPerMtok": 10}}}});
+        assert!(AppConfig::validate(&priced).is_ok());
+        let errors = AppConfig::validate(&json!({
+            "usage": {"prices": {"gpt-4o": {"inputPerMtok": -1, "outputPerMtok": 10}}},
+        }))
+        .unwrap_err();
+        assert_eq!(paths(&errors), vec!["usage.prices.gpt-4o.inputPerMtok"]);
+    }
+
+    #[test]
+    fn rejects_non_objects() {
+        assert!(AppConfig::validate(&json!([1, 2])).is_err());
+    }
+}
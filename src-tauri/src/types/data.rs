@@ -1,7 +1,7 @@
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DataTick {
     pub source_id: String,
@@ -12,6 +12,15 @@ pub struct DataTick {
     pub raw: Option<serde_json::Value>,
 }
 
+/// Time window for `ticks_query`, in epoch millis (inclusive).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TickRange {
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub limit: Option<u32>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum SourceHealthStatus {
@@ -31,3 +40,33 @@ pub struct SourceHealth {
     pub latency_ms: u64,
     pub message: Option<String>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourceType {
+    Polling,
+    Streaming,
+    File,
+}
+
+/// A configured data source, handed to the agent at start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceConfig {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub source_type: SourceType,
+    /// Agent plugin that implements the source, e.g. `yahoo` or `csv`.
+    pub plugin: String,
+    /// Plugin settings such as the endpoint, directory, or symbols.
+    #[serde(default)]
+    pub config: serde_json::Value,
+    pub poll_interval_ms: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
@@ -27,6 +27,77 @@ pub enum MatchType {
     Hybrid,
 }
 
+/// Narrows `memory_search`. Tags and sources match any listed value; the
+/// time range is in epoch millis and inclusive.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemorySearchFilter {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub until: Option<u64>,
+}
+
+impl MemorySearchFilter {
+    pub fn validate(&self) -> Result<(), String> {
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since > until {
+                return Err(format!("Search range starts after it ends: {} > {}", since, until));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What `memory_import` does with an entry whose id is already stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MemoryMergeStrategy {
+    /// Keep the stored entry.
+    #[default]
+    Skip,
+    /// Overwrite the stored entry with the imported one.
+    Replace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryImportResult {
+    pub imported: u64,
+    /// Entries left out because their id was already stored.
+    pub skipped: u64,
+}
+
+/// Outcome of a `memory:compact` call, as reported by the agent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryCompactionResult {
+    pub entries_before: u64,
+    pub entries_after: u64,
+    /// Groups of near-duplicates merged into one entry each.
+    pub clusters: u64,
+    /// Groups whose merged content was written by the LLM.
+    pub summarized: u64,
+}
+
+/// One recorded `memory_compact` run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MemoryCompaction {
+    pub id: i64,
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub threshold: f64,
+    pub summarize: bool,
+    /// Unset when the run failed.
+    pub result: Option<MemoryCompactionResult>,
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct MemoryEvent {
@@ -57,6 +57,7 @@ mod tests {
         }"#;
         let anomaly: anomaly::Anomaly = serde_json::from_str(json).unwrap();
         assert_eq!(anomaly.severity, anomaly::Severity::High);
+        assert_eq!(anomaly.status, anomaly::AnomalyStatus::New);
         let re_json = serde_json::to_string(&anomaly).unwrap();
         let anomaly2: anomaly::Anomaly = serde_json::from_str(&re_json).unwrap();
         assert_eq!(anomaly.id, anomaly2.id);
@@ -9,6 +9,21 @@ pub enum ProviderHealthStatus {
     RateLimited,
 }
 
+/// Token usage of one LLM call, from the agent's `llm:usage` notification.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmUsage {
+    pub provider_id: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub latency_ms: u64,
+    /// Epoch millis.
+    pub timestamp: u64,
+    pub session_id: Option<String>,
+    pub cycle_id: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProviderHealth {
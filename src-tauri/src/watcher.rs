@@ -1,20 +1,121 @@
-use notify::{Event, EventKind, RecommendedWatcher};
-use std::path::PathBuf;
-use std::sync::mpsc;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::Manager;
 
+use crate::bridge::SidecarBridge;
+use crate::commands::config::{config_effective_db, config_reload_file_db, publish_config_change};
+use crate::commands::watcher::watch_paths_list_db;
+use crate::csv_source::{csv_source_config, ingest_file, publish_ingest};
+use crate::workspace::WorkspaceDb;
+
+/// Config file in the data dir that is merged into the stored config when edited.
+pub const CONFIG_FILE: &str = "config.json";
+/// A batch is flushed after this many debounce windows even if events keep coming.
+const MAX_BATCH_WINDOWS: u32 = 10;
+
+/// The `watcher` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatcherConfig {
+    /// Quiet period after the last event before a batch is handled. Editors
+    /// often write a file in several steps per save.
+    pub debounce_ms: u64,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self { debounce_ms: 500 }
+    }
+}
+
+/// Parse the `watcher` section of the app config.
+pub fn watcher_config(app_config: &serde_json::Value) -> WatcherConfig {
+    app_config
+        .get("watcher")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum WatchEvent {
     ConfigChanged,
     SourceFileChanged { path: PathBuf },
 }
 
-pub fn classify_event(event: &Event, config_path: &std::path::Path) -> Option<WatchEvent> {
+/// Source file patterns the data dir is always watched for.
+pub const DEFAULT_SOURCE_PATTERNS: &[&str] = &["*.csv", "*.jsonl", "*.ndjson", "*.parquet"];
+
+/// Directories and file name globs whose changes are ingested as sources.
+#[derive(Debug, Clone, Default)]
+pub struct WatchRegistry {
+    entries: Vec<(PathBuf, glob::Pattern)>,
+}
+
+impl WatchRegistry {
+    /// The data dir with [`DEFAULT_SOURCE_PATTERNS`], plus the user's entries.
+    /// Entries whose pattern no longer parses are skipped.
+    pub fn new(data_dir: &Path, paths: &[WatchPath]) -> Self {
+        let mut registry = Self::default();
+        for pattern in DEFAULT_SOURCE_PATTERNS {
+            registry.add(data_dir, pattern);
+        }
+        for path in paths {
+            registry.add(Path::new(&path.dir), &path.pattern);
+        }
+        registry
+    }
+
+    fn add(&mut self, dir: &Path, pattern: &str) {
+        match glob::Pattern::new(pattern) {
+            Ok(pattern) => self.entries.push((dir.to_path_buf(), pattern)),
+            Err(e) => tracing::warn!(pattern, error = %e, "Ignoring invalid watch pattern"),
+        }
+    }
+
+    /// Whether `path` sits directly in a watched dir and matches its pattern.
+    pub fn matches(&self, path: &Path) -> bool {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        let name = name.to_string_lossy();
+        self.entries
+            .iter()
+            .any(|(dir, pattern)| dir == parent && pattern.matches(&name))
+    }
+
+    /// Distinct directories to register with the OS watcher.
+    pub fn dirs(&self) -> BTreeSet<&Path> {
+        self.entries.iter().map(|(dir, _)| dir.as_path()).collect()
+    }
+}
+
+/// A user-registered watch directory and file name glob.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchPath {
+    pub id: i64,
+    pub dir: String,
+    pub pattern: String,
+}
+
+pub fn classify_event(
+    event: &Event,
+    config_path: &std::path::Path,
+    registry: &WatchRegistry,
+) -> Option<WatchEvent> {
     match event.kind {
         EventKind::Modify(_) | EventKind::Create(_) => {
             for path in &event.paths {
                 if path == config_path {
                     return Some(WatchEvent::ConfigChanged);
                 }
-                if path.extension().is_some_and(|ext| ext == "csv") {
+                if registry.matches(path) {
                     return Some(WatchEvent::SourceFileChanged {
                         path: path.clone(),
                     });
@@ -29,10 +130,11 @@ pub fn classify_event(event: &Event, config_path: &std::path::Path) -> Option<Wa
 pub fn create_watcher(
     tx: mpsc::Sender<WatchEvent>,
     config_path: PathBuf,
+    registry: WatchRegistry,
 ) -> Result<RecommendedWatcher, notify::Error> {
     let watcher = notify::recommended_watcher(move |res: Result<Event, notify::Error>| {
         if let Ok(event) = res {
-            if let Some(watch_event) = classify_event(&event, &config_path) {
+            if let Some(watch_event) = classify_event(&event, &config_path, &registry) {
                 let _ = tx.send(watch_event);
             }
         }
@@ -40,6 +142,197 @@ pub fn create_watcher(
     Ok(watcher)
 }
 
+/// Watch events collected over one debounce window, deduplicated by path.
+#[derive(Debug, Default, PartialEq)]
+pub struct EventBatch {
+    pub config_changed: bool,
+    pub source_files: BTreeSet<PathBuf>,
+}
+
+impl EventBatch {
+    fn add(&mut self, event: WatchEvent) {
+        match event {
+            WatchEvent::ConfigChanged => self.config_changed = true,
+            WatchEvent::SourceFileChanged { path } => {
+                self.source_files.insert(path);
+            }
+        }
+    }
+}
+
+/// Block for the next event, then collect more until `window` passes without
+/// one (or `MAX_BATCH_WINDOWS` windows in total). `None` once the sender is gone.
+pub fn next_batch(rx: &mpsc::Receiver<WatchEvent>, window: Duration) -> Option<EventBatch> {
+    let mut batch = EventBatch::default();
+    batch.add(rx.recv().ok()?);
+    let deadline = Instant::now() + window * MAX_BATCH_WINDOWS;
+    loop {
+        let wait = window.min(deadline.saturating_duration_since(Instant::now()));
+        match rx.recv_timeout(wait) {
+            Ok(event) => batch.add(event),
+            Err(_) => return Some(batch),
+        }
+    }
+}
+
+/// Whether the watcher is running, and on which directory.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatcherStatus {
+    pub running: bool,
+    pub dir: Option<String>,
+    pub debounce_ms: u64,
+}
+
+struct ActiveWatcher {
+    dir: PathBuf,
+    // Dropping the watcher closes the channel, which ends the event thread
+    _watcher: RecommendedWatcher,
+}
+
+/// Managed state owning the data dir watcher.
+pub struct FileWatcher {
+    active: Mutex<Option<ActiveWatcher>>,
+    /// Shared with the event thread so config changes apply without a restart.
+    debounce_ms: Arc<AtomicU64>,
+}
+
+impl FileWatcher {
+    pub fn new() -> Self {
+        Self {
+            active: Mutex::new(None),
+            debounce_ms: Arc::new(AtomicU64::new(WatcherConfig::default().debounce_ms)),
+        }
+    }
+
+    pub fn status(&self) -> WatcherStatus {
+        let active = self.active.lock().unwrap_or_else(|e| e.into_inner());
+        WatcherStatus {
+            running: active.is_some(),
+            dir: active.as_ref().map(|a| a.dir.display().to_string()),
+            debounce_ms: self.debounce_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn set_config(&self, config: WatcherConfig) {
+        self.debounce_ms.store(config.debounce_ms, Ordering::Relaxed);
+    }
+
+    /// Watch `dir`, replacing any watcher already running.
+    pub fn start(&self, app: tauri::AppHandle, dir: &Path) -> Result<WatcherStatus, String> {
+        self.stop();
+        let pool = app.state::<WorkspaceDb>().pool();
+        if let Ok(app_config) = config_effective_db(&pool) {
+            self.set_config(watcher_config(&app_config));
+        }
+        let registry = WatchRegistry::new(dir, &watch_paths_list_db(&pool)?);
+        let dirs: Vec<PathBuf> = registry.dirs().into_iter().map(Path::to_path_buf).collect();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = create_watcher(tx, dir.join(CONFIG_FILE), registry)
+            .map_err(|e| format!("Failed to create file watcher: {}", e))?;
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+        for extra in dirs.iter().filter(|d| d.as_path() != dir) {
+            // A missing user dir should not stop the rest from being watched
+            if let Err(e) = watcher.watch(extra, RecursiveMode::NonRecursive) {
+                tracing::warn!(dir = %extra.display(), error = %e, "Failed to watch directory");
+            }
+        }
+        let debounce_ms = Arc::clone(&self.debounce_ms);
+        std::thread::Builder::new()
+            .name("finwatch-watcher".to_string())
+            .spawn(move || handle_events(app, rx, debounce_ms))
+            .map_err(|e| format!("Failed to spawn watcher thread: {}", e))?;
+        tracing::info!(dir = %dir.display(), "File watcher started");
+        *self.active.lock().unwrap_or_else(|e| e.into_inner()) = Some(ActiveWatcher {
+            dir: dir.to_path_buf(),
+            _watcher: watcher,
+        });
+        Ok(self.status())
+    }
+
+    /// Stop watching. Returns whether a watcher was running.
+    pub fn stop(&self) -> bool {
+        let previous = self.active.lock().unwrap_or_else(|e| e.into_inner()).take();
+        if let Some(previous) = &previous {
+            tracing::info!(dir = %previous.dir.display(), "File watcher stopped");
+        }
+        previous.is_some()
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Handle debounced batches until the watcher is dropped.
+fn handle_events(app: tauri::AppHandle, rx: mpsc::Receiver<WatchEvent>, debounce_ms: Arc<AtomicU64>) {
+    let mut last_config: Option<String> = None;
+    loop {
+        let window = Duration::from_millis(debounce_ms.load(Ordering::Relaxed));
+        let Some(batch) = next_batch(&rx, window) else {
+            return;
+        };
+        if batch.config_changed {
+            reload_config(&app, &mut last_config);
+        }
+        for path in batch.source_files {
+            ingest_csv(&app, &path);
+        }
+    }
+}
+
+fn ingest_csv(app: &tauri::AppHandle, path: &Path) {
+    let pool = app.state::<WorkspaceDb>().pool();
+    let config = match config_effective_db(&pool) {
+        Ok(app_config) => csv_source_config(&app_config),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to load config for CSV ingestion");
+            return;
+        }
+    };
+    if !config.enabled || !path.is_file() {
+        return;
+    }
+    match ingest_file(&pool, path, &config) {
+        Ok(ingest) => {
+            let report = publish_ingest(app, ingest);
+            match &report.error {
+                None => tracing::info!(
+                    source = report.source_id,
+                    imported = report.imported,
+                    skipped = report.skipped,
+                    "Ingested CSV file"
+                ),
+                Some(e) => tracing::warn!(source = report.source_id, error = %e, "CSV ingestion failed"),
+            }
+        }
+        Err(e) => tracing::warn!(error = %e, path = %path.display(), "Failed to record CSV ingestion"),
+    }
+}
+
+fn reload_config(app: &tauri::AppHandle, last_config: &mut Option<String>) {
+    let workspace = app.state::<WorkspaceDb>();
+    let path = workspace.dir().join(CONFIG_FILE);
+    let Ok(contents) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    if last_config.as_deref() == Some(contents.as_str()) {
+        return;
+    }
+    match config_reload_file_db(&workspace.pool(), &path) {
+        Ok(diff) => {
+            tracing::info!(path = %path.display(), "Reloaded config file");
+            *last_config = Some(contents);
+            publish_config_change(app, &app.state::<SidecarBridge>(), diff);
+        }
+        Err(e) => tracing::warn!(error = %e, "Ignoring invalid config file"),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,6 +346,14 @@ mod tests {
         }
     }
 
+    fn registry() -> WatchRegistry {
+        WatchRegistry::new(Path::new("/home/user/.finwatch"), &[WatchPath {
+            id: 1,
+            dir: "/home/user/data".to_string(),
+            pattern: "*.csv".to_string(),
+        }])
+    }
+
     #[test]
     fn classify_config_modify() {
         let config = PathBuf::from("/home/user/.finwatch/config.json");
@@ -60,7 +361,7 @@ mod tests {
             EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Content)),
             vec![config.clone()],
         );
-        match classify_event(&event, &config) {
+        match classify_event(&event, &config, &registry()) {
             Some(WatchEvent::ConfigChanged) => {}
             other => panic!("Expected ConfigChanged, got {:?}", other.is_some()),
         }
@@ -71,7 +372,7 @@ mod tests {
         let config = PathBuf::from("/home/user/.finwatch/config.json");
         let csv = PathBuf::from("/home/user/data/trades.csv");
         let event = make_event(EventKind::Create(CreateKind::File), vec![csv.clone()]);
-        match classify_event(&event, &config) {
+        match classify_event(&event, &config, &registry()) {
             Some(WatchEvent::SourceFileChanged { path }) => assert_eq!(path, csv),
             other => panic!("Expected SourceFileChanged, got {:?}", other.is_some()),
         }
@@ -84,7 +385,7 @@ mod tests {
             EventKind::Remove(notify::event::RemoveKind::File),
             vec![config.clone()],
         );
-        assert!(classify_event(&event, &config).is_none());
+        assert!(classify_event(&event, &config, &registry()).is_none());
     }
 
     #[test]
@@ -95,14 +396,100 @@ mod tests {
             EventKind::Modify(ModifyKind::Data(notify::event::DataChange::Content)),
             vec![txt],
         );
-        assert!(classify_event(&event, &config).is_none());
+        assert!(classify_event(&event, &config, &registry()).is_none());
     }
 
     #[test]
     fn create_watcher_compiles() {
         let (tx, _rx) = mpsc::channel();
         let config = PathBuf::from("/tmp/test-config.json");
-        let result = create_watcher(tx, config);
+        let result = create_watcher(tx, config, WatchRegistry::default());
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn file_watcher_starts_stopped() {
+        let watcher = FileWatcher::new();
+        assert_eq!(
+            watcher.status(),
+            WatcherStatus {
+                running: false,
+                dir: None,
+                debounce_ms: 500,
+            }
+        );
+        assert!(!watcher.stop());
+    }
+
+    #[test]
+    fn next_batch_dedups_a_burst() {
+        let (tx, rx) = mpsc::channel();
+        let csv = PathBuf::from("/data/trades.csv");
+        for _ in 0..3 {
+            tx.send(WatchEvent::ConfigChanged).unwrap();
+            tx.send(WatchEvent::SourceFileChanged { path: csv.clone() }).unwrap();
+        }
+        tx.send(WatchEvent::SourceFileChanged {
+            path: PathBuf::from("/data/quotes.csv"),
+        })
+        .unwrap();
+
+        let batch = next_batch(&rx, Duration::from_millis(10)).unwrap();
+        assert!(batch.config_changed);
+        assert_eq!(batch.source_files.len(), 2);
+        assert!(batch.source_files.contains(&csv));
+
+        drop(tx);
+        assert_eq!(next_batch(&rx, Duration::from_millis(10)), None);
+    }
+
+    #[test]
+    fn next_batch_splits_events_separated_by_the_window() {
+        let (tx, rx) = mpsc::channel();
+        let sender = std::thread::spawn(move || {
+            tx.send(WatchEvent::ConfigChanged).unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+            tx.send(WatchEvent::ConfigChanged).unwrap();
+        });
+        assert!(next_batch(&rx, Duration::from_millis(20)).unwrap().config_changed);
+        assert!(next_batch(&rx, Duration::from_millis(20)).unwrap().config_changed);
+        sender.join().unwrap();
+    }
+
+    #[test]
+    fn watcher_config_defaults_to_500ms() {
+        assert_eq!(watcher_config(&serde_json::json!({})).debounce_ms, 500);
+        let config = watcher_config(&serde_json::json!({"watcher": {"debounceMs": 50}}));
+        assert_eq!(config.debounce_ms, 50);
+    }
+
+    #[test]
+    fn registry_matches_by_dir_and_glob() {
+        let registry = WatchRegistry::new(Path::new("/fw"), &[WatchPath {
+            id: 1,
+            dir: "/feeds".to_string(),
+            pattern: "trades-*.jsonl".to_string(),
+        }]);
+        assert!(registry.matches(Path::new("/fw/prices.csv")));
+        assert!(registry.matches(Path::new("/feeds/trades-2024.jsonl")));
+        assert!(!registry.matches(Path::new("/feeds/prices.csv")));
+        assert!(!registry.matches(Path::new("/fw/sub/prices.csv")));
+        assert_eq!(registry.dirs().len(), 2);
+    }
+
+    #[test]
+    fn classify_uses_registry_patterns() {
+        let config = PathBuf::from("/fw/config.json");
+        let registry = WatchRegistry::new(Path::new("/fw"), &[WatchPath {
+            id: 1,
+            dir: "/fw".to_string(),
+            pattern: "*.jsonl".to_string(),
+        }]);
+        let jsonl = PathBuf::from("/fw/ticks.jsonl");
+        let event = make_event(EventKind::Create(CreateKind::File), vec![jsonl.clone()]);
+        assert_eq!(
+            classify_event(&event, &config, &registry),
+            Some(WatchEvent::SourceFileChanged { path: jsonl })
+        );
+    }
 }
@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::commands::anomalies::anomalies_insert_db;
+use crate::commands::ticks::ticks_insert_db;
+use crate::db::DbPool;
+use crate::events::{emit_event, event_names};
+use crate::loopback::{Body, HttpError, HttpRequest, ListenerStatus, LoopbackServer};
+use crate::types::anomaly::Anomaly;
+use crate::types::data::DataTick;
+use crate::workspace::WorkspaceDb;
+
+/// The `webhook` section of the app config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WebhookConfig {
+    /// Listen on localhost for POSTed ticks and anomalies.
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 8787,
+        }
+    }
+}
+
+/// Parse the `webhook` section of the app config.
+pub fn webhook_config(app_config: &serde_json::Value) -> WebhookConfig {
+    app_config
+        .get("webhook")
+        .cloned()
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default()
+}
+
+/// The stored webhook token, creating one on first use.
+pub fn ensure_token() -> Result<String, String> {
+    crate::loopback::ensure_token(crate::keychain::webhook_token_get, crate::keychain::webhook_token_set)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany<T> {
+    Many(Vec<T>),
+    One(T),
+}
+
+fn parse_items<T: serde::de::DeserializeOwned>(body: &[u8]) -> Result<Vec<T>, HttpError> {
+    let items = match serde_json::from_slice::<OneOrMany<T>>(body) {
+        Ok(OneOrMany::Many(items)) => items,
+        Ok(OneOrMany::One(item)) => vec![item],
+        Err(e) => return Err(HttpError(400, format!("Invalid JSON: {}", e))),
+    };
+    if items.is_empty() {
+        return Err(HttpError(400, "Nothing to ingest".to_string()));
+    }
+    Ok(items)
+}
+
+fn validate_tick(tick: &DataTick) -> Result<(), String> {
+    if tick.source_id.trim().is_empty() {
+        return Err("tick sourceId must not be empty".to_string());
+    }
+    if tick.metrics.is_empty() {
+        return Err("tick must have at least one metric".to_string());
+    }
+    if let Some((name, _)) = tick.metrics.iter().find(|(_, v)| !v.is_finite()) {
+        return Err(format!("tick metric '{}' is not a finite number", name));
+    }
+    Ok(())
+}
+
+fn validate_anomaly(anomaly: &Anomaly) -> Result<(), String> {
+    if anomaly.id.trim().is_empty() || anomaly.source.trim().is_empty() {
+        return Err("anomaly id and source must not be empty".to_string());
+    }
+    if anomaly.description.trim().is_empty() {
+        return Err("anomaly description must not be empty".to_string());
+    }
+    Ok(())
+}
+
+/// What a request delivered, once validated.
+#[derive(Debug, Clone)]
+pub enum Ingested {
+    Ticks(Vec<DataTick>),
+    Anomalies(Vec<Anomaly>),
+}
+
+impl Ingested {
+    pub fn len(&self) -> usize {
+        match self {
+            Ingested::Ticks(t) => t.len(),
+            Ingested::Anomalies(a) => a.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Route and validate an authorized request: `POST /ticks` or `POST /anomalies`,
+/// each taking one JSON object or an array of them.
+pub fn parse_ingest(request: &HttpRequest) -> Result<Ingested, HttpError> {
+    let ingested = match request.path.as_str() {
+        "/ticks" | "/anomalies" if request.method != "POST" => {
+            return Err(HttpError(405, "Use POST".to_string()))
+        }
+        "/ticks" => Ingested::Ticks(parse_items(&request.body)?),
+        "/anomalies" => Ingested::Anomalies(parse_items(&request.body)?),
+        _ => return Err(HttpError(404, "Not found".to_string())),
+    };
+    let invalid = match &ingested {
+        Ingested::Ticks(ticks) => ticks.iter().map(validate_tick).find_map(Result::err),
+        Ingested::Anomalies(anomalies) => anomalies.iter().map(validate_anomaly).find_map(Result::err),
+    };
+    match invalid {
+        Some(e) => Err(HttpError(422, e)),
+        None => Ok(ingested),
+    }
+}
+
+/// Store everything in `ingested`.
+pub fn persist(pool: &DbPool, ingested: &Ingested) -> Result<(), String> {
+    match ingested {
+        Ingested::Ticks(ticks) => ticks.iter().try_for_each(|t| ticks_insert_db(pool, t)),
+        Ingested::Anomalies(anomalies) => anomalies.iter().try_for_each(|a| anomalies_insert_db(pool, a)),
+    }
+}
+
+fn publish<R: Runtime>(app: &AppHandle<R>, ingested: &Ingested) {
+    match ingested {
+        Ingested::Ticks(ticks) => {
+            for tick in ticks {
+                let _ = emit_event(app, event_names::DATA_TICK, tick);
+            }
+            crate::alerts::check_ticks(app, ticks);
+        }
+        Ingested::Anomalies(anomalies) => {
+            for anomaly in anomalies {
+                let _ = emit_event(app, event_names::ANOMALY_DETECTED, anomaly);
+                if let Ok(payload) = serde_json::to_value(anomaly) {
+                    crate::anomaly_notifier::notify_anomaly(app, &payload);
+                }
+            }
+        }
+    }
+}
+
+fn handle<R: Runtime>(app: &AppHandle<R>, request: &HttpRequest) -> Result<Body, HttpError> {
+    let ingested = parse_ingest(request)?;
+    let pool = app.state::<WorkspaceDb>().pool();
+    persist(&pool, &ingested).map_err(|e| HttpError(500, e))?;
+    publish(app, &ingested);
+    Ok(Body::Json(serde_json::json!({ "accepted": ingested.len() })))
+}
+
+pub type WebhookStatus = ListenerStatus;
+
+/// Managed state owning the localhost webhook listener.
+pub struct WebhookServer(LoopbackServer);
+
+impl WebhookServer {
+    pub fn new() -> Self {
+        Self(LoopbackServer::new("webhook"))
+    }
+
+    pub fn status(&self) -> WebhookStatus {
+        self.0.status()
+    }
+
+    /// Listen on `127.0.0.1:port` with `token`; see [`LoopbackServer::start`].
+    pub fn start<R: Runtime>(&self, app: AppHandle<R>, port: u16, token: String) -> Result<WebhookStatus, String> {
+        self.0.start(port, token, move |request| handle(&app, request))
+    }
+
+    /// Replace the token a running listener checks, without rebinding.
+    pub fn set_token(&self, token: String) {
+        self.0.set_token(token)
+    }
+
+    /// Stop listening. Returns whether a listener was running.
+    pub fn stop(&self) -> bool {
+        self.0.stop()
+    }
+
+    /// Start or stop the listener to match `config`.
+    pub fn apply<R: Runtime>(&self, app: AppHandle<R>, config: WebhookConfig) -> Result<WebhookStatus, String> {
+        self.0.apply(config.enabled, config.port, ensure_token, move |request| {
+            handle(&app, request)
+        })
+    }
+}
+
+impl Default for WebhookServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loopback::read_request;
+
+    fn request(method: &str, path: &str, auth: Option<&str>, body: &str) -> String {
+        let auth = auth.map_or(String::new(), |t| format!("Authorization: Bearer {}\r\n", t));
+        format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}Content-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            auth,
+            body.len(),
+            body
+        )
+    }
+
+    fn parse(raw: &str) -> Result<HttpRequest, HttpError> {
+        read_request(&mut raw.as_bytes())
+    }
+
+    const TICK: &str = r#"{"sourceId":"py","timestamp":1700000000000,"symbol":"AAPL",
+        "metrics":{"close":190.5},"metadata":{},"raw":null}"#;
+
+    #[test]
+    fn parses_single_and_batched_payloads() {
+        let one = parse(&request("POST", "/ticks", None, TICK)).unwrap();
+        assert_eq!(parse_ingest(&one).unwrap().len(), 1);
+        let many = parse(&request("POST", "/ticks", None, &format!("[{},{}]", TICK, TICK))).unwrap();
+        assert_eq!(parse_ingest(&many).unwrap().len(), 2);
+
+        let anomaly = r#"{"id":"ext-1","severity":"high","source":"py","symbol":"AAPL",
+            "timestamp":1700000000000,"description":"Spike","metrics":{},
+            "preScreenScore":0.9,"sessionId":"external"}"#;
+        let req = parse(&request("POST", "/anomalies", None, anomaly)).unwrap();
+        assert!(matches!(parse_ingest(&req).unwrap(), Ingested::Anomalies(a) if a[0].id == "ext-1"));
+    }
+
+    #[test]
+    fn routing_and_validation_errors() {
+        let status = |raw: String| parse_ingest(&parse(&raw).unwrap()).unwrap_err().0;
+        assert_eq!(status(request("GET", "/ticks", None, "")), 405);
+        assert_eq!(status(request("POST", "/nope", None, TICK)), 404);
+        assert_eq!(status(request("POST", "/ticks", None, "{")), 400);
+        assert_eq!(status(request("POST", "/ticks", None, "[]")), 400);
+        let no_metrics = r#"{"sourceId":"py","timestamp":1,"symbol":null,"metrics":{},"metadata":{},"raw":null}"#;
+        assert_eq!(status(request("POST", "/ticks", None, no_metrics)), 422);
+    }
+
+    #[test]
+    fn persists_ticks() {
+        let dir = tempfile::tempdir().unwrap();
+        let pool = crate::db::create_pool(&dir.path().join("test.sqlite")).unwrap();
+        crate::db::init_db(&pool).unwrap();
+        crate::migrations::run_pending(&pool).unwrap();
+        let ingested = parse_ingest(&parse(&request("POST", "/ticks", None, TICK)).unwrap()).unwrap();
+        persist(&pool, &ingested).unwrap();
+        let range = crate::types::data::TickRange::default();
+        let stored = crate::commands::ticks::ticks_query_db(&pool, "AAPL", &range).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].source_id, "py");
+    }
+}
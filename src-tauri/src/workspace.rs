@@ -0,0 +1,279 @@
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::Serialize;
+
+use crate::db::{self, DbPool};
+use crate::migrations;
+
+/// Workspace used when none has been selected. Its data lives directly in the
+/// root data dir so installs predating workspaces keep their history.
+pub const DEFAULT_WORKSPACE: &str = "default";
+/// File in the root data dir recording the active workspace name.
+const ACTIVE_FILE: &str = "active-workspace";
+/// Subdirectory of the root data dir holding non-default workspaces.
+const WORKSPACES_DIR: &str = "workspaces";
+
+/// A workspace as listed by `workspace_list`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceInfo {
+    pub name: String,
+    pub active: bool,
+    pub path: String,
+}
+
+/// Workspace names become directory names: letters, digits, `-` and `_` only.
+pub fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 {
+        return Err("Workspace name must be 1-64 characters".to_string());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "Invalid workspace name '{}': use letters, digits, '-' or '_'",
+            name
+        ));
+    }
+    Ok(())
+}
+
+/// Data directory of a workspace under `root`.
+pub fn workspace_dir(root: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_WORKSPACE {
+        root.to_path_buf()
+    } else {
+        root.join(WORKSPACES_DIR).join(name)
+    }
+}
+
+/// SQLite file of the workspace stored in `dir`.
+pub fn db_path(dir: &Path) -> PathBuf {
+    dir.join("state").join("finwatch.sqlite")
+}
+
+/// Name of the active workspace, falling back to the default when unset or invalid.
+pub fn read_active(root: &Path) -> String {
+    std::fs::read_to_string(root.join(ACTIVE_FILE))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|name| validate_name(name).is_ok() && workspace_dir(root, name).is_dir())
+        .unwrap_or_else(|| DEFAULT_WORKSPACE.to_string())
+}
+
+fn write_active(root: &Path, name: &str) -> Result<(), String> {
+    std::fs::create_dir_all(root).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    std::fs::write(root.join(ACTIVE_FILE), name)
+        .map_err(|e| format!("Failed to record active workspace: {}", e))
+}
+
+/// All workspaces under `root`, default first, then by name.
+pub fn list(root: &Path) -> Result<Vec<WorkspaceInfo>, String> {
+    let active = read_active(root);
+    let mut names = vec![DEFAULT_WORKSPACE.to_string()];
+    match std::fs::read_dir(root.join(WORKSPACES_DIR)) {
+        Ok(entries) => {
+            let mut others: Vec<String> = entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.path().is_dir())
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|n| validate_name(n).is_ok() && n != DEFAULT_WORKSPACE)
+                .collect();
+            others.sort();
+            names.extend(others);
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(format!("Failed to list workspaces: {}", e)),
+    }
+    Ok(names
+        .into_iter()
+        .map(|name| WorkspaceInfo {
+            active: name == active,
+            path: workspace_dir(root, &name).display().to_string(),
+            name,
+        })
+        .collect())
+}
+
+/// Create an empty workspace directory with an initialized database.
+pub fn create(root: &Path, name: &str) -> Result<WorkspaceInfo, String> {
+    validate_name(name)?;
+    let dir = workspace_dir(root, name);
+    if name == DEFAULT_WORKSPACE || dir.exists() {
+        return Err(format!("Workspace '{}' already exists", name));
+    }
+    open_pool(&dir)?;
+    Ok(WorkspaceInfo {
+        name: name.to_string(),
+        active: false,
+        path: dir.display().to_string(),
+    })
+}
+
+/// Open, initialize, and migrate the database of the workspace in `dir`.
+pub fn open_pool(dir: &Path) -> Result<DbPool, String> {
+    let pool = db::create_pool(&db_path(dir))
+        .map_err(|e| format!("Failed to open workspace database: {}", e))?;
+    db::init_db(&pool).map_err(|e| format!("Failed to initialize database: {}", e))?;
+    migrations::run_pending(&pool).map_err(|e| format!("Failed to run migrations: {}", e))?;
+    Ok(pool)
+}
+
+struct ActiveWorkspace {
+    name: String,
+    pool: DbPool,
+}
+
+/// Managed state holding the pool of the active workspace. Commands take a
+/// cheap clone of the pool per call, so a switch never invalidates a query
+/// already in flight.
+pub struct WorkspaceDb {
+    root: PathBuf,
+    active: RwLock<ActiveWorkspace>,
+}
+
+impl WorkspaceDb {
+    /// Open the workspace recorded as active under `root`.
+    pub fn open(root: PathBuf) -> Result<Self, String> {
+        let name = read_active(&root);
+        let pool = open_pool(&workspace_dir(&root, &name))?;
+        Ok(Self {
+            root,
+            active: RwLock::new(ActiveWorkspace { name, pool }),
+        })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Pool of the active workspace.
+    pub fn pool(&self) -> DbPool {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .pool
+            .clone()
+    }
+
+    pub fn name(&self) -> String {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .name
+            .clone()
+    }
+
+    /// Directory of the active workspace.
+    pub fn dir(&self) -> PathBuf {
+        workspace_dir(&self.root, &self.name())
+    }
+
+    /// Make `name` the active workspace, checkpointing the previous database.
+    pub fn switch(&self, name: &str) -> Result<WorkspaceInfo, String> {
+        validate_name(name)?;
+        let dir = workspace_dir(&self.root, name);
+        if !dir.is_dir() {
+            return Err(format!("Workspace '{}' does not exist", name));
+        }
+        let pool = open_pool(&dir)?;
+        let previous = {
+            let mut active = self.active.write().unwrap_or_else(|e| e.into_inner());
+            std::mem::replace(
+                &mut *active,
+                ActiveWorkspace {
+                    name: name.to_string(),
+                    pool,
+                },
+            )
+        };
+        if let Err(e) = db::checkpoint(&previous.pool) {
+            tracing::warn!(error = %e, workspace = previous.name, "Failed to checkpoint previous workspace");
+        }
+        write_active(&self.root, name)?;
+        tracing::info!(from = previous.name, to = name, "Switched workspace");
+        Ok(WorkspaceInfo {
+            name: name.to_string(),
+            active: true,
+            path: dir.display().to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_names() {
+        assert!(validate_name("paper-2026_q1").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../escape").is_err());
+        assert!(validate_name("has space").is_err());
+    }
+
+    #[test]
+    fn default_workspace_lives_in_root() {
+        let root = Path::new("/tmp/fw");
+        assert_eq!(workspace_dir(root, DEFAULT_WORKSPACE), root);
+        assert_eq!(workspace_dir(root, "live"), root.join("workspaces").join("live"));
+    }
+
+    #[test]
+    fn list_includes_default_and_created() {
+        let root = tempfile::tempdir().unwrap();
+        create(root.path(), "paper").unwrap();
+        create(root.path(), "live").unwrap();
+        let names: Vec<String> = list(root.path()).unwrap().into_iter().map(|w| w.name).collect();
+        assert_eq!(names, vec!["default", "live", "paper"]);
+        assert!(list(root.path()).unwrap()[0].active);
+    }
+
+    #[test]
+    fn create_rejects_duplicates() {
+        let root = tempfile::tempdir().unwrap();
+        create(root.path(), "paper").unwrap();
+        assert!(create(root.path(), "paper").is_err());
+        assert!(create(root.path(), DEFAULT_WORKSPACE).is_err());
+    }
+
+    #[test]
+    fn switch_swaps_pool_and_persists_choice() {
+        let root = tempfile::tempdir().unwrap();
+        let ws = WorkspaceDb::open(root.path().to_path_buf()).unwrap();
+        crate::commands::config::config_set_db(&ws.pool(), r#"{"model":"default"}"#).unwrap();
+
+        create(root.path(), "paper").unwrap();
+        ws.switch("paper").unwrap();
+        assert_eq!(ws.name(), "paper");
+        assert_eq!(crate::commands::config::config_get_db(&ws.pool()).unwrap(), "{}");
+        assert_eq!(read_active(root.path()), "paper");
+
+        // Reopening picks up the persisted active workspace
+        let reopened = WorkspaceDb::open(root.path().to_path_buf()).unwrap();
+        assert_eq!(reopened.name(), "paper");
+
+        ws.switch(DEFAULT_WORKSPACE).unwrap();
+        assert_eq!(
+            crate::commands::config::config_get_db(&ws.pool()).unwrap(),
+            r#"{"model":"default"}"#
+        );
+    }
+
+    #[test]
+    fn switch_to_missing_workspace_fails() {
+        let root = tempfile::tempdir().unwrap();
+        let ws = WorkspaceDb::open(root.path().to_path_buf()).unwrap();
+        assert!(ws.switch("nope").is_err());
+        assert_eq!(ws.name(), DEFAULT_WORKSPACE);
+    }
+
+    #[test]
+    fn stale_active_file_falls_back_to_default() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join(ACTIVE_FILE), "deleted").unwrap();
+        assert_eq!(read_active(root.path()), DEFAULT_WORKSPACE);
+    }
+}
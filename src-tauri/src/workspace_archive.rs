@@ -0,0 +1,358 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::commands::config::{apply_config, parse_config_json, ImportMode};
+use crate::db::DbPool;
+use crate::time::now_millis;
+use crate::workspace::{self, WorkspaceInfo};
+
+/// Bumped when the archive layout changes incompatibly.
+pub const ARCHIVE_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const DB_ENTRY: &str = "state/finwatch.sqlite";
+const CONFIG_ENTRY: &str = "config.json";
+/// Minimum time between scheduled backups of one workspace.
+const BACKUP_INTERVAL_MS: i64 = 24 * 60 * 60 * 1000;
+/// Scheduled backups kept per workspace.
+pub const BACKUPS_KEPT: usize = 7;
+
+/// Describes an exported archive; stored as `manifest.json` inside it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveManifest {
+    pub version: u32,
+    pub workspace: String,
+    pub app_version: String,
+    /// Epoch millis.
+    pub exported_at: i64,
+}
+
+/// Write the workspace database and its config to a zip at `dest`. Agent logs
+/// are shared by all workspaces and credentials live in the OS keychain, so
+/// neither is included.
+pub fn export(pool: &DbPool, workspace: &str, dest: &Path) -> Result<ArchiveManifest, String> {
+    // VACUUM INTO gives a consistent single-file copy even while the WAL is live
+    let mut snapshot = dest.as_os_str().to_owned();
+    snapshot.push(".sqlite-snapshot");
+    let snapshot = PathBuf::from(snapshot);
+    let _ = std::fs::remove_file(&snapshot);
+    let result =
+        snapshot_db(pool, &snapshot).and_then(|()| write_archive(pool, workspace, &snapshot, dest));
+    let _ = std::fs::remove_file(&snapshot);
+    result
+}
+
+fn snapshot_db(pool: &DbPool, snapshot: &Path) -> Result<(), String> {
+    let conn = pool.get().map_err(|e| e.to_string())?;
+    conn.execute("VACUUM INTO ?1", [snapshot.to_string_lossy()])
+        .map_err(|e| format!("Failed to snapshot database: {}", e))?;
+    Ok(())
+}
+
+fn write_archive(
+    pool: &DbPool,
+    workspace: &str,
+    snapshot: &Path,
+    dest: &Path,
+) -> Result<ArchiveManifest, String> {
+    let config = crate::commands::config::config_get_db(pool)?;
+    let manifest = ArchiveManifest {
+        version: ARCHIVE_VERSION,
+        workspace: workspace.to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        exported_at: now_millis() as i64,
+    };
+
+    let file = File::create(dest).map_err(|e| format!("Failed to create archive: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let manifest_json = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+    write_entry(&mut zip, MANIFEST_ENTRY, options, &mut manifest_json.as_slice())?;
+    write_entry(&mut zip, CONFIG_ENTRY, options, &mut config.as_bytes())?;
+    let mut db_file = File::open(snapshot).map_err(|e| e.to_string())?;
+    write_entry(&mut zip, DB_ENTRY, options, &mut db_file)?;
+    zip.finish()
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok(manifest)
+}
+
+fn write_entry<W: Write + std::io::Seek>(
+    zip: &mut ZipWriter<W>,
+    name: &str,
+    options: SimpleFileOptions,
+    reader: &mut dyn Read,
+) -> Result<(), String> {
+    zip.start_file(name, options)
+        .map_err(|e| format!("Failed to write archive: {}", e))?;
+    std::io::copy(reader, zip).map_err(|e| format!("Failed to write archive: {}", e))?;
+    Ok(())
+}
+
+/// Read the manifest of an archive without extracting it.
+pub fn read_manifest(src: &Path) -> Result<ArchiveManifest, String> {
+    let mut archive = open_archive(src)?;
+    let entry = archive
+        .by_name(MANIFEST_ENTRY)
+        .map_err(|_| "Not a FinWatch workspace archive".to_string())?;
+    let manifest: ArchiveManifest =
+        serde_json::from_reader(entry).map_err(|e| format!("Invalid archive manifest: {}", e))?;
+    if manifest.version > ARCHIVE_VERSION {
+        return Err(format!(
+            "Archive version {} is newer than supported version {}",
+            manifest.version, ARCHIVE_VERSION
+        ));
+    }
+    Ok(manifest)
+}
+
+/// Restore an archive into a new workspace `name` under `root`. The database is
+/// migrated to the current schema and the archived config is validated and
+/// written over it; on any failure the new workspace is removed.
+pub fn import(root: &Path, name: &str, src: &Path) -> Result<WorkspaceInfo, String> {
+    workspace::validate_name(name)?;
+    let dir = workspace::workspace_dir(root, name);
+    if name == workspace::DEFAULT_WORKSPACE || dir.exists() {
+        return Err(format!("Workspace '{}' already exists", name));
+    }
+    read_manifest(src)?;
+
+    let result = extract(src, &dir).and_then(|config| {
+        let pool = workspace::open_pool(&dir)?;
+        match config {
+            Some(config) => restore_config(&pool, &config),
+            None => Ok(()),
+        }
+    });
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(e);
+    }
+    Ok(WorkspaceInfo {
+        name: name.to_string(),
+        active: false,
+        path: dir.display().to_string(),
+    })
+}
+
+/// Extract the database into `dir` and return the archived config, if any.
+/// Other entries are skipped, so a crafted archive cannot write outside `dir`.
+fn extract(src: &Path, dir: &Path) -> Result<Option<String>, String> {
+    let mut archive = open_archive(src)?;
+    let config = match archive.by_name(CONFIG_ENTRY) {
+        Ok(mut entry) => {
+            let mut config = String::new();
+            entry
+                .read_to_string(&mut config)
+                .map_err(|e| format!("Failed to extract {}: {}", CONFIG_ENTRY, e))?;
+            Some(config)
+        }
+        Err(_) => None,
+    };
+    let mut entry = archive
+        .by_name(DB_ENTRY)
+        .map_err(|_| "Archive does not contain a database".to_string())?;
+    let target = workspace::db_path(dir);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut out = File::create(&target).map_err(|e| e.to_string())?;
+    std::io::copy(&mut entry, &mut out)
+        .map_err(|e| format!("Failed to extract {}: {}", DB_ENTRY, e))?;
+    Ok(config)
+}
+
+/// Validate the archived config as `config_import` does and make it the
+/// workspace's config.
+fn restore_config(pool: &DbPool, config: &str) -> Result<(), String> {
+    parse_config_json(config, "Archived config")
+        .and_then(|value| apply_config(pool, &value, ImportMode::Replace))
+        .map(|_| ())
+        .map_err(|e| format!("Failed to restore config: {}", e))
+}
+
+/// Directory holding scheduled backups: `~/.finwatch/backups/`.
+pub fn backups_dir() -> PathBuf {
+    crate::db::finwatch_root_dir().join("backups")
+}
+
+/// Scheduled backups of `workspace` in `dir` as (epoch millis, path), oldest first.
+fn backups(dir: &Path, workspace: &str) -> Vec<(i64, PathBuf)> {
+    let prefix = format!("{}-", workspace);
+    let mut found: Vec<(i64, PathBuf)> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let ts = name.strip_prefix(&prefix)?.strip_suffix(".zip")?.parse().ok()?;
+            Some((ts, entry.path()))
+        })
+        .collect();
+    found.sort();
+    found
+}
+
+/// Archive `workspace` to `dir` unless it was backed up in the last day,
+/// keeping the newest `keep` backups. Returns the new archive, if one was made.
+pub fn backup_if_due(
+    pool: &DbPool,
+    workspace: &str,
+    dir: &Path,
+    now_ms: i64,
+    keep: usize,
+) -> Result<Option<PathBuf>, String> {
+    if backups(dir, workspace)
+        .last()
+        .is_some_and(|(ts, _)| now_ms - ts < BACKUP_INTERVAL_MS)
+    {
+        return Ok(None);
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create backup dir: {}", e))?;
+    let dest = dir.join(format!("{}-{}.zip", workspace, now_ms));
+    export(pool, workspace, &dest)?;
+    let existing = backups(dir, workspace);
+    for (_, old) in existing.iter().take(existing.len().saturating_sub(keep)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(Some(dest))
+}
+
+fn open_archive(src: &Path) -> Result<ZipArchive<File>, String> {
+    let file = File::open(src).map_err(|e| format!("Failed to open archive: {}", e))?;
+    ZipArchive::new(file).map_err(|e| format!("Invalid archive: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::config::{config_get_db, config_set_db};
+
+    /// Write an archive holding a fresh database and `config` as its config entry.
+    fn archive_with_config(dir: &Path, config: &str) -> PathBuf {
+        let pool = workspace::open_pool(dir).unwrap();
+        let snapshot = dir.join("snap.sqlite");
+        snapshot_db(&pool, &snapshot).unwrap();
+        let archive = dir.join("crafted.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        let manifest = serde_json::json!({
+            "version": 1, "workspace": "x", "appVersion": "0", "exportedAt": 0
+        });
+        zip.start_file(MANIFEST_ENTRY, options).unwrap();
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.start_file(CONFIG_ENTRY, options).unwrap();
+        zip.write_all(config.as_bytes()).unwrap();
+        zip.start_file(DB_ENTRY, options).unwrap();
+        zip.write_all(&std::fs::read(&snapshot).unwrap()).unwrap();
+        zip.finish().unwrap();
+        archive
+    }
+
+    #[test]
+    fn export_then_import_round_trips_state() {
+        let source_root = tempfile::tempdir().unwrap();
+        let pool = workspace::open_pool(source_root.path()).unwrap();
+        config_set_db(&pool, r#"{"model":"m"}"#).unwrap();
+
+        let out = tempfile::tempdir().unwrap();
+        let archive = out.path().join("backup.zip");
+        export(&pool, "default", &archive).unwrap();
+        assert_eq!(read_manifest(&archive).unwrap().workspace, "default");
+        let archived = open_archive(&archive).unwrap();
+        assert!(!archived.file_names().any(|name| name.starts_with("logs/")));
+
+        let target_root = tempfile::tempdir().unwrap();
+        let info = import(target_root.path(), "restored", &archive).unwrap();
+        let restored = workspace::open_pool(Path::new(&info.path)).unwrap();
+        assert_eq!(config_get_db(&restored).unwrap(), r#"{"model":"m"}"#);
+        assert!(!Path::new(&info.path).join("logs").exists());
+    }
+
+    #[test]
+    fn import_restores_the_archived_config() {
+        let source = tempfile::tempdir().unwrap();
+        // The database snapshot holds the default config; config.json wins
+        let archive = archive_with_config(source.path(), r#"{"model":"archived"}"#);
+        let root = tempfile::tempdir().unwrap();
+        let info = import(root.path(), "restored", &archive).unwrap();
+        let restored = workspace::open_pool(Path::new(&info.path)).unwrap();
+        assert_eq!(config_get_db(&restored).unwrap(), r#"{"model":"archived"}"#);
+
+        let other = tempfile::tempdir().unwrap();
+        let invalid = archive_with_config(other.path(), r#"{"feed":"bogus"}"#);
+        let err = import(root.path(), "broken", &invalid).unwrap_err();
+        assert!(err.starts_with("Failed to restore config"), "{}", err);
+        assert!(!workspace::workspace_dir(root.path(), "broken").exists());
+    }
+
+    #[test]
+    fn backups_run_daily_and_prune() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = workspace::open_pool(root.path()).unwrap();
+        let dir = root.path().join("backups");
+        let day = BACKUP_INTERVAL_MS;
+
+        assert!(backup_if_due(&pool, "default", &dir, day, 2).unwrap().is_some());
+        assert!(backup_if_due(&pool, "default", &dir, day + 1_000, 2).unwrap().is_none());
+        assert!(backup_if_due(&pool, "default", &dir, 2 * day, 2).unwrap().is_some());
+        let latest = backup_if_due(&pool, "default", &dir, 3 * day, 2).unwrap().unwrap();
+        assert_eq!(read_manifest(&latest).unwrap().workspace, "default");
+
+        let kept: Vec<i64> = backups(&dir, "default").into_iter().map(|(ts, _)| ts).collect();
+        assert_eq!(kept, vec![2 * day, 3 * day]);
+    }
+
+    #[test]
+    fn import_refuses_existing_workspace() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = workspace::open_pool(root.path()).unwrap();
+        let archive = root.path().join("backup.zip");
+        export(&pool, "default", &archive).unwrap();
+        workspace::create(root.path(), "paper").unwrap();
+        assert!(import(root.path(), "paper", &archive).is_err());
+        assert!(import(root.path(), workspace::DEFAULT_WORKSPACE, &archive).is_err());
+    }
+
+    #[test]
+    fn import_rejects_non_archives_and_cleans_up() {
+        let root = tempfile::tempdir().unwrap();
+        let bogus = root.path().join("bogus.zip");
+        std::fs::write(&bogus, "not a zip").unwrap();
+        assert!(import(root.path(), "restored", &bogus).is_err());
+        assert!(!workspace::workspace_dir(root.path(), "restored").exists());
+    }
+
+    #[test]
+    fn import_ignores_entries_outside_known_paths() {
+        let root = tempfile::tempdir().unwrap();
+        let pool = workspace::open_pool(root.path()).unwrap();
+        let snapshot = root.path().join("snap.sqlite");
+        pool.get()
+            .unwrap()
+            .execute("VACUUM INTO ?1", [snapshot.to_string_lossy()])
+            .unwrap();
+
+        let archive = root.path().join("crafted.zip");
+        let mut zip = ZipWriter::new(File::create(&archive).unwrap());
+        let options = SimpleFileOptions::default();
+        let manifest = serde_json::json!({
+            "version": 1, "workspace": "x", "appVersion": "0", "exportedAt": 0
+        });
+        zip.start_file(MANIFEST_ENTRY, options).unwrap();
+        zip.write_all(manifest.to_string().as_bytes()).unwrap();
+        zip.start_file(DB_ENTRY, options).unwrap();
+        zip.write_all(&std::fs::read(&snapshot).unwrap()).unwrap();
+        zip.start_file("logs/agent/../../escape.txt", options).unwrap();
+        zip.write_all(b"nope").unwrap();
+        zip.finish().unwrap();
+
+        let info = import(root.path(), "restored", &archive).unwrap();
+        assert!(!Path::new(&info.path).join("escape.txt").exists());
+        assert!(!root.path().join("workspaces").join("escape.txt").exists());
+    }
+}
@@ -211,7 +211,10 @@ export default function App() {
             onCredentialsSave={(keyId, secret) => {
               invoke("credentials_set", { mode: "paper", keyId, secretKey: secret });
             }}
-            onConfigSave={(config) => {
+            onConfigSave={({ anthropicApiKey, openrouterApiKey, ...config }) => {
+              // API keys go to the OS keychain, never into the config JSON
+              if (anthropicApiKey) invoke("llm_key_set", { provider: "anthropic", key: anthropicApiKey });
+              if (openrouterApiKey) invoke("llm_key_set", { provider: "openrouter", key: openrouterApiKey });
               invoke("config_update", { patch: JSON.stringify(config) });
             }}
             onAgentStart={() => {
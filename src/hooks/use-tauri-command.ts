@@ -1,5 +1,6 @@
 import { useState, useCallback } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { errorMessage } from "../utils/errors.js";
 
 type CommandState<T> = {
   data: T | undefined;
@@ -23,7 +24,7 @@ export function useTauriCommand<T>(command: string): CommandState<T> {
         setLoading(false);
         return result;
       } catch (err) {
-        const message = err instanceof Error ? err.message : String(err);
+        const message = errorMessage(err);
         setError(message);
         setLoading(false);
         throw err;
@@ -1,6 +1,7 @@
 import { useState, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { useTauriEvent } from "../hooks/use-tauri-event";
+import { errorMessage } from "../utils/errors.js";
 import { BacktestConfigSchema } from "@finwatch/shared";
 import type { BacktestConfig, BacktestProgress } from "@finwatch/shared";
 
@@ -85,7 +86,7 @@ export function BacktestConfigPage({ progress, onProgress, onComplete, runs, onV
       setRunning(true);
       await invoke("backtest_start", { config: JSON.stringify(config) });
     } catch (err) {
-      setError(err instanceof Error ? err.message : String(err));
+      setError(errorMessage(err));
       setRunning(false);
     }
   };
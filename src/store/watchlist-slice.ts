@@ -6,6 +6,7 @@ export type Asset = {
   exchange: string;
   asset_class: string;
   status: string;
+  fetched_at?: string;
 };
 
 type WatchlistState = {
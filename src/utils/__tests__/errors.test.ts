@@ -0,0 +1,46 @@
+import { describe, it, expect } from "vitest";
+import { errorMessage, isBridgeError, isConfigError } from "../errors.js";
+
+describe("isBridgeError", () => {
+  it("recognizes serialized bridge errors", () => {
+    expect(isBridgeError({ kind: "timeout", message: "JSON-RPC request 3 timed out", retryable: true })).toBe(true);
+  });
+
+  it("rejects strings and plain objects", () => {
+    expect(isBridgeError("Sidecar not running")).toBe(false);
+    expect(isBridgeError({ message: "nope" })).toBe(false);
+    expect(isBridgeError(null)).toBe(false);
+  });
+});
+
+describe("isConfigError", () => {
+  it("recognizes config validation errors", () => {
+    const err = {
+      kind: "invalid",
+      message: "Invalid config: feed: must be one of: iex, sip",
+      fields: [{ path: "feed", message: "must be one of: iex, sip" }],
+    };
+    expect(isConfigError(err)).toBe(true);
+    expect(errorMessage(err)).toBe("Invalid config: feed: must be one of: iex, sip");
+  });
+
+  it("rejects bridge errors", () => {
+    expect(isConfigError({ kind: "timeout", message: "t", retryable: true })).toBe(false);
+  });
+});
+
+describe("errorMessage", () => {
+  it("uses the message of Error instances", () => {
+    expect(errorMessage(new Error("boom"))).toBe("boom");
+  });
+
+  it("uses the message of bridge errors", () => {
+    expect(errorMessage({ kind: "sidecarDown", message: "Sidecar process crashed", retryable: false })).toBe(
+      "Sidecar process crashed",
+    );
+  });
+
+  it("stringifies anything else", () => {
+    expect(errorMessage("plain string")).toBe("plain string");
+  });
+});
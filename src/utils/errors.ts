@@ -0,0 +1,42 @@
+/** Structured error returned by Tauri commands that call the agent (Rust `BridgeError`). */
+export type BridgeError = {
+  kind: "timeout" | "transport" | "sidecarDown" | "rpcError" | "internal";
+  message: string;
+  retryable: boolean;
+  code?: number;
+  data?: unknown;
+  timeoutMs?: number;
+};
+
+export function isBridgeError(err: unknown): err is BridgeError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as BridgeError).kind === "string" &&
+    typeof (err as BridgeError).message === "string" &&
+    typeof (err as BridgeError).retryable === "boolean"
+  );
+}
+
+/** Validation error returned by `config_update` (Rust `ConfigError`). */
+export type ConfigError = {
+  kind: "invalid" | "internal";
+  message: string;
+  fields: { path: string; message: string }[];
+};
+
+export function isConfigError(err: unknown): err is ConfigError {
+  return (
+    typeof err === "object" &&
+    err !== null &&
+    typeof (err as ConfigError).message === "string" &&
+    Array.isArray((err as ConfigError).fields)
+  );
+}
+
+/** Human-readable message for any error thrown by `invoke`. */
+export function errorMessage(err: unknown): string {
+  if (err instanceof Error) return err.message;
+  if (isBridgeError(err) || isConfigError(err)) return err.message;
+  return String(err);
+}